@@ -0,0 +1,367 @@
+/// AIS data import and export for the Marine vessel simulator.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// Reads decoded AIS position reports from the CSV files of the public AIS archives and converts them to ship logs, one per vessel, see read_ais_csv().
+/// The ship logs can then be evaluated like any other ship log, e.g. saved with ship_logs_to_csv() and evaluated with evaluate_cargo_shipping_logs_with()
+/// Supported formats:
+/// - Marine Cadastre (US), <https://marinecadastre.gov/ais/>
+/// - Danish Maritime Authority, <http://aisdata.ais.dk/>
+/// Also encodes simulated voyages as !AIVDM sentences, position reports (message types 1 and 3) and static and voyage related data (message type 5), so the simulator can be a synthetic AIS target for VTS and collision avoidance software, see AisVessel
+
+use crate::*;   // To use everything from the crate
+use std::collections::HashMap;
+
+/// Enum of the AIS CSV formats read_ais_csv() can read
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AisFormat {
+    /// Comma separated with the columns MMSI, BaseDateTime, LAT, LON, SOG, COG, Heading, VesselName, ..., Status, ..., Draft, ...
+    /// The navigation status is the AIS navigation status code
+    MarineCadastre,
+    /// Comma separated with the columns # Timestamp, Type of mobile, MMSI, Latitude, Longitude, Navigational status, ROT, SOG, COG, Heading, ..., Name, ..., Draught, ...
+    /// The timestamps are in the format DD/MM/YYYY hh:mm:ss and the navigation status is written out, e.g. "Under way using engine"
+    DanishMaritimeAuthority,
+}
+
+impl AisFormat {
+    /// Returns the header names of the columns for the MMSI, timestamp, latitude, longitude, SOG, COG, heading, name, navigation status and draft
+    fn column_names(&self) -> [&'static str; 10] {
+        return match self {
+            AisFormat::MarineCadastre => ["MMSI", "BaseDateTime", "LAT", "LON", "SOG", "COG", "Heading", "VesselName", "Status", "Draft"],
+            AisFormat::DanishMaritimeAuthority => ["MMSI", "# Timestamp", "Latitude", "Longitude", "SOG", "COG", "Heading", "Name", "Navigational status", "Draught"],
+        };
+    }
+}
+
+/// The ship log of one vessel in an AIS file, see read_ais_csv()
+#[derive(Debug, Clone, PartialEq)]
+pub struct AisTrack {
+    /// Maritime Mobile Service Identity of the vessel
+    pub mmsi: u32,
+    /// Name of the vessel, if any of the position reports had it
+    pub name: Option<String>,
+    /// The position reports of the vessel in chronological order
+    /// The initial and final coordinates of every entry are the first and last position of the vessel in the file
+    pub ship_log: Vec<ShipLogEntry>,
+}
+
+/// Reads decoded AIS position reports from a CSV file and converts them to one ship log per vessel, sorted by MMSI
+/// The speed over ground and course over ground are the velocity of the entries (SOG is converted from knots to \[m/s\]), the AIS heading is the heading and the draught is the draft
+/// Values marked as not available in AIS, e.g. heading 511 or SOG 102.3, are None
+/// Rows without a valid MMSI, timestamp or position are skipped with one warning for the whole file, since the public archives have many of them
+/// mmsi_filter: If set, only the vessels with these MMSIs are read, useful for the large daily files of the archives
+/// Note: A ship log of a vessel covers everything the vessel did in the file. Use TripSegmentation::PortAreas or TripSegmentation::TimeGap to split it into trips
+/// # Example:
+/// `let tracks = read_ais_csv("AIS_2024_01_01.csv", AisFormat::MarineCadastre, Some(&[367123450]))?;`
+/// `ship_logs_to_csv("vessel.csv", &tracks[0].ship_log)?;`
+/// `let stats = evaluate_cargo_shipping_logs_with("vessel.csv", &TripSegmentation::TimeGap { max_gap: time::Duration::hours(12) })?;`
+pub fn read_ais_csv(file_path: &str, format: AisFormat, mmsi_filter: Option<&[u32]>) -> Result<Vec<AisTrack>, MarineSimError> {
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(b',')
+        .has_headers(true)
+        .flexible(true)
+        .from_path(file_path)?;
+
+    // Find the columns by their header names
+    let header = csv_reader.headers()?.clone();
+    let column_names = format.column_names();
+    let mut columns: [Option<usize>; 10] = [None; 10];
+    for (column, name) in columns.iter_mut().zip(column_names) {
+        *column = header.iter().position(|header_name| header_name.trim() == name);
+    }
+    for (column, name) in columns.iter().zip(column_names).take(4) {
+        if column.is_none() {
+            return Err(MarineSimError::CsvParse { file: file_path.to_string(), message: format!("Missing the {} column of the {:?} AIS format", name, format) });
+        }
+    }
+    let [mmsi_column, timestamp_column, latitude_column, longitude_column, sog_column, cog_column, heading_column, name_column, status_column, draft_column] = columns;
+    let get = |record: &csv::StringRecord, column: Option<usize>| -> Option<String> {
+        return column.and_then(|column| record.get(column)).map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+    };
+    let get_f64 = |record: &csv::StringRecord, column: Option<usize>| -> Option<f64> {
+        return get(record, column).and_then(|value| value.parse::<f64>().ok()).filter(|value| value.is_finite());
+    };
+
+    let mut tracks: HashMap<u32, AisTrack> = HashMap::new();
+    let mut num_skipped: u64 = 0;
+    for result in csv_reader.records() {
+        let record = match result {
+            Ok(record) => record,
+            Err(_) => {
+                num_skipped += 1;
+                continue;
+            }
+        };
+
+        // MMSI, timestamp and position are required
+        let mmsi: Option<u32> = get(&record, mmsi_column).and_then(|mmsi| mmsi.parse::<u32>().ok());
+        let timestamp: Option<UtcDateTime> = get(&record, timestamp_column).and_then(|timestamp| match format {
+            AisFormat::MarineCadastre => string_to_utc_date_time(timestamp).ok(),
+            AisFormat::DanishMaritimeAuthority => get_dma_timestamp(&timestamp),
+        });
+        let latitude = get_f64(&record, latitude_column).filter(|latitude| latitude.abs() <= 90.0);
+        let longitude = get_f64(&record, longitude_column).filter(|longitude| longitude.abs() <= 180.0);
+        let (mmsi, timestamp, location) = match (mmsi, timestamp, latitude, longitude) {
+            (Some(mmsi), Some(timestamp), Some(latitude), Some(longitude)) => (mmsi, timestamp, geo::Point::new(longitude, latitude)),
+            _ => {
+                num_skipped += 1;
+                continue;
+            }
+        };
+        if let Some(mmsi_filter) = mmsi_filter {
+            if !mmsi_filter.contains(&mmsi) {
+                continue;
+            }
+        }
+
+        // Optional values, with the AIS values for not available left out
+        let sog = get_f64(&record, sog_column).filter(|sog| *sog >= 0.0 && *sog < 102.3);
+        let cog = get_f64(&record, cog_column).filter(|cog| *cog >= 0.0 && *cog < 360.0);
+        let velocity: Option<PhysVec> = match (sog, cog) {
+            (Some(sog), Some(cog)) => Some(PhysVec::new(sog*1852.0/3600.0, cog)),
+            _ => None,
+        };
+        let heading = get_f64(&record, heading_column).filter(|heading| *heading >= 0.0 && *heading < 360.0);
+        let navigation_status: Option<NavigationStatus> = match format {
+            AisFormat::MarineCadastre => get_f64(&record, status_column).and_then(|status| NavigationStatus::try_from(status as u8).ok()),
+            AisFormat::DanishMaritimeAuthority => get(&record, status_column).and_then(|status| get_dma_navigation_status(&status)),
+        };
+        let draft = get_f64(&record, draft_column).filter(|draft| *draft > 0.0);
+
+        let track = tracks.entry(mmsi).or_insert_with(|| AisTrack { mmsi, name: None, ship_log: Vec::new() });
+        if track.name.is_none() {
+            track.name = get(&record, name_column);
+        }
+        track.ship_log.push(ShipLogEntry::new(timestamp, location, location, location, None, velocity, None, heading, None, None, draft, navigation_status));
+    }
+    if num_skipped > 0 {
+        tracing::warn!("Skipped {} rows without a valid MMSI, timestamp or position in {}", num_skipped, file_path);
+    }
+
+    // Sort the ship logs and set the initial and final coordinates of the entries
+    let mut tracks: Vec<AisTrack> = tracks.into_values().collect();
+    tracks.sort_by_key(|track| track.mmsi);
+    for track in tracks.iter_mut() {
+        track.ship_log.sort_by_key(|entry| entry.timestamp);
+        let coordinates_initial = track.ship_log[0].coordinates_current;
+        let coordinates_final = track.ship_log[track.ship_log.len() - 1].coordinates_current;
+        for entry in track.ship_log.iter_mut() {
+            entry.coordinates_initial = coordinates_initial;
+            entry.coordinates_final = coordinates_final;
+        }
+    }
+    return Ok(tracks);
+}
+
+/// Converts a Danish Maritime Authority timestamp, DD/MM/YYYY hh:mm:ss, to a UtcDateTime. None if it is not valid
+fn get_dma_timestamp(timestamp: &str) -> Option<UtcDateTime> {
+    if timestamp.len() < 16 || !timestamp.is_ascii() {
+        return None;
+    }
+    // Reorder to YYYY-MM-DD hh:mm:ss
+    let reordered = format!("{}-{}-{}{}", &timestamp[6..10], &timestamp[3..5], &timestamp[0..2], &timestamp[10..]);
+    return string_to_utc_date_time(reordered).ok();
+}
+
+/// Converts a navigation status written out by the Danish Maritime Authority, e.g. "Under way using engine", to a NavigationStatus. None if it is unknown
+fn get_dma_navigation_status(status: &str) -> Option<NavigationStatus> {
+    return match status.to_lowercase().as_str() {
+        "under way using engine" => Some(NavigationStatus::UnderwayUsingEngine),
+        "at anchor" => Some(NavigationStatus::AtAnchor),
+        "not under command" => Some(NavigationStatus::NotUnderCommand),
+        "restricted manoeuverability" | "restricted maneuverability" => Some(NavigationStatus::RestrictedManeuverability),
+        "constrained by her draught" => Some(NavigationStatus::ConstrainedByDraft),
+        "moored" => Some(NavigationStatus::Moored),
+        "aground" => Some(NavigationStatus::Aground),
+        "engaged in fishing" => Some(NavigationStatus::EngagedInFishing),
+        "under way sailing" => Some(NavigationStatus::UnderwaySailing),
+        "ais-sart" => Some(NavigationStatus::ActiveAisSart),
+        "unknown value" => Some(NavigationStatus::NotDefinedOrDefault),
+        _ => None,
+    };
+}
+
+/// Static data of a vessel for encoding AIS messages, see AisVessel.ship_log_to_aivdm()
+#[derive(Debug, Clone, PartialEq)]
+pub struct AisVessel {
+    /// Maritime Mobile Service Identity, 9 digits
+    pub mmsi: u32,
+    pub imo: Option<u32>,
+    /// Up to 7 characters
+    pub call_sign: String,
+    /// Up to 20 characters
+    pub name: String,
+    /// AIS ship type code, e.g. 36 for sailing vessels and 70 for cargo ships
+    pub ship_type: u8,
+    /// \[m\]. Distances from the AIS antenna to the bow, stern, port and starboard sides
+    pub dimensions: [f64; 4],
+    /// Destination, up to 20 characters
+    pub destination: String,
+}
+
+impl AisVessel {
+    /// AIS ship type code for sailing vessels
+    pub const SHIP_TYPE_SAILING: u8 = 36;
+
+    /// Creates the static data of a boat, with the antenna in the middle of the boat and the ship type for sailing vessels
+    /// The call sign and destination are left empty
+    pub fn from_boat(mmsi: u32, boat: &Boat) -> AisVessel {
+        let length = boat.length.map_or(0.0, |length| length.get::<uom::si::length::meter>());
+        let width = boat.width.map_or(0.0, |width| width.get::<uom::si::length::meter>());
+        return AisVessel {
+            mmsi,
+            imo: boat.imo,
+            call_sign: String::new(),
+            name: boat.name.clone().unwrap_or_default(),
+            ship_type: AisVessel::SHIP_TYPE_SAILING,
+            dimensions: [length/2.0, length/2.0, width/2.0, width/2.0],
+            destination: String::new(),
+        };
+    }
+
+    /// Encodes a ship log entry as a position report, message type 1 (scheduled) or 3 (special, e.g. when interrogated), as one !AIVDM sentence
+    /// The speed and course over ground are the velocity of the entry, values that are not in the entry are encoded as not available
+    /// Returns an error if the message type is not 1 or 3
+    /// # Example:
+    /// `let sentence = vessel.position_report(&boat.ship_log[0], 1)?;`
+    pub fn position_report(&self, entry: &ShipLogEntry, message_type: u8) -> Result<String, MarineSimError> {
+        if message_type != 1 && message_type != 3 {
+            return Err(MarineSimError::InvalidInput(format!("AIS position reports are message type 1 or 3, not {}", message_type)));
+        }
+        let mut bits = AisBits::new();
+        bits.push(message_type as u64, 6);
+        bits.push(0, 2);    // Repeat indicator
+        bits.push(self.mmsi as u64, 30);
+        bits.push(entry.navigation_status.map_or(NavigationStatus::NotDefinedOrDefault as u64, |status| status as u64), 4);
+        bits.push_signed(-128, 8);   // Rate of turn not available
+        // Speed over ground in 1/10 knots, 1023 is not available
+        bits.push(entry.velocity.map_or(1023, |velocity| ((velocity.magnitude*36000.0/1852.0).round() as u64).min(1022)), 10);
+        bits.push(0, 1);    // Position accuracy
+        // Position in 1/10000 minutes
+        bits.push_signed((entry.coordinates_current.x()*600000.0).round() as i64, 28);
+        bits.push_signed((entry.coordinates_current.y()*600000.0).round() as i64, 27);
+        // Course over ground in 1/10 degrees, 3600 is not available, and true heading, 511 is not available
+        bits.push(entry.velocity.map_or(3600, |velocity| ((velocity.angle.rem_euclid(360.0)*10.0).round() as u64) % 3600), 12);
+        bits.push(entry.heading.map_or(511, |heading| (heading.rem_euclid(360.0).round() as u64) % 360), 9);
+        bits.push(entry.timestamp.second() as u64, 6);
+        bits.push(0, 2);    // Maneuver indicator
+        bits.push(0, 3);    // Spare
+        bits.push(0, 1);    // RAIM
+        bits.push(0, 19);   // Radio status
+        return Ok(bits.to_aivdm_sentences(0).remove(0));
+    }
+
+    /// Encodes the static and voyage related data, message type 5, as two !AIVDM sentences since it does not fit in one
+    /// eta: Estimated time of arrival, if known
+    /// draft: \[m\]. Present draft, if known
+    /// sequence_id: Sequential message id, 0 to 9, that ties the two sentences together
+    pub fn static_report(&self, eta: Option<UtcDateTime>, draft: Option<f64>, sequence_id: u8) -> Vec<String> {
+        let mut bits = AisBits::new();
+        bits.push(5, 6);
+        bits.push(0, 2);    // Repeat indicator
+        bits.push(self.mmsi as u64, 30);
+        bits.push(0, 2);    // AIS version
+        bits.push(self.imo.unwrap_or(0) as u64, 30);
+        bits.push_text(&self.call_sign, 7);
+        bits.push_text(&self.name, 20);
+        bits.push(self.ship_type as u64, 8);
+        // Dimensions in meters, the bow and stern up to 511 m and the sides up to 63 m
+        for (dimension, num_bits, max) in [(self.dimensions[0], 9, 511.0), (self.dimensions[1], 9, 511.0), (self.dimensions[2], 6, 63.0), (self.dimensions[3], 6, 63.0)] {
+            bits.push(dimension.round().clamp(0.0, max) as u64, num_bits);
+        }
+        bits.push(1, 4);    // GPS position fix
+        // ETA month, day, hour and minute, 0, 0, 24 and 60 are not available
+        match eta {
+            Some(eta) => {
+                bits.push(eta.month() as u64, 4);
+                bits.push(eta.day() as u64, 5);
+                bits.push(eta.hour() as u64, 5);
+                bits.push(eta.minute() as u64, 6);
+            },
+            None => {
+                bits.push(0, 4);
+                bits.push(0, 5);
+                bits.push(24, 5);
+                bits.push(60, 6);
+            },
+        }
+        // Draft in 1/10 meters
+        bits.push(draft.map_or(0, |draft| (draft*10.0).round().clamp(0.0, 255.0) as u64), 8);
+        bits.push_text(&self.destination, 20);
+        bits.push(0, 1);    // Data terminal ready
+        bits.push(0, 1);    // Spare
+        return bits.to_aivdm_sentences(sequence_id);
+    }
+
+    /// Encodes a ship log as !AIVDM sentences, the static and voyage related data followed by a position report (message type 1) for every entry
+    /// The draft of the static data is the first draft in the ship log and the ETA is the time of the last entry
+    /// # Example:
+    /// `let vessel = AisVessel::from_boat(251000001, &boat);`
+    /// `std::fs::write("targets.nmea", vessel.ship_log_to_aivdm(&boat.ship_log)?.join("\r\n"))?;`
+    pub fn ship_log_to_aivdm(&self, ship_log: &[ShipLogEntry]) -> Result<Vec<String>, MarineSimError> {
+        let mut sentences = self.static_report(ship_log.last().map(|entry| entry.timestamp), ship_log.iter().find_map(|entry| entry.draft), 0);
+        for entry in ship_log {
+            sentences.push(self.position_report(entry, 1)?);
+        }
+        return Ok(sentences);
+    }
+}
+
+/// Bits of an AIS message, most significant bit first
+struct AisBits {
+    bits: Vec<bool>,
+}
+
+impl AisBits {
+    fn new() -> AisBits {
+        AisBits {
+            bits: Vec::new(),
+        }
+    }
+
+    /// Adds the num_bits lowest bits of an unsigned value
+    fn push(&mut self, value: u64, num_bits: usize) {
+        for i in (0..num_bits).rev() {
+            self.bits.push((value >> i) & 1 == 1);
+        }
+    }
+
+    /// Adds a signed value as a two's complement number of num_bits bits
+    fn push_signed(&mut self, value: i64, num_bits: usize) {
+        self.push((value as u64) & ((1u64 << num_bits) - 1), num_bits);
+    }
+
+    /// Adds text in 6-bit ASCII, padded with @ to num_chars characters. Lowercase letters are made uppercase and characters that can not be encoded become ?
+    fn push_text(&mut self, text: &str, num_chars: usize) {
+        let mut chars = text.to_uppercase().chars().collect::<Vec<char>>();
+        chars.resize(num_chars.max(chars.len()), '@');
+        for c in chars.into_iter().take(num_chars) {
+            let code = match c as u32 {
+                64..=95 => c as u32 - 64,
+                32..=63 => c as u32,
+                _ => '?' as u32,
+            };
+            self.push(code as u64, 6);
+        }
+    }
+
+    /// Armors the bits into the payload characters and splits them into !AIVDM sentences of at most 60 payload characters, on radio channel A
+    fn to_aivdm_sentences(&self, sequence_id: u8) -> Vec<String> {
+        // Pad to a whole number of 6-bit characters
+        let fill_bits = (6 - self.bits.len() % 6) % 6;
+        let mut bits = self.bits.clone();
+        bits.resize(bits.len() + fill_bits, false);
+        let payload: String = bits.chunks(6).map(|chunk| {
+            let value = chunk.iter().fold(0u8, |value, bit| (value << 1) | (*bit as u8));
+            (if value < 40 { value + 48 } else { value + 56 }) as char
+        }).collect();
+
+        let fragments: Vec<&str> = payload.as_bytes().chunks(60).map(|chunk| std::str::from_utf8(chunk).unwrap_or_default()).collect();
+        let num_fragments = fragments.len();
+        return fragments.iter().enumerate().map(|(i, fragment)| {
+            let sequence = if num_fragments > 1 { sequence_id.to_string() } else { String::new() };
+            let fill = if i + 1 == num_fragments { fill_bits } else { 0 };
+            let fields = format!("AIVDM,{},{},{},A,{},{}", num_fragments, i + 1, sequence, fragment, fill);
+            format!("!{}*{:02X}", fields, get_nmea_checksum(&fields))
+        }).collect();
+    }
+}
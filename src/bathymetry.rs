@@ -0,0 +1,100 @@
+/// Bathymetry (water depth) for the Marine vessel simulator, used to check that vessels have enough water under the keel.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// The bathymetry is read from a GEBCO NetCDF grid, download a subset for your area from https://download.gebco.net/
+/// Note: The whole grid is read into memory, so use a subset for the area you are simulating and not the global grid.
+
+use crate::*;   // To use everything from the crate
+
+/// Bathymetry grid with elevation in \[m\] relative to mean sea level on a regular latitude/longitude grid
+#[derive(Debug, Clone)]
+pub struct Bathymetry {
+    /// Latitudes of the grid rows in degrees, ascending
+    pub latitudes: Vec<f64>,
+    /// Longitudes of the grid columns in degrees, ascending
+    pub longitudes: Vec<f64>,
+    /// Elevation in \[m\], negative below sea level. Row major, elevation\[row*longitudes.len() + column\]
+    pub elevation: Vec<f32>,
+}
+
+impl Bathymetry {
+    /// Loads bathymetry from a GEBCO NetCDF file with the variables "lat", "lon" and "elevation"
+    /// # Example:
+    /// `let bathymetry = Bathymetry::from_gebco_netcdf("gebco_2024_n66.0_s50.0_w-25.0_e10.0.nc")?;`
+    /// Needs the "netcdf" feature
+    #[cfg(feature = "netcdf")]
+    pub fn from_gebco_netcdf(file_path: &str) -> Result<Bathymetry, io::Error> {
+        // Check file extension
+        if !check_file_extension(file_path, ".nc") {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "File path must end with .nc"));
+        }
+
+        // Open file
+        let file = match netcdf::open(file_path) {
+            Ok(f) => f,
+            Err(e) => return Err(io::Error::new(io::ErrorKind::Other, format!("Error opening bathymetry file {}: {}", file_path, e))),
+        };
+
+        // Read the variables
+        let latitudes: Vec<f64> = read_netcdf_variable(&file, "lat")?;
+        let longitudes: Vec<f64> = read_netcdf_variable(&file, "lon")?;
+        let elevation: Vec<f32> = read_netcdf_variable(&file, "elevation")?;
+
+        // Sanity check the grid
+        if latitudes.len() * longitudes.len() != elevation.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Bathymetry grid size does not match, {} latitudes and {} longitudes but {} elevation values", latitudes.len(), longitudes.len(), elevation.len())));
+        }
+
+        return Ok(Bathymetry {
+            latitudes,
+            longitudes,
+            elevation,
+        });
+    }
+
+    /// Returns the water depth in \[m\] at a point, positive below sea level, using the closest grid cell
+    /// On land the depth is zero or negative
+    /// Returns None if the point is outside of the grid
+    pub fn depth_at(&self, point: geo::Point) -> Option<f64> {
+        let row = closest_index(&self.latitudes, point.y())?;
+        let column = closest_index(&self.longitudes, point.x())?;
+        return Some(-(self.elevation[row*self.longitudes.len() + column] as f64));
+    }
+}
+
+/// Reads all values of a NetCDF variable
+#[cfg(feature = "netcdf")]
+fn read_netcdf_variable<T: netcdf::NcTypeDescriptor + Copy>(file: &netcdf::File, name: &str) -> Result<Vec<T>, io::Error> {
+    let variable = match file.variable(name) {
+        Some(v) => v,
+        None => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Variable {} missing from bathymetry file", name))),
+    };
+    match variable.get_values::<T, _>(..) {
+        Ok(values) => return Ok(values),
+        Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Error reading variable {} from bathymetry file: {}", name, e))),
+    }
+}
+
+/// Returns the index of the closest value in an ascending, evenly spaced vector, or None if the value is outside of it by more than half a step
+fn closest_index(values: &Vec<f64>, value: f64) -> Option<usize> {
+    if values.is_empty() {
+        return None;
+    }
+    // Half a grid step, to allow points on the edge of the outermost cells
+    let half_step = match values.len() {
+        1 => 0.0,
+        n => (values[n-1] - values[0]) / ((n - 1) as f64) / 2.0,
+    };
+    if value < values[0] - half_step || value > values[values.len()-1] + half_step {
+        return None;
+    }
+    // First index with a value bigger than or equal to value, then check if the one before is closer
+    let i = values.partition_point(|v| *v < value);
+    if i == 0 {
+        return Some(0);
+    }
+    if i == values.len() || (value - values[i-1]) <= (values[i] - value) {
+        return Some(i-1);
+    }
+    return Some(i);
+}
@@ -0,0 +1,208 @@
+/// Calibration of boat parameters against real ship logs for the Marine vessel simulator.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// The parameters are fitted by simulating the recorded voyages over and over and minimizing the validation error, see validate_ship_log(), with the Nelder-Mead method.
+/// Note: With stochastic simulation methods, e.g. SimMethod::MeanAndSTDVelocity, the error changes between runs with the same parameters and the fit is less accurate
+
+use crate::*;   // To use everything from the crate
+
+/// Enum of the validation errors calibrate_boat() can minimize, see ValidationMetrics
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CalibrationObjective {
+    /// Error in the arrival time, in seconds
+    ArrivalTime,
+    /// Root mean square of the distance to the recorded position at the same time since departure, in meters
+    PositionRmse,
+    /// Root mean square of the distance to the recorded track, in meters
+    CrossTrackRmse,
+}
+
+/// Results of calibrate_boat()
+#[derive(Clone)]
+pub struct CalibrationResult {
+    /// A copy of the boat with the calibrated parameters and an empty ship log
+    pub boat: Boat,
+    /// The calibrated value of each parameter
+    pub parameters: Vec<(BoatParameter, f64)>,
+    /// Root mean square of the objective over the recorded voyages with the initial parameters
+    pub initial_error: f64,
+    /// Root mean square of the objective over the recorded voyages with the calibrated parameters
+    pub error: f64,
+    /// Number of times the recorded voyages were simulated
+    pub evaluations: usize,
+}
+
+/// Fits the parameters of a boat to recorded voyages, e.g. from AIS data, by minimizing the validation error with the Nelder-Mead method
+/// Each recorded voyage is simulated along boat.route_plan, departing at the first entry of the recorded ship log, and compared with validate_ship_log()
+/// The error is the root mean square of the objective over all recorded voyages, voyages that are not completed count with their error at the end of the simulation
+/// boat: The boat to calibrate, must have a route plan and all the parameters set to initial guesses. The boat itself is not changed
+/// recorded_voyages: The ship logs of the recorded voyages, see csv_to_ship_log()
+/// parameters: The parameters to fit, they are kept positive
+/// max_evaluations: Maximum number of times the recorded voyages are simulated
+/// # Example:
+/// `let result = calibrate_boat(&boat, &simulation, &recorded_voyages, &[BoatParameter::WindVelocityMultiplier], CalibrationObjective::ArrivalTime, 200)?;`
+/// `println!("Wind velocity multiplier: {}, error {:.0} s", result.parameters[0].1, result.error);`
+pub fn calibrate_boat(boat: &Boat, simulation: &Simulation, recorded_voyages: &[Vec<ShipLogEntry>], parameters: &[BoatParameter], objective: CalibrationObjective, max_evaluations: usize) -> Result<CalibrationResult, io::Error> {
+    // Validate input
+    if boat.route_plan.is_none() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Boat has no route plan"));
+    }
+    if recorded_voyages.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "No recorded voyages to calibrate against"));
+    }
+    if parameters.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "No parameters to calibrate"));
+    }
+    let mut initial_values: Vec<f64> = Vec::with_capacity(parameters.len());
+    for parameter in parameters {
+        match parameter.get(boat) {
+            Some(value) => initial_values.push(value),
+            None => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Boat has no initial value for {:?}", parameter))),
+        }
+    }
+
+    // Template without logs so they are not copied for every evaluation
+    let mut template = boat.clone();
+    template.ship_log = Vec::new();
+    template.decimated_ship_log = Vec::new();
+    template.events = Vec::new();
+    template.waypoint_arrivals = Vec::new();
+    template.ship_log_hook = None;
+
+    // Fit
+    let initial_error = get_calibration_error(&template, simulation, recorded_voyages, parameters, &initial_values, objective)?;
+    let mut error_result: Result<(), io::Error> = Ok(());
+    let (values, error, evaluations) = nelder_mead(|values| {
+        // Keep the parameters positive
+        if values.iter().any(|value| *value <= 0.0) {
+            return f64::INFINITY;
+        }
+        match get_calibration_error(&template, simulation, recorded_voyages, parameters, values, objective) {
+            Ok(error) => error,
+            Err(e) => {
+                error_result = Err(e);
+                f64::INFINITY
+            }
+        }
+    }, &initial_values, max_evaluations);
+    error_result?;
+
+    // Calibrated boat
+    for (parameter, value) in parameters.iter().zip(values.iter()) {
+        parameter.set(&mut template, *value)?;
+    }
+    return Ok(CalibrationResult {
+        boat: template,
+        parameters: parameters.iter().copied().zip(values.into_iter()).collect(),
+        initial_error,
+        error,
+        evaluations: evaluations + 1,
+    });
+}
+
+/// Simulates the recorded voyages with the parameter values and returns the root mean square of the objective
+fn get_calibration_error(template: &Boat, simulation: &Simulation, recorded_voyages: &[Vec<ShipLogEntry>], parameters: &[BoatParameter], values: &[f64], objective: CalibrationObjective) -> Result<f64, io::Error> {
+    let mut boat = template.clone();
+    for (parameter, value) in parameters.iter().zip(values.iter()) {
+        parameter.set(&mut boat, *value)?;
+    }
+
+    let mut sum_sq: f64 = 0.0;
+    for recorded in recorded_voyages {
+        let departure = match recorded.first() {
+            Some(entry) => entry.timestamp,
+            None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "Recorded voyage has an empty ship log")),
+        };
+        let log_start = boat.ship_log.len();
+        sim_waypoint_mission(&mut boat, departure, simulation)?;
+        let metrics = validate_ship_log(&boat.ship_log[log_start..], recorded)?;
+        let error = match objective {
+            CalibrationObjective::ArrivalTime => metrics.arrival_time_error.as_seconds_f64(),
+            CalibrationObjective::PositionRmse => metrics.position_rmse.unwrap_or(f64::INFINITY),
+            CalibrationObjective::CrossTrackRmse => metrics.cross_track_rmse,
+        };
+        sum_sq += error.powi(2);
+    }
+    return Ok((sum_sq / recorded_voyages.len() as f64).sqrt());
+}
+
+/// Minimizes f with the Nelder-Mead simplex method starting from x0, the first simplex steps 10% from x0 in each dimension (0.1 if the value is zero)
+/// Stops after max_evaluations evaluations of f or when the values of f in the simplex are within 1e-9 of each other
+/// Returns the best point, the value of f there and the number of evaluations of f
+fn nelder_mead<F: FnMut(&[f64]) -> f64>(mut f: F, x0: &[f64], max_evaluations: usize) -> (Vec<f64>, f64, usize) {
+    // Coefficients for reflection, expansion, contraction and shrinking
+    let (alpha, gamma, rho, sigma) = (1.0, 2.0, 0.5, 0.5);
+    let n = x0.len();
+
+    // Initial simplex
+    let mut simplex: Vec<(Vec<f64>, f64)> = Vec::with_capacity(n + 1);
+    simplex.push((x0.to_vec(), f(x0)));
+    for i in 0..n {
+        let mut x = x0.to_vec();
+        x[i] = match x[i] == 0.0 {
+            true => 0.1,
+            false => x[i]*1.1,
+        };
+        let value = f(&x);
+        simplex.push((x, value));
+    }
+    let mut evaluations = n + 1;
+
+    while evaluations < max_evaluations {
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        if (simplex[n].1 - simplex[0].1).abs() <= 1e-9 {
+            break;
+        }
+
+        // Centroid of all but the worst point
+        let mut centroid = vec![0.0; n];
+        for (x, _) in &simplex[..n] {
+            for i in 0..n {
+                centroid[i] += x[i] / n as f64;
+            }
+        }
+        let towards = |from: &Vec<f64>, coefficient: f64| -> Vec<f64> {
+            (0..n).map(|i| centroid[i] + coefficient*(from[i] - centroid[i])).collect()
+        };
+
+        // Reflect the worst point through the centroid
+        let reflected = towards(&simplex[n].0, -alpha);
+        let reflected_value = f(&reflected);
+        evaluations += 1;
+        if reflected_value < simplex[0].1 {
+            // Try going further
+            let expanded = towards(&simplex[n].0, -gamma);
+            let expanded_value = f(&expanded);
+            evaluations += 1;
+            simplex[n] = match expanded_value < reflected_value {
+                true => (expanded, expanded_value),
+                false => (reflected, reflected_value),
+            };
+        }
+        else if reflected_value < simplex[n-1].1 {
+            simplex[n] = (reflected, reflected_value);
+        }
+        else {
+            // Contract towards the centroid
+            let contracted = towards(&simplex[n].0, rho);
+            let contracted_value = f(&contracted);
+            evaluations += 1;
+            if contracted_value < simplex[n].1 {
+                simplex[n] = (contracted, contracted_value);
+            }
+            else {
+                // Shrink everything towards the best point
+                let best = simplex[0].0.clone();
+                for (x, value) in simplex.iter_mut().skip(1) {
+                    *x = (0..n).map(|i| best[i] + sigma*(x[i] - best[i])).collect();
+                    *value = f(x);
+                    evaluations += 1;
+                }
+            }
+        }
+    }
+
+    simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let (x, value) = simplex.swap_remove(0);
+    return (x, value, evaluations);
+}
@@ -0,0 +1,77 @@
+/// Config files for the Marine vessel simulator, only with the "serde" feature.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// Boats and simulations can be defined in JSON (.json), TOML (.toml) or YAML (.yaml or .yml) files, so experiments can be versioned and re-run without recompiling.
+/// The fields in the files have the same names as in Boat and Simulation, quantities with units are tagged with their unit, see serde_units.
+/// Settings that can not be written to a file, e.g. Simulation.copernicus, Simulation.weather and progress bars, must be set after loading.
+
+use crate::*;   // To use everything from the crate
+
+/// Reads a JSON, TOML or YAML file, the format is chosen from the file extension
+fn read_config_file<T: serde::de::DeserializeOwned>(file_path: &str) -> Result<T, MarineSimError> {
+    let contents = std::fs::read_to_string(file_path)?;
+    let config_error = |message: String| MarineSimError::ConfigParse { file: file_path.to_string(), message };
+    if check_file_extension(file_path, ".json") {
+        return serde_json::from_str(&contents).map_err(|e| config_error(e.to_string()));
+    }
+    if check_file_extension(file_path, ".toml") {
+        return toml::from_str(&contents).map_err(|e| config_error(e.to_string()));
+    }
+    if check_file_extension(file_path, ".yaml") || check_file_extension(file_path, ".yml") {
+        return serde_yaml::from_str(&contents).map_err(|e| config_error(e.to_string()));
+    }
+    return Err(MarineSimError::InvalidInput(format!("Config file must end with \".json\", \".toml\", \".yaml\" or \".yml\", got {}", file_path)));
+}
+
+/// Writes a JSON, TOML or YAML file, the format is chosen from the file extension
+/// Note: Overwrites existing files
+fn write_config_file<T: serde::Serialize>(file_path: &str, value: &T) -> Result<(), MarineSimError> {
+    let contents = if check_file_extension(file_path, ".json") {
+        serde_json::to_string_pretty(value).map_err(|e| MarineSimError::InvalidInput(e.to_string()))?
+    }
+    else if check_file_extension(file_path, ".toml") {
+        toml::to_string_pretty(value).map_err(|e| MarineSimError::InvalidInput(e.to_string()))?
+    }
+    else if check_file_extension(file_path, ".yaml") || check_file_extension(file_path, ".yml") {
+        serde_yaml::to_string(value).map_err(|e| MarineSimError::InvalidInput(e.to_string()))?
+    }
+    else {
+        return Err(MarineSimError::InvalidInput(format!("Config file must end with \".json\", \".toml\", \".yaml\" or \".yml\", got {}", file_path)));
+    };
+    std::fs::write(file_path, contents)?;
+    return Ok(());
+}
+
+impl Boat {
+    /// Reads a boat from a JSON, TOML or YAML config file, fields that are left out get the defaults from Boat::new()
+    /// # Example:
+    /// `let boat = Boat::from_config_file("configs/cargo_ship.toml")?;`
+    pub fn from_config_file(file_path: &str) -> Result<Boat, MarineSimError> {
+        return read_config_file(file_path);
+    }
+
+    /// Saves the boat to a JSON, TOML or YAML config file, see Boat::from_config_file()
+    pub fn save_config_file(&self, file_path: &str) -> Result<(), MarineSimError> {
+        return write_config_file(file_path, self);
+    }
+}
+
+impl Simulation {
+    /// Reads simulation settings from a JSON, TOML or YAML config file
+    /// simulation_method, start_times, time_step and max_iterations must be in the file, other settings default to the same as in Simulation::new()
+    /// The settings in the file are validated, see Simulation.validate_config(). Settings that can not be in the file, e.g. copernicus, are set after loading, so call Simulation.validate() when it is set up
+    /// # Example:
+    /// `let mut simulation = Simulation::from_config_file("configs/north_atlantic_2025.json")?;`
+    /// `simulation.copernicus = Some(copernicus);`
+    /// `simulation.validate()?;`
+    pub fn from_config_file(file_path: &str) -> Result<Simulation, MarineSimError> {
+        let simulation: Simulation = read_config_file(file_path)?;
+        simulation.validate_config()?;
+        return Ok(simulation);
+    }
+
+    /// Saves the simulation settings to a JSON, TOML or YAML config file, see Simulation::from_config_file()
+    pub fn save_config_file(&self, file_path: &str) -> Result<(), MarineSimError> {
+        return write_config_file(file_path, self);
+    }
+}
@@ -0,0 +1,129 @@
+/// Earth model for the Marine vessel simulator.
+/// All distances, bearings and destinations in the crate are computed on the same model of the Earth, which can be chosen with set_earth_model().
+/// A simulation can use its own model with Simulation.earth_model, see with_earth_model()
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// Note: The spherical trigonometry in e.g. get_min_point_to_great_circle_dist() and SailingLeg.cross_track_distance() uses the mean radius of the model, see EarthModel.radius()
+/// Note: Rhumb lines are always computed on a sphere with the mean radius of the Earth
+
+use crate::*;   // To use everything from the crate
+
+/// Mean radius of the Earth in \[m\] (mean radius of the GRS80 ellipsoid), the same as geo::Haversine uses
+pub const MEAN_EARTH_RADIUS: f64 = 6371008.8;
+
+/// Enum of the models of the Earth
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EarthModel {
+    /// A sphere with a radius in \[m\], distances and bearings use the haversine formulas. Fast
+    Sphere { radius: f64 },
+    /// The WGS84 ellipsoid, distances and bearings use geodesics (geo::Geodesic). Accurate to within millimetres but slower
+    Wgs84,
+}
+
+impl EarthModel {
+    /// A sphere with the mean radius of the Earth, the default model
+    pub const MEAN_SPHERE: EarthModel = EarthModel::Sphere { radius: MEAN_EARTH_RADIUS };
+
+    /// Returns the radius of the model in \[m\], the mean radius of the Earth for the ellipsoid
+    pub fn radius(&self) -> f64 {
+        match self {
+            EarthModel::Sphere { radius } => *radius,
+            EarthModel::Wgs84 => MEAN_EARTH_RADIUS,
+        }
+    }
+
+    /// Returns the shortest distance in \[m\] between two points
+    pub fn distance(&self, p1: geo::Point, p2: geo::Point) -> f64 {
+        match self {
+            EarthModel::Sphere { radius } => geo::HaversineMeasure::new(*radius).distance(p1, p2),
+            EarthModel::Wgs84 => geo::Geodesic.distance(p1, p2),
+        }
+    }
+
+    /// Returns the bearing in degrees, in \[0, 360), from p1 towards p2 along the shortest path. North: 0°, East: 90°, South: 180°, West: 270°
+    pub fn bearing(&self, p1: geo::Point, p2: geo::Point) -> f64 {
+        let bearing = match self {
+            EarthModel::Sphere { radius } => geo::HaversineMeasure::new(*radius).bearing(p1, p2),
+            EarthModel::Wgs84 => geo::Geodesic.bearing(p1, p2),
+        };
+        return bearing.rem_euclid(360.0);
+    }
+
+    /// Returns the point reached by going distance \[m\] from origin with the bearing in degrees
+    pub fn destination(&self, origin: geo::Point, bearing: f64, distance: f64) -> geo::Point {
+        match self {
+            EarthModel::Sphere { radius } => geo::HaversineMeasure::new(*radius).destination(origin, bearing, distance),
+            EarthModel::Wgs84 => geo::Geodesic.destination(origin, bearing, distance),
+        }
+    }
+}
+
+/// The Earth model used by the crate, see set_earth_model()
+static EARTH_MODEL: std::sync::RwLock<EarthModel> = std::sync::RwLock::new(EarthModel::MEAN_SPHERE);
+
+/// Sets the Earth model used by the whole crate, e.g. by the simulators, routers and statistics. The default is EarthModel::MEAN_SPHERE
+/// Set it once at the start of the program, changing it while simulations are running gives inconsistent results
+/// # Example:
+/// `set_earth_model(EarthModel::Wgs84);`
+pub fn set_earth_model(model: EarthModel) {
+    *EARTH_MODEL.write().unwrap() = model;
+}
+
+thread_local! {
+    /// Earth model used on this thread instead of the one set by set_earth_model(), see with_earth_model()
+    static THREAD_EARTH_MODEL: std::cell::Cell<Option<EarthModel>> = const { std::cell::Cell::new(None) };
+}
+
+/// Returns the Earth model used by the crate, see set_earth_model()
+/// Inside with_earth_model() it is the model given to it on this thread
+pub fn get_earth_model() -> EarthModel {
+    if let Some(model) = THREAD_EARTH_MODEL.with(|thread_model| thread_model.get()) {
+        return model;
+    }
+    return *EARTH_MODEL.read().unwrap();
+}
+
+/// Runs a function with all distances, bearings and destinations on this thread computed on an Earth model, without changing the model of the rest of the program
+/// Used by sim_waypoint_mission() for Simulation.earth_model, so simulations with different models can run at the same time, e.g. on different threads
+/// # Example:
+/// `let wgs84_dist = with_earth_model(EarthModel::Wgs84, || route_plan.iter().map(|leg| leg.length()).sum::<f64>());`
+pub fn with_earth_model<T, F: FnOnce() -> T>(model: EarthModel, f: F) -> T {
+    // Puts the previous model back when dropped, also if f panics
+    struct ThreadEarthModelGuard(Option<EarthModel>);
+    impl Drop for ThreadEarthModelGuard {
+        fn drop(&mut self) {
+            THREAD_EARTH_MODEL.with(|thread_model| thread_model.set(self.0));
+        }
+    }
+    let _guard = ThreadEarthModelGuard(THREAD_EARTH_MODEL.with(|thread_model| thread_model.replace(Some(model))));
+    return f();
+}
+
+/// The Earth with the model set by set_earth_model(), used for all distances, bearings and destinations in the crate
+/// # Example:
+/// `let dist = Earth.distance(reykjavik, rotterdam);`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Earth;
+
+impl Earth {
+    /// Returns the radius in \[m\] of the Earth model, see EarthModel.radius()
+    pub fn radius(&self) -> f64 {
+        return get_earth_model().radius();
+    }
+
+    /// Returns the shortest distance in \[m\] between two points, see EarthModel.distance()
+    pub fn distance(&self, p1: geo::Point, p2: geo::Point) -> f64 {
+        return get_earth_model().distance(p1, p2);
+    }
+
+    /// Returns the bearing in degrees from p1 towards p2, see EarthModel.bearing()
+    pub fn bearing(&self, p1: geo::Point, p2: geo::Point) -> f64 {
+        return get_earth_model().bearing(p1, p2);
+    }
+
+    /// Returns the point reached by going distance \[m\] from origin with the bearing in degrees, see EarthModel.destination()
+    pub fn destination(&self, origin: geo::Point, bearing: f64, distance: f64) -> geo::Point {
+        return get_earth_model().destination(origin, bearing, distance);
+    }
+}
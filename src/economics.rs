@@ -0,0 +1,135 @@
+/// Voyage economics for the Marine vessel simulator.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// Computes the cost of simulated voyages from a cost model with the charter rate, fuel price, port fees and canal fees, so propulsion options can be compared in $/ton and $/day.
+/// The costs can be written as metadata columns of the shipping statistics csv files, see VoyageCosts.add_to_statistics()
+/// Any currency can be used as long as all the prices in the cost model are in the same currency, the docs use $
+
+use crate::*;   // To use everything from the crate
+
+/// Prices used to compute the cost of a voyage, see CostModel.voyage_costs()
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CostModel {
+    /// \[$/day\]. Charter rate, or the daily running cost of an owned vessel
+    pub charter_rate: f64,
+    /// \[$/ton\]. Fuel price
+    pub fuel_price: f64,
+    /// \[$\]. Fee for each port call, including the port of arrival
+    pub port_fee: f64,
+    /// \[$\]. Canal fees for each voyage, e.g. for a Panama or Suez canal transit
+    pub canal_fees: f64,
+}
+
+impl CostModel {
+    pub fn new(charter_rate: f64, fuel_price: f64, port_fee: f64, canal_fees: f64) -> CostModel {
+        CostModel {
+            charter_rate,
+            fuel_price,
+            port_fee,
+            canal_fees,
+        }
+    }
+
+    /// Computes the costs of a voyage
+    /// duration: Time from departure to arrival, charged at the charter rate
+    /// fuel: \[kg\]. Fuel consumed
+    /// num_port_calls: Number of port calls the port fee is charged for
+    /// cargo: \[tons\]. Cargo delivered, for the cost per ton
+    pub fn costs(&self, duration: time::Duration, fuel: f64, num_port_calls: u64, cargo: f64) -> VoyageCosts {
+        let days: f64 = duration.as_seconds_f64() / 86400.0;
+        let charter: f64 = self.charter_rate*days;
+        let fuel_cost: f64 = self.fuel_price*fuel/1000.0;
+        let port_fees: f64 = self.port_fee*(num_port_calls as f64);
+        let total: f64 = charter + fuel_cost + port_fees + self.canal_fees;
+
+        return VoyageCosts {
+            duration,
+            charter,
+            fuel: fuel_cost,
+            port_fees,
+            canal_fees: self.canal_fees,
+            total,
+            cost_per_ton: if cargo > 0.0 { Some(total / cargo) } else { None },
+            cost_per_day: if days > 0.0 { Some(total / days) } else { None },
+        };
+    }
+
+    /// Computes the costs of a simulated voyage, see sim_waypoint_mission()
+    /// The port fee is charged for each port call of the voyage (SimulationEventKind::PortCall) and for the port of arrival
+    /// The fuel is taken from the voyage KPIs, a voyage without fuel data has no fuel cost, e.g. a voyage under sail
+    /// cargo: \[tons\]. Cargo delivered on the voyage
+    /// # Example:
+    /// `let cost_model = CostModel::new(12000.0, 650.0, 8000.0, 0.0);`
+    /// `let costs = cost_model.voyage_costs(&sim_waypoint_mission(&mut boat, departure, &simulation)?, 2500.0);`
+    /// `println!("{:.2} $/ton", costs.cost_per_ton.unwrap());`
+    pub fn voyage_costs(&self, result: &SimulationResult, cargo: f64) -> VoyageCosts {
+        let num_port_calls: u64 = 1 + result.kpis.events.iter().filter(|event| matches!(event.kind, SimulationEventKind::PortCall { .. })).count() as u64;
+        return self.costs(result.kpis.duration, result.kpis.fuel.unwrap_or(0.0), num_port_calls, cargo);
+    }
+}
+
+/// Costs of a voyage, see CostModel.voyage_costs(). All costs are in the currency of the cost model
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct VoyageCosts {
+    /// Time the charter rate was charged for
+    pub duration: time::Duration,
+    /// \[$\]
+    pub charter: f64,
+    /// \[$\]
+    pub fuel: f64,
+    /// \[$\]
+    pub port_fees: f64,
+    /// \[$\]
+    pub canal_fees: f64,
+    /// \[$\]. Sum of all the costs
+    pub total: f64,
+    /// \[$/ton\]. Total cost per ton of cargo delivered, None if no cargo was delivered
+    pub cost_per_ton: Option<f64>,
+    /// \[$/day\]. Total cost per day of the voyage, None if the voyage has no duration
+    pub cost_per_day: Option<f64>,
+}
+
+impl VoyageCosts {
+    /// Returns the mean costs of voyages, e.g. of all the simulated departures of a propulsion option. None if there are no voyages
+    /// The cost per ton and cost per day are the means over the voyages where they are known
+    pub fn mean(costs: &[VoyageCosts]) -> Option<VoyageCosts> {
+        if costs.is_empty() {
+            return None;
+        }
+        let n = costs.len() as f64;
+        let mean = |value: fn(&VoyageCosts) -> f64| -> f64 { costs.iter().map(value).sum::<f64>() / n };
+        let mean_option = |value: fn(&VoyageCosts) -> Option<f64>| -> Option<f64> {
+            let values: Vec<f64> = costs.iter().filter_map(value).collect();
+            return get_mean_and_std(&values).ok().map(|(mean, _)| mean);
+        };
+        return Some(VoyageCosts {
+            duration: time::Duration::seconds_f64(mean(|costs| costs.duration.as_seconds_f64())),
+            charter: mean(|costs| costs.charter),
+            fuel: mean(|costs| costs.fuel),
+            port_fees: mean(|costs| costs.port_fees),
+            canal_fees: mean(|costs| costs.canal_fees),
+            total: mean(|costs| costs.total),
+            cost_per_ton: mean_option(|costs| costs.cost_per_ton),
+            cost_per_day: mean_option(|costs| costs.cost_per_day),
+        });
+    }
+
+    /// Adds the costs to the metadata of shipping statistics, so they are written alongside the statistics by append_shipping_statistics_to_csv()
+    /// Costs that are None are not added
+    /// # Example:
+    /// `VoyageCosts::mean(&costs).unwrap().add_to_statistics(&mut statistics);`
+    /// `append_shipping_statistics_to_csv("results.csv", "Wind-assist", &statistics)?;`
+    pub fn add_to_statistics(&self, statistics: &mut ShippingStatistics) {
+        statistics.set_metadata("charter_cost[$]", &self.charter.to_string());
+        statistics.set_metadata("fuel_cost[$]", &self.fuel.to_string());
+        statistics.set_metadata("port_fees[$]", &self.port_fees.to_string());
+        statistics.set_metadata("canal_fees[$]", &self.canal_fees.to_string());
+        statistics.set_metadata("total_cost[$]", &self.total.to_string());
+        if let Some(cost_per_ton) = self.cost_per_ton {
+            statistics.set_metadata("cost_per_ton[$/ton]", &cost_per_ton.to_string());
+        }
+        if let Some(cost_per_day) = self.cost_per_day {
+            statistics.set_metadata("cost_per_day[$/day]", &cost_per_day.to_string());
+        }
+    }
+}
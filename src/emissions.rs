@@ -0,0 +1,301 @@
+/// EU MRV and IMO CII reporting for the Marine vessel simulator.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// Aggregates the distance, cargo and fuel of the voyages of a year into the regulatory metrics, the transport work, the CO2 emitted per ton-nautical mile (EEOI, as in EU MRV) and the IMO Carbon Intensity Indicator (CII) with its rating band.
+/// Useful to express the results of e.g. a wind-assist retrofit in the metrics shipowners report on.
+/// The CO2 conversion factors are from MEPC.364(79), the CII reference lines from MEPC.353(78), the reduction factors from MEPC.338(76) and the rating boundaries from MEPC.354(78)
+/// Note: The simulators do not model engines, so the fuel of each voyage has to be given, e.g. from engine logs or an engine model
+
+use crate::*;   // To use everything from the crate
+
+/// Enum of marine fuels, used to convert the fuel consumed to CO2 emitted
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FuelType {
+    /// Heavy fuel oil (HFO)
+    HeavyFuelOil,
+    /// Light fuel oil (LFO)
+    LightFuelOil,
+    /// Marine diesel oil and marine gas oil (MDO/MGO)
+    MarineDieselOil,
+    /// Liquefied natural gas (LNG)
+    LiquefiedNaturalGas,
+    Methanol,
+}
+
+impl FuelType {
+    /// Returns the CO2 conversion factor Cf, in tons of CO2 emitted per ton of fuel consumed
+    pub fn co2_factor(&self) -> f64 {
+        return match self {
+            FuelType::HeavyFuelOil => 3.114,
+            FuelType::LightFuelOil => 3.151,
+            FuelType::MarineDieselOil => 3.206,
+            FuelType::LiquefiedNaturalGas => 2.750,
+            FuelType::Methanol => 1.375,
+        };
+    }
+}
+
+/// Enum of the ship types with a CII reference line, the reference line and the rating boundaries depend on the ship type
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CiiShipType {
+    BulkCarrier,
+    GasCarrier,
+    Tanker,
+    ContainerShip,
+    GeneralCargoShip,
+    RefrigeratedCargoCarrier,
+    CombinationCarrier,
+}
+
+impl CiiShipType {
+    /// Returns the capacity used for the CII and the parameters a and c of the reference line CII_ref = a*capacity^(-c)
+    /// deadweight: \[tons\]
+    fn reference_line(&self, deadweight: f64) -> (f64, f64, f64) {
+        return match self {
+            CiiShipType::BulkCarrier => (deadweight.min(279000.0), 4745.0, 0.622),
+            CiiShipType::GasCarrier if deadweight >= 65000.0 => (deadweight, 14405e7, 2.071),
+            CiiShipType::GasCarrier => (deadweight, 8104.0, 0.639),
+            CiiShipType::Tanker => (deadweight, 5247.0, 0.610),
+            CiiShipType::ContainerShip => (deadweight, 1984.0, 0.489),
+            CiiShipType::GeneralCargoShip if deadweight >= 20000.0 => (deadweight, 31948.0, 0.792),
+            CiiShipType::GeneralCargoShip => (deadweight, 588.0, 0.3885),
+            CiiShipType::RefrigeratedCargoCarrier => (deadweight, 4600.0, 0.557),
+            CiiShipType::CombinationCarrier => (deadweight, 5119.0, 0.622),
+        };
+    }
+
+    /// Returns the boundaries between the rating bands A/B, B/C, C/D and D/E as ratios of the attained CII to the required CII
+    fn rating_boundaries(&self, deadweight: f64) -> [f64; 4] {
+        return match self {
+            CiiShipType::BulkCarrier => [0.86, 0.94, 1.06, 1.18],
+            CiiShipType::GasCarrier if deadweight >= 65000.0 => [0.81, 0.91, 1.12, 1.44],
+            CiiShipType::GasCarrier => [0.85, 0.95, 1.06, 1.25],
+            CiiShipType::Tanker => [0.82, 0.93, 1.08, 1.28],
+            CiiShipType::ContainerShip => [0.83, 0.94, 1.07, 1.19],
+            CiiShipType::GeneralCargoShip => [0.83, 0.94, 1.06, 1.19],
+            CiiShipType::RefrigeratedCargoCarrier => [0.78, 0.91, 1.07, 1.20],
+            CiiShipType::CombinationCarrier => [0.87, 0.96, 1.06, 1.14],
+        };
+    }
+}
+
+/// Returns the CII reduction factor Z in percent for a year, relative to the 2019 reference line
+/// Years before 2019 have no reduction and years after 2026 use the 2026 factor since later factors have not been decided yet
+pub fn get_cii_reduction_factor(year: i32) -> f64 {
+    return match year {
+        ..=2019 => 0.0,
+        2020 => 1.0,
+        2021 => 2.0,
+        2022 => 3.0,
+        2023 => 5.0,
+        2024 => 7.0,
+        2025 => 9.0,
+        _ => 11.0,
+    };
+}
+
+/// CII rating band, A is the best and E the worst. Ships rated D for three years in a row or E for one year need a corrective action plan
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CiiRating {
+    A,
+    B,
+    C,
+    D,
+    E,
+}
+
+impl fmt::Display for CiiRating {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Ship particulars needed for the CII, see EmissionsReport
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CiiShip {
+    pub ship_type: CiiShipType,
+    /// \[tons\]. Deadweight tonnage, the capacity of the ship in the CII
+    pub deadweight: f64,
+    /// The fuel the ship burns
+    pub fuel_type: FuelType,
+}
+
+impl CiiShip {
+    pub fn new(ship_type: CiiShipType, deadweight: f64, fuel_type: FuelType) -> CiiShip {
+        CiiShip {
+            ship_type,
+            deadweight,
+            fuel_type,
+        }
+    }
+}
+
+/// Distance, transport work and fuel of one voyage, the input of EmissionsReport::from_voyages()
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct VoyageActivity {
+    /// \[m\]. Distance sailed
+    pub distance: f64,
+    /// \[ton*m\]. Cargo carried times the distance it was carried
+    pub transport_work: f64,
+    /// \[kg\]. Fuel consumed
+    pub fuel: f64,
+}
+
+impl VoyageActivity {
+    /// Computes the activity of a voyage from its ship log, e.g. from a simulation. The transport work is the cargo on board times the distance sailed between each pair of entries, the cargo is taken from the earlier entry
+    /// fuel: \[kg\]. Fuel consumed on the voyage, e.g. VoyageKpis.fuel or from engine logs
+    /// # Example:
+    /// `let activity = VoyageActivity::from_ship_log(&boat.ship_log, 1200.0);`
+    pub fn from_ship_log(ship_log: &[ShipLogEntry], fuel: f64) -> VoyageActivity {
+        let mut distance: f64 = 0.0;
+        let mut transport_work: f64 = 0.0;
+        for pair in ship_log.windows(2) {
+            let dist = Earth.distance(pair[0].coordinates_current, pair[1].coordinates_current);
+            distance += dist;
+            if let Some(cargo) = pair[0].cargo_on_board {
+                transport_work += cargo.get::<uom::si::mass::ton>()*dist;
+            }
+        }
+        return VoyageActivity {
+            distance,
+            transport_work,
+            fuel,
+        };
+    }
+}
+
+/// Annual emissions report of a ship with the EU MRV and IMO CII metrics, see EmissionsReport::from_voyages()
+/// Metrics that can not be computed, e.g. the EEOI when no cargo was carried, are None
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmissionsReport {
+    /// The reporting year, decides the CII reduction factor
+    pub year: i32,
+    /// Number of voyages in the report
+    pub num_voyages: usize,
+    /// \[nmi\]. Total distance sailed
+    pub distance: f64,
+    /// \[ton*nmi\]. Total transport work, cargo carried times the distance it was carried
+    pub transport_work: f64,
+    /// \[tons\]. Total fuel consumed
+    pub fuel: f64,
+    /// \[tons\]. Total CO2 emitted
+    pub co2: f64,
+    /// \[gCO2/(ton*nmi)\]. Energy efficiency operational indicator, the CO2 emitted per transport work as in EU MRV
+    pub eeoi: Option<f64>,
+    /// \[gCO2/(dwt*nmi)\]. Attained CII, the annual efficiency ratio (AER), CO2 emitted per deadweight capacity and distance sailed
+    pub attained_cii: Option<f64>,
+    /// \[gCO2/(dwt*nmi)\]. Required CII for the year, the reference line reduced by get_cii_reduction_factor()
+    pub required_cii: f64,
+    /// CII rating band of the attained CII
+    pub cii_rating: Option<CiiRating>,
+}
+
+impl EmissionsReport {
+    /// Aggregates the voyages of a year into an emissions report
+    /// Returns an error if the deadweight of the ship is not positive
+    /// # Example:
+    /// `let ship = CiiShip::new(CiiShipType::GeneralCargoShip, 3000.0, FuelType::MarineDieselOil);`
+    /// `let report = EmissionsReport::from_voyages(2025, &ship, &[VoyageActivity::from_ship_log(&boat.ship_log, 25000.0)])?;`
+    /// `println!("{}", report);`
+    pub fn from_voyages(year: i32, ship: &CiiShip, voyages: &[VoyageActivity]) -> Result<EmissionsReport, MarineSimError> {
+        if !(ship.deadweight > 0.0) {
+            return Err(MarineSimError::InvalidInput("The deadweight of the ship must be positive".to_string()));
+        }
+
+        // Totals in the units of the regulations
+        let metres_per_nautical_mile: f64 = 1852.0;
+        let distance: f64 = voyages.iter().map(|voyage| voyage.distance).sum::<f64>() / metres_per_nautical_mile;
+        let transport_work: f64 = voyages.iter().map(|voyage| voyage.transport_work).sum::<f64>() / metres_per_nautical_mile;
+        let fuel: f64 = voyages.iter().map(|voyage| voyage.fuel).sum::<f64>() / 1000.0;
+        let co2: f64 = fuel*ship.fuel_type.co2_factor();
+
+        // Attained and required CII, unit [gCO2/(dwt*nmi)]
+        let (capacity, a, c) = ship.ship_type.reference_line(ship.deadweight);
+        let attained_cii: Option<f64> = if distance > 0.0 { Some(co2*1e6 / (capacity*distance)) } else { None };
+        let required_cii: f64 = (1.0 - get_cii_reduction_factor(year)/100.0)*a*capacity.powf(-c);
+
+        // Rating band from the ratio of the attained CII to the required CII
+        let boundaries = ship.ship_type.rating_boundaries(ship.deadweight);
+        let cii_rating: Option<CiiRating> = attained_cii.map(|attained| {
+            let ratio = attained / required_cii;
+            match boundaries.iter().position(|boundary| ratio < *boundary) {
+                Some(0) => CiiRating::A,
+                Some(1) => CiiRating::B,
+                Some(2) => CiiRating::C,
+                Some(3) => CiiRating::D,
+                _ => CiiRating::E,
+            }
+        });
+
+        return Ok(EmissionsReport {
+            year,
+            num_voyages: voyages.len(),
+            distance,
+            transport_work,
+            fuel,
+            co2,
+            eeoi: if transport_work > 0.0 { Some(co2*1e6 / transport_work) } else { None },
+            attained_cii,
+            required_cii,
+            cii_rating,
+        });
+    }
+}
+
+/// std::Display for EmissionsReport, one line per metric. Values that could not be computed are shown as "-"
+impl fmt::Display for EmissionsReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = |v: Option<f64>| -> String {
+            return match v {
+                Some(v) => format!("{:.2}", v),
+                None => "-".to_string(),
+            };
+        };
+        writeln!(f, "Year: {}, voyages: {}", self.year, self.num_voyages)?;
+        writeln!(f, "Distance [nmi]: {:.1}", self.distance)?;
+        writeln!(f, "Transport work [ton*nmi]: {:.0}", self.transport_work)?;
+        writeln!(f, "Fuel [tons]: {:.2}, CO2 [tons]: {:.2}", self.fuel, self.co2)?;
+        writeln!(f, "EEOI [gCO2/(ton*nmi)]: {}", value(self.eeoi))?;
+        write!(f, "CII [gCO2/(dwt*nmi)]: attained {}, required {:.2}, rating {}", value(self.attained_cii), self.required_cii, self.cii_rating.map_or("-".to_string(), |rating| rating.to_string()))
+    }
+}
+
+/// Writes emissions reports to a csv file, one row per report, e.g. one per propulsion option or per year
+/// Will overwrite any file with the same file name at csv_file_path. csv_file_path must end with ".csv"
+/// Values that are None are written as empty cells
+/// # Example:
+/// `emissions_reports_to_csv("emissions.csv", &[("Diesel only", &diesel_report), ("Wind-assist", &wind_assist_report)])?;`
+pub fn emissions_reports_to_csv(csv_file_path: &str, reports: &[(&str, &EmissionsReport)]) -> Result<(), MarineSimError> {
+    if !check_file_extension(csv_file_path, ".csv") {
+        return Err(MarineSimError::InvalidInput("The filepath must end with \".csv\"".to_string()));
+    }
+
+    // Create a CSV writer with a semicolon delimiter
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(csv_file_path)?;
+
+    // Write the header and rows
+    wtr.write_record(["name", "year", "num_voyages", "distance[nmi]", "transport_work[ton*nmi]", "fuel[tons]", "co2[tons]", "eeoi[gCO2/ton-nmi]", "attained_cii[gCO2/dwt-nmi]", "required_cii[gCO2/dwt-nmi]", "cii_rating"])?;
+    let f64_cell = |value: Option<f64>| value.map_or(String::new(), |v| v.to_string());
+    for (name, report) in reports {
+        wtr.write_record([
+            name.to_string(),
+            report.year.to_string(),
+            report.num_voyages.to_string(),
+            report.distance.to_string(),
+            report.transport_work.to_string(),
+            report.fuel.to_string(),
+            report.co2.to_string(),
+            f64_cell(report.eeoi),
+            f64_cell(report.attained_cii),
+            report.required_cii.to_string(),
+            report.cii_rating.map_or(String::new(), |rating| rating.to_string()),
+        ])?;
+    }
+
+    // Flush and close the writer
+    wtr.flush()?;
+    return Ok(());
+}
@@ -0,0 +1,121 @@
+/// Error type for the Marine vessel simulator.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// All fallible functions in lib.rs, simulators.rs and vessels.rs return MarineSimError so library users can match on what went wrong, e.g. retry when fetching weather fails but fix the input file when a CSV file can not be parsed.
+/// MarineSimError converts to and from io::Error, so the ? operator works between functions that return either of them.
+
+use crate::*;   // To use everything from the crate
+
+/// Enum of the errors the simulator can return
+#[derive(Debug, thiserror::Error)]
+pub enum MarineSimError {
+    /// Reading or writing a file failed
+    #[error("I/O error: {0}")]
+    Io(io::Error),
+    /// The CSV reader failed, e.g. the file could not be opened or a line could not be read
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    /// A CSV file, or another data file such as a GPX file, was read but its contents could not be parsed
+    #[error("Could not parse {file}: {message}")]
+    CsvParse {
+        /// Path to the CSV file
+        file: String,
+        /// What is wrong with the contents
+        message: String,
+    },
+    /// A field in a CSV file is missing or can not be parsed
+    #[error("Could not parse {field} on line {line} of {file}: {message}")]
+    CsvField {
+        /// Path to the CSV file
+        file: String,
+        /// Line number in the file, the header is line 1
+        line: u64,
+        /// Name of the field
+        field: String,
+        /// What is wrong with the field
+        message: String,
+    },
+    /// A config file was read but its contents could not be parsed, see Boat::from_config_file()
+    #[error("Could not parse config file {file}: {message}")]
+    ConfigParse {
+        /// Path to the config file
+        file: String,
+        /// What is wrong with the contents
+        message: String,
+    },
+    /// The boat is missing a field that is needed, e.g. a route plan or a sail
+    #[error("Boat is missing {0}")]
+    MissingBoatField(String),
+    /// The boat is missing fields that are needed for a simulation method, see BoatBuilder.build_for()
+    #[error("Boat is missing {}", .0.join(", "))]
+    MissingBoatFields(Vec<String>),
+    /// Weather data could not be fetched, e.g. from copernicus
+    #[error("Could not fetch weather data: {0}")]
+    WeatherFetch(String),
+    /// Invalid coordinates or a route that can not be sailed, e.g. through sea ice
+    #[error("Geometry error: {0}")]
+    Geometry(String),
+    /// Invalid input to a function, e.g. a negative time step or vectors of different lengths
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    /// Reading or writing the SQLite database failed, see ResultDatabase
+    #[cfg(feature = "storage")]
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+    /// A simulation failed, context says which one and source why
+    #[error("{context}: {source}")]
+    Simulation {
+        /// Which simulation failed, e.g. the start time
+        context: String,
+        /// Why it failed
+        source: Box<MarineSimError>,
+    },
+}
+
+impl MarineSimError {
+    /// Wraps an error from a simulation with context, e.g. which start time was simulated
+    /// # Example:
+    /// `return Err(MarineSimError::simulation(format!("Error during simulation departing {}", departure), e));`
+    pub fn simulation(context: String, source: MarineSimError) -> MarineSimError {
+        return MarineSimError::Simulation {
+            context,
+            source: Box::new(source),
+        };
+    }
+
+    /// Returns the innermost error, i.e. the error a simulation failed with
+    pub fn root_cause(&self) -> &MarineSimError {
+        return match self {
+            MarineSimError::Simulation { source, .. } => source.root_cause(),
+            e => e,
+        };
+    }
+}
+
+/// So functions that return MarineSimError can use the ? operator on functions that return io::Error, e.g. a WeatherProvider
+/// A MarineSimError that was converted to an io::Error is unwrapped again
+impl From<io::Error> for MarineSimError {
+    fn from(e: io::Error) -> MarineSimError {
+        if e.get_ref().is_some_and(|inner| inner.is::<MarineSimError>()) {
+            // The inner error was checked above so the unwraps can not fail
+            return *e.into_inner().unwrap().downcast::<MarineSimError>().unwrap();
+        }
+        return MarineSimError::Io(e);
+    }
+}
+
+/// So functions that return io::Error can use the ? operator on functions that return MarineSimError
+impl From<MarineSimError> for io::Error {
+    fn from(e: MarineSimError) -> io::Error {
+        let kind = match e.root_cause() {
+            MarineSimError::Io(io_error) => io_error.kind(),
+            MarineSimError::Csv(_) | MarineSimError::CsvParse { .. } | MarineSimError::CsvField { .. } | MarineSimError::ConfigParse { .. } => io::ErrorKind::InvalidData,
+            MarineSimError::MissingBoatField(_) | MarineSimError::MissingBoatFields(_) | MarineSimError::InvalidInput(_) | MarineSimError::Geometry(_) => io::ErrorKind::InvalidInput,
+            _ => io::ErrorKind::Other,
+        };
+        return match e {
+            MarineSimError::Io(io_error) => io_error,
+            e => io::Error::new(kind, e),
+        };
+    }
+}
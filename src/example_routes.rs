@@ -0,0 +1,166 @@
+/// Library of example route plans for the Marine vessel simulator.
+/// The routes are meant as shared, realistic inputs for studies and benchmarks so they don't have to start from scratch.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// Note: The waypoints are chosen to keep the legs clear of land but they are NOT meant for navigation.
+
+use crate::*;   // To use everything from the crate
+
+/// One waypoint of an example route: (latitude, longitude, tacking_width \[m\], min_proximity \[m\], port name)
+/// The tacking width and minimum proximity apply to the leg ending at the waypoint, so they are ignored for the first waypoint
+/// The port name is Some if the vessel stops at the waypoint
+type ExampleWaypoint = (f64, f64, f64, f64, Option<&'static str>);
+
+/// Reykjavik, Iceland to Rotterdam, Netherlands. South of the Faroe Islands, through the Fair Isle channel and across the North Sea
+const REYKJAVIK_ROTTERDAM: &[ExampleWaypoint] = &[
+    (64.150, -21.950, 0.0, 0.0, Some("Reykjavik")),
+    (64.170, -22.300, 2000.0, 500.0, None),
+    (63.750, -22.900, 10000.0, 2000.0, None),
+    (63.200, -20.300, 30000.0, 5000.0, None),
+    (61.000, -7.500, 100000.0, 10000.0, None),
+    (59.450, -1.750, 20000.0, 5000.0, None),
+    (56.000, 2.500, 60000.0, 10000.0, None),
+    (52.000, 3.800, 30000.0, 2000.0, None),
+    (51.980, 4.050, 2000.0, 500.0, Some("Rotterdam")),
+];
+
+/// Halifax, Canada to Le Havre, France. South of Sable Island and the Grand Banks, across the Atlantic and up the English Channel
+const HALIFAX_LE_HAVRE: &[ExampleWaypoint] = &[
+    (44.640, -63.560, 0.0, 0.0, Some("Halifax")),
+    (44.400, -63.400, 5000.0, 1000.0, None),
+    (43.600, -60.000, 40000.0, 5000.0, None),
+    (43.000, -50.000, 100000.0, 10000.0, None),
+    (46.500, -30.000, 200000.0, 10000.0, None),
+    (49.600, -6.800, 200000.0, 10000.0, None),
+    (50.000, -3.000, 40000.0, 5000.0, None),
+    (49.550, 0.000, 20000.0, 2000.0, None),
+    (49.480, 0.100, 2000.0, 500.0, Some("Le Havre")),
+];
+
+/// Baltic feeder loop from Gdansk, Poland calling at Klaipeda, Riga and Nynashamn (Stockholm) and back to Gdansk
+/// East of Gotland both ways, through the Irbe strait into and out of the Gulf of Riga
+const BALTIC_FEEDER_LOOP: &[ExampleWaypoint] = &[
+    (54.400, 18.670, 0.0, 0.0, Some("Gdansk")),
+    (54.800, 19.200, 5000.0, 1000.0, None),
+    (55.700, 20.900, 20000.0, 2000.0, None),
+    (55.720, 21.100, 2000.0, 500.0, Some("Klaipeda")),
+    (55.700, 20.900, 2000.0, 500.0, None),
+    (56.500, 20.600, 20000.0, 2000.0, None),
+    (57.400, 21.200, 20000.0, 2000.0, None),
+    (57.800, 21.600, 10000.0, 1000.0, None),
+    (57.880, 22.500, 5000.0, 1000.0, None),
+    (57.500, 23.500, 20000.0, 2000.0, None),
+    (57.050, 24.030, 2000.0, 500.0, Some("Riga")),
+    (57.500, 23.500, 2000.0, 500.0, None),
+    (57.880, 22.500, 20000.0, 2000.0, None),
+    (57.800, 21.600, 5000.0, 1000.0, None),
+    (58.600, 20.000, 30000.0, 2000.0, None),
+    (58.650, 18.100, 20000.0, 2000.0, None),
+    (58.900, 17.950, 2000.0, 500.0, Some("Nynashamn")),
+    (58.650, 18.100, 2000.0, 500.0, None),
+    (58.600, 20.000, 20000.0, 2000.0, None),
+    (56.500, 19.700, 40000.0, 5000.0, None),
+    (54.800, 19.200, 40000.0, 2000.0, None),
+    (54.400, 18.670, 2000.0, 500.0, Some("Gdansk")),
+];
+
+/// Enum of the example routes in the library
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ExampleRoute {
+    /// Reykjavik, Iceland to Rotterdam, Netherlands
+    ReykjavikRotterdam,
+    /// Halifax, Canada to Le Havre, France
+    HalifaxLeHavre,
+    /// Baltic feeder loop Gdansk - Klaipeda - Riga - Nynashamn - Gdansk
+    BalticFeederLoop,
+}
+
+impl ExampleRoute {
+    /// Returns all the example routes in the library
+    pub fn all() -> Vec<ExampleRoute> {
+        return vec![ExampleRoute::ReykjavikRotterdam, ExampleRoute::HalifaxLeHavre, ExampleRoute::BalticFeederLoop];
+    }
+
+    /// Returns the name of the example route
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExampleRoute::ReykjavikRotterdam => "Reykjavik-Rotterdam",
+            ExampleRoute::HalifaxLeHavre => "Halifax-Le Havre",
+            ExampleRoute::BalticFeederLoop => "Baltic feeder loop",
+        }
+    }
+
+    /// Returns the waypoint table of the example route
+    fn waypoints(&self) -> &'static [ExampleWaypoint] {
+        match self {
+            ExampleRoute::ReykjavikRotterdam => REYKJAVIK_ROTTERDAM,
+            ExampleRoute::HalifaxLeHavre => HALIFAX_LE_HAVRE,
+            ExampleRoute::BalticFeederLoop => BALTIC_FEEDER_LOOP,
+        }
+    }
+
+    /// Returns the route plan of the example route, ready to be used as boat.route_plan
+    /// # Example:
+    /// `boat.route_plan = Some(ExampleRoute::ReykjavikRotterdam.route_plan());`
+    pub fn route_plan(&self) -> RoutePlan {
+        let waypoints = self.waypoints();
+        // Init route plan
+        let mut route_plan: Vec<SailingLeg> = Vec::with_capacity(waypoints.len() - 1);
+
+        // Make a leg between each pair of consecutive waypoints
+        for pair in waypoints.windows(2) {
+            let (start_lat, start_lon, _, _, _) = pair[0];
+            let (end_lat, end_lon, tacking_width, min_proximity, _) = pair[1];
+            route_plan.push(SailingLeg::new(geo::Point::new(start_lon, start_lat), geo::Point::new(end_lon, end_lat), tacking_width, min_proximity));
+        }
+
+        // Return route plan
+        return RoutePlan::from(route_plan);
+    }
+
+    /// Returns the ports on the example route in the order they are visited, including the start and end ports
+    /// Each port is returned with its location and the number of the leg that ends there, the start port is at leg 0
+    pub fn port_stops(&self) -> Vec<(&'static str, geo::Point, usize)> {
+        let mut ports: Vec<(&'static str, geo::Point, usize)> = Vec::new();
+        for (i, (lat, lon, _, _, port)) in self.waypoints().iter().enumerate() {
+            if let Some(name) = port {
+                ports.push((*name, geo::Point::new(*lon, *lat), i));
+            }
+        }
+        return ports;
+    }
+
+    /// Saves the route plan of the example route to a CSV file that can be loaded with load_route_plan()
+    /// Useful as a starting point for your own route plans
+    pub fn save_route_plan_to_csv(&self, file_path: &str) -> Result<(), io::Error> {
+        // Check file extension
+        if !check_file_extension(file_path, ".csv") {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "File path must end with .csv"));
+        }
+
+        // Create a CSV writer with a semicolon delimiter
+        let mut wtr = csv::WriterBuilder::new()
+            .delimiter(b';')
+            .from_path(file_path)?;
+
+        // Write header
+        wtr.write_record(&["leg", "start_lat", "start_lon", "end_lat", "end_lon", "tacking_width[m]", "min_proximity[m]"])?;
+
+        // Write legs
+        for (i, leg) in self.route_plan().iter().enumerate() {
+            wtr.write_record(&[
+                (i + 1).to_string(),
+                leg.p1.y().to_string(),
+                leg.p1.x().to_string(),
+                leg.p2.y().to_string(),
+                leg.p2.x().to_string(),
+                leg.tacking_width.to_string(),
+                leg.min_proximity.to_string(),
+            ])?;
+        }
+
+        // Flush the writer to ensure all data is written
+        wtr.flush()?;
+        return Ok(());
+    }
+}
@@ -0,0 +1,107 @@
+/// GPX import and export for the Marine vessel simulator.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// GPX is the GPS exchange format that most chart plotters and planning software read and write, e.g. OpenCPN and Navionics.
+/// Ship logs are exported as GPX tracks and GPX routes are imported as route plans, see ship_log_to_gpx() and gpx_to_route_plan().
+/// Only the parts of GPX 1.1 the simulator uses are read and written, the positions of route and track points and the times of track points. See: <https://www.topografix.com/gpx.asp>
+
+use crate::*;   // To use everything from the crate
+
+/// Writes a ship log to a GPX file as a track with one track point per ship log entry, with the position and time of the entry
+/// Will overwrite any file with the same file name at gpx_file_path. gpx_file_path must end with ".gpx"
+/// name: The name of the track, e.g. the name of the boat
+/// # Example:
+/// `ship_log_to_gpx("voyage.gpx", &boat.ship_log, "Sailing cargo ship")?;`
+pub fn ship_log_to_gpx(gpx_file_path: &str, ship_log: &[ShipLogEntry], name: &str) -> Result<(), MarineSimError> {
+    if !check_file_extension(gpx_file_path, ".gpx") {
+        return Err(MarineSimError::InvalidInput("The filepath must end with \".gpx\"".to_string()));
+    }
+
+    let mut gpx = String::new();
+    gpx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    gpx.push_str("<gpx version=\"1.1\" creator=\"marine_vessel_simulator\" xmlns=\"http://www.topografix.com/GPX/1/1\">\n");
+    gpx.push_str("  <trk>\n");
+    gpx.push_str(&format!("    <name>{}</name>\n", escape_xml(name)));
+    gpx.push_str("    <trkseg>\n");
+    for entry in ship_log {
+        let timestamp = match entry.timestamp.format(&time::format_description::well_known::Rfc3339) {
+            Ok(timestamp) => timestamp,
+            Err(e) => return Err(MarineSimError::InvalidInput(format!("Could not format timestamp {}: {}", entry.timestamp, e))),
+        };
+        gpx.push_str(&format!("      <trkpt lat=\"{}\" lon=\"{}\"><time>{}</time></trkpt>\n", entry.coordinates_current.y(), entry.coordinates_current.x(), timestamp));
+    }
+    gpx.push_str("    </trkseg>\n");
+    gpx.push_str("  </trk>\n");
+    gpx.push_str("</gpx>\n");
+
+    std::fs::write(gpx_file_path, gpx)?;
+    return Ok(());
+}
+
+/// Reads the route in a GPX file as a route plan, with a leg between each pair of consecutive route points
+/// All the legs get the default tacking width and minimum proximity, see RoutePlan::DEFAULT_TACKING_WIDTH and RoutePlan::DEFAULT_MIN_PROXIMITY
+/// If the file has more than one route, the routes are joined in the order they appear. A file without route points is read from its track points instead, e.g. a recorded voyage
+/// Returns an error if the file has fewer than two points or a point without a valid latitude and longitude
+/// # Example:
+/// `boat.route_plan = Some(gpx_to_route_plan("planned_in_opencpn.gpx")?);`
+pub fn gpx_to_route_plan(gpx_file_path: &str) -> Result<RoutePlan, MarineSimError> {
+    let gpx = std::fs::read_to_string(gpx_file_path)?;
+
+    // Route points, or track points if the file has no route
+    let mut points = get_gpx_points(&gpx, "rtept", gpx_file_path)?;
+    if points.is_empty() {
+        points = get_gpx_points(&gpx, "trkpt", gpx_file_path)?;
+    }
+    if points.len() < 2 {
+        return Err(MarineSimError::CsvParse { file: gpx_file_path.to_string(), message: format!("Needs at least 2 route points to make a route plan, found {}", points.len()) });
+    }
+
+    let legs: Vec<SailingLeg> = points.windows(2).map(|pair| SailingLeg::new(pair[0], pair[1], RoutePlan::DEFAULT_TACKING_WIDTH, RoutePlan::DEFAULT_MIN_PROXIMITY)).collect();
+    return Ok(RoutePlan::from(legs));
+}
+
+/// Returns the positions of all the elements with the tag, e.g. "rtept", in the order they appear, x is longitude and y is latitude
+fn get_gpx_points(gpx: &str, tag: &str, gpx_file_path: &str) -> Result<Vec<geo::Point>, MarineSimError> {
+    let mut points: Vec<geo::Point> = Vec::new();
+    let start_tag = format!("<{}", tag);
+    let mut rest: &str = gpx;
+    while let Some(start) = rest.find(&start_tag) {
+        rest = &rest[start + start_tag.len()..];
+        // Skip longer tags that start with the same name
+        if !rest.starts_with(|c: char| c.is_whitespace()) {
+            continue;
+        }
+        let attributes = match rest.find('>') {
+            Some(end) => &rest[..end],
+            None => return Err(MarineSimError::CsvParse { file: gpx_file_path.to_string(), message: format!("Unterminated <{}> element", tag) }),
+        };
+        match (get_xml_attribute(attributes, "lat").and_then(|lat| lat.parse::<f64>().ok()), get_xml_attribute(attributes, "lon").and_then(|lon| lon.parse::<f64>().ok())) {
+            (Some(lat), Some(lon)) if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) => points.push(geo::Point::new(lon, lat)),
+            _ => return Err(MarineSimError::CsvParse { file: gpx_file_path.to_string(), message: format!("<{}> element without a valid lat and lon: <{}{}>", tag, tag, attributes) }),
+        }
+    }
+    return Ok(points);
+}
+
+/// Returns the value of an attribute of an XML element, the attributes are the text between the tag name and the closing >
+fn get_xml_attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    for quote in ['"', '\''] {
+        let pattern = format!("{}={}", name, quote);
+        let mut search_from: usize = 0;
+        while let Some(position) = attributes[search_from..].find(&pattern) {
+            let start = search_from + position;
+            // The attribute name must not be the end of a longer name, e.g. "lat" in "xlat"
+            if start == 0 || attributes[..start].ends_with(|c: char| c.is_whitespace()) {
+                let value_start = start + pattern.len();
+                return attributes[value_start..].find(quote).map(|end| &attributes[value_start..value_start + end]);
+            }
+            search_from = start + pattern.len();
+        }
+    }
+    return None;
+}
+
+/// Escapes the characters that have a special meaning in XML text
+fn escape_xml(text: &str) -> String {
+    return text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;");
+}
@@ -0,0 +1,145 @@
+/// Land mask for the Marine vessel simulator, used to detect when a vessel would sail onto land (run aground).
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// The land mask is a set of polygons in longitude/latitude, e.g. a subset of the GSHHG coastlines (https://www.soest.hawaii.edu/pwessel/gshhg/)
+/// exported to a CSV file, see LandMask::from_csv()
+/// Note: Line crossings are checked in longitude/latitude space so they are approximate for long lines, long lines are split into shorter segments first.
+
+use crate::*;   // To use everything from the crate
+use geo::{Contains, Intersects};    // To check if points and lines are on land
+
+/// Lines longer than this, in \[m\], are split into segments of at most this length before checking if they cross land
+const LAND_MASK_MAX_SEGMENT_LENGTH: f64 = 10000.0;
+
+/// Land mask made of land polygons
+#[derive(Debug, Clone)]
+pub struct LandMask {
+    /// The land polygons, x is longitude and y is latitude
+    pub polygons: Vec<geo::Polygon>,
+}
+
+impl LandMask {
+    /// Creates a new land mask from land polygons, x is longitude and y is latitude
+    pub fn new(polygons: Vec<geo::Polygon>) -> LandMask {
+        LandMask {
+            polygons,
+        }
+    }
+
+    /// Loads a land mask from a CSV file with the following columns in order, the header names are not important:
+    /// polygon;latitude;longitude
+    /// Each polygon is the rows with the same polygon id, in order around the polygon. The delimiter is a semicolon.
+    /// # Example:
+    /// `let land_mask = LandMask::from_csv("north_atlantic_coastlines.csv")?;`
+    pub fn from_csv(file_path: &str) -> Result<LandMask, io::Error> {
+        // Check file extension
+        if !check_file_extension(file_path, ".csv") {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "File path must end with .csv"));
+        }
+
+        // Read the polygons, the polygon ids are not needed
+        let polygons: Vec<geo::Polygon> = read_polygons_from_csv(file_path)?.into_iter().map(|(_, polygon)| polygon).collect();
+
+        return Ok(LandMask::new(polygons));
+    }
+
+    /// Returns true if the point is on land
+    pub fn is_on_land(&self, point: geo::Point) -> bool {
+        return self.polygons.iter().any(|polygon| polygon.contains(&point));
+    }
+
+    /// Returns true if the great circle line from p1 to p2 crosses or touches land
+    pub fn crosses_land(&self, p1: geo::Point, p2: geo::Point) -> bool {
+        return line_crosses_polygons(&self.polygons, p1, p2);
+    }
+
+    /// Checks a simulation step from one location to the next
+    /// Returns an error identifying the leg if the step would put the vessel on land or sail it across land
+    pub fn check_step(&self, from: geo::Point, to: geo::Point, leg_number: u32) -> Result<(), io::Error> {
+        if self.crosses_land(from, to) {
+            return Err(io::Error::new(io::ErrorKind::Other, format!("Vessel ran aground on leg {} between latitude {:.4}, longitude {:.4} and latitude {:.4}, longitude {:.4}. Is a waypoint misplaced?", leg_number, from.y(), from.x(), to.y(), to.x())));
+        }
+        return Ok(());
+    }
+
+    /// Checks if any leg in a route plan crosses land, useful to find misplaced waypoints before simulating
+    /// Returns the leg numbers (starting at 1) of the legs that cross land
+    pub fn legs_crossing_land(&self, route_plan: &Vec<SailingLeg>) -> Vec<usize> {
+        let mut legs: Vec<usize> = Vec::new();
+        for (i, leg) in route_plan.iter().enumerate() {
+            if self.crosses_land(leg.p1, leg.p2) {
+                legs.push(i + 1);
+            }
+        }
+        return legs;
+    }
+}
+
+/// Reads polygons from a CSV file with the following columns in order, the header names are not important:
+/// polygon;latitude;longitude
+/// Each polygon is the rows with the same polygon id, in order around the polygon. The delimiter is a semicolon.
+/// Returns the polygons with their ids, x is longitude and y is latitude
+pub(crate) fn read_polygons_from_csv(file_path: &str) -> Result<Vec<(String, geo::Polygon)>, io::Error> {
+    // Read the CSV file
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(file_path)?;
+
+    // Init polygons, the id of the polygon being read and its vertices
+    let mut polygons: Vec<(String, geo::Polygon)> = Vec::new();
+    let mut current_id: Option<String> = None;
+    let mut vertices: Vec<(f64, f64)> = Vec::new();
+
+    for (i, result) in csv_reader.records().enumerate() {
+        let record = result?;
+        // Line number in file, header is line 1
+        let line = i + 2;
+        let id = match record.get(0) {
+            Some(id) => id.trim().to_string(),
+            None => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Polygon id missing from polygon file on line {}", line))),
+        };
+        let lat: f64 = match record.get(1).map(|s| s.trim().parse::<f64>()) {
+            Some(Ok(lat)) => lat,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Invalid latitude in polygon file on line {}", line))),
+        };
+        let lon: f64 = match record.get(2).map(|s| s.trim().parse::<f64>()) {
+            Some(Ok(lon)) => lon,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Invalid longitude in polygon file on line {}", line))),
+        };
+
+        // New polygon id, finish the last polygon
+        if current_id.as_ref() != Some(&id) {
+            if let (Some(last_id), true) = (current_id, vertices.len() >= 3) {
+                polygons.push((last_id, geo::Polygon::new(geo::LineString::from(vertices), vec![])));
+            }
+            vertices = Vec::new();
+            current_id = Some(id);
+        }
+        vertices.push((lon, lat));
+    }
+    // Finish the last polygon
+    if let (Some(last_id), true) = (current_id, vertices.len() >= 3) {
+        polygons.push((last_id, geo::Polygon::new(geo::LineString::from(vertices), vec![])));
+    }
+
+    return Ok(polygons);
+}
+
+/// Returns true if the great circle line from p1 to p2 crosses or touches any of the polygons
+/// The line is split into short segments first, see LAND_MASK_MAX_SEGMENT_LENGTH
+pub(crate) fn line_crosses_polygons(polygons: &[geo::Polygon], p1: geo::Point, p2: geo::Point) -> bool {
+    // Split the line into short segments along the great circle
+    let line = SailingLeg::new(p1, p2, 0.0, 0.0);
+    let n_segments = (line.length() / LAND_MASK_MAX_SEGMENT_LENGTH).ceil().max(1.0) as usize;
+    let mut last_point = p1;
+    for i in 1..=n_segments {
+        let point = line.point_at(i as f64 / n_segments as f64);
+        let segment = geo::Line::new(last_point, point);
+        if polygons.iter().any(|polygon| polygon.intersects(&segment)) {
+            return true;
+        }
+        last_point = point;
+    }
+    return false;
+}
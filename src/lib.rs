@@ -7,16 +7,23 @@
 
 /// External crates
 use csv; // CSV reader to read csv files
-use geo::{self, Haversine, Rhumb, Bearing, Distance, Destination};    // Geographical calculations. Used to calculate the distance between two coordinates and bearings
+use geo::{self, Rhumb, Bearing, Distance, Destination};    // Geographical calculations. Used to calculate the distance between two coordinates and bearings
 use year_helper; // Year helper to calculate the number of days in a year based on the month and if it's a leap year or not
 use std::{io, fmt, f64::consts, fs::File, io::Write}; // To use errors, formatting, constants, write to file
 // use plotters; // Plotters for visualizing data on a map. Uses only rust, no javascript. Will probably be removed in favor of plotly
 use plotly; // Plotly for visualizing data on a map. Testing in comparison agains plotters
+#[cfg(feature = "copernicus")]
 use copernicusmarine_rs;    // To get weather data
 use time;   // To do time calculations
 use time::UtcDateTime;  // To use UtcDateTime
+#[cfg(feature = "progress_bar")]
 use indicatif;   // For progress bar
+#[cfg(feature = "progress_bar")]
 use atty;       // To check if terminal is interactive or not
+#[cfg(feature = "netcdf")]
+use netcdf;     // To read bathymetry files
+use thiserror;  // To derive the error type, see MarineSimError
+use tracing;    // Warnings, progress and debug messages as tracing events, the program using the crate chooses what is shown with a tracing subscriber, e.g. tracing_subscriber::fmt()
 
 
 // Internal modules
@@ -24,17 +31,88 @@ pub mod simulators;
 pub use crate::simulators::*; // Import the simulators module
 pub mod vessels;
 pub use crate::vessels::*; // Import the simulators module
+pub mod example_routes;
+pub use crate::example_routes::*; // Import the example routes module
+pub mod land_mask;
+pub use crate::land_mask::*; // Import the land mask module
+pub mod bathymetry;
+pub use crate::bathymetry::*; // Import the bathymetry module
+pub mod weather;
+pub use crate::weather::*; // Import the weather module
+pub mod routing;
+pub use crate::routing::*; // Import the routing module
+pub mod restricted_zones;
+pub use crate::restricted_zones::*; // Import the restricted zones module
+pub mod earth;
+pub use crate::earth::*; // Import the earth module
+pub mod calibration;
+pub use crate::calibration::*; // Import the calibration module
+pub mod error;
+pub use crate::error::*; // Import the error module
+pub mod emissions;
+pub use crate::emissions::*; // Import the emissions module
+pub mod economics;
+pub use crate::economics::*; // Import the economics module
+pub mod gpx;
+pub use crate::gpx::*; // Import the GPX module
+pub mod nmea;
+pub use crate::nmea::*; // Import the NMEA module
+pub mod ais;
+pub use crate::ais::*; // Import the AIS module
+pub mod telemetry;
+pub use crate::telemetry::*; // Import the telemetry module
+pub mod search_and_rescue;
+pub use crate::search_and_rescue::*; // Import the search and rescue module
+pub mod ports;
+pub use crate::ports::*; // Import the port database module
+#[cfg(feature = "serde")]
+pub mod serde_units;   // Unit-tagged serialization of uom quantities, used with #[serde(with = ...)]
+#[cfg(feature = "serde")]
+pub mod config;   // Boat and Simulation config files, see Boat::from_config_file()
+#[cfg(feature = "parquet")]
+pub mod parquet_export;
+#[cfg(feature = "parquet")]
+pub use crate::parquet_export::*; // Import the Parquet export module
+#[cfg(feature = "storage")]
+pub mod storage;
+#[cfg(feature = "storage")]
+pub use crate::storage::*; // Import the storage module
+#[cfg(feature = "xlsx")]
+pub mod xlsx;
+#[cfg(feature = "xlsx")]
+pub use crate::xlsx::*; // Import the Excel import module
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "server")]
+pub use crate::server::*; // Import the REST API server module
+#[cfg(feature = "scripting")]
+pub mod scripting;
+#[cfg(feature = "scripting")]
+pub use crate::scripting::*; // Import the scripting module
 
 // Constants
 //----------------------------------------------------
 /// If you have a number in meters per second and you need to know how many knots it is then multiply this constant with the value in meters per second.
 const KNOTS_TO_METERS_PER_SECOND: f64 = 1.94384;
+/// Copernicus marine dataset with the global ocean waves analysis and forecast, used to get the significant wave height
+#[cfg(feature = "copernicus")]
+const COPERNICUS_WAVE_DATASET_ID: &str = "cmems_mod_glo_wav_anfc_0.083deg_PT3H-i";
+/// Name of the significant wave height variable in COPERNICUS_WAVE_DATASET_ID
+#[cfg(feature = "copernicus")]
+const COPERNICUS_WAVE_HEIGHT_VARIABLE: &str = "VHM0";
+/// Copernicus marine dataset with the global ocean physics analysis and forecast daily means, used to get the sea ice concentration
+#[cfg(feature = "copernicus")]
+const COPERNICUS_SEA_ICE_DATASET_ID: &str = "cmems_mod_glo_phy_anfc_0.083deg_P1D-m";
+/// Name of the sea ice area fraction variable in COPERNICUS_SEA_ICE_DATASET_ID
+#[cfg(feature = "copernicus")]
+const COPERNICUS_SEA_ICE_VARIABLE: &str = "siconc";
 
 
 // Structs and enums
 //----------------------------------------------------
 /// A physics vector struct that holds vector data... for physics :)
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PhysVec {
     /// Magnitude, make sure that the units are correct
     pub magnitude: f64,
@@ -110,13 +188,467 @@ impl std::ops::Sub for PhysVec {
     }
 }
 
+/// Struct to hold the route efficiency of a voyage.
+/// Route efficiency is the great-circle distance divided by the distance actually sailed.
+/// 1.0 means the vessel sailed the shortest possible path, lower values mean more distance was lost to tacking and weather detours.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteEfficiency {
+    /// Route efficiency of the whole voyage, from the first to the last ship log entry. None if no distance was sailed
+    pub voyage: Option<f64>,
+    /// Route efficiency of each leg in the route plan. None if the leg was not completed
+    pub legs: Vec<Option<f64>>,
+}
+
+/// Struct to hold how well a simulated voyage matches a recorded voyage, e.g. from AIS data, see validate_ship_log()
+/// Errors are simulated minus recorded, so positive errors mean the simulated vessel was slower, sailed further etc.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationMetrics {
+    /// \[m\]. Root mean square of the distance from each simulated position to the recorded track
+    pub cross_track_rmse: f64,
+    /// \[m\]. Largest distance from a simulated position to the recorded track
+    pub cross_track_max: f64,
+    /// \[m\]. Root mean square of the distance between the simulated and recorded positions at the same time since departure. None if the voyages do not overlap in time
+    pub position_rmse: Option<f64>,
+    /// Error in the time from departure to arrival, simulated duration minus recorded duration
+    pub arrival_time_error: time::Duration,
+    /// \[m\]. Error in the distance sailed
+    pub distance_error: f64,
+    /// \[m/s\]. Error in the average speed over ground. None if either voyage has no duration
+    pub speed_bias: Option<f64>,
+}
+
+/// Struct returned by evaluate_cargo_shipping_logs() with the statistics of the trips in a shipping log file and the values of each trip
+/// Statistics that could not be computed, e.g. the cargo when no trip had cargo on board, are None
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShippingLogStats {
+    /// \[m/s\]. Mean speed over ground between log entries
+    pub speed_mean: Option<f64>,
+    /// \[m/s\]
+    pub speed_std: Option<f64>,
+    /// \[tons\]. Mean cargo per trip
+    pub cargo_mean: Option<f64>,
+    /// \[tons\]
+    pub cargo_std: Option<f64>,
+    pub travel_time_min: Option<time::Duration>,
+    pub travel_time_max: Option<time::Duration>,
+    pub travel_time_mean: Option<time::Duration>,
+    pub travel_time_std: Option<time::Duration>,
+    /// \[m\]. Mean distance sailed per trip
+    pub dist_mean: Option<f64>,
+    /// \[m\]
+    pub dist_std: Option<f64>,
+    /// See RouteEfficiency
+    pub route_efficiency_mean: Option<f64>,
+    pub route_efficiency_std: Option<f64>,
+    /// Number of trips started in the log file
+    pub num_trips: u64,
+    /// \[m/s\]. Median, percentiles, min and max of the speeds, see DistributionStats. Mean and std alone hide skew, e.g. in voyage times
+    pub speed_distribution: Option<DistributionStats>,
+    /// \[s\]. Median, percentiles, min and max of the travel times
+    pub travel_time_distribution: Option<DistributionStats>,
+    /// \[m\]. Median, percentiles, min and max of the distances sailed
+    pub dist_distribution: Option<DistributionStats>,
+    /// \[m/s\]. Mean wind speed encountered on all trips, None if no wind was logged
+    pub wind_speed_mean: Option<f64>,
+    /// \[m/s\]. Maximum wind speed encountered on any trip
+    pub wind_speed_max: Option<f64>,
+    /// \[m\]. Maximum significant wave height encountered on any trip, None if no wave height was logged
+    pub wave_height_max: Option<f64>,
+    /// \[%\]. Mean of the percentages of time each trip sailed upwind, see WeatherSummary
+    pub upwind_percent_mean: Option<f64>,
+    /// \[%\]. Mean of the percentages of time each trip sailed downwind
+    pub downwind_percent_mean: Option<f64>,
+    /// \[%\]. Mean of the percentages of time each trip was tacking
+    pub tacking_percent_mean: Option<f64>,
+    /// \[m/s\]. Speed over ground between each pair of log entries
+    pub speeds: Vec<f64>,
+    /// \[tons\]. Cargo of each finished trip that had cargo on board
+    pub cargos: Vec<f64>,
+    /// Travel time of each finished trip
+    pub travel_times: Vec<time::Duration>,
+    /// \[m\]. Distance sailed on each finished trip
+    pub distances: Vec<f64>,
+    /// Route efficiency of each finished trip that sailed some distance
+    pub route_efficiencies: Vec<f64>,
+    /// Weather encountered on each trip with weather data in the log
+    pub weather_summaries: Vec<WeatherSummary>,
+    /// Entries that were flagged or dropped by the cleaning pass, None if the log was not cleaned, see evaluate_cargo_shipping_logs_cleaned()
+    pub cleaning_report: Option<CleaningReport>,
+}
+
+/// Enum of the rules for where trips in a cargo shipping log start and end, see extract_trips_with()
+#[derive(Debug, Clone, PartialEq)]
+pub enum TripSegmentation {
+    /// A trip starts where the current coordinates are the initial coordinates and ends within destination_minimum_proximity \[m\] of the final coordinates. Used by evaluate_cargo_shipping_logs()
+    /// Breaks on noisy logs where the vessel is never logged exactly at the initial coordinates
+    Coordinates { destination_minimum_proximity: f64 },
+    /// A new trip starts when the cargo on board changes, e.g. for logs where the vessel loads and unloads at every port
+    CargoChange,
+    /// A new trip starts when there is more than max_gap between two log entries, e.g. when the AIS transponder is turned off in port
+    TimeGap { max_gap: time::Duration },
+    /// A trip starts when the vessel leaves a port area and ends when it enters a port area
+    PortAreas { ports: Vec<PortArea> },
+}
+
+/// Struct for a geofenced port area, a circle around the port, see TripSegmentation::PortAreas
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortArea {
+    /// Name of the port
+    pub name: String,
+    /// Center of the port area
+    pub location: geo::Point,
+    /// \[m\]. Radius of the port area
+    pub radius: f64,
+}
+
+impl PortArea {
+    /// Creates a new port area
+    pub fn new(name: &str, location: geo::Point, radius: f64) -> PortArea {
+        PortArea {
+            name: name.to_string(),
+            location,
+            radius,
+        }
+    }
+
+    /// Returns true if the point is within the port area
+    pub fn contains(&self, point: geo::Point) -> bool {
+        return Earth.distance(self.location, point) <= self.radius;
+    }
+}
+
+/// Enum of what the cleaning pass does with physically impossible log entries, see LogCleaning
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CleaningAction {
+    /// Keep the entries but count them in the CleaningReport
+    Flag,
+    /// Leave the entries out of the trips
+    Drop,
+}
+
+/// Settings for the cleaning pass of cargo shipping logs, see evaluate_cargo_shipping_logs_cleaned()
+/// An entry is excluded if it has the same or an earlier timestamp than the entry before it (zero duration leg), is farther than max_jump_distance from it or was reached faster than max_speed
+/// Note: When dropping, the entries are compared with the last kept entry, so if the first entry of a log is wrong the following entries may be dropped too
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LogCleaning {
+    /// \[m/s\]. Maximum speed over ground between two entries
+    pub max_speed: f64,
+    /// \[m\]. Maximum distance between two entries no matter how much time is between them, None means no limit
+    pub max_jump_distance: Option<f64>,
+    /// Flag or drop the excluded entries
+    pub action: CleaningAction,
+}
+
+impl Default for LogCleaning {
+    /// Drops entries reached faster than 25 m/s (about 50 knots) and zero duration legs, no limit on jumps
+    fn default() -> LogCleaning {
+        return LogCleaning {
+            max_speed: 25.0,
+            max_jump_distance: None,
+            action: CleaningAction::Drop,
+        };
+    }
+}
+
+/// Report of the cleaning pass of a cargo shipping log, see LogCleaning
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CleaningReport {
+    /// Number of entries in the log file
+    pub n_entries: usize,
+    /// Number of entries that were flagged or dropped
+    pub n_excluded: usize,
+    /// Entries with the same or an earlier timestamp than the entry before
+    pub n_zero_duration: usize,
+    /// Entries farther than max_jump_distance from the entry before
+    pub n_jumps: usize,
+    /// Entries reached faster than max_speed
+    pub n_too_fast: usize,
+    /// Line numbers of the excluded entries in the file, the header is line 1
+    pub excluded_lines: Vec<u64>,
+}
+
+/// Struct for one trip in a cargo shipping log, see extract_trips()
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trip {
+    /// Time of the first log entry of the trip
+    pub start_time: UtcDateTime,
+    /// Time of the last log entry of the trip
+    pub end_time: UtcDateTime,
+    pub coordinates_initial: geo::Point,
+    pub coordinates_final: geo::Point,
+    /// \[m\]. Distance sailed, the sum of the distances between the log entries
+    pub distance: f64,
+    pub duration: time::Duration,
+    /// \[m/s\]. Distance divided by duration, None if the trip has no duration
+    pub speed_mean: Option<f64>,
+    /// \[m/s\]. Speed over ground between each pair of log entries
+    pub speeds: Vec<f64>,
+    /// \[tons\]. Last cargo on board logged during the trip, None if no cargo was logged
+    pub cargo: Option<f64>,
+    /// True if the trip reached its final coordinates
+    pub completed: bool,
+    /// Weather encountered during the trip, None if the log has no wind or wave height data, see WeatherSummary
+    pub weather: Option<WeatherSummary>,
+}
+
+impl Trip {
+    /// Returns the route efficiency of the trip, the great-circle distance divided by the distance sailed, see RouteEfficiency. None if no distance was sailed
+    pub fn route_efficiency(&self) -> Option<f64> {
+        if self.distance <= 0.0 {
+            return None;
+        }
+        return Some(Earth.distance(self.coordinates_initial, self.coordinates_final) / self.distance);
+    }
+}
+
+impl ShippingLogStats {
+    /// Computes the statistics of trips, e.g. from extract_trips() after removing outliers
+    /// Speeds are taken from all the trips, travel times, distances, cargo and route efficiencies only from completed trips
+    pub fn from_trips(trips: &[Trip]) -> ShippingLogStats {
+        let speed_vec: Vec<f64> = trips.iter().flat_map(|trip| trip.speeds.iter().copied()).collect();
+        let completed: Vec<&Trip> = trips.iter().filter(|trip| trip.completed).collect();
+        let cargo_vec: Vec<f64> = completed.iter().filter_map(|trip| trip.cargo).collect();
+        let dist_vec: Vec<f64> = completed.iter().map(|trip| trip.distance).collect();
+        let route_efficiency_vec: Vec<f64> = completed.iter().filter_map(|trip| trip.route_efficiency()).collect();
+        let travel_time_vec: Vec<time::Duration> = completed.iter().map(|trip| trip.duration).collect();
+        let weather_summaries: Vec<WeatherSummary> = trips.iter().filter_map(|trip| trip.weather.clone()).collect();
+        let num_trips = trips.len() as u64;
+
+        // Calculate the mean and standard deviation of the vectors
+        let speed_mean: Option<f64>;
+        let speed_std: Option<f64>;
+        let cargo_mean: Option<f64>;
+        let cargo_std: Option<f64>;
+        let travel_time_min: Option<time::Duration>;
+        let travel_time_max: Option<time::Duration>;
+        let travel_time_mean: Option<time::Duration>;
+        let travel_time_std: Option<time::Duration>;
+        let dist_mean: Option<f64>;
+        let dist_std: Option<f64>;
+        let route_efficiency_mean: Option<f64>;
+        let route_efficiency_std: Option<f64>;
+
+        match get_vec_f64_mean_and_std(&speed_vec, true) {
+            Ok((mean, std)) => {
+                speed_mean = Some(mean);
+                speed_std = Some(std);
+            },
+            Err(_) => {
+                // eprintln!("Error calculating speed mean and std. Set to zero. Error message: {}", e);
+                speed_mean = None;
+                speed_std = None;
+            }
+        }
+        match get_vec_f64_mean_and_std(&cargo_vec, true) {
+            Ok((mean, std)) => {
+                cargo_mean = Some(mean);
+                cargo_std = Some(std);
+            },
+            Err(_) => {
+                // eprintln!("Error calculating cargo mean and std. Set to None. Error message: {}", e);
+                cargo_mean = None;
+                cargo_std = None;
+            }
+        }
+
+        match get_mean_and_std(&travel_time_vec) {
+            Ok((mean, std)) => {
+                travel_time_mean = Some(mean);
+                travel_time_std = Some(std);
+            },
+            Err(e) => {
+                tracing::warn!("Error calculating travel time mean and std. Set to zero. Error message: {}", e);
+                travel_time_mean = None;
+                travel_time_std = None;
+            }
+        }
+
+        // Find min and max travel times
+        travel_time_min = travel_time_vec.iter().min().cloned();
+        travel_time_max = travel_time_vec.iter().max().cloned();
+        match get_vec_f64_mean_and_std(&dist_vec, true) {
+            Ok((mean, std)) => {
+                dist_mean = Some(mean);
+                dist_std = Some(std);
+            },
+            Err(e) => {
+                tracing::warn!("Error calculating distance mean and std. Set to zero. Error message: {}", e);
+                dist_mean = None;
+                dist_std = None;
+            }
+        }
+        match get_vec_f64_mean_and_std(&route_efficiency_vec, true) {
+            Ok((mean, std)) => {
+                route_efficiency_mean = Some(mean);
+                route_efficiency_std = Some(std);
+            },
+            Err(_) => {
+                route_efficiency_mean = None;
+                route_efficiency_std = None;
+            }
+        }
+
+        // Weather encountered, the mean wind speed is weighted by the number of wind speeds logged on each trip
+        let wind_speeds: Vec<&DistributionStats> = weather_summaries.iter().filter_map(|weather| weather.wind_speed.as_ref()).collect();
+        let num_wind_speeds: usize = wind_speeds.iter().map(|wind_speed| wind_speed.n).sum();
+        let wind_speed_mean = if num_wind_speeds > 0 { Some(wind_speeds.iter().map(|wind_speed| wind_speed.mean*(wind_speed.n as f64)).sum::<f64>() / (num_wind_speeds as f64)) } else { None };
+        let percent_mean = |percent: fn(&WeatherSummary) -> Option<f64>| -> Option<f64> {
+            let percents: Vec<f64> = weather_summaries.iter().filter_map(percent).collect();
+            return get_mean_and_std(&percents).ok().map(|(mean, _)| mean);
+        };
+        let upwind_percent_mean = percent_mean(|weather| weather.upwind_percent);
+        let downwind_percent_mean = percent_mean(|weather| weather.downwind_percent);
+        let tacking_percent_mean = percent_mean(|weather| weather.tacking_percent);
+
+        return ShippingLogStats {
+            speed_mean,
+            speed_std,
+            cargo_mean,
+            cargo_std,
+            travel_time_min,
+            travel_time_max,
+            travel_time_mean,
+            travel_time_std,
+            dist_mean,
+            dist_std,
+            route_efficiency_mean,
+            route_efficiency_std,
+            num_trips,
+            speed_distribution: DistributionStats::from_values(&speed_vec),
+            travel_time_distribution: get_distribution_stats(&travel_time_vec),
+            dist_distribution: DistributionStats::from_values(&dist_vec),
+            wind_speed_mean,
+            wind_speed_max: wind_speeds.iter().map(|wind_speed| wind_speed.max).reduce(f64::max),
+            wave_height_max: weather_summaries.iter().filter_map(|weather| weather.max_wave_height).reduce(f64::max),
+            upwind_percent_mean,
+            downwind_percent_mean,
+            tacking_percent_mean,
+            speeds: speed_vec,
+            cargos: cargo_vec,
+            travel_times: travel_time_vec,
+            distances: dist_vec,
+            route_efficiencies: route_efficiency_vec,
+            weather_summaries,
+            cleaning_report: None,
+        };
+    }
+}
+
+/// std::Display for ShippingLogStats, one line per statistic with travel times in days and distances in km. Values that could not be computed are shown as "-"
+impl fmt::Display for ShippingLogStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = |v: Option<f64>, scale: f64| -> String {
+            return match v {
+                Some(v) => format!("{:.2}", v*scale),
+                None => "-".to_string(),
+            };
+        };
+        let days = |d: Option<time::Duration>| value(d.map(|d| d.as_seconds_f64()), 1.0/86400.0);
+        writeln!(f, "Trips: {}", self.num_trips)?;
+        writeln!(f, "Speed [m/s]: mean {}, std {}", value(self.speed_mean, 1.0), value(self.speed_std, 1.0))?;
+        writeln!(f, "Cargo [tons]: mean {}, std {}", value(self.cargo_mean, 1.0), value(self.cargo_std, 1.0))?;
+        writeln!(f, "Travel time [days]: mean {}, std {}, min {}, max {}", days(self.travel_time_mean), days(self.travel_time_std), days(self.travel_time_min), days(self.travel_time_max))?;
+        writeln!(f, "Travel time [days]: p5 {}, median {}, p95 {}", value(self.travel_time_distribution.as_ref().map(|d| d.p5), 1.0/86400.0), value(self.travel_time_distribution.as_ref().map(|d| d.p50), 1.0/86400.0), value(self.travel_time_distribution.as_ref().map(|d| d.p95), 1.0/86400.0))?;
+        writeln!(f, "Distance [km]: mean {}, std {}", value(self.dist_mean, 0.001), value(self.dist_std, 0.001))?;
+        writeln!(f, "Route efficiency: mean {}, std {}", value(self.route_efficiency_mean, 1.0), value(self.route_efficiency_std, 1.0))?;
+        writeln!(f, "Wind [m/s]: mean {}, max {}. Max wave height [m]: {}", value(self.wind_speed_mean, 1.0), value(self.wind_speed_max, 1.0), value(self.wave_height_max, 1.0))?;
+        write!(f, "Time [%]: upwind {}, downwind {}, tacking {}", value(self.upwind_percent_mean, 1.0), value(self.downwind_percent_mean, 1.0), value(self.tacking_percent_mean, 1.0))
+    }
+}
+
+/// Struct to hold the statistics of a set of shipping logs, one row of the csv files written by save_shipping_logs_evaluation_to_csv() and append_shipping_statistics_to_csv()
+/// Values that could not be computed are None
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ShippingStatistics {
+    /// \[m/s\]
+    pub speed_mean: Option<f64>,
+    /// \[m/s\]
+    pub speed_std: Option<f64>,
+    /// \[tons\]
+    pub cargo_mean: Option<f64>,
+    /// \[tons\]
+    pub cargo_std: Option<f64>,
+    pub travel_time_mean: Option<time::Duration>,
+    pub travel_time_std: Option<time::Duration>,
+    /// \[m\]
+    pub dist_mean: Option<f64>,
+    /// \[m\]
+    pub dist_std: Option<f64>,
+    /// See RouteEfficiency
+    pub route_efficiency_mean: Option<f64>,
+    pub route_efficiency_std: Option<f64>,
+    pub num_trips: u64,
+    /// \[m/s\]. Median, percentiles, min and max of the speeds, see DistributionStats
+    pub speed_distribution: Option<DistributionStats>,
+    /// \[s\]. Median, percentiles, min and max of the travel times
+    pub travel_time_distribution: Option<DistributionStats>,
+    /// \[m\]. Median, percentiles, min and max of the distances sailed
+    pub dist_distribution: Option<DistributionStats>,
+    /// \[m/s\]. Mean wind speed encountered, see ShippingLogStats
+    pub wind_speed_mean: Option<f64>,
+    /// \[m/s\]
+    pub wind_speed_max: Option<f64>,
+    /// \[m\]. Maximum significant wave height encountered
+    pub wave_height_max: Option<f64>,
+    /// \[%\]. Mean percentage of time sailed upwind per trip
+    pub upwind_percent_mean: Option<f64>,
+    /// \[%\]
+    pub downwind_percent_mean: Option<f64>,
+    /// \[%\]. Mean percentage of time tacking per trip
+    pub tacking_percent_mean: Option<f64>,
+    /// Key-value metadata about the scenario, e.g. ("vessel_class", "schooner") or ("weather_year", "2024"). Written as extra columns after the statistics, one column per key
+    pub metadata: Vec<(String, String)>,
+}
+
+impl ShippingStatistics {
+    /// Evaluates the cargo shipping logs in a CSV file, see evaluate_cargo_shipping_logs()
+    /// # Example:
+    /// `append_shipping_statistics_to_csv("results.csv", "Sailing cargo ship", &ShippingStatistics::from_cargo_shipping_logs("ship_logs.csv", 1000.0)?)?;`
+    pub fn from_cargo_shipping_logs(file_path: &str, destination_minimum_proximity: f64) -> Result<ShippingStatistics, MarineSimError> {
+        let stats = evaluate_cargo_shipping_logs(file_path, destination_minimum_proximity)?;
+        return Ok(ShippingStatistics {
+            speed_mean: stats.speed_mean,
+            speed_std: stats.speed_std,
+            cargo_mean: stats.cargo_mean,
+            cargo_std: stats.cargo_std,
+            travel_time_mean: stats.travel_time_mean,
+            travel_time_std: stats.travel_time_std,
+            dist_mean: stats.dist_mean,
+            dist_std: stats.dist_std,
+            route_efficiency_mean: stats.route_efficiency_mean,
+            route_efficiency_std: stats.route_efficiency_std,
+            num_trips: stats.num_trips,
+            speed_distribution: stats.speed_distribution,
+            travel_time_distribution: stats.travel_time_distribution,
+            dist_distribution: stats.dist_distribution,
+            wind_speed_mean: stats.wind_speed_mean,
+            wind_speed_max: stats.wind_speed_max,
+            wave_height_max: stats.wave_height_max,
+            upwind_percent_mean: stats.upwind_percent_mean,
+            downwind_percent_mean: stats.downwind_percent_mean,
+            tacking_percent_mean: stats.tacking_percent_mean,
+            metadata: Vec::new(),
+        });
+    }
+
+    /// Sets the metadata value for a key, replaces the value if the key is already set
+    /// # Example:
+    /// `statistics.set_metadata("seed", &seed.to_string());`
+    pub fn set_metadata(&mut self, key: &str, value: &str) {
+        match self.metadata.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_string(),
+            None => self.metadata.push((key.to_string(), value.to_string())),
+        }
+    }
+}
+
 // Functions
 //----------------------------------------------------
 
 /// This function evaluates the cargo shipping logs from a CSV file and calculates the mean and standard deviation of the speed and cargo delivery values. The CSV file is expected to have the following columns:<br>
 /// timestamp;coordinates_initial;coordinates_current;coordinates_final;cargo_on_board (weight in tons)<br><br>
 /// The delimiter is a semicolon.
-/// file_path: Path to the CSV file
+/// file_path: Path to the CSV file. With the "xlsx" feature it can also be an Excel workbook with the same columns, see xlsx_to_ship_log()
 /// distance: The total sailing distance. Note if distance = 0 the function evaluates the sailing distance by drawing a straight line for each leg of the trip 
 /// Notes:
 /// Timestamps are expected to be in the ISO format of YYYY-MM-DD hh:mm.
@@ -127,2668 +659,7507 @@ impl std::ops::Sub for PhysVec {
 /// let filename: &str = "../data/mydata.csv";
 /// // Distance in meters
 /// let distance: f64 = 50;
-/// let (speed_mean, speed_std, cargo_mean, cargo_std) = evaluate_cargo_shipping_logs(filename, distance);
+/// let stats = evaluate_cargo_shipping_logs(filename, distance)?;
+/// println!("{}", stats);
 /// ```
+/// Returns the statistics along with the values of each trip, see ShippingLogStats
+/// The route efficiency of each trip is the great-circle distance between the initial and final coordinates divided by the distance sailed, see RouteEfficiency.
 /// TODO: Add error message for when the trip does not reach the destination
-pub fn evaluate_cargo_shipping_logs(file_path: &str, destination_minimum_proximity: f64) ->
-    (Option<f64>, Option<f64>,
-        Option<f64>, Option<f64>,
-        Option<time::Duration>, Option<time::Duration>,
-        Option<time::Duration>, Option<time::Duration>,
-        Option<f64>, Option<f64>, u64) {
+pub fn evaluate_cargo_shipping_logs(file_path: &str, destination_minimum_proximity: f64) -> Result<ShippingLogStats, MarineSimError> {
+    let trips = extract_trips(file_path, destination_minimum_proximity)?;
+    return Ok(ShippingLogStats::from_trips(&trips));
+}
 
-    // Read the CSV file
-    let mut csv_reader = csv::ReaderBuilder::new()
-        .delimiter(b';')
-        .has_headers(true)
-        .flexible(true)
-        .from_path(file_path)
-        .expect(format!("Failed to open file: {}", file_path).as_str());
-
-    // Initialize variables to store the sum and count of speed and cargo values
-    let mut speed_vec: Vec<f64> = Vec::new();
-    let mut cargo_vec: Vec<f64> = Vec::new();
-    let mut dist_vec: Vec<f64> = Vec::new();
-    let mut travel_time_vec: Vec<time::Duration> = Vec::new();
-
-    // Init empty csv column variable
-    let mut timestamp: time::UtcDateTime;
-    let mut coordinates_initial: geo::Point;
-    let mut coordinates_current: geo::Point;
-    let mut coordinates_final: geo::Point;
-    let mut cargo_on_board_option: Option<f64>;         // weight in tons
-
-    // Init empty working variables
-    // Distances are in meters
-    let mut dist: f64;
-    let mut trip_dist: f64 = 0.0;
-    let mut last_timestamp = time::UtcDateTime::now();
-    let mut start_time = time::UtcDateTime::now();
-    let mut cargo_on_trip: Option<f64> = None;
-    let mut num_trips: u64 = 0;
-    let mut coordinates_last: geo::Point = geo::Point::new(0.0, 0.0);
-
-    // Iterate through each line of the CSV file to calculate the mean and standard deviation of speed and cargo values, using each leg (each leg is 2 points) of the trip/s
-    for result in csv_reader.records() {
-        match result {
-            Ok(log_entry) => {
-                // Get all values in row as usable data
-                timestamp = string_to_utc_date_time(log_entry.get(0).expect("No timestamp found").to_string());
-                coordinates_initial = match string_to_point(log_entry.get(1).expect("No initial coordinate found").to_string()) {
-                    Ok(c) => c,
-                    Err(e) => panic!("Error parsing initial coordinates: {}", e),
-                };
-                coordinates_current = match string_to_point(log_entry.get(2).expect("No current coordinate found").to_string()) {
-                    Ok(c) => c,
-                    Err(e) => panic!("Error parsing current coordinates: {}", e),
-                };
-                coordinates_final = match string_to_point(log_entry.get(3).expect("No final coordinate found").to_string()) {
-                    Ok(c) => c,
-                    Err(e) => panic!("Error parsing final coordinates: {}", e),
-                };
-                cargo_on_board_option = match log_entry.get(4).unwrap().to_string().parse() {
-                    Ok(cargo) => Some(cargo),
-                    Err(_) => None,
-                };
+/// Same as evaluate_cargo_shipping_logs() but with a selectable rule for where trips start and end, see TripSegmentation
+/// # Example:
+/// `let stats = evaluate_cargo_shipping_logs_with("ship_logs.csv", &TripSegmentation::TimeGap { max_gap: time::Duration::hours(12) })?;`
+pub fn evaluate_cargo_shipping_logs_with(file_path: &str, segmentation: &TripSegmentation) -> Result<ShippingLogStats, MarineSimError> {
+    let trips = extract_trips_with(file_path, segmentation)?;
+    return Ok(ShippingLogStats::from_trips(&trips));
+}
 
-                // If initial coordinate, the trip just started
-                if coordinates_current == coordinates_initial {
-                    // Increment the number of trips
-                    num_trips += 1;
-                    // Log start time
-                    last_timestamp = timestamp;
-                    start_time = timestamp;
-                    // Set the last coordinates to the initial coordinates
-                    coordinates_last = coordinates_initial;
-                }
-                // Else then it's a working point or the endpoint and we can calculate the distance
-                else {
-                    // Add the distance traveled from last coordinates
-                    dist = Haversine.distance(coordinates_last, coordinates_current); // [m]
-                    // Update trip distance
-                    trip_dist += dist;
-                    // Calculate the speed in m/s
-                    let speed = dist / (timestamp - last_timestamp).as_seconds_f64();
-
-                    // Update last_timestamp
-                    last_timestamp = timestamp;
-
-                    // Add speed value to speed vector
-                    speed_vec.push(speed);
-                }
+/// Reads the trips in a cargo shipping log CSV file, see evaluate_cargo_shipping_logs() for the format of the file
+/// A trip starts at an entry where the current coordinates are the initial coordinates and ends at the first entry within destination_minimum_proximity \[m\] of the final coordinates, see TripSegmentation::Coordinates
+/// Entries before the first trip starts and between trips are skipped. A trip that does not reach its final coordinates is returned with completed set to false
+/// Useful to inspect individual voyages and filter outliers before computing statistics with ShippingLogStats::from_trips()
+/// # Example:
+/// `let trips: Vec<Trip> = extract_trips("ship_logs.csv", 1000.0)?.into_iter().filter(|trip| trip.duration < time::Duration::days(60)).collect();`
+/// `println!("{}", ShippingLogStats::from_trips(&trips));`
+pub fn extract_trips(file_path: &str, destination_minimum_proximity: f64) -> Result<Vec<Trip>, MarineSimError> {
+    return extract_trips_with(file_path, &TripSegmentation::Coordinates { destination_minimum_proximity });
+}
 
-                // If there is cargo on board, set cargo_on_trip to the cargo on board. If the cargo changes then that should be the end of the trip
-                if cargo_on_board_option.is_some() {
-                    cargo_on_trip = cargo_on_board_option;                    
-                }
+/// Reads the trips in a cargo shipping log CSV file using the segmentation rule, see TripSegmentation and extract_trips()
+/// With all rules but TripSegmentation::Coordinates the initial and final coordinates of a trip are where the vessel was at the first and last entry of the trip
+pub fn extract_trips_with(file_path: &str, segmentation: &TripSegmentation) -> Result<Vec<Trip>, MarineSimError> {
+    let rows = read_trip_log_rows(file_path)?;
+    return Ok(get_trips_from_rows(&rows, segmentation));
+}
 
-                // If current coord is not inital or final this is a working point, set current coordinates as last coordinates
-                if coordinates_current != coordinates_initial && coordinates_current != coordinates_final {
-                    // Update last coordinates
-                    coordinates_last = coordinates_current;
-                }
+/// Same as evaluate_cargo_shipping_logs_with() but with a cleaning pass that flags or drops physically impossible log entries first, see LogCleaning
+/// The report of the cleaning is in ShippingLogStats.cleaning_report
+/// # Example:
+/// `let stats = evaluate_cargo_shipping_logs_cleaned("ship_logs.csv", &TripSegmentation::Coordinates { destination_minimum_proximity: 1000.0 }, &LogCleaning::default())?;`
+/// `println!("Excluded {} of {} log entries", stats.cleaning_report.as_ref().unwrap().n_excluded, stats.cleaning_report.as_ref().unwrap().n_entries);`
+pub fn evaluate_cargo_shipping_logs_cleaned(file_path: &str, segmentation: &TripSegmentation, cleaning: &LogCleaning) -> Result<ShippingLogStats, MarineSimError> {
+    let (trips, report) = extract_trips_cleaned(file_path, segmentation, cleaning)?;
+    let mut stats = ShippingLogStats::from_trips(&trips);
+    stats.cleaning_report = Some(report);
+    return Ok(stats);
+}
+
+/// Same as extract_trips_with() but with a cleaning pass that flags or drops physically impossible log entries first, see LogCleaning
+/// Returns the trips and a report of the entries that were flagged or dropped
+pub fn extract_trips_cleaned(file_path: &str, segmentation: &TripSegmentation, cleaning: &LogCleaning) -> Result<(Vec<Trip>, CleaningReport), MarineSimError> {
+    let rows = read_trip_log_rows(file_path)?;
+    let (rows, report) = clean_trip_log_rows(rows, cleaning);
+    return Ok((get_trips_from_rows(&rows, segmentation), report));
+}
 
+/// Checks each log entry against the entry before it and flags or drops entries that are physically impossible, see LogCleaning
+/// When dropping, each entry is compared with the last entry that was kept
+fn clean_trip_log_rows(rows: Vec<TripLogRow>, cleaning: &LogCleaning) -> (Vec<TripLogRow>, CleaningReport) {
+    let mut report = CleaningReport {
+        n_entries: rows.len(),
+        ..CleaningReport::default()
+    };
+    let mut kept: Vec<TripLogRow> = Vec::with_capacity(rows.len());
+    // The entry to compare with, the last kept entry when dropping and the entry before when flagging
+    let mut previous: Option<(UtcDateTime, geo::Point)> = None;
+    for row in rows {
+        let mut excluded = false;
+        if let Some((previous_time, previous_location)) = previous {
+            let dt = (row.timestamp - previous_time).as_seconds_f64();
+            let dist = Earth.distance(previous_location, row.coordinates_current);
+            if dt <= 0.0 {
+                report.n_zero_duration += 1;
+                excluded = true;
+            }
+            else if cleaning.max_jump_distance.is_some_and(|max_jump| dist > max_jump) {
+                report.n_jumps += 1;
+                excluded = true;
+            }
+            else if dist / dt > cleaning.max_speed {
+                report.n_too_fast += 1;
+                excluded = true;
+            }
+        }
+        if excluded {
+            report.n_excluded += 1;
+            report.excluded_lines.push(row.line);
+        }
+        if !excluded || cleaning.action == CleaningAction::Flag {
+            previous = Some((row.timestamp, row.coordinates_current));
+            kept.push(row);
+        }
+    }
+    return (kept, report);
+}
+
+/// Splits the log entries into trips using the segmentation rule, see TripSegmentation
+fn get_trips_from_rows(rows: &[TripLogRow], segmentation: &TripSegmentation) -> Vec<Trip> {
+    let mut trips: Vec<Trip> = Vec::new();
+
+    match segmentation {
+        TripSegmentation::Coordinates { destination_minimum_proximity } => {
+            // Index of the first entry of the trip that is being read, None between trips
+            let mut start: Option<usize> = None;
+            for (i, row) in rows.iter().enumerate() {
+                // If initial coordinate, a new trip just started
+                if row.coordinates_current == row.coordinates_initial {
+                    if let Some(s) = start {
+                        trips.push(get_trip_from_rows(&rows[s..i], false, true));
+                    }
+                    start = Some(i);
+                }
                 // If final coordinate, the trip just ended
-                if Haversine.distance(coordinates_current, coordinates_final) <= destination_minimum_proximity {
-                    // Add travel time to travel time vector
-                    travel_time_vec.push(timestamp - start_time);
-                    // Add trip distance to distance vector
-                    dist_vec.push(trip_dist);
-                    // If there is cargo, Add cargo to cargo vector
-                    if cargo_on_trip.is_some() {
-                        cargo_vec.push(cargo_on_trip.unwrap());
+                if let Some(s) = start {
+                    if Earth.distance(row.coordinates_current, row.coordinates_final) <= *destination_minimum_proximity {
+                        trips.push(get_trip_from_rows(&rows[s..=i], true, true));
+                        start = None;
                     }
-                     
-                    // Reset trip distance distance
-                    trip_dist = 0.0;
-                    // Reset cargo
-                    cargo_on_trip = None;
                 }
             }
-            // Handle the error if the log_entry cannot be read
-            Err(ref err) => {
-                eprintln!("Error reading log_entry: {:?}\nError: {}", result, err);
+            if let Some(s) = start {
+                trips.push(get_trip_from_rows(&rows[s..], false, true));
+            }
+        }
+        TripSegmentation::CargoChange => {
+            let mut start: usize = 0;
+            let mut cargo: Option<f64> = None;
+            for (i, row) in rows.iter().enumerate() {
+                if let Some(row_cargo) = row.cargo {
+                    if cargo.is_some_and(|c| c != row_cargo) {
+                        if i - start > 1 {
+                            trips.push(get_trip_from_rows(&rows[start..i], true, false));
+                        }
+                        start = i;
+                    }
+                    cargo = Some(row_cargo);
+                }
+            }
+            if rows.len() - start > 1 {
+                trips.push(get_trip_from_rows(&rows[start..], false, false));
+            }
+        }
+        TripSegmentation::TimeGap { max_gap } => {
+            let mut start: usize = 0;
+            for i in 1..rows.len() {
+                if rows[i].timestamp - rows[i-1].timestamp > *max_gap {
+                    if i - start > 1 {
+                        trips.push(get_trip_from_rows(&rows[start..i], true, false));
+                    }
+                    start = i;
+                }
+            }
+            if rows.len() > start + 1 {
+                trips.push(get_trip_from_rows(&rows[start..], false, false));
+            }
+        }
+        TripSegmentation::PortAreas { ports } => {
+            let in_port = |point: geo::Point| ports.iter().any(|port| port.contains(point));
+            // The trip starts at the last entry in port before leaving and ends at the first entry in a port after that
+            let mut start: Option<usize> = None;
+            let mut last_in_port: Option<usize> = None;
+            for (i, row) in rows.iter().enumerate() {
+                if in_port(row.coordinates_current) {
+                    if let Some(s) = start {
+                        trips.push(get_trip_from_rows(&rows[s..=i], true, false));
+                        start = None;
+                    }
+                    last_in_port = Some(i);
+                }
+                else if start.is_none() {
+                    start = last_in_port;
+                }
+            }
+            if let Some(s) = start {
+                trips.push(get_trip_from_rows(&rows[s..], false, false));
             }
         }
     }
 
-    // Calculate the mean and standard deviation of the vectors
-    let speed_mean: Option<f64>;
-    let speed_std: Option<f64>;
-    let cargo_mean: Option<f64>;
-    let cargo_std: Option<f64>;
-    let travel_time_min: Option<time::Duration>;
-    let travel_time_max: Option<time::Duration>;
-    let travel_time_mean: Option<time::Duration>;
-    let travel_time_std: Option<time::Duration>;
-    let dist_mean: Option<f64>;
-    let dist_std: Option<f64>;
+    return trips;
+}
 
-    match get_vec_f64_mean_and_std(&speed_vec, true) {
-        Ok((mean, std)) => {
-            speed_mean = Some(mean);
-            speed_std = Some(std);
-        },
-        Err(_) => {
-            // eprintln!("Error calculating speed mean and std. Set to zero. Error message: {}", e);
-            speed_mean = None;
-            speed_std = None;
-        }
+/// One entry of a cargo shipping log CSV file, see read_trip_log_rows()
+struct TripLogRow {
+    timestamp: UtcDateTime,
+    coordinates_initial: geo::Point,
+    coordinates_current: geo::Point,
+    coordinates_final: geo::Point,
+    /// \[tons\]
+    cargo: Option<f64>,
+    /// \[°\]
+    course: Option<f64>,
+    /// \[°\]
+    heading: Option<f64>,
+    /// \[m/s\]
+    wind: Option<PhysVec>,
+    /// \[m\]
+    wave_height: Option<f64>,
+    /// Line number in the file, the header is line 1
+    line: u64,
+}
+
+impl TripLogRow {
+    /// Returns the row as a ship log entry, the fields that are not in the row are None
+    fn to_ship_log_entry(&self) -> ShipLogEntry {
+        return ShipLogEntry {
+            timestamp: self.timestamp,
+            coordinates_initial: self.coordinates_initial,
+            coordinates_current: self.coordinates_current,
+            coordinates_final: self.coordinates_final,
+            cargo_on_board: self.cargo.map(|cargo| uom::si::f64::Mass::new::<uom::si::mass::ton>(cargo)),
+            velocity: None,
+            course: self.course,
+            heading: self.heading,
+            track_angle: None,
+            true_bearing: None,
+            draft: None,
+            navigation_status: None,
+            depth: None,
+            wind: self.wind,
+            wave_height: self.wave_height,
+            cross_track_error: None,
+            foiling: None,
+        };
+    }
+}
+
+/// Reads all entries of a cargo shipping log CSV file, lines that can not be read are skipped with a warning
+/// With the "xlsx" feature, Excel workbooks with the same columns are read as well, see read_xlsx_records()
+fn read_trip_log_rows(file_path: &str) -> Result<Vec<TripLogRow>, MarineSimError> {
+    #[cfg(feature = "xlsx")]
+    if is_excel_file(file_path) {
+        return read_xlsx_records(file_path)?.1.iter().map(|log_entry| get_trip_log_row(log_entry, file_path)).collect();
+    }
+
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .flexible(true)
+        .from_path(file_path)?;
+
+    let mut rows: Vec<TripLogRow> = Vec::new();
+    for result in csv_reader.records() {
+        let log_entry = match result {
+            Ok(log_entry) => log_entry,
+            // Handle the error if the log_entry cannot be read
+            Err(err) => {
+                tracing::warn!("Error reading log_entry: {}", err);
+                continue;
+            }
+        };
+        rows.push(get_trip_log_row(&log_entry, file_path)?);
     }
-    match get_vec_f64_mean_and_std(&cargo_vec, true) {
-        Ok((mean, std)) => {
-            cargo_mean = Some(mean);
-            cargo_std = Some(std);
+    return Ok(rows);
+}
+
+/// Reads an entry of a cargo shipping log from a record of the file
+fn get_trip_log_row(log_entry: &csv::StringRecord, file_path: &str) -> Result<TripLogRow, MarineSimError> {
+    return Ok(TripLogRow {
+        timestamp: convert_csv_field(log_entry, 0, "timestamp", file_path, string_to_utc_date_time)?,
+        coordinates_initial: convert_csv_field(log_entry, 1, "initial coordinate", file_path, string_to_point)?,
+        coordinates_current: convert_csv_field(log_entry, 2, "current coordinate", file_path, string_to_point)?,
+        coordinates_final: convert_csv_field(log_entry, 3, "final coordinate", file_path, string_to_point)?,
+        cargo: log_entry.get(4).unwrap_or_default().trim().parse().ok(),
+        // The course, heading, wind and wave height columns of ship logs written by ship_logs_to_csv(), None if missing
+        course: log_entry.get(6).unwrap_or_default().trim().parse().ok(),
+        heading: log_entry.get(7).unwrap_or_default().trim().parse().ok(),
+        wind: match (log_entry.get(12).unwrap_or_default().trim().parse().ok(), log_entry.get(13).unwrap_or_default().trim().parse().ok()) {
+            (Some(speed), Some(angle)) => Some(PhysVec::new(speed, angle)),
+            _ => None,
         },
-        Err(_) => {
-            // eprintln!("Error calculating cargo mean and std. Set to None. Error message: {}", e);
-            cargo_mean = None;
-            cargo_std = None;
+        wave_height: log_entry.get(14).unwrap_or_default().trim().parse().ok(),
+        line: log_entry.position().map_or(0, |position| position.line()),
+    });
+}
+
+/// Makes a trip from its log entries, rows must not be empty
+/// use_logged_endpoints: If true, the initial coordinates are taken from the first entry and the final coordinates from the last entry, otherwise where the vessel was at the first and last entry
+fn get_trip_from_rows(rows: &[TripLogRow], completed: bool, use_logged_endpoints: bool) -> Trip {
+    let first = &rows[0];
+    let last = &rows[rows.len() - 1];
+
+    // Distance and speed between each pair of log entries
+    let mut distance: f64 = 0.0;
+    let mut speeds: Vec<f64> = Vec::with_capacity(rows.len() - 1);
+    for pair in rows.windows(2) {
+        let dist = Earth.distance(pair[0].coordinates_current, pair[1].coordinates_current);
+        distance += dist;
+        speeds.push(dist / (pair[1].timestamp - pair[0].timestamp).as_seconds_f64());
+    }
+    let duration = last.timestamp - first.timestamp;
+    let ship_log: Vec<ShipLogEntry> = rows.iter().map(|row| row.to_ship_log_entry()).collect();
+
+    return Trip {
+        start_time: first.timestamp,
+        end_time: last.timestamp,
+        coordinates_initial: if use_logged_endpoints { first.coordinates_initial } else { first.coordinates_current },
+        coordinates_final: if use_logged_endpoints { last.coordinates_final } else { last.coordinates_current },
+        distance,
+        duration,
+        speed_mean: if duration.is_positive() { Some(distance / duration.as_seconds_f64()) } else { None },
+        speeds,
+        // The last cargo logged, if the cargo changes then that should be the end of the trip
+        cargo: rows.iter().rev().find_map(|row| row.cargo),
+        completed,
+        weather: WeatherSummary::from_ship_log(&ship_log),
+    };
+}
+
+/// Computes the route efficiency of a voyage and each leg of the route plan from the ship log.
+/// Route efficiency is the great-circle distance divided by the distance actually sailed, see RouteEfficiency.
+/// The ship log entries are assigned to the legs of the route plan in order, a leg is considered completed when the vessel is within the minimum proximity of the end of the leg.
+/// ship_log: The ship log of a single voyage, e.g. from a simulation
+/// route_plan: The route plan the voyage followed
+/// # Example:
+/// `let efficiency = get_route_efficiency(&boat.ship_log, boat.route_plan.as_ref().unwrap());`
+pub fn get_route_efficiency(ship_log: &Vec<ShipLogEntry>, route_plan: &Vec<SailingLeg>) -> Result<RouteEfficiency, MarineSimError> {
+    // Validate input
+    if ship_log.len() < 2 {
+        return Err(MarineSimError::InvalidInput("Ship log must have at least 2 entries to compute route efficiency".to_string()));
+    }
+    if route_plan.is_empty() {
+        return Err(MarineSimError::InvalidInput("Route plan is empty, cannot compute route efficiency".to_string()));
+    }
+
+    // Init distance sailed on each leg and in total, unit [m]
+    let mut leg_dist_sailed: Vec<f64> = vec![0.0; route_plan.len()];
+    let mut total_dist_sailed: f64 = 0.0;
+    // Init the index of the current leg and the number of legs completed
+    let mut current_leg: usize = 0;
+    let mut legs_completed: usize = 0;
+
+    // Loop through the ship log and add the distance between each entry to the current leg
+    for i in 1..ship_log.len() {
+        let dist = Earth.distance(ship_log[i-1].coordinates_current, ship_log[i].coordinates_current);
+        total_dist_sailed += dist;
+        leg_dist_sailed[current_leg] += dist;
+
+        // If the vessel is within the minimum proximity of the end of the current leg, the leg is completed
+        if legs_completed == current_leg && Earth.distance(ship_log[i].coordinates_current, route_plan[current_leg].p2) <= route_plan[current_leg].min_proximity {
+            legs_completed += 1;
+            // Move to next leg if there is one
+            if current_leg < route_plan.len() - 1 {
+                current_leg += 1;
+            }
         }
     }
 
-    // Parse travel_time_vec to travel_time_vec_secs
-    let travel_time_vec_secs = travel_time_vec.iter().map(|d| d.as_seconds_f64()).collect::<Vec<f64>>();
-    match get_vec_f64_mean_and_std(&travel_time_vec_secs, true) {
-        Ok((mean, std)) => {
-            let mean_secs = mean as i64;
-            travel_time_mean = Some(time::Duration::new(mean_secs, ((mean - mean_secs as f64)*1000000000.0) as i32));
-            let std_secs = std as i64;
-            travel_time_std = Some(time::Duration::new(std_secs, ((std - std_secs as f64)*1000000000.0) as i32));
-        },
-        Err(e) => {
-            eprintln!("Error calculating travel time mean and std. Set to zero. Error message: {}", e);
-            travel_time_mean = None;
-            travel_time_std = None;
+    // Compute efficiency of each completed leg
+    let mut legs: Vec<Option<f64>> = Vec::new();
+    for (i, leg) in route_plan.iter().enumerate() {
+        if i < legs_completed && leg_dist_sailed[i] > 0.0 {
+            legs.push(Some(leg.length() / leg_dist_sailed[i]));
+        } else {
+            legs.push(None);
         }
     }
 
-    // Find min and max travel times
-    travel_time_min = travel_time_vec.iter().min().cloned();
-    travel_time_max = travel_time_vec.iter().max().cloned();
-    match get_vec_f64_mean_and_std(&dist_vec, true) {
-        Ok((mean, std)) => {
-            dist_mean = Some(mean);
-            dist_std = Some(std);
-        },
-        Err(e) => {
-            eprintln!("Error calculating distance mean and std. Set to zero. Error message: {}", e);
-            dist_mean = None;
-            dist_std = None;
+    // Compute efficiency of the whole voyage from the first to the last location in the ship log
+    let voyage: Option<f64> = match total_dist_sailed > 0.0 {
+        true => Some(Earth.distance(ship_log.first().unwrap().coordinates_current, ship_log.last().unwrap().coordinates_current) / total_dist_sailed),
+        false => None,
+    };
+
+    // Return route efficiency
+    return Ok(RouteEfficiency { voyage, legs });
+}
+
+/// Compares a simulated voyage with a recorded voyage of the same route, e.g. a real AIS track, to calibrate and validate the simulator
+/// The voyages are aligned by the time since their first ship log entry, so they do not need to start at the same time
+/// simulated: Ship log of the simulated voyage
+/// recorded: Ship log of the recorded voyage, see aishub_shiplog_csv_to_marine_vessel_simulator_shiplog_csv() and csv_to_ship_log()
+/// Returns the error metrics, see ValidationMetrics, or an error if either ship log has fewer than 2 entries
+/// # Example:
+/// `let metrics = validate_ship_log(&boat.ship_log, &csv_to_ship_log("recorded_voyage.csv")?)?;`
+/// `println!("Cross track RMSE: {:.0} m, arrival error: {}", metrics.cross_track_rmse, metrics.arrival_time_error);`
+pub fn validate_ship_log(simulated: &[ShipLogEntry], recorded: &[ShipLogEntry]) -> Result<ValidationMetrics, MarineSimError> {
+    // Validate input
+    if simulated.len() < 2 || recorded.len() < 2 {
+        return Err(MarineSimError::InvalidInput("Both ship logs must have at least 2 entries to validate".to_string()));
+    }
+
+    // Distance from each simulated position to the closest segment of the recorded track
+    let mut cross_track_sum_sq: f64 = 0.0;
+    let mut cross_track_max: f64 = 0.0;
+    for entry in simulated {
+        let dist = recorded.windows(2).map(|pair| get_min_point_to_segment_dist(pair[0].coordinates_current, pair[1].coordinates_current, entry.coordinates_current)).fold(f64::INFINITY, f64::min);
+        cross_track_sum_sq += dist.powi(2);
+        cross_track_max = cross_track_max.max(dist);
+    }
+    let cross_track_rmse = (cross_track_sum_sq / simulated.len() as f64).sqrt();
+
+    // Distance to the recorded position at the same time since departure, interpolated between the recorded entries
+    let simulated_start = simulated[0].timestamp;
+    let recorded_start = recorded[0].timestamp;
+    let mut position_sum_sq: f64 = 0.0;
+    let mut num_positions: usize = 0;
+    let mut j: usize = 1;
+    for entry in simulated {
+        let recorded_time = recorded_start + (entry.timestamp - simulated_start);
+        while j < recorded.len() - 1 && recorded[j].timestamp < recorded_time {
+            j += 1;
         }
+        let (before, after) = (&recorded[j-1], &recorded[j]);
+        if recorded_time < before.timestamp || recorded_time > after.timestamp {
+            continue;
+        }
+        let span = (after.timestamp - before.timestamp).as_seconds_f64();
+        let fraction = match span > 0.0 {
+            true => (recorded_time - before.timestamp).as_seconds_f64() / span,
+            false => 0.0,
+        };
+        let recorded_position = Earth.destination(before.coordinates_current, Earth.bearing(before.coordinates_current, after.coordinates_current), fraction*Earth.distance(before.coordinates_current, after.coordinates_current));
+        position_sum_sq += Earth.distance(entry.coordinates_current, recorded_position).powi(2);
+        num_positions += 1;
+    }
+    let position_rmse = match num_positions {
+        0 => None,
+        n => Some((position_sum_sq / n as f64).sqrt()),
+    };
+
+    // Arrival time, distance and speed
+    let simulated_kpis = VoyageKpis::from_ship_log(simulated);
+    let recorded_kpis = VoyageKpis::from_ship_log(recorded);
+    let speed_bias = match (simulated_kpis.speed_mean, recorded_kpis.speed_mean) {
+        (Some(simulated_speed), Some(recorded_speed)) => Some(simulated_speed - recorded_speed),
+        _ => None,
+    };
+
+    return Ok(ValidationMetrics {
+        cross_track_rmse,
+        cross_track_max,
+        position_rmse,
+        arrival_time_error: simulated_kpis.duration - recorded_kpis.duration,
+        distance_error: simulated_kpis.distance - recorded_kpis.distance,
+        speed_bias,
+    });
+}
+
+/// Get shortest distance in meters from point p3 to the great circle segment from p1 to p2, unlike get_min_point_to_great_circle_dist() the line does not continue past p1 and p2
+/// Uses the closed form cross track and along track distances, see SailingLeg.cross_track_distance() and SailingLeg.along_track_distance()
+fn get_min_point_to_segment_dist(p1: geo::Point, p2: geo::Point, p3: geo::Point) -> f64 {
+    let segment_length = Earth.distance(p1, p2);
+    if segment_length == 0.0 {
+        return Earth.distance(p1, p3);
+    }
+    let segment = SailingLeg::new(p1, p2, 0.0, 0.0);
+    // Behind p1 the closest point is p1 and past p2 it is p2
+    let along_track = segment.along_track_distance(p3);
+    if along_track < 0.0 {
+        return Earth.distance(p1, p3);
+    }
+    if along_track > segment_length {
+        return Earth.distance(p2, p3);
     }
-    // Return the values
-    return (speed_mean, speed_std, cargo_mean, cargo_std, travel_time_min, travel_time_max, travel_time_mean, travel_time_std, dist_mean, dist_std, num_trips)
+    return segment.cross_track_distance(p3).abs();
+}
+
+/// How a csv file that already exists is written to, see save_shipping_logs_evaluation_to_csv()
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CsvWriteMode {
+    /// Replace the file
+    Overwrite,
+    /// Keep the rows in the file. Rows with the same name as a new row are updated and the other new rows are added at the end, as in append_shipping_statistics_to_csv()
+    Append,
 }
 
 /// Saves the given parameters to a csv file at csv_file_path
-/// Will overwrite any file with the same file name at csv_file_path.
-/// Does not append rows to existing csv files.
+/// With CsvWriteMode::Overwrite any file with the same file name at csv_file_path is overwritten,
+/// with CsvWriteMode::Append the rows are added to the rows already in the file, keyed by name, see CsvWriteMode
 /// csv_file_path must end with ".csv"
 /// names is the first column of the csv file and will help indicate what the statistics are for.
 /// All vectors must have the same length
+/// Values that are None are written as empty cells
+/// The distributions add the min, 5th percentile, median, 95th percentile and max of the speed, travel time (in days) and distance, see ShippingLogStats
+/// The weather columns are left empty, use append_shipping_statistics_to_csv() with ShippingStatistics::from_cargo_shipping_logs() to write them
 /// Returns mean distance in kilometers and distance standard deviation in meters
-pub fn save_shipping_logs_evaluation_to_csv(csv_file_path: &str, name_vec: Vec<&str>, speed_mean_vec: Vec<Option<f64>>, speed_std_vec: Vec<Option<f64>>, cargo_mean_vec: Vec<Option<f64>>, cargo_std_vec: Vec<Option<f64>>, travel_time_mean_vec: Vec<Option<time::Duration>>, travel_time_std_vec: Vec<Option<time::Duration>>, dist_mean_vec: Vec<Option<f64>>, dist_std_vec: Vec<Option<f64>>, num_trips_vec: Vec<u64>) -> Result<String, io::Error> {
+pub fn save_shipping_logs_evaluation_to_csv(csv_file_path: &str, name_vec: Vec<&str>, speed_mean_vec: Vec<Option<f64>>, speed_std_vec: Vec<Option<f64>>, cargo_mean_vec: Vec<Option<f64>>, cargo_std_vec: Vec<Option<f64>>, travel_time_mean_vec: Vec<Option<time::Duration>>, travel_time_std_vec: Vec<Option<time::Duration>>, dist_mean_vec: Vec<Option<f64>>, dist_std_vec: Vec<Option<f64>>, route_efficiency_mean_vec: Vec<Option<f64>>, route_efficiency_std_vec: Vec<Option<f64>>, num_trips_vec: Vec<u64>, speed_distribution_vec: Vec<Option<DistributionStats>>, travel_time_distribution_vec: Vec<Option<DistributionStats>>, dist_distribution_vec: Vec<Option<DistributionStats>>, mode: CsvWriteMode) -> Result<String, MarineSimError> {
     // Check if csv_file_path ends with ".csv"
-    let num_chars = csv_file_path.chars().count();
-    if &csv_file_path[(num_chars-4)..] != ".csv" {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "The filepath must end with \".csv\""));
+    if !check_file_extension(csv_file_path, ".csv") {
+        return Err(MarineSimError::InvalidInput("The filepath must end with \".csv\"".to_string()));
     }
 
     // Check if vectors are the same size
     let vec_size = name_vec.len();
-    if speed_mean_vec.len() != vec_size || speed_std_vec.len() != vec_size || cargo_mean_vec.len() != vec_size || cargo_std_vec.len() != vec_size || travel_time_mean_vec.len() != vec_size || travel_time_std_vec.len() != vec_size || dist_mean_vec.len() != vec_size || dist_std_vec.len() != vec_size || num_trips_vec.len() != vec_size {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "All input vectors must have the same length"));
+    if speed_mean_vec.len() != vec_size || speed_std_vec.len() != vec_size || cargo_mean_vec.len() != vec_size || cargo_std_vec.len() != vec_size || travel_time_mean_vec.len() != vec_size || travel_time_std_vec.len() != vec_size || dist_mean_vec.len() != vec_size || dist_std_vec.len() != vec_size || route_efficiency_mean_vec.len() != vec_size || route_efficiency_std_vec.len() != vec_size || num_trips_vec.len() != vec_size || speed_distribution_vec.len() != vec_size || travel_time_distribution_vec.len() != vec_size || dist_distribution_vec.len() != vec_size {
+        return Err(MarineSimError::InvalidInput("All input vectors must have the same length".to_string()));
     }
 
-    // Create a CSV writer with a semicolon delimiter
-    let mut wtr = csv::WriterBuilder::new()
-        .delimiter(b';')
-        .has_headers(true)
-        .from_path(csv_file_path)?;
-
-    // Write the header
-    wtr.write_record(&["name","speed_mean[m/s]","speed_std[m/s]","cargo_mean[tons]","cargo_std[tons]","travel_time_mean[days]","travel_time_std[days]","dist_mean[m]","dist_std[m]","num_trips:"])?;
-
-    // Write the ship log entries
+    // Make a row for each name
+    let mut rows: Vec<ShippingStatisticsRow> = Vec::with_capacity(vec_size);
     for i in 0..vec_size {
-        // Get name
-        let name = name_vec[i];
-        // Get speed_mean
-        let speed_mean = &speed_mean_vec[i].unwrap().to_string();
-        // Get speed_std
-        let speed_std = &speed_std_vec[i].unwrap().to_string();
-        // Get cargo_mean, if None, set to empty string
-        let cargo_mean = &match cargo_mean_vec[i] {
-            Some(c) => c.to_string(),
-            None => String::from(""),
+        let statistics = ShippingStatistics {
+            speed_mean: speed_mean_vec[i],
+            speed_std: speed_std_vec[i],
+            cargo_mean: cargo_mean_vec[i],
+            cargo_std: cargo_std_vec[i],
+            travel_time_mean: travel_time_mean_vec[i],
+            travel_time_std: travel_time_std_vec[i],
+            dist_mean: dist_mean_vec[i],
+            dist_std: dist_std_vec[i],
+            route_efficiency_mean: route_efficiency_mean_vec[i],
+            route_efficiency_std: route_efficiency_std_vec[i],
+            num_trips: num_trips_vec[i],
+            speed_distribution: speed_distribution_vec[i].clone(),
+            travel_time_distribution: travel_time_distribution_vec[i].clone(),
+            dist_distribution: dist_distribution_vec[i].clone(),
+            metadata: Vec::new(),
+            ..Default::default()
         };
-        // Get cargo_std, if None, set to empty string
-        let cargo_std = &match cargo_std_vec[i] {
-            Some(c) => c.to_string(),
-            None => String::from(""),
-        };
-        // Get travel_time_mean
-        let travel_time_mean = &travel_time_mean_vec[i].unwrap().to_string();
-        // Get travel_time_std
-        let travel_time_std = &travel_time_std_vec[i].unwrap().to_string();
-        // Get dist_mean in meters
-        let dist_mean = &(dist_mean_vec[i].unwrap()).to_string();
-        // Get dist_std in meters
-        let dist_std = &dist_std_vec[i].unwrap().to_string();
-        // Get num_trips
-        let num_trips = &num_trips_vec[i].to_string();
+        rows.push(get_shipping_statistics_record(name_vec[i], &statistics));
+    }
 
-        // Write the record
-        wtr.write_record(&[
-            name,
-            speed_mean,
-            speed_std,
-            cargo_mean,
-            cargo_std,
-            travel_time_mean,
-            travel_time_std,
-            dist_mean,
-            dist_std,
-            num_trips,
-        ])?;
+    // Add the rows already in the file, if appending
+    if mode == CsvWriteMode::Append {
+        let mut existing_rows = read_shipping_statistics_rows(csv_file_path)?;
+        merge_shipping_statistics_rows(&mut existing_rows, rows);
+        rows = existing_rows;
     }
 
-    // Flush and close the writer
-    wtr.flush()?;
+    // Write the rows
+    write_shipping_statistics_rows(csv_file_path, &rows)?;
     return Ok(("Saved shipping log statistics to csv file").to_string());
 }
 
-/// Visualize ship logs with plotly on map
-/// figure_file_path: Option<&str> - Path to the file where the figure will be saved. If None, the figure will not be saved to a file.
-pub fn plot_ship_logs(shiplogs: Vec<ShipLogEntry>, figure_file_path: Option<&str>) -> Result<(), io::Error> {
-    // Init vectors for coordinates
-    let mut y_vec: Vec<f64> = Vec::new();
-    let mut x_vec: Vec<f64> = Vec::new();
+/// Header of the statistics columns of the shipping statistics csv files, the metadata columns come after
+const SHIPPING_STATISTICS_CSV_HEADER: [&str; 33] = ["name","speed_mean[m/s]","speed_std[m/s]","cargo_mean[tons]","cargo_std[tons]","travel_time_mean[days]","travel_time_std[days]","dist_mean[m]","dist_std[m]","route_efficiency_mean[-]","route_efficiency_std[-]","num_trips:",
+    "speed_min[m/s]","speed_p5[m/s]","speed_median[m/s]","speed_p95[m/s]","speed_max[m/s]",
+    "travel_time_min[days]","travel_time_p5[days]","travel_time_median[days]","travel_time_p95[days]","travel_time_max[days]",
+    "dist_min[m]","dist_p5[m]","dist_median[m]","dist_p95[m]","dist_max[m]",
+    "wind_speed_mean[m/s]","wind_speed_max[m/s]","wave_height_max[m]","upwind_time_mean[%]","downwind_time_mean[%]","tacking_time_mean[%]"];
+/// Number of columns in shipping statistics csv files written before the distribution columns were added, they are still read
+const SHIPPING_STATISTICS_CSV_LEGACY_COLUMNS: usize = 12;
+/// Number of columns in shipping statistics csv files written before the weather columns were added, they are still read
+const SHIPPING_STATISTICS_CSV_PRE_WEATHER_COLUMNS: usize = 27;
+
+/// Appends the statistics of one scenario to a shipping statistics csv file, keyed by the scenario name
+/// If the file already has a row with the same name the row is updated, otherwise the row is added at the end. If the file does not exist it is created
+/// The metadata of the statistics is written as extra columns, rows without a metadata key have an empty cell in its column
+/// Useful to save the results of each scenario as soon as it has been simulated, instead of all at the end with save_shipping_logs_evaluation_to_csv()
+/// csv_file_path must end with ".csv"
+/// Values that are None are written as empty cells
+/// # Example:
+/// `append_shipping_statistics_to_csv("results.csv", "Sailing cargo ship", &statistics)?;`
+pub fn append_shipping_statistics_to_csv(csv_file_path: &str, name: &str, statistics: &ShippingStatistics) -> Result<(), MarineSimError> {
+    // Check if csv_file_path ends with ".csv"
+    if !check_file_extension(csv_file_path, ".csv") {
+        return Err(MarineSimError::InvalidInput("The filepath must end with \".csv\"".to_string()));
+    }
 
-    // Iterate through each entry of in the shiplog to draw the values
-    for entry in shiplogs {
-        // Add coordinates to vectors
-        x_vec.push(entry.coordinates_current.x());
-        y_vec.push(entry.coordinates_current.y());
+    // Read the rows already in the file, update or add the row for the name and write the file again
+    let mut rows = read_shipping_statistics_rows(csv_file_path)?;
+    merge_shipping_statistics_rows(&mut rows, vec![get_shipping_statistics_record(name, statistics)]);
+    return write_shipping_statistics_rows(csv_file_path, &rows);
+}
+
+/// Merges the rows of one shipping statistics csv file into another, keyed by the scenario name
+/// Rows in other_csv_file_path update the rows with the same name in csv_file_path, rows with new names are added at the end
+/// Useful to combine results from simulations that were run separately, e.g. on different computers
+pub fn merge_shipping_statistics_csv_files(csv_file_path: &str, other_csv_file_path: &str) -> Result<(), MarineSimError> {
+    // Check if csv_file_path ends with ".csv"
+    if !check_file_extension(csv_file_path, ".csv") || !check_file_extension(other_csv_file_path, ".csv") {
+        return Err(MarineSimError::InvalidInput("The filepaths must end with \".csv\"".to_string()));
+    }
+    if !std::path::Path::new(other_csv_file_path).exists() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("Shipping statistics file {} not found", other_csv_file_path)).into());
     }
 
-    // Setup trace of ship logs
-    let trace = plotly::ScatterGeo::new(y_vec, x_vec)
-                    .name("Ship logs")
-                    .mode(plotly::common::Mode::LinesMarkersText)
-                    .show_legend(true);  // ScatterGeo::new(latitudes, longitudes).name("Ship Logs").marker_color("blue"));
+    let mut rows = read_shipping_statistics_rows(csv_file_path)?;
+    merge_shipping_statistics_rows(&mut rows, read_shipping_statistics_rows(other_csv_file_path)?);
+    return write_shipping_statistics_rows(csv_file_path, &rows);
+}
 
-    // Set layout as instructed by andrei-ng https://github.com/plotly/plotly.rs/pull/301
-    let layout = plotly::Layout::new()
-        .drag_mode(plotly::layout::DragMode::Zoom)
-        .margin(plotly::layout::Margin::new().top(20).left(10).bottom(30).right(10))
-        .auto_size(true)
-        .geo(
-            plotly::layout::LayoutGeo::new()
-                .showocean(true)
-                .showlakes(true)
-                .showcountries(true)
-                .showland(true)
-                .oceancolor(plotly::color::Rgb::new(0, 255, 255))
-                .lakecolor(plotly::color::Rgb::new(0, 255, 255))
-                .landcolor(plotly::color::Rgb::new(230, 145, 56))
-                .lataxis(
-                    plotly::layout::Axis::new()
-                        .show_grid(true)
-                        .grid_color(plotly::color::Rgb::new(102, 102, 102)),
-                )
-                .lonaxis(
-                    plotly::layout::Axis::new()
-                        .show_grid(true)
-                        .grid_color(plotly::color::Rgb::new(102, 102, 102)),
-                )
-                .projection(
-                    plotly::layout::Projection::new().projection_type(plotly::layout::ProjectionType::Orthographic),
-                ),
-        );
-
-    // Create a plotly figure with the coordinates
-    let mut figure = plotly::Plot::new();
-    // Add trace
-    figure.add_trace(trace);
-    // Set layout to orthographic
-    figure.set_layout(layout);
-    // Get configuration and make responsive for automatically sizing according to window size
-    let fig_config = figure.configuration().clone().responsive(true).fill_frame(true);
-    // Set config
-    figure.set_configuration(fig_config);
-
-    // Open plot
-    figure.show();
+/// Combines shipping statistics csv files into one comparison table with a row for each scenario name, e.g. the results of scenarios that were evaluated one at a time
+/// The rows are in the order of the files, rows with a name that is already in an earlier file update that row. The metadata columns of all files are kept
+/// Will overwrite any file with the same file name at output_csv_file_path, which may be one of the files that are combined
+/// Returns an error if a file does not exist or is not a shipping statistics csv file
+/// # Example:
+/// `combine_shipping_statistics_csv_files("comparison.csv", &["results_sail.csv", "results_diesel.csv", "results_hybrid.csv"])?;`
+pub fn combine_shipping_statistics_csv_files(output_csv_file_path: &str, csv_file_paths: &[&str]) -> Result<(), MarineSimError> {
+    // Check if the file paths end with ".csv"
+    if !check_file_extension(output_csv_file_path, ".csv") || csv_file_paths.iter().any(|csv_file_path| !check_file_extension(csv_file_path, ".csv")) {
+        return Err(MarineSimError::InvalidInput("The filepaths must end with \".csv\"".to_string()));
+    }
 
-    // Save the figure to a file if file path is provided
-    if let Some(file_path) = figure_file_path {
-        figure.write_html(file_path);
+    // Read all files before writing, the output file may be one of them
+    let mut rows: Vec<ShippingStatisticsRow> = Vec::new();
+    for csv_file_path in csv_file_paths {
+        if !std::path::Path::new(csv_file_path).exists() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("Shipping statistics file {} not found", csv_file_path)).into());
+        }
+        merge_shipping_statistics_rows(&mut rows, read_shipping_statistics_rows(csv_file_path)?);
     }
+    return write_shipping_statistics_rows(output_csv_file_path, &rows);
+}
 
-    // Return Ok if all went well
-    return Ok(());
+/// A row of a shipping statistics csv file, the statistics cells (starting with the name) and the metadata
+type ShippingStatisticsRow = (Vec<String>, Vec<(String, String)>);
+
+/// Returns the cells of a shipping statistics csv row and its metadata, None values are empty cells
+fn get_shipping_statistics_record(name: &str, statistics: &ShippingStatistics) -> ShippingStatisticsRow {
+    let f64_cell = |value: Option<f64>| value.map_or(String::new(), |v| v.to_string());
+    let duration_cell = |value: Option<time::Duration>| value.map_or(String::new(), |v| v.to_string());
+    let mut cells = vec![
+        name.to_string(),
+        f64_cell(statistics.speed_mean),
+        f64_cell(statistics.speed_std),
+        f64_cell(statistics.cargo_mean),
+        f64_cell(statistics.cargo_std),
+        duration_cell(statistics.travel_time_mean),
+        duration_cell(statistics.travel_time_std),
+        f64_cell(statistics.dist_mean),
+        f64_cell(statistics.dist_std),
+        f64_cell(statistics.route_efficiency_mean),
+        f64_cell(statistics.route_efficiency_std),
+        statistics.num_trips.to_string(),
+    ];
+    // Min, p5, median, p95 and max of each distribution
+    for (distribution, scale) in [(&statistics.speed_distribution, 1.0), (&statistics.travel_time_distribution, 1.0/86400.0), (&statistics.dist_distribution, 1.0)] {
+        match distribution {
+            Some(d) => cells.extend([d.min, d.p5, d.p50, d.p95, d.max].iter().map(|v| (v*scale).to_string())),
+            None => cells.extend(std::iter::repeat_n(String::new(), 5)),
+        }
+    }
+    // Weather encountered
+    cells.extend([statistics.wind_speed_mean, statistics.wind_speed_max, statistics.wave_height_max, statistics.upwind_percent_mean, statistics.downwind_percent_mean, statistics.tacking_percent_mean].map(f64_cell));
+    return (cells, statistics.metadata.clone());
 }
 
-/// Visualize ship logs and the route with plotly on map
-/// figure_file_path: Option<&str> - Path to the file where the figure will be saved. If None, the figure will not be saved to a file.
-pub fn visualize_ship_logs_and_route(ship_logs_file_path: &str, route_plan_file_path: &str, figure_file_path: Option<&str>) -> Result<(), io::Error> {
-    // Read the CSV file
+/// Reads the rows of a shipping statistics csv file as text, an empty vector if the file does not exist
+/// Returns an error if the file does not start with the statistics columns, so other csv files are not overwritten by mistake
+/// Empty metadata cells are left out of the metadata of the row
+fn read_shipping_statistics_rows(csv_file_path: &str) -> Result<Vec<ShippingStatisticsRow>, MarineSimError> {
+    if !std::path::Path::new(csv_file_path).exists() {
+        return Ok(Vec::new());
+    }
     let mut csv_reader = csv::ReaderBuilder::new()
         .delimiter(b';')
         .has_headers(true)
-        .from_path(ship_logs_file_path)
-        .expect("Failed to open the file");
+        .from_path(csv_file_path)?;
 
-    // Init vectors for coordinates
-    let mut y_vec: Vec<f64> = Vec::new();
-    let mut x_vec: Vec<f64> = Vec::new();
+    // Check header, the columns after the statistics are metadata keys
+    let header = csv_reader.headers()?.clone();
+    // Files without the distribution or weather columns get empty cells for them
+    let num_columns = match [SHIPPING_STATISTICS_CSV_HEADER.len(), SHIPPING_STATISTICS_CSV_PRE_WEATHER_COLUMNS, SHIPPING_STATISTICS_CSV_LEGACY_COLUMNS].into_iter()
+        .find(|num_columns| header.iter().take(*num_columns).eq(SHIPPING_STATISTICS_CSV_HEADER.iter().take(*num_columns).copied())) {
+        Some(num_columns) => num_columns,
+        None => return Err(MarineSimError::CsvParse { file: csv_file_path.to_string(), message: "Not a shipping statistics csv file, the header does not match".to_string() }),
+    };
+    let metadata_keys: Vec<String> = header.iter().skip(num_columns).map(|key| key.to_string()).collect();
 
-    // Iterate through each line of the CSV file to draw the values
+    let mut rows: Vec<ShippingStatisticsRow> = Vec::new();
     for result in csv_reader.records() {
-        match result {
-            Ok(log_entry) => {
-                // Get current coordinates
-                let coordinates_current = match string_to_point(log_entry.get(2).expect("No current coordinate found").to_string()){
-                    Ok(c) => c,
-                    Err(e) => panic!("Error parsing current coordinates: {}", e),
-                };
+        let record = result?;
+        let mut cells: Vec<String> = record.iter().take(num_columns).map(|cell| cell.to_string()).collect();
+        cells.resize(SHIPPING_STATISTICS_CSV_HEADER.len(), String::new());
+        let metadata: Vec<(String, String)> = metadata_keys.iter().zip(record.iter().skip(num_columns))
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(key, value)| (key.clone(), value.to_string()))
+            .collect();
+        rows.push((cells, metadata));
+    }
+    return Ok(rows);
+}
 
-                // Add coordinates to vectors
-                x_vec.push(coordinates_current.x());
-                y_vec.push(coordinates_current.y());
-            }
-            Err(err) => {
-                eprintln!("Error reading log_entry: {}", err);
+/// Updates the rows with the same name (first cell) as a new row and adds the new rows with new names at the end
+fn merge_shipping_statistics_rows(rows: &mut Vec<ShippingStatisticsRow>, new_rows: Vec<ShippingStatisticsRow>) {
+    for new_row in new_rows {
+        match rows.iter_mut().find(|row| row.0.first() == new_row.0.first()) {
+            Some(row) => *row = new_row,
+            None => rows.push(new_row),
+        }
+    }
+}
+
+/// Writes the rows to a shipping statistics csv file with a header, overwrites the file
+/// There is a metadata column for every metadata key in the rows, in the order the keys first appear
+fn write_shipping_statistics_rows(csv_file_path: &str, rows: &Vec<ShippingStatisticsRow>) -> Result<(), MarineSimError> {
+    // Find the metadata keys
+    let mut metadata_keys: Vec<&str> = Vec::new();
+    for (_, metadata) in rows {
+        for (key, _) in metadata {
+            if !metadata_keys.contains(&key.as_str()) {
+                metadata_keys.push(key.as_str());
             }
-        } // End match
-    } // End for loop
+        }
+    }
 
-    // Setup trace of ship logs
-    let trace = plotly::ScatterGeo::new(y_vec, x_vec)
-                    .name("Ship logs")
-                    .mode(plotly::common::Mode::LinesMarkersText)
-                    .show_legend(true);  // ScatterGeo::new(latitudes, longitudes).name("Ship Logs").marker_color("blue"));
+    // Create a CSV writer with a semicolon delimiter
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(csv_file_path)?;
 
-    // Set layout as instructed by andrei-ng https://github.com/plotly/plotly.rs/pull/301
-    let layout = plotly::Layout::new()
-        .drag_mode(plotly::layout::DragMode::Zoom)
-        .margin(plotly::layout::Margin::new().top(20).left(10).bottom(30).right(10))
-        .auto_size(true)
-        .geo(
-            plotly::layout::LayoutGeo::new()
-                .showocean(true)
-                .showlakes(true)
-                .showcountries(true)
-                .showland(true)
-                .oceancolor(plotly::color::Rgb::new(0, 255, 255))
-                .lakecolor(plotly::color::Rgb::new(0, 255, 255))
-                .landcolor(plotly::color::Rgb::new(230, 145, 56))
-                .lataxis(
-                    plotly::layout::Axis::new()
-                        .show_grid(true)
-                        .grid_color(plotly::color::Rgb::new(102, 102, 102)),
-                )
-                .lonaxis(
-                    plotly::layout::Axis::new()
-                        .show_grid(true)
-                        .grid_color(plotly::color::Rgb::new(102, 102, 102)),
-                )
-                .projection(
-                    plotly::layout::Projection::new().projection_type(plotly::layout::ProjectionType::Orthographic),
-                ),
-        );
+    // Write the header and rows
+    let mut header: Vec<&str> = SHIPPING_STATISTICS_CSV_HEADER.to_vec();
+    header.extend(metadata_keys.iter());
+    wtr.write_record(&header)?;
+    for (cells, metadata) in rows {
+        let metadata_cells = metadata_keys.iter().map(|key| match metadata.iter().find(|(k, _)| k == key) {
+            Some((_, value)) => value.as_str(),
+            None => "",
+        });
+        wtr.write_record(cells.iter().map(|cell| cell.as_str()).chain(metadata_cells))?;
+    }
 
+    // Flush and close the writer
+    wtr.flush()?;
+    return Ok(());
+}
 
-    // Create a plotly figure with the coordinates
-    let mut figure = plotly::Plot::new();
-    // Add trace
-    figure.add_trace(trace);
-    // Set layout to orthographic
-    figure.set_layout(layout);
-    // Get configuration and make responsive for automatically sizing according to window size
-    let fig_config = figure.configuration().clone().responsive(true).fill_frame(true);
-    // Set config
-    figure.set_configuration(fig_config);
+/// How a figure is shown and saved by plot_ship_logs() and visualize_ship_logs_and_route()
+/// The format of the saved file is taken from the file extension:
+/// - .html: Interactive figure, always available
+/// - .png, .jpeg, .jpg, .webp, .svg and .pdf: Static image, only with the "kaleido" feature, which renders the figure with the plotly kaleido executable
+/// # Example:
+/// `visualize_ship_logs_and_route("ship_log.csv", "route_plan.csv", &PlotOptions::headless("figures/voyage.png"))?;`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlotOptions {
+    /// If true the figure is opened in the default browser. Set to false on servers and in CI where there is no browser
+    pub show: bool,
+    /// Path to the file where the figure is saved. If None, the figure is not saved to a file
+    pub file_path: Option<String>,
+    /// Width of static images in pixels
+    pub width: usize,
+    /// Height of static images in pixels
+    pub height: usize,
+    /// Scale of static images, e.g. 2.0 for twice the resolution at the same size
+    pub scale: f64,
+    /// Style of the maps, e.g. the projection and colors. Not used by plots that are not maps
+    pub map_style: MapStyle,
+}
 
+impl PlotOptions {
+    /// Opens the figure in the browser without saving it, the same as before PlotOptions
+    pub fn new() -> PlotOptions {
+        return PlotOptions {
+            show: true,
+            file_path: None,
+            width: 1200,
+            height: 800,
+            scale: 1.0,
+            map_style: MapStyle::new(),
+        };
+    }
 
-    // Init vectors for coordinates
-    let mut x_vec: Vec<f64> = Vec::new();
-    let mut y_vec: Vec<f64> = Vec::new();
+    /// Saves the figure to file_path without opening it, for servers and CI
+    pub fn headless(file_path: &str) -> PlotOptions {
+        return PlotOptions {
+            show: false,
+            file_path: Some(file_path.to_string()),
+            ..PlotOptions::new()
+        };
+    }
 
-    // Add each waypoint
-    // TODO: with label to plot
-    let route_plan = match load_route_plan(route_plan_file_path){
-        Ok(r) => r,
-        Err(e) => panic!("Error loading route plan: {}", e),
-    };
-    for leg in &route_plan {
-        // Add the start point to the vectors
-        x_vec.push(leg.p1.y());
-        y_vec.push(leg.p1.x());
+    /// Sets the size of static images in pixels
+    pub fn image_size(mut self, width: usize, height: usize) -> PlotOptions {
+        self.width = width;
+        self.height = height;
+        return self;
     }
-    // Add last point to the vectors
-    let last_leg = route_plan.last().unwrap();
-    x_vec.push(last_leg.p2.y());
-    y_vec.push(last_leg.p2.x());
 
-    // Add a line between the start and end points
-    figure.add_trace(plotly::ScatterGeo::new(x_vec, y_vec)
-        .mode(plotly::common::Mode::LinesMarkersText)
-        .name("Route Plan"));
+    /// Sets the style of the maps, see MapStyle
+    pub fn map_style(mut self, map_style: MapStyle) -> PlotOptions {
+        self.map_style = map_style;
+        return self;
+    }
+}
 
-    // Init vectors for coordinates
-    let mut x_vec_port: Vec<f64> = Vec::new();
-    let mut y_vec_port: Vec<f64> = Vec::new();
-    let mut x_vec_starboard: Vec<f64> = Vec::new();
-    let mut y_vec_starboard: Vec<f64> = Vec::new();
+impl Default for PlotOptions {
+    fn default() -> PlotOptions {
+        return PlotOptions::new();
+    }
+}
 
-    // TODO: Add tacking boundary
-    for (i, leg) in route_plan.iter().enumerate() {
-        // If tacking width changes between legs, we should first plot the previous width at the current location before plotting the new width
-        if i > 0 && leg.tacking_width != route_plan[i-1].tacking_width {
-            // Get last_leg
-            let last_leg = &route_plan[i-1];
-            // Get last tacking width
-            let last_tacking_width = last_leg.tacking_width;
-            // Get point half a tacking width to the left and right of the leg
-            let bearing = Haversine.bearing(last_leg.p1, last_leg.p2);
-            // Get the left and right points but at the location of the current leg
-            let port_point = Haversine.destination(leg.p1, bearing - 90.0, last_tacking_width / 2.0);
-            let starboard_point = Haversine.destination(leg.p1, bearing + 90.0, last_tacking_width / 2.0);
-
-            x_vec_port.push(port_point.y());
-            y_vec_port.push(port_point.x());
-            x_vec_starboard.push(starboard_point.y());
-            y_vec_starboard.push(starboard_point.x());
-        }
-
-        // Get point half a tacking width to the left and right of the leg
-        let bearing = Haversine.bearing(leg.p1, leg.p2);
-        // Get the left and right points
-        let port_point = Haversine.destination(leg.p1, bearing - 90.0, leg.tacking_width / 2.0);
-        let starboard_point = Haversine.destination(leg.p1, bearing + 90.0, leg.tacking_width / 2.0);
-
-        x_vec_port.push(port_point.y());
-        y_vec_port.push(port_point.x());
-        x_vec_starboard.push(starboard_point.y());
-        y_vec_starboard.push(starboard_point.x());
-    }
-    // Add last point to the vectors
-    let bearing = Haversine.bearing(last_leg.p1, last_leg.p2);
-    // Get the left and right points
-    let port_point = Haversine.destination(last_leg.p2, bearing - 90.0, last_leg.tacking_width / 2.0);
-    //let right_point = leg.p1.destination(leg.tacking_width / 2.0, bearing + 90.0);
-    let starboard_point = Haversine.destination(last_leg.p2, bearing + 90.0, last_leg.tacking_width / 2.0);
-    // Append points
-    x_vec_port.push(port_point.y());
-    y_vec_port.push(port_point.x());
-    x_vec_starboard.push(starboard_point.y());
-    y_vec_starboard.push(starboard_point.x());
-
-    // Add a lines for the tacking boundary to plot
-    figure.add_trace(plotly::ScatterGeo::new(x_vec_starboard, y_vec_starboard)
-        .mode(plotly::common::Mode::LinesMarkersText)
-        .name("Tacking boundary starboard side"));
-    figure.add_trace(plotly::ScatterGeo::new(x_vec_port, y_vec_port)
-        .mode(plotly::common::Mode::LinesMarkersText)
-        .name("Tacking boundary port side"));
-        //.line(plotly::Line::new().color("red")));
+/// Map projections of MapStyle
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum MapProjection {
+    /// The earth as a globe seen from space, the default
+    Orthographic,
+    /// Mercator projection, courses are straight lines but areas far from the equator are enlarged
+    Mercator,
+    /// Natural Earth projection, a compromise that shows the whole world with little distortion
+    NaturalEarth,
+}
 
+/// Colors, projection, margins and grid of the maps, e.g. for publications and dashboards, see PlotOptions
+/// Colors are RGB values in \[0, 255\]
+/// # Example:
+/// `let style = MapStyle { projection: MapProjection::Mercator, show_grid: false, ..MapStyle::dark() };`
+/// `visualize_ship_logs_and_route("ship_log.csv", "route_plan.csv", &PlotOptions::new().map_style(style))?;`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapStyle {
+    /// How the earth is drawn on the map
+    pub projection: MapProjection,
+    /// Color of the ocean
+    pub ocean_color: (u8, u8, u8),
+    /// Color of lakes
+    pub lake_color: (u8, u8, u8),
+    /// Color of land
+    pub land_color: (u8, u8, u8),
+    /// If true the borders of countries are drawn
+    pub show_countries: bool,
+    /// If true latitude and longitude grid lines are drawn
+    pub show_grid: bool,
+    /// Color of the grid lines
+    pub grid_color: (u8, u8, u8),
+    /// Margins around the map in pixels, top, left, bottom and right
+    pub margins: (usize, usize, usize, usize),
+    /// If true the plotly dark theme is used for the background, text and legend
+    pub dark_mode: bool,
+    /// Color of the tacking corridors of the route plan
+    pub corridor_color: (u8, u8, u8),
+    /// Opacity of the tacking corridors in \[0, 1\], 0 is invisible. The outline is twice as opaque
+    pub corridor_opacity: f64,
+}
 
+impl MapStyle {
+    /// The default style, an orthographic globe with cyan ocean and orange land
+    pub fn new() -> MapStyle {
+        return MapStyle {
+            projection: MapProjection::Orthographic,
+            ocean_color: (0, 255, 255),
+            lake_color: (0, 255, 255),
+            land_color: (230, 145, 56),
+            show_countries: true,
+            show_grid: true,
+            grid_color: (102, 102, 102),
+            margins: (20, 10, 30, 10),
+            dark_mode: false,
+            corridor_color: (0, 114, 178),
+            corridor_opacity: 0.25,
+        };
+    }
 
-    // TODO: Add vector at each point that shows wind direction at that point at that points time?????
+    /// Dark mode with muted colors, e.g. for dashboards
+    pub fn dark() -> MapStyle {
+        return MapStyle {
+            ocean_color: (22, 38, 58),
+            lake_color: (22, 38, 58),
+            land_color: (60, 60, 60),
+            grid_color: (90, 90, 90),
+            dark_mode: true,
+            ..MapStyle::new()
+        };
+    }
+}
+
+impl Default for MapStyle {
+    fn default() -> MapStyle {
+        return MapStyle::new();
+    }
+}
 
+/// Shows and saves a figure as set in the plot options
+/// The file extension is checked before the figure is shown, so a wrong file path does not leave a browser tab behind
+fn output_figure(figure: &plotly::Plot, options: &PlotOptions) -> Result<(), MarineSimError> {
+    let image_format = match &options.file_path {
+        Some(file_path) => get_figure_image_format(file_path)?,
+        None => None,
+    };
 
     // Open plot
-    figure.show();
+    if options.show {
+        figure.show();
+    }
 
     // Save the figure to a file if file path is provided
-    if let Some(file_path) = figure_file_path {
-        figure.write_html(file_path);
+    if let Some(file_path) = &options.file_path {
+        match image_format {
+            None => figure.write_html(file_path),
+            #[cfg(feature = "kaleido")]
+            Some(format) => figure.write_image(file_path, format, options.width, options.height, options.scale),
+            #[cfg(not(feature = "kaleido"))]
+            Some(_) => return Err(MarineSimError::InvalidInput(format!("Saving {} as a static image needs the \"kaleido\" feature, save as .html instead", file_path))),
+        }
     }
-
-    // Return Ok if all went well
     return Ok(());
 }
 
+/// The static image format of a figure file from its extension, None for .html
+/// Returns an error if the extension is not supported
+#[cfg(feature = "kaleido")]
+fn get_figure_image_format(file_path: &str) -> Result<Option<plotly::ImageFormat>, MarineSimError> {
+    let format = match get_figure_file_extension(file_path)?.as_str() {
+        "html" => return Ok(None),
+        "png" => plotly::ImageFormat::PNG,
+        "jpeg" | "jpg" => plotly::ImageFormat::JPEG,
+        "webp" => plotly::ImageFormat::WEBP,
+        "svg" => plotly::ImageFormat::SVG,
+        "pdf" => plotly::ImageFormat::PDF,
+        extension => return Err(MarineSimError::InvalidInput(format!("Can not save figure as .{}, use .html, .png, .jpeg, .webp, .svg or .pdf", extension))),
+    };
+    return Ok(Some(format));
+}
 
-// Helper functions
-//----------------------------------------------------
-/// Converts a string into an uom::si::f64::Time object
-/// time_string: The string to convert in the format YYYY-MM-DD hh:mm
-/// # Example:
-/// `let my_timestamp: uom::si::f64::Time = str_to_coordinate("52.5200,13.4050");`
-pub fn string_to_utc_date_time(time_string: String) -> time::UtcDateTime {
-    // Remove all whitespaces in string
-    let mut working_str: &str = (&time_string[..]).trim();
-
-    // If string is longer than 16 characters but shorter than 25, just take first 16 characters
-    if working_str.len() > 16 && working_str.len() < 25 {
-        working_str = &working_str[0..16];
-    }
+/// The static image format of a figure file from its extension, None for .html
+/// Without the "kaleido" feature only .html is saved, static image formats are returned as their extension so output_figure() can explain what is missing
+#[cfg(not(feature = "kaleido"))]
+fn get_figure_image_format(file_path: &str) -> Result<Option<String>, MarineSimError> {
+    return match get_figure_file_extension(file_path)?.as_str() {
+        "html" => Ok(None),
+        extension @ ("png" | "jpeg" | "jpg" | "webp" | "svg" | "pdf") => Ok(Some(extension.to_string())),
+        extension => Err(MarineSimError::InvalidInput(format!("Can not save figure as .{}, use .html, .png, .jpeg, .webp, .svg or .pdf", extension))),
+    };
+}
 
-    // Check if the string is valid
-    if !((working_str.len() == 16) || (working_str.len() == 25)) {
-        panic!("Invalid time format with length {}:\n{}", working_str.len(), working_str);
-    }
+/// The lowercase file extension of a figure file without the dot
+fn get_figure_file_extension(file_path: &str) -> Result<String, MarineSimError> {
+    return match std::path::Path::new(file_path).extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => Ok(extension.to_lowercase()),
+        None => Err(MarineSimError::InvalidInput(format!("The figure file path {} has no file extension", file_path))),
+    };
+}
 
-    // Get parts from string
-    let year:    i32 = working_str[0..4].parse::<i32>().expect(format!("Invalid year: {}\nInput string: {}\nError\n", &working_str[0..4], working_str).as_str());
-    let month = time::Month::try_from(working_str[5..7].parse::<u8>().expect(format!("Invalid month: {}\nInput string: {}\nError\n", &working_str[5..7], working_str).as_str())).expect("Invalid month");
-    let day_of_month: u8 = working_str[8..10].parse::<u8>().expect(format!("Invalid day: {}\nInput string: {}\nError\n", &working_str[8..10], working_str).as_str());
-    let date = time::Date::from_calendar_date(year, month, day_of_month).expect("Could not create time::Date from values");
+/// Visualize ship logs with plotly on map
+/// options: How the figure is shown and saved, see PlotOptions
+pub fn plot_ship_logs(shiplogs: Vec<ShipLogEntry>, options: &PlotOptions) -> Result<(), MarineSimError> {
+    // Init vectors for coordinates
+    let mut y_vec: Vec<f64> = Vec::new();
+    let mut x_vec: Vec<f64> = Vec::new();
 
-    let hour: u8 = working_str[11..13].parse::<u8>().expect(format!("Invalid hour: {}\nInput string: {}\nError\n", &working_str[11..13], working_str).as_str());
-    let minutes: u8 = working_str[14..16].parse::<u8>().expect(format!("Invalid minute: {}\nInput string: {}\nError\n", &working_str[14..16], working_str).as_str());
-    let mut seconds: u8 = 0;
-    // If we have seconds, get them
-    if working_str.len() >= 19 {
-        seconds = working_str[17..19].parse::<u8>().expect(format!("Invalid second: {}\nInput string: {}\nError\n", &working_str[17..19], working_str).as_str());
+    // Iterate through each entry of in the shiplog to draw the values
+    for entry in shiplogs {
+        // Add coordinates to vectors
+        x_vec.push(entry.coordinates_current.x());
+        y_vec.push(entry.coordinates_current.y());
     }
-    let time_hms = time::Time::from_hms(hour, minutes, seconds).expect("Could not create time::Time from values");
 
-    // Attempt to parse the string into a uom::si::f64::Time object
-    let time_out = time::UtcDateTime::new(date, time_hms);
-    
-    // Return
-    return time_out;
-}
+    // Setup trace of ship logs, with the longitudes unwrapped so the line does not go across the whole map at the ±180° meridian
+    let trace = plotly::ScatterGeo::new(y_vec, unwrap_longitudes(&x_vec))
+                    .name("Ship logs")
+                    .mode(plotly::common::Mode::LinesMarkersText)
+                    .show_legend(true);  // ScatterGeo::new(latitudes, longitudes).name("Ship Logs").marker_color("blue"));
 
-/// Converts a time_stamp to a string in the format YYYY-MM-DD hh:mm
-/// Is this function never called?
-pub fn timestamp_to_string(time_stamp: uom::si::f64::Time) -> String {
-    // Get the year and day from the time_stamp
-    let year: i32 = time_stamp.get::<uom::si::time::year>() as i32;
-    let time_left: uom::si::f64::Time = uom::si::time::Time::new::<uom::si::time::year>(time_stamp.get::<uom::si::time::year>() - (year as f64));
+    // Create a plotly figure with the coordinates
+    let mut figure = get_map_figure(&options.map_style);
+    // Add trace
+    figure.add_trace(trace);
 
-    let day_of_year: u16 = 1 + time_left.get::<uom::si::time::day>() as u16;
+    // Show and save the figure
+    return output_figure(&figure, options);
+}
 
-    // Find the month from the day and year (if it's a leap year)
-    let (month, day_of_month): (u8, u16) = month_from_day(day_of_year, year);
+/// Visualize ship logs and the route with plotly on map
+/// options: How the figure is shown and saved, see PlotOptions
+/// # Example:
+/// `visualize_ship_logs_and_route("ship_log.csv", "route_plan.csv", &PlotOptions::new())?;`
+pub fn visualize_ship_logs_and_route(ship_logs_file_path: &str, route_plan_file_path: &str, options: &PlotOptions) -> Result<(), MarineSimError> {
+    let figure = get_ship_logs_and_route_figure(ship_logs_file_path, route_plan_file_path, &options.map_style)?;
 
-    // Find hour
-    let time_left: uom::si::f64::Time = uom::si::time::Time::new::<uom::si::time::year>(time_left.get::<uom::si::time::day>() - (day_of_year as f64));
-    let hour: u16 = time_left.get::<uom::si::time::hour>() as u16;
+    // Show and save the figure
+    return output_figure(&figure, options);
+}
 
-    // Find minute
-    let time_left: uom::si::f64::Time = uom::si::time::Time::new::<uom::si::time::year>(time_left.get::<uom::si::time::hour>() - (hour as f64));
-    let minute: u16 = time_left.get::<uom::si::time::minute>() as u16;
+/// Colors of the voyages in visualize_ship_logs_multi(), the Okabe-Ito palette which can be told apart with color vision deficiency
+/// Used in this order and repeated if there are more voyages than colors
+const VOYAGE_COLORS: [(u8, u8, u8); 8] = [(230, 159, 0), (86, 180, 233), (0, 158, 115), (213, 94, 0), (0, 114, 178), (204, 121, 167), (240, 228, 66), (0, 0, 0)];
 
-    // Format the string
-    let time_string: String = format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day_of_month, hour, minute);
+/// Visualize several ship logs on one map with plotly, e.g. to compare a fossil fuelled ship with simple and complex sailing ships on the same route
+/// Each voyage has its own color and its name in the legend
+/// ship_logs: The name and ship log CSV file path of each voyage, see ship_logs_to_csv()
+/// route_plan_file_path: The route plan shown with its tacking corridors, None to only show the voyages
+/// options: How the figure is shown and saved, see PlotOptions
+/// # Example:
+/// `visualize_ship_logs_multi(&[("Fossil", "fossil.csv"), ("Simple sail", "simple_sail.csv"), ("Complex sail", "complex_sail.csv")], Some("route_plan.csv"), &PlotOptions::new())?;`
+pub fn visualize_ship_logs_multi(ship_logs: &[(&str, &str)], route_plan_file_path: Option<&str>, options: &PlotOptions) -> Result<(), MarineSimError> {
+    let figure = get_ship_logs_multi_figure(ship_logs, route_plan_file_path, &options.map_style)?;
 
-    // return string
-    return time_string;
+    // Show and save the figure
+    return output_figure(&figure, options);
 }
 
-/// Finds out which month of the year it is given the day number and year (in case it is a leap year)
-pub fn month_from_day(day_of_year: u16, year: i32) -> (u8, u16) {
-    let mut days_left: u16 = day_of_year;
-
-    println!("Day of year: {}", days_left);
-
-    // Check if the day is valid
-    if days_left > 366 {
-        panic!("Invalid day");
+/// Makes the plotly map of visualize_ship_logs_multi()
+fn get_ship_logs_multi_figure(ship_logs: &[(&str, &str)], route_plan_file_path: Option<&str>, map_style: &MapStyle) -> Result<plotly::Plot, MarineSimError> {
+    if ship_logs.is_empty() {
+        return Err(MarineSimError::InvalidInput("No ship logs to visualize".to_string()));
     }
 
-    // Init month
-    let month: u8;
-
-    // Leap year check
-    let is_leap_year: bool = year_helper::is_leap_year(year);
+    let mut figure = get_map_figure(map_style);
+    // Show the legend even if there is only one voyage
+    let layout = figure.layout().clone().show_legend(true);
+    figure.set_layout(layout);
 
-    // match through the number of days
-    match days_left {
-        1..=31 => {
-            month = 1; // January
-        }
-        32..=59 => {
-            month = 2; // February
-            days_left -= 31; // January has 31 days
-        }
-        60..=90 => {
-            // if leap year, day 60 is 29th of February
-            if is_leap_year && days_left == 60 {
-                month = 2; // February
-            } else {
-                month = 3; // March
-            }
-            days_left -= 59;    // remove days in previous months of a normal year
-        }
-        91..=120 => {
-            month = 4; // April
-            days_left -= 90;
-        }
-        121..=151 => {
-            month = 5; // May
-            days_left -= 120;
-        }
-        152..=181 => {
-            month = 6; // June
-            days_left -= 151;
-        }
-        182..=212 => {
-            month = 7; // July
-            days_left -= 181;
-        }
-        213..=243 => {
-            month = 8; // August
-            days_left -= 212;
-        }
-        244..=273 => {
-            month = 9; // September
-            days_left -= 243;
-        }
-        274..=304 => {
-            month = 10; // October
-            days_left -= 273;
-        }
-        305..=334 => {
-            month = 11; // November
-            days_left -= 304;
-        }
-        335..=366 => {
-            month = 12; // December
-            days_left -= 334;
-        }
-        _ => {
-            panic!("Invalid day");
-        }
+    // The route plan goes under the voyages
+    if let Some(route_plan_file_path) = route_plan_file_path {
+        add_route_plan_traces(&mut figure, route_plan_file_path, map_style)?;
     }
 
-    // If month is big enough and it's a leap year, subtract 1 day from days left
-    if is_leap_year && month > 2 {
-        days_left -= 1; // Leap year, add one day to February
+    for (i, (name, ship_log_file_path)) in ship_logs.iter().enumerate() {
+        let (y_vec, x_vec) = read_ship_log_csv_coordinates(ship_log_file_path)?;
+        let (r, g, b) = VOYAGE_COLORS[i % VOYAGE_COLORS.len()];
+        let color = plotly::color::Rgb::new(r, g, b);
+        figure.add_trace(plotly::ScatterGeo::new(y_vec, unwrap_longitudes(&x_vec))
+            .name(*name)
+            .mode(plotly::common::Mode::LinesMarkers)
+            .line(plotly::common::Line::new().color(color))
+            .marker(plotly::common::Marker::new().color(color).size(4))
+            .show_legend(true));
     }
 
-    println!("Month: {}, Day of month : {}", month, days_left);
-
-    // Return the month and how many days are left
-    return (month, days_left);
+    return Ok(figure);
 }
 
-/// Converts a string into a geo::Point object
-/// point_string: The string to convert
+/// Plot the speed over ground, heading and course, and wind speed of a ship log over time with plotly, one plot under the other with a shared time axis
+/// Shows what maps can not, e.g. the speed drops of a storm passage or a heading far off the course while tacking
+/// The speed over ground is the velocity of each entry, or the speed from the previous entry if the entry has no velocity. Values that are not in the ship log are left as gaps
+/// options: How the figure is shown and saved, see PlotOptions
 /// # Example:
-/// `let my_coord: geo::Point = string_to_point("52.5200,13.4050");`
-/// Note that the output is a geo::Point::new(longitude, latitude) but the input string must be in the format of latitude,longitude so the order is reversed
-pub fn string_to_point(coord_string: String) -> Result<geo::Point, io::Error> {
-    // Remove all spaces in string
-    let coord_str_vec: Vec<&str> = coord_string.trim().split(',').collect();
+/// `plot_ship_log_time_series(&boat.ship_log, &PlotOptions::headless("figures/time_series.html"))?;`
+pub fn plot_ship_log_time_series(ship_log: &[ShipLogEntry], options: &PlotOptions) -> Result<(), MarineSimError> {
+    let figure = get_ship_log_time_series_figure(ship_log)?;
 
-    // Check if string is empty
-    if coord_str_vec[0].len() == 0 ||coord_str_vec[1].len() == 0 {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Input coordinate string is empty: {}", coord_string)));
-    }
+    // Show and save the figure
+    return output_figure(&figure, options);
+}
 
-    // Check if the coordinates are valid, should have latitude and longitude
-    if coord_str_vec.len() != 2 {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid coordinate format: {}. Should be \"latitude,longitude\"", coord_string)));
+/// Makes the plotly figure of plot_ship_log_time_series()
+fn get_ship_log_time_series_figure(ship_log: &[ShipLogEntry]) -> Result<plotly::Plot, MarineSimError> {
+    if ship_log.is_empty() {
+        return Err(MarineSimError::InvalidInput("The ship log is empty".to_string()));
     }
 
-    // Parse the latitude and longitude as f64
-    let mut latitude: f64 = match coord_str_vec[0].trim().parse::<f64>() {
-        Ok(lat) => lat,
-        Err(e) => return Err(io::Error::new(io::ErrorKind::Other, format!("Invalid latitude: {:?}\nInput string: {}\nError: {}\n", coord_str_vec, coord_string, e))),
-    };
-    let mut longitude: f64 = match coord_str_vec[1].trim().parse::<f64>() {
-        Ok(lon) => lon,
-        Err(e) => return Err(io::Error::new(io::ErrorKind::Other, format!("Invalid longitude: {:?}\nInput string: {}\nError: {}\n", coord_str_vec, coord_string, e))),
-    };
-
-    // Make sure longitude is between -180° and 360°
-    while longitude < -180.0 {
-        longitude += 360.0;
-    }
-    while longitude > 360.0 {
-        longitude -= 360.0;
+    // Init vectors for the values, None is a gap in the line
+    let mut timestamps: Vec<String> = Vec::with_capacity(ship_log.len());
+    let mut speed_over_ground: Vec<Option<f64>> = Vec::with_capacity(ship_log.len());
+    let mut headings: Vec<Option<f64>> = Vec::with_capacity(ship_log.len());
+    let mut courses: Vec<Option<f64>> = Vec::with_capacity(ship_log.len());
+    let mut wind_speeds: Vec<Option<f64>> = Vec::with_capacity(ship_log.len());
+
+    for (i, entry) in ship_log.iter().enumerate() {
+        timestamps.push(format_timestamp(entry.timestamp));
+        let speed = match (entry.velocity, i) {
+            (Some(velocity), _) => Some(velocity.magnitude),
+            (None, 0) => None,
+            (None, _) => {
+                let previous = &ship_log[i-1];
+                let seconds = (entry.timestamp - previous.timestamp).as_seconds_f64();
+                if seconds > 0.0 { Some(Earth.distance(previous.coordinates_current, entry.coordinates_current) / seconds) } else { None }
+            },
+        };
+        speed_over_ground.push(speed);
+        headings.push(entry.heading.map(|heading| heading.rem_euclid(360.0)));
+        courses.push(entry.course.map(|course| course.rem_euclid(360.0)));
+        wind_speeds.push(entry.wind.map(|wind| wind.magnitude));
     }
 
-    // Make sure latitude is between -90° and 90°
-    while latitude < -90.0 {
-        latitude += 180.0;
+    let mut figure = plotly::Plot::new();
+    figure.add_trace(plotly::Scatter::new(timestamps.clone(), speed_over_ground)
+        .name("Speed over ground")
+        .mode(plotly::common::Mode::Lines));
+    // Heading and course as markers, since lines jump across the plot when they pass north
+    figure.add_trace(plotly::Scatter::new(timestamps.clone(), headings)
+        .name("Heading")
+        .mode(plotly::common::Mode::Markers)
+        .marker(plotly::common::Marker::new().size(3))
+        .y_axis("y2"));
+    figure.add_trace(plotly::Scatter::new(timestamps.clone(), courses)
+        .name("Course")
+        .mode(plotly::common::Mode::Markers)
+        .marker(plotly::common::Marker::new().size(3))
+        .y_axis("y2"));
+    figure.add_trace(plotly::Scatter::new(timestamps, wind_speeds)
+        .name("Wind speed")
+        .mode(plotly::common::Mode::Lines)
+        .y_axis("y3"));
+
+    // Three rows with a shared time axis, speed on top
+    let layout = plotly::Layout::new()
+        .grid(plotly::layout::LayoutGrid::new().rows(3).columns(1).pattern(plotly::layout::GridPattern::Coupled))
+        .height(900)
+        .y_axis(plotly::layout::Axis::new().title("Speed over ground [m/s]"))
+        .y_axis2(plotly::layout::Axis::new().title("Angle [°]").range(vec![0.0, 360.0]).dtick(90.0))
+        .y_axis3(plotly::layout::Axis::new().title("Wind speed [m/s]"))
+        .x_axis(plotly::layout::Axis::new().title("Time [UTC]"));
+    figure.set_layout(layout);
+    // Get configuration and make responsive for automatically sizing according to window size
+    let fig_config = figure.configuration().clone().responsive(true);
+    figure.set_configuration(fig_config);
+
+    return Ok(figure);
+}
+
+/// Percentiles of the travel time bands of departure_travel_times_to_csv() and plot_departure_travel_times()
+const DEPARTURE_BAND_PERCENTILES: [f64; 5] = [10.0, 25.0, 50.0, 75.0, 90.0];
+
+/// Travel time of one departure of a multi start time simulation, see get_departure_travel_times()
+struct DepartureTravelTime {
+    departure: UtcDateTime,
+    completed: bool,
+    /// \[days\]. Simulated time of the voyage, also if it was not completed
+    travel_time: f64,
+    distance: f64,
+    speed_mean: Option<f64>,
+    /// \[days\]. Travel times at DEPARTURE_BAND_PERCENTILES of the completed voyages departing within the band window, None if there are none
+    band: Option<[f64; 5]>,
+}
+
+/// Pairs the results of sim_waypoint_missions() with their departures and works out the travel time bands
+/// band_window: The travel time bands of each departure are from the completed voyages departing within half the band window before or after it
+fn get_departure_travel_times(simulation: &Simulation, results: &[SimulationResult], band_window: time::Duration) -> Result<Vec<DepartureTravelTime>, MarineSimError> {
+    if simulation.start_times.len() != results.len() {
+        return Err(MarineSimError::InvalidInput(format!("There are {} start times but {} simulation results", simulation.start_times.len(), results.len())));
     }
-    while latitude > 90.0 {
-        latitude -= 180.0;
+    if results.is_empty() {
+        return Err(MarineSimError::InvalidInput("No simulation results".to_string()));
     }
 
-    // Make return point
-    let return_point = geo::Point::new(longitude, latitude);
-    
-    // Return point
-    return Ok(return_point);
+    // Sorted by departure, start times do not have to be in order
+    let mut departures: Vec<(UtcDateTime, &SimulationResult)> = simulation.start_times.iter().copied().zip(results.iter()).collect();
+    departures.sort_by_key(|(departure, _)| *departure);
+
+    let mut rows: Vec<DepartureTravelTime> = Vec::with_capacity(departures.len());
+    for (departure, result) in &departures {
+        let mut window: Vec<f64> = departures.iter()
+            .filter(|(other, other_result)| other_result.is_completed() && (*other - *departure).abs() <= band_window / 2)
+            .map(|(_, other_result)| other_result.kpis.duration.as_seconds_f64() / 86400.0)
+            .collect();
+        window.sort_by(|a, b| a.total_cmp(b));
+        let band = if window.is_empty() { None } else { Some(DEPARTURE_BAND_PERCENTILES.map(|percentile| get_percentile(&window, percentile))) };
+        rows.push(DepartureTravelTime {
+            departure: *departure,
+            completed: result.is_completed(),
+            travel_time: result.kpis.duration.as_seconds_f64() / 86400.0,
+            distance: result.kpis.distance,
+            speed_mean: result.kpis.speed_mean,
+            band,
+        });
+    }
+    return Ok(rows);
 }
 
-/// Calculates the haversine distance between two points and returns the distance in uom::si::f64::Length
-pub fn haversine_distance_uom_units(p1: geo::Point, p2: geo::Point) -> uom::si::f64::Length {
-    // Calculate the haversine distance between two points
-    let dist: uom::si::f64::Length = uom::si::length::Length::new::<uom::si::length::meter>(geo::Haversine.distance(p1, p2));
-    return dist;
-}
+/// Saves the travel time of each departure of a multi start time simulation to a CSV file, with the travel time percentiles of the departures around it
+/// Columns: departure;completed;travel_time[days];dist[m];speed_mean[m/s];p10[days];p25[days];p50[days];p75[days];p90[days], sorted by departure
+/// Will overwrite any file with the same file name at csv_file_path
+/// results: The results of sim_waypoint_missions(), in the order of simulation.start_times
+/// band_window: The percentiles of each departure are from the completed voyages departing within half the band window before or after it, e.g. 30 days for a monthly view
+/// # Example:
+/// `let results = sim_waypoint_missions(&mut boat, &simulation)?;`
+/// `departure_travel_times_to_csv("departures.csv", &simulation, &results, time::Duration::days(30))?;`
+pub fn departure_travel_times_to_csv(csv_file_path: &str, simulation: &Simulation, results: &[SimulationResult], band_window: time::Duration) -> Result<(), MarineSimError> {
+    let rows = get_departure_travel_times(simulation, results, band_window)?;
 
-/// Get shortest distance between a line and a point on a sphere
-/// The line is the haversine line with endpoints p1 and p2
-/// Point p3 is the point that the shortest distance to the line between p1 and p2 will be calculated from.
-/// The distance is calculated with the spherical law of sines
-/// Returns the distance in meters
-pub fn get_min_point_to_great_circle_dist(p1: geo::Point, p2: geo::Point, p3: geo::Point) -> f64 {
-    // Quick check if already at end points
-    if p1 == p3 || p2 == p3 {
-        return 0.0;
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .from_path(csv_file_path)?;
+    wtr.write_record(["departure", "completed", "travel_time[days]", "dist[m]", "speed_mean[m/s]", "p10[days]", "p25[days]", "p50[days]", "p75[days]", "p90[days]"])?;
+    for row in rows {
+        let mut record: Vec<String> = vec![
+            format_timestamp(row.departure),
+            row.completed.to_string(),
+            row.travel_time.to_string(),
+            row.distance.to_string(),
+            row.speed_mean.map_or(String::new(), |speed| speed.to_string()),
+        ];
+        match row.band {
+            Some(band) => record.extend(band.iter().map(|value| value.to_string())),
+            None => record.extend(std::iter::repeat(String::new()).take(DEPARTURE_BAND_PERCENTILES.len())),
+        }
+        wtr.write_record(&record)?;
     }
-    // Using analytical solution from https://www.reddit.com/r/askmath/comments/1n6kc8d/whats_the_shortest_distance_d_from_a_point_on_a/
-    // Where p1 is U, P2 is V and P3 is W.
-    // Radius of sphere (Earth) is r
-    let r = geo::Haversine.radius();
-    // b is the distance from U to W (from p1 to p3)
-    let b = geo::Haversine.distance(p1, p3);
-    // Get the angle VUW (the angle between p2 and p3 as seen from p1), c_angle_radians is in [0, 2PI]
-    let c_angle_radians = (geo::Haversine.bearing(p1, p2) - geo::Haversine.bearing(p1, p3)).abs() * consts::PI/180.0;
 
-    // Calculate distance based on spherical law of sines https://en.wikipedia.org/wiki/Law_of_sines#Spherical_law_of_sines
-    // Note b/r gives an angle in radians that should always be in [0, PI] meaning that (b/r).sin() is always zero or a positive number and
-    // c_angle_radians.sin() can be a number in [-1,1] so we could be taking the arcsin of a negative number which results in a negative number
-    // Since the distance is always the same, regardless of the sign, we take the absolute value
-    let d = r*(c_angle_radians.sin() * (b/r).sin()).asin().abs();
-    return d;
+    // Flush and close the writer
+    wtr.flush()?;
+    return Ok(());
 }
 
-/// Converts a string into a f64 object
-/// cargo_string: The string to convert, must be in metric tons (1 metric ton = 1000 kg)
+/// Plot the travel time as a function of the departure date of a multi start time simulation with plotly, the headline figure of start time sweeps
+/// Each departure is a marker, voyages that were not completed are red crosses at their simulated time
+/// The median travel time of the departures around each departure is a line in 10-90 % and 25-75 % percentile bands, see departure_travel_times_to_csv()
+/// results: The results of sim_waypoint_missions(), in the order of simulation.start_times
+/// band_window: The percentiles of each departure are from the completed voyages departing within half the band window before or after it
+/// options: How the figure is shown and saved, see PlotOptions
 /// # Example:
-/// `let my_tons: f64 = string_to_tons("500.3");`
-pub fn string_to_tons(cargo_string: String) -> Option<f64> {
-    // Remove all spaces in string
-    let cargo_str: &str = (&cargo_string[..]).trim();
-    
-    // Check if the string is valid
-    if cargo_str.len() == 0 {
-        return None;
+/// `plot_departure_travel_times(&simulation, &results, time::Duration::days(30), &PlotOptions::headless("figures/departures.html"))?;`
+pub fn plot_departure_travel_times(simulation: &Simulation, results: &[SimulationResult], band_window: time::Duration, options: &PlotOptions) -> Result<(), MarineSimError> {
+    let figure = get_departure_travel_times_figure(simulation, results, band_window)?;
+
+    // Show and save the figure
+    return output_figure(&figure, options);
+}
+
+/// Makes the plotly figure of plot_departure_travel_times()
+fn get_departure_travel_times_figure(simulation: &Simulation, results: &[SimulationResult], band_window: time::Duration) -> Result<plotly::Plot, MarineSimError> {
+    let rows = get_departure_travel_times(simulation, results, band_window)?;
+    let departures: Vec<String> = rows.iter().map(|row| format_timestamp(row.departure)).collect();
+    let band = |i: usize| -> Vec<Option<f64>> { rows.iter().map(|row| row.band.map(|band| band[i])).collect() };
+
+    let mut figure = plotly::Plot::new();
+    // Bands, the lower line first so the upper line fills down to it
+    for (lower, upper, name, alpha) in [(0, 4, "10-90 %", 0.2), (1, 3, "25-75 %", 0.35)] {
+        figure.add_trace(plotly::Scatter::new(departures.clone(), band(lower))
+            .name(name)
+            .mode(plotly::common::Mode::Lines)
+            .line(plotly::common::Line::new().width(0.0))
+            .legend_group(name)
+            .show_legend(false));
+        figure.add_trace(plotly::Scatter::new(departures.clone(), band(upper))
+            .name(name)
+            .mode(plotly::common::Mode::Lines)
+            .line(plotly::common::Line::new().width(0.0))
+            .fill(plotly::common::Fill::ToNextY)
+            .fill_color(plotly::color::Rgba::new(0, 114, 178, alpha))
+            .legend_group(name));
+    }
+    figure.add_trace(plotly::Scatter::new(departures.clone(), band(2))
+        .name("Median")
+        .mode(plotly::common::Mode::Lines)
+        .line(plotly::common::Line::new().color(plotly::color::Rgb::new(0, 114, 178))));
+
+    // Each departure
+    let (completed, not_completed): (Vec<&DepartureTravelTime>, Vec<&DepartureTravelTime>) = rows.iter().partition(|row| row.completed);
+    figure.add_trace(plotly::Scatter::new(completed.iter().map(|row| format_timestamp(row.departure)).collect(), completed.iter().map(|row| row.travel_time).collect())
+        .name("Completed")
+        .mode(plotly::common::Mode::Markers)
+        .marker(plotly::common::Marker::new().color(plotly::color::Rgb::new(0, 0, 0)).size(5)));
+    if !not_completed.is_empty() {
+        figure.add_trace(plotly::Scatter::new(not_completed.iter().map(|row| format_timestamp(row.departure)).collect(), not_completed.iter().map(|row| row.travel_time).collect())
+            .name("Not completed")
+            .mode(plotly::common::Mode::Markers)
+            .marker(plotly::common::Marker::new().color(plotly::color::Rgb::new(213, 94, 0)).symbol(plotly::common::MarkerSymbol::X).size(7)));
     }
 
-    // Parse the cargo as f64
-    let cargo: f64 = cargo_str.parse::<f64>().expect("Invalid cargo");
+    let layout = plotly::Layout::new()
+        .x_axis(plotly::layout::Axis::new().title("Departure [UTC]"))
+        .y_axis(plotly::layout::Axis::new().title("Travel time [days]"));
+    figure.set_layout(layout);
+    // Get configuration and make responsive for automatically sizing according to window size
+    let fig_config = figure.configuration().clone().responsive(true);
+    figure.set_configuration(fig_config);
 
-    // return cargo
-    return Some(cargo);
+    return Ok(figure);
 }
 
-/// Returns the average and standard deviation of all values in a vector of f64 objects
-/// data_vec: The vector of f64 objects
-/// only_finite_numbers: True if invalid entries should be ignored. Example vector [1, 3, inf, 5] if true then the mean will be 3, if false then the mean will be inf.
+/// Plot a polar speed plot CSV file as a polar chart with plotly, one line per true wind speed with the boat speed against the true wind angle
+/// Reads the files of make_polar_speed_plot_csv() and PolarDiagram.from_csv(), the first row is "TWA\\TWS" followed by the wind speeds and each following row a wind angle followed by the boat speeds
+/// Empty cells are gaps in the lines since there was no data for them, and wind speeds without any boat speeds are left out. The units are those of the file
+/// options: How the figure is shown and saved, see PlotOptions
 /// # Example:
-/// `let (my_mean, my_std) = get_vec_f64_mean_and_std(&my_vec);`
-pub fn get_vec_f64_mean_and_std(data_vec: &Vec<f64>, only_finite_numbers: bool) -> Result<(f64, f64), io::Error> {
-    // Validate that the input vector has at least 1 value
-    if data_vec.is_empty() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "vector is empty, cannot calculate mean and standard deviation"));
-    }
-    
-    // Calculate the mean of the vector
-    let mut total: f64 = 0.0;
-    let mut num_points_used: u64 = 0;
-
-    // loop through vector, add all values to total
-    for value in data_vec {
-        if only_finite_numbers {
-            if value.is_finite() {
-                total = total + *value;
-                num_points_used += 1;
-            }
+/// `plot_polar_speed_plot_csv("polars/boat_mean.csv", &PlotOptions::headless("polars/boat_mean.png"))?;`
+pub fn plot_polar_speed_plot_csv(csv_file_path: &str, options: &PlotOptions) -> Result<(), MarineSimError> {
+    let figure = get_polar_speed_plot_figure(csv_file_path)?;
+
+    // Show and save the figure
+    return output_figure(&figure, options);
+}
+
+/// Makes the plotly figure of plot_polar_speed_plot_csv()
+fn get_polar_speed_plot_figure(csv_file_path: &str) -> Result<plotly::Plot, MarineSimError> {
+    // Read the CSV file
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(csv_file_path)?;
+
+    // Wind speeds from the header, skip the first column
+    let wind_speeds: Vec<String> = csv_reader.headers()?.iter().skip(1).map(|cell| cell.trim().to_string()).collect();
+
+    // Wind angles and the boat speeds of each wind speed, None for empty cells
+    let mut wind_angles: Vec<f64> = Vec::new();
+    let mut boat_speeds: Vec<Vec<Option<f64>>> = vec![Vec::new(); wind_speeds.len()];
+    for result in csv_reader.records() {
+        let record = result?;
+        let parse_cell = |cell: &str| -> Result<Option<f64>, MarineSimError> {
+            return match cell.trim() {
+                "" => Ok(None),
+                c => match c.parse::<f64>() {
+                    Ok(v) => Ok(Some(v)),
+                    Err(_) => Err(MarineSimError::CsvParse { file: csv_file_path.to_string(), message: format!("Invalid value {:?} in polar speed plot", cell) }),
+                },
+            };
+        };
+        match parse_cell(record.get(0).unwrap_or(""))? {
+            Some(angle) => wind_angles.push(angle),
+            None => continue,
         }
-        else {
-            total = total + *value;
-            num_points_used += 1;
+        for (i, speeds) in boat_speeds.iter_mut().enumerate() {
+            speeds.push(parse_cell(record.get(i + 1).unwrap_or(""))?);
         }
     }
-    // Find mean
-    let vec_mean: f64 = total / (num_points_used as f64);
-
-    // Calculate the standard deviation of the speed vector
-    let mut variance: f64 = 0.0;
+    if wind_angles.is_empty() {
+        return Err(MarineSimError::CsvParse { file: csv_file_path.to_string(), message: "The polar speed plot has no wind angles".to_string() });
+    }
 
-    // loop through vector, add all values to variance, then divide by number of values -1 to create variance
-    for value in data_vec {
-        if only_finite_numbers {
-            if value.is_finite() {
-            variance = variance + (value - vec_mean).powi(2);
-            }
-        }
-        else {
-            variance = variance + (value - vec_mean).powi(2);
+    // One trace per wind speed with data
+    let mut figure = plotly::Plot::new();
+    for (wind_speed, speeds) in wind_speeds.iter().zip(boat_speeds) {
+        if speeds.iter().all(|speed| speed.is_none()) {
+            continue;
         }
+        figure.add_trace(plotly::ScatterPolar::new(wind_angles.clone(), speeds)
+            .name(format!("TWS {}", wind_speed))
+            .mode(plotly::common::Mode::LinesMarkers)
+            .connect_gaps(false));
     }
-    variance = variance / ((num_points_used - 1) as f64);
 
-    // Find standard deviation from variance
-    let vec_std: f64 = variance.sqrt();
+    let layout = plotly::Layout::new()
+        .title(format!("Boat speed through water against true wind angle (TWA) and speed (TWS), {}", csv_file_path).as_str())
+        .show_legend(true);
+    figure.set_layout(layout);
+    // Get configuration and make responsive for automatically sizing according to window size
+    let fig_config = figure.configuration().clone().responsive(true);
+    figure.set_configuration(fig_config);
+
+    return Ok(figure);
+}
+
+/// Length of the wind arrows in animate_ship_log() in \[m\] per \[m/s\] of wind speed
+const WIND_ARROW_METERS_PER_MPS: f64 = 5000.0;
+
+/// Settings of the voyage animation of animate_ship_log()
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationOptions {
+    /// If true an arrow shows the wind at the boat in each frame, pointing where the wind blows to. Entries without wind have no arrow
+    pub show_wind: bool,
+    /// Most frames in the animation. Long ship logs are thinned out evenly, the first and last entries are always frames
+    pub max_frames: usize,
+    /// How long each frame is shown when playing in milliseconds
+    pub frame_duration_ms: u64,
+}
 
-    // Return the mean and standard deviation
-    return Ok((vec_mean, vec_std));
+impl AnimationOptions {
+    /// 500 frames of 100 ms each with wind arrows
+    pub fn new() -> AnimationOptions {
+        return AnimationOptions {
+            show_wind: true,
+            max_frames: 500,
+            frame_duration_ms: 100,
+        };
+    }
 }
 
+impl Default for AnimationOptions {
+    fn default() -> AnimationOptions {
+        return AnimationOptions::new();
+    }
+}
 
-/// Returns the average and standard deviation of a vector
+/// Animate a voyage on the map with a time slider and play and pause buttons, since static maps of months long voyages are hard to read
+/// The whole track is shown faintly under the boat, which moves along it as the slider advances
+/// The animation is an HTML file, plot_options.file_path must end with ".html" or be None. If it is None and plot_options.show is true the animation is saved in the temporary folder of the system
+/// ship_logs_file_path: Ship log CSV file, see ship_logs_to_csv()
+/// route_plan_file_path: The route plan shown with its tacking corridors, None to only show the voyage
 /// # Example:
-/// `let (my_mean, my_std) = get_time_mean_and_std(&my_vec);`
-pub fn get_duration_mean_and_std(duration_vec: &Vec<time::Duration>) ->
-    Result<(time::Duration, time::Duration), io::Error> {
-        println!("DURATION EVALUATION THINGY: DURATION VEC: {:?}", duration_vec);
-        println!("DURATION VEC IS EMPTY?: {:?}", duration_vec.is_empty());
-    // Validate that the vector has at least 1 value
-    if duration_vec.is_empty() {
-        println!("DURATION VEC IS EMPTY? DOUBLE TAKE: {:?}", duration_vec.is_empty());
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Duration vector is empty, cannot calculate mean and standard deviation"));
+/// `animate_ship_log("ship_log.csv", Some("route_plan.csv"), &PlotOptions::headless("voyage.html"), &AnimationOptions::new())?;`
+pub fn animate_ship_log(ship_logs_file_path: &str, route_plan_file_path: Option<&str>, plot_options: &PlotOptions, animation_options: &AnimationOptions) -> Result<(), MarineSimError> {
+    let file_path = match &plot_options.file_path {
+        Some(file_path) if get_figure_file_extension(file_path)? == "html" => file_path.clone(),
+        Some(file_path) => return Err(MarineSimError::InvalidInput(format!("Animations can only be saved as .html, not {}", file_path))),
+        None if plot_options.show => std::env::temp_dir().join("marine_vessel_simulator_animation.html").to_string_lossy().to_string(),
+        None => return Ok(()),
+    };
+
+    let ship_log = csv_to_ship_log(ship_logs_file_path)?;
+    let html = get_ship_log_animation_html(&ship_log, route_plan_file_path, &plot_options.map_style, animation_options)?;
+    std::fs::write(&file_path, html)?;
+
+    // Open plot
+    if plot_options.show {
+        open_in_browser(&file_path)?;
     }
+    return Ok(());
+}
 
-    // Calculate the mean of the vector
-    let mut tot_duration = time::Duration::new(0, 0);
+/// Makes the HTML page of animate_ship_log()
+/// The map is made with plotly and the frames, slider and buttons are added to it in the page, with plotly.js from its CDN as in the HTML files of plotly
+fn get_ship_log_animation_html(ship_log: &[ShipLogEntry], route_plan_file_path: Option<&str>, map_style: &MapStyle, animation_options: &AnimationOptions) -> Result<String, MarineSimError> {
+    if ship_log.is_empty() {
+        return Err(MarineSimError::InvalidInput("The ship log is empty".to_string()));
+    }
+    if animation_options.max_frames < 2 {
+        return Err(MarineSimError::InvalidInput("The animation needs at least 2 frames".to_string()));
+    }
 
-    // loop through vector, add all values to the total
-    for duration in duration_vec {
-        tot_duration = tot_duration + *duration;
+    let mut figure = get_map_figure(map_style);
+    // Number of traces before the boat, the frames update the boat and wind traces by their index
+    let mut trace_count: usize = 0;
+    if let Some(route_plan_file_path) = route_plan_file_path {
+        trace_count += add_route_plan_traces(&mut figure, route_plan_file_path, map_style)?;
     }
-    // Find mean
-    let duration_mean = tot_duration.checked_div(duration_vec.len() as i32).unwrap();
 
-    // Calculate the standard deviation of the vector
-    let mut variance: i64 = 0;
+    // The whole track
+    let latitudes: Vec<f64> = ship_log.iter().map(|entry| entry.coordinates_current.y()).collect();
+    let longitudes: Vec<f64> = ship_log.iter().map(|entry| entry.coordinates_current.x()).collect();
+    figure.add_trace(plotly::ScatterGeo::new(latitudes, unwrap_longitudes(&longitudes))
+        .name("Track")
+        .mode(plotly::common::Mode::Lines)
+        .line(plotly::common::Line::new().color(plotly::color::Rgb::new(102, 102, 102)).width(1.0)));
+    trace_count += 1;
+
+    // The boat and the wind at the first entry, moved by the frames
+    let boat_trace_index = trace_count;
+    let first = &ship_log[0];
+    figure.add_trace(plotly::ScatterGeo::new(vec![first.coordinates_current.y()], vec![first.coordinates_current.x()])
+        .name("Boat")
+        .mode(plotly::common::Mode::Markers)
+        .marker(plotly::common::Marker::new().color(plotly::color::Rgb::new(213, 94, 0)).size(12)));
+    let (wind_latitudes, wind_longitudes) = get_wind_arrow_coordinates(first);
+    figure.add_trace(plotly::ScatterGeo::new(wind_latitudes, wind_longitudes)
+        .name("Wind")
+        .mode(plotly::common::Mode::Lines)
+        .line(plotly::common::Line::new().color(plotly::color::Rgb::new(0, 114, 178)).width(3.0))
+        .visible(if animation_options.show_wind { plotly::common::Visible::True } else { plotly::common::Visible::False }));
+
+    // Evenly spread frames, always with the last entry
+    let step = ship_log.len().div_ceil(animation_options.max_frames - 1).max(1);
+    let mut frame_indices: Vec<usize> = (0..ship_log.len()).step_by(step).collect();
+    if frame_indices.last() != Some(&(ship_log.len() - 1)) {
+        frame_indices.push(ship_log.len() - 1);
+    }
 
-    // loop through vector, add all values to variance, then divide by number of values -1 to create variance
-    for duration in duration_vec {
-        let multiplier = duration.checked_sub(duration_mean).expect("Could not subtract value from time::Duration. Maybe an overflow occurred?").whole_seconds();
-        variance = variance + multiplier*multiplier;
+    let mut frames: Vec<String> = Vec::with_capacity(frame_indices.len());
+    let mut slider_steps: Vec<String> = Vec::with_capacity(frame_indices.len());
+    for (frame, i) in frame_indices.iter().enumerate() {
+        let entry = &ship_log[*i];
+        let (wind_latitudes, wind_longitudes) = get_wind_arrow_coordinates(entry);
+        frames.push(format!("{{\"name\":\"{}\",\"traces\":[{},{}],\"data\":[{{\"lat\":[{}],\"lon\":[{}]}},{{\"lat\":{},\"lon\":{}}}]}}",
+            frame, boat_trace_index, boat_trace_index + 1,
+            entry.coordinates_current.y(), entry.coordinates_current.x(),
+            get_json_number_array(&wind_latitudes), get_json_number_array(&wind_longitudes)));
+        let label = format!("{}-{:02}-{:02} {:02}:{:02}", entry.timestamp.year(), entry.timestamp.month() as u8, entry.timestamp.day(), entry.timestamp.hour(), entry.timestamp.minute());
+        slider_steps.push(format!("{{\"label\":\"{}\",\"method\":\"animate\",\"args\":[[\"{}\"],{{\"mode\":\"immediate\",\"frame\":{{\"duration\":0,\"redraw\":true}},\"transition\":{{\"duration\":0}}}}]}}", label, frame));
     }
-    let variance_f64 = (variance as f64) / ((duration_vec.len() - 1) as f64);
 
-    // Find standard deviation from variance
-    let duration_std = time::Duration::new((variance_f64).sqrt() as i64, 0);
+    // Geo maps are only updated with redraw, the slider follows the frames while playing
+    let play = format!("{{\"label\":\"Play\",\"method\":\"animate\",\"args\":[null,{{\"fromcurrent\":true,\"frame\":{{\"duration\":{},\"redraw\":true}},\"transition\":{{\"duration\":0}}}}]}}", animation_options.frame_duration_ms);
+    let pause = "{\"label\":\"Pause\",\"method\":\"animate\",\"args\":[[null],{\"mode\":\"immediate\",\"frame\":{\"duration\":0,\"redraw\":true},\"transition\":{\"duration\":0}}]}";
+    let update_menus = format!("[{{\"type\":\"buttons\",\"direction\":\"left\",\"showactive\":false,\"x\":0.05,\"y\":0.05,\"xanchor\":\"right\",\"yanchor\":\"top\",\"buttons\":[{},{}]}}]", play, pause);
+    let sliders = format!("[{{\"active\":0,\"x\":0.05,\"y\":0.05,\"len\":0.9,\"yanchor\":\"top\",\"currentvalue\":{{\"prefix\":\"Time: \"}},\"steps\":[{}]}}]", slider_steps.join(","));
+
+    return Ok(format!(r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8" />
+    <title>Voyage animation</title>
+    <script src="https://cdn.plot.ly/plotly-2.35.2.min.js"></script>
+</head>
+<body>
+    <div id="plotly-animation" style="width:100%;height:95vh;"></div>
+    <script>
+        var figure = {};
+        figure.layout.updatemenus = {};
+        figure.layout.sliders = {};
+        figure.frames = [{}];
+        Plotly.newPlot("plotly-animation", figure);
+    </script>
+</body>
+</html>
+"#, figure.to_json(), update_menus, sliders, frames.join(",\n")));
+}
+
+/// Returns the latitudes and longitudes of the wind arrow of a ship log entry, from the boat to where the wind blows to
+/// The arrow head is two short lines at the end, separated from the shaft by a gap (None) so it is one trace. Empty if the entry has no wind
+fn get_wind_arrow_coordinates(entry: &ShipLogEntry) -> (Vec<Option<f64>>, Vec<Option<f64>>) {
+    let wind = match entry.wind {
+        Some(wind) if wind.magnitude > 0.0 => wind,
+        _ => return (Vec::new(), Vec::new()),
+    };
+    // The wind angle is where the wind comes from
+    let direction = wind.angle + 180.0;
+    let length = wind.magnitude * WIND_ARROW_METERS_PER_MPS;
+    let start = entry.coordinates_current;
+    let end = Earth.destination(start, direction, length);
+    let head_port = Earth.destination(end, direction + 150.0, length * 0.3);
+    let head_starboard = Earth.destination(end, direction - 150.0, length * 0.3);
+    let points = [Some(start), Some(end), None, Some(head_port), Some(end), Some(head_starboard)];
+    return (points.iter().map(|point| point.map(|p| p.y())).collect(), points.iter().map(|point| point.map(|p| p.x())).collect());
+}
 
-    // Return the mean and standard deviation
-    return Ok((duration_mean, duration_std));
+/// Writes numbers as a JSON array, None as null
+fn get_json_number_array(values: &[Option<f64>]) -> String {
+    let values: Vec<String> = values.iter().map(|value| match value {
+        Some(value) if value.is_finite() => value.to_string(),
+        _ => "null".to_string(),
+    }).collect();
+    return format!("[{}]", values.join(","));
 }
 
+/// Opens a file with the default program of the system, e.g. an HTML file in the default browser
+fn open_in_browser(file_path: &str) -> Result<(), MarineSimError> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", "", file_path]);
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = std::process::Command::new("open");
+        command.arg(file_path);
+        command
+    };
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = {
+        let mut command = std::process::Command::new("xdg-open");
+        command.arg(file_path);
+        command
+    };
+    command.spawn()?;
+    return Ok(());
+}
 
-/// Loads route plan from a CSV file
-/// Returns a vector of SailingLeg objects where each entry is a a leg of the trip
-/// The CSV file is expected to have the following columns in order but the header names are not important:
-/// Leg number;start_latitude;start_longitude;end_latitude;end_longitude;tacking_width\[meters\]
-/// The delimiter is a semicolon.
-/// file_path: Path to the CSV file
-/// # Example:
-/// `let file_path: &str = "my_route_plan.csv";`
-pub fn load_route_plan(file_path: &str) -> Result<Vec<SailingLeg>, io::Error> {
+/// Makes the plotly map of the ship logs and the route plan with its tacking corridors, used by visualize_ship_logs_and_route() and visualize_results_folder()
+fn get_ship_logs_and_route_figure(ship_logs_file_path: &str, route_plan_file_path: &str, map_style: &MapStyle) -> Result<plotly::Plot, MarineSimError> {
+    let (y_vec, x_vec) = read_ship_log_csv_coordinates(ship_logs_file_path)?;
+
+    // Setup trace of ship logs, with the longitudes unwrapped so the line does not go across the whole map at the ±180° meridian
+    let trace = plotly::ScatterGeo::new(y_vec, unwrap_longitudes(&x_vec))
+                    .name("Ship logs")
+                    .mode(plotly::common::Mode::LinesMarkersText)
+                    .show_legend(true);  // ScatterGeo::new(latitudes, longitudes).name("Ship Logs").marker_color("blue"));
+
+    // Create a plotly figure with the coordinates
+    let mut figure = get_map_figure(map_style);
+    // Add trace
+    figure.add_trace(trace);
+
+    // Add the route plan and its tacking corridors
+    add_route_plan_traces(&mut figure, route_plan_file_path, map_style)?;
+
+    // TODO: Add vector at each point that shows wind direction at that point at that points time?????
+
+    return Ok(figure);
+}
+
+/// Reads the current coordinates of a ship log CSV file, see ship_logs_to_csv()
+/// Returns the latitudes and longitudes. Lines that can not be read are skipped with a message
+fn read_ship_log_csv_coordinates(ship_logs_file_path: &str) -> Result<(Vec<f64>, Vec<f64>), MarineSimError> {
     // Read the CSV file
     let mut csv_reader = csv::ReaderBuilder::new()
         .delimiter(b';')
         .has_headers(true)
-        .from_path(file_path)
-        .expect(format!("Failed to open route plan file: {}", file_path).as_str());
+        .from_path(ship_logs_file_path)?;
 
-    // Initialize a vector to store the route plan
-    let mut route_plan: Vec<SailingLeg> = Vec::new();
+    // Init vectors for coordinates
+    let mut y_vec: Vec<f64> = Vec::new();
+    let mut x_vec: Vec<f64> = Vec::new();
 
-    // Iterate through each line of the CSV file and add the coordinates to the route plan
+    // Iterate through each line of the CSV file to draw the values
     for result in csv_reader.records() {
         match result {
-            Ok(leg) => {
-                // Get the SailingLeg data from the CSV file
-                // First column is the leg number, so we skip it
-                // Start_coord
-                let start_lat = leg.get(1).expect("Start latitude missing from route plan").to_string();
-                let start_long = leg.get(2).expect("Start longitude missing from route plan").to_string();
-                // End_coord
-                let end_lat = leg.get(3).expect("End latitude missing from route plan").to_string();
-                let end_long = leg.get(4).expect("End longitude missing from route plan").to_string();
-                // Tacking width
-                let tacking_width = leg.get(5).expect("Tacking width missing from route plan").to_string();
-                // Get minimum proximity
-                let min_prox = leg.get(6).expect("Minimum proximity missing from route plan").to_string();
-
-                // Make a SailingLeg object
-                let temp_sailing_leg: SailingLeg = SailingLeg {
-                    p1: string_to_point(format!("{},{}", start_lat, start_long)).expect("Invalid start coordinates in route plan"),
-                    p2: string_to_point(format!("{},{}", end_lat, end_long)).expect("Invalid end coordinates in route plan"),
-                    tacking_width: tacking_width.parse::<f64>().expect("Invalid tacking width"),
-                    min_proximity: min_prox.parse::<f64>().expect("Invalid minimum proximity"),
+            Ok(log_entry) => {
+                // Get current coordinates
+                let coordinates_current = match log_entry.get(2) {
+                    Some(c) => string_to_point(c.to_string())?,
+                    None => return Err(MarineSimError::CsvParse { file: ship_logs_file_path.to_string(), message: "No current coordinate found".to_string() }),
                 };
 
-                // Add the SailingLeg object to the route plan
-                route_plan.push(temp_sailing_leg);
+                // Add coordinates to vectors
+                x_vec.push(coordinates_current.x());
+                y_vec.push(coordinates_current.y());
             }
             Err(err) => {
-                eprintln!("Error reading leg: {}", err);
+                tracing::warn!("Error reading log_entry: {}", err);
             }
-        }
-    }
+        } // End match
+    } // End for loop
 
-    // Return the route plan
-    return Ok(route_plan);
+    return Ok((y_vec, x_vec));
 }
 
-/// Function that writes the ship logs to a CSV file with the following columns:
-/// timestamp;coordinates_initial;coordinates_current;coordinates_final;cargo_on_board
-/// Note that the coordinates are in the format of ISO 6709 using decimal places with a comma between latitude and longitude. "latitude,longitude" (e.g., "52.5200,13.4050")
-/// The cargo is in metric tons (1 metric ton = 1000 kg)
-/// csv_file_path: Path to the CSV file
-/// ship_logs: The ship logs from the vessel
-/// Note: The csv file delimiter is a semicolon
-pub fn ship_logs_to_csv(csv_file_path: &str, ship_logs: &Vec<ShipLogEntry>) -> Result<(), io::Error> {
-    // Create a CSV writer with a semicolon delimiter
-    // let mut wtr = csv::WriterBuilder::new().delimiter(b';').from_path(csv_file_path)?;
-    let mut wtr = csv::WriterBuilder::new()
-        .delimiter(b';')
-        .has_headers(true)
-        .from_path(csv_file_path)?;
-
-    // Write the header
-    wtr.write_record(&["timestamp", "coordinates_initial", "coordinates_current", "coordinates_final", "cargo_on_board[ton]", "velocity[m/s]", "course[°]", "heading", "true_bearing[°]", "draught[m]", "navigation_status"])?;
+/// Makes an empty plotly figure with a map of the earth in the map style, the base of all the maps of the simulator
+fn get_map_figure(map_style: &MapStyle) -> plotly::Plot {
+    let rgb = |(r, g, b): (u8, u8, u8)| plotly::color::Rgb::new(r, g, b);
+    let projection = match map_style.projection {
+        MapProjection::Orthographic => plotly::layout::ProjectionType::Orthographic,
+        MapProjection::Mercator => plotly::layout::ProjectionType::Mercator,
+        MapProjection::NaturalEarth => plotly::layout::ProjectionType::NaturalEarth,
+    };
+    let (top, left, bottom, right) = map_style.margins;
 
-    // Write the ship log entries
-    for entry in ship_logs.iter() {
-        let mut _timestamp_string: String = String::new();  //Underscored to avoid unused variable warning since it is used in wtr.write_record
-        _timestamp_string.push_str(entry.timestamp.year().to_string().as_str());
-        _timestamp_string.push_str("-");
-        // If month is 1 digit, add a leading zero
-        if (entry.timestamp.month() as i16) < 10 {
-            _timestamp_string.push_str("0");
-        }
-        _timestamp_string.push_str((entry.timestamp.month() as i8).to_string().as_str());
-        _timestamp_string.push_str("-");
-        // If day is 1 digit, add a leading zero
-        if entry.timestamp.day() < 10 {
-            _timestamp_string.push_str("0");
-        }
-        _timestamp_string.push_str(entry.timestamp.day().to_string().as_str());
-        _timestamp_string.push_str(" ");
-        // If hour is 1 digit, add a leading zero
-        if entry.timestamp.hour() < 10 {
-            _timestamp_string.push_str("0");
-        }
-        _timestamp_string.push_str(entry.timestamp.hour().to_string().as_str());
-        _timestamp_string.push_str(":");
-        // If minute is 1 digit, add a leading zero
-        if entry.timestamp.minute() < 10 {
-            _timestamp_string.push_str("0");
-        }
-        _timestamp_string.push_str(entry.timestamp.minute().to_string().as_str());
-        _timestamp_string.push_str(":");
-        // If second is 1 digit, add a leading zero
-        if entry.timestamp.second() < 10 {
-            _timestamp_string.push_str("0");
-        }
-        _timestamp_string.push_str(entry.timestamp.second().to_string().as_str());
-
-        // If cargo is None, set to empty string
-        let cargo = match entry.cargo_on_board {
-            Some(c) => c.get::<uom::si::mass::ton>().to_string(),
-            None => String::from(""),
-        };
+    // Set layout as instructed by andrei-ng https://github.com/plotly/plotly.rs/pull/301
+    let mut layout = plotly::Layout::new()
+        .drag_mode(plotly::layout::DragMode::Zoom)
+        .margin(plotly::layout::Margin::new().top(top).left(left).bottom(bottom).right(right))
+        .auto_size(true)
+        .geo(
+            plotly::layout::LayoutGeo::new()
+                .showocean(true)
+                .showlakes(true)
+                .showcountries(map_style.show_countries)
+                .showland(true)
+                .oceancolor(rgb(map_style.ocean_color))
+                .lakecolor(rgb(map_style.lake_color))
+                .landcolor(rgb(map_style.land_color))
+                .lataxis(
+                    plotly::layout::Axis::new()
+                        .show_grid(map_style.show_grid)
+                        .grid_color(rgb(map_style.grid_color)),
+                )
+                .lonaxis(
+                    plotly::layout::Axis::new()
+                        .show_grid(map_style.show_grid)
+                        .grid_color(rgb(map_style.grid_color)),
+                )
+                .projection(
+                    plotly::layout::Projection::new().projection_type(projection),
+                ),
+        );
+    if map_style.dark_mode {
+        layout = layout.template(&*plotly::layout::themes::PLOTLY_DARK);
+    }
 
-        // If velocity is None, set to empty string
-        let velocity = match entry.velocity {
-            Some(v) => v.magnitude.to_string(),
-            None => String::from(""),
-        };
+    // Create a plotly figure
+    let mut figure = plotly::Plot::new();
+    // Set layout
+    figure.set_layout(layout);
+    // Get configuration and make responsive for automatically sizing according to window size
+    let fig_config = figure.configuration().clone().responsive(true).fill_frame(true);
+    // Set config
+    figure.set_configuration(fig_config);
+    return figure;
+}
 
-        // If course is None, set to empty string
-        let course = match entry.course {
-            Some(c) => c.to_string(),
-            None => String::from(""),
-        };
+/// Adds the route plan and the tacking corridor of each leg to a map figure
+/// The corridor of a leg is a filled polygon half the tacking width to each side of the leg, so changes in width and sharp turns between legs are drawn as they are simulated
+/// Legs without a tacking width have no corridor. Returns the number of traces added
+fn add_route_plan_traces(figure: &mut plotly::Plot, route_plan_file_path: &str, map_style: &MapStyle) -> Result<usize, MarineSimError> {
+    let route_plan = load_route_plan(route_plan_file_path)?;
+    let last_leg = match route_plan.last() {
+        Some(leg) => leg,
+        None => return Err(MarineSimError::CsvParse { file: route_plan_file_path.to_string(), message: "Route plan has no legs".to_string() }),
+    };
+    let mut trace_count: usize = 0;
 
-        // If heading is None, set to empty string
-        let heading = match entry.heading {
-            Some(h) => h.to_string(),
-            None => String::from(""),
-        };
+    // Tacking corridors first so the route plan is drawn on top of them
+    let (r, g, b) = map_style.corridor_color;
+    for (i, leg) in route_plan.iter().enumerate() {
+        if !(leg.tacking_width > 0.0) {
+            continue;
+        }
+        // Corners of the corridor, port side forwards and starboard side back to close the polygon
+        let bearing_start = Earth.bearing(leg.p1, leg.p2);
+        let bearing_end = (Earth.bearing(leg.p2, leg.p1) + 180.0).rem_euclid(360.0);
+        let half_width = leg.tacking_width / 2.0;
+        let corners = [
+            Earth.destination(leg.p1, bearing_start - 90.0, half_width),
+            Earth.destination(leg.p2, bearing_end - 90.0, half_width),
+            Earth.destination(leg.p2, bearing_end + 90.0, half_width),
+            Earth.destination(leg.p1, bearing_start + 90.0, half_width),
+            Earth.destination(leg.p1, bearing_start - 90.0, half_width),
+        ];
+        // Unwrapped so a corridor across the ±180° meridian is not filled around the rest of the globe
+        let corner_longitudes = unwrap_longitudes(&corners.iter().map(|corner| corner.x()).collect::<Vec<f64>>());
+        figure.add_trace(plotly::ScatterGeo::new(corners.iter().map(|corner| corner.y()).collect(), corner_longitudes)
+            .name("Tacking corridor")
+            .legend_group("Tacking corridor")
+            .show_legend(trace_count == 0)
+            .mode(plotly::common::Mode::Lines)
+            .fill(plotly::common::Fill::ToSelf)
+            .fill_color(plotly::color::Rgba::new(r, g, b, map_style.corridor_opacity))
+            .line(plotly::common::Line::new().color(plotly::color::Rgba::new(r, g, b, (map_style.corridor_opacity * 2.0).min(1.0))).width(1.0))
+            .hover_text(format!("Leg {}, tacking width {} m", i + 1, leg.tacking_width)));
+        trace_count += 1;
+    }
 
-        // If true_bearing is None, set to empty string
-        let true_bearing = match entry.true_bearing {
-            Some(tb) => tb.to_string(),
-            None => String::from(""),
-        };
+    // Waypoints of the route plan
+    // TODO: with label to plot
+    let mut latitudes: Vec<f64> = route_plan.iter().map(|leg| leg.p1.y()).collect();
+    let mut longitudes: Vec<f64> = route_plan.iter().map(|leg| leg.p1.x()).collect();
+    latitudes.push(last_leg.p2.y());
+    longitudes.push(last_leg.p2.x());
+    figure.add_trace(plotly::ScatterGeo::new(latitudes, unwrap_longitudes(&longitudes))
+        .mode(plotly::common::Mode::LinesMarkersText)
+        .name("Route Plan"));
+    trace_count += 1;
 
-        // If draught is None, set to empty string
-        let draft = match entry.draft {
-            Some(d) => d.to_string(),
-            None => String::from(""),
-        };
+    return Ok(trace_count);
+}
 
-        // If navigation_status is None, set to empty string
-        let navigation_status = match &entry.navigation_status {
-            Some(ns) => (*ns as u64).to_string(),
-            None => String::from(""),
-        };
 
-        // Write the record
-        wtr.write_record(&[
-            _timestamp_string, //entry.timestamp.to_string(), // timestamp_to_string(entry.timestamp),
-            format!("{},{}", entry.coordinates_initial.y(), entry.coordinates_initial.x()),
-            format!("{},{}", entry.coordinates_current.y(), entry.coordinates_current.x()),
-            format!("{},{}", entry.coordinates_final.y(), entry.coordinates_final.x()),
-            cargo,            // entry.cargo_on_board.unwrap().get::<uom::si::mass::ton>().to_string(),
-            velocity,
-            course,
-            heading,
-            true_bearing,
-            draft,
-            navigation_status,
-        ])?;
+/// File name ending of ship logs in a results folder, see visualize_results_folder()
+pub const SHIP_LOG_FILE_SUFFIX: &str = "_ship_log.csv";
+/// File name ending of route plans in a results folder, see visualize_results_folder()
+pub const ROUTE_PLAN_FILE_SUFFIX: &str = "_route_plan.csv";
+/// Route plan shared by all ship logs in a results folder that have no route plan of their own, see visualize_results_folder()
+pub const SHARED_ROUTE_PLAN_FILE_NAME: &str = "route_plan.csv";
+/// Manifest that pairs ship logs with route plans in a results folder, see visualize_results_folder()
+pub const RESULTS_MANIFEST_FILE_NAME: &str = "manifest.csv";
+
+/// Finds the ship logs in a results folder and pairs each one with its route plan
+/// If the folder has a manifest.csv, it is used with the columns name;ship_log;route_plan where the paths are relative to the results folder
+/// Otherwise each <name>_ship_log.csv is paired with <name>_route_plan.csv, or with route_plan.csv if there is no route plan with the same name
+/// Ship logs without a route plan are skipped with a message
+/// Returns (name, ship log file path, route plan file path) for each pair, sorted by name
+pub fn find_results_in_folder(results_folder: &str) -> Result<Vec<(String, String, String)>, MarineSimError> {
+    let folder = std::path::Path::new(results_folder);
+    let mut results: Vec<(String, String, String)> = Vec::new();
+
+    // Use the manifest if there is one
+    let manifest_path = folder.join(RESULTS_MANIFEST_FILE_NAME);
+    if manifest_path.is_file() {
+        let mut csv_reader = csv::ReaderBuilder::new()
+            .delimiter(b';')
+            .has_headers(true)
+            .from_path(&manifest_path)?;
+        for (i, record) in csv_reader.records().enumerate() {
+            let record = record?;
+            match (record.get(0), record.get(1), record.get(2)) {
+                (Some(name), Some(ship_log), Some(route_plan)) => results.push((
+                    name.trim().to_string(),
+                    folder.join(ship_log.trim()).to_string_lossy().to_string(),
+                    folder.join(route_plan.trim()).to_string_lossy().to_string(),
+                )),
+                _ => return Err(MarineSimError::CsvParse { file: manifest_path.display().to_string(), message: format!("Line {} must have the columns name;ship_log;route_plan", i + 2) }),
+            }
+        }
+    }
+    // Otherwise pair the files by name
+    else {
+        let shared_route_plan = folder.join(SHARED_ROUTE_PLAN_FILE_NAME);
+        for file in std::fs::read_dir(folder)? {
+            let file_name = file?.file_name();
+            let file_name = file_name.to_string_lossy();
+            let name = match file_name.strip_suffix(SHIP_LOG_FILE_SUFFIX) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            let route_plan = folder.join(name.clone() + ROUTE_PLAN_FILE_SUFFIX);
+            let route_plan = if route_plan.is_file() {
+                route_plan
+            } else if shared_route_plan.is_file() {
+                shared_route_plan.clone()
+            } else {
+                tracing::warn!("No route plan found for {:?}. Skipping this file", file_name);
+                continue;
+            };
+            results.push((name, folder.join(file_name.as_ref()).to_string_lossy().to_string(), route_plan.to_string_lossy().to_string()));
+        }
     }
 
-    // Flush and close the writer
-    wtr.flush()?;
-    Ok(())
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    return Ok(results);
 }
 
-/// The reciprocal function to ship_logs_to_csv takes a csv file and returns the ship logs.
-/// Function that writes the ship logs to a CSV file with the following columns:
-/// timestamp;coordinates_initial;coordinates_current;coordinates_final;cargo_on_board
-/// Note that the coordinates are in the format of ISO 6709 using decimal places with a comma between latitude and longitude. "latitude,longitude" (e.g., "52.5200,13.4050")
-/// The cargo is considered to be in metric tons (1 metric ton = 1000 kg)
-/// csv_file_path: Path to the CSV file
-/// boat: The boat object containing the ship logs
-/// Note: The csv file delimiter is a semicolon
-/// Note: It is assumed that the course over ground is specified in 10 times the degrees. That is 3593 degrees course in the csv file are 359.3 degrees 
-pub fn csv_to_ship_log(csv_file_path: &str) -> Result<Vec<ShipLogEntry>, io::Error> {
-    // Check if the file ends with ".csv" and if it does not return an error
-    if csv_file_path.chars().rev().take(4).collect::<Vec<_>>().into_iter().rev().collect::<String>() != ".csv" {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("File path does not end with .csv\nFile: {:?}", csv_file_path)));
-    }
- 
-    // Init empty Ship Log book
-    let mut ship_log = vec![];
+/// Makes maps and a report for every ship log in a results folder in one go, e.g. for large experiment campaigns
+/// The ship logs are paired with their route plans by find_results_in_folder()
+/// For each ship log the map from visualize_ship_logs_and_route() is saved to <output_folder>/<name>.html without opening it,
+/// and a row with the key performance indicators of the voyage is added to <output_folder>/report.csv
+/// Ship logs that can not be visualized are skipped with a message
+/// Returns the names of the ship logs that were visualized
+/// # Example:
+/// `let names = visualize_results_folder("results/campaign_1", "figures/campaign_1")?;`
+pub fn visualize_results_folder(results_folder: &str, output_folder: &str) -> Result<Vec<String>, MarineSimError> {
+    let results = find_results_in_folder(results_folder)?;
+    std::fs::create_dir_all(output_folder)?;
 
-    // Read the CSV file
-    let mut csv_reader = csv::ReaderBuilder::new()
+    let mut csv_writer = csv::WriterBuilder::new()
         .delimiter(b';')
-        .has_headers(true)
-        .from_path(csv_file_path)
-        .expect(format!("Failed to open file: {}\n", csv_file_path).as_str());
+        .from_path(std::path::Path::new(output_folder).join("report.csv"))?;
+    csv_writer.write_record(["name", "ship_log", "route_plan", "start_time", "travel_time[days]", "dist[m]", "speed_mean[m/s]", "route_efficiency[-]", "map"])?;
 
-    // Loop through all lines of file, append each line to the ship log
-    for result in csv_reader.records() {
-        match result {
-            Ok(entry) => {
-                let timestamp = string_to_utc_date_time(entry.get(0).unwrap().to_owned());
-                let coordinates_initial = geo::Point::new(
-                    entry.get(1).unwrap().split(',').next().unwrap().parse::<f64>().expect("Error getting initial coordinates from csv file"),
-                    entry.get(1).unwrap().split(',').nth(1).unwrap().parse::<f64>().expect("Error getting initial coordinates from csv file")
-                );
-                let coordinates_current = geo::Point::new(
-                    entry.get(2).unwrap().split(',').next().unwrap().parse::<f64>().expect("Error getting current coordinates from csv file"),
-                    entry.get(2).unwrap().split(',').nth(1).unwrap().parse::<f64>().expect("Error getting current coordinates from csv file")
-                );
-                let coordinates_final = geo::Point::new(
-                    entry.get(3).unwrap().split(',').next().unwrap().parse::<f64>().expect("Error getting final coordinates from csv file"),
-                    entry.get(3).unwrap().split(',').nth(1).unwrap().parse::<f64>().expect("Error getting final coordinates from csv file")
-                );
-                // If there is no cargo written down, set to None
-                let cargo_on_board = match entry.get(4).unwrap() {
-                    "" => None,
-                    cargo => Some(uom::si::f64::Mass::new::<uom::si::mass::ton>(cargo.parse::<f64>().unwrap())),
-                };
-                // If no course written down, set to None
-                let course = match entry.get(6).unwrap() {
-                    "" => None,
-                    course => Some(course.parse::<f64>().unwrap()/10.0),
-                };
-                // Init velocity
-                let velocity: Option<PhysVec>;
-                // If speed and course are known, set velocity PhysVec to use them otherwise set velocity to None
-                if entry.get(5).unwrap().parse::<f64>().is_ok() && course.is_some() {
-                    velocity = Some(PhysVec::new(entry.get(5).unwrap().parse::<f64>().expect("Error getting velocity from csv file"), course.expect("Unknown course over ground when parsing velocity from ship log csv file")));
-                }
-                else {
-                    velocity = None;
-                }
-                // if there is a heading written down, set the heading to that, otherwise, set to None
-                let heading: Option<f64> = match entry.get(7).unwrap() {
-                    "" => None,
-                    h => Some(h.parse::<f64>().unwrap()),
-                };
-                // Track angle is between last and current ship log entry, if this is the first entry, set to None
-                let track_angle = match ship_log.len() {
-                    0 => None,
-                    _ => {
-                        let last_entry: &ShipLogEntry = ship_log.last().unwrap();
-                        let last_coords: geo::Point = last_entry.coordinates_current;
-                        let curr_coords: geo::Point = coordinates_current;
-                        Some(geo::Haversine.bearing(last_coords, curr_coords))
-                    }
-                };
-                // If no true_bearing written down, set to None
-                let true_bearing = match entry.get(8).unwrap() {
-                    "" => None,
-                    bearing => Some(bearing.parse::<f64>().unwrap()),
-                };
-                // If no draft written doen, set to None
-                let draft = match entry.get(9).unwrap() {
-                    "" => None,
-                    draft => Some(draft.parse::<f64>().unwrap()),
-                };
-                let navigation_status: Option<NavigationStatus> = match NavigationStatus::try_from(entry.get(10).unwrap().parse::<u8>().expect(format!("Error getting navigation status from {:?}. Entry: {:?}", csv_file_path, entry).as_str())) {
-                    Ok(status) => Some(status),
-                    Err(_) => None,                    
-                }; //Some(entry.get(10).map(|s| s.parse::<u8>().expect("Failed to parse navigation status")).expect("Failed to parse navigation status"));
+    let mut visualized: Vec<String> = Vec::new();
+    for (name, ship_log_path, route_plan_path) in results {
+        if !std::path::Path::new(&route_plan_path).is_file() {
+            tracing::warn!("Route plan {:?} not found. Skipping {:?}", route_plan_path, ship_log_path);
+            continue;
+        }
 
-                ship_log.push(
-                    ShipLogEntry {
-                        timestamp,
-                        coordinates_initial,
-                        coordinates_current,
-                        coordinates_final,
-                        cargo_on_board,
-                        velocity,
-                        course,
-                        heading,
-                        track_angle,
-                        true_bearing,
-                        draft,
-                        navigation_status,
-                    });
-                }
-            Err(err) => {
-                eprintln!("Error reading ship log entry: {}", err);
-                }
+        // Map
+        let figure_path = std::path::Path::new(output_folder).join(name.clone() + ".html");
+        match get_ship_logs_and_route_figure(&ship_log_path, &route_plan_path, &MapStyle::new()) {
+            Ok(figure) => figure.write_html(&figure_path),
+            Err(e) => {
+                tracing::warn!("Could not visualize {:?}. Error: {}", ship_log_path, e);
+                continue;
             }
         }
 
-    // Return ship log
-    return Ok(ship_log);
-}
+        // Report
+        let ship_log = match csv_to_ship_log(&ship_log_path) {
+            Ok(s) => s,
+            Err(e) => {
+                tracing::warn!("Could not read ship log {:?}. Error: {}", ship_log_path, e);
+                continue;
+            }
+        };
+        let route_plan = load_route_plan(&route_plan_path)?;
+        let kpis = VoyageKpis::from_ship_log(&ship_log);
+        let route_efficiency = get_route_efficiency(&ship_log, &route_plan.legs).ok().and_then(|efficiency| efficiency.voyage);
+        csv_writer.write_record([
+            name.clone(),
+            ship_log_path,
+            route_plan_path,
+            ship_log.first().map_or(String::new(), |entry| format_timestamp(entry.timestamp)),
+            (kpis.duration.as_seconds_f64()/86400.0).to_string(),
+            kpis.distance.to_string(),
+            kpis.speed_mean.map_or(String::new(), |speed| speed.to_string()),
+            route_efficiency.map_or(String::new(), |efficiency| efficiency.to_string()),
+            figure_path.to_string_lossy().to_string(),
+        ])?;
+        visualized.push(name);
+    }
+    csv_writer.flush()?;
 
-/// Function that translates coordinates to x,y values between 0 and 1 for plotting
-pub fn geo_point_to_xy(point_in: geo::Point) -> (f32, f32) {
-    // Normalize latitude to 0..1 where 0.5 is equator
-    let y = (-point_in.y() + 90.0) / 180.0;
-    // Normalize longitude to 0..1 where 0.5 is prime meridian
-    let x = (point_in.x() + 180.0) / 360.0;
+    return Ok(visualized);
+}
 
-    // Return the coordinates as a tuple
-    return (x as f32, y as f32);
+// Helper functions
+//----------------------------------------------------
+/// Formats a timestamp as "YYYY-MM-DD hh:mm:ss" in UTC, the format of the timestamps in the CSV files of the crate, e.g. ship_logs_to_csv()
+/// Plotly reads it as a date. parse_timestamp() reads it back
+/// # Example:
+/// `let timestamp_string: String = format_timestamp(boat.ship_log[0].timestamp);`
+pub fn format_timestamp(timestamp: UtcDateTime) -> String {
+    return format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", timestamp.year(), timestamp.month() as u8, timestamp.day(), timestamp.hour(), timestamp.minute(), timestamp.second());
 }
 
-/// Function that gets the angle from north given the northward PhysVec property (effectively, the magnitude going from north to south) and eastward PhysVec property (effectively, the magnitude going from west to east)
-pub fn get_north_angle_from_northward_and_eastward_property(eastward: f64, northward: f64) -> f64 {
-    let mut north_angle = northward.atan2(eastward) * 180.0 / std::f64::consts::PI;
+/// Parses a timestamp, e.g. one written by format_timestamp(), see string_to_utc_date_time() for the formats that can be read
+/// # Example:
+/// `let timestamp: time::UtcDateTime = parse_timestamp("2025-04-14 12:30:00")?;`
+pub fn parse_timestamp(timestamp_string: &str) -> Result<time::UtcDateTime, MarineSimError> {
+    return string_to_utc_date_time(timestamp_string.to_string());
+}
 
-    // Adjust result from atan2 to be between 0 and 360
-    if north_angle < 0.0 {
-        north_angle += 360.0;
+/// Converts a string into a time::UtcDateTime object
+/// time_string: The string to convert, an ISO 8601 or RFC 3339 date and time, YYYY-MM-DD hh:mm with optional seconds and fractional seconds, e.g. YYYY-MM-DD hh:mm:ss.sss
+/// The date and time can be separated by 'T' or a space and can end with a timezone, "Z", "UTC" or an offset such as "+01:00", "-0230" or "+01". Times with an offset are converted to UTC, times without a timezone are taken as UTC
+/// Returns an error if the string is not a valid date and time
+/// # Example:
+/// `let my_timestamp: time::UtcDateTime = string_to_utc_date_time("2025-04-14 12:30".to_string())?;`
+/// `let same_timestamp: time::UtcDateTime = string_to_utc_date_time("2025-04-14T13:30:00+01:00".to_string())?;`
+pub fn string_to_utc_date_time(time_string: String) -> Result<time::UtcDateTime, MarineSimError> {
+    // Remove whitespace around the string
+    let working_str: &str = time_string.trim();
+
+    // Only ASCII characters are allowed so the string can be sliced by byte
+    if !working_str.is_ascii() {
+        return Err(MarineSimError::InvalidInput(format!("Invalid time format, only ASCII characters are allowed: {:?}", time_string)));
+    }
+    // The date is always YYYY-MM-DD followed by the separator and at least hh:mm
+    if working_str.len() < 16 {
+        return Err(MarineSimError::InvalidInput(format!("Invalid time format with length {}, should be YYYY-MM-DD hh:mm: {:?}", working_str.len(), working_str)));
     }
-    
-    // transform angle to be based from north not east
-    north_angle -= 90.0;
 
-    // Adjusting if went out of bounds
-    while north_angle >= 360.0 {
-        north_angle -= 360.0;
+    // Split the timezone from the date and time, it is after the time of day so after the 16th character
+    let (date_time_str, offset): (&str, time::UtcOffset) = if let Some(date_time_str) = working_str.strip_suffix("UTC").or_else(|| working_str.strip_suffix(['Z', 'z'])) {
+        (date_time_str.trim_end(), time::UtcOffset::UTC)
     }
-    while north_angle < 0.0 {
-        north_angle += 360.0;
+    else if let Some(sign_index) = working_str[16..].find(['+', '-']).map(|i| i + 16) {
+        (working_str[..sign_index].trim_end(), get_utc_offset(&working_str[sign_index..]).ok_or_else(|| MarineSimError::InvalidInput(format!("Invalid timezone offset {:?} in {:?}, should be e.g. +01:00", &working_str[sign_index..], working_str)))?)
     }
+    else {
+        (working_str, time::UtcOffset::UTC)
+    };
 
-    return north_angle;
-}
-
-/// Segments a waypoint mission
-pub fn segment_waypoint_mission(route_plan: Vec<SailingLeg>, n_segments: u64) -> (Vec<geo::Point>, f64) {
-    // Get total length of route, in meters, if going shortest path
-    let mut total_dist: f64 = 0.0;
-
-    // For each leg, add leg distance to total_dist
-    for leg in &route_plan {
-        // get leg points
-        let p1 = leg.p1;
-        let p2 = leg.p2;
-        total_dist += geo::Haversine.distance(p1, p2);
+    // The date and time of day can be separated by 'T' or a space
+    let mut date_time_string = date_time_str.to_string();
+    if matches!(date_time_string.as_bytes().get(10), Some(b'T') | Some(b't')) {
+        date_time_string.replace_range(10..11, " ");
     }
 
-    // Get number of segments with a sanity check against zero n_segments:
-    let n: u64;
-    if n_segments < 1 {
-        n = 1;
-    } else {
-        n = n_segments;
+    // With fractional seconds, with seconds or without seconds
+    let formats = [
+        time::macros::format_description!("[year]-[month]-[day] [hour]:[minute]:[second].[subsecond]"),
+        time::macros::format_description!("[year]-[month]-[day] [hour]:[minute]:[second]"),
+        time::macros::format_description!("[year]-[month]-[day] [hour]:[minute]"),
+    ];
+    let mut parse_error: Option<time::error::Parse> = None;
+    for format in formats {
+        match time::PrimitiveDateTime::parse(&date_time_string, format) {
+            Ok(date_time) => return Ok(date_time.assume_offset(offset).to_utc()),
+            Err(e) => parse_error = Some(e),
+        }
     }
+    return Err(MarineSimError::InvalidInput(format!("Invalid date and time {:?}, should be YYYY-MM-DD hh:mm, optionally with seconds and a timezone: {}", working_str, parse_error.unwrap())));
+}
 
-    // Get length of each segment
-    let segment_dist: f64 = total_dist/(n as f64);
+/// Parses a timezone offset from UTC, "+hh:mm", "+hhmm" or "+hh" with '+' or '-'. None if it is not valid
+fn get_utc_offset(offset_string: &str) -> Option<time::UtcOffset> {
+    let sign: i8 = match offset_string.as_bytes().first() {
+        Some(b'+') => 1,
+        Some(b'-') => -1,
+        _ => return None,
+    };
+    let digits = offset_string[1..].replace(':', "");
+    if !(digits.len() == 2 || digits.len() == 4) || !digits.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i8 = digits[0..2].parse().ok()?;
+    let minutes: i8 = if digits.len() == 4 { digits[2..4].parse().ok()? } else { 0 };
+    return time::UtcOffset::from_hms(sign*hours, sign*minutes, 0).ok();
+}
 
-    // Make points of analysis using the path along the waypoint route and the n_segments from the simulaion
-    // Init with first point
-    let mut waypoints: Vec<geo::Point> = vec![route_plan[0].p1];
+/// Converts a time_stamp, the time since 0000-01-01 00:00 UTC, to a string in the format of format_timestamp()
+/// Returns an error if the time_stamp is not a date time::UtcDateTime can hold
+#[deprecated(note = "Timestamps in the crate are time::UtcDateTime, use format_timestamp()")]
+pub fn timestamp_to_string(time_stamp: uom::si::f64::Time) -> Result<String, MarineSimError> {
+    let year_zero = time::Date::from_calendar_date(0, time::Month::January, 1).map_err(|e| MarineSimError::InvalidInput(e.to_string()))?;
+    let timestamp = UtcDateTime::new(year_zero, time::Time::MIDNIGHT).checked_add(time::Duration::seconds_f64(time_stamp.get::<uom::si::time::second>()));
+    return match timestamp {
+        Some(timestamp) => Ok(format_timestamp(timestamp)),
+        None => Err(MarineSimError::InvalidInput(format!("Time stamp {:?} is out of range", time_stamp))),
+    };
+}
 
-    // Until the last waypoint has been reached, travel the segment_dist along the path and add the waypoint to the waypoints vector
-    // Init distance left of segment
-    let mut seg_dist_left: f64;
-    // Init leg number
-    let mut current_leg = 0;
-    // Init boat location
-    let mut location = route_plan[0].p1;
-    let mut num_points = 1;
-    while waypoints.last().unwrap() != &route_plan.last().unwrap().p2 {
-        // If number of waypoints is number of segments then the next waypoint should be the last point, make it happen explicitly since floating point error can
-        // cause issues where we have 1 too many points otherwise
-        if num_points == n_segments {
-            waypoints.push(route_plan.last().unwrap().p2);
-            break;
-        }
-        // Reset seg_dist_left
-        seg_dist_left = segment_dist.clone();
+/// Finds out which month of the year it is given the day number and year (in case it is a leap year)
+pub fn month_from_day(day_of_year: u16, year: i32) -> Result<(u8, u16), MarineSimError> {
+    let mut days_left: u16 = day_of_year;
 
-        // While there is still distance left in seg_dist_left, keep going towards the next point on the leg
-        while seg_dist_left > 0.0 {
-            // Get p2 of the current leg
-            let p2 = route_plan[(current_leg) as usize].p2;
+    tracing::trace!("Day of year: {}", days_left);
 
-            // Get distance to next waypoint
-            let dist_to_next_waypoint = geo::Haversine.distance(location, p2);
+    // Check if the day is valid
+    if days_left > 366 {
+        return Err(MarineSimError::InvalidInput(format!("Invalid day of year: {}", day_of_year)));
+    }
 
-            // If distance to next waypoint is shorter or same as segment distance left, then travel to waypoint, update boat location and leg number and reduce segment distance left
-            if dist_to_next_waypoint <= seg_dist_left {
-                location = p2;
-                current_leg = current_leg + 1;
+    // Init month
+    let month: u8;
 
-                seg_dist_left -= dist_to_next_waypoint;
+    // Leap year check
+    let is_leap_year: bool = year_helper::is_leap_year(year);
 
-                // If last point, set seg_dist_left to zero
-                if location == route_plan.last().unwrap().p2 {
-                    seg_dist_left = 0.0;
-                }
-            }
-            // Else, travel the segment distance along the path between the last waypoint and the next waypoint, set the seg_dist_left to zero and append that point to waypoints
-            else {
-                let heading = geo::Haversine.bearing(location, p2);
-                location = geo::Haversine.destination(location, heading, seg_dist_left);
-                seg_dist_left = 0.0;
+    // match through the number of days
+    match days_left {
+        1..=31 => {
+            month = 1; // January
+        }
+        32..=59 => {
+            month = 2; // February
+            days_left -= 31; // January has 31 days
+        }
+        60..=90 => {
+            // if leap year, day 60 is 29th of February
+            if is_leap_year && days_left == 60 {
+                month = 2; // February
+            } else {
+                month = 3; // March
             }
+            days_left -= 59;    // remove days in previous months of a normal year
+        }
+        91..=120 => {
+            month = 4; // April
+            days_left -= 90;
+        }
+        121..=151 => {
+            month = 5; // May
+            days_left -= 120;
+        }
+        152..=181 => {
+            month = 6; // June
+            days_left -= 151;
+        }
+        182..=212 => {
+            month = 7; // July
+            days_left -= 181;
+        }
+        213..=243 => {
+            month = 8; // August
+            days_left -= 212;
+        }
+        244..=273 => {
+            month = 9; // September
+            days_left -= 243;
+        }
+        274..=304 => {
+            month = 10; // October
+            days_left -= 273;
+        }
+        305..=334 => {
+            month = 11; // November
+            days_left -= 304;
+        }
+        335..=366 => {
+            month = 12; // December
+            days_left -= 334;
+        }
+        _ => {
+            return Err(MarineSimError::InvalidInput(format!("Invalid day of year: {}", day_of_year)));
         }
-        // Add location
-        waypoints.push(location);
-        num_points += 1;
     }
 
-    // Return waypoints and segment distance
-    return (waypoints, segment_dist);
-}
+    // If month is big enough and it's a leap year, subtract 1 day from days left
+    if is_leap_year && month > 2 {
+        days_left -= 1; // Leap year, add one day to February
+    }
 
-/// Downloads the weather data needed to run the fast_sim_waypoint_mission_weather_data_from_copernicus
-/// points: the locations to get weather data for
-/// timestamp: the time that the weather happened
-/// path_to_file: where to save the data
-pub fn get_weather_data_for_points(points: Vec<geo::Point>, timestamp: UtcDateTime, path_to_file: String, copernicus: copernicusmarine_rs::Copernicus) -> Result<String, io::Error> {
-    println!("Getting weather data");
-    // Initialize weather data vectors
-    let mut wind_vec: Vec<PhysVec> = Vec::new();
-    let mut ocean_current_vec: Vec<Option<PhysVec>> = Vec::new();
-    // Get number of points
-    let num_points = points.len();
+    tracing::trace!("Month: {}, Day of month : {}", month, days_left);
 
-    // Start a progress bar with twice the tasks as num_points
-    let progress_bar = indicatif::ProgressBar::new((num_points) as u64);
-    // Set progress bar style
-    progress_bar.set_style(indicatif::ProgressStyle::with_template("[{elapsed_precise}] {bar} {pos:>3}/{len:3} ETA:{duration_precise:>1}").unwrap()); //.progress_chars("##-"));
-    // Configure live redraw
-    progress_bar.set_draw_target(indicatif::ProgressDrawTarget::stdout());
-    progress_bar.enable_steady_tick(std::time::Duration::from_millis(500));
-    // Start progress bar
-    progress_bar.inc(0);
+    // Return the month and how many days are left
+    return Ok((month, days_left));
+}
 
-    // For each point, get the wind and current
-    for i in 0..num_points {
-        // Get the wind data
-        let dataset_id: String = match copernicusmarine_rs::get_dataset_id(copernicusmarine_rs::CopernicusVariable::EastwardWind, timestamp, timestamp) {
-            Ok(id) => id,
-            Err(e) => panic!("Error getting dataset id from copernicusmarine: {}", e),
-        };
-        // let wind_data = match copernicus.get_f64_values("cmems_obs-wind_glo_phy_nrt_l4_0.125deg_PT1H".to_string(), vec!["eastward_wind".to_string(), "northward_wind".to_string()], timestamp, timestamp, points[i].x(), points[i].x(), points[i].y(), points[i].y(), None, None) {
-        let wind_data = match copernicus.get_f64_values(dataset_id, vec!["eastward_wind".to_string(), "northward_wind".to_string()], timestamp, timestamp, points[i].x(), points[i].x(), points[i].y(), points[i].y(), None, None) {
-            Ok(w) => w,
-            Err(e) => panic!("Error getting wind data from copernicusmarine: {}", e),
-        };
-        let wind_east_data = &wind_data[0];
-        let wind_north_data = &wind_data[1];
-
-        // Wind speed and direction
-        let wind_east: f64 = wind_east_data[0].unwrap();
-        let wind_north: f64 = wind_north_data[0].unwrap();
-        let wind_angle: f64 = get_north_angle_from_northward_and_eastward_property(wind_east, wind_north);   // Angle in degrees
-        let wind_speed = uom::si::f64::Velocity::new::<uom::si::velocity::meter_per_second>((wind_east*wind_east + wind_north*wind_north).sqrt().into());
-        wind_vec.push(PhysVec::new(wind_speed.get::<uom::si::velocity::meter_per_second>(), wind_angle));    // unit [m/s]
-
-        // Get ocean current data from Copernicus
-        // "uo" is the eastward sea water velocity and "vo" is the northward sea water velocity
-        let dataset_id: String = match copernicusmarine_rs::get_dataset_id(copernicusmarine_rs::CopernicusVariable::EastwardSeaWaterVelocity, timestamp, timestamp) {
-            Ok(id) => id,
-            Err(e) => panic!("Error getting dataset id from copernicusmarine: {}", e),
-        };
-        // let ocean_current_data = match copernicus.get_f64_values("cmems_mod_glo_phy-cur_anfc_0.083deg_PT6H-i".to_string(), vec!["uo".to_string(), "vo".to_string()], timestamp, timestamp, points[i].x(), points[i].x(), points[i].y(), points[i].y(), Some(1.0), Some(1.0)){
-        // let ocean_current_data = match copernicus.get_f64_values(dataset_id, vec!["uo".to_string(), "vo".to_string()], timestamp, timestamp, points[i].x(), points[i].x(), points[i].y(), points[i].y(), Some(0.49402499198913574), Some(0.49402499198913574)){
-        // let ocean_current_data = match copernicus.get_f64_values(dataset_id, vec!["uo".to_string(), "vo".to_string()], timestamp, timestamp, points[i].x(), points[i].x(), points[i].y(), points[i].y(), Some(0.0), Some(50.0)){
-        let ocean_current_data = match copernicus.get_f64_values(dataset_id, vec!["uo".to_string(), "vo".to_string()], timestamp, timestamp, points[i].x(), points[i].x(), points[i].y(), points[i].y(), Some(0.0), Some(1.0)){
-            Ok(o) => o,
-            Err(e) => panic!("Error getting ocean current data from copernicusmarine: {}", e),
-        };
-        let ocean_current_east_data = &ocean_current_data[0];
-        let ocean_current_north_data = &ocean_current_data[1];
-
-        // Ocean current speed and direction
-        // If we don't have ocean_current data, push None to ocean_current_vec.
-        if ocean_current_east_data[0].is_none() && ocean_current_north_data[0].is_none() {
-            ocean_current_vec.push(None);
-        }
-        else {
-            let mut ocean_current_east: f64 = 0.0;
-            let mut ocean_current_north: f64 = 0.0;
-            if ocean_current_east_data[0].is_some() {
-                ocean_current_east = ocean_current_east_data[0].expect("Ocean current fill value?");
-            }
-            if ocean_current_north_data[0].is_some() {
-                ocean_current_north = ocean_current_north_data[0].expect("Ocean current fill value?");
-            }
-            let ocean_current_angle: f64 = get_north_angle_from_northward_and_eastward_property(ocean_current_east, ocean_current_north);   // Angle in degrees
-            let ocean_current_speed = uom::si::f64::Velocity::new::<uom::si::velocity::meter_per_second>((ocean_current_east*ocean_current_east + ocean_current_north*ocean_current_north).sqrt().into());
-            ocean_current_vec.push(Some(PhysVec::new(ocean_current_speed.get::<uom::si::velocity::meter_per_second>(), ocean_current_angle)));    // unit [m/s]
-        }
+/// Converts a string into a geo::Point object
+/// point_string: The string to convert
+/// # Example:
+/// `let my_coord: geo::Point = string_to_point("52.5200,13.4050".to_string())?;`
+/// Note that the output is a geo::Point::new(longitude, latitude) but the input string must be in the format of latitude,longitude so the order is reversed
+/// The latitude must be in \[-90, 90\]. The longitude must be in \[-180, 360\] and is returned in \[-180, 180\], e.g. 190 becomes -170, see normalize_longitude()
+pub fn string_to_point(coord_string: String) -> Result<geo::Point, MarineSimError> {
+    // Remove all spaces in string
+    let coord_str_vec: Vec<&str> = coord_string.trim().split(',').collect();
 
-        // Update progress bar
-        progress_bar.inc(1);
+    // Check if the coordinates are valid, should have latitude and longitude
+    if coord_str_vec.len() != 2 {
+        return Err(MarineSimError::Geometry(format!("Invalid coordinate format: {}. Should be \"latitude,longitude\"", coord_string)));
     }
 
-    // Save all the points in a csv file
-    // Check if csv_file_path ends with ".csv"
-    let num_chars = path_to_file.chars().count();
-    if &path_to_file[(num_chars-4)..] != ".csv" {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "The filepath must end with \".csv\""));
+    // Check if string is empty
+    if coord_str_vec[0].trim().len() == 0 || coord_str_vec[1].trim().len() == 0 {
+        return Err(MarineSimError::Geometry(format!("Input coordinate string is empty: {}", coord_string)));
     }
 
+    // Parse the latitude and longitude as f64
+    let latitude: f64 = match coord_str_vec[0].trim().parse::<f64>() {
+        Ok(lat) => lat,
+        Err(e) => return Err(MarineSimError::Geometry(format!("Invalid latitude: {:?}\nInput string: {}\nError: {}\n", coord_str_vec, coord_string, e))),
+    };
+    let mut longitude: f64 = match coord_str_vec[1].trim().parse::<f64>() {
+        Ok(lon) => lon,
+        Err(e) => return Err(MarineSimError::Geometry(format!("Invalid longitude: {:?}\nInput string: {}\nError: {}\n", coord_str_vec, coord_string, e))),
+    };
 
-    // Check if vectors are the same size
-    if &wind_vec.len() != &ocean_current_vec.len() || wind_vec.len() != num_points {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "All vectors must have the same length"));
+    // Infinite or NaN coordinates are not coordinates
+    if !latitude.is_finite() || !longitude.is_finite() {
+        return Err(MarineSimError::Geometry(format!("Coordinates must be finite numbers: {}", coord_string)));
     }
 
-    // Create a CSV writer with a semicolon delimiter
-    let mut wtr = csv::WriterBuilder::new()
-        .delimiter(b';')
-        .has_headers(true)
-        .from_path(path_to_file)?;
-
-    // Write the header
-    wtr.write_record(&["time","longitude","latitude","wind_speed[m/s]","wind_angle[°]","ocean_current_speed[m/s]","ocean_current_angle[°]"])?;
-
-    // Write the ship log entries
-    for i in 0..num_points {
-        // Get longitude
-        let longitude = points[i].x().to_string();
-        // Get latitude
-        let latitude = points[i].y().to_string();
-        // Get wind_speed
-        let wind_speed = wind_vec[i].magnitude.to_string();
-        // Get wind_angle
-        let wind_angle = wind_vec[i].angle.to_string();
-        // Get ocean_current_speed and angle
-        let ocean_current_speed: String;
-        let ocean_current_angle: String;
-        if ocean_current_vec[i].is_some() {
-            ocean_current_speed = ocean_current_vec[i].unwrap().magnitude.to_string();
-            ocean_current_angle = ocean_current_vec[i].unwrap().angle.to_string();
-        }
-        else {
-            ocean_current_speed = "".to_string();
-            ocean_current_angle = String::new();
-            // ocean_current_speed = "None".to_string();
-            // ocean_current_angle = "None".to_string();
-        }
+    // A latitude out of range is wrong data, e.g. swapped latitude and longitude, and can not be wrapped into range
+    if !(-90.0..=90.0).contains(&latitude) {
+        return Err(MarineSimError::Geometry(format!("Latitude {} is not between -90° and 90°: {}", latitude, coord_string)));
+    }
 
-        // Write the record
-        wtr.write_record(&[
-            copernicusmarine_rs::utc_date_time_to_string(timestamp),
-            longitude,
-            latitude,
-            wind_speed,
-            wind_angle,
-            ocean_current_speed,
-            ocean_current_angle,])?;        
+    // Longitudes from 0° to 360° are common in weather data, they are moved to between -180° and 180°
+    if !(-180.0..=360.0).contains(&longitude) {
+        return Err(MarineSimError::Geometry(format!("Longitude {} is not between -180° and 360°: {}", longitude, coord_string)));
+    }
+    if longitude > 180.0 {
+        longitude = normalize_longitude(longitude);
     }
 
-    // Flush and close the writer
-    wtr.flush()?;
+    // Make return point
+    let return_point = geo::Point::new(longitude, latitude);
+    
+    // Return point
+    return Ok(return_point);
+}
 
-    // Finish progress_bar
-    progress_bar.finish();
+/// Calculates the haversine distance between two points and returns the distance in uom::si::f64::Length
+pub fn haversine_distance_uom_units(p1: geo::Point, p2: geo::Point) -> uom::si::f64::Length {
+    // Calculate the haversine distance between two points
+    let dist: uom::si::f64::Length = uom::si::length::Length::new::<uom::si::length::meter>(Earth.distance(p1, p2));
+    return dist;
+}
 
-    // Return ok
-    return Ok("weather data retrieved and saved successfully".to_string());
+/// Returns the signed cross track distance (cross track error, XTE) in \[m\] from the great circle of a leg to a point, see SailingLeg.cross_track_distance()
+/// Positive if the point is to the right (starboard) of the leg when going from p1 to p2, negative if to the left (port). The absolute value is get_min_point_to_great_circle_dist()
+/// The simulators record it in ShipLogEntry.cross_track_error at each step
+/// # Example:
+/// `let xte = cross_track_distance(&route_plan[0], boat.location.unwrap());`
+pub fn cross_track_distance(leg: &SailingLeg, point: geo::Point) -> f64 {
+    return leg.cross_track_distance(point);
 }
 
-/// Function that gets weather data from file
-/// The output tuple is the (timstamp, location, wind vector, ocean current vector)
-pub fn get_weather_data_from_csv_file(path_to_file: String) -> (Vec<UtcDateTime>, Vec<geo::Point>, Vec<PhysVec>, Vec<Option<PhysVec>>) {
-    // Read the CSV file
-    let mut csv_reader = csv::ReaderBuilder::new()
-        .delimiter(b';')
-        .has_headers(true)
-        .from_path(path_to_file.clone())
-        .expect(format!("Failed to open file: {}\n", path_to_file.as_str()).as_str());
+/// Returns a longitude in degrees as a longitude in (-180, 180], e.g. 190 becomes -170 and -180 becomes 180
+pub fn normalize_longitude(longitude: f64) -> f64 {
+    let longitude = (longitude + 180.0).rem_euclid(360.0) - 180.0;
+    if longitude == -180.0 {
+        return 180.0;
+    }
+    return longitude;
+}
 
-    // Initialize return vectors
-    let mut timestamps: Vec<UtcDateTime> = Vec::new();
-    let mut points: Vec<geo::Point> = Vec::new();
-    let mut wind_vec: Vec<PhysVec> = Vec::new();
-    let mut ocean_current_vec: Vec<Option<PhysVec>> = Vec::new();
+/// Returns the difference in degrees from one longitude to another the short way around the globe, in (-180, 180]
+/// Positive if to is east of from, e.g. from 170 to -170 is 20
+pub fn get_longitude_difference(from: f64, to: f64) -> f64 {
+    return normalize_longitude(to - from);
+}
 
-    // Iterate through each line of the CSV file and add the coordinates to the route plan
-    for result in csv_reader.records() {
-        match result {
-            Ok(entry) => {
-                // timestamp
-                let timestamp = entry.get(0).expect("timestamp missing from weather data file").to_string();
-                timestamps.push(string_to_utc_date_time(timestamp));
-                // Point
-                let longitude: f64 = entry.get(1).expect("longitude missing from weather data file").parse::<f64>().unwrap();
-                let latitude: f64 =  entry.get(2).expect("latitude missing from weather data file").parse::<f64>().unwrap();
-                let point = geo::Point::new(longitude, latitude);
-                points.push(point);
-                // Wind
-                let wind_speed = entry.get(3).expect("wind speed missing from weather data file").parse::<f64>().unwrap();
-                let wind_angle = entry.get(4).expect("wind angle missing from weather data file").parse::<f64>().unwrap();
-                let wind = PhysVec::new(wind_speed, wind_angle);
-                wind_vec.push(wind);
-                // Ocean current
-                let ocean_current_speed_csv_entry = entry.get(5).unwrap();
-                let ocean_current_angle_csv_entry = entry.get(6).unwrap();
-                let ocean_current_speed: f64;
-                let ocean_current_angle: f64;
-                let ocean_current: Option<PhysVec>;
-                if ocean_current_speed_csv_entry == "" || ocean_current_angle_csv_entry == "" {
-                    ocean_current = None;
-                } else {
-                    ocean_current_speed = ocean_current_speed_csv_entry.parse::<f64>().unwrap();
-                    ocean_current_angle = ocean_current_angle_csv_entry.parse::<f64>().unwrap();
-                    ocean_current = Some(PhysVec::new(ocean_current_speed, ocean_current_angle));
-                }
-                ocean_current_vec.push(ocean_current);
-            }
-            Err(err) => {
-                eprintln!("Error reading weather data from file: {}", err);
-            }
+/// Unwraps longitudes in degrees so each longitude is less than 180° from the one before it, e.g. \[170, -175, -160\] becomes \[170, 185, 200\]
+/// Lines between the points then go the short way across the ±180° meridian instead of across the whole map. The first longitude is kept as it is
+/// # Example:
+/// `let longitudes = unwrap_longitudes(&ship_log.iter().map(|entry| entry.coordinates_current.x()).collect::<Vec<f64>>());`
+pub fn unwrap_longitudes(longitudes: &[f64]) -> Vec<f64> {
+    let mut unwrapped: Vec<f64> = Vec::with_capacity(longitudes.len());
+    for longitude in longitudes {
+        match unwrapped.last() {
+            Some(previous) => unwrapped.push(previous + get_longitude_difference(*previous, *longitude)),
+            None => unwrapped.push(*longitude),
         }
     }
-    
-    // Return (timestamps, points, wind_vec, ocean_current_vec)
-    return (timestamps, points, wind_vec, ocean_current_vec);
+    return unwrapped;
 }
 
+/// Get shortest distance between a line and a point on a sphere
+/// The line is the haversine line with endpoints p1 and p2
+/// Point p3 is the point that the shortest distance to the line between p1 and p2 will be calculated from.
+/// The distance is calculated with the spherical law of sines
+/// Returns the distance in meters
+pub fn get_min_point_to_great_circle_dist(p1: geo::Point, p2: geo::Point, p3: geo::Point) -> f64 {
+    // Quick check if already at end points
+    if p1 == p3 || p2 == p3 {
+        return 0.0;
+    }
+    // Using analytical solution from https://www.reddit.com/r/askmath/comments/1n6kc8d/whats_the_shortest_distance_d_from_a_point_on_a/
+    // Where p1 is U, P2 is V and P3 is W.
+    // Radius of sphere (Earth) is r
+    let r = Earth.radius();
+    // b is the distance from U to W (from p1 to p3)
+    let b = Earth.distance(p1, p3);
+    // Get the angle VUW (the angle between p2 and p3 as seen from p1), c_angle_radians is in [0, 2PI]
+    let c_angle_radians = (Earth.bearing(p1, p2) - Earth.bearing(p1, p3)).abs() * consts::PI/180.0;
+
+    // Calculate distance based on spherical law of sines https://en.wikipedia.org/wiki/Law_of_sines#Spherical_law_of_sines
+    // Note b/r gives an angle in radians that should always be in [0, PI] meaning that (b/r).sin() is always zero or a positive number and
+    // c_angle_radians.sin() can be a number in [-1,1] so we could be taking the arcsin of a negative number which results in a negative number
+    // Since the distance is always the same, regardless of the sign, we take the absolute value
+    let d = r*(c_angle_radians.sin() * (b/r).sin()).asin().abs();
+    return d;
+}
 
-/// Function that saves the settings of the simulation to a text file.
-/// Note: This function does not care about overwriting existing files, it will always overwrite.
-pub fn save_sim_settings_to_file(file_path: &str, sim: Simulation) -> Result<(), io::Error> {
-    // Check that file_path ends with ".txt"
-    let num_chars = file_path.chars().count();
-    if &file_path[(num_chars-4)..] != ".txt" {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "The filepath must end with \".txt\""));
+/// Converts a string into a f64 object
+/// cargo_string: The string to convert, must be in metric tons (1 metric ton = 1000 kg)
+/// Returns None if the string is empty and an error if it is not a number
+/// # Example:
+/// `let my_tons: Option<f64> = string_to_tons("500.3".to_string())?;`
+pub fn string_to_tons(cargo_string: String) -> Result<Option<f64>, MarineSimError> {
+    // Remove all spaces in string
+    let cargo_str: &str = (&cargo_string[..]).trim();
+    
+    // Check if the string is valid
+    if cargo_str.len() == 0 {
+        return Ok(None);
     }
 
-    // Make string to write to file
-    let mut settings_string: String = String::new();
-    settings_string.push_str("Simulation settings:\n");
-    settings_string.push_str(&format!("Simulation method: {:?}\n", sim.simulation_method));
-    settings_string.push_str(&format!("Simulation start times: {:?}\n", sim.start_times));
-    settings_string.push_str(&format!("Simulation time step: {}\n", sim.time_step));
-    settings_string.push_str(&format!("Simulation max iterations: {}\n", sim.max_iterations));
-    settings_string.push_str(&format!("Simulation weather_data_file: {:?}\n", sim.weather_data_file));
-    settings_string.push_str(&format!("Simulation copernicus: {:?}\n", sim.copernicus));
-    settings_string.push_str(&format!("Simulation progress bar: {:?}\n", sim.progress_bar));
-    settings_string.push_str(&format!("Simulation number of segments: {:?}\n", sim.n_segments));
+    // Parse the cargo as f64
+    let cargo: f64 = match cargo_str.parse::<f64>() {
+        Ok(cargo) => cargo,
+        Err(e) => return Err(MarineSimError::InvalidInput(format!("Invalid cargo {:?}: {}", cargo_str, e))),
+    };
 
-    // Write string to file
-    println!("Saving simulation settings to file: {}", settings_string);
+    // return cargo
+    return Ok(Some(cargo));
+}
 
-    let mut f = File::create(file_path)?;
-    f.write_all(&settings_string.into_bytes())?;
+/// Returns the average and standard deviation of all values in a vector of f64 objects, see get_mean_and_std()
+/// data_vec: The vector of f64 objects
+/// only_finite_numbers: True if invalid entries should be ignored. Example vector [1, 3, inf, 5] if true then the mean will be 3, if false then the mean will be inf and the standard deviation NaN.
+/// Returns an error if the vector has no finite values, the standard deviation is zero if it has only one
+/// # Example:
+/// `let (my_mean, my_std) = get_vec_f64_mean_and_std(&my_vec, true)?;`
+pub fn get_vec_f64_mean_and_std(data_vec: &Vec<f64>, only_finite_numbers: bool) -> Result<(f64, f64), MarineSimError> {
+    // Invalid entries that are not ignored make the mean invalid too
+    if !only_finite_numbers && data_vec.iter().any(|value| !value.is_finite()) {
+        let vec_mean: f64 = data_vec.iter().sum::<f64>() / (data_vec.len() as f64);
+        return Ok((vec_mean, f64::NAN));
+    }
+    return get_mean_and_std(data_vec);
+}
 
 
-    // Return ok
-    return Ok(());
+/// Values that statistics can be computed for with get_mean_and_std() and get_distribution_stats()
+/// The values are converted to f64 in their base unit, seconds for time::Duration and SI base units for uom quantities, e.g. kilograms for mass
+pub trait StatisticsValue: Copy {
+    /// Returns the value in its base unit
+    fn to_base_f64(&self) -> f64;
+    /// Makes a value from a number in its base unit
+    fn from_base_f64(value: f64) -> Self;
 }
 
-/// Function that takes generates and saves a polar speed plot csv file
-/// for a wind propelled vessel.
-/// Based on this issue: <https://github.com/G0rocks/marine_vessel_simulator/issues/50>
-/// The file_path is where the results will be saved as a csv file.
-/// The min file contains the minimum vessel speed through water for each apparent wind angle and apparent wind speed segment.
-/// The max file contains the maximum vessel speed through water for each apparent wind angle and apparent wind speed segment.
-/// The mean file contains the mean vessel speed through water for each apparent wind angle and apparent wind speed segment.
-/// The source data file contains the source data used to compute the min, mean and max. Made from the polar plot data vector. Column 1 is the apparent wind angle, column 2 is the apparent wind speed, column 3 is the vessel speed through water, column 4 is the heading, column 5 is the wind speed (not apparent), column 6 is the wind angle (not apparent), column 7 is the ocean current speed (not apparent), column 8 is the ocean current angle (not apparent).
-/// The csv files can be used to make a polar plot in openCPN or similar programs.
-/// Until this issue has been dealt with (<https://github.com/G0rocks/marine_vessel_simulator/issues/42>) then marine_vessel_simulator does not support using the polar plot but it can be uploaded to openCPN or similar programs to use them.
-/// The polar plot data vector columns are: Column 1 is the apparent wind angle, column 2 is the apparent wind speed and column 3 is the vessel speed through water
-/// Warning: All calculations assume meters per second are being used and if knots are being used the vessel speed will be multiplied by 1.94384 to transform into knots and the columns (with the wind speed) will be multiplied by 2 (to ensure that the file can be opened by openCPN) meaning that if knots are used then the potentially there will be issues in using the data than if meters per second are used.
-/// Note: If not ocean current data is retrieved, the current is assumed to be flowing at zero meters per second
-/// Note: If no degree_segment_size is given, defaults to 5°. If a segment size is given it must be so that 180° is divisible by the segment size
-/// Note: If no wind_speed_segment_size is given, defaults to 1 m/s. If a segment size is given it must be so that 40 m/s is divisible by the segment size. Will always use m/s and not knots.
-/// Note: As of 2026-02-06 OpenCPN polar plugin only accepts values in degree increments of 5° and column increments of 2 (no unit). In order to generate a polar speed plot csv file which can be opened by this plugin the same constraints are put on the input degree and wind speed segment sizes, that is that they must be divisible by 5° and 2 m/s. Follow this issue for updates: <https://github.com/G0rocks/marine_vessel_simulator/issues/56>
-pub fn make_polar_speed_plot_csv(ship_log: Vec<ShipLogEntry>, simulation: &Simulation, file_path: &str, true_if_knots_false_if_meters_per_second: bool, degree_segment_size: Option<f64>, wind_speed_segment_size: Option<f64>) -> Result<Vec<Vec<f64>>, io::Error> {
-    // Add ".csv" to the end of the file path if it is not there already
-    let mut working_file_path: String = file_path.to_owned();
-    if file_path.chars().rev().take(4).collect::<Vec<_>>().into_iter().rev().collect::<String>() != ".csv" {
-        working_file_path = file_path.to_owned() + ".csv";
+impl StatisticsValue for f64 {
+    fn to_base_f64(&self) -> f64 {
+        return *self;
     }
-    // Make file paths for all the polar plot data files
-    let working_file_path_min: String = working_file_path.replace(".csv", "_min.csv");
-    let working_file_path_mean: String = working_file_path.replace(".csv", "_mean.csv");
-    let working_file_path_max: String = working_file_path.replace(".csv", "_max.csv");
-    let working_file_path_source: String = working_file_path.replace(".csv", "_source_data.csv");
-
-    // Check if the file path already exist, if not create the folder
-    if !std::path::Path::new(&file_path).exists() {
-        std::fs::create_dir_all(std::path::Path::new(&file_path).parent().unwrap())?;
+    fn from_base_f64(value: f64) -> f64 {
+        return value;
     }
+}
 
-    // Get working degree segment size from degree_segment_size and evaluate if it is so that 180° are divisible by it
-    let working_degree_segment_size: f64 = degree_segment_size.unwrap_or_else(|| 5.0);
-    if 180.0 % working_degree_segment_size != 0.0 {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid input: degree segment size: {}°.\nThe degree segment size must be so that 180° is divisible by the angle", working_degree_segment_size)));
+impl StatisticsValue for time::Duration {
+    fn to_base_f64(&self) -> f64 {
+        return self.as_seconds_f64();
     }
-    if working_degree_segment_size % 5.0 != 0.0 {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid input: degree segment size: {}°.\nThe degree segment size must be divisible by 5° to ensure compatibility with openCPN polar plugin", working_degree_segment_size)));
+    fn from_base_f64(value: f64) -> time::Duration {
+        return time::Duration::seconds_f64(value);
     }
-    // Get working wind speed segment size from wind_speed_segment_size and evaluate if it is so that 40 m/s is divisible by it
-    let working_wind_speed_segment_size: f64 = wind_speed_segment_size.unwrap_or_else(|| 1.0);
-    if 40.0 % working_wind_speed_segment_size != 0.0 {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid input: wind speed segment size: {} m/s.\nThe wind speed segment size must be so that 40 m/s is divisible by the segment size", working_wind_speed_segment_size)));
+}
+
+/// Any uom quantity, e.g. uom::si::f64::Mass, uom::si::f64::Velocity or uom::si::f64::Energy
+impl<D, U> StatisticsValue for uom::si::Quantity<D, U, f64>
+where
+    D: uom::si::Dimension + ?Sized,
+    U: uom::si::Units<f64> + ?Sized,
+{
+    fn to_base_f64(&self) -> f64 {
+        return self.value;
     }
-    if working_wind_speed_segment_size % 2.0 != 0.0 {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid input: wind speed segment size: {} m/s.\nThe wind speed segment size must be divisible by 2 m/s to ensure compatibility with openCPN polar plugin", working_wind_speed_segment_size)));
+    fn from_base_f64(value: f64) -> Self {
+        return uom::si::Quantity { dimension: std::marker::PhantomData, units: std::marker::PhantomData, value };
     }
+}
 
-    // Init empty polar plot data vector which will have subvectors. Column 1 is the apparent wind angle, column 2 is the apparent wind speed, column 3 is the vessel speed through water, column 4 is the heading, column 5 is the wind speed (not apparent), column 6 is the wind angle (not apparent), column 7 is the ocean current speed (not apparent), column 8 is the ocean current angle (not apparent).
-    // NOTE CURRENTLY ONLY HAS 4 COLUMNS!!!
-    let mut polar_plot_data_vector: Vec<Vec<f64>> = Vec::new();
+/// Returns the average and sample standard deviation of the values, values that are not finite are ignored
+/// The standard deviation is zero if there is only one value
+/// Works for f64, time::Duration and uom quantities, see StatisticsValue
+/// # Example:
+/// `let (mass_mean, mass_std) = get_mean_and_std(&cargo_masses)?;`
+pub fn get_mean_and_std<T: StatisticsValue>(values: &[T]) -> Result<(T, T), MarineSimError> {
+    let numbers: Vec<f64> = values.iter().map(|value| value.to_base_f64()).filter(|value| value.is_finite()).collect();
+    if numbers.is_empty() {
+        return Err(MarineSimError::InvalidInput("No finite values, cannot calculate mean and standard deviation".to_string()));
+    }
+    let n = numbers.len() as f64;
+    let mean: f64 = numbers.iter().sum::<f64>() / n;
+    let std: f64 = match numbers.len() {
+        1 => 0.0,
+        _ => (numbers.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt(),
+    };
+    return Ok((T::from_base_f64(mean), T::from_base_f64(std)));
+}
 
-    // Check for interactive terminal for progress bar
-    let is_interactive_terminal = atty::is(atty::Stream::Stdout);
-
-    // If simulation has progress bar, set it up and use it
-    if !(simulation.progress_bar.is_none()) {
-        // Set length
-        // Hiding progress bar to prevent two lines of progress bar being drawn
-        simulation.progress_bar.as_ref().unwrap().set_draw_target(indicatif::ProgressDrawTarget::hidden());
-        simulation.progress_bar.as_ref().unwrap().set_length((ship_log.len() as u64) + 1);
-        // If terminal is interactive, use live redraw, otherwise use static redraw
-        if is_interactive_terminal {
-            // Normal terminal behavior (live redraw)
-            simulation.progress_bar.as_ref().unwrap().set_draw_target(indicatif::ProgressDrawTarget::stdout());
-            simulation.progress_bar.as_ref().unwrap().enable_steady_tick(std::time::Duration::from_millis(500));
-        } else {
-            // Force static redraw every step to stdout (or to log)
-            // bar.set_draw_target(indicatif::ProgressDrawTarget::stdout_with_hz(1)); // Or `.stdout_with_hz(1)` for slow redraw
-            let eta = time::UtcDateTime::now().saturating_add(time::Duration::new(simulation.progress_bar.as_ref().unwrap().eta().as_secs() as i64, 0)); // What time the simulations will end
-            println!("Elapsed: {:?}, Steps {}/{}, ETA: {}-{}-{} {}:{}:{}", simulation.progress_bar.as_ref().unwrap().elapsed(), simulation.progress_bar.as_ref().unwrap().position(), simulation.progress_bar.as_ref().unwrap().length().unwrap(), eta.year(), eta.month() as u8, eta.day(), eta.hour()+1, eta.minute(), eta.second());
-        }
-        simulation.progress_bar.as_ref().unwrap().inc(0);
-    }
+/// Returns the distribution statistics of the values in their base unit, see DistributionStats and StatisticsValue. None if there are no finite values
+/// # Example:
+/// `let travel_time = get_distribution_stats(&travel_times);   // In seconds`
+pub fn get_distribution_stats<T: StatisticsValue>(values: &[T]) -> Option<DistributionStats> {
+    return DistributionStats::from_values(&values.iter().map(|value| value.to_base_f64()).collect());
+}
 
-    // Loop through ship_log
-    for entry in ship_log {
-        // Get timestamp
-        let timestamp = entry.timestamp;
+/// Returns the average and standard deviation of a vector of durations, see get_mean_and_std()
+/// # Example:
+/// `let (my_mean, my_std) = get_duration_mean_and_std(&my_vec);`
+pub fn get_duration_mean_and_std(duration_vec: &Vec<time::Duration>) -> Result<(time::Duration, time::Duration), MarineSimError> {
+    return get_mean_and_std(duration_vec);
+}
 
-        // Get vessel location coordinates
-        let location = entry.coordinates_current;
-        let longitude = location.x();
-        let latitude = location.y();
-        
-        // Get velocity and heading
-        let vessel_velocity = entry.velocity;
-        let heading = entry.heading;
 
-        // If vessel velocity or heading is None skip this entry since we need both data fields
-        if vessel_velocity == None || heading == None {
-            // Update progress bar if a progress bar is in use
-            if !(simulation.progress_bar.is_none()) {
-                // update progress bar
-                simulation.progress_bar.as_ref().unwrap().inc(1);
-                // If not interactive terminal, print progressbar manually
-                if is_interactive_terminal == false {
-                    let eta = time::UtcDateTime::now().saturating_add(time::Duration::new(simulation.progress_bar.as_ref().unwrap().eta().as_secs() as i64, 0)); // What time the simulations will end
-                println!("Elapsed: {} secs, Steps {}/{}, ETA: {}-{}-{} {}:{}:{}", simulation.progress_bar.as_ref().unwrap().elapsed().as_secs(), simulation.progress_bar.as_ref().unwrap().position(), simulation.progress_bar.as_ref().unwrap().length().unwrap(), eta.year(), eta.month() as u8, eta.day(), eta.hour(), eta.minute(), eta.second());
-                }   // End if
-            }   // End if
+/// Loads route plan from a CSV file
+/// Returns a RoutePlan where each leg is a leg of the trip
+/// The CSV file is expected to have the following columns in order but the header names are not important:
+/// Leg number;start_latitude;start_longitude;end_latitude;end_longitude;tacking_width\[meters\];min_proximity\[meters\]
+/// Optionally followed by a port call at the end of the leg, see PortCall:
+/// cargo_loaded\[ton\];cargo_unloaded\[ton\];port_wait\[hours\]
+/// Legs without a port call leave the port call columns empty or out. Empty port call cells are read as 0
+/// The delimiter is a semicolon.
+/// Returns a CsvField error with the line and field if a value is missing or invalid
+/// file_path: Path to the CSV file
+/// # Example:
+/// `let file_path: &str = "my_route_plan.csv";`
+pub fn load_route_plan(file_path: &str) -> Result<RoutePlan, MarineSimError> {
+    // Read the CSV file
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        // Legs without a port call can leave the port call columns out
+        .flexible(true)
+        .from_path(file_path)
+        .map_err(|e| io::Error::new(io::ErrorKind::NotFound, format!("Failed to open route plan file: {}. Error: {}", file_path, e)))?;
 
-            // Skip to next iteration of loop
-            continue;
-        }
+    // Initialize a vector to store the route plan
+    let mut route_plan: Vec<SailingLeg> = Vec::new();
 
-        // Get wind and ocean current data from timestamp and location from Copernicus
-        let dataset_id: String = match copernicusmarine_rs::get_dataset_id(copernicusmarine_rs::CopernicusVariable::EastwardWind, timestamp, timestamp) {
-            Ok(id) => id,
-            Err(e) => panic!("Error getting dataset id from copernicusmarine: {}", e),
+    // Iterate through each line of the CSV file and add the coordinates to the route plan
+    for result in csv_reader.records() {
+        // The csv error says which line could not be read
+        let leg = result?;
+
+        // Get the SailingLeg data from the CSV file
+        // First column is the leg number, so we skip it
+        // Start_coord
+        let start_lat = parse_required_csv_field::<f64>(&leg, 1, "start_latitude", file_path)?;
+        let start_long = parse_required_csv_field::<f64>(&leg, 2, "start_longitude", file_path)?;
+        // End_coord
+        let end_lat = parse_required_csv_field::<f64>(&leg, 3, "end_latitude", file_path)?;
+        let end_long = parse_required_csv_field::<f64>(&leg, 4, "end_longitude", file_path)?;
+        // Tacking width
+        let tacking_width = parse_required_csv_field::<f64>(&leg, 5, "tacking_width", file_path)?;
+        // Get minimum proximity
+        let min_proximity = parse_required_csv_field::<f64>(&leg, 6, "min_proximity", file_path)?;
+        // Get port call, if any of the port call columns has a value, empty cells are 0
+        let cargo_loaded = parse_csv_field::<f64>(&leg, 7, "cargo_loaded", file_path)?;
+        let cargo_unloaded = parse_csv_field::<f64>(&leg, 8, "cargo_unloaded", file_path)?;
+        let port_wait = parse_csv_field::<f64>(&leg, 9, "port_wait", file_path)?;
+        let port_call: Option<PortCall> = match (cargo_loaded, cargo_unloaded, port_wait) {
+            (None, None, None) => None,
+            _ => Some(PortCall::new(cargo_loaded.unwrap_or(0.0), cargo_unloaded.unwrap_or(0.0), time::Duration::seconds_f64(port_wait.unwrap_or(0.0)*3600.0))),
         };
-        // let wind_data = match simulation.copernicus.as_ref().unwrap().get_f64_values("cmems_obs-wind_glo_phy_nrt_l4_0.125deg_PT1H".to_string(), vec!["eastward_wind".to_string(), "northward_wind".to_string()], boat_time_now, boat_time_now, longitude, longitude, latitude, latitude, None, None) {
-        let wind_data = match simulation.copernicus.as_ref().unwrap().get_f64_values(dataset_id, vec!["eastward_wind".to_string(), "northward_wind".to_string()], timestamp, timestamp, longitude, longitude, latitude, latitude, None, None) {
-            Ok(w) => w,
-            Err(e) => return Err(io::Error::new(io::ErrorKind::Other, format!("Error getting wind data from copernicusmarine: {}", e))),
+
+        // Make a SailingLeg object, the coordinates are checked and the longitudes moved to between -180° and 180° like any other coordinates, see string_to_point()
+        let temp_sailing_leg: SailingLeg = SailingLeg {
+            p1: convert_csv_field(&leg, 1, "start coordinates", file_path, |_| string_to_point(format!("{},{}", start_lat, start_long)))?,
+            p2: convert_csv_field(&leg, 3, "end coordinates", file_path, |_| string_to_point(format!("{},{}", end_lat, end_long)))?,
+            tacking_width,
+            min_proximity,
+            port_call,
+            anchor_wait: None,
+            traffic_lane: None,
         };
-        let wind_east_data = &wind_data[0];
-        let wind_north_data = &wind_data[1];
 
-        // Wind speed and direction
-        let wind_east: f64 = wind_east_data[0].unwrap();
-        let wind_north: f64 = wind_north_data[0].unwrap();
-        let wind_angle: f64 = get_north_angle_from_northward_and_eastward_property(wind_east, wind_north);   // Angle in degrees
-        let wind_speed = uom::si::f64::Velocity::new::<uom::si::velocity::meter_per_second>((wind_east*wind_east + wind_north*wind_north).sqrt().into());
-        let wind = PhysVec::new(wind_speed.get::<uom::si::velocity::meter_per_second>(), wind_angle);    // unit [m/s]
+        // Add the SailingLeg object to the route plan
+        route_plan.push(temp_sailing_leg);
+    }
 
-        // Get ocean current data from Copernicus
-        // "uo" is the eastward sea water velocity and "vo" is the northward sea water velocity
-        let dataset_id: String = match copernicusmarine_rs::get_dataset_id(copernicusmarine_rs::CopernicusVariable::EastwardSeaWaterVelocity, timestamp, timestamp) {
-            Ok(id) => id,
-            Err(e) => panic!("Error getting dataset id from copernicusmarine: {}", e),
-        };
-        let ocean_current_data = match simulation.copernicus.as_ref().unwrap().get_f64_values(dataset_id, vec!["uo".to_string(), "vo".to_string()], timestamp, timestamp, longitude, longitude, latitude, latitude, Some(0.0), Some(1.0)){
-            Ok(o) => o,
-            Err(e) => panic!("Error getting ocean current data from copernicusmarine: {}", e),
-        };
-        let ocean_current_east_data = &ocean_current_data[0];
-        let ocean_current_north_data = &ocean_current_data[1];
+    // Return the route plan
+    return Ok(RoutePlan::from(route_plan));
+}
 
-        // Ocean current speed and direction
-        let ocean_current_east: f64 = match ocean_current_east_data[0] {
-            Some(v) => v,
-            None => 0.0,
-        };
-        let ocean_current_north: f64 = match ocean_current_north_data[0] {
-            Some(v) => v,
-            None => 0.0,
-        };
-        let ocean_current_angle: f64 = get_north_angle_from_northward_and_eastward_property(ocean_current_east, ocean_current_north);   // Angle in degrees
-        let ocean_current_speed = uom::si::f64::Velocity::new::<uom::si::velocity::meter_per_second>((ocean_current_east*ocean_current_east + ocean_current_north*ocean_current_north).sqrt().into());
-        let ocean_current = PhysVec::new(ocean_current_speed.get::<uom::si::velocity::meter_per_second>(), ocean_current_angle);    // unit [m/s]
+/// Function that writes the ship logs to a CSV file with the following columns:
+/// timestamp;coordinates_initial;coordinates_current;coordinates_final;cargo_on_board
+/// Note that the coordinates are in the format of ISO 6709 using decimal places with a comma between latitude and longitude. "latitude,longitude" (e.g., "52.5200,13.4050")
+/// The cargo is in metric tons (1 metric ton = 1000 kg)
+/// csv_file_path: Path to the CSV file
+/// ship_logs: The ship logs from the vessel
+/// Note: The csv file delimiter is a semicolon
+/// Note: Optional fields that are None are written as empty cells
+pub fn ship_logs_to_csv(csv_file_path: &str, ship_logs: &Vec<ShipLogEntry>) -> Result<(), MarineSimError> {
+    // Create a CSV writer with a semicolon delimiter
+    // let mut wtr = csv::WriterBuilder::new().delimiter(b';').from_path(csv_file_path)?;
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(csv_file_path)?;
 
-        // Offset velocity by ocean current velocity (a.k.a. account for set and drift)
-        let mut vessel_velocity_through_water: PhysVec = match vessel_velocity {
-            Some(v) => v - ocean_current,
-            None => {
-                // If no vessel velocity, return error since no polar plot data can be generated
-                return Err(io::Error::new(io::ErrorKind::Other, "No vessel velocity data in ship log entry, cannot generate polar plot data"));
-            }
-        };
-        // Make sure the angle is between 0.0 and 360.0 degrees
-        while vessel_velocity_through_water.angle < 0.0 {
-            vessel_velocity_through_water.angle += 360.0;
-        }
-        while vessel_velocity_through_water.angle >= 360.0 {
-            vessel_velocity_through_water.angle -= 360.0;
-        }
+    // Write the header
+    wtr.write_record(&SHIP_LOG_CSV_HEADER)?;
 
-        // Compute apparent wind
-        let apparent_wind = wind - ocean_current;
-        // Include heading
-        let mut apparent_wind = PhysVec::new(apparent_wind.magnitude, apparent_wind.angle - heading.unwrap());
-        // Make sure the angle is between 0.0 and 360.0 degrees
-        while apparent_wind.angle < 0.0 {
-            apparent_wind.angle += 360.0;
-        }
-        while apparent_wind.angle >= 360.0 {
-            apparent_wind.angle -= 360.0;
-        }
+    // Write the ship log entries
+    for entry in ship_logs.iter() {
+        wtr.write_record(&get_ship_log_csv_record(entry))?;
+    }
 
-        // Log apparent wind angle, wind speed and vessel speed to polar plot data vector
-        polar_plot_data_vector.push(vec![apparent_wind.angle, apparent_wind.magnitude, vessel_velocity_through_water.magnitude, heading.unwrap(), wind.magnitude, wind.angle, ocean_current.magnitude, ocean_current.angle]);
+    // Flush and close the writer
+    wtr.flush()?;
+    Ok(())
+}
 
-        // Update progress bar if a progress bar is in use
-        if !(simulation.progress_bar.is_none()) {
-            // update progress bar
-            simulation.progress_bar.as_ref().unwrap().inc(1);
-            // If not interactive terminal, print progressbar manually
-            if is_interactive_terminal == false {
-                let eta = time::UtcDateTime::now().saturating_add(time::Duration::new(simulation.progress_bar.as_ref().unwrap().eta().as_secs() as i64, 0)); // What time the simulations will end
-                println!("Elapsed: {} secs, Steps {}/{}, ETA: {}-{}-{} {}:{}:{}", simulation.progress_bar.as_ref().unwrap().elapsed().as_secs(), simulation.progress_bar.as_ref().unwrap().position(), simulation.progress_bar.as_ref().unwrap().length().unwrap(), eta.year(), eta.month() as u8, eta.day(), eta.hour(), eta.minute(), eta.second());
-            }   // End if
-        }   // End if
-    }   // End for loop
+/// Header of the ship log CSV files, see ship_logs_to_csv()
+/// The velocity angle, track angle, cross track error and foiling columns were added last so ship log CSV files from before can still be read, see ship_log_from_csv()
+const SHIP_LOG_CSV_HEADER: [&str; 19] = ["timestamp", "coordinates_initial", "coordinates_current", "coordinates_final", "cargo_on_board[ton]", "velocity[m/s]", "course[°]", "heading", "true_bearing[°]", "draught[m]", "navigation_status", "depth[m]", "wind_speed[m/s]", "wind_angle[°]", "wave_height[m]", "velocity_angle[°]", "track_angle[°]", "cross_track_error[m]", "foiling"];
 
-    // Make mutable standardized polar plot data vector (based off of opencpn polar plot csv files but with wind in m/s). Include option for having None for unknown values
-    // Column 0 is the angle of the apparent wind in degrees
-    // column 1-40 contains tuples which are (n, VTW.magnitude) where n signifies how many values have been used to generate the average value VTW.magnitude which is the magnitude of the Velocity through water vector.
-    // Note the VTW.magnitude is given in m/s in 1 m/s increments
-    let mut standard_data_vector_mean: Vec<Vec<(usize, Option<f64>)>> = Vec::new();
+/// Returns the fields of a ship log entry in a ship log CSV file, in the order of SHIP_LOG_CSV_HEADER
+fn get_ship_log_csv_record(entry: &ShipLogEntry) -> [String; 19] {
+    // If cargo is None, set to empty string
+    let cargo = match entry.cargo_on_board {
+        Some(c) => c.get::<uom::si::mass::ton>().to_string(),
+        None => String::from(""),
+    };
 
-    // First find how many degree and wind speed segments we have
-    let num_degree_segments: u16 = (180.0/working_degree_segment_size) as u16 + 1;   // +1 for the degrees since both 0° and 180° are included  // Keeping this even though it is currently unused since when this issue gets resolved we can use it again: https://github.com/G0rocks/marine_vessel_simulator/issues/56
-    let num_wind_speed_segments: u8 = (40.0/working_wind_speed_segment_size) as u8;     // No +1 since 0 m/s is not included but 40 m/s is included
-    
-    // Fill the first column of the standard data vector with working_degree_segment_size increments, set all unknown values to None
-    // Use 0..37 since OpenCPN polar plugin only accepts values in 5° increments
-    for i in 0..37 {
-        let angle = (i as f64) * 5.0;
-        let mut sub_vec: Vec<(usize, Option<f64>)> = Vec::new();
-        sub_vec.push((angle as usize, Some(angle)));
-        for _k in 0..num_wind_speed_segments {
-            sub_vec.push((0, None));
-        }
-        standard_data_vector_mean.push(sub_vec);
-    }
+    // If velocity is None, set to empty strings
+    let (velocity, velocity_angle) = match entry.velocity {
+        Some(v) => (v.magnitude.to_string(), v.angle.to_string()),
+        None => (String::from(""), String::from("")),
+    };
 
-    // Make a copy of the standard data vector for the minimum and maximum values as well
-    let mut standard_data_vector_min: Vec<Vec<(usize, Option<f64>)>> = standard_data_vector_mean.clone();
-    let mut standard_data_vector_max: Vec<Vec<(usize, Option<f64>)>> = standard_data_vector_mean.clone();
+    // If course is None, set to empty string
+    let course = match entry.course {
+        Some(c) => c.to_string(),
+        None => String::from(""),
+    };
 
-    // Now that all the data has been collected for the polar plot, we loop through it and standardize it to be formatted in the similar numbers that the weather routing programs would use it
-    for i in 0..polar_plot_data_vector.len() {
-        // We only need one side of the polar plot, let's use the right side, everything else can be mirrored afterwards (effectively potentially doubles the available data)
-        if polar_plot_data_vector[i][0] > 180.0 {
-            polar_plot_data_vector[i][0] = 360.0 - polar_plot_data_vector[i][0];
-        }
+    // If heading is None, set to empty string
+    let heading = match entry.heading {
+        Some(h) => h.to_string(),
+        None => String::from(""),
+    };
 
-        // Find the nearest wind angle (in 5° increments) to this wind angle
-        let nearest_angle_diff: f64 = polar_plot_data_vector[i][0] % working_degree_segment_size;
-        let nearest_angle: f64;
-        if nearest_angle_diff < working_degree_segment_size/2.0 {
-            // Round down to the nearest working_degree_segment_size
-            nearest_angle = polar_plot_data_vector[i][0] - nearest_angle_diff;
-        } else {
-            // Round up to the nearest working_degree_segment_size
-            nearest_angle = polar_plot_data_vector[i][0] + (working_degree_segment_size - nearest_angle_diff);
-        }
+    // If true_bearing is None, set to empty string
+    let true_bearing = match entry.true_bearing {
+        Some(tb) => tb.to_string(),
+        None => String::from(""),
+    };
 
-        // Find the row in the standard_data_vector that corresponds to this nearest angle. Rows given in 5° increments until OpenCPN polar plugin accepts other angles
-        // Use this when the OpenCPN polar plugin accepts custom angles: let row: usize = (nearest_angle/working_degree_segment_size) as usize;
-        let row: usize = (nearest_angle/5.0) as usize;
+    // If draught is None, set to empty string
+    let draft = match entry.draft {
+        Some(d) => d.to_string(),
+        None => String::from(""),
+    };
 
-        // Find the nearest wind speed (in 1 m/s increments) to this wind speed
-        let nearest_wind_speed_diff: f64 = polar_plot_data_vector[i][1] % working_wind_speed_segment_size;
-        let mut nearest_wind_speed: f64;
-        if nearest_wind_speed_diff < working_wind_speed_segment_size/2.0 {
-            // Round down to the nearest working_wind_speed_segment_size
-            nearest_wind_speed = polar_plot_data_vector[i][1] - nearest_wind_speed_diff;
-        } else {
-            // Round up to the nearest working_wind_speed_segment_size
-            nearest_wind_speed = polar_plot_data_vector[i][1] + (working_wind_speed_segment_size - nearest_wind_speed_diff);
-        }
-        // If the nearest wind speed is set to zero then the column will be zero so we move the nearest wind speed up one segment size
-        if nearest_wind_speed == 0.0 {
-            nearest_wind_speed += working_wind_speed_segment_size;
-        }
+    // If navigation_status is None, set to empty string
+    let navigation_status = match &entry.navigation_status {
+        Some(ns) => (*ns as u64).to_string(),
+        None => String::from(""),
+    };
 
-        // Find the row in the standard_data_vector that corresponds to this nearest angle
-        let column: usize = (nearest_wind_speed/working_wind_speed_segment_size) as usize;
+    // If depth is None, set to empty string
+    let depth = match entry.depth {
+        Some(d) => d.to_string(),
+        None => String::from(""),
+    };
 
-        // Input the value into the standard data vectors
-        // Inputting into min vector
-        if standard_data_vector_min[row][column].1.is_some() {
-            // Get current number of values used to make the minimum
-            let current_n: usize = standard_data_vector_min[row][column].0;
-            // Get current minimum vessel speed
-            let current_speed: f64 = standard_data_vector_min[row][column].1.unwrap();
-            // If the new value is lesser than the current value in the standard data vector, replace it
-            if polar_plot_data_vector[i][2] < current_speed {
-                standard_data_vector_min[row][column] = (current_n + 1, Some(polar_plot_data_vector[i][2]));
-            }
-        }
-        // Otherwise if this is the first value, put it directly into the standard_data_vector and set the counter to 1.
-        else {
-            standard_data_vector_min[row][column] = (1, Some(polar_plot_data_vector[i][2]));
-        }
+    // If wind is None, set to empty strings
+    let (wind_speed, wind_angle) = match entry.wind {
+        Some(w) => (w.magnitude.to_string(), w.angle.to_string()),
+        None => (String::from(""), String::from("")),
+    };
 
-        // Inputting into mean vector
+    // If wave height is None, set to empty string
+    let wave_height = match entry.wave_height {
+        Some(h) => h.to_string(),
+        None => String::from(""),
+    };
 
-        // TODO: Improvement idea: If there are any values in the standard_data_vector in that index and the index that surrounds the current value, linearly interpolate the current value in the direction of the index
-        // Let's average it directly and skip the linear interpolation for now, adding an issue about it
-        if standard_data_vector_mean[row][column].1.is_some() {
-            // Get current number of values used to make the average
-            let current_n: usize = standard_data_vector_mean[row][column].0;
-            // Get current average vessel speed
-            let current_speed: f64 = standard_data_vector_mean[row][column].1.unwrap();
-            // Make new average vessel speed by adding the new value and incrementing the number of values used to make the average
-            standard_data_vector_mean[row][column] = (current_n + 1, Some((current_speed*(current_n as f64) + polar_plot_data_vector[i][2])/((current_n + 1) as f64)));
-        }
-        // Otherwise if this is the first value, assume it stays the same and put it directly into the standard_data_vector
-        else {
-            standard_data_vector_mean[row][column] = (1, Some(polar_plot_data_vector[i][2]));
-        }
+    // If track angle is None, set to empty string
+    let track_angle = match entry.track_angle {
+        Some(ta) => ta.to_string(),
+        None => String::from(""),
+    };
 
-        // Inputting into max vector
-        if standard_data_vector_max[row][column].1.is_some() {
-            // Get current number of values used to make the maximum
-            let current_n: usize = standard_data_vector_max[row][column].0;
-            // Get current maximum vessel speed
-            let current_speed: f64 = standard_data_vector_max[row][column].1.unwrap();
-            // If the new value is greater than the current value in the standard data vector, replace it
-            if polar_plot_data_vector[i][2] > current_speed {
-                standard_data_vector_max[row][column] = (current_n + 1, Some(polar_plot_data_vector[i][2]));
-            }
-        }
-        // Otherwise if this is the first value, put it directly into the standard_data_vector and set the counter to 1.
-        else {
-            standard_data_vector_max[row][column] = (1, Some(polar_plot_data_vector[i][2]));
+    // If cross track error is None, set to empty string
+    let cross_track_error = match entry.cross_track_error {
+        Some(xte) => xte.to_string(),
+        None => String::from(""),
+    };
+
+    // If foiling is None, set to empty string
+    let foiling = match entry.foiling {
+        Some(f) => f.to_string(),
+        None => String::from(""),
+    };
+
+    return [
+        format_timestamp(entry.timestamp),
+        format!("{},{}", entry.coordinates_initial.y(), entry.coordinates_initial.x()),
+        format!("{},{}", entry.coordinates_current.y(), entry.coordinates_current.x()),
+        format!("{},{}", entry.coordinates_final.y(), entry.coordinates_final.x()),
+        cargo,
+        velocity,
+        course,
+        heading,
+        true_bearing,
+        draft,
+        navigation_status,
+        depth,
+        wind_speed,
+        wind_angle,
+        wave_height,
+        velocity_angle,
+        track_angle,
+        cross_track_error,
+        foiling,
+    ];
+}
+
+/// Where the ship log of each simulated voyage is written when the simulation streams its ship logs, see Simulation.set_ship_log_writer()
+/// Implemented by CsvShipLogWriter and, with the "parquet" feature, ShipLogParquetWriter
+pub trait ShipLogWriter: Send {
+    /// Writes the ship log of a voyage
+    fn write_ship_log(&mut self, ship_log: &[ShipLogEntry]) -> Result<(), io::Error>;
+
+    /// Called when all voyages have been written, e.g. to flush buffers or write a file footer
+    fn finish(&mut self) -> Result<(), io::Error> {
+        return Ok(());
+    }
+}
+
+/// Writes ship logs to a CSV file one voyage at a time, in the same format as ship_logs_to_csv()
+/// # Example:
+/// `simulation.set_ship_log_writer(CsvShipLogWriter::create("ship_logs.csv")?);`
+pub struct CsvShipLogWriter {
+    writer: csv::Writer<File>,
+}
+
+impl CsvShipLogWriter {
+    /// Creates the CSV file and writes the header
+    /// Will overwrite any file with the same file name at csv_file_path
+    pub fn create(csv_file_path: &str) -> Result<CsvShipLogWriter, MarineSimError> {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(b';')
+            .has_headers(true)
+            .from_path(csv_file_path)?;
+        writer.write_record(&SHIP_LOG_CSV_HEADER)?;
+        return Ok(CsvShipLogWriter {
+            writer,
+        });
+    }
+}
+
+impl ShipLogWriter for CsvShipLogWriter {
+    fn write_ship_log(&mut self, ship_log: &[ShipLogEntry]) -> Result<(), io::Error> {
+        for entry in ship_log {
+            self.writer.write_record(&get_ship_log_csv_record(entry))?;
         }
+        return self.writer.flush();
     }
 
-    // TODO See issue https://github.com/G0rocks/marine_vessel_simulator/issues/62
-    // Check if standard_data_vector contains any values. If it does not then return error
+    fn finish(&mut self) -> Result<(), io::Error> {
+        return self.writer.flush();
+    }
+}
 
-    // Now that the underlying polar plot data is ready, save the results to a csv file
-    // Saving the file with the minimum data
-    // Create a CSV writer with a semicolon delimiter
-    let mut writer_min = csv::WriterBuilder::new()
+/// Makes a CsvField error for a field of a CSV record, with the line number of the record
+fn csv_field_error(record: &csv::StringRecord, field: &str, file_path: &str, message: String) -> MarineSimError {
+    return MarineSimError::CsvField {
+        file: file_path.to_string(),
+        line: record.position().map_or(0, |position| position.line()),
+        field: field.to_string(),
+        message,
+    };
+}
+
+/// Gets a field from a CSV record, returns a CsvField error if the record does not have it
+fn get_csv_field<'a>(record: &'a csv::StringRecord, index: usize, field: &str, file_path: &str) -> Result<&'a str, MarineSimError> {
+    return record.get(index).ok_or_else(|| csv_field_error(record, field, file_path, format!("Missing column {}", index + 1)));
+}
+
+/// Converts a field from a CSV record with convert, e.g. string_to_point(), returns a CsvField error if the field is missing or can not be converted
+fn convert_csv_field<T, F: FnOnce(String) -> Result<T, MarineSimError>>(record: &csv::StringRecord, index: usize, field: &str, file_path: &str, convert: F) -> Result<T, MarineSimError> {
+    let value = get_csv_field(record, index, field, file_path)?;
+    return convert(value.to_string()).map_err(|e| csv_field_error(record, field, file_path, format!("{:?}: {}", value, e)));
+}
+
+/// Parses a field from a CSV record, returns a CsvField error if the field is missing, empty or can not be parsed
+fn parse_required_csv_field<T: std::str::FromStr>(record: &csv::StringRecord, index: usize, field: &str, file_path: &str) -> Result<T, MarineSimError> where T::Err: fmt::Display {
+    return parse_csv_field::<T>(record, index, field, file_path)?.ok_or_else(|| csv_field_error(record, field, file_path, "Missing value".to_string()));
+}
+
+/// Parses a field from a CSV record, returns None if the field is missing or empty and a CsvField error if it can not be parsed
+fn parse_csv_field<T: std::str::FromStr>(record: &csv::StringRecord, index: usize, field: &str, file_path: &str) -> Result<Option<T>, MarineSimError> where T::Err: fmt::Display {
+    return match record.get(index).map(|value| value.trim()) {
+        None | Some("") => Ok(None),
+        Some(value) => match value.parse::<T>() {
+            Ok(parsed) => Ok(Some(parsed)),
+            Err(e) => Err(csv_field_error(record, field, file_path, format!("{:?}: {}", value, e))),
+        },
+    };
+}
+
+/// The reciprocal function to ship_logs_to_csv takes a csv file and returns the ship logs.
+/// Function that writes the ship logs to a CSV file with the following columns:
+/// timestamp;coordinates_initial;coordinates_current;coordinates_final;cargo_on_board
+/// Note that the coordinates are in the format of ISO 6709 using decimal places with a comma between latitude and longitude. "latitude,longitude" (e.g., "52.5200,13.4050")
+/// The cargo is considered to be in metric tons (1 metric ton = 1000 kg)
+/// csv_file_path: Path to the CSV file
+/// boat: The boat object containing the ship logs
+/// Note: The csv file delimiter is a semicolon
+/// Note: Lines that can not be read are skipped with a message, use ship_log_from_csv() to get an error instead
+/// Note: Files with fewer columns than ship_logs_to_csv() writes can be read, the missing columns are None, see get_ship_log_entry_from_csv_record()
+pub fn csv_to_ship_log(csv_file_path: &str) -> Result<Vec<ShipLogEntry>, MarineSimError> {
+    // Check if the file ends with ".csv" and if it does not return an error
+    if csv_file_path.chars().rev().take(4).collect::<Vec<_>>().into_iter().rev().collect::<String>() != ".csv" {
+        return Err(MarineSimError::InvalidInput(format!("File path does not end with .csv\nFile: {:?}", csv_file_path)));
+    }
+ 
+    // Init empty Ship Log book
+    let mut ship_log = vec![];
+
+    // Read the CSV file
+    let mut csv_reader = csv::ReaderBuilder::new()
         .delimiter(b';')
         .has_headers(true)
-        .from_path(working_file_path_min)?;
+        .from_path(csv_file_path)?;
 
-    // Write the header
-    let mut header_vec: Vec<String> = Vec::new();
-    // First column in the header is "TWA\TWS" and not "wind angle [°]" because that is what openCPN uses
-    header_vec.push("TWA\\TWS".to_string());
-    for i in 1..(num_wind_speed_segments+1) {
-        // If knots, format in knots
-        if true_if_knots_false_if_meters_per_second {
-            header_vec.push(format!("{}", (i as f64)*working_wind_speed_segment_size*1.94384));
-        } // Otherwise use meters per second (preferred)
-        else {
-            header_vec.push(format!("{}", (i as f64)*working_wind_speed_segment_size));
+    // Loop through all lines of file, append each line to the ship log
+    for result in csv_reader.records() {
+        match result {
+            Ok(entry) => {
+                let new_entry = get_ship_log_entry_from_csv_record(&entry, ship_log.last(), csv_file_path)?;
+                ship_log.push(new_entry);
+                }
+            Err(err) => {
+                tracing::warn!("Error reading ship log entry: {}", err);
+                }
+            }
         }
+
+    // Return ship log
+    return Ok(ship_log);
+}
+
+/// Reads a ship log CSV file written by ship_logs_to_csv() or CsvShipLogWriter back into the exact ship log entries that were written, e.g. to reload, merge and re-analyze simulated voyages
+/// Every field of ShipLogEntry is read, including the direction of the velocity and the track angle. Timestamps are written to the second so fractions of seconds are lost
+/// Unlike csv_to_ship_log(), the header must be the header ship_logs_to_csv() writes and a line that can not be read is an error
+/// Files from before the velocity angle, track angle and cross track error columns were added are read as in csv_to_ship_log()
+/// # Example:
+/// `ship_logs_to_csv("voyage.csv", &boat.ship_log)?;`
+/// `let ship_log = ship_log_from_csv("voyage.csv")?;`
+pub fn ship_log_from_csv(csv_file_path: &str) -> Result<Vec<ShipLogEntry>, MarineSimError> {
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(csv_file_path)?;
+
+    check_ship_log_header(csv_reader.headers()?, csv_file_path)?;
+
+    let mut ship_log: Vec<ShipLogEntry> = Vec::new();
+    for result in csv_reader.records() {
+        let record = result?;
+        let entry = get_ship_log_entry_from_csv_record(&record, ship_log.last(), csv_file_path)?;
+        ship_log.push(entry);
+    }
+    return Ok(ship_log);
+}
+
+/// Returns an error if the header of a ship log file is not the header ship_logs_to_csv() writes, or the start of it for files from before the velocity angle, track angle, cross track error and foiling columns were added
+fn check_ship_log_header(header: &csv::StringRecord, file_path: &str) -> Result<(), MarineSimError> {
+    let num_columns = header.len();
+    if num_columns < SHIP_LOG_CSV_HEADER.len() - 4 || num_columns > SHIP_LOG_CSV_HEADER.len() || header.iter().zip(SHIP_LOG_CSV_HEADER.iter()).any(|(column, expected)| column.trim() != *expected) {
+        return Err(MarineSimError::CsvParse {
+            file: file_path.to_string(),
+            message: format!("The header is not the header ship_logs_to_csv() writes: {}", header.iter().collect::<Vec<&str>>().join(";")),
+        });
+    }
+    return Ok(());
+}
+
+/// Reads a ship log entry from a record of a ship log CSV file, see ship_logs_to_csv()
+/// Optional fields that are empty or missing are None. In files without the velocity angle column the course is used as the direction of the velocity,
+/// and in files without the track angle column the track angle is the bearing from the previous entry
+/// previous: The previous entry of the ship log, None for the first entry
+fn get_ship_log_entry_from_csv_record(entry: &csv::StringRecord, previous: Option<&ShipLogEntry>, csv_file_path: &str) -> Result<ShipLogEntry, MarineSimError> {
+    let timestamp = convert_csv_field(entry, 0, "timestamp", csv_file_path, string_to_utc_date_time)?;
+    // The coordinates are written as "latitude,longitude", see string_to_point()
+    let coordinates_initial = convert_csv_field(entry, 1, "coordinates_initial", csv_file_path, string_to_point)?;
+    let coordinates_current = convert_csv_field(entry, 2, "coordinates_current", csv_file_path, string_to_point)?;
+    let coordinates_final = convert_csv_field(entry, 3, "coordinates_final", csv_file_path, string_to_point)?;
+    // If there is no cargo written down, set to None
+    let cargo_on_board = parse_csv_field::<f64>(entry, 4, "cargo_on_board", csv_file_path)?.map(|cargo| uom::si::f64::Mass::new::<uom::si::mass::ton>(cargo));
+    // If no course written down, set to None
+    let course = parse_csv_field::<f64>(entry, 6, "course", csv_file_path)?;
+    // If speed and direction are known, set velocity PhysVec to use them otherwise set velocity to None. Files from before the velocity angle column use the course
+    let velocity_angle = match entry.get(15) {
+        Some(_) => parse_csv_field::<f64>(entry, 15, "velocity_angle", csv_file_path)?,
+        None => course,
+    };
+    let velocity: Option<PhysVec> = match (parse_csv_field::<f64>(entry, 5, "velocity", csv_file_path)?, velocity_angle) {
+        (Some(speed), Some(angle)) => Some(PhysVec::new(speed, angle)),
+        _ => None,
+    };
+    // if there is a heading written down, set the heading to that, otherwise, set to None
+    let heading = parse_csv_field::<f64>(entry, 7, "heading", csv_file_path)?;
+    // Track angle is between last and current ship log entry, if this is the first entry, set to None. Files from before the track angle column get it from the previous entry
+    let track_angle = match entry.get(16) {
+        Some(_) => parse_csv_field::<f64>(entry, 16, "track_angle", csv_file_path)?,
+        None => previous.map(|last_entry| Earth.bearing(last_entry.coordinates_current, coordinates_current)),
+    };
+    // If no true_bearing written down, set to None
+    let true_bearing = parse_csv_field::<f64>(entry, 8, "true_bearing", csv_file_path)?;
+    // If no draft written doen, set to None
+    let draft = parse_csv_field::<f64>(entry, 9, "draught", csv_file_path)?;
+    // If no navigation status written down, set to None
+    let navigation_status: Option<NavigationStatus> = match parse_csv_field::<u8>(entry, 10, "navigation_status", csv_file_path)? {
+        Some(navstat) => NavigationStatus::try_from(navstat).ok(),
+        None => None,
+    };
+    // If no depth written down, or the file is from before the depth column was added, set to None
+    let depth = parse_csv_field::<f64>(entry, 11, "depth", csv_file_path)?;
+    // If no wind written down, set to None
+    let wind = match (parse_csv_field::<f64>(entry, 12, "wind_speed", csv_file_path)?, parse_csv_field::<f64>(entry, 13, "wind_angle", csv_file_path)?) {
+        (Some(speed), Some(angle)) => Some(PhysVec::new(speed, angle)),
+        _ => None,
+    };
+    // If no wave height written down, or the file is from before the wave height column was added, set to None
+    let wave_height = parse_csv_field::<f64>(entry, 14, "wave_height", csv_file_path)?;
+    // If no cross track error written down, or the file is from before the cross track error column was added, set to None
+    let cross_track_error = parse_csv_field::<f64>(entry, 17, "cross_track_error", csv_file_path)?;
+    // If not known if the vessel was foiling, or the file is from before the foiling column was added, set to None
+    let foiling = parse_csv_field::<bool>(entry, 18, "foiling", csv_file_path)?;
+
+    return Ok(ShipLogEntry {
+        timestamp,
+        coordinates_initial,
+        coordinates_current,
+        coordinates_final,
+        cargo_on_board,
+        velocity,
+        course,
+        heading,
+        track_angle,
+        true_bearing,
+        draft,
+        navigation_status,
+        depth,
+        wind,
+        wave_height,
+        cross_track_error,
+        foiling,
+    });
+}
+
+/// Replays a ship log exported with ship_logs_to_csv(), e.g. from an earlier simulation or a real voyage, without running the simulator again
+/// Returns a new boat that went through every entry of the ship log in order, see Boat.replay_ship_log_entry(), so it ends in the state of the last entry with the whole ship log in boat.ship_log
+/// Useful to plot, compute statistics of or validate earlier voyages
+/// # Example:
+/// `let boat = replay_ship_log("results/voyage_1_ship_log.csv")?;`
+/// `let kpis = VoyageKpis::from_ship_log(&boat.ship_log);`
+pub fn replay_ship_log(csv_file_path: &str) -> Result<Boat, MarineSimError> {
+    let ship_log = csv_to_ship_log(csv_file_path)?;
+    if ship_log.is_empty() {
+        return Err(MarineSimError::CsvParse { file: csv_file_path.to_string(), message: "Ship log has no entries".to_string() });
+    }
+
+    let mut boat = Boat::new();
+    for entry in ship_log {
+        boat.replay_ship_log_entry(entry);
+    }
+    return Ok(boat);
+}
+
+/// Function that translates coordinates to x,y values between 0 and 1 for plotting
+pub fn geo_point_to_xy(point_in: geo::Point) -> (f32, f32) {
+    // Normalize latitude to 0..1 where 0.5 is equator
+    let y = (-point_in.y() + 90.0) / 180.0;
+    // Normalize longitude to 0..1 where 0.5 is prime meridian
+    let x = (point_in.x() + 180.0) / 360.0;
+
+    // Return the coordinates as a tuple
+    return (x as f32, y as f32);
+}
+
+/// Function that gets the angle from north given the northward PhysVec property (effectively, the magnitude going from north to south) and eastward PhysVec property (effectively, the magnitude going from west to east)
+pub fn get_north_angle_from_northward_and_eastward_property(eastward: f64, northward: f64) -> f64 {
+    let mut north_angle = northward.atan2(eastward) * 180.0 / std::f64::consts::PI;
+
+    // Adjust result from atan2 to be between 0 and 360
+    if north_angle < 0.0 {
+        north_angle += 360.0;
     }
     
-    writer_min.write_record(&header_vec)?;
+    // transform angle to be based from north not east
+    north_angle -= 90.0;
 
-    // Write the standard_data_vector into the csv file
-    for row in standard_data_vector_min.iter() {
-        // Init empty record to write
-        let mut record: Vec<String> = Vec::new();
-        // Add the wind angle to the first column
-        // record.push(row[0].0);
+    // Adjusting if went out of bounds
+    while north_angle >= 360.0 {
+        north_angle -= 360.0;
+    }
+    while north_angle < 0.0 {
+        north_angle += 360.0;
+    }
 
-        // For the rest of the row, if the boat speed is None, add an empty string, else, add the boat speed to the record
-        // Note: The first cell should always be some as it should include the apparent wind angle
-        for (i, cell) in row.iter().enumerate() {
-            // If the value is Some, add it
-            if cell.1.is_some() {
-                // If it's the first column, just add it since it is the angle of the wind
-                if i == 0 {
-                    record.push(cell.1.unwrap().to_string());
-                } // Otherwise, check if we're using knots or meters per second
-                else {
-                    // If knots, transform to knots
-                    if true_if_knots_false_if_meters_per_second {
-                        record.push((cell.1.unwrap() * 1.94384).to_string());
-                    } // Otherwise, use meters_per_second
-                    else {
-                        record.push(cell.1.unwrap().to_string());
-                    }
+    return north_angle;
+}
+
+/// Segments a waypoint mission
+pub fn segment_waypoint_mission(route_plan: Vec<SailingLeg>, n_segments: u64) -> (Vec<geo::Point>, f64) {
+    // Get total length of route, in meters, if going shortest path
+    let mut total_dist: f64 = 0.0;
+
+    // For each leg, add leg distance to total_dist
+    for leg in &route_plan {
+        total_dist += leg.length();
+    }
+
+    // Get number of segments with a sanity check against zero n_segments:
+    let n: u64;
+    if n_segments < 1 {
+        n = 1;
+    } else {
+        n = n_segments;
+    }
+
+    // Get length of each segment
+    let segment_dist: f64 = total_dist/(n as f64);
+
+    // Make points of analysis using the path along the waypoint route and the n_segments from the simulaion
+    // Init with first point
+    let mut waypoints: Vec<geo::Point> = vec![route_plan[0].p1];
+
+    // Until the last waypoint has been reached, travel the segment_dist along the path and add the waypoint to the waypoints vector
+    // Init distance left of segment
+    let mut seg_dist_left: f64;
+    // Init leg number
+    let mut current_leg = 0;
+    // Init boat location
+    let mut location = route_plan[0].p1;
+    let mut num_points = 1;
+    while waypoints.last().unwrap() != &route_plan.last().unwrap().p2 {
+        // If number of waypoints is number of segments then the next waypoint should be the last point, make it happen explicitly since floating point error can
+        // cause issues where we have 1 too many points otherwise
+        if num_points == n_segments {
+            waypoints.push(route_plan.last().unwrap().p2);
+            break;
+        }
+        // Reset seg_dist_left
+        seg_dist_left = segment_dist.clone();
+
+        // While there is still distance left in seg_dist_left, keep going towards the next point on the leg
+        while seg_dist_left > 0.0 {
+            // Get p2 of the current leg
+            let p2 = route_plan[(current_leg) as usize].p2;
+
+            // Get distance to next waypoint
+            let dist_to_next_waypoint = Earth.distance(location, p2);
+
+            // If distance to next waypoint is shorter or same as segment distance left, then travel to waypoint, update boat location and leg number and reduce segment distance left
+            if dist_to_next_waypoint <= seg_dist_left {
+                location = p2;
+                current_leg = current_leg + 1;
+
+                seg_dist_left -= dist_to_next_waypoint;
+
+                // If last point, set seg_dist_left to zero
+                if location == route_plan.last().unwrap().p2 {
+                    seg_dist_left = 0.0;
                 }
-            } // Otherwise, add empty string
+            }
+            // Else, travel the segment distance along the path between the last waypoint and the next waypoint, set the seg_dist_left to zero and append that point to waypoints
             else {
-                record.push(String::new());
+                let heading = Earth.bearing(location, p2);
+                location = Earth.destination(location, heading, seg_dist_left);
+                seg_dist_left = 0.0;
             }
         }
+        // Add location
+        waypoints.push(location);
+        num_points += 1;
+    }
+
+    // Return waypoints and segment distance
+    return (waypoints, segment_dist);
+}
+
+/// Downloads the weather data needed to run the fast_sim_waypoint_mission_weather_data_from_copernicus
+/// points: the locations to get weather data for
+/// timestamp: the time that the weather happened
+/// path_to_file: where to save the data
+#[cfg(feature = "copernicus")]
+pub fn get_weather_data_for_points(points: Vec<geo::Point>, timestamp: UtcDateTime, path_to_file: String, copernicus: copernicusmarine_rs::Copernicus) -> Result<String, MarineSimError> {
+    tracing::info!("Getting weather data");
+    // Initialize weather data vectors
+    let mut wind_vec: Vec<PhysVec> = Vec::new();
+    let mut ocean_current_vec: Vec<Option<PhysVec>> = Vec::new();
+    // Get number of points
+    let num_points = points.len();
+
+    // Start a progress bar with twice the tasks as num_points
+    let progress_bar = indicatif::ProgressBar::new((num_points) as u64);
+    // Set progress bar style
+    progress_bar.set_style(indicatif::ProgressStyle::with_template("[{elapsed_precise}] {bar} {pos:>3}/{len:3} ETA:{duration_precise:>1}").unwrap()); //.progress_chars("##-"));
+    // Configure live redraw
+    progress_bar.set_draw_target(indicatif::ProgressDrawTarget::stdout());
+    progress_bar.enable_steady_tick(std::time::Duration::from_millis(500));
+    // Start progress bar
+    progress_bar.inc(0);
+
+    // For each point, get the wind and current
+    for i in 0..num_points {
+        // Get the wind data
+        let dataset_id: String = match copernicusmarine_rs::get_dataset_id(copernicusmarine_rs::CopernicusVariable::EastwardWind, timestamp, timestamp) {
+            Ok(id) => id,
+            Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting dataset id from copernicusmarine: {}", e))),
+        };
+        // let wind_data = match copernicus.get_f64_values("cmems_obs-wind_glo_phy_nrt_l4_0.125deg_PT1H".to_string(), vec!["eastward_wind".to_string(), "northward_wind".to_string()], timestamp, timestamp, points[i].x(), points[i].x(), points[i].y(), points[i].y(), None, None) {
+        let wind_data = match copernicus.get_f64_values(dataset_id, vec!["eastward_wind".to_string(), "northward_wind".to_string()], timestamp, timestamp, points[i].x(), points[i].x(), points[i].y(), points[i].y(), None, None) {
+            Ok(w) => w,
+            Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting wind data from copernicusmarine: {}", e))),
+        };
+        // Wind speed and direction
+        let (wind_east, wind_north): (f64, f64) = match get_copernicus_east_north(&wind_data, "wind")? {
+            (Some(east), Some(north)) => (east, north),
+            _ => return Err(MarineSimError::WeatherFetch("No wind data from copernicusmarine".to_string())),
+        };
+        let wind_angle: f64 = get_north_angle_from_northward_and_eastward_property(wind_east, wind_north);   // Angle in degrees
+        let wind_speed = uom::si::f64::Velocity::new::<uom::si::velocity::meter_per_second>((wind_east*wind_east + wind_north*wind_north).sqrt().into());
+        wind_vec.push(PhysVec::new(wind_speed.get::<uom::si::velocity::meter_per_second>(), wind_angle));    // unit [m/s]
+
+        // Get ocean current data from Copernicus
+        // "uo" is the eastward sea water velocity and "vo" is the northward sea water velocity
+        let dataset_id: String = match copernicusmarine_rs::get_dataset_id(copernicusmarine_rs::CopernicusVariable::EastwardSeaWaterVelocity, timestamp, timestamp) {
+            Ok(id) => id,
+            Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting dataset id from copernicusmarine: {}", e))),
+        };
+        // let ocean_current_data = match copernicus.get_f64_values("cmems_mod_glo_phy-cur_anfc_0.083deg_PT6H-i".to_string(), vec!["uo".to_string(), "vo".to_string()], timestamp, timestamp, points[i].x(), points[i].x(), points[i].y(), points[i].y(), Some(1.0), Some(1.0)){
+        // let ocean_current_data = match copernicus.get_f64_values(dataset_id, vec!["uo".to_string(), "vo".to_string()], timestamp, timestamp, points[i].x(), points[i].x(), points[i].y(), points[i].y(), Some(0.49402499198913574), Some(0.49402499198913574)){
+        // let ocean_current_data = match copernicus.get_f64_values(dataset_id, vec!["uo".to_string(), "vo".to_string()], timestamp, timestamp, points[i].x(), points[i].x(), points[i].y(), points[i].y(), Some(0.0), Some(50.0)){
+        let ocean_current_data = match copernicus.get_f64_values(dataset_id, vec!["uo".to_string(), "vo".to_string()], timestamp, timestamp, points[i].x(), points[i].x(), points[i].y(), points[i].y(), Some(0.0), Some(1.0)){
+            Ok(o) => o,
+            Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting ocean current data from copernicusmarine: {}", e))),
+        };
+        // Ocean current speed and direction
+        // If we don't have ocean_current data, push None to ocean_current_vec.
+        let (ocean_current_east, ocean_current_north) = get_copernicus_east_north(&ocean_current_data, "ocean current")?;
+        if ocean_current_east.is_none() && ocean_current_north.is_none() {
+            ocean_current_vec.push(None);
+        }
+        else {
+            let ocean_current_east: f64 = ocean_current_east.unwrap_or(0.0);
+            let ocean_current_north: f64 = ocean_current_north.unwrap_or(0.0);
+            let ocean_current_angle: f64 = get_north_angle_from_northward_and_eastward_property(ocean_current_east, ocean_current_north);   // Angle in degrees
+            let ocean_current_speed = uom::si::f64::Velocity::new::<uom::si::velocity::meter_per_second>((ocean_current_east*ocean_current_east + ocean_current_north*ocean_current_north).sqrt().into());
+            ocean_current_vec.push(Some(PhysVec::new(ocean_current_speed.get::<uom::si::velocity::meter_per_second>(), ocean_current_angle)));    // unit [m/s]
+        }
+
+        // Update progress bar
+        progress_bar.inc(1);
+    }
+
+    // Save all the points in a csv file
+    // Check if csv_file_path ends with ".csv"
+    if !check_file_extension(&path_to_file, ".csv") {
+        return Err(MarineSimError::InvalidInput("The filepath must end with \".csv\"".to_string()));
+    }
+
+
+    // Check if vectors are the same size
+    if &wind_vec.len() != &ocean_current_vec.len() || wind_vec.len() != num_points {
+        return Err(MarineSimError::InvalidInput("All vectors must have the same length".to_string()));
+    }
+
+    // Create a CSV writer with a semicolon delimiter
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(path_to_file)?;
+
+    // Write the header
+    wtr.write_record(&["time","longitude","latitude","wind_speed[m/s]","wind_angle[°]","ocean_current_speed[m/s]","ocean_current_angle[°]"])?;
+
+    // Write the ship log entries
+    for i in 0..num_points {
+        // Get longitude
+        let longitude = points[i].x().to_string();
+        // Get latitude
+        let latitude = points[i].y().to_string();
+        // Get wind_speed
+        let wind_speed = wind_vec[i].magnitude.to_string();
+        // Get wind_angle
+        let wind_angle = wind_vec[i].angle.to_string();
+        // Get ocean_current_speed and angle
+        let ocean_current_speed: String;
+        let ocean_current_angle: String;
+        if ocean_current_vec[i].is_some() {
+            ocean_current_speed = ocean_current_vec[i].unwrap().magnitude.to_string();
+            ocean_current_angle = ocean_current_vec[i].unwrap().angle.to_string();
+        }
+        else {
+            ocean_current_speed = "".to_string();
+            ocean_current_angle = String::new();
+            // ocean_current_speed = "None".to_string();
+            // ocean_current_angle = "None".to_string();
+        }
+
+        // Write the record
+        wtr.write_record(&[
+            copernicusmarine_rs::utc_date_time_to_string(timestamp),
+            longitude,
+            latitude,
+            wind_speed,
+            wind_angle,
+            ocean_current_speed,
+            ocean_current_angle,])?;        
+    }
+
+    // Flush and close the writer
+    wtr.flush()?;
+
+    // Finish progress_bar
+    progress_bar.finish();
+
+    // Return ok
+    return Ok("weather data retrieved and saved successfully".to_string());
+}
+
+/// Gets the significant wave height in meters at a location and time from copernicus marine
+/// Returns None if copernicus has no wave data for the location (e.g. on land or in sea ice)
+/// Note: Uses the global ocean waves analysis and forecast dataset which only covers the last few years
+#[cfg(feature = "copernicus")]
+pub fn get_wave_height_from_copernicus(copernicus: &copernicusmarine_rs::Copernicus, timestamp: UtcDateTime, location: geo::Point) -> Result<Option<f64>, MarineSimError> {
+    return get_copernicus_value_at_point(copernicus, COPERNICUS_WAVE_DATASET_ID, COPERNICUS_WAVE_HEIGHT_VARIABLE, timestamp, location);
+}
+
+/// Gets the sea ice concentration at a location and time from copernicus marine
+/// The concentration is the fraction of the sea surface covered by ice, in \[0, 1\]
+/// Returns None if copernicus has no sea ice data for the location (e.g. on land)
+#[cfg(feature = "copernicus")]
+pub fn get_sea_ice_concentration_from_copernicus(copernicus: &copernicusmarine_rs::Copernicus, timestamp: UtcDateTime, location: geo::Point) -> Result<Option<f64>, MarineSimError> {
+    return get_copernicus_value_at_point(copernicus, COPERNICUS_SEA_ICE_DATASET_ID, COPERNICUS_SEA_ICE_VARIABLE, timestamp, location);
+}
+
+/// Gets the wind in \[m/s\] at a location and time from copernicus marine
+/// Returns an error if copernicus has no wind data for the location
+#[cfg(feature = "copernicus")]
+pub fn get_wind_from_copernicus(copernicus: &copernicusmarine_rs::Copernicus, timestamp: UtcDateTime, location: geo::Point) -> Result<PhysVec, MarineSimError> {
+    // Get the dataset id for the time
+    let dataset_id: String = match copernicusmarine_rs::get_dataset_id(copernicusmarine_rs::CopernicusVariable::EastwardWind, timestamp, timestamp) {
+        Ok(id) => id,
+        Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting dataset id from copernicusmarine: {}", e))),
+    };
+    // Get the wind data
+    let wind_data = match copernicus.get_f64_values(dataset_id, vec!["eastward_wind".to_string(), "northward_wind".to_string()], timestamp, timestamp, location.x(), location.x(), location.y(), location.y(), None, None) {
+        Ok(w) => w,
+        Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting wind data from copernicusmarine: {}", e))),
+    };
+
+    // Wind speed and direction
+    let (wind_east, wind_north) = match get_copernicus_east_north(&wind_data, "wind")? {
+        (Some(e), Some(n)) => (e, n),
+        _ => return Err(MarineSimError::WeatherFetch(format!("No wind data from copernicusmarine at {:?}", location))),
+    };
+    let wind_angle: f64 = get_north_angle_from_northward_and_eastward_property(wind_east, wind_north);   // Angle in degrees
+
+    // Return wind
+    return Ok(PhysVec::new((wind_east*wind_east + wind_north*wind_north).sqrt(), wind_angle));
+}
+
+/// Gets the ocean current in \[m/s\] at a location and time from copernicus marine, averaged over the top meter of the ocean
+/// Returns None if copernicus has no ocean current data for the location (e.g. on land)
+#[cfg(feature = "copernicus")]
+pub fn get_ocean_current_from_copernicus(copernicus: &copernicusmarine_rs::Copernicus, timestamp: UtcDateTime, location: geo::Point) -> Result<Option<PhysVec>, MarineSimError> {
+    // Get the dataset id for the time
+    // "uo" is the eastward sea water velocity and "vo" is the northward sea water velocity
+    let dataset_id: String = match copernicusmarine_rs::get_dataset_id(copernicusmarine_rs::CopernicusVariable::EastwardSeaWaterVelocity, timestamp, timestamp) {
+        Ok(id) => id,
+        Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting dataset id from copernicusmarine: {}", e))),
+    };
+    // Get the ocean current data
+    let ocean_current_data = match copernicus.get_f64_values(dataset_id, vec!["uo".to_string(), "vo".to_string()], timestamp, timestamp, location.x(), location.x(), location.y(), location.y(), Some(0.0), Some(1.0)) {
+        Ok(o) => o,
+        Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting ocean current data from copernicusmarine: {}", e))),
+    };
+
+    // Ocean current speed and direction
+    match get_copernicus_east_north(&ocean_current_data, "ocean current")? {
+        (Some(east), Some(north)) => return Ok(Some(PhysVec::new((east*east + north*north).sqrt(), get_north_angle_from_northward_and_eastward_property(east, north)))),
+        _ => return Ok(None),
+    }
+}
+
+/// Returns the first value of the eastward and the northward component of copernicus marine data with two variables, e.g. "eastward_wind" and "northward_wind"
+/// A component without data, e.g. on land, is None. Returns a WeatherFetch error if copernicus did not return both variables
+/// name: What the data is, used in the error message, e.g. "wind"
+#[cfg(feature = "copernicus")]
+pub(crate) fn get_copernicus_east_north(data: &[Vec<Option<f64>>], name: &str) -> Result<(Option<f64>, Option<f64>), MarineSimError> {
+    return match (data.first(), data.get(1)) {
+        (Some(east), Some(north)) => Ok((east.first().copied().flatten(), north.first().copied().flatten())),
+        _ => Err(MarineSimError::WeatherFetch(format!("Expected the eastward and northward {} from copernicusmarine, got {} variables", name, data.len()))),
+    };
+}
+
+/// Gets a single surface value of a copernicus marine variable at a location and time
+/// Returns None if copernicus has no data for the location
+#[cfg(feature = "copernicus")]
+fn get_copernicus_value_at_point(copernicus: &copernicusmarine_rs::Copernicus, dataset_id: &str, variable: &str, timestamp: UtcDateTime, location: geo::Point) -> Result<Option<f64>, MarineSimError> {
+    // Get the data
+    let data = match copernicus.get_f64_values(dataset_id.to_string(), vec![variable.to_string()], timestamp, timestamp, location.x(), location.x(), location.y(), location.y(), None, None) {
+        Ok(d) => d,
+        Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting {} data from copernicusmarine: {}", variable, e))),
+    };
+
+    // Return the first value, if there is one
+    return Ok(data.first().and_then(|v| v.first().copied()).flatten());
+}
+
+/// Gets the total time the vessel spent in a navigation status according to the ship log
+/// The time between two consecutive entries is counted if the later entry has the navigation status
+/// Useful for finding out how long a vessel was storm-bound, see StormTactic
+/// # Example:
+/// `let time_hove_to = get_time_in_navigation_status(&boat.ship_log, NavigationStatus::NotUnderCommand);`
+pub fn get_time_in_navigation_status(ship_log: &[ShipLogEntry], navigation_status: NavigationStatus) -> time::Duration {
+    // Init total time
+    let mut total_time = time::Duration::ZERO;
+
+    // Loop through consecutive entries
+    for i in 1..ship_log.len() {
+        if ship_log[i].navigation_status == Some(navigation_status) {
+            total_time += ship_log[i].timestamp - ship_log[i-1].timestamp;
+        }
+    }
+
+    // Return total time
+    return total_time;
+}
+
+/// Resamples a ship log to a fixed time interval, e.g. to make an hourly or daily log for plotting and sharing
+/// The resampled log starts with the first entry and has an entry every interval after that, the last entry of the ship log is always included so the resampled log ends where the ship log ends
+/// The positions are interpolated along the great circle between the surrounding entries, the other fields are taken from the entry before
+/// Returns the ship log as is if the interval is not positive
+/// # Example:
+/// `let hourly_log = resample_ship_log(&boat.ship_log, time::Duration::HOUR);`
+pub fn resample_ship_log(ship_log: &[ShipLogEntry], interval: time::Duration) -> Vec<ShipLogEntry> {
+    // Sanity check
+    if ship_log.len() < 2 || interval <= time::Duration::ZERO {
+        return ship_log.to_vec();
+    }
+
+    return resample_ship_log_with(ship_log, ShipLogResampler::new(interval, false));
+}
+
+/// Resamples a ship log to times that are whole multiples of the interval, e.g. on the hour for an interval of an hour, see resample_ship_log()
+/// Logs resampled with the same interval have the same timestamps, so logs with different sampling rates can be compared entry by entry, e.g. a simulated and a recorded voyage
+/// The first and last entries of the ship log are always included
+/// Returns the ship log as is if the interval is not positive
+/// # Example:
+/// `let simulated = resample_ship_log_aligned(&boat.ship_log, time::Duration::HOUR);`
+/// `let recorded = resample_ship_log_aligned(&ais_log, time::Duration::HOUR);`
+pub fn resample_ship_log_aligned(ship_log: &[ShipLogEntry], interval: time::Duration) -> Vec<ShipLogEntry> {
+    // Sanity check
+    if ship_log.len() < 2 || interval <= time::Duration::ZERO {
+        return ship_log.to_vec();
+    }
+
+    return resample_ship_log_with(ship_log, ShipLogResampler::new(interval, true));
+}
+
+/// Resamples a whole ship log with a resampler, see ShipLogResampler
+fn resample_ship_log_with(ship_log: &[ShipLogEntry], mut resampler: ShipLogResampler) -> Vec<ShipLogEntry> {
+    let mut resampled_log: Vec<ShipLogEntry> = Vec::new();
+    for entry in ship_log {
+        resampler.add(entry, &mut resampled_log);
+    }
+    resampler.finish(&mut resampled_log);
+    return resampled_log;
+}
+
+/// Resamples a ship log one entry at a time, so the decimated ship log of a voyage can be made while the voyage is simulated, see resample_ship_log() and Simulation.decimated_log_interval
+/// The resampled log has the first entry and then an entry every interval, from the first entry or from the first whole multiple of the interval if aligned
+/// Entries with a non-positive interval are passed through as they are
+#[derive(Debug, Clone)]
+pub(crate) struct ShipLogResampler {
+    interval: time::Duration,
+    /// If true the resample times are whole multiples of the interval, see resample_ship_log_aligned()
+    aligned: bool,
+    /// Time of the next resampled entry
+    next_time: UtcDateTime,
+    /// The last entry added
+    before: Option<ShipLogEntry>,
+    /// Timestamp and position of the last resampled entry
+    last_resampled: Option<(UtcDateTime, geo::Point)>,
+}
+
+impl ShipLogResampler {
+    /// Creates a resampler with the interval, aligned to whole multiples of the interval if aligned is true
+    pub(crate) fn new(interval: time::Duration, aligned: bool) -> ShipLogResampler {
+        ShipLogResampler {
+            interval,
+            aligned,
+            next_time: UtcDateTime::UNIX_EPOCH,
+            before: None,
+            last_resampled: None,
+        }
+    }
+
+    /// Adds the next entry of the ship log, the resampled entries up to it are pushed to resampled_log
+    pub(crate) fn add(&mut self, entry: &ShipLogEntry, resampled_log: &mut Vec<ShipLogEntry>) {
+        if self.interval <= time::Duration::ZERO {
+            resampled_log.push(entry.clone());
+            self.last_resampled = Some((entry.timestamp, entry.coordinates_current));
+        }
+        else if let Some(before) = &self.before {
+            // Add an entry for every resample time between the entries
+            while self.next_time < entry.timestamp {
+                // Skip entries with the same timestamp
+                if self.next_time >= before.timestamp {
+                    let fraction = (self.next_time - before.timestamp).as_seconds_f64() / (entry.timestamp - before.timestamp).as_seconds_f64();
+                    let mut resampled = before.clone();
+                    resampled.timestamp = self.next_time;
+                    resampled.coordinates_current = SailingLeg::new(before.coordinates_current, entry.coordinates_current, 0.0, 0.0).point_at(fraction);
+                    if let Some((_, last_location)) = self.last_resampled {
+                        resampled.track_angle = Some(Earth.bearing(last_location, resampled.coordinates_current));
+                    }
+                    self.last_resampled = Some((resampled.timestamp, resampled.coordinates_current));
+                    resampled_log.push(resampled);
+                }
+                self.next_time += self.interval;
+            }
+            // Resample time lands exactly on an entry, use the entry
+            if self.next_time == entry.timestamp {
+                resampled_log.push(entry.clone());
+                self.last_resampled = Some((entry.timestamp, entry.coordinates_current));
+                self.next_time += self.interval;
+            }
+        }
+        else {
+            // The resampled log starts with the first entry
+            self.next_time = match self.aligned {
+                true => get_first_aligned_time(entry.timestamp, self.interval),
+                false => entry.timestamp + self.interval,
+            };
+            resampled_log.push(entry.clone());
+            self.last_resampled = Some((entry.timestamp, entry.coordinates_current));
+        }
+        self.before = Some(entry.clone());
+    }
+
+    /// Pushes the last entry added to resampled_log if it is not there already, so the resampled log ends where the ship log ends
+    pub(crate) fn finish(&self, resampled_log: &mut Vec<ShipLogEntry>) {
+        if let (Some(last_entry), Some((last_timestamp, last_location))) = (&self.before, self.last_resampled) {
+            if last_timestamp != last_entry.timestamp {
+                let mut entry = last_entry.clone();
+                entry.track_angle = Some(Earth.bearing(last_location, entry.coordinates_current));
+                resampled_log.push(entry);
+            }
+        }
+    }
+}
+
+/// Returns the first whole multiple of the interval since the unix epoch after the timestamp, see resample_ship_log_aligned()
+fn get_first_aligned_time(timestamp: UtcDateTime, interval: time::Duration) -> UtcDateTime {
+    // Computed in nanoseconds so short intervals do not overflow
+    let since_epoch = (timestamp - UtcDateTime::UNIX_EPOCH).whole_nanoseconds();
+    let interval_nanoseconds = interval.whole_nanoseconds();
+    let first_nanoseconds = since_epoch.div_euclid(interval_nanoseconds)*interval_nanoseconds + interval_nanoseconds;
+    return UtcDateTime::UNIX_EPOCH + time::Duration::new((first_nanoseconds / 1_000_000_000) as i64, (first_nanoseconds % 1_000_000_000) as i32);
+}
+
+/// Latitude of the Arctic circle in degrees. It moves slowly with the tilt of the earth's axis, this is the value for 2025
+pub const ARCTIC_CIRCLE_LATITUDE: f64 = 66.5636;
+/// Latitude of the tropic of Cancer in degrees, 90° minus the latitude of the Arctic circle
+pub const TROPIC_LATITUDE: f64 = 90.0 - ARCTIC_CIRCLE_LATITUDE;
+/// Routes are split into pieces of at most this length, in \[m\], when looking for where they cross graticule lines
+const CROSSING_MAX_SEGMENT_LENGTH: f64 = 50000.0;
+
+/// Enum of notable lines on the map a route can cross, see get_route_plan_crossings()
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum GraticuleLine {
+    Equator,
+    ArcticCircle,
+    AntarcticCircle,
+    TropicOfCancer,
+    TropicOfCapricorn,
+    /// Meridian at a longitude in degrees, in (-180, 180\]
+    Meridian(f64),
+}
+
+impl GraticuleLine {
+    /// Returns the name of the line, e.g. for labels on maps and in voyage narratives
+    pub fn name(&self) -> String {
+        match self {
+            GraticuleLine::Equator => return "Equator".to_string(),
+            GraticuleLine::ArcticCircle => return "Arctic circle".to_string(),
+            GraticuleLine::AntarcticCircle => return "Antarctic circle".to_string(),
+            GraticuleLine::TropicOfCancer => return "Tropic of Cancer".to_string(),
+            GraticuleLine::TropicOfCapricorn => return "Tropic of Capricorn".to_string(),
+            GraticuleLine::Meridian(longitude) => {
+                if *longitude == 0.0 {
+                    return "Prime meridian".to_string();
+                }
+                if *longitude == 180.0 {
+                    return "Antimeridian".to_string();
+                }
+                match *longitude > 0.0 {
+                    true => return format!("Meridian {}°E", longitude),
+                    false => return format!("Meridian {}°W", -longitude),
+                }
+            },
+        }
+    }
+}
+
+/// Where and when a route crosses a notable line on the map
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RouteCrossing {
+    /// The line crossed
+    pub line: GraticuleLine,
+    /// Where the route crosses the line
+    pub location: geo::Point,
+    /// When the line is crossed, interpolated between ship log entries. None for route plans
+    pub timestamp: Option<UtcDateTime>,
+    /// The leg (starting at 1) the line is crossed on. None for ship logs
+    pub leg: Option<usize>,
+}
+
+/// Finds where a route plan crosses the equator, the Arctic and Antarctic circles, the tropics and the meridians every meridian_spacing degrees (e.g. 10°)
+/// The legs are followed along the great circle. Returns the crossings in the order they are sailed
+/// # Example:
+/// `for crossing in get_route_plan_crossings(&route_plan, 10.0) { println!("Leg {}: {}", crossing.leg.unwrap(), crossing.line.name()); }`
+pub fn get_route_plan_crossings(route_plan: &Vec<SailingLeg>, meridian_spacing: f64) -> Vec<RouteCrossing> {
+    let mut crossings: Vec<RouteCrossing> = Vec::new();
+    for (i, leg) in route_plan.iter().enumerate() {
+        get_crossings_between(leg.p1, leg.p2, None, Some(i + 1), meridian_spacing, &mut crossings);
+    }
+    return crossings;
+}
+
+/// Finds where and when a ship log crosses the equator, the Arctic and Antarctic circles, the tropics and the meridians every meridian_spacing degrees (e.g. 10°)
+/// The vessel is assumed to sail along the great circle between entries at constant speed. Returns the crossings in the order they are sailed
+/// # Example:
+/// `let crossings = get_ship_log_crossings(&boat.ship_log, 10.0);`
+pub fn get_ship_log_crossings(ship_log: &[ShipLogEntry], meridian_spacing: f64) -> Vec<RouteCrossing> {
+    let mut crossings: Vec<RouteCrossing> = Vec::new();
+    for pair in ship_log.windows(2) {
+        get_crossings_between(pair[0].coordinates_current, pair[1].coordinates_current, Some((pair[0].timestamp, pair[1].timestamp)), None, meridian_spacing, &mut crossings);
+    }
+    return crossings;
+}
+
+/// Makes a plotly trace with a labeled marker at each crossing, to add to a map
+/// # Example:
+/// `figure.add_trace(get_route_crossings_trace(&crossings));`
+pub fn get_route_crossings_trace(crossings: &[RouteCrossing]) -> Box<plotly::ScatterGeo<f64, f64>> {
+    let latitudes: Vec<f64> = crossings.iter().map(|c| c.location.y()).collect();
+    let longitudes: Vec<f64> = crossings.iter().map(|c| c.location.x()).collect();
+    let labels: Vec<String> = crossings.iter().map(|c| match c.timestamp {
+        Some(t) => format!("{} {}", c.line.name(), t),
+        None => c.line.name(),
+    }).collect();
+    return plotly::ScatterGeo::new(latitudes, longitudes)
+        .name("Crossings")
+        .mode(plotly::common::Mode::MarkersText)
+        .text_array(labels)
+        .show_legend(true);
+}
+
+/// Finds the crossings on the great circle line from p1 to p2 and adds them to crossings in the order they are crossed
+/// times is the time at p1 and p2, if known
+fn get_crossings_between(p1: geo::Point, p2: geo::Point, times: Option<(UtcDateTime, UtcDateTime)>, leg: Option<usize>, meridian_spacing: f64, crossings: &mut Vec<RouteCrossing>) {
+    let latitude_lines = [
+        (GraticuleLine::Equator, 0.0),
+        (GraticuleLine::ArcticCircle, ARCTIC_CIRCLE_LATITUDE),
+        (GraticuleLine::AntarcticCircle, -ARCTIC_CIRCLE_LATITUDE),
+        (GraticuleLine::TropicOfCancer, TROPIC_LATITUDE),
+        (GraticuleLine::TropicOfCapricorn, -TROPIC_LATITUDE),
+    ];
+
+    // Split the line into short pieces, a great circle can cross the same latitude twice
+    let line = SailingLeg::new(p1, p2, 0.0, 0.0);
+    let n_pieces = (line.length() / CROSSING_MAX_SEGMENT_LENGTH).ceil().max(1.0) as usize;
+    for i in 0..n_pieces {
+        let a = line.point_at(i as f64 / n_pieces as f64);
+        let b = line.point_at((i + 1) as f64 / n_pieces as f64);
+        // Crossings in this piece, (fraction of the piece, line, location)
+        let mut piece_crossings: Vec<(f64, GraticuleLine, geo::Point)> = Vec::new();
+
+        // Latitude lines, a line counts as crossed when exactly one end of the piece is south of it
+        for (graticule_line, latitude) in latitude_lines {
+            if (a.y() < latitude) != (b.y() < latitude) {
+                let fraction = (latitude - a.y()) / (b.y() - a.y());
+                piece_crossings.push((fraction, graticule_line, geo::Point::new(normalize_longitude(a.x() + fraction*get_longitude_difference(a.x(), b.x())), latitude)));
+            }
+        }
+
+        // Meridians, unwrap the longitude of b so the piece does not go the long way around
+        if meridian_spacing > 0.0 {
+            let b_lon = a.x() + get_longitude_difference(a.x(), b.x());
+            let (low, high) = (a.x().min(b_lon), a.x().max(b_lon));
+            let mut k = (low / meridian_spacing).ceil() as i64;
+            while (k as f64)*meridian_spacing <= high {
+                let meridian = (k as f64)*meridian_spacing;
+                if (a.x() < meridian) != (b_lon < meridian) {
+                    let fraction = (meridian - a.x()) / (b_lon - a.x());
+                    let longitude = normalize_longitude(meridian);
+                    piece_crossings.push((fraction, GraticuleLine::Meridian(longitude), geo::Point::new(longitude, a.y() + fraction*(b.y() - a.y()))));
+                }
+                k += 1;
+            }
+        }
+
+        // Add the crossings in the order they are crossed
+        piece_crossings.sort_by(|x, y| x.0.total_cmp(&y.0));
+        for (fraction, graticule_line, location) in piece_crossings {
+            let timestamp = times.map(|(t1, t2)| t1 + (t2 - t1)*((i as f64 + fraction) / n_pieces as f64));
+            crossings.push(RouteCrossing {
+                line: graticule_line,
+                location,
+                timestamp,
+                leg,
+            });
+        }
+    }
+}
+
+/// Function that gets weather data from file
+/// The output tuple is the (timstamp, location, wind vector, ocean current vector)
+pub fn get_weather_data_from_csv_file(path_to_file: String) -> Result<(Vec<UtcDateTime>, Vec<geo::Point>, Vec<PhysVec>, Vec<Option<PhysVec>>), MarineSimError> {
+    // Read the CSV file
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(path_to_file.clone())?;
+
+    // Initialize return vectors
+    let mut timestamps: Vec<UtcDateTime> = Vec::new();
+    let mut points: Vec<geo::Point> = Vec::new();
+    let mut wind_vec: Vec<PhysVec> = Vec::new();
+    let mut ocean_current_vec: Vec<Option<PhysVec>> = Vec::new();
+
+    // Iterate through each line of the CSV file and add the coordinates to the route plan
+    for result in csv_reader.records() {
+        match result {
+            Ok(entry) => {
+                // timestamp
+                timestamps.push(convert_csv_field(&entry, 0, "timestamp", &path_to_file, string_to_utc_date_time)?);
+                // Point
+                let longitude: f64 = parse_required_csv_field::<f64>(&entry, 1, "longitude", &path_to_file)?;
+                let latitude: f64 = parse_required_csv_field::<f64>(&entry, 2, "latitude", &path_to_file)?;
+                let point = geo::Point::new(longitude, latitude);
+                points.push(point);
+                // Wind
+                let wind_speed = parse_required_csv_field::<f64>(&entry, 3, "wind speed", &path_to_file)?;
+                let wind_angle = parse_required_csv_field::<f64>(&entry, 4, "wind angle", &path_to_file)?;
+                let wind = PhysVec::new(wind_speed, wind_angle);
+                wind_vec.push(wind);
+                // Ocean current, None if either the speed or angle is empty
+                let ocean_current: Option<PhysVec> = match (parse_csv_field::<f64>(&entry, 5, "ocean current speed", &path_to_file)?, parse_csv_field::<f64>(&entry, 6, "ocean current angle", &path_to_file)?) {
+                    (Some(ocean_current_speed), Some(ocean_current_angle)) => Some(PhysVec::new(ocean_current_speed, ocean_current_angle)),
+                    _ => None,
+                };
+                ocean_current_vec.push(ocean_current);
+            }
+            Err(err) => {
+                tracing::warn!("Error reading weather data from file: {}", err);
+            }
+        }
+    }
+    
+    // Return (timestamps, points, wind_vec, ocean_current_vec)
+    return Ok((timestamps, points, wind_vec, ocean_current_vec));
+}
+
+
+/// Function that saves the settings of the simulation to a text file.
+/// Note: This function does not care about overwriting existing files, it will always overwrite.
+pub fn save_sim_settings_to_file(file_path: &str, sim: Simulation) -> Result<(), MarineSimError> {
+    // Check that file_path ends with ".txt"
+    if !check_file_extension(file_path, ".txt") {
+        return Err(MarineSimError::InvalidInput("The filepath must end with \".txt\"".to_string()));
+    }
+
+    // Make string to write to file
+    let mut settings_string: String = String::new();
+    settings_string.push_str("Simulation settings:\n");
+    settings_string.push_str(&format!("Simulation method: {:?}\n", sim.simulation_method));
+    settings_string.push_str(&format!("Simulation start times: {:?}\n", sim.start_times));
+    settings_string.push_str(&format!("Simulation time step: {}\n", sim.time_step));
+    settings_string.push_str(&format!("Simulation max iterations: {}\n", sim.max_iterations));
+    settings_string.push_str(&format!("Simulation weather_data_file: {:?}\n", sim.weather_data_file));
+    settings_string.push_str(&format!("Simulation copernicus: {:?}\n", sim.copernicus));
+    #[cfg(feature = "progress_bar")]
+    settings_string.push_str(&format!("Simulation progress bar: {:?}\n", sim.progress_bar));
+    settings_string.push_str(&format!("Simulation number of segments: {:?}\n", sim.n_segments));
+
+    // Write string to file
+    tracing::debug!("Saving simulation settings to file: {}", settings_string);
+
+    let mut f = File::create(file_path)?;
+    f.write_all(&settings_string.into_bytes())?;
+
+
+    // Return ok
+    return Ok(());
+}
+
+/// Function that takes generates and saves a polar speed plot csv file
+/// for a wind propelled vessel.
+/// Based on this issue: <https://github.com/G0rocks/marine_vessel_simulator/issues/50>
+/// The file_path is where the results will be saved as a csv file.
+/// The min file contains the minimum vessel speed through water for each apparent wind angle and apparent wind speed segment.
+/// The max file contains the maximum vessel speed through water for each apparent wind angle and apparent wind speed segment.
+/// The mean file contains the mean vessel speed through water for each apparent wind angle and apparent wind speed segment.
+/// The source data file contains the source data used to compute the min, mean and max. Made from the polar plot data vector. Column 1 is the apparent wind angle, column 2 is the apparent wind speed, column 3 is the vessel speed through water, column 4 is the heading, column 5 is the wind speed (not apparent), column 6 is the wind angle (not apparent), column 7 is the ocean current speed (not apparent), column 8 is the ocean current angle (not apparent).
+/// The csv files can be used to make a polar plot in openCPN or similar programs.
+/// The mean file is also rendered as a polar chart next to it, <file_path>_mean.html, see plot_polar_speed_plot_csv() to save it as an image.
+/// Until this issue has been dealt with (<https://github.com/G0rocks/marine_vessel_simulator/issues/42>) then marine_vessel_simulator does not support using the polar plot but it can be uploaded to openCPN or similar programs to use them.
+/// The polar plot data vector columns are: Column 1 is the apparent wind angle, column 2 is the apparent wind speed and column 3 is the vessel speed through water
+/// Warning: All calculations assume meters per second are being used and if knots are being used the vessel speed will be multiplied by 1.94384 to transform into knots and the columns (with the wind speed) will be multiplied by 2 (to ensure that the file can be opened by openCPN) meaning that if knots are used then the potentially there will be issues in using the data than if meters per second are used.
+/// Note: If not ocean current data is retrieved, the current is assumed to be flowing at zero meters per second
+/// Note: If no degree_segment_size is given, defaults to 5°. If a segment size is given it must be so that 180° is divisible by the segment size
+/// Note: If no wind_speed_segment_size is given, defaults to 1 m/s. If a segment size is given it must be so that 40 m/s is divisible by the segment size. Will always use m/s and not knots.
+/// Note: As of 2026-02-06 OpenCPN polar plugin only accepts values in degree increments of 5° and column increments of 2 (no unit). In order to generate a polar speed plot csv file which can be opened by this plugin the same constraints are put on the input degree and wind speed segment sizes, that is that they must be divisible by 5° and 2 m/s. Follow this issue for updates: <https://github.com/G0rocks/marine_vessel_simulator/issues/56>
+#[cfg(feature = "copernicus")]
+pub fn make_polar_speed_plot_csv(ship_log: Vec<ShipLogEntry>, simulation: &Simulation, file_path: &str, true_if_knots_false_if_meters_per_second: bool, degree_segment_size: Option<f64>, wind_speed_segment_size: Option<f64>) -> Result<Vec<Vec<f64>>, MarineSimError> {
+    // Add ".csv" to the end of the file path if it is not there already
+    let mut working_file_path: String = file_path.to_owned();
+    if file_path.chars().rev().take(4).collect::<Vec<_>>().into_iter().rev().collect::<String>() != ".csv" {
+        working_file_path = file_path.to_owned() + ".csv";
+    }
+    // Make file paths for all the polar plot data files
+    let working_file_path_min: String = working_file_path.replace(".csv", "_min.csv");
+    let working_file_path_mean: String = working_file_path.replace(".csv", "_mean.csv");
+    let working_file_path_max: String = working_file_path.replace(".csv", "_max.csv");
+    let working_file_path_source: String = working_file_path.replace(".csv", "_source_data.csv");
+
+    // Check if the file path already exist, if not create the folder
+    if !std::path::Path::new(&file_path).exists() {
+        std::fs::create_dir_all(std::path::Path::new(&file_path).parent().unwrap())?;
+    }
+
+    // Get working degree segment size from degree_segment_size and evaluate if it is so that 180° are divisible by it
+    let working_degree_segment_size: f64 = degree_segment_size.unwrap_or_else(|| 5.0);
+    if 180.0 % working_degree_segment_size != 0.0 {
+        return Err(MarineSimError::InvalidInput(format!("Invalid input: degree segment size: {}°.\nThe degree segment size must be so that 180° is divisible by the angle", working_degree_segment_size)));
+    }
+    if working_degree_segment_size % 5.0 != 0.0 {
+        return Err(MarineSimError::InvalidInput(format!("Invalid input: degree segment size: {}°.\nThe degree segment size must be divisible by 5° to ensure compatibility with openCPN polar plugin", working_degree_segment_size)));
+    }
+    // Get working wind speed segment size from wind_speed_segment_size and evaluate if it is so that 40 m/s is divisible by it
+    let working_wind_speed_segment_size: f64 = wind_speed_segment_size.unwrap_or_else(|| 1.0);
+    if 40.0 % working_wind_speed_segment_size != 0.0 {
+        return Err(MarineSimError::InvalidInput(format!("Invalid input: wind speed segment size: {} m/s.\nThe wind speed segment size must be so that 40 m/s is divisible by the segment size", working_wind_speed_segment_size)));
+    }
+    if working_wind_speed_segment_size % 2.0 != 0.0 {
+        return Err(MarineSimError::InvalidInput(format!("Invalid input: wind speed segment size: {} m/s.\nThe wind speed segment size must be divisible by 2 m/s to ensure compatibility with openCPN polar plugin", working_wind_speed_segment_size)));
+    }
+
+    // Init empty polar plot data vector which will have subvectors. Column 1 is the apparent wind angle, column 2 is the apparent wind speed, column 3 is the vessel speed through water, column 4 is the heading, column 5 is the wind speed (not apparent), column 6 is the wind angle (not apparent), column 7 is the ocean current speed (not apparent), column 8 is the ocean current angle (not apparent).
+    // NOTE CURRENTLY ONLY HAS 4 COLUMNS!!!
+    let mut polar_plot_data_vector: Vec<Vec<f64>> = Vec::new();
+
+    // Check for interactive terminal for progress bar
+    let is_interactive_terminal = atty::is(atty::Stream::Stdout);
+
+    // If simulation has progress bar, set it up and use it
+    if !(simulation.progress_bar.is_none()) {
+        // Set length
+        // Hiding progress bar to prevent two lines of progress bar being drawn
+        simulation.progress_bar.as_ref().unwrap().set_draw_target(indicatif::ProgressDrawTarget::hidden());
+        simulation.progress_bar.as_ref().unwrap().set_length((ship_log.len() as u64) + 1);
+        // If terminal is interactive, use live redraw, otherwise use static redraw
+        if is_interactive_terminal {
+            // Normal terminal behavior (live redraw)
+            simulation.progress_bar.as_ref().unwrap().set_draw_target(indicatif::ProgressDrawTarget::stdout());
+            simulation.progress_bar.as_ref().unwrap().enable_steady_tick(std::time::Duration::from_millis(500));
+        } else {
+            // Force static redraw every step to stdout (or to log)
+            // bar.set_draw_target(indicatif::ProgressDrawTarget::stdout_with_hz(1)); // Or `.stdout_with_hz(1)` for slow redraw
+            let eta = time::UtcDateTime::now().saturating_add(time::Duration::new(simulation.progress_bar.as_ref().unwrap().eta().as_secs() as i64, 0)); // What time the simulations will end
+            tracing::info!(elapsed_secs = simulation.progress_bar.as_ref().unwrap().elapsed().as_secs(), steps = simulation.progress_bar.as_ref().unwrap().position(), total_steps = simulation.progress_bar.as_ref().unwrap().length().unwrap(), eta = %format_timestamp(eta), "Simulation progress");
+        }
+        simulation.progress_bar.as_ref().unwrap().inc(0);
+    }
+
+    // Loop through ship_log
+    for entry in ship_log {
+        // Get timestamp
+        let timestamp = entry.timestamp;
+
+        // Get vessel location coordinates
+        let location = entry.coordinates_current;
+        let longitude = location.x();
+        let latitude = location.y();
+        
+        // Get velocity and heading
+        let vessel_velocity = entry.velocity;
+        let heading = entry.heading;
+
+        // If vessel velocity or heading is None skip this entry since we need both data fields
+        if vessel_velocity == None || heading == None {
+            // Update progress bar if a progress bar is in use
+            if !(simulation.progress_bar.is_none()) {
+                // update progress bar
+                simulation.progress_bar.as_ref().unwrap().inc(1);
+                // If not interactive terminal, print progressbar manually
+                if is_interactive_terminal == false {
+                    let eta = time::UtcDateTime::now().saturating_add(time::Duration::new(simulation.progress_bar.as_ref().unwrap().eta().as_secs() as i64, 0)); // What time the simulations will end
+                tracing::info!(elapsed_secs = simulation.progress_bar.as_ref().unwrap().elapsed().as_secs(), steps = simulation.progress_bar.as_ref().unwrap().position(), total_steps = simulation.progress_bar.as_ref().unwrap().length().unwrap(), eta = %format_timestamp(eta), "Simulation progress");
+                }   // End if
+            }   // End if
+
+            // Skip to next iteration of loop
+            continue;
+        }
+
+        // Get wind and ocean current data from timestamp and location from Copernicus
+        let dataset_id: String = match copernicusmarine_rs::get_dataset_id(copernicusmarine_rs::CopernicusVariable::EastwardWind, timestamp, timestamp) {
+            Ok(id) => id,
+            Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting dataset id from copernicusmarine: {}", e))),
+        };
+        // let wind_data = match simulation.copernicus.as_ref().unwrap().get_f64_values("cmems_obs-wind_glo_phy_nrt_l4_0.125deg_PT1H".to_string(), vec!["eastward_wind".to_string(), "northward_wind".to_string()], boat_time_now, boat_time_now, longitude, longitude, latitude, latitude, None, None) {
+        let wind_data = match simulation.copernicus.as_ref().unwrap().get_f64_values(dataset_id, vec!["eastward_wind".to_string(), "northward_wind".to_string()], timestamp, timestamp, longitude, longitude, latitude, latitude, None, None) {
+            Ok(w) => w,
+            Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting wind data from copernicusmarine: {}", e))),
+        };
+        // Wind speed and direction
+        let (wind_east, wind_north): (f64, f64) = match get_copernicus_east_north(&wind_data, "wind")? {
+            (Some(east), Some(north)) => (east, north),
+            _ => return Err(MarineSimError::WeatherFetch("No wind data from copernicusmarine".to_string())),
+        };
+        let wind_angle: f64 = get_north_angle_from_northward_and_eastward_property(wind_east, wind_north);   // Angle in degrees
+        let wind_speed = uom::si::f64::Velocity::new::<uom::si::velocity::meter_per_second>((wind_east*wind_east + wind_north*wind_north).sqrt().into());
+        let wind = PhysVec::new(wind_speed.get::<uom::si::velocity::meter_per_second>(), wind_angle);    // unit [m/s]
+
+        // Get ocean current data from Copernicus
+        // "uo" is the eastward sea water velocity and "vo" is the northward sea water velocity
+        let dataset_id: String = match copernicusmarine_rs::get_dataset_id(copernicusmarine_rs::CopernicusVariable::EastwardSeaWaterVelocity, timestamp, timestamp) {
+            Ok(id) => id,
+            Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting dataset id from copernicusmarine: {}", e))),
+        };
+        let ocean_current_data = match simulation.copernicus.as_ref().unwrap().get_f64_values(dataset_id, vec!["uo".to_string(), "vo".to_string()], timestamp, timestamp, longitude, longitude, latitude, latitude, Some(0.0), Some(1.0)){
+            Ok(o) => o,
+            Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting ocean current data from copernicusmarine: {}", e))),
+        };
+        // Ocean current speed and direction
+        let (ocean_current_east, ocean_current_north) = get_copernicus_east_north(&ocean_current_data, "ocean current")?;
+        let ocean_current_east: f64 = match ocean_current_east {
+            Some(v) => v,
+            None => 0.0,
+        };
+        let ocean_current_north: f64 = match ocean_current_north {
+            Some(v) => v,
+            None => 0.0,
+        };
+        let ocean_current_angle: f64 = get_north_angle_from_northward_and_eastward_property(ocean_current_east, ocean_current_north);   // Angle in degrees
+        let ocean_current_speed = uom::si::f64::Velocity::new::<uom::si::velocity::meter_per_second>((ocean_current_east*ocean_current_east + ocean_current_north*ocean_current_north).sqrt().into());
+        let ocean_current = PhysVec::new(ocean_current_speed.get::<uom::si::velocity::meter_per_second>(), ocean_current_angle);    // unit [m/s]
+
+        // Offset velocity by ocean current velocity (a.k.a. account for set and drift)
+        let mut vessel_velocity_through_water: PhysVec = match vessel_velocity {
+            Some(v) => v - ocean_current,
+            None => {
+                // If no vessel velocity, return error since no polar plot data can be generated
+                return Err(MarineSimError::InvalidInput("No vessel velocity data in ship log entry, cannot generate polar plot data".to_string()));
+            }
+        };
+        // Make sure the angle is between 0.0 and 360.0 degrees
+        while vessel_velocity_through_water.angle < 0.0 {
+            vessel_velocity_through_water.angle += 360.0;
+        }
+        while vessel_velocity_through_water.angle >= 360.0 {
+            vessel_velocity_through_water.angle -= 360.0;
+        }
+
+        // Compute apparent wind
+        let apparent_wind = wind - ocean_current;
+        // Include heading
+        let mut apparent_wind = PhysVec::new(apparent_wind.magnitude, apparent_wind.angle - heading.unwrap());
+        // Make sure the angle is between 0.0 and 360.0 degrees
+        while apparent_wind.angle < 0.0 {
+            apparent_wind.angle += 360.0;
+        }
+        while apparent_wind.angle >= 360.0 {
+            apparent_wind.angle -= 360.0;
+        }
+
+        // Log apparent wind angle, wind speed and vessel speed to polar plot data vector
+        polar_plot_data_vector.push(vec![apparent_wind.angle, apparent_wind.magnitude, vessel_velocity_through_water.magnitude, heading.unwrap(), wind.magnitude, wind.angle, ocean_current.magnitude, ocean_current.angle]);
+
+        // Update progress bar if a progress bar is in use
+        if !(simulation.progress_bar.is_none()) {
+            // update progress bar
+            simulation.progress_bar.as_ref().unwrap().inc(1);
+            // If not interactive terminal, print progressbar manually
+            if is_interactive_terminal == false {
+                let eta = time::UtcDateTime::now().saturating_add(time::Duration::new(simulation.progress_bar.as_ref().unwrap().eta().as_secs() as i64, 0)); // What time the simulations will end
+                tracing::info!(elapsed_secs = simulation.progress_bar.as_ref().unwrap().elapsed().as_secs(), steps = simulation.progress_bar.as_ref().unwrap().position(), total_steps = simulation.progress_bar.as_ref().unwrap().length().unwrap(), eta = %format_timestamp(eta), "Simulation progress");
+            }   // End if
+        }   // End if
+    }   // End for loop
+
+    // Make mutable standardized polar plot data vector (based off of opencpn polar plot csv files but with wind in m/s). Include option for having None for unknown values
+    // Column 0 is the angle of the apparent wind in degrees
+    // column 1-40 contains tuples which are (n, VTW.magnitude) where n signifies how many values have been used to generate the average value VTW.magnitude which is the magnitude of the Velocity through water vector.
+    // Note the VTW.magnitude is given in m/s in 1 m/s increments
+    let mut standard_data_vector_mean: Vec<Vec<(usize, Option<f64>)>> = Vec::new();
+
+    // First find how many degree and wind speed segments we have
+    let num_degree_segments: u16 = (180.0/working_degree_segment_size) as u16 + 1;   // +1 for the degrees since both 0° and 180° are included  // Keeping this even though it is currently unused since when this issue gets resolved we can use it again: https://github.com/G0rocks/marine_vessel_simulator/issues/56
+    let num_wind_speed_segments: u8 = (40.0/working_wind_speed_segment_size) as u8;     // No +1 since 0 m/s is not included but 40 m/s is included
+    
+    // Fill the first column of the standard data vector with working_degree_segment_size increments, set all unknown values to None
+    // Use 0..37 since OpenCPN polar plugin only accepts values in 5° increments
+    for i in 0..37 {
+        let angle = (i as f64) * 5.0;
+        let mut sub_vec: Vec<(usize, Option<f64>)> = Vec::new();
+        sub_vec.push((angle as usize, Some(angle)));
+        for _k in 0..num_wind_speed_segments {
+            sub_vec.push((0, None));
+        }
+        standard_data_vector_mean.push(sub_vec);
+    }
+
+    // Make a copy of the standard data vector for the minimum and maximum values as well
+    let mut standard_data_vector_min: Vec<Vec<(usize, Option<f64>)>> = standard_data_vector_mean.clone();
+    let mut standard_data_vector_max: Vec<Vec<(usize, Option<f64>)>> = standard_data_vector_mean.clone();
+
+    // Now that all the data has been collected for the polar plot, we loop through it and standardize it to be formatted in the similar numbers that the weather routing programs would use it
+    for i in 0..polar_plot_data_vector.len() {
+        // We only need one side of the polar plot, let's use the right side, everything else can be mirrored afterwards (effectively potentially doubles the available data)
+        if polar_plot_data_vector[i][0] > 180.0 {
+            polar_plot_data_vector[i][0] = 360.0 - polar_plot_data_vector[i][0];
+        }
+
+        // Find the nearest wind angle (in 5° increments) to this wind angle
+        let nearest_angle_diff: f64 = polar_plot_data_vector[i][0] % working_degree_segment_size;
+        let nearest_angle: f64;
+        if nearest_angle_diff < working_degree_segment_size/2.0 {
+            // Round down to the nearest working_degree_segment_size
+            nearest_angle = polar_plot_data_vector[i][0] - nearest_angle_diff;
+        } else {
+            // Round up to the nearest working_degree_segment_size
+            nearest_angle = polar_plot_data_vector[i][0] + (working_degree_segment_size - nearest_angle_diff);
+        }
+
+        // Find the row in the standard_data_vector that corresponds to this nearest angle. Rows given in 5° increments until OpenCPN polar plugin accepts other angles
+        // Use this when the OpenCPN polar plugin accepts custom angles: let row: usize = (nearest_angle/working_degree_segment_size) as usize;
+        let row: usize = (nearest_angle/5.0) as usize;
+
+        // Find the nearest wind speed (in 1 m/s increments) to this wind speed
+        let nearest_wind_speed_diff: f64 = polar_plot_data_vector[i][1] % working_wind_speed_segment_size;
+        let mut nearest_wind_speed: f64;
+        if nearest_wind_speed_diff < working_wind_speed_segment_size/2.0 {
+            // Round down to the nearest working_wind_speed_segment_size
+            nearest_wind_speed = polar_plot_data_vector[i][1] - nearest_wind_speed_diff;
+        } else {
+            // Round up to the nearest working_wind_speed_segment_size
+            nearest_wind_speed = polar_plot_data_vector[i][1] + (working_wind_speed_segment_size - nearest_wind_speed_diff);
+        }
+        // If the nearest wind speed is set to zero then the column will be zero so we move the nearest wind speed up one segment size
+        if nearest_wind_speed == 0.0 {
+            nearest_wind_speed += working_wind_speed_segment_size;
+        }
+
+        // Find the row in the standard_data_vector that corresponds to this nearest angle
+        let column: usize = (nearest_wind_speed/working_wind_speed_segment_size) as usize;
+
+        // Input the value into the standard data vectors
+        // Inputting into min vector
+        if standard_data_vector_min[row][column].1.is_some() {
+            // Get current number of values used to make the minimum
+            let current_n: usize = standard_data_vector_min[row][column].0;
+            // Get current minimum vessel speed
+            let current_speed: f64 = standard_data_vector_min[row][column].1.unwrap();
+            // If the new value is lesser than the current value in the standard data vector, replace it
+            if polar_plot_data_vector[i][2] < current_speed {
+                standard_data_vector_min[row][column] = (current_n + 1, Some(polar_plot_data_vector[i][2]));
+            }
+        }
+        // Otherwise if this is the first value, put it directly into the standard_data_vector and set the counter to 1.
+        else {
+            standard_data_vector_min[row][column] = (1, Some(polar_plot_data_vector[i][2]));
+        }
+
+        // Inputting into mean vector
+
+        // TODO: Improvement idea: If there are any values in the standard_data_vector in that index and the index that surrounds the current value, linearly interpolate the current value in the direction of the index
+        // Let's average it directly and skip the linear interpolation for now, adding an issue about it
+        if standard_data_vector_mean[row][column].1.is_some() {
+            // Get current number of values used to make the average
+            let current_n: usize = standard_data_vector_mean[row][column].0;
+            // Get current average vessel speed
+            let current_speed: f64 = standard_data_vector_mean[row][column].1.unwrap();
+            // Make new average vessel speed by adding the new value and incrementing the number of values used to make the average
+            standard_data_vector_mean[row][column] = (current_n + 1, Some((current_speed*(current_n as f64) + polar_plot_data_vector[i][2])/((current_n + 1) as f64)));
+        }
+        // Otherwise if this is the first value, assume it stays the same and put it directly into the standard_data_vector
+        else {
+            standard_data_vector_mean[row][column] = (1, Some(polar_plot_data_vector[i][2]));
+        }
+
+        // Inputting into max vector
+        if standard_data_vector_max[row][column].1.is_some() {
+            // Get current number of values used to make the maximum
+            let current_n: usize = standard_data_vector_max[row][column].0;
+            // Get current maximum vessel speed
+            let current_speed: f64 = standard_data_vector_max[row][column].1.unwrap();
+            // If the new value is greater than the current value in the standard data vector, replace it
+            if polar_plot_data_vector[i][2] > current_speed {
+                standard_data_vector_max[row][column] = (current_n + 1, Some(polar_plot_data_vector[i][2]));
+            }
+        }
+        // Otherwise if this is the first value, put it directly into the standard_data_vector and set the counter to 1.
+        else {
+            standard_data_vector_max[row][column] = (1, Some(polar_plot_data_vector[i][2]));
+        }
+    }
+
+    // TODO See issue https://github.com/G0rocks/marine_vessel_simulator/issues/62
+    // Check if standard_data_vector contains any values. If it does not then return error
+
+    // Now that the underlying polar plot data is ready, save the results to a csv file
+    // Saving the file with the minimum data
+    // Create a CSV writer with a semicolon delimiter
+    let mut writer_min = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(working_file_path_min)?;
+
+    // Write the header
+    let mut header_vec: Vec<String> = Vec::new();
+    // First column in the header is "TWA\TWS" and not "wind angle [°]" because that is what openCPN uses
+    header_vec.push("TWA\\TWS".to_string());
+    for i in 1..(num_wind_speed_segments+1) {
+        // If knots, format in knots
+        if true_if_knots_false_if_meters_per_second {
+            header_vec.push(format!("{}", (i as f64)*working_wind_speed_segment_size*1.94384));
+        } // Otherwise use meters per second (preferred)
+        else {
+            header_vec.push(format!("{}", (i as f64)*working_wind_speed_segment_size));
+        }
+    }
+    
+    writer_min.write_record(&header_vec)?;
+
+    // Write the standard_data_vector into the csv file
+    for row in standard_data_vector_min.iter() {
+        // Init empty record to write
+        let mut record: Vec<String> = Vec::new();
+        // Add the wind angle to the first column
+        // record.push(row[0].0);
+
+        // For the rest of the row, if the boat speed is None, add an empty string, else, add the boat speed to the record
+        // Note: The first cell should always be some as it should include the apparent wind angle
+        for (i, cell) in row.iter().enumerate() {
+            // If the value is Some, add it
+            if cell.1.is_some() {
+                // If it's the first column, just add it since it is the angle of the wind
+                if i == 0 {
+                    record.push(cell.1.unwrap().to_string());
+                } // Otherwise, check if we're using knots or meters per second
+                else {
+                    // If knots, transform to knots
+                    if true_if_knots_false_if_meters_per_second {
+                        record.push((cell.1.unwrap() * 1.94384).to_string());
+                    } // Otherwise, use meters_per_second
+                    else {
+                        record.push(cell.1.unwrap().to_string());
+                    }
+                }
+            } // Otherwise, add empty string
+            else {
+                record.push(String::new());
+            }
+        }
+
+        // Write the record
+        writer_min.write_record(&record)?;
+    }
+
+    // Flush and close the writer
+    writer_min.flush()?;
+
+    // Saving the file with the mean data
+    // Create a CSV writer with a semicolon delimiter
+    let mut writer_mean = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(&working_file_path_mean)?;
+
+    // Write the header
+    let mut header_vec: Vec<String> = Vec::new();
+    // First column in the header is "TWA\TWS" and not "wind angle [°]" because that is what openCPN uses
+    header_vec.push("TWA\\TWS".to_string());
+    for i in 1..(num_wind_speed_segments+1) {
+        // If knots, format in knots
+        if true_if_knots_false_if_meters_per_second {
+            header_vec.push(format!("{}", (i as f64)*working_wind_speed_segment_size*1.94384));
+        } // Otherwise use meters per second (preferred)
+        else {
+            header_vec.push(format!("{}", (i as f64)*working_wind_speed_segment_size));
+        }
+    }
+    
+    writer_mean.write_record(&header_vec)?;
+
+    // Write the standard_data_vector_mean into the csv file
+    for row in standard_data_vector_mean.iter() {
+        // Init empty record to write
+        let mut record: Vec<String> = Vec::new();
+        // Add the wind angle to the first column
+        // record.push(row[0].0);
+
+        // For the rest of the row, if the boat speed is None, add an empty string, else, add the boat speed to the record
+        // Note: The first cell should always be some as it should include the apparent wind angle
+        for (i, cell) in row.iter().enumerate() {
+            // If the value is Some, add it
+            if cell.1.is_some() {
+                // If it's the first column, just add it since it is the angle of the wind
+                if i == 0 {
+                    record.push(cell.1.unwrap().to_string());
+                } // Otherwise, check if we're using knots or meters per second
+                else {
+                    // If knots, transform to knots
+                    if true_if_knots_false_if_meters_per_second {
+                        record.push((cell.1.unwrap() * 1.94384).to_string());
+                    } // Otherwise, use meters_per_second
+                    else {
+                        record.push(cell.1.unwrap().to_string());
+                    }
+                }
+            } // Otherwise, add empty string
+            else {
+                record.push(String::new());
+            }
+        }
+
+        // Write the record
+        writer_mean.write_record(&record)?;
+    }
+
+    // Flush and close the writer
+    writer_mean.flush()?;
+
+    // Render the mean polar chart next to the CSV file
+    plot_polar_speed_plot_csv(&working_file_path_mean, &PlotOptions::headless(&working_file_path_mean.replace(".csv", ".html")))?;
+    
+    // Saving the file with the maximum data
+    // Create a CSV writer with a semicolon delimiter
+    let mut writer_max = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(working_file_path_max)?;
+
+    // Write the header
+    let mut header_vec: Vec<String> = Vec::new();
+    // First column in the header is "TWA\TWS" and not "wind angle [°]" because that is what openCPN uses
+    header_vec.push("TWA\\TWS".to_string());
+    for i in 1..(num_wind_speed_segments+1) {
+        // If knots, format in knots
+        if true_if_knots_false_if_meters_per_second {
+            header_vec.push(format!("{}", (i as f64)*working_wind_speed_segment_size*1.94384));
+        } // Otherwise use meters per second (preferred)
+        else {
+            header_vec.push(format!("{}", (i as f64)*working_wind_speed_segment_size));
+        }
+    }
+    
+    writer_max.write_record(&header_vec)?;
+
+    // Write the standard_data_vector into the csv file
+    for row in standard_data_vector_max.iter() {
+        // Init empty record to write
+        let mut record: Vec<String> = Vec::new();
+        // Add the wind angle to the first column
+        // record.push(row[0].0);
+
+        // For the rest of the row, if the boat speed is None, add an empty string, else, add the boat speed to the record
+        // Note: The first cell should always be some as it should include the apparent wind angle
+        for (i, cell) in row.iter().enumerate() {
+            // If the value is Some, add it
+            if cell.1.is_some() {
+                // If it's the first column, just add it since it is the angle of the wind
+                if i == 0 {
+                    record.push(cell.1.unwrap().to_string());
+                } // Otherwise, check if we're using knots or meters per second
+                else {
+                    // If knots, transform to knots
+                    if true_if_knots_false_if_meters_per_second {
+                        record.push((cell.1.unwrap() * 1.94384).to_string());
+                    } // Otherwise, use meters_per_second
+                    else {
+                        record.push(cell.1.unwrap().to_string());
+                    }
+                }
+            } // Otherwise, add empty string
+            else {
+                record.push(String::new());
+            }
+        }
+
+        // Write the record
+        writer_max.write_record(&record)?;
+    }
+
+    // Flush and close the writer
+    writer_max.flush()?;
+
+    // Save the source data
+    // Create a CSV writer with a semicolon delimiter
+    let mut writer_source_data = csv::WriterBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(working_file_path_source)?;
+
+    // Write the header
+    let mut header_vec: Vec<String> = Vec::new();
+    // Column 1 is the apparent wind angle, column 2 is the apparent wind speed, column 3 is the vessel speed through water, column 4 is the heading, column 5 is the wind speed (not apparent), column 6 is the wind angle (not apparent), column 7 is the ocean current speed (not apparent), column 8 is the ocean current angle (not apparent).
+    header_vec.push("AWA [°]".to_string());
+    // If knots, format in knots
+    if true_if_knots_false_if_meters_per_second {
+        header_vec.push("AWS [knots]".to_string());
+        header_vec.push("Vessel speed through water [knots]".to_string());
+    } // Otherwise use meters per second (preferred)
+    else {
+        header_vec.push("AWS [m/s]".to_string());
+        header_vec.push("Vessel speed through water [m/s]".to_string());
+    }
+    header_vec.push("Heading [°]".to_string());
+    header_vec.push("wind speed [m/s]".to_string());
+    header_vec.push("wind angle [°]".to_string());
+    header_vec.push("ocean current speed [m/s]".to_string());
+    header_vec.push("ocean current angle [°]".to_string());
+    
+    writer_source_data.write_record(&header_vec)?;
+
+    // Write the standard_data_vector into the csv file
+    for row in polar_plot_data_vector.iter() {
+        // Init empty record to write
+        let mut record: Vec<String> = Vec::new();
+
+        // Add the first cell (angle) to the record
+        record.push(row[0].to_string());
+
+        // Add the second and third cells (speeds) to the record
+        // If knots, transform to knots
+        if true_if_knots_false_if_meters_per_second {
+            record.push((row[1] * KNOTS_TO_METERS_PER_SECOND).to_string());
+            record.push((row[2] * KNOTS_TO_METERS_PER_SECOND).to_string());
+        } // Otherwise, use meters_per_second
+        else {
+            record.push(row[1].to_string());
+            record.push(row[2].to_string());
+        }
+
+        // Add the fourth cell (heading angle)
+        record.push(row[3].to_string());
+
+        // Add wind data to cells 5 and 6
+        record.push(row[4].to_string());
+        record.push(row[5].to_string());
+
+        // Add ocean current data to cells 7 and 8
+        record.push(row[6].to_string());
+        record.push(row[7].to_string());
+
+        // Write the record
+        writer_source_data.write_record(&record)?;
+    }
+
+    // Flush and close the writer
+    writer_source_data.flush()?;
+
+    // Finish progress bar if a progress bar is in use
+    if let Some(progress_bar) = simulation.progress_bar.as_ref() {
+        progress_bar.finish();
+    }
+
+    // Return data vector
+    return Ok(polar_plot_data_vector);
+}
+
+/// Function that copies a csv file of ship logs taken from (aishub_data_collector)[<https://crates.io/crates/aishub_data_collector>]
+/// and saves a copy of the ship log csv file formatted for marine_vessel_simulator
+/// Note both the input and output filepaths must end with ".csv"
+/// Note the initial coordinates default to (0.0, 0.0) degrees.
+/// Note that this function assumes the aishub data is stored in the AIS encoding not the human readable format
+/// More info on aishub api: <https://www.aishub.net/api>
+/// 
+/// If this function is no longer working since there is a new version of aishub_data_collector or similar, please submit an issue on the (marine_vessel_simulator issue tracker)[<https://github.com/G0rocks/marine_vessel_simulator/issues>]
+/// Last updated 2026-02-01, it works with aishub_data_collector version 1.1.0
+/// aishub_data_collector currently saves data into a csv file with the heading:
+/// 
+/// A,B,C,CALLSIGN,COG,D,DEST,DRAUGHT,DEVICE,ETA,HEADING,IMO,LATITUDE,LONGITUDE,MMSI,NAME,NAVSTAT,PAC,ROT,SOG,TSTAMP,TYPE
+pub fn aishub_shiplog_csv_to_marine_vessel_simulator_shiplog_csv(filepath_input: &str, filepath_output: &str) -> Result<Vec<ShipLogEntry>, MarineSimError> {
+    // Check if filepath_input ends with ".csv", if not, return an invalid input error
+    if !check_file_extension(filepath_input, ".csv") {
+        return Err(MarineSimError::InvalidInput("Input file path must end with '.csv'".to_string()));
+    }
+    // Check if filepath_output ends with ".csv", if not, return an invalid input error
+    if !check_file_extension(filepath_output, ".csv") {
+        return Err(MarineSimError::InvalidInput("Output file path must end with '.csv'".to_string()));
+    }
+    // Check if file is delimited with ';' and if not, return an error
+    // To do this, get the second column in the first row delimited by ';' and if it does not exist then assume the file is not delimited with ';'
+    // Read the CSV file
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(filepath_input)?;
+    // if first line is empty, return error
+    let first_entry = match csv_reader.records().next() {
+        Some(entry) => entry?,
+        None => return Err(MarineSimError::CsvParse { file: filepath_input.to_string(), message: "Input file does not have any data".to_string() }),
+    };
+    let column_2 = first_entry.get(1);
+    if column_2.is_none() {
+        return Err(MarineSimError::CsvParse { file: filepath_input.to_string(), message: "Input file must be delimited with ';'".to_string() });
+    }
+    // Read the CSV file
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(filepath_input)?;
+    // Make sure there are at least 2 lines, if not then return error
+    let num_lines = csv_reader.records().count();
+    if num_lines < 2 {
+        return Err(MarineSimError::CsvParse { file: filepath_input.to_string(), message: "Input file needs to have at least 2 lines of data".to_string() });
+    }
+
+    // Read the aishub ship log csv file into a Shiplog struct
+    // Init ship logs vector
+    let mut aishub_logs: Vec<ShipLogEntry> = Vec::new();
+
+    // Since aishub does not provide information on cargo on board, set cargo on board to None
+    let cargo_on_board = None;
+
+    // Read the CSV file again to start from the beginning
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(filepath_input)?;
+
+    // Get the initial and final coordinates
+    // Init coordinates_initial
+    let first_record = match csv_reader.records().next() {
+        Some(Ok(r)) => r,
+        Some(Err(e)) => return Err(MarineSimError::CsvParse { file: filepath_input.to_string(), message: format!("Error getting first record from aishub_data_collector file: {}", e) }),
+        None => return Err(MarineSimError::CsvParse { file: filepath_input.to_string(), message: "Could not get first entry from file".to_string() }),
+    };
+    let latitude = parse_required_csv_field::<f64>(&first_record, 12, "LATITUDE", filepath_input)?/600000.0;
+    let longitude = parse_required_csv_field::<f64>(&first_record, 13, "LONGITUDE", filepath_input)?/600000.0;
+
+    let coordinates_initial = geo::Point::new(longitude, latitude);
+
+    // Get final coordinates
+    let last_record =  match csv_reader.records().last() {
+        Some(Ok(r)) => r,
+        Some(Err(e)) => return Err(MarineSimError::CsvParse { file: filepath_input.to_string(), message: format!("Error getting last record from aishub_data_collector csv file: {}", e) }),
+        None => return Err(MarineSimError::CsvParse { file: filepath_input.to_string(), message: "Could not get last entry from file".to_string() }),
+    };
+    let latitude = parse_required_csv_field::<f64>(&last_record, 12, "LATITUDE", filepath_input)?/600000.0;
+    let longitude = parse_required_csv_field::<f64>(&last_record, 13, "LONGITUDE", filepath_input)?/600000.0;
+    let coordinates_final: geo::Point = geo::Point::new(longitude, latitude);
+
+    // Start another csv reader stream since we already went through the whole stream.
+    // This way we can start from the first record again
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(filepath_input)?;
+
+    // Loop through all lines of file, append each line to the ship log
+    for result in csv_reader.records() {
+        match result {
+            Ok(entry) => {
+                // Get the entry simplified to reduce how verbose everything is
+                // let entry = entry.get(0).unwrap().split(',');
+                // Get all aishub_data_collector csv file fields
+                // For each entry which aishub could signal is not available, check if the value is valid or not
+                let _a = entry.get(0);
+                let _b = entry.get(1);
+                let _c = entry.get(2);
+                let _callsign = entry.get(3);
+                // If cog is 3600 the value is unknown
+                let mut cog: Option<f64> = Some(parse_required_csv_field::<f64>(&entry, 4, "COG", filepath_input)?);
+                if cog.unwrap() == 3600.0 {
+                    cog = None;
+                }
+                else {
+                    cog = Some(cog.unwrap()/10.0);
+                }
+                let _d = entry.get(5);
+                let _dest = entry.get(6);
+                // Draft must be non-zero, if zero, report as None
+                let mut draft = Some(0.0);
+                if draft.unwrap() == 0.0 {
+                    draft = None;
+                }
+                let _device = entry.get(8);
+                let _eta = entry.get(9);
+                // A heading of 511 means not available
+                let mut heading: Option<f64> = Some(parse_required_csv_field::<f64>(&entry, 10, "HEADING", filepath_input)?);
+                if heading.unwrap() == 511.0 {
+                    heading = None;
+                }
+                let _imo = entry.get(11);
+                // Aishub stores latitude and longitude data in 1/10000 minute
+                let latitude = parse_required_csv_field::<f64>(&entry, 12, "LATITUDE", filepath_input)?/600000.0;
+                let longitude = parse_required_csv_field::<f64>(&entry, 13, "LONGITUDE", filepath_input)?/600000.0;
+                let _mmsi = entry.get(14);
+                let _name = entry.get(15);
+                let navstat: Option<u8> = parse_csv_field::<u8>(&entry, 16, "NAVSTAT", filepath_input)?;
+                let _pac = entry.get(17);
+                let _rot = entry.get(18);
+                // If sog is 1024 the value is unknown
+                let mut sog: Option<f64> = Some(parse_required_csv_field::<f64>(&entry, 19, "SOG", filepath_input)?);
+                if sog.unwrap() == 1024.0 {
+                    sog = None;
+                }
+                else {
+                    // sog is given in knots so in addition to dividing by 10.0 we must also convert to meters per second
+                    sog = Some(sog.unwrap()/10.0/1.944);
+                }
+                let tstamp = parse_required_csv_field::<i64>(&entry, 20, "TSTAMP", filepath_input)?;
+                let _vessel_type = entry.get(21);
+
+                // Convert all aishub_data_collector fields into marine_vessel_simulator ShipLogEntry fields. Note aishub stores tstamp in unix time
+                let timestamp: time::UtcDateTime = match time::UtcDateTime::from_unix_timestamp(tstamp) {
+                    Ok(t) => t,
+                    Err(e) => return Err(MarineSimError::CsvParse { file: filepath_input.to_string(), message: format!("Error converting tstamp to UtcDateTime: {}", e) }),
+                };
+
+                let coordinates_current = geo::Point::new(longitude,latitude);
+                
+                // Init velocity
+                let velocity: Option<PhysVec>;
+                // If sog is unknown, set velocity to None
+                if sog == None {
+                    velocity = None;
+                }   // Otherwise if cog is None, check if sog is zero
+                else if cog == None {
+                    // If sog is zero set sog and cog to zero in velocity since the direction does not matter
+                    if sog.unwrap() == 0.0 {
+                        velocity = Some(PhysVec::new(0.0, 0.0));
+                    } // Otherwise set velocity to None since we don't know the direction
+                    else {
+                        velocity = None;
+                    }
+                } // Otherwise, we know both cog and sog
+                else {
+                    velocity = Some(PhysVec::new(sog.unwrap(), cog.unwrap()));
+                }
+
+                // Track angle is between last and current ship log entry, if this is the first entry, set to None
+                let track_angle = match aishub_logs.len() {
+                    0 => None,
+                    _ => {
+                        let last_entry: &ShipLogEntry = aishub_logs.last().unwrap();
+                        let last_coords: geo::Point = last_entry.coordinates_current;
+                        let curr_coords: geo::Point = coordinates_current;
+                        Some(Earth.bearing(last_coords, curr_coords))
+                    }
+                };
+                // Set true_bearing to angle between current location and final coordinates
+                let true_bearing = Some(Earth.bearing(coordinates_current, coordinates_final));
+
+                let navigation_status: Option<NavigationStatus> = match navstat {
+                    Some(n) => match NavigationStatus::try_from(n) {
+                                    Ok(status) => Some(status),
+                                    Err(_) => None,
+                                },
+                    None => None
+                };
+
+                // Add ship log entry
+                aishub_logs.push(
+                    ShipLogEntry {
+                        timestamp,
+                        coordinates_initial,
+                        coordinates_current,
+                        coordinates_final,
+                        cargo_on_board,
+                        velocity,
+                        course: cog,
+                        heading,
+                        track_angle,
+                        true_bearing,
+                        draft,
+                        navigation_status,
+                        depth: None,
+                        wind: None,
+                        wave_height: None,
+                        cross_track_error: None,
+                        foiling: None,
+                    });
+                }
+            Err(err) => {
+                tracing::warn!("Error reading ship log entry from aishub_data_collector: {}", err);
+            }
+        }
+    }
+
+    // Write Shiplog to csv file
+    ship_logs_to_csv(filepath_output, &aishub_logs)?;
+
+    // Return success
+    return Ok(aishub_logs);
+}
+
+
+/// Helper function that checks if a file extensions matches the given file extension.
+/// A file called "mydata.csv" passed through this function with either ".csv" or "csv" will return true
+pub fn check_file_extension(filepath: &str, extension: &str) -> bool {
+    // Get num_chars in filepath and extension
+    let num_chars_in_file = filepath.len();
+    let num_chars_in_extension = extension.len();
+
+    // Check if filepath has at least the same number of characters as the extension. If fewer, retun false
+    if num_chars_in_file < num_chars_in_extension {
+        return false;
+    }
+
+    // Check if the filepath string ends with the extension, if so return true, otherwise return false
+    // Note: ends_with is used instead of slicing so that filepaths with multi-byte characters do not panic
+    return filepath.ends_with(extension);
+}
+
+/// Function that formats every file, not just a single file, in the input folder as long as they are from aishub_data_collector and formats them to work with marine_vessel_simulator 
+pub fn format_shipping_log_data(input_folder: &String, output_folder: &String,) -> Result<(), MarineSimError> {
+    // Get list of all files in input folder
+    let files: std::fs::ReadDir = std::fs::read_dir(std::path::Path::new(input_folder))?;
+
+    // Loop through all the files
+    for file in files {
+        // Get DirEntry
+        let file: std::fs::DirEntry = file?;
+
+        // Check if the file is a .csv file, if not, skip this file
+        let filename = file.file_name();
+        let filename: &str = filename.to_str().unwrap();
+        if !check_file_extension(filename, ".csv") {
+            // Notify user and skip file
+            tracing::info!("File {:?} is not a .csv file. Skipping this file", filename);
+            continue;
+        }
+
+        // Get input and output paths
+        let input_file_path: String = input_folder.clone() + "/" + filename;
+        let output_file_path: String = output_folder.clone() + "/" + filename;
+
+        // Run marine_vessel_simulator formatting function on each file
+        // Output formatted file into output folder
+        let _ = match aishub_shiplog_csv_to_marine_vessel_simulator_shiplog_csv(input_file_path.as_str(), output_file_path.as_str()) {
+            Ok(_) => {},
+            Err(e) => tracing::warn!("Could not format file {:?}. Error: {}", input_file_path, e),
+        };
+    }
+
+    // return ok
+    return Ok(());
+}
+
+/// Function that takes all the csv files in the input folder, filters them by the given values (if None then does not filter) and outputs them to the output folder
+/// Only .csv files formatted by aishub_data_collector can be in the input_folder
+/// All files in the output_folder that are named the same name as the files in the input_folder will be overwritten.
+/// navstat filter. Only ship log entries that have the same navigational status as given are included in the output file.
+/// min_vel filter. Minimum velocity, only ship log entries with a velocity strictly higher than the minimum are included in the output file. This means if the min_vel is zero then velocities of zero are not included but all higher velocities are. 
+pub fn filter_shipping_log_data(input_folder: &String, output_folder: &String, min_vel: Option<f64>, navstat: Option<NavigationStatus>) -> Result<(), MarineSimError> {
+    // Get list of all files in input folder
+    let files: std::fs::ReadDir = std::fs::read_dir(std::path::Path::new(input_folder))?;
+
+    // Loop through all the files
+    for file in files {
+        // Get DirEntry
+        let file: std::fs::DirEntry = file?;
+
+        // Check if the file is a .csv file, if not, skip this file
+        let filename = file.file_name();
+        let filename: &str = filename.to_str().unwrap();
+        if !check_file_extension(filename, ".csv") {
+            // Notify user and skip file
+            tracing::info!("File {:?} is not a .csv file. Skipping this file", filename);
+            continue;
+        }
+
+        // Get shiplogs from csv file. If there is an error, notify user and skip this file
+        let input_filepath = input_folder.clone() + "/" + filename;
+        let input_shiplog = match csv_to_ship_log(input_filepath.as_str()){
+            Ok(s) => s,
+            Err(e) => {
+                tracing::warn!("An error occured while getting shiplog data from {:?}. Skipping this file. Error message: {}", input_filepath.as_str(), e);
+                continue;
+            },
+        };
+
+        // init empty output shiplog
+        let mut output_shiplog: Vec<ShipLogEntry> = vec![];
+
+        // Loop through each entry in the shiplog
+        for entry in input_shiplog {
+            // For each entry, if it passes all the filters, add it to the output shiplog, if not, continue to next entry
+            // Check if velocity filter is in use
+            if min_vel.is_some() {
+                // Check if entry contains velocity, if not then continue to next entry
+                if entry.velocity.is_none() {
+                    continue;
+                }
+                // Check if entries velocity is higher than min_vel, if not then continue to next entry
+                if entry.velocity.unwrap().magnitude <= min_vel.unwrap() {
+                    continue;
+                }
+            }
+
+            // Check if navigational status filter is in use
+            if navstat.is_some() {
+                // Check if entry contains navstat, if not then continue to next entry
+                if entry.navigation_status.is_none() {
+                    continue;
+                }
+                // Check if navigation status of input shiplog entry matches filter, if not, skip this entry and continue to next one
+                if entry.navigation_status.unwrap() != navstat.unwrap() {
+                    continue;
+                }
+            }
+
+            // Add entry to output shiplog since all filters have been passed
+            output_shiplog.push(entry);
+        }
+
+        // If output shiplog is empty, do not make a filtered file, notify user and skip to next file
+        if output_shiplog.len() < 1 {
+            tracing::warn!("No data left after filtering {:?}", input_filepath);
+            continue;
+        }
+
+        // Check if output folder exists, if not, create it
+        if !std::path::Path::new(output_folder).exists() {
+            let _ = match std::fs::create_dir_all(output_folder) {
+                Ok(_) => {},
+                Err(e) => return Err(MarineSimError::Io(io::Error::new(e.kind(), format!("Error creating directory {:?}: {}", output_folder, e))))
+            };
+        }
+
+        // Make output filepath
+        let output_filepath: String = output_folder.clone() + "/" + filename;
+
+        // Check if file exists, if it does, delete it
+        if std::path::Path::new(&output_filepath).exists() {
+            let _ = match std::fs::remove_file(&output_filepath) {
+                Ok(_) => {},
+                Err(e) => return Err(MarineSimError::Io(io::Error::new(e.kind(), format!("Error removing file {:?}: {}", output_filepath, e)))),
+            };
+        }
+
+        // Save output shiplog to output folder and continue to next file
+        let _ = ship_logs_to_csv(output_filepath.as_str(), &output_shiplog);
+    }   // End loop through files
+
+    // Return ok
+    return Ok(());
+}
+
+/// Function that loops through all the data points in a polar plot source data csv file to find the speed grade coefficient of the vessel.
+/// The speed grade controls how quickly the vessel reaches v_max with increasing wind speed.
+/// v_max is the maximum velocity the vessel can reach (hull speed)
+/// returns the mean k and the standard deviation
+pub fn get_k(source_data_path: &str, vmax: f64) -> Result<(f64, f64), MarineSimError> {
+    // Check if the file is a .csv file, if not, return -1000.0
+    if !check_file_extension(source_data_path, ".csv") {
+        // Return error
+        return Err(MarineSimError::InvalidInput(format!("File {:?} is not a .csv file. Cannot calculate k value", source_data_path)));
+    }
+
+    // Init k vector
+    let mut k_vec: Vec<f64> = Vec::new();
+
+    // Read the CSV file
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .delimiter(b';')
+        .has_headers(true)
+        .from_path(source_data_path)?;
+
+    // Loop through all lines of file, compute k for each one and add to k_vec
+    for result in csv_reader.records() {
+        match result {
+            Ok(entry) => {
+                // Get AWA [°]
+                let awa: f64 = parse_required_csv_field::<f64>(&entry, 0, "AWA", source_data_path)?;
+                // Get AWS [m/s]
+                let aws: f64 = parse_required_csv_field::<f64>(&entry, 1, "AWS", source_data_path)?;
+                // Get VWS (Vessel water sped, the speed through water) [m/s]
+                let vws: f64 = parse_required_csv_field::<f64>(&entry, 2, "VWS", source_data_path)?;
+                // Get Heading [°]
+                // let heading: f64 = entry.get(3).unwrap().parse::<f64>().unwrap();
+                // Get wind speed [m/s]
+                // let wind_speed: f64 = entry.get(4).unwrap().parse::<f64>().unwrap();
+                // get wind angle [°]
+                // let wind_angle: f64 = entry.get(5).unwrap().parse::<f64>().unwrap();
+                // get ocean current speed [m/s]
+                // let ocean_current_speed: f64 = entry.get(6).unwrap().parse::<f64>().unwrap();
+                // get ocean current angle [°]
+                // let ocean_current_angle: f64 = entry.get(7).unwrap().parse::<f64>().unwrap();
+
+                // compute k
+                let k: f64 = (1.0/aws)*((std::f64::consts::PI * vws)/(2.0*vmax*(1.0 - (awa*std::f64::consts::PI/180.0 + (std::f64::consts::PI / 4.0)).cos()))).tan();
+
+                // add k to k_vec
+                k_vec.push(k);
+                },
+            // If there is some error with the result, notify user and continue to the next one
+            Err(e) => return Err(MarineSimError::CsvParse { file: source_data_path.to_string(), message: format!("Error getting entry: {}", e) }),
+        };
+    }
+
+    let k_vec_len: f64 = k_vec.len() as f64;
+
+    // Compute mean k
+    let mut sum_k: f64 = 0.0;
+    for k in k_vec.iter() {
+        sum_k = sum_k + k;
+    }
+    let mean_k: f64 = sum_k / k_vec_len;
+
+    // Compute standard deviation of k
+    let mut sum_k: f64 = 0.0;
+    for k in k_vec.iter() {
+        sum_k = sum_k + (k - mean_k).powi(2);
+    }
+    let std_k: f64 = (sum_k / (k_vec_len-1.0)).sqrt();
+
+    // Return vmax
+    return Ok((mean_k, std_k));
+}
+
+
+
+// Set up tests here
+//-----------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Test get_min_point_to_great_circle_dist function
+    #[test]
+    fn get_min_point_to_great_circle_dist_test() {
+        let tolerance = 1.0;
+        println!("Testing get_min_point_to_great_circle_dist function...");
+        println!("Tolerance: {} meter", tolerance);
+        println!("Earth radius: {} meters", Earth.radius());
+        // First test short distance on both sides of equator and close to both end points
+        let lon1 = 0.0;
+        let lat1 = 0.0;
+        let lon2 = 10.0;
+        let lat2 = 0.0;
+        let lon3 = 10.0;
+        let lat3 = 10.0;
+        let lon4 = 50.0;
+        let lat4 = -10.0;
+        let lon5 = 0.0;
+        let lat5 = 0.000001;
+        let lon6 = 10.0;
+        let lat6 = 0.000001;
+        let p1 = geo::Point::new(lon1, lat1);
+        let p2 = geo::Point::new(lon2, lat2);
+        let p3 = geo::Point::new(lon3, lat3);
+        let p4 = geo::Point::new(lon4, lat4);
+        let p5 = geo::Point::new(lon5, lat5);
+        let p6 = geo::Point::new(lon6, lat6);
+        let correct_dist = Earth.radius() * (lat3*2.0*std::f64::consts::PI/360.0); // 1111.950802335329128468111081452 kilometers
+        let dist = get_min_point_to_great_circle_dist(p1, p2, p3);
+        // Assert if dist is closer than the tolerance to the correct_dist
+        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
+        let dist = get_min_point_to_great_circle_dist(p1, p2, p4);
+        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
+        let correct_dist = Earth.radius() * (lat5*2.0*std::f64::consts::PI/360.0); // 1111.950802335329128468111081452 kilometers
+        let dist = get_min_point_to_great_circle_dist(p1, p2, p5);
+        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
+        let dist = get_min_point_to_great_circle_dist(p1, p2, p6);
+        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
+        
+        // Then test long distance from prime meridian
+        let lon1 = 0.0;
+        let lat1 = 89.0;
+        let lon2 = 0.0;
+        let lat2 = -89.0;
+        let lon3 = 0.0;
+        let lat3 = 0.0;
+        let lon4 = -50.0;
+        let lat4 = 0.0;
+        let lon5 = 0.0;
+        let lat5 = 89.000001;
+        let lon6 = 0.0;
+        let lat6 = -89.000001;
+        let p1 = geo::Point::new(lon1, lat1);
+        let p2 = geo::Point::new(lon2, lat2);
+        let p3 = geo::Point::new(lon3, lat3);
+        let p4 = geo::Point::new(lon4, lat4);
+        let p5 = geo::Point::new(lon5, lat5);
+        let p6 = geo::Point::new(lon6, lat6);
+        // Assert if dist is closer than the tolerance to the correct_dist
+        let correct_dist = 0.0;
+        let dist = get_min_point_to_great_circle_dist(p1, p2, p3);
+        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
+        // Assert if dist is closer than the tolerance to the correct_dist
+        let correct_dist = Earth.radius() * (lon4*2.0*std::f64::consts::PI/360.0).abs();
+        let dist = get_min_point_to_great_circle_dist(p1, p2, p4);
+        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
+
+        let correct_dist = Earth.radius() * ((lat5-lat1)*2.0*std::f64::consts::PI/360.0).abs();
+        let dist = get_min_point_to_great_circle_dist(p1, p2, p5);
+        // Assert if dist is closer than the tolerance to the correct_dist
+        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
+        let correct_dist = Earth.radius() * ((lat6-lat2)*2.0*std::f64::consts::PI/360.0).abs();
+        let dist = get_min_point_to_great_circle_dist(p1, p2, p6);
+        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
+        
+        // Test for edge cases where p1 or p2 and p3 are the same
+        let correct_dist = 0.0;
+        let dist = get_min_point_to_great_circle_dist(p1, p2, p1);
+        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
+        let dist = get_min_point_to_great_circle_dist(p1, p2, p2);
+        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0); 
+    }
+
+    // Test SailingLeg length, point_at, bearing_at and cross_track_distance on a leg along the equator
+    #[test]
+    fn sailing_leg_test() {
+        let tolerance = 1.0;
+        let leg = SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(10.0, 0.0), 1000.0, 100.0);
+        // 10° along the equator
+        let correct_dist = Earth.radius() * (10.0*2.0*std::f64::consts::PI/360.0);
+        assert_eq!((correct_dist-leg.length()).abs() <= tolerance, true, "Correct length: {:.2} km, calculated length: {:.2} km", correct_dist/1000.0, leg.length()/1000.0);
+
+        // Midpoint is 5° east on the equator
+        let midpoint = leg.point_at(0.5);
+        assert_eq!(Earth.distance(midpoint, geo::Point::new(5.0, 0.0)) <= tolerance, true, "Midpoint: {:?}", midpoint);
+        // End points and clamping
+        assert_eq!(leg.point_at(0.0), leg.p1);
+        assert_eq!(leg.point_at(1.5), leg.p2);
+
+        // Heading east the whole way
+        for fraction in [0.0, 0.5, 1.0] {
+            assert_eq!((leg.bearing_at(fraction) - 90.0).abs() < 1e-6, true, "Bearing at {}: {}", fraction, leg.bearing_at(fraction));
+        }
+
+        // 10° north of the leg is to the left (port) side, 10° south to the right (starboard) side
+        let correct_dist = Earth.radius() * (10.0*2.0*std::f64::consts::PI/360.0);
+        let dist = leg.cross_track_distance(geo::Point::new(10.0, 10.0));
+        assert_eq!((correct_dist+dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", -correct_dist/1000.0, dist/1000.0);
+        let dist = leg.cross_track_distance(geo::Point::new(50.0, -10.0));
+        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
+    }
+
+    // Test resample_ship_log on a ship log with an entry every 10 minutes resampled to every hour
+    #[test]
+    fn resample_ship_log_test() {
+        let start_time = time::UtcDateTime::from_unix_timestamp(0).unwrap();
+        let p1 = geo::Point::new(0.0, 0.0);
+        let p2 = geo::Point::new(1.0, 0.0);
+        let leg = SailingLeg::new(p1, p2, 0.0, 0.0);
+        // 2 hours and 30 minutes of sailing from p1 to p2
+        let ship_log: Vec<ShipLogEntry> = (0..=15).map(|i| ShipLogEntry::new(start_time + time::Duration::minutes(10*i), p1, leg.point_at(i as f64/15.0), p2, None, None, None, None, None, None, None, None)).collect();
+
+        let resampled_log = resample_ship_log(&ship_log, time::Duration::HOUR);
+        // Entries at 0, 1 and 2 hours and the last one at 2.5 hours
+        assert_eq!(resampled_log.len(), 4);
+        assert_eq!(resampled_log[1].timestamp, start_time + time::Duration::HOUR);
+        assert_eq!(resampled_log[3].timestamp, ship_log.last().unwrap().timestamp);
+        assert_eq!(resampled_log[3].coordinates_current, p2);
+        // After 1 hour the vessel is 6/15 of the way
+        assert_eq!(Earth.distance(resampled_log[1].coordinates_current, leg.point_at(6.0/15.0)) < 1.0, true, "Location after 1 hour: {:?}", resampled_log[1].coordinates_current);
+
+        // Resampling to a shorter interval than the log interpolates between entries
+        let resampled_log = resample_ship_log(&ship_log, time::Duration::minutes(5));
+        assert_eq!(resampled_log.len(), 31);
+        assert_eq!(Earth.distance(resampled_log[1].coordinates_current, leg.point_at(0.5/15.0)) < 1.0, true, "Location after 5 minutes: {:?}", resampled_log[1].coordinates_current);
+
+
+        // Aligned resampling of a log that starts 25 minutes past the hour has entries on the hour
+        let late_log: Vec<ShipLogEntry> = ship_log.iter().map(|entry| {
+            let mut entry = entry.clone();
+            entry.timestamp += time::Duration::minutes(25);
+            entry
+        }).collect();
+        let resampled_log = resample_ship_log_aligned(&late_log, time::Duration::HOUR);
+        assert_eq!(resampled_log.iter().map(|entry| entry.timestamp).collect::<Vec<UtcDateTime>>(), vec![start_time + time::Duration::minutes(25), start_time + time::Duration::HOUR, start_time + time::Duration::hours(2), start_time + time::Duration::minutes(175)]);
+    }
+
+    // Test the polar diagram interpolation and an isochrone route in constant wind
+    #[test]
+    fn isochrone_routing_test() {
+        let polar = PolarDiagram::new(
+            vec![0.0, 45.0, 90.0, 135.0, 180.0],
+            vec![5.0, 10.0],
+            vec![vec![0.0, 0.0], vec![2.0, 3.0], vec![3.0, 4.0], vec![3.0, 5.0], vec![2.0, 4.0]],
+        ).unwrap();
+        // On the grid, between grid values, on the other tack and below the lowest wind speed
+        assert_eq!((polar.boat_speed(10.0, 90.0) - 4.0).abs() < 1e-9, true);
+        assert_eq!((polar.boat_speed(7.5, 90.0) - 3.5).abs() < 1e-9, true);
+        assert_eq!((polar.boat_speed(10.0, 270.0) - 4.0).abs() < 1e-9, true);
+        assert_eq!((polar.boat_speed(2.5, 90.0) - 1.5).abs() < 1e-9, true);
+
+        // Wind from the west, sailing east from (0, 0) to (1, 0)
+        let start = geo::Point::new(0.0, 0.0);
+        let end = geo::Point::new(1.0, 0.0);
+        let weather = ConstantWeather::new(PhysVec::new(10.0, 270.0), None);
+        let router = IsochroneRouter::new(polar, time::Duration::HOUR);
+        let departure = time::UtcDateTime::from_unix_timestamp(0).unwrap();
+        let route_plan = router.route(&weather, start, end, departure).unwrap();
+
+        // The route starts and ends at the right points and is not much longer than the direct route
+        assert_eq!(route_plan.first().unwrap().p1, start);
+        assert_eq!(route_plan.last().unwrap().p2, end);
+        let route_length: f64 = route_plan.iter().map(|leg| leg.length()).sum();
+        let direct_length = Earth.distance(start, end);
+        assert_eq!(route_length < direct_length*1.1, true, "Route length: {:.2} km, direct length: {:.2} km", route_length/1000.0, direct_length/1000.0);
+    }
+
+    // Test that the grid router goes around an island in the way
+    #[test]
+    fn grid_routing_test() {
+        let polar = PolarDiagram::new(vec![0.0, 45.0, 90.0, 180.0], vec![10.0], vec![vec![0.0], vec![3.0], vec![4.0], vec![4.0]]).unwrap();
+        // Square island between the start and end
+        let island = geo::Polygon::new(geo::LineString::from(vec![(0.4, -0.2), (0.6, -0.2), (0.6, 0.2), (0.4, 0.2)]), vec![]);
+        let land_mask = LandMask::new(vec![island]);
+        let start = geo::Point::new(0.0, 0.0);
+        let end = geo::Point::new(1.0, 0.0);
+
+        // Wind from the north
+        let weather = ConstantWeather::new(PhysVec::new(10.0, 0.0), None);
+        let mut router = GridRouter::new(polar, 0.1);
+        router.margin = 1.0;
+        router.land_mask = Some(land_mask.clone());
+        let departure = time::UtcDateTime::from_unix_timestamp(0).unwrap();
+        let route_plan = router.route(&weather, start, end, departure).unwrap();
+
+        // The route goes from start to end without crossing the island
+        assert_eq!(route_plan.first().unwrap().p1, start);
+        assert_eq!(route_plan.last().unwrap().p2, end);
+        assert_eq!(land_mask.legs_crossing_land(&route_plan).is_empty(), true, "Route plan crosses land: {:?}", route_plan);
+    }
+
+    // Test that nested weather uses the regional model inside its area, the global model outside it and blends them at the edge
+    #[test]
+    fn nested_weather_test() {
+        let timestamp = time::UtcDateTime::from_unix_timestamp(0).unwrap();
+        let mut weather = NestedWeather::new(Box::new(ConstantWeather::new(PhysVec::new(10.0, 0.0), None)));
+        weather.blend_width = 1.0;
+        weather.add_region(WeatherRegion::new("Regional", geo::Rect::new((0.0, 0.0), (10.0, 10.0)), 1, Box::new(ConstantWeather::new(PhysVec::new(10.0, 90.0), Some(PhysVec::new(1.0, 180.0))))));
+
+        // Inside, outside and half way into the blending zone
+        let wind_inside = weather.wind(timestamp, geo::Point::new(5.0, 5.0)).unwrap();
+        let wind_outside = weather.wind(timestamp, geo::Point::new(20.0, 5.0)).unwrap();
+        let wind_edge = weather.wind(timestamp, geo::Point::new(5.0, 0.5)).unwrap();
+        assert_eq!((wind_inside.magnitude - 10.0).abs() < 1e-9 && (wind_inside.angle - 90.0).abs() < 1e-9, true, "Wind inside: {}", wind_inside);
+        assert_eq!((wind_outside.magnitude - 10.0).abs() < 1e-9 && wind_outside.angle.abs() < 1e-9, true, "Wind outside: {}", wind_outside);
+        assert_eq!((wind_edge.angle - 45.0).abs() < 1e-9, true, "Wind at the edge: {}", wind_edge);
+
+        // Only the regional model knows the ocean current
+        assert_eq!(weather.ocean_current(timestamp, geo::Point::new(20.0, 5.0)).unwrap(), None);
+        assert_eq!((weather.ocean_current(timestamp, geo::Point::new(5.0, 0.5)).unwrap().unwrap().magnitude - 1.0).abs() < 1e-9, true);
+    }
 
-        // Write the record
-        writer_min.write_record(&record)?;
+    // Test that a route plan from Scotland to Svalbard crosses the Arctic circle, the prime meridian and 10°E in that order
+    #[test]
+    fn route_crossings_test() {
+        let route_plan = vec![SailingLeg::new(geo::Point::new(-5.0, 58.0), geo::Point::new(15.0, 78.0), 0.0, 0.0)];
+        let crossings = get_route_plan_crossings(&route_plan, 10.0);
+        let names: Vec<String> = crossings.iter().map(|c| c.line.name()).collect();
+        assert_eq!(names, vec!["Arctic circle", "Prime meridian", "Meridian 10°E"], "Crossings: {:?}", crossings);
+        assert_eq!((crossings[0].location.y() - ARCTIC_CIRCLE_LATITUDE).abs() < 1e-9, true);
+        assert_eq!(crossings[0].leg, Some(1));
     }
 
-    // Flush and close the writer
-    writer_min.flush()?;
+    // Test rerouting a leg around a restricted zone in the way
+    #[test]
+    fn restricted_zone_reroute_test() {
+        let zone_polygon = geo::Polygon::new(geo::LineString::from(vec![(0.4, -0.2), (0.6, -0.2), (0.6, 0.3), (0.4, 0.3)]), vec![]);
+        let mut route_plan = RoutePlan::great_circle(geo::Point::new(0.0, 0.0), geo::Point::new(1.0, 0.0), 0.0);
+        route_plan.zones.push(RestrictedZone::new("Firing range", ZoneKind::MilitaryZone, ZoneAction::Avoid, zone_polygon));
+
+        // One leg is rerouted, the new legs start and end at the same points and go around the zone on the short (south) side
+        assert_eq!(route_plan.reroute_around_zones().unwrap(), 1);
+        assert_eq!(route_plan.len() > 1, true);
+        assert_eq!(route_plan.first().unwrap().p1, geo::Point::new(0.0, 0.0));
+        assert_eq!(route_plan.last().unwrap().p2, geo::Point::new(1.0, 0.0));
+        assert_eq!(route_plan.iter().any(|leg| route_plan.zones[0].crosses(leg.p1, leg.p2)), false);
+        assert_eq!(route_plan.iter().all(|leg| leg.p2.y() <= 0.0), true, "Route plan: {:?}", route_plan.legs);
+
+        // The simulation reroutes a copy, the boat keeps its own route plan after the voyage, also if the voyage fails
+        let mut boat = Boat::new();
+        let mut route_plan = RoutePlan::great_circle(geo::Point::new(0.0, 0.0), geo::Point::new(1.0, 0.0), 0.0);
+        route_plan.zones.push(RestrictedZone::new("Firing range", ZoneKind::MilitaryZone, ZoneAction::Avoid, geo::Polygon::new(geo::LineString::from(vec![(0.4, -0.2), (0.6, -0.2), (0.6, 0.3), (0.4, 0.3)]), vec![])));
+        let num_legs = route_plan.len();
+        boat.route_plan = Some(route_plan);
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+        assert_eq!(sim_waypoint_mission(&mut boat, start_time, &simulation).is_err(), true, "The boat has no mean velocity");
+        assert_eq!(boat.route_plan.as_ref().unwrap().len(), num_legs);
+        boat.velocity_mean = Some(5.0);
+        assert_eq!(sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap().is_completed(), true);
+        assert_eq!(boat.route_plan.as_ref().unwrap().len(), num_legs);
+    }
 
-    // Saving the file with the mean data
-    // Create a CSV writer with a semicolon delimiter
-    let mut writer_mean = csv::WriterBuilder::new()
-        .delimiter(b';')
-        .has_headers(true)
-        .from_path(working_file_path_mean)?;
+    #[test]
+    fn port_call_test() {
+        // Two legs along the equator with a port call at the middle waypoint
+        let mut legs = vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 0.0),
+            SailingLeg::new(geo::Point::new(0.5, 0.0), geo::Point::new(1.0, 0.0), 0.0, 0.0),
+        ];
+        legs[0].port_call = Some(PortCall::new(100.0, 40.0, time::Duration::hours(12)));
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(legs));
+        boat.velocity_mean = Some(5.0);
+        boat.cargo_current = uom::si::f64::Mass::new::<uom::si::mass::ton>(50.0);
+        boat.cargo_max_capacity = Some(uom::si::f64::Mass::new::<uom::si::mass::ton>(200.0));
+        boat.draft = Some(5.0);
+        boat.tons_per_cm_immersion = Some(20.0);
+
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+        let (status, _) = sim_waypoint_mission_constant_velocity(&mut boat, start_time, &simulation).unwrap();
+        assert_eq!(status, SimulationStatus::Completed);
+
+        // 60 tons loaded, 3 cm deeper
+        assert_eq!((boat.cargo_current.get::<uom::si::mass::ton>() - 110.0).abs() < 1e-9, true);
+        assert_eq!((boat.draft.unwrap() - 5.03).abs() < 1e-9, true);
+        assert_eq!(boat.events.iter().any(|event| matches!(event.kind, SimulationEventKind::PortCall { .. })), true);
+
+        // The voyage takes the sailing time plus the wait in port, the sailing time is about 111 km / 5 m/s = 6.2 hours rounded up to whole time steps
+        let duration = boat.ship_log.last().unwrap().timestamp - start_time;
+        assert_eq!(duration >= time::Duration::hours(18) && duration <= time::Duration::hours(20), true, "Duration: {}", duration);
+        let moored_entries = boat.ship_log.iter().filter(|entry| entry.navigation_status == Some(NavigationStatus::Moored)).count();
+        assert_eq!(moored_entries, 2);
+
+        // Cargo by type is unloaded and loaded at the port call, so the cargo statistics match the cargo on board after it
+        let make_boat = |port_call: PortCall| {
+            let mut legs = vec![
+                SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 0.0),
+                SailingLeg::new(geo::Point::new(0.5, 0.0), geo::Point::new(1.0, 0.0), 0.0, 0.0),
+            ];
+            legs[0].port_call = Some(port_call);
+            let mut boat = Boat::new();
+            boat.route_plan = Some(RoutePlan::new(legs));
+            boat.velocity_mean = Some(5.0);
+            boat.teu_capacity = Some(100);
+            boat.add_cargo(Cargo::Containers { teu: 80, mass: uom::si::f64::Mass::new::<uom::si::mass::ton>(1000.0) }).unwrap();
+            return boat;
+        };
+        let port_call = PortCall::new(0.0, 0.0, time::Duration::hours(12)).with_cargo_unload(CargoType::Containers).with_cargo_load(Cargo::Bulk { mass: uom::si::f64::Mass::new::<uom::si::mass::ton>(600.0) });
+        let mut boat = make_boat(port_call);
+        let result = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        assert_eq!(result.is_completed(), true);
+        assert_eq!((boat.cargo_current.get::<uom::si::mass::ton>() - 600.0).abs() < 1e-9, true, "Cargo on board: {:?}", boat.cargo_current);
+        assert_eq!(boat.get_cargo_amount(CargoType::Containers), 0.0);
+        let statistics = get_cargo_statistics(&boat, &result.kpis);
+        assert_eq!(statistics.len(), 1);
+        assert_eq!(statistics[0].cargo_type, CargoType::Bulk);
+        assert_eq!((statistics[0].amount - 600.0).abs() < 1e-9, true);
+
+        // Untyped cargo with cargo by type on board is an error, as is loading more containers than fit
+        let mut boat = make_boat(PortCall::new(10.0, 0.0, time::Duration::hours(12)));
+        assert_eq!(sim_waypoint_mission(&mut boat, start_time, &simulation).is_err(), true);
+        let mut boat = make_boat(PortCall::new(0.0, 0.0, time::Duration::hours(12)).with_cargo_load(Cargo::Containers { teu: 40, mass: uom::si::f64::Mass::new::<uom::si::mass::ton>(500.0) }));
+        assert_eq!(sim_waypoint_mission(&mut boat, start_time, &simulation).is_err(), true);
+        assert_eq!(boat.get_cargo_amount(CargoType::Containers), 80.0);
+    }
 
-    // Write the header
-    let mut header_vec: Vec<String> = Vec::new();
-    // First column in the header is "TWA\TWS" and not "wind angle [°]" because that is what openCPN uses
-    header_vec.push("TWA\\TWS".to_string());
-    for i in 1..(num_wind_speed_segments+1) {
-        // If knots, format in knots
-        if true_if_knots_false_if_meters_per_second {
-            header_vec.push(format!("{}", (i as f64)*working_wind_speed_segment_size*1.94384));
-        } // Otherwise use meters per second (preferred)
-        else {
-            header_vec.push(format!("{}", (i as f64)*working_wind_speed_segment_size));
+    #[test]
+    fn fit_polar_diagram_test() {
+        // Ship log with wind from the north, fast on a beam reach (heading east) and slow going downwind (heading south)
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let mut ship_log: Vec<ShipLogEntry> = Vec::new();
+        for i in 0..20 {
+            let heading = match i % 2 {
+                0 => 90.0,
+                _ => 180.0,
+            };
+            let speed = match i % 2 {
+                0 => 6.0,
+                _ => 3.0,
+            };
+            let mut entry = ShipLogEntry::new(start_time + time::Duration::hours(i), geo::Point::new(0.0, 0.0), geo::Point::new(0.0, 0.0), geo::Point::new(1.0, 0.0), None, Some(PhysVec::new(speed, heading)), None, Some(heading), None, None, None, None);
+            entry.wind = Some(PhysVec::new(10.0, 0.0));
+            ship_log.push(entry);
         }
-    }
-    
-    writer_mean.write_record(&header_vec)?;
 
-    // Write the standard_data_vector_mean into the csv file
-    for row in standard_data_vector_mean.iter() {
-        // Init empty record to write
-        let mut record: Vec<String> = Vec::new();
-        // Add the wind angle to the first column
-        // record.push(row[0].0);
+        let polar_diagram = PolarDiagram::fit_to_ship_log(&ship_log, None, 10.0, 2.0).unwrap();
+        assert_eq!((polar_diagram.boat_speed(10.0, 90.0) - 6.0).abs() < 1e-9, true);
+        assert_eq!((polar_diagram.boat_speed(10.0, 180.0) - 3.0).abs() < 1e-9, true);
+        // Interpolated between the two
+        assert_eq!((polar_diagram.boat_speed(10.0, 135.0) - 4.5).abs() < 1e-9, true);
 
-        // For the rest of the row, if the boat speed is None, add an empty string, else, add the boat speed to the record
-        // Note: The first cell should always be some as it should include the apparent wind angle
-        for (i, cell) in row.iter().enumerate() {
-            // If the value is Some, add it
-            if cell.1.is_some() {
-                // If it's the first column, just add it since it is the angle of the wind
-                if i == 0 {
-                    record.push(cell.1.unwrap().to_string());
-                } // Otherwise, check if we're using knots or meters per second
-                else {
-                    // If knots, transform to knots
-                    if true_if_knots_false_if_meters_per_second {
-                        record.push((cell.1.unwrap() * 1.94384).to_string());
-                    } // Otherwise, use meters_per_second
-                    else {
-                        record.push(cell.1.unwrap().to_string());
-                    }
-                }
-            } // Otherwise, add empty string
-            else {
-                record.push(String::new());
-            }
+        // No wind and no weather provider, nothing to fit to
+        for entry in ship_log.iter_mut() {
+            entry.wind = None;
         }
+        assert_eq!(PolarDiagram::fit_to_ship_log(&ship_log, None, 10.0, 2.0).is_err(), true);
+    }
 
-        // Write the record
-        writer_mean.write_record(&record)?;
+    #[test]
+    fn round_trip_schedule_test() {
+        // About 111 km each way at 5 m/s, 7 hours of sailing each way and 5 hours in port at each end
+        // A round trip takes 19 hours from departure until the vessel is back, with the 5 hours in the home port a new round trip starts every 24 hours
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(1.0, 0.0), 0.0, 0.0)]));
+        boat.velocity_mean = Some(5.0);
+        let period_start = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![], time::Duration::hours(1), 1000, None, None);
+        let schedule = sim_round_trips(&mut boat, &simulation, period_start, period_start + time::Duration::days(10), time::Duration::hours(5)).unwrap();
+
+        assert_eq!(schedule.round_trips.len(), 10, "Round trips: {:?}", schedule.round_trips);
+        assert_eq!((schedule.round_trips_per_year - 365.25).abs() < 1e-6, true);
+        assert_eq!(schedule.round_trip_duration_mean, Some(time::Duration::hours(19)));
+        // The vessel ends each round trip back at the start and the route plan is left as it was
+        assert_eq!(boat.ship_log.last().unwrap().coordinates_current, geo::Point::new(0.0, 0.0));
+        assert_eq!(boat.route_plan.as_ref().unwrap()[0].p2, geo::Point::new(1.0, 0.0));
     }
 
-    // Flush and close the writer
-    writer_mean.flush()?;
-    
-    // Saving the file with the maximum data
-    // Create a CSV writer with a semicolon delimiter
-    let mut writer_max = csv::WriterBuilder::new()
-        .delimiter(b';')
-        .has_headers(true)
-        .from_path(working_file_path_max)?;
+    #[test]
+    fn append_shipping_statistics_test() {
+        let file_path = std::env::temp_dir().join("marine_vessel_simulator_append_shipping_statistics_test.csv");
+        let file_path = file_path.to_str().unwrap();
+        let _ = std::fs::remove_file(file_path);
+
+        // Add two scenarios one at a time, then update the first
+        let mut statistics = ShippingStatistics::default();
+        statistics.speed_mean = Some(5.0);
+        statistics.num_trips = 3;
+        append_shipping_statistics_to_csv(file_path, "Sail", &statistics).unwrap();
+        append_shipping_statistics_to_csv(file_path, "Motor", &statistics).unwrap();
+        statistics.speed_mean = Some(6.0);
+        append_shipping_statistics_to_csv(file_path, "Sail", &statistics).unwrap();
+
+        let rows = read_shipping_statistics_rows(file_path).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].0[0], "Sail");
+        assert_eq!(rows[0].0[1], "6");
+        assert_eq!(rows[1].0[0], "Motor");
+        assert_eq!(rows[1].0[1], "5");
+        assert_eq!(rows[1].0[2], "");
+        assert_eq!(rows[1].0[11], "3");
+
+        // Metadata columns, rows without a key get an empty cell
+        let mut tagged = statistics.clone();
+        tagged.set_metadata("vessel_class", "schooner");
+        tagged.set_metadata("seed", "42");
+        append_shipping_statistics_to_csv(file_path, "Sail 2024", &tagged).unwrap();
+        let rows = read_shipping_statistics_rows(file_path).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].1.is_empty(), true);
+        assert_eq!(rows[2].1, vec![("vessel_class".to_string(), "schooner".to_string()), ("seed".to_string(), "42".to_string())]);
+        let _ = std::fs::remove_file(file_path);
+    }
 
-    // Write the header
-    let mut header_vec: Vec<String> = Vec::new();
-    // First column in the header is "TWA\TWS" and not "wind angle [°]" because that is what openCPN uses
-    header_vec.push("TWA\\TWS".to_string());
-    for i in 1..(num_wind_speed_segments+1) {
-        // If knots, format in knots
-        if true_if_knots_false_if_meters_per_second {
-            header_vec.push(format!("{}", (i as f64)*working_wind_speed_segment_size*1.94384));
-        } // Otherwise use meters per second (preferred)
-        else {
-            header_vec.push(format!("{}", (i as f64)*working_wind_speed_segment_size));
+    #[test]
+    fn earth_model_test() {
+        // The default is a sphere with the mean radius of the Earth
+        assert_eq!(get_earth_model(), EarthModel::MEAN_SPHERE);
+        let p1 = geo::Point::new(0.0, 0.0);
+        let p2 = geo::Point::new(1.0, 0.0);
+        let tolerance = 0.01;
+
+        // 1° along the equator on the sphere and on the WGS84 ellipsoid, which is wider at the equator
+        let sphere_dist = EarthModel::MEAN_SPHERE.distance(p1, p2);
+        let correct_dist = MEAN_EARTH_RADIUS * std::f64::consts::PI/180.0;
+        assert_eq!((sphere_dist - correct_dist).abs() < tolerance, true, "Sphere distance: {} m", sphere_dist);
+        let wgs84_dist = EarthModel::Wgs84.distance(p1, p2);
+        assert_eq!((wgs84_dist - 111319.49).abs() < tolerance, true, "WGS84 distance: {} m", wgs84_dist);
+
+        // Bearings are in [0, 360) and destinations go back to the points
+        for model in [EarthModel::MEAN_SPHERE, EarthModel::Sphere { radius: 6378137.0 }, EarthModel::Wgs84] {
+            assert_eq!((model.bearing(p2, p1) - 270.0).abs() < 1e-6, true, "{:?} bearing: {}", model, model.bearing(p2, p1));
+            let destination = model.destination(p1, model.bearing(p1, p2), model.distance(p1, p2));
+            assert_eq!(model.distance(destination, p2) < tolerance, true, "{:?} destination: {:?}", model, destination);
         }
     }
-    
-    writer_max.write_record(&header_vec)?;
 
-    // Write the standard_data_vector into the csv file
-    for row in standard_data_vector_max.iter() {
-        // Init empty record to write
-        let mut record: Vec<String> = Vec::new();
-        // Add the wind angle to the first column
-        // record.push(row[0].0);
+    #[test]
+    fn waypoint_arrivals_test() {
+        // Two legs of 0.5° along the equator at 5 m/s with 1 hour time steps
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 0.0),
+            SailingLeg::new(geo::Point::new(0.5, 0.0), geo::Point::new(1.0, 0.0), 0.0, 0.0),
+        ]));
+        boat.velocity_mean = Some(5.0);
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+        let kpis = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap().kpis;
+
+        // The arrival times are interpolated within the time steps
+        assert_eq!(kpis.waypoint_arrivals.len(), 2);
+        assert_eq!(kpis.waypoint_arrivals, boat.waypoint_arrivals);
+        for (i, arrival) in kpis.waypoint_arrivals.iter().enumerate() {
+            assert_eq!(arrival.leg, i as u32 + 1);
+            let correct_time = Earth.distance(geo::Point::new(0.0, 0.0), arrival.waypoint) / 5.0;
+            let time = (arrival.timestamp - start_time).as_seconds_f64();
+            assert_eq!((time - correct_time).abs() < 1.0, true, "Arrival at waypoint {}: {} s, should be {} s", i + 1, time, correct_time);
+        }
+    }
 
-        // For the rest of the row, if the boat speed is None, add an empty string, else, add the boat speed to the record
-        // Note: The first cell should always be some as it should include the apparent wind angle
-        for (i, cell) in row.iter().enumerate() {
-            // If the value is Some, add it
-            if cell.1.is_some() {
-                // If it's the first column, just add it since it is the angle of the wind
-                if i == 0 {
-                    record.push(cell.1.unwrap().to_string());
-                } // Otherwise, check if we're using knots or meters per second
-                else {
-                    // If knots, transform to knots
-                    if true_if_knots_false_if_meters_per_second {
-                        record.push((cell.1.unwrap() * 1.94384).to_string());
-                    } // Otherwise, use meters_per_second
-                    else {
-                        record.push(cell.1.unwrap().to_string());
-                    }
-                }
-            } // Otherwise, add empty string
-            else {
-                record.push(String::new());
-            }
+    #[test]
+    fn monte_carlo_test() {
+        // Statistics of known values
+        let stats = DistributionStats::from_values(&vec![4.0, 1.0, 3.0, 2.0, 5.0, f64::NAN]).unwrap();
+        assert_eq!(stats.n, 5);
+        assert_eq!((stats.mean - 3.0).abs() < 1e-9, true, "Mean: {}, should be 3", stats.mean);
+        assert_eq!((stats.std - 2.5_f64.sqrt()).abs() < 1e-9, true, "Standard deviation: {}, should be {}", stats.std, 2.5_f64.sqrt());
+        assert_eq!((stats.min, stats.max), (1.0, 5.0));
+        assert_eq!((stats.p5 - 1.2).abs() < 1e-9, true, "5th percentile: {}, should be 1.2", stats.p5);
+        assert_eq!((stats.p50 - 3.0).abs() < 1e-9, true, "Median: {}, should be 3", stats.p50);
+        assert_eq!(DistributionStats::from_values(&vec![f64::NAN]), None);
+
+        // Ensemble of one leg of 0.5° along the equator with a random velocity and departure
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 0.0),
+        ]));
+        boat.velocity_mean = Some(5.0);
+        boat.velocity_std = Some(0.5);
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let mut simulation = Simulation::new(SimMethod::MeanAndSTDVelocity, vec![start_time], time::Duration::minutes(10), 1000, None, None);
+        simulation.departure_jitter = Some(time::Duration::hours(6));
+        let result = run_monte_carlo(&mut boat, &mut simulation, 20).unwrap();
+        assert_eq!(result.n_runs, 20);
+        assert_eq!(result.runs.len(), 20);
+        assert_eq!(result.n_completed, 20);
+        for (departure, _) in &result.runs {
+            assert_eq!((*departure - start_time).abs() <= time::Duration::hours(6), true, "Departure {} is outside the jitter", departure);
         }
 
-        // Write the record
-        writer_max.write_record(&record)?;
+        // The travel time is around the time at the mean velocity
+        let travel_time = result.travel_time.unwrap();
+        let mean_time = Earth.distance(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0)) / 5.0;
+        assert_eq!(travel_time.p5 <= travel_time.p50 && travel_time.p50 <= travel_time.p95, true, "Percentiles out of order: {:?}", travel_time);
+        assert_eq!(travel_time.mean_ci95.0 <= travel_time.mean && travel_time.mean <= travel_time.mean_ci95.1, true, "Mean outside its confidence interval: {:?}", travel_time);
+        assert_eq!((travel_time.mean - mean_time).abs() < 0.2*mean_time, true, "Mean travel time: {} s, should be around {} s", travel_time.mean, mean_time);
+
+        // No start time
+        simulation.start_times = Vec::new();
+        assert_eq!(run_monte_carlo(&mut boat, &mut simulation, 20).is_err(), true);
     }
 
-    // Flush and close the writer
-    writer_max.flush()?;
+    #[test]
+    fn visualize_results_folder_test() {
+        let results_folder = std::env::temp_dir().join("marine_vessel_simulator_visualize_results_folder_test");
+        let output_folder = results_folder.join("figures");
+        let _ = std::fs::remove_dir_all(&results_folder);
+        std::fs::create_dir_all(&results_folder).unwrap();
+        let results_folder = results_folder.to_str().unwrap();
+        let output_folder = output_folder.to_str().unwrap();
+
+        // Simulate a voyage and save the ship log under two names, one with its own route plan and one with the shared route plan
+        let route_plan_csv = "leg;start_lat;start_lon;end_lat;end_lon;tacking_width;min_proximity\n1;0.0;0.0;0.0;0.5;0.0;0.0\n";
+        std::fs::write(format!("{}/own{}", results_folder, ROUTE_PLAN_FILE_SUFFIX), route_plan_csv).unwrap();
+        std::fs::write(format!("{}/{}", results_folder, SHARED_ROUTE_PLAN_FILE_NAME), route_plan_csv).unwrap();
+        let mut boat = Boat::new();
+        boat.route_plan = Some(load_route_plan(&format!("{}/{}", results_folder, SHARED_ROUTE_PLAN_FILE_NAME)).unwrap());
+        boat.velocity_mean = Some(5.0);
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+        sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        ship_logs_to_csv(&format!("{}/own{}", results_folder, SHIP_LOG_FILE_SUFFIX), &boat.ship_log).unwrap();
+        ship_logs_to_csv(&format!("{}/shared{}", results_folder, SHIP_LOG_FILE_SUFFIX), &boat.ship_log).unwrap();
+
+        // Pairing by name
+        let results = find_results_in_folder(results_folder).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].0, "own");
+        assert_eq!(results[0].2.ends_with(&format!("own{}", ROUTE_PLAN_FILE_SUFFIX)), true);
+        assert_eq!(results[1].0, "shared");
+        assert_eq!(results[1].2.ends_with(SHARED_ROUTE_PLAN_FILE_NAME), true);
+
+        // Maps and report
+        let names = visualize_results_folder(results_folder, output_folder).unwrap();
+        assert_eq!(names, vec!["own".to_string(), "shared".to_string()]);
+        assert_eq!(std::path::Path::new(&format!("{}/own.html", output_folder)).is_file(), true);
+        let report = std::fs::read_to_string(format!("{}/report.csv", output_folder)).unwrap();
+        assert_eq!(report.lines().count(), 3);
+
+        // The manifest takes precedence over the names
+        std::fs::write(format!("{}/{}", results_folder, RESULTS_MANIFEST_FILE_NAME), format!("name;ship_log;route_plan\nonly;shared{};{}\n", SHIP_LOG_FILE_SUFFIX, SHARED_ROUTE_PLAN_FILE_NAME)).unwrap();
+        let results = find_results_in_folder(results_folder).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, "only");
+
+        let _ = std::fs::remove_dir_all(results_folder);
+    }
 
-    // Save the source data
-    // Create a CSV writer with a semicolon delimiter
-    let mut writer_source_data = csv::WriterBuilder::new()
-        .delimiter(b';')
-        .has_headers(true)
-        .from_path(working_file_path_source)?;
+    #[test]
+    fn simulation_events_test() {
+        // Two legs of 0.5° along the equator at 5 m/s
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 0.0),
+            SailingLeg::new(geo::Point::new(0.5, 0.0), geo::Point::new(1.0, 0.0), 0.0, 0.0),
+        ]));
+        boat.velocity_mean = Some(5.0);
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let mut simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+
+        // Record the events with a callback
+        let received: std::sync::Arc<std::sync::Mutex<Vec<SimulationEvent>>> = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let received_clone = received.clone();
+        simulation.add_event_callback(move |event| received_clone.lock().unwrap().push(event.clone()));
+        let sim_result = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        assert_eq!(sim_result.status, SimulationStatus::Completed);
+        let kpis = sim_result.kpis;
+
+        // The callback, the boat and the KPIs get the same events in chronological order
+        let received = received.lock().unwrap().clone();
+        assert_eq!(received, kpis.events);
+        assert_eq!(received, boat.events);
+        let kinds: Vec<(Option<u32>, &SimulationEventKind)> = received.iter().map(|event| (event.leg, &event.kind)).collect();
+        assert_eq!(kinds, vec![
+            (Some(1), &SimulationEventKind::LegStarted),
+            (Some(1), &SimulationEventKind::WaypointReached { waypoint: geo::Point::new(0.5, 0.0) }),
+            (Some(2), &SimulationEventKind::LegStarted),
+            (Some(2), &SimulationEventKind::WaypointReached { waypoint: geo::Point::new(1.0, 0.0) }),
+            (boat.current_leg, &SimulationEventKind::SimulationFinished { status: SimulationStatus::Completed }),
+        ]);
+        for i in 1..received.len() {
+            assert_eq!(received[i-1].timestamp <= received[i].timestamp, true, "Events out of order: {:?}", received);
+        }
+    }
 
-    // Write the header
-    let mut header_vec: Vec<String> = Vec::new();
-    // Column 1 is the apparent wind angle, column 2 is the apparent wind speed, column 3 is the vessel speed through water, column 4 is the heading, column 5 is the wind speed (not apparent), column 6 is the wind angle (not apparent), column 7 is the ocean current speed (not apparent), column 8 is the ocean current angle (not apparent).
-    header_vec.push("AWA [°]".to_string());
-    // If knots, format in knots
-    if true_if_knots_false_if_meters_per_second {
-        header_vec.push("AWS [knots]".to_string());
-        header_vec.push("Vessel speed through water [knots]".to_string());
-    } // Otherwise use meters per second (preferred)
-    else {
-        header_vec.push("AWS [m/s]".to_string());
-        header_vec.push("Vessel speed through water [m/s]".to_string());
+    #[test]
+    fn simulation_result_test() {
+        // One leg of 0.5° along the equator at 5 m/s with 1 hour time steps, about 55.6 km so 4 time steps of 18 km
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 0.0),
+        ]));
+        boat.velocity_mean = Some(5.0);
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+        let sim_result = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        assert_eq!(sim_result.status, SimulationStatus::Completed);
+        assert_eq!(sim_result.is_completed(), true);
+        assert_eq!(sim_result.iterations, 4);
+        assert_eq!(sim_result.kpis.num_tacks, Some(0));
+        let distance = Earth.distance(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0));
+        assert_eq!((sim_result.kpis.distance - distance).abs() < 1.0, true, "Distance: {} m, should be {} m", sim_result.kpis.distance, distance);
+
+        // Running out of iterations
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 2, None, None);
+        let sim_result = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        assert_eq!(sim_result.status, SimulationStatus::MaxIterationsReached);
+        assert_eq!(sim_result.is_completed(), false);
+        assert_eq!(sim_result.iterations, 2);
+        assert_eq!(sim_result.status.to_string(), "Maximized number of iterations. Stopping simulation");
+
+        // Running out of simulated time stops after the same time with any time step
+        for time_step in [time::Duration::minutes(10), time::Duration::hours(1)] {
+            let mut simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time_step, 1000, None, None);
+            simulation.max_simulated_duration = Some(time::Duration::hours(2));
+            let sim_result = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+            assert_eq!(sim_result.status, SimulationStatus::MaxSimulatedDurationReached);
+            assert_eq!(sim_result.kpis.duration, time::Duration::hours(2));
+        }
     }
-    header_vec.push("Heading [°]".to_string());
-    header_vec.push("wind speed [m/s]".to_string());
-    header_vec.push("wind angle [°]".to_string());
-    header_vec.push("ocean current speed [m/s]".to_string());
-    header_vec.push("ocean current angle [°]".to_string());
-    
-    writer_source_data.write_record(&header_vec)?;
 
-    // Write the standard_data_vector into the csv file
-    for row in polar_plot_data_vector.iter() {
-        // Init empty record to write
-        let mut record: Vec<String> = Vec::new();
+    #[test]
+    fn find_latest_departure_test() {
+        // One leg of 0.5° along the equator at 5 m/s takes about 3.1 hours
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 0.0),
+        ]));
+        boat.velocity_mean = Some(5.0);
+        let earliest_departure = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![earliest_departure], time::Duration::minutes(10), 1000, None, None);
+        let travel_time = time::Duration::seconds_f64(Earth.distance(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0)) / 5.0);
+
+        // Arrive within a day, the latest departure is the deadline minus the travel time
+        let required_arrival = earliest_departure + time::Duration::days(1);
+        let tolerance = time::Duration::minutes(5);
+        let latest = find_latest_departure(&mut boat, &simulation, earliest_departure, required_arrival, tolerance).unwrap().unwrap();
+        let correct_departure = required_arrival - travel_time;
+        assert_eq!(latest.departure <= correct_departure, true, "Departure {} is too late, should be by {}", latest.departure, correct_departure);
+        assert_eq!(correct_departure - latest.departure <= tolerance, true, "Departure {} is too early, should be {}", latest.departure, correct_departure);
+        assert_eq!(latest.arrival <= required_arrival, true);
+        assert_eq!(latest.slack, required_arrival - latest.arrival);
+
+        // Impossible deadline
+        let required_arrival = earliest_departure + time::Duration::hours(2);
+        assert_eq!(find_latest_departure(&mut boat, &simulation, earliest_departure, required_arrival, tolerance).unwrap(), None);
+    }
 
-        // Add the first cell (angle) to the record
-        record.push(row[0].to_string());
+    #[test]
+    fn replay_ship_log_test() {
+        let file_path = std::env::temp_dir().join("marine_vessel_simulator_replay_ship_log_test.csv");
+        let file_path = file_path.to_str().unwrap();
+
+        // Simulate a voyage and export the ship log
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 0.0),
+        ]));
+        boat.velocity_mean = Some(5.0);
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+        let kpis = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap().kpis;
+        ship_logs_to_csv(file_path, &boat.ship_log).unwrap();
+
+        // The replayed boat ends where the simulated boat ended, with the same voyage
+        let replayed = replay_ship_log(file_path).unwrap();
+        assert_eq!(replayed.ship_log.len(), boat.ship_log.len());
+        assert_eq!(replayed.time_now, boat.ship_log.last().unwrap().timestamp);
+        let dist = Earth.distance(replayed.location.unwrap(), geo::Point::new(0.5, 0.0));
+        assert_eq!(dist < 1.0, true, "Replayed boat is {} m from the end of the route", dist);
+        let replayed_kpis = VoyageKpis::from_ship_log(&replayed.ship_log);
+        assert_eq!(replayed_kpis.duration, kpis.duration);
+        assert_eq!((replayed_kpis.distance - kpis.distance).abs() < 1.0, true, "Replayed distance: {} m, should be {} m", replayed_kpis.distance, kpis.distance);
+
+        let _ = std::fs::remove_file(file_path);
+    }
 
-        // Add the second and third cells (speeds) to the record
-        // If knots, transform to knots
-        if true_if_knots_false_if_meters_per_second {
-            record.push((row[1] * KNOTS_TO_METERS_PER_SECOND).to_string());
-            record.push((row[2] * KNOTS_TO_METERS_PER_SECOND).to_string());
-        } // Otherwise, use meters_per_second
-        else {
-            record.push(row[1].to_string());
-            record.push(row[2].to_string());
-        }
+    #[test]
+    fn validate_ship_log_test() {
+        // Simulate one leg of 0.5° along the equator at 5 m/s
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 0.0),
+        ]));
+        boat.velocity_mean = Some(5.0);
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+        sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+
+        // A voyage compared with itself has no errors
+        let metrics = validate_ship_log(&boat.ship_log, &boat.ship_log).unwrap();
+        assert_eq!(metrics.cross_track_rmse < 1e-3, true, "Cross track RMSE: {} m, should be 0", metrics.cross_track_rmse);
+        assert_eq!(metrics.position_rmse.unwrap() < 1e-3, true);
+        assert_eq!(metrics.arrival_time_error, time::Duration::ZERO);
+        assert_eq!(metrics.speed_bias.unwrap().abs() < 1e-9, true);
+
+        // Recorded voyage a day later, 1 km north of the simulated one and one hour slower
+        let offset = 1000.0;
+        let recorded: Vec<ShipLogEntry> = boat.ship_log.iter().enumerate().map(|(i, entry)| {
+            let mut recorded_entry = entry.clone();
+            recorded_entry.coordinates_current = Earth.destination(entry.coordinates_current, 0.0, offset);
+            recorded_entry.timestamp = entry.timestamp + time::Duration::days(1);
+            if i == boat.ship_log.len() - 1 {
+                recorded_entry.timestamp += time::Duration::hours(1);
+            }
+            recorded_entry
+        }).collect();
+        let metrics = validate_ship_log(&boat.ship_log, &recorded).unwrap();
+        assert_eq!((metrics.cross_track_rmse - offset).abs() < 1.0, true, "Cross track RMSE: {} m, should be {} m", metrics.cross_track_rmse, offset);
+        assert_eq!((metrics.cross_track_max - offset).abs() < 1.0, true, "Max cross track distance: {} m, should be {} m", metrics.cross_track_max, offset);
+        assert_eq!(metrics.position_rmse.unwrap() >= offset - 1.0, true);
+        assert_eq!(metrics.arrival_time_error, -time::Duration::hours(1));
+        assert_eq!(metrics.speed_bias.unwrap() > 0.0, true, "Simulated voyage is faster, speed bias: {}", metrics.speed_bias.unwrap());
+
+        // Too short ship logs
+        assert_eq!(validate_ship_log(&boat.ship_log[..1], &recorded).is_err(), true);
+    }
 
-        // Add the fourth cell (heading angle)
-        record.push(row[3].to_string());
+    #[test]
+    fn calibrate_boat_test() {
+        // Record a voyage of a boat sailing at 5 m/s along one leg of 0.5° along the equator
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 0.0),
+        ]));
+        boat.velocity_mean = Some(5.0);
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::minutes(1), 10000, None, None);
+        sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        let recorded = vec![boat.ship_log.clone()];
+
+        // Start from a wrong guess and fit the mean velocity to the arrival time
+        boat.velocity_mean = Some(4.0);
+        let result = calibrate_boat(&boat, &simulation, &recorded, &[BoatParameter::VelocityMean], CalibrationObjective::ArrivalTime, 100).unwrap();
+        let velocity = result.parameters[0].1;
+        assert_eq!((velocity - 5.0).abs() < 0.1, true, "Calibrated velocity: {} m/s, should be 5 m/s", velocity);
+        assert_eq!(result.boat.velocity_mean, Some(velocity));
+        assert_eq!(result.error < result.initial_error, true, "Error {} is not smaller than the initial error {}", result.error, result.initial_error);
+        assert_eq!(result.evaluations <= 101, true);
+        // The boat itself is not changed
+        assert_eq!(boat.velocity_mean, Some(4.0));
+
+        // Parameters without an initial value
+        assert_eq!(calibrate_boat(&boat, &simulation, &recorded, &[BoatParameter::SailArea], CalibrationObjective::ArrivalTime, 100).is_err(), true);
+    }
 
-        // Add wind data to cells 5 and 6
-        record.push(row[4].to_string());
-        record.push(row[5].to_string());
+    #[test]
+    fn sweep_parameter_test() {
+        assert_eq!(ParameterSweep::linspace(BoatParameter::SailArea, 100.0, 500.0, 5).values, vec![100.0, 200.0, 300.0, 400.0, 500.0]);
+
+        // Travel time along one leg of 0.5° along the equator for mean velocities from 2 to 6 m/s
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 0.0),
+        ]));
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time, start_time + time::Duration::days(1)], time::Duration::minutes(10), 1000, None, None);
+        let sweep = ParameterSweep::linspace(BoatParameter::VelocityMean, 2.0, 6.0, 3);
+        let curve = sweep_parameter(&boat, &sweep, &simulation).unwrap();
+        assert_eq!(curve.len(), 3);
+        assert_eq!(boat.velocity_mean, None);
+
+        // Faster boats arrive sooner
+        let dist = Earth.distance(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0));
+        for point in &curve {
+            assert_eq!(point.n_completed, 2);
+            assert_eq!(point.results.len(), 2);
+            let travel_time = point.travel_time.as_ref().unwrap().mean;
+            assert_eq!((travel_time - dist/point.value).abs() < 600.0, true, "Travel time at {} m/s: {} s, should be about {} s", point.value, travel_time, dist/point.value);
+        }
+        assert_eq!(curve[0].travel_time.as_ref().unwrap().mean > curve[2].travel_time.as_ref().unwrap().mean, true);
+    }
 
-        // Add ocean current data to cells 7 and 8
-        record.push(row[6].to_string());
-        record.push(row[7].to_string());
+    #[test]
+    fn marine_sim_error_test() {
+        // Missing boat fields
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 0.0),
+        ]));
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::minutes(10), 1000, None, None);
+        match sim_waypoint_mission(&mut boat, start_time, &simulation) {
+            Err(MarineSimError::MissingBoatField(field)) => assert_eq!(field, "mean velocity"),
+            other => panic!("Expected a missing boat field error, got {:?}", other.map(|r| r.status)),
+        }
 
-        // Write the record
-        writer_source_data.write_record(&record)?;
+        // Errors from simulations keep their cause
+        let e = MarineSimError::simulation("Error during simulation 0".to_string(), MarineSimError::MissingBoatField("sail".to_string()));
+        assert_eq!(matches!(e.root_cause(), MarineSimError::MissingBoatField(_)), true);
+        assert_eq!(e.to_string(), "Error during simulation 0: Boat is missing sail");
+
+        // Round trip through io::Error
+        let io_error: io::Error = e.into();
+        assert_eq!(io_error.kind(), io::ErrorKind::InvalidInput);
+        let e: MarineSimError = io_error.into();
+        assert_eq!(matches!(e, MarineSimError::Simulation { .. }), true);
+        let e: MarineSimError = io::Error::new(io::ErrorKind::NotFound, "no file").into();
+        assert_eq!(matches!(e, MarineSimError::Io(_)), true);
+
+        // Too much cargo
+        boat.cargo_max_capacity = Some(uom::si::f64::Mass::new::<uom::si::mass::ton>(10.0));
+        assert_eq!(matches!(boat.load_cargo(uom::si::f64::Mass::new::<uom::si::mass::ton>(20.0)), Err(MarineSimError::InvalidInput(_))), true);
+        assert_eq!(boat.cargo_current.get::<uom::si::mass::ton>(), 0.0);
+        boat.load_cargo(uom::si::f64::Mass::new::<uom::si::mass::ton>(5.0)).unwrap();
+        assert_eq!(boat.cargo_current.get::<uom::si::mass::ton>(), 5.0);
+
+        // Invalid values in a ship log file
+        let file_path = std::env::temp_dir().join("marine_sim_error_test_ship_log.csv");
+        std::fs::write(&file_path, "timestamp;coordinates_initial;coordinates_current;coordinates_final;cargo_on_board[ton];velocity[m/s];course[°];heading;true_bearing[°];draught[m];navigation_status;depth[m];wind_speed[m/s];wind_angle[°]\n2026-01-01 00:00;0,0;0,0;0,1;;fast;90;;;;;;;\n").unwrap();
+        match csv_to_ship_log(file_path.to_str().unwrap()) {
+            Err(MarineSimError::CsvField { file, line, field, .. }) => {
+                assert_eq!(file, file_path.to_str().unwrap());
+                assert_eq!(line, 2);
+                assert_eq!(field, "velocity");
+            },
+            other => panic!("Expected a CSV parse error, got {:?}", other.map(|log| log.len())),
+        }
+        std::fs::remove_file(&file_path).unwrap();
     }
 
-    // Flush and close the writer
-    writer_source_data.flush()?;
+    #[test]
+    fn panic_free_csv_parsing_test() {
+        // Strings
+        assert_eq!(string_to_utc_date_time("2026-02-28 13:45".to_string()).unwrap(), UtcDateTime::new(time::Date::from_calendar_date(2026, time::Month::February, 28).unwrap(), time::Time::from_hms(13, 45, 0).unwrap()));
+        assert_eq!(string_to_utc_date_time("2026-02-30 13:45".to_string()).is_err(), true);
+        assert_eq!(string_to_utc_date_time("2026-02-28".to_string()).is_err(), true);
+        assert_eq!(string_to_utc_date_time("2026-02-28 1ö:45".to_string()).is_err(), true);
+        // ISO 8601 and RFC 3339 with seconds, fractional seconds and timezones
+        let noon = UtcDateTime::new(time::Date::from_calendar_date(2026, time::Month::February, 28).unwrap(), time::Time::from_hms(12, 0, 0).unwrap());
+        assert_eq!(string_to_utc_date_time("2026-02-28 12:00:00".to_string()).unwrap(), noon);
+        assert_eq!(string_to_utc_date_time("2026-02-28T12:00:00Z".to_string()).unwrap(), noon);
+        assert_eq!(string_to_utc_date_time("2026-02-28 12:00:00 UTC".to_string()).unwrap(), noon);
+        assert_eq!(string_to_utc_date_time("2026-02-28T13:00:00+01:00".to_string()).unwrap(), noon);
+        assert_eq!(string_to_utc_date_time("2026-02-28T09:30-0230".to_string()).unwrap(), noon);
+        assert_eq!(string_to_utc_date_time("2026-03-01T02:00:00+14".to_string()).unwrap(), noon);
+        assert_eq!(string_to_utc_date_time("2026-02-28T12:00:00.250Z".to_string()).unwrap(), noon + time::Duration::milliseconds(250));
+        assert_eq!(string_to_utc_date_time("2026-02-28T12:00:00+1:00".to_string()).is_err(), true);
+        assert_eq!(string_to_utc_date_time("2026-02-28 12:00:00 CET".to_string()).is_err(), true);
+        assert_eq!(string_to_tons("500.5".to_string()).unwrap(), Some(500.5));
+        assert_eq!(string_to_tons(" ".to_string()).unwrap(), None);
+        assert_eq!(string_to_tons("lots".to_string()).is_err(), true);
+        assert_eq!(string_to_point("64.1,-21.9".to_string()).unwrap(), geo::Point::new(-21.9, 64.1));
+        assert_eq!(string_to_point("64.1".to_string()).is_err(), true);
+        assert_eq!(string_to_point("inf,0".to_string()).is_err(), true);
+        // Poles and the ±180° meridian
+        assert_eq!(string_to_point("90,0".to_string()).unwrap(), geo::Point::new(0.0, 90.0));
+        assert_eq!(string_to_point("-90,45".to_string()).unwrap(), geo::Point::new(45.0, -90.0));
+        assert_eq!(string_to_point("90.5,0".to_string()).is_err(), true);
+        assert_eq!(string_to_point("-100,0".to_string()).is_err(), true);
+        assert_eq!(string_to_point("0,180".to_string()).unwrap(), geo::Point::new(180.0, 0.0));
+        assert_eq!(string_to_point("0,-180".to_string()).unwrap(), geo::Point::new(-180.0, 0.0));
+        assert_eq!(string_to_point("0,190".to_string()).unwrap(), geo::Point::new(-170.0, 0.0));
+        assert_eq!(string_to_point("0,360".to_string()).unwrap(), geo::Point::new(0.0, 0.0));
+        assert_eq!(string_to_point("0,361".to_string()).is_err(), true);
+        assert_eq!(string_to_point("0,-181".to_string()).is_err(), true);
+
+        // Route plan with an invalid tacking width on the second leg, the header is line 1
+        let file_path = std::env::temp_dir().join("panic_free_csv_parsing_test_route_plan.csv");
+        let header = "leg;start_latitude;start_longitude;end_latitude;end_longitude;tacking_width[m];min_proximity[m]\n";
+        std::fs::write(&file_path, format!("{}1;0;0;0;1;100;500\n2;0;1;0;2;wide;500\n", header)).unwrap();
+        match load_route_plan(file_path.to_str().unwrap()) {
+            Err(MarineSimError::CsvField { line, field, message, .. }) => {
+                assert_eq!(line, 3);
+                assert_eq!(field, "tacking_width");
+                assert_eq!(message.contains("wide"), true, "Message should show the value: {}", message);
+            },
+            other => panic!("Expected a CSV field error, got {:?}", other.map(|route_plan| route_plan.len())),
+        }
 
-    // Finish progress bar
-    simulation.progress_bar.as_ref().unwrap().finish();
+        // Missing value, and a port call with the columns left out on the first leg
+        std::fs::write(&file_path, format!("{}1;0;0;0;1;100;500\n2;0;1;0;2;100\n", header)).unwrap();
+        assert_eq!(matches!(load_route_plan(file_path.to_str().unwrap()), Err(MarineSimError::CsvField { line: 3, .. })), true);
+        std::fs::write(&file_path, format!("{}1;0;0;0;1;100;500\n2;0;1;0;2;100;500;10;;24\n", header)).unwrap();
+        let route_plan = load_route_plan(file_path.to_str().unwrap()).unwrap();
+        assert_eq!(route_plan.len(), 2);
+        assert_eq!(route_plan[0].port_call, None);
+        assert_eq!(route_plan[1].port_call, Some(PortCall::new(10.0, 0.0, time::Duration::hours(24))));
+        std::fs::remove_file(&file_path).unwrap();
+    }
 
-    // Return data vector
-    return Ok(polar_plot_data_vector);
-}
+    #[test]
+    fn boat_builder_test() {
+        let route_plan = RoutePlan::from(vec![SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(1.0, 0.0), 1000.0, 100.0)]);
+
+        // Everything needed for constant velocity
+        let boat = Boat::builder().name("Builder boat").velocity_mean(5.0).route_plan(route_plan.clone()).build_for(SimMethod::ConstVelocity);
+        assert_eq!(boat.is_ok(), true, "Boat with mean velocity and route plan should build for constant velocity");
+        let boat = boat.unwrap();
+        assert_eq!(boat.name, Some("Builder boat".to_string()));
+        assert_eq!(boat.velocity_mean, Some(5.0));
+
+        // Every missing field is listed
+        match Boat::builder().velocity_mean(5.0).build_for(SimMethod::MeanAndSTDVelocity) {
+            Err(MarineSimError::MissingBoatFields(missing)) => assert_eq!(missing, vec!["route plan".to_string(), "standard deviation velocity".to_string()]),
+            _ => panic!("Boat without route plan and velocity std should not build"),
+        }
+        match Boat::builder().route_plan(route_plan).mass(uom::si::f64::Mass::new::<uom::si::mass::ton>(10.0)).build_for(SimMethod::FastWeatherDataFromCopernicus) {
+            Err(MarineSimError::MissingBoatFields(missing)) => assert_eq!(missing.len(), 3, "Should miss sail, minimum angle of attack and wind velocity multiplier, missed {:?}", missing),
+            _ => panic!("Boat without sail should not build for copernicus"),
+        }
+    }
 
-/// Function that copies a csv file of ship logs taken from (aishub_data_collector)[<https://crates.io/crates/aishub_data_collector>]
-/// and saves a copy of the ship log csv file formatted for marine_vessel_simulator
-/// Note both the input and output filepaths must end with ".csv"
-/// Note the initial coordinates default to (0.0, 0.0) degrees.
-/// Note that this function assumes the aishub data is stored in the AIS encoding not the human readable format
-/// More info on aishub api: <https://www.aishub.net/api>
-/// 
-/// If this function is no longer working since there is a new version of aishub_data_collector or similar, please submit an issue on the (marine_vessel_simulator issue tracker)[<https://github.com/G0rocks/marine_vessel_simulator/issues>]
-/// Last updated 2026-02-01, it works with aishub_data_collector version 1.1.0
-/// aishub_data_collector currently saves data into a csv file with the heading:
-/// 
-/// A,B,C,CALLSIGN,COG,D,DEST,DRAUGHT,DEVICE,ETA,HEADING,IMO,LATITUDE,LONGITUDE,MMSI,NAME,NAVSTAT,PAC,ROT,SOG,TSTAMP,TYPE
-pub fn aishub_shiplog_csv_to_marine_vessel_simulator_shiplog_csv(filepath_input: &str, filepath_output: &str) -> Result<Vec<ShipLogEntry>, io::Error> {
-    // Check if filepath_input ends with ".csv", if not, return an invalid input error
-    if !check_file_extension(filepath_input, ".csv") {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Input file path must end with '.csv'"));
+    #[test]
+    fn simulation_builder_test() {
+        let start_time = UtcDateTime::new(time::Date::from_calendar_date(2025, time::Month::May, 1).unwrap(), time::Time::MIDNIGHT);
+
+        // Defaults
+        let simulation = Simulation::builder(SimMethod::ConstVelocity).start_time(start_time).build();
+        assert_eq!(simulation.is_ok(), true, "Simulation with a start time should build");
+        let simulation = simulation.unwrap();
+        assert_eq!(simulation.time_step, time::Duration::minutes(10));
+        assert_eq!(simulation.start_times, vec![start_time]);
+
+        // Invalid simulations
+        assert_eq!(matches!(Simulation::builder(SimMethod::ConstVelocity).build(), Err(MarineSimError::InvalidInput(_))), true, "Simulation without start times should not build");
+        assert_eq!(Simulation::builder(SimMethod::ConstVelocity).start_time(start_time).time_step(time::Duration::ZERO).build().is_err(), true, "Zero time step should not build");
+        assert_eq!(Simulation::builder(SimMethod::ConstVelocity).start_time(start_time).weather_data_file("weather.csv").build().is_err(), true, "Constant velocity does not use weather");
+        assert_eq!(Simulation::builder(SimMethod::WeatherDataFromCopernicus).start_time(start_time).weather_data_file("weather.csv").build().is_err(), true, "Copernicus method without copernicus info should not build");
+
+        // Simulation::new() can be validated as well
+        let simulation = Simulation::new(SimMethod::MeanAndSTDVelocity, vec![start_time], time::Duration::minutes(-10), 1000, None, None);
+        assert_eq!(simulation.validate().is_err(), true, "Negative time step should not validate");
     }
-    // Check if filepath_output ends with ".csv", if not, return an invalid input error
-    if !check_file_extension(filepath_output, ".csv") {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Output file path must end with '.csv'"));
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_test() {
+        // Boat with a sail and route plan through JSON and back
+        let mut boat = Boat::builder().name("Serde boat").mass(uom::si::f64::Mass::new::<uom::si::mass::ton>(12.5)).velocity_mean(4.0).build();
+        boat.sail = Some(Sail::new(uom::si::f64::Area::new::<uom::si::area::square_meter>(150.0), 10.0, 1.2, 0.1));
+        boat.route_plan = Some(RoutePlan::from(vec![SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(1.0, 0.0), 1000.0, 100.0)]));
+        let json = serde_json::to_string(&boat).unwrap();
+        assert_eq!(json.contains("{\"value\":12.5,\"unit\":\"t\"}"), true, "Mass should be tagged with its unit, got {}", json);
+        let boat_copy: Boat = serde_json::from_str(&json).unwrap();
+        assert_eq!(boat_copy.name, boat.name);
+        assert_eq!(boat_copy.mass, boat.mass);
+        assert_eq!(boat_copy.sail.unwrap().area.get::<uom::si::area::square_meter>(), 150.0);
+        assert_eq!(boat_copy.route_plan.unwrap().len(), 1);
+
+        // Other units and left out fields when reading
+        let boat: Boat = serde_json::from_str("{\"name\": \"Config boat\", \"mass\": {\"value\": 12500, \"unit\": \"kg\"}}").unwrap();
+        assert_eq!((boat.mass.unwrap().get::<uom::si::mass::ton>() - 12.5).abs() < 1e-9, true);
+        assert_eq!(boat.velocity_mean, None);
+        assert_eq!(serde_json::from_str::<Boat>("{\"mass\": {\"value\": 1, \"unit\": \"stone\"}}").is_err(), true, "Unknown units should not be read");
+
+        // Simulation settings
+        let start_time = UtcDateTime::new(time::Date::from_calendar_date(2025, time::Month::May, 1).unwrap(), time::Time::MIDNIGHT);
+        let simulation = Simulation::builder(SimMethod::ConstVelocity).start_time(start_time).build().unwrap();
+        let simulation_copy: Simulation = serde_json::from_str(&serde_json::to_string(&simulation).unwrap()).unwrap();
+        assert_eq!(simulation_copy.start_times, vec![start_time]);
+        assert_eq!(simulation_copy.time_step, simulation.time_step);
     }
-    // Check if file is delimited with ';' and if not, return an error
-    // To do this, get the second column in the first row delimited by ';' and if it does not exist then assume the file is not delimited with ';'
-    // Read the CSV file
-    let mut csv_reader = csv::ReaderBuilder::new()
-        .delimiter(b';')
-        .has_headers(true)
-        .from_path(filepath_input)
-        .expect(format!("Failed to open file: {}\n", filepath_input).as_str());
-    let first_entry = csv_reader.records().next();
-    // if first line is empty, return error
-    if first_entry.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Input file {:?} does not have any data", filepath_input)));
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn config_file_test() {
+        let dir = std::env::temp_dir();
+
+        // Boat written by hand in TOML
+        let boat_file = dir.join("marine_vessel_simulator_config_test_boat.toml");
+        std::fs::write(&boat_file, "name = \"Config boat\"\nvelocity_mean = 5.0\nmass = { value = 20.0, unit = \"t\" }\n").unwrap();
+        let boat = Boat::from_config_file(boat_file.to_str().unwrap()).unwrap();
+        assert_eq!(boat.name, Some("Config boat".to_string()));
+        assert_eq!(boat.velocity_mean, Some(5.0));
+        assert_eq!(boat.mass, Some(uom::si::f64::Mass::new::<uom::si::mass::ton>(20.0)));
+
+        // Simulation saved and read again as JSON
+        let start_time = UtcDateTime::new(time::Date::from_calendar_date(2025, time::Month::May, 1).unwrap(), time::Time::MIDNIGHT);
+        let simulation_file = dir.join("marine_vessel_simulator_config_test_simulation.json");
+        let simulation = Simulation::builder(SimMethod::MeanAndSTDVelocity).start_time(start_time).max_iterations(500).build().unwrap();
+        simulation.save_config_file(simulation_file.to_str().unwrap()).unwrap();
+        let simulation_copy = Simulation::from_config_file(simulation_file.to_str().unwrap()).unwrap();
+        assert_eq!(simulation_copy.max_iterations, 500);
+        assert_eq!(simulation_copy.start_times, vec![start_time]);
+        assert_eq!(simulation_copy.validate().is_ok(), true, "Read simulation should be valid");
+
+        // Boat saved and read again as YAML
+        let yaml_file = dir.join("marine_vessel_simulator_config_test_boat.yaml");
+        boat.save_config_file(yaml_file.to_str().unwrap()).unwrap();
+        let boat_copy = Boat::from_config_file(yaml_file.to_str().unwrap()).unwrap();
+        assert_eq!(boat_copy.name, Some("Config boat".to_string()));
+        assert_eq!(boat_copy.mass, boat.mass);
+
+        // A simulation with copernicus can be read before copernicus is set, but not invalid settings
+        let copernicus_simulation = Simulation::new(SimMethod::WeatherDataFromCopernicus, vec![start_time], time::Duration::hours(1), 500, Some("weather.csv".to_string()), None);
+        copernicus_simulation.save_config_file(simulation_file.to_str().unwrap()).unwrap();
+        assert_eq!(Simulation::from_config_file(simulation_file.to_str().unwrap()).is_ok(), true);
+        std::fs::write(&simulation_file, serde_json::to_string(&simulation).unwrap().replace("\"max_iterations\":500", "\"max_iterations\":0")).unwrap();
+        assert_eq!(Simulation::from_config_file(simulation_file.to_str().unwrap()).is_err(), true, "Invalid simulation should not be read");
+
+        // Errors
+        std::fs::write(&boat_file, "velocity_mean = \"fast\"\n").unwrap();
+        assert_eq!(matches!(Boat::from_config_file(boat_file.to_str().unwrap()), Err(MarineSimError::ConfigParse { .. })), true, "Invalid config should not be read");
+        assert_eq!(Boat::from_config_file("boat.xml").is_err(), true, "Unsupported file extension should not be read");
+
+        std::fs::remove_file(boat_file).unwrap();
+        std::fs::remove_file(yaml_file).unwrap();
+        std::fs::remove_file(simulation_file).unwrap();
     }
-    let first_entry = first_entry.expect(format!("First entry in .csv file {:?} returned None", filepath_input).as_str()).unwrap();
-    let column_2 = first_entry.get(1);
-    if column_2.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Input file must be delimited with ';'"));
+
+    #[test]
+    fn shipping_log_stats_test() {
+        // One trip along the equator with a working point half way
+        let file_path = std::env::temp_dir().join("shipping_log_stats_test.csv");
+        let log = "timestamp;coordinates_initial;coordinates_current;coordinates_final;cargo_on_board\n\
+            2025-05-01 00:00;0,0;0,0;0,1;100\n\
+            2025-05-01 12:00;0,0;0,0.5;0,1;100\n\
+            2025-05-02 00:00;0,0;0,1;0,1;100\n";
+        std::fs::write(&file_path, log).unwrap();
+        let stats = evaluate_cargo_shipping_logs(file_path.to_str().unwrap(), 100.0).unwrap();
+        assert_eq!(stats.num_trips, 1);
+        assert_eq!(stats.speeds.len(), 2);
+        assert_eq!(stats.travel_times, vec![time::Duration::days(1)]);
+        assert_eq!(stats.cargos, vec![100.0]);
+        assert_eq!((stats.distances[0] - Earth.distance(geo::Point::new(0.0, 0.0), geo::Point::new(1.0, 0.0))).abs() < 1.0, true, "Distance sailed should be one degree of longitude, got {}", stats.distances[0]);
+        assert_eq!(stats.travel_time_min, Some(time::Duration::days(1)));
+        assert_eq!(stats.to_string().starts_with("Trips: 1\n"), true, "Unexpected display: {}", stats);
+        std::fs::remove_file(file_path).unwrap();
     }
-    // Read the CSV file
-    let mut csv_reader = csv::ReaderBuilder::new()
-        .delimiter(b';')
-        .has_headers(true)
-        .from_path(filepath_input)
-        .expect(format!("Failed to open file: {}\n", filepath_input).as_str());
-    // Make sure there are at least 2 lines, if not then return error
-    let num_lines = csv_reader.records().count();
-    if num_lines < 2 {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Input file {:?} needs to have at least 2 lines of data", filepath_input)));
+
+    #[test]
+    fn extract_trips_test() {
+        // A finished trip, a trip that never arrives and an entry before the first trip
+        let file_path = std::env::temp_dir().join("extract_trips_test.csv");
+        let log = "timestamp;coordinates_initial;coordinates_current;coordinates_final;cargo_on_board\n\
+            2025-04-30 00:00;0,0;0,-0.5;0,1;\n\
+            2025-05-01 00:00;0,0;0,0;0,1;100\n\
+            2025-05-02 00:00;0,0;0,1;0,1;100\n\
+            2025-05-10 00:00;0,1;0,1;0,0;50\n\
+            2025-05-11 00:00;0,1;0,0.5;0,0;50\n";
+        std::fs::write(&file_path, log).unwrap();
+        let trips = extract_trips(file_path.to_str().unwrap(), 100.0).unwrap();
+        assert_eq!(trips.len(), 2);
+        assert_eq!(trips[0].completed, true);
+        assert_eq!(trips[0].duration, time::Duration::days(1));
+        assert_eq!(trips[0].cargo, Some(100.0));
+        assert_eq!((trips[0].speed_mean.unwrap() - trips[0].distance/86400.0).abs() < 1e-9, true);
+        assert_eq!(trips[1].completed, false);
+        assert_eq!(trips[1].cargo, Some(50.0));
+
+        // Statistics only use the finished trip for travel times
+        let stats = ShippingLogStats::from_trips(&trips);
+        assert_eq!(stats.num_trips, 2);
+        assert_eq!(stats.travel_times, vec![time::Duration::days(1)]);
+        assert_eq!(stats.speeds.len(), 2);
+        std::fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn trip_segmentation_test() {
+        // Noisy log that never logs the vessel exactly at the initial coordinates, with a 3 day stop in port and a cargo change
+        let file_path = std::env::temp_dir().join("trip_segmentation_test.csv");
+        let log = "timestamp;coordinates_initial;coordinates_current;coordinates_final;cargo_on_board\n\
+            2025-05-01 00:00;0,0;0,0.001;0,1;100\n\
+            2025-05-01 12:00;0,0;0,0.5;0,1;100\n\
+            2025-05-02 00:00;0,0;0,0.999;0,1;100\n\
+            2025-05-05 00:00;0,1;0,1.001;0,0;50\n\
+            2025-05-05 12:00;0,1;0,0.5;0,0;50\n\
+            2025-05-06 00:00;0,1;0,0.001;0,0;50\n";
+        std::fs::write(&file_path, log).unwrap();
+        let file = file_path.to_str().unwrap();
+
+        // The coordinate rule finds no trips
+        assert_eq!(extract_trips(file, 100.0).unwrap().len(), 0);
+
+        // The other rules find both trips
+        let by_gap = extract_trips_with(file, &TripSegmentation::TimeGap { max_gap: time::Duration::hours(24) }).unwrap();
+        assert_eq!(by_gap.len(), 2);
+        assert_eq!(by_gap[0].duration, time::Duration::days(1));
+        assert_eq!(by_gap[0].completed, true);
+        let by_cargo = extract_trips_with(file, &TripSegmentation::CargoChange).unwrap();
+        assert_eq!(by_cargo.len(), 2);
+        assert_eq!(by_cargo[1].cargo, Some(50.0));
+        let ports = vec![PortArea::new("West", geo::Point::new(0.0, 0.0), 1000.0), PortArea::new("East", geo::Point::new(1.0, 0.0), 1000.0)];
+        let by_port = extract_trips_with(file, &TripSegmentation::PortAreas { ports }).unwrap();
+        assert_eq!(by_port.len(), 2);
+        assert_eq!(by_port.iter().all(|trip| trip.completed), true, "Both trips end in a port");
+        assert_eq!(by_port[0].start_time, UtcDateTime::new(time::Date::from_calendar_date(2025, time::Month::May, 1).unwrap(), time::Time::MIDNIGHT));
+        std::fs::remove_file(file_path).unwrap();
     }
 
-    // Read the aishub ship log csv file into a Shiplog struct
-    // Init ship logs vector
-    let mut aishub_logs: Vec<ShipLogEntry> = Vec::new();
+    #[test]
+    fn shipping_statistics_distribution_test() {
+        // Skewed travel times, one trip takes much longer than the others
+        let trips: Vec<Trip> = [1, 1, 1, 1, 10].iter().map(|days| {
+            let start_time = UtcDateTime::new(time::Date::from_calendar_date(2025, time::Month::May, 1).unwrap(), time::Time::MIDNIGHT);
+            Trip {
+                start_time,
+                end_time: start_time + time::Duration::days(*days),
+                coordinates_initial: geo::Point::new(0.0, 0.0),
+                coordinates_final: geo::Point::new(1.0, 0.0),
+                distance: 111000.0,
+                duration: time::Duration::days(*days),
+                speed_mean: Some(111000.0/(*days as f64*86400.0)),
+                speeds: vec![111000.0/(*days as f64*86400.0)],
+                cargo: None,
+                completed: true,
+                weather: None,
+            }
+        }).collect();
+        let stats = ShippingLogStats::from_trips(&trips);
+        let travel_time = stats.travel_time_distribution.clone().unwrap();
+        assert_eq!(travel_time.p50, 86400.0, "Median should be one day");
+        assert_eq!(travel_time.max, 10.0*86400.0);
+        assert_eq!(stats.travel_time_mean.unwrap() > time::Duration::days(2), true, "Mean is pulled up by the long trip");
+
+        // Written to the csv file
+        let file_path = std::env::temp_dir().join("marine_vessel_simulator_shipping_statistics_distribution_test.csv");
+        let file_path = file_path.to_str().unwrap();
+        save_shipping_logs_evaluation_to_csv(file_path, vec!["Skewed"], vec![stats.speed_mean], vec![stats.speed_std], vec![None], vec![None], vec![stats.travel_time_mean], vec![stats.travel_time_std], vec![stats.dist_mean], vec![stats.dist_std], vec![None], vec![None], vec![stats.num_trips], vec![stats.speed_distribution.clone()], vec![stats.travel_time_distribution.clone()], vec![None], CsvWriteMode::Overwrite).unwrap();
+        let rows = read_shipping_statistics_rows(file_path).unwrap();
+        assert_eq!(rows[0].0[19], "1", "travel_time_median[days] should be 1");
+        assert_eq!(rows[0].0[26], "", "No distance distribution");
+
+        // Files without the distribution columns can still be read
+        std::fs::write(file_path, format!("{}\nOld;5;;;;;;;;;;3\n", SHIPPING_STATISTICS_CSV_HEADER[..SHIPPING_STATISTICS_CSV_LEGACY_COLUMNS].join(";"))).unwrap();
+        let rows = read_shipping_statistics_rows(file_path).unwrap();
+        assert_eq!(rows[0].0.len(), SHIPPING_STATISTICS_CSV_HEADER.len());
+        assert_eq!(rows[0].0[11], "3");
+        let _ = std::fs::remove_file(file_path);
+    }
 
-    // Since aishub does not provide information on cargo on board, set cargo on board to None
-    let cargo_on_board = None;
+    #[test]
+    fn generic_statistics_test() {
+        // f64
+        let (mean, std) = get_mean_and_std(&[1.0, 2.0, 3.0, f64::INFINITY]).unwrap();
+        assert_eq!((mean, std), (2.0, 1.0));
+        assert_eq!(get_vec_f64_mean_and_std(&vec![1.0, 2.0, 3.0, f64::INFINITY], true).unwrap(), (2.0, 1.0));
+        assert_eq!(get_vec_f64_mean_and_std(&vec![1.0, 2.0, 3.0, f64::INFINITY], false).unwrap().0, f64::INFINITY);
+        assert_eq!(get_vec_f64_mean_and_std(&vec![4.0], true).unwrap(), (4.0, 0.0), "One value has zero standard deviation");
+        assert_eq!(get_vec_f64_mean_and_std(&vec![f64::NAN, f64::INFINITY], true).is_err(), true, "No finite values");
+        assert_eq!(get_vec_f64_mean_and_std(&Vec::new(), true).is_err(), true);
+
+        // uom quantities keep their unit
+        let masses = [uom::si::f64::Mass::new::<uom::si::mass::ton>(1.0), uom::si::f64::Mass::new::<uom::si::mass::ton>(3.0)];
+        let (mass_mean, _) = get_mean_and_std(&masses).unwrap();
+        assert_eq!((mass_mean.get::<uom::si::mass::ton>() - 2.0).abs() < 1e-9, true);
+        let energies = [uom::si::f64::Energy::new::<uom::si::energy::kilowatt_hour>(10.0)];
+        assert_eq!(get_mean_and_std(&energies).unwrap().1.get::<uom::si::energy::joule>(), 0.0, "One value has zero standard deviation");
+
+        // Durations, with the same error message as the other types when empty
+        let (duration_mean, _) = get_duration_mean_and_std(&vec![time::Duration::hours(1), time::Duration::hours(3)]).unwrap();
+        assert_eq!(duration_mean, time::Duration::hours(2));
+        match get_duration_mean_and_std(&Vec::new()) {
+            Err(MarineSimError::InvalidInput(message)) => assert_eq!(message.contains("Speed"), false),
+            _ => panic!("Empty vector should be an error"),
+        }
+        assert_eq!(get_distribution_stats(&[time::Duration::days(1)]).unwrap().p50, 86400.0);
+    }
 
-    // Read the CSV file again to start from the beginning
-    let mut csv_reader = csv::ReaderBuilder::new()
-        .delimiter(b';')
-        .has_headers(true)
-        .from_path(filepath_input)
-        .expect(format!("Failed to open file: {}\n", filepath_input).as_str());
+    #[test]
+    fn shipping_log_cleaning_test() {
+        // One trip with a GPS glitch 500 km away and a duplicated timestamp
+        let file_path = std::env::temp_dir().join("shipping_log_cleaning_test.csv");
+        let log = "timestamp;coordinates_initial;coordinates_current;coordinates_final;cargo_on_board\n\
+            2025-05-01 00:00;0,0;0,0;0,1;100\n\
+            2025-05-01 06:00;0,0;0,0.25;0,1;100\n\
+            2025-05-01 06:10;0,0;4.5,0.25;0,1;100\n\
+            2025-05-01 06:00;0,0;0,0.26;0,1;100\n\
+            2025-05-01 12:00;0,0;0,0.5;0,1;100\n\
+            2025-05-02 00:00;0,0;0,1;0,1;100\n";
+        std::fs::write(&file_path, log).unwrap();
+        let file = file_path.to_str().unwrap();
+        let segmentation = TripSegmentation::Coordinates { destination_minimum_proximity: 100.0 };
+
+        // Dropping the glitch and the duplicate
+        let stats = evaluate_cargo_shipping_logs_cleaned(file, &segmentation, &LogCleaning::default()).unwrap();
+        let report = stats.cleaning_report.clone().unwrap();
+        assert_eq!(report.n_entries, 6);
+        assert_eq!(report.n_excluded, 2);
+        assert_eq!(report.n_too_fast, 1);
+        assert_eq!(report.n_zero_duration, 1);
+        assert_eq!(report.excluded_lines, vec![4, 5]);
+        let one_degree = Earth.distance(geo::Point::new(0.0, 0.0), geo::Point::new(1.0, 0.0));
+        assert_eq!((stats.distances[0] - one_degree).abs() < 1.0, true, "Cleaned distance should be one degree, got {}", stats.distances[0]);
+
+        // Flagging keeps them in the trip
+        let cleaning = LogCleaning { action: CleaningAction::Flag, ..LogCleaning::default() };
+        let (trips, report) = extract_trips_cleaned(file, &segmentation, &cleaning).unwrap();
+        assert_eq!(report.n_excluded, 2);
+        assert_eq!(trips[0].distance > 2.0*one_degree, true, "Flagged glitch should still be in the distance");
+        std::fs::remove_file(file_path).unwrap();
+    }
 
-    // Get the initial and final coordinates
-    // Init coordinates_initial
-    let first_record = match csv_reader.records().next().expect("Could not get first entry from file") {
-        Ok(r) => r,
-        Err(e) => Err(io::Error::new(io::ErrorKind::Other, format!("Error getting first record from aishub_data_collector file: {}", e)))?,
-    };
-    let latitude = match first_record.get(12).expect("Could not get latitude from first record").parse::<f64>() {
-        Ok(v) => v/600000.0,
-        Err(e) => panic!("Error getting initial coordinates from aishub_data_collector csv file: {}", e),
-    };
-    let longitude = match first_record.get(13).expect("Could not get longitude from first record").parse::<f64>() {
-        Ok(v) => v/600000.0,
-        Err(e) => panic!("Error getting initial coordinates from aishub_data_collector csv file: {}", e),
-    };
+    #[test]
+    fn weather_summary_test() {
+        // Three hours of sailing north, upwind with a wind from the north, then beating to windward and finally turning downwind
+        let start_time = time::UtcDateTime::from_unix_timestamp(0).unwrap();
+        let p1 = geo::Point::new(0.0, 0.0);
+        let p2 = geo::Point::new(0.0, 0.1);
+        let leg = SailingLeg::new(p1, p2, 0.0, 0.0);
+        let mut ship_log: Vec<ShipLogEntry> = (0..=3).map(|i| ShipLogEntry::new(start_time + time::Duration::hours(i), p1, leg.point_at(i as f64/3.0), p2, None, None, Some(0.0), Some(0.0), None, None, None, None)).collect();
+        for (entry, wind_speed) in ship_log.iter_mut().zip([8.0, 10.0, 12.0, 6.0]) {
+            entry.wind = Some(PhysVec::new(wind_speed, 0.0));
+        }
+        ship_log[2].heading = Some(45.0);
+        ship_log[2].wave_height = Some(2.5);
+        ship_log[3].heading = Some(180.0);
+        ship_log[3].course = Some(180.0);
+        ship_log[3].wave_height = Some(1.5);
+
+        let weather = WeatherSummary::from_ship_log(&ship_log).unwrap();
+        assert_eq!(weather.wind_speed.as_ref().unwrap().max, 12.0);
+        assert_eq!(weather.max_wave_height, Some(2.5));
+        assert_eq!((weather.upwind_percent.unwrap() - 200.0/3.0).abs() < 1e-9, true, "Upwind: {:?}", weather.upwind_percent);
+        assert_eq!((weather.downwind_percent.unwrap() - 100.0/3.0).abs() < 1e-9, true, "Downwind: {:?}", weather.downwind_percent);
+        assert_eq!((weather.tacking_percent.unwrap() - 100.0/3.0).abs() < 1e-9, true, "Tacking: {:?}", weather.tacking_percent);
+        assert_eq!(VoyageKpis::from_ship_log(&ship_log).weather, Some(weather.clone()));
+
+        // No weather data, no summary
+        let calm_log: Vec<ShipLogEntry> = ship_log.iter().map(|entry| {
+            let mut entry = entry.clone();
+            entry.wind = None;
+            entry.wave_height = None;
+            entry
+        }).collect();
+        assert_eq!(WeatherSummary::from_ship_log(&calm_log), None);
+
+        // The weather is kept in the ship log csv file and ends up in the shipping statistics
+        let file_path = std::env::temp_dir().join("marine_vessel_simulator_weather_summary_test.csv");
+        let file_path = file_path.to_str().unwrap();
+        ship_logs_to_csv(file_path, &ship_log).unwrap();
+        assert_eq!(csv_to_ship_log(file_path).unwrap()[2].wave_height, Some(2.5));
+        let statistics = ShippingStatistics::from_cargo_shipping_logs(file_path, 1000.0).unwrap();
+        assert_eq!(statistics.wind_speed_max, Some(12.0));
+        assert_eq!(statistics.wave_height_max, Some(2.5));
+        assert_eq!((statistics.tacking_percent_mean.unwrap() - 100.0/3.0).abs() < 1e-9, true, "Tacking: {:?}", statistics.tacking_percent_mean);
+        std::fs::remove_file(file_path).unwrap();
+    }
 
-    let coordinates_initial = geo::Point::new(longitude, latitude);
+    #[test]
+    fn emissions_report_test() {
+        // 100 nmi with 2000 tons of cargo on board, then 100 nmi in ballast
+        let start_time = time::UtcDateTime::from_unix_timestamp(0).unwrap();
+        let p1 = geo::Point::new(0.0, 0.0);
+        let p2 = Earth.destination(p1, 90.0, 185200.0);
+        let p3 = Earth.destination(p1, 90.0, 370400.0);
+        let cargo = uom::si::f64::Mass::new::<uom::si::mass::ton>(2000.0);
+        let ship_log = vec![
+            ShipLogEntry::new(start_time, p1, p1, p3, Some(cargo), None, None, None, None, None, None, None),
+            ShipLogEntry::new(start_time + time::Duration::days(1), p1, p2, p3, None, None, None, None, None, None, None, None),
+            ShipLogEntry::new(start_time + time::Duration::days(2), p1, p3, p3, None, None, None, None, None, None, None, None),
+        ];
+        let activity = VoyageActivity::from_ship_log(&ship_log, 10000.0);
+        assert_eq!((activity.distance - 370400.0).abs() < 1.0, true, "Distance: {}", activity.distance);
+        assert_eq!((activity.transport_work - 2000.0*185200.0).abs() < 2000.0, true, "Transport work: {}", activity.transport_work);
+
+        // 10 tons of marine diesel oil emit 32.06 tons of CO2
+        let ship = CiiShip::new(CiiShipType::GeneralCargoShip, 3000.0, FuelType::MarineDieselOil);
+        let report = EmissionsReport::from_voyages(2025, &ship, &[activity]).unwrap();
+        assert_eq!((report.co2 - 32.06).abs() < 1e-9, true, "CO2: {}", report.co2);
+        assert_eq!((report.eeoi.unwrap() - 32.06e6/(2000.0*100.0)).abs() < 1.0, true, "EEOI: {:?}", report.eeoi);
+        assert_eq!((report.attained_cii.unwrap() - 32.06e6/(3000.0*200.0)).abs() < 0.1, true, "Attained CII: {:?}", report.attained_cii);
+        // Required CII is 588*3000^(-0.3885) reduced by 9%, about 23.2, so an attained CII of 53.4 is rated E
+        assert_eq!((report.required_cii - 0.91*588.0*3000f64.powf(-0.3885)).abs() < 1e-9, true, "Required CII: {}", report.required_cii);
+        assert_eq!(report.cii_rating, Some(CiiRating::E));
+
+        // A tenth of the fuel with wind-assist gives a better rating
+        let wind_assist = EmissionsReport::from_voyages(2025, &ship, &[VoyageActivity { fuel: 1000.0, ..activity }]).unwrap();
+        assert_eq!(wind_assist.cii_rating < report.cii_rating, true, "Rating with wind-assist: {:?}", wind_assist.cii_rating);
+
+        // Invalid ship
+        assert_eq!(EmissionsReport::from_voyages(2025, &CiiShip::new(CiiShipType::Tanker, 0.0, FuelType::HeavyFuelOil), &[activity]).is_err(), true);
+
+        // Written to a csv file
+        let file_path = std::env::temp_dir().join("marine_vessel_simulator_emissions_report_test.csv");
+        let file_path = file_path.to_str().unwrap();
+        emissions_reports_to_csv(file_path, &[("Diesel", &report), ("Wind-assist", &wind_assist)]).unwrap();
+        let contents = std::fs::read_to_string(file_path).unwrap();
+        assert_eq!(contents.lines().count(), 3);
+        assert_eq!(contents.lines().nth(1).unwrap().ends_with(";E"), true, "Row: {}", contents);
+        std::fs::remove_file(file_path).unwrap();
+    }
 
-    // Get final coordinates
-    let last_record =  match csv_reader.records().last().expect("Could not get last entry from csv file") {
-        Ok(r) => r,
-        Err(e) => Err(io::Error::new(io::ErrorKind::Other, format!("Error getting last record from aishub_data_collector csv file: {}", e)))?,
-    };
-    let latitude = match last_record.get(12).unwrap().parse::<f64>() {
-        Ok(v) => v/600000.0,
-        Err(e) => panic!("Error getting final coordinates from aishub_data_collector csv file: {}", e),
-    };
-    let longitude = match last_record.get(13).unwrap().parse::<f64>() {
-        Ok(v) => v/600000.0,
-        Err(e) => panic!("Error getting final coordinates from aishub_data_collector csv file: {}", e),
-    };
-    let coordinates_final: geo::Point = geo::Point::new(longitude, latitude);
+    #[test]
+    fn voyage_costs_test() {
+        // A 10 day voyage with one port call on the way and 5 tons of fuel
+        let start_time = time::UtcDateTime::from_unix_timestamp(0).unwrap();
+        let p1 = geo::Point::new(0.0, 0.0);
+        let p2 = geo::Point::new(1.0, 0.0);
+        let ship_log = vec![
+            ShipLogEntry::new(start_time, p1, p1, p2, None, None, None, None, None, None, None, None),
+            ShipLogEntry::new(start_time + time::Duration::days(10), p1, p2, p2, None, None, None, None, None, None, None, None),
+        ];
+        let mut kpis = VoyageKpis::from_ship_log(&ship_log);
+        kpis.fuel = Some(5000.0);
+        kpis.events.push(SimulationEvent { timestamp: start_time + time::Duration::days(5), location: p1, leg: Some(1), kind: SimulationEventKind::PortCall { cargo_loaded: 0.0, cargo_unloaded: 0.0, wait: time::Duration::ZERO } });
+        let result = SimulationResult { status: SimulationStatus::Completed, iterations: 2, kpis, progress: VoyageProgress::new(start_time, 1, Earth.distance(p1, p2)) };
+
+        let cost_model = CostModel::new(10000.0, 600.0, 5000.0, 20000.0);
+        let costs = cost_model.voyage_costs(&result, 1000.0);
+        assert_eq!(costs.charter, 100000.0);
+        assert_eq!(costs.fuel, 3000.0);
+        assert_eq!(costs.port_fees, 10000.0, "The port call and the port of arrival");
+        assert_eq!(costs.total, 133000.0);
+        assert_eq!(costs.cost_per_ton, Some(133.0));
+        assert_eq!(costs.cost_per_day, Some(13300.0));
+        assert_eq!(cost_model.voyage_costs(&result, 0.0).cost_per_ton, None);
+
+        // Mean of two voyages, written alongside the statistics
+        let mean = VoyageCosts::mean(&[costs, cost_model.costs(time::Duration::days(20), 0.0, 1, 1000.0)]).unwrap();
+        assert_eq!(mean.duration, time::Duration::days(15));
+        assert_eq!(mean.cost_per_ton, Some((133.0 + 225.0)/2.0));
+        assert_eq!(VoyageCosts::mean(&[]), None);
+        let mut statistics = ShippingStatistics::default();
+        mean.add_to_statistics(&mut statistics);
+        assert_eq!(statistics.metadata.iter().any(|(key, value)| key == "cost_per_ton[$/ton]" && value == "179"), true, "Metadata: {:?}", statistics.metadata);
+    }
 
-    // Start another csv reader stream since we already went through the whole stream.
-    // This way we can start from the first record again
-    let mut csv_reader = csv::ReaderBuilder::new()
-        .delimiter(b';')
-        .has_headers(true)
-        .from_path(filepath_input)
-        .expect(format!("Failed to open file: {}\n", filepath_input).as_str());
+    #[test]
+    fn gpx_test() {
+        // Route planned in a chart plotter, with attributes in either order and a route point name
+        let file_path = std::env::temp_dir().join("marine_vessel_simulator_gpx_route_test.gpx");
+        let file_path = file_path.to_str().unwrap();
+        std::fs::write(file_path, "<?xml version=\"1.0\"?>\n<gpx version=\"1.1\" creator=\"OpenCPN\">\n<rte>\n<name>Crossing</name>\n<rtept lat=\"64.1\" lon=\"-22.0\"><name>Reykjavik</name></rtept>\n<rtept lon='-10.0' lat='62.0'/>\n<rtept lat=\"55.7\" lon=\"12.6\"></rtept>\n</rte>\n</gpx>\n").unwrap();
+        let route_plan = gpx_to_route_plan(file_path).unwrap();
+        assert_eq!(route_plan.len(), 2);
+        assert_eq!(route_plan[0].p1, geo::Point::new(-22.0, 64.1));
+        assert_eq!(route_plan[1].p1, geo::Point::new(-10.0, 62.0));
+        assert_eq!(route_plan[1].p2, geo::Point::new(12.6, 55.7));
+        assert_eq!(route_plan[0].tacking_width, RoutePlan::DEFAULT_TACKING_WIDTH);
+        std::fs::remove_file(file_path).unwrap();
+
+        // Ship log exported as a track and read back as a route
+        let start_time = time::UtcDateTime::from_unix_timestamp(0).unwrap();
+        let p1 = geo::Point::new(0.0, 0.0);
+        let p2 = geo::Point::new(1.0, 0.5);
+        let ship_log = vec![
+            ShipLogEntry::new(start_time, p1, p1, p2, None, None, None, None, None, None, None, None),
+            ShipLogEntry::new(start_time + time::Duration::HOUR, p1, p2, p2, None, None, None, None, None, None, None, None),
+        ];
+        let file_path = std::env::temp_dir().join("marine_vessel_simulator_gpx_track_test.gpx");
+        let file_path = file_path.to_str().unwrap();
+        ship_log_to_gpx(file_path, &ship_log, "Boat & crew").unwrap();
+        let contents = std::fs::read_to_string(file_path).unwrap();
+        assert_eq!(contents.contains("<name>Boat &amp; crew</name>"), true, "GPX: {}", contents);
+        assert_eq!(contents.contains("<time>1970-01-01T01:00:00Z</time>"), true, "GPX: {}", contents);
+        let route_plan = gpx_to_route_plan(file_path).unwrap();
+        assert_eq!(route_plan.len(), 1);
+        assert_eq!(route_plan[0].p2, p2);
+        std::fs::remove_file(file_path).unwrap();
+
+        // Invalid files
+        assert_eq!(ship_log_to_gpx("voyage.csv", &ship_log, "Boat").is_err(), true);
+        let file_path = std::env::temp_dir().join("marine_vessel_simulator_gpx_invalid_test.gpx");
+        let file_path = file_path.to_str().unwrap();
+        std::fs::write(file_path, "<gpx><rte><rtept lat=\"95.0\" lon=\"0.0\"/><rtept lat=\"0.0\" lon=\"0.0\"/></rte></gpx>").unwrap();
+        assert_eq!(matches!(gpx_to_route_plan(file_path), Err(MarineSimError::CsvParse { .. })), true, "Latitude out of range");
+        std::fs::remove_file(file_path).unwrap();
+    }
 
-    // Loop through all lines of file, append each line to the ship log
-    for result in csv_reader.records() {
-        match result {
-            Ok(entry) => {
-                // Get the entry simplified to reduce how verbose everything is
-                // let entry = entry.get(0).unwrap().split(',');
-                // Get all aishub_data_collector csv file fields
-                // For each entry which aishub could signal is not available, check if the value is valid or not
-                let _a = entry.get(0);
-                let _b = entry.get(1);
-                let _c = entry.get(2);
-                let _callsign = entry.get(3);
-                // If cog is 3600 the value is unknown
-                let mut cog: Option<f64> = Some(entry.get(4).unwrap().parse::<f64>().unwrap());
-                if cog.unwrap() == 3600.0 {
-                    cog = None;
-                }
-                else {
-                    cog = Some(cog.unwrap()/10.0);
-                }
-                let _d = entry.get(5);
-                let _dest = entry.get(6);
-                // Draft must be non-zero, if zero, report as None
-                let mut draft = Some(0.0);
-                if draft.unwrap() == 0.0 {
-                    draft = None;
-                }
-                let _device = entry.get(8);
-                let _eta = entry.get(9);
-                // A heading of 511 means not available
-                let mut heading: Option<f64> = Some(entry.get(10).expect("Could not get heading from file").parse::<f64>().unwrap());
-                if heading.unwrap() == 511.0 {
-                    heading = None;
-                }
-                let _imo = entry.get(11);
-                // Aishub stores latitude and longitude data in 1/10000 minute
-                let latitude = entry.get(12).unwrap().parse::<f64>().unwrap()/600000.0;
-                let longitude = entry.get(13).unwrap().parse::<f64>().unwrap()/600000.0;
-                let _mmsi = entry.get(14);
-                let _name = entry.get(15);
-                let navstat: Option<u8> = match entry.get(16) {
-                    Some(n) => Some(n.parse::<u8>().unwrap()),
-                    None => None
-                };
-                let _pac = entry.get(17);
-                let _rot = entry.get(18);
-                // If sog is 1024 the value is unknown
-                let mut sog: Option<f64> = Some(entry.get(19).expect("Could not get sog from file").parse::<f64>().unwrap());
-                if sog.unwrap() == 1024.0 {
-                    sog = None;
-                }
-                else {
-                    // sog is given in knots so in addition to dividing by 10.0 we must also convert to meters per second
-                    sog = Some(sog.unwrap()/10.0/1.944);
-                }
-                let tstamp = entry.get(20).expect("Could not get tstamp from file").parse::<i64>().unwrap();
-                let _vessel_type = entry.get(21);
+    #[test]
+    fn nmea_test() {
+        // Sailing east at 5 m/s off Iceland with a northerly wind
+        let timestamp = time::UtcDateTime::new(time::Date::from_calendar_date(2025, time::Month::May, 1).unwrap(), time::Time::from_hms(12, 34, 56).unwrap());
+        let location = geo::Point::new(-22.25, 64.5);
+        let mut entry = ShipLogEntry::new(timestamp, location, location, location, None, Some(PhysVec::new(5.0, 90.0)), None, Some(85.0), None, None, None, None);
+        entry.wind = Some(PhysVec::new(10.0, 0.0));
+        assert_eq!(get_nmea_sentences(&entry, None), vec![
+            "$GPRMC,123456.00,A,6430.0000,N,02215.0000,W,9.7,90.0,010525,,,A*7A".to_string(),
+            "$IIVHW,85.0,T,,M,9.7,N,18.0,K*71".to_string(),
+            "$IIMWV,275.0,T,19.4,N,A*07".to_string(),
+            "$IIHDT,85.0,T*1F".to_string(),
+        ]);
+        assert_eq!(get_nmea_sentence("IIHDT,90.0,T"), "$IIHDT,90.0,T*1B");
+
+        // Without velocity, heading or wind only RMC with the speed from the previous entry is written
+        let previous = ShipLogEntry::new(timestamp - time::Duration::HOUR, location, Earth.destination(location, 270.0, 1852.0*6.0), location, None, None, None, None, None, None, None, None);
+        let bare_entry = ShipLogEntry::new(timestamp, location, location, location, None, None, None, None, None, None, None, None);
+        let sentences = get_nmea_sentences(&bare_entry, Some(&previous));
+        assert_eq!(sentences.len(), 1);
+        assert_eq!(sentences[0].contains(",W,6.0,"), true, "RMC: {}", sentences[0]);
+
+        // Written to a file, one sentence per line
+        let file_path = std::env::temp_dir().join("marine_vessel_simulator_nmea_test.nmea");
+        let file_path = file_path.to_str().unwrap();
+        ship_log_to_nmea_file(file_path, &[previous, entry]).unwrap();
+        let contents = std::fs::read_to_string(file_path).unwrap();
+        assert_eq!(contents.lines().count(), 5);
+        assert_eq!(contents.ends_with("$IIHDT,85.0,T*1F\r\n"), true);
+        std::fs::remove_file(file_path).unwrap();
+
+        // Invalid time scale
+        assert_eq!(matches!(write_ship_log_nmea(&mut Vec::new(), &[bare_entry], Some(0.0)), Err(MarineSimError::InvalidInput(_))), true);
+    }
 
-                // Convert all aishub_data_collector fields into marine_vessel_simulator ShipLogEntry fields. Note aishub stores tstamp in unix time
-                let timestamp: time::UtcDateTime = match time::UtcDateTime::from_unix_timestamp(tstamp) {
-                    Ok(t) => t,
-                    Err(e) => Err(io::Error::new(io::ErrorKind::Other, format!("Error converting tstamp to UtcDateTime: {}", e)))?,
-                };
+    #[test]
+    fn ais_import_test() {
+        // Marine Cadastre, two vessels out of order with a heading that is not available and a row without a position
+        let file_path = std::env::temp_dir().join("marine_vessel_simulator_ais_marine_cadastre_test.csv");
+        let file_path = file_path.to_str().unwrap();
+        std::fs::write(file_path, "MMSI,BaseDateTime,LAT,LON,SOG,COG,Heading,VesselName,IMO,CallSign,VesselType,Status,Length,Width,Draft,Cargo,TransceiverClass\n\
+            367000002,2024-01-01T01:00:00,30.0,-90.0,0.0,0.0,511,TUG,,,52,5,20,8,3.0,,A\n\
+            367000001,2024-01-01T01:00:00,30.1,-89.9,10.0,90.0,88,CARGO ONE,IMO1,CALL,70,0,100,20,6.5,,A\n\
+            367000001,2024-01-01T00:00:00,30.0,-90.0,10.0,90.0,89,CARGO ONE,IMO1,CALL,70,0,100,20,6.5,,A\n\
+            367000001,2024-01-01T02:00:00,,,10.0,90.0,89,CARGO ONE,IMO1,CALL,70,0,100,20,6.5,,A\n").unwrap();
+        let tracks = read_ais_csv(file_path, AisFormat::MarineCadastre, None).unwrap();
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].mmsi, 367000001);
+        assert_eq!(tracks[0].name, Some("CARGO ONE".to_string()));
+        assert_eq!(tracks[0].ship_log.len(), 2, "The row without a position is skipped");
+        assert_eq!(tracks[0].ship_log[0].heading, Some(89.0), "Sorted by time");
+        assert_eq!(tracks[0].ship_log[0].coordinates_final, geo::Point::new(-89.9, 30.1));
+        assert_eq!((tracks[0].ship_log[0].velocity.unwrap().magnitude - 10.0*1852.0/3600.0).abs() < 1e-9, true);
+        assert_eq!(tracks[1].ship_log[0].heading, None, "Heading 511 is not available");
+        assert_eq!(tracks[1].ship_log[0].navigation_status, Some(NavigationStatus::Moored));
+        assert_eq!(read_ais_csv(file_path, AisFormat::MarineCadastre, Some(&[367000002])).unwrap().len(), 1);
+        assert_eq!(read_ais_csv(file_path, AisFormat::DanishMaritimeAuthority, None).is_err(), true, "Wrong format");
+        std::fs::remove_file(file_path).unwrap();
+
+        // Danish Maritime Authority
+        let file_path = std::env::temp_dir().join("marine_vessel_simulator_ais_dma_test.csv");
+        let file_path = file_path.to_str().unwrap();
+        std::fs::write(file_path, "# Timestamp,Type of mobile,MMSI,Latitude,Longitude,Navigational status,ROT,SOG,COG,Heading,IMO,Callsign,Name,Ship type,Cargo type,Width,Length,Type of position fixing device,Draught,Destination,ETA,Data source type,A,B,C,D\n\
+            31/12/2024 23:59:00,Class A,219000001,55.7,12.6,Under way sailing,0.0,6.0,45.0,40,Unknown,OXAB,WIND CARGO,Cargo,,10,60,GPS,4.2,COPENHAGEN,,AIS,,,,\n").unwrap();
+        let tracks = read_ais_csv(file_path, AisFormat::DanishMaritimeAuthority, None).unwrap();
+        assert_eq!(tracks.len(), 1);
+        assert_eq!(tracks[0].ship_log[0].timestamp, UtcDateTime::new(time::Date::from_calendar_date(2024, time::Month::December, 31).unwrap(), time::Time::from_hms(23, 59, 0).unwrap()));
+        assert_eq!(tracks[0].ship_log[0].navigation_status, Some(NavigationStatus::UnderwaySailing));
+        assert_eq!(tracks[0].ship_log[0].draft, Some(4.2));
+        std::fs::remove_file(file_path).unwrap();
+    }
 
-                let coordinates_current = geo::Point::new(longitude,latitude);
-                
-                // Init velocity
-                let velocity: Option<PhysVec>;
-                // If sog is unknown, set velocity to None
-                if sog == None {
-                    velocity = None;
-                }   // Otherwise if cog is None, check if sog is zero
-                else if cog == None {
-                    // If sog is zero set sog and cog to zero in velocity since the direction does not matter
-                    if sog.unwrap() == 0.0 {
-                        velocity = Some(PhysVec::new(0.0, 0.0));
-                    } // Otherwise set velocity to None since we don't know the direction
-                    else {
-                        velocity = None;
+    #[test]
+    fn aivdm_encoder_test() {
+        // Decodes the payload of !AIVDM sentences to bits and reads unsigned and signed fields from them
+        let decode = |sentences: &[String]| -> Vec<bool> {
+            let mut bits: Vec<bool> = Vec::new();
+            for sentence in sentences {
+                // The checksum is valid
+                let (fields, checksum) = sentence[1..].split_once('*').unwrap();
+                assert_eq!(format!("{:02X}", get_nmea_checksum(fields)), checksum);
+                for c in fields.split(',').nth(5).unwrap().bytes() {
+                    let mut value = c - 48;
+                    if value > 40 {
+                        value -= 8;
                     }
-                } // Otherwise, we know both cog and sog
-                else {
-                    velocity = Some(PhysVec::new(sog.expect("Could not get sog when making velocity vector"), cog.expect("Could not get cog when making velocity vector")));
+                    bits.extend((0..6).rev().map(|i| (value >> i) & 1 == 1));
                 }
+            }
+            bits
+        };
+        let field = |bits: &[bool], start: usize, len: usize| -> u64 { bits[start..start + len].iter().fold(0, |value, bit| (value << 1) | (*bit as u64)) };
+        let signed_field = |bits: &[bool], start: usize, len: usize| -> i64 { ((field(bits, start, len) << (64 - len)) as i64) >> (64 - len) };
+
+        let mut boat = Boat::new();
+        boat.name = Some("Wind cargo".to_string());
+        boat.length = Some(uom::si::f64::Length::new::<uom::si::length::meter>(40.0));
+        boat.width = Some(uom::si::f64::Length::new::<uom::si::length::meter>(8.0));
+        let vessel = AisVessel::from_boat(257000001, &boat);
+        let timestamp = time::UtcDateTime::new(time::Date::from_calendar_date(2025, time::Month::May, 1).unwrap(), time::Time::from_hms(12, 0, 30).unwrap());
+        let location = geo::Point::new(-5.25, 60.5);
+        let entry = ShipLogEntry::new(timestamp, location, location, location, None, Some(PhysVec::new(5.0, 90.0)), None, Some(88.0), None, None, Some(3.5), Some(NavigationStatus::UnderwaySailing));
+
+        // Position report
+        let sentence = vessel.position_report(&entry, 1).unwrap();
+        assert_eq!(sentence.starts_with("!AIVDM,1,1,,A,"), true, "Sentence: {}", sentence);
+        let bits = decode(&[sentence]);
+        assert_eq!(bits.len(), 168);
+        assert_eq!(field(&bits, 0, 6), 1);
+        assert_eq!(field(&bits, 8, 30), 257000001);
+        assert_eq!(field(&bits, 38, 4), 8, "Under way sailing");
+        assert_eq!(field(&bits, 50, 10), 97, "5 m/s is 9.7 knots");
+        assert_eq!(signed_field(&bits, 61, 28), -3150000);
+        assert_eq!(signed_field(&bits, 89, 27), 36300000);
+        assert_eq!(field(&bits, 116, 12), 900);
+        assert_eq!(field(&bits, 128, 9), 88);
+        assert_eq!(field(&bits, 137, 6), 30);
+        assert_eq!(vessel.position_report(&entry, 2).is_err(), true, "Not a position report");
+
+        // Static and voyage related data in two sentences
+        let sentences = vessel.ship_log_to_aivdm(&[entry]).unwrap();
+        assert_eq!(sentences.len(), 3);
+        assert_eq!(sentences[0].starts_with("!AIVDM,2,1,0,A,"), true, "Sentence: {}", sentences[0]);
+        assert_eq!(sentences[1].starts_with("!AIVDM,2,2,0,A,"), true, "Sentence: {}", sentences[1]);
+        let bits = decode(&sentences[..2]);
+        assert_eq!(field(&bits, 0, 6), 5);
+        let name: String = (0..20).map(|i| match field(&bits, 112 + 6*i, 6) as u8 { code if code < 32 => (code + 64) as char, code => code as char }).collect();
+        assert_eq!(name, "WIND CARGO@@@@@@@@@@");
+        assert_eq!(field(&bits, 232, 8), AisVessel::SHIP_TYPE_SAILING as u64);
+        assert_eq!(field(&bits, 240, 9), 20, "Antenna in the middle of the boat");
+        assert_eq!(field(&bits, 294, 8), 35, "Draft of 3.5 m");
+    }
 
-                // Track angle is between last and current ship log entry, if this is the first entry, set to None
-                let track_angle = match aishub_logs.len() {
-                    0 => None,
-                    _ => {
-                        let last_entry: &ShipLogEntry = aishub_logs.last().unwrap();
-                        let last_coords: geo::Point = last_entry.coordinates_current;
-                        let curr_coords: geo::Point = coordinates_current;
-                        Some(geo::Haversine.bearing(last_coords, curr_coords))
-                    }
-                };
-                // Set true_bearing to angle between current location and final coordinates
-                let true_bearing = Some(geo::Haversine.bearing(coordinates_current, coordinates_final));
+    #[test]
+    #[cfg(feature = "parquet")]
+    fn parquet_export_test() {
+        let file_path = std::env::temp_dir().join("marine_vessel_simulator_parquet_test.parquet");
+        let file_path = file_path.to_str().unwrap();
+        let timestamp = UtcDateTime::new(time::Date::from_calendar_date(2025, time::Month::May, 1).unwrap(), time::Time::MIDNIGHT);
+        let start = geo::Point::new(-21.9, 64.1);
+        let end = geo::Point::new(4.5, 51.9);
+        let mut entry = ShipLogEntry::new(timestamp, start, start, end, None, Some(PhysVec::new(5.0, 120.0)), None, Some(118.0), None, None, Some(3.5), Some(NavigationStatus::UnderwaySailing));
+        entry.wind = Some(PhysVec::new(8.0, 270.0));
+        entry.wave_height = Some(1.5);
+        let mut second_entry = entry.clone();
+        second_entry.timestamp = timestamp + time::Duration::hours(1);
+        second_entry.wind = None;
+        let ship_log = vec![entry, second_entry];
+        ship_logs_to_parquet(file_path, &[("Boat A", &ship_log[..]), ("Boat B", &ship_log[..1])]).unwrap();
+        assert_eq!(ship_logs_to_parquet("ship_logs.csv", &[]).is_err(), true, "Only .parquet files should be written");
+
+        // Read the file back
+        let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(File::open(file_path).unwrap()).unwrap().build().unwrap();
+        let batches: Vec<arrow::record_batch::RecordBatch> = reader.map(|batch| batch.unwrap()).collect();
+        assert_eq!(batches.iter().map(|batch| batch.num_rows()).sum::<usize>(), 3);
+        let batch = &batches[0];
+        assert_eq!(batch.schema().as_ref(), &get_ship_log_parquet_schema());
+        let column = |name: &str| batch.column_by_name(name).unwrap().as_any().downcast_ref::<arrow::array::Float64Array>().unwrap().clone();
+        assert_eq!(column("latitude[°]").value(0), 64.1);
+        assert_eq!(column("wind_speed[m/s]").value(0), 8.0);
+        assert_eq!(column("wind_speed[m/s]").is_null(1), true, "Missing wind should be null");
+        assert_eq!(column("wave_height[m]").value(1), 1.5);
+        let timestamps = batch.column_by_name("timestamp").unwrap().as_any().downcast_ref::<arrow::array::TimestampMillisecondArray>().unwrap();
+        assert_eq!(timestamps.value(1) - timestamps.value(0), 3600000);
+        std::fs::remove_file(file_path).unwrap();
+    }
 
-                let navigation_status: Option<NavigationStatus> = match navstat {
-                    Some(n) => match NavigationStatus::try_from(n) {
-                                    Ok(status) => Some(status),
-                                    Err(_) => None,
-                                },
-                    None => None
-                };
+    #[test]
+    #[cfg(feature = "storage")]
+    fn storage_test() {
+        let database = ResultDatabase::open_in_memory().unwrap();
+        let boat = Boat::builder().name("Database boat").velocity_mean(4.0).build();
+        let boat_id = database.insert_boat(&boat).unwrap();
+        let start_time = UtcDateTime::new(time::Date::from_calendar_date(2025, time::Month::May, 1).unwrap(), time::Time::MIDNIGHT);
+        let simulation = Simulation::builder(SimMethod::ConstVelocity).start_time(start_time).build().unwrap();
+        let simulation_id = database.insert_simulation(boat_id, "Sweep 1", &simulation).unwrap();
+
+        // A run with a ship log of two entries
+        let start = geo::Point::new(-21.9, 64.1);
+        let end = geo::Point::new(4.5, 51.9);
+        let mut entry = ShipLogEntry::new(start_time, start, start, end, Some(uom::si::f64::Mass::new::<uom::si::mass::ton>(10.0)), Some(PhysVec::new(5.0, 120.0)), Some(115.0), Some(118.0), None, None, Some(3.5), Some(NavigationStatus::UnderwaySailing));
+        entry.wind = Some(PhysVec::new(8.0, 270.0));
+        let mut second_entry = entry.clone();
+        second_entry.timestamp = start_time + time::Duration::hours(1);
+        second_entry.coordinates_current = geo::Point::new(-21.8, 64.05);
+        second_entry.wave_height = Some(1.5);
+        let ship_log = vec![entry, second_entry];
+        let result = SimulationResult { status: SimulationStatus::Completed, iterations: 2, kpis: VoyageKpis::from_ship_log(&ship_log), progress: VoyageProgress::new(start_time, 1, 0.0) };
+        let run_id = database.insert_run(simulation_id, start_time, &result, &ship_log).unwrap();
+
+        let runs = database.get_runs(Some(simulation_id)).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].id, run_id);
+        assert_eq!(runs[0].boat_name, Some("Database boat".to_string()));
+        assert_eq!(runs[0].simulation_name, "Sweep 1");
+        assert_eq!(runs[0].departure, start_time);
+        assert_eq!(runs[0].duration, time::Duration::hours(1));
+        assert_eq!(database.get_runs(Some(simulation_id + 1)).unwrap().len(), 0);
+
+        let ship_log_copy = database.get_ship_log(run_id).unwrap();
+        assert_eq!(ship_log_copy.len(), 2);
+        assert_eq!(ship_log_copy[1].timestamp, ship_log[1].timestamp);
+        assert_eq!(ship_log_copy[1].coordinates_current, ship_log[1].coordinates_current);
+        assert_eq!(ship_log_copy[0].wind, ship_log[0].wind);
+        assert_eq!(ship_log_copy[1].wave_height, Some(1.5));
+        assert_eq!(ship_log_copy[0].navigation_status, Some(NavigationStatus::UnderwaySailing));
+        assert_eq!(ship_log_copy[0].cargo_on_board, ship_log[0].cargo_on_board);
+        assert_eq!(database.get_ship_log(run_id + 1).is_err(), true, "Unknown run");
+
+        // Statistics with metadata
+        let mut statistics = ShippingStatistics { num_trips: 1, speed_mean: Some(4.0), travel_time_mean: Some(time::Duration::hours(30)), ..Default::default() };
+        statistics.set_metadata("boat_length[m]", "40");
+        database.insert_statistics(Some(simulation_id), "Database boat", &statistics).unwrap();
+        let statistics_copy = database.get_statistics(None).unwrap();
+        assert_eq!(statistics_copy.len(), 1);
+        assert_eq!(statistics_copy[0].0, "Database boat");
+        assert_eq!(statistics_copy[0].1.travel_time_mean, Some(time::Duration::hours(30)));
+        assert_eq!(statistics_copy[0].1.metadata, vec![("boat_length[m]".to_string(), "40".to_string())]);
+    }
 
-                // Add ship log entry
-                aishub_logs.push(
-                    ShipLogEntry {
-                        timestamp,
-                        coordinates_initial,
-                        coordinates_current,
-                        coordinates_final,
-                        cargo_on_board,
-                        velocity,
-                        course: cog,
-                        heading,
-                        track_angle,
-                        true_bearing,
-                        draft,
-                        navigation_status,
-                    });
-                }
-            Err(err) => {
-                eprintln!("Error reading ship log entry from aishub_data_collector: {}", err);
-            }
+    #[test]
+    fn ship_log_writer_test() {
+        let file_path = std::env::temp_dir().join("marine_vessel_simulator_ship_log_writer_test.csv");
+        let file_path = file_path.to_str().unwrap();
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 0.0)]));
+        boat.velocity_mean = Some(5.0);
+        let start_times = vec![UtcDateTime::from_unix_timestamp(0).unwrap(), UtcDateTime::from_unix_timestamp(86400).unwrap()];
+
+        // The same simulation with the ship logs kept in the boat
+        let mut boat_copy = boat.clone();
+        let mut simulation = Simulation::new(SimMethod::ConstVelocity, start_times.clone(), time::Duration::hours(1), 1000, None, None);
+        #[cfg(feature = "progress_bar")]
+        {
+            simulation.progress_bar = Some(indicatif::ProgressBar::hidden());
         }
+        let results_copy = sim_waypoint_missions(&mut boat_copy, &simulation).unwrap();
+
+        // Streamed to a CSV file
+        simulation.set_ship_log_writer(CsvShipLogWriter::create(file_path).unwrap());
+        let results = sim_waypoint_missions(&mut boat, &simulation).unwrap();
+        assert_eq!(boat.ship_log.len(), 0, "The ship logs should have been written to the file");
+        assert_eq!(results, results_copy, "Streaming should not change the results");
+        let ship_log = csv_to_ship_log(file_path).unwrap();
+        assert_eq!(ship_log.len(), boat_copy.ship_log.len());
+        assert_eq!(ship_log.iter().map(|entry| entry.timestamp).collect::<Vec<UtcDateTime>>(), boat_copy.ship_log.iter().map(|entry| entry.timestamp).collect::<Vec<UtcDateTime>>());
+        std::fs::remove_file(file_path).unwrap();
     }
 
-    // Write Shiplog to csv file
-    ship_logs_to_csv(filepath_output, &aishub_logs)?;
-
-    // Return success
-    return Ok(aishub_logs);
-}
+    #[test]
+    fn ship_log_from_csv_test() {
+        let file_path = std::env::temp_dir().join("marine_vessel_simulator_ship_log_from_csv_test.csv");
+        let file_path = file_path.to_str().unwrap();
+        let timestamp = UtcDateTime::new(time::Date::from_calendar_date(2025, time::Month::May, 1).unwrap(), time::Time::from_hms(6, 30, 15).unwrap());
+        let start = geo::Point::new(-21.9, 64.1);
+        let end = geo::Point::new(4.5, 51.9);
+        // The velocity angle and track angle differ from the course so they must be read from their own columns
+        let mut entry = ShipLogEntry::new(timestamp, start, geo::Point::new(-21.5, 63.9), end, Some(uom::si::f64::Mass::new::<uom::si::mass::ton>(12.5)), Some(PhysVec::new(5.25, 131.5)), Some(120.0), Some(128.0), Some(135.0), Some(118.5), Some(3.5), Some(NavigationStatus::UnderwaySailing));
+        entry.depth = Some(250.0);
+        entry.wind = Some(PhysVec::new(8.0, 270.0));
+        entry.wave_height = Some(1.5);
+        entry.cross_track_error = Some(-120.5);
+        let mut second_entry = ShipLogEntry::new(timestamp + time::Duration::hours(1), start, geo::Point::new(-21.3, 63.8), end, None, None, None, None, None, None, None, None);
+        second_entry.track_angle = Some(140.0);
+        let ship_log = vec![entry, second_entry];
+        ship_logs_to_csv(file_path, &ship_log).unwrap();
+
+        let ship_log_copy = ship_log_from_csv(file_path).unwrap();
+        assert_eq!(ship_log_copy.len(), 2);
+        for (entry, entry_copy) in ship_log.iter().zip(ship_log_copy.iter()) {
+            assert_eq!(format!("{:?}", entry_copy), format!("{:?}", entry), "The entry should be read back exactly");
+        }
 
+        // Files from before the velocity angle, track angle and cross track error columns use the course and the bearing from the previous entry
+        let contents = std::fs::read_to_string(file_path).unwrap();
+        let old_contents: String = contents.lines().map(|line| line.rsplitn(4, ';').last().unwrap().to_string() + "\n").collect();
+        std::fs::write(file_path, old_contents).unwrap();
+        let ship_log_copy = ship_log_from_csv(file_path).unwrap();
+        assert_eq!(ship_log_copy[0].velocity, Some(PhysVec::new(5.25, 120.0)));
+        assert_eq!(ship_log_copy[0].track_angle, None);
+        assert_eq!(ship_log_copy[1].track_angle.is_some(), true);
+        assert_eq!(ship_log_copy[0].cross_track_error, None);
+
+        // Other files are not read
+        std::fs::write(file_path, "time;lat;lon\n2025-05-01 00:00:00;64.1;-21.9\n").unwrap();
+        assert_eq!(ship_log_from_csv(file_path).is_err(), true, "Unknown header");
+        std::fs::remove_file(file_path).unwrap();
+    }
 
-/// Helper function that checks if a file extensions matches the given file extension.
-/// A file called "mydata.csv" passed through this function with either ".csv" or "csv" will return true
-pub fn check_file_extension(filepath: &str, extension: &str) -> bool {
-    // Get num_chars in filepath and extension
-    let num_chars_in_file = filepath.len();
-    let num_chars_in_extension = extension.len();
+    #[test]
+    fn statistics_csv_append_and_combine_test() {
+        let dir = std::env::temp_dir();
+        let sail_path = dir.join("marine_vessel_simulator_statistics_sail_test.csv");
+        let diesel_path = dir.join("marine_vessel_simulator_statistics_diesel_test.csv");
+        let comparison_path = dir.join("marine_vessel_simulator_statistics_comparison_test.csv");
+        let (sail_path, diesel_path, comparison_path) = (sail_path.to_str().unwrap(), diesel_path.to_str().unwrap(), comparison_path.to_str().unwrap());
+        let save = |file_path: &str, name: &str, speed_mean: f64, mode: CsvWriteMode| {
+            save_shipping_logs_evaluation_to_csv(file_path, vec![name], vec![Some(speed_mean)], vec![None], vec![None], vec![None], vec![None], vec![None], vec![None], vec![None], vec![None], vec![None], vec![1], vec![None], vec![None], vec![None], mode).unwrap();
+        };
 
-    // Check if filepath has at least the same number of characters as the extension. If fewer, retun false
-    if num_chars_in_file < num_chars_in_extension {
-        return false;
+        // Evaluate the scenarios one at a time, appending keeps the earlier rows and overwriting does not
+        save(sail_path, "Sail", 5.0, CsvWriteMode::Overwrite);
+        save(sail_path, "Hybrid", 5.5, CsvWriteMode::Append);
+        save(sail_path, "Sail", 4.5, CsvWriteMode::Append);
+        let rows = read_shipping_statistics_rows(sail_path).unwrap();
+        assert_eq!(rows.iter().map(|row| (row.0[0].as_str(), row.0[1].as_str())).collect::<Vec<(&str, &str)>>(), vec![("Sail", "4.5"), ("Hybrid", "5.5")]);
+        save(diesel_path, "Old diesel", 7.0, CsvWriteMode::Append);
+        save(diesel_path, "Diesel", 6.0, CsvWriteMode::Overwrite);
+        assert_eq!(read_shipping_statistics_rows(diesel_path).unwrap().len(), 1);
+
+        // One comparison table
+        let mut statistics = ShippingStatistics::default();
+        statistics.speed_mean = Some(5.2);
+        statistics.set_metadata("fuel[t]", "0");
+        append_shipping_statistics_to_csv(diesel_path, "Hybrid", &statistics).unwrap();
+        combine_shipping_statistics_csv_files(comparison_path, &[sail_path, diesel_path]).unwrap();
+        let rows = read_shipping_statistics_rows(comparison_path).unwrap();
+        assert_eq!(rows.iter().map(|row| (row.0[0].as_str(), row.0[1].as_str())).collect::<Vec<(&str, &str)>>(), vec![("Sail", "4.5"), ("Hybrid", "5.2"), ("Diesel", "6")]);
+        assert_eq!(rows[1].1, vec![("fuel[t]".to_string(), "0".to_string())]);
+        assert_eq!(combine_shipping_statistics_csv_files(comparison_path, &[sail_path, "missing.csv"]).is_err(), true, "Missing files should not be combined");
+        for file_path in [sail_path, diesel_path, comparison_path] {
+            std::fs::remove_file(file_path).unwrap();
+        }
     }
 
-    // Check if the filepath string ends with the extension, if so return true, otherwise return false
-    if &filepath[(num_chars_in_file - num_chars_in_extension)..] == extension {
-        return true;
-    } else {
-        return false;
+    #[test]
+    #[cfg(feature = "xlsx")]
+    fn xlsx_import_test() {
+        // Date cells are read as UTC timestamps, 45778.5 is noon on 2025-05-01
+        let date = calamine::Data::DateTime(calamine::ExcelDateTime::new(45778.5, calamine::ExcelDateTimeType::DateTime, false));
+        assert_eq!(get_xlsx_cell_text(&date), "2025-05-01 12:00:00");
+        assert_eq!(string_to_utc_date_time(get_xlsx_cell_text(&date)).unwrap(), UtcDateTime::new(time::Date::from_calendar_date(2025, time::Month::May, 1).unwrap(), time::Time::from_hms(12, 0, 0).unwrap()));
+        assert_eq!(get_xlsx_cell_text(&calamine::Data::DateTimeIso("2025-05-01T06:30:00".to_string())), "2025-05-01 06:30:00");
+        assert_eq!(get_xlsx_cell_text(&calamine::Data::Float(12.5)), "12.5");
+        assert_eq!(get_xlsx_cell_text(&calamine::Data::String(" 64.1,-21.9 ".to_string())), "64.1,-21.9");
+        assert_eq!(get_xlsx_cell_text(&calamine::Data::Empty), "");
+
+        assert_eq!(is_excel_file("logs.xlsx"), true);
+        assert_eq!(is_excel_file("logs.csv"), false);
+        assert_eq!(xlsx_to_ship_log("missing_workbook.xlsx").is_err(), true, "Missing workbooks should return an error");
+
+        // Workbooks saved in the 1904 date system count the days from 1904-01-01, 44316.5 is noon on 2025-05-01
+        let date_1904 = calamine::Data::DateTime(calamine::ExcelDateTime::new(44316.5, calamine::ExcelDateTimeType::DateTime, true));
+        assert_eq!(get_xlsx_cell_text(&date_1904), "2025-05-01 12:00:00");
+
+        // A workbook in the 1904 date system with the header of ship_logs_to_csv()
+        let ship_log = xlsx_to_ship_log(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/ship_log_1904.xlsx")).unwrap();
+        assert_eq!(ship_log.len(), 2);
+        assert_eq!(ship_log[0].timestamp, UtcDateTime::new(time::Date::from_calendar_date(2025, time::Month::May, 1).unwrap(), time::Time::from_hms(12, 0, 0).unwrap()));
+        assert_eq!(ship_log[1].timestamp - ship_log[0].timestamp, time::Duration::hours(6));
+        assert_eq!(ship_log[1].coordinates_current, geo::Point::new(-21.5, 64.1));
+        assert_eq!(ship_log[1].velocity.unwrap().magnitude, 5.5);
+
+        // Any other header is an error
+        let header = csv::StringRecord::from(vec!["time", "from", "position", "to", "cargo"]);
+        assert_eq!(matches!(check_ship_log_header(&header, "logs.xlsx"), Err(MarineSimError::CsvParse { .. })), true);
     }
-}
-
-/// Function that formats every file, not just a single file, in the input folder as long as they are from aishub_data_collector and formats them to work with marine_vessel_simulator 
-pub fn format_shipping_log_data(input_folder: &String, output_folder: &String,) -> Result<(), io::Error> {
-    // Get list of all files in input folder
-    let files: std::fs::ReadDir = std::fs::read_dir(std::path::Path::new(input_folder)).expect(format!("Error reading input folder {:?}", input_folder).as_str());
 
-    // Loop through all the files
-    for file in files {
-        // Get DirEntry
-        let file: std::fs::DirEntry = file.expect("Error reading file in input folder");
+    #[test]
+    fn plot_options_test() {
+        let folder = std::env::temp_dir().join("marine_vessel_simulator_plot_options_test");
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        let folder = folder.to_str().unwrap();
+
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 0.0)]));
+        boat.velocity_mean = Some(5.0);
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+        sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+
+        // Headless, saved as HTML without opening a browser
+        let options = PlotOptions::headless(&format!("{}/voyage.html", folder));
+        assert_eq!(options.show, false);
+        plot_ship_logs(boat.ship_log.clone(), &options).unwrap();
+        assert_eq!(std::path::Path::new(&format!("{}/voyage.html", folder)).is_file(), true);
+
+        // Unsupported extensions are errors before anything is shown
+        assert!(plot_ship_logs(boat.ship_log.clone(), &PlotOptions::headless(&format!("{}/voyage.txt", folder))).is_err());
+        assert!(plot_ship_logs(boat.ship_log.clone(), &PlotOptions::headless(&format!("{}/voyage", folder))).is_err());
+
+        // Static images need the kaleido feature
+        #[cfg(not(feature = "kaleido"))]
+        assert!(plot_ship_logs(boat.ship_log.clone(), &PlotOptions::headless(&format!("{}/voyage.png", folder))).is_err());
+
+        let _ = std::fs::remove_dir_all(folder);
+    }
 
-        // Check if the file is a .csv file, if not, skip this file
-        let filename = file.file_name();
-        let filename: &str = filename.to_str().unwrap();
-        if !check_file_extension(filename, ".csv") {
-            // Notify user and skip file
-            println!("File {:?} is not a .csv file. Skipping this file", filename);
-            continue;
+    #[test]
+    fn visualize_ship_logs_multi_test() {
+        let folder = std::env::temp_dir().join("marine_vessel_simulator_visualize_ship_logs_multi_test");
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        let folder = folder.to_str().unwrap();
+
+        // Two voyages on the same route at different speeds
+        let route_plan_path = format!("{}/route_plan.csv", folder);
+        std::fs::write(&route_plan_path, "leg;start_lat;start_lon;end_lat;end_lon;tacking_width;min_proximity\n1;0.0;0.0;0.0;0.5;0.0;0.0\n").unwrap();
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+        for (name, velocity) in [("fast", 8.0), ("slow", 4.0)] {
+            let mut boat = Boat::new();
+            boat.route_plan = Some(load_route_plan(&route_plan_path).unwrap());
+            boat.velocity_mean = Some(velocity);
+            sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+            ship_logs_to_csv(&format!("{}/{}.csv", folder, name), &boat.ship_log).unwrap();
         }
+        let fast_path = format!("{}/fast.csv", folder);
+        let slow_path = format!("{}/slow.csv", folder);
+
+        // One trace per voyage after the route plan and tacking corridors, each with its own color
+        let figure = get_ship_logs_multi_figure(&[("Fast", &fast_path), ("Slow", &slow_path)], Some(&route_plan_path), &MapStyle::new()).unwrap();
+        let json = figure.to_json();
+        assert_eq!(json.contains("\"name\":\"Fast\""), true);
+        assert_eq!(json.contains("\"name\":\"Slow\""), true);
+        assert_eq!(json.contains("\"name\":\"Route Plan\""), true);
+        assert_eq!(get_ship_logs_multi_figure(&[("Fast", &fast_path)], None, &MapStyle::new()).unwrap().to_json().contains("Route Plan"), false);
+
+        // Saved headless
+        let figure_path = format!("{}/comparison.html", folder);
+        visualize_ship_logs_multi(&[("Fast", &fast_path), ("Slow", &slow_path)], Some(&route_plan_path), &PlotOptions::headless(&figure_path)).unwrap();
+        assert_eq!(std::path::Path::new(&figure_path).is_file(), true);
+
+        // Nothing to visualize
+        assert!(visualize_ship_logs_multi(&[], None, &PlotOptions::headless(&figure_path)).is_err());
+
+        let _ = std::fs::remove_dir_all(folder);
+    }
 
-        // Get input and output paths
-        let input_file_path: String = input_folder.clone() + "/" + filename;
-        let output_file_path: String = output_folder.clone() + "/" + filename;
+    #[test]
+    fn animate_ship_log_test() {
+        let folder = std::env::temp_dir().join("marine_vessel_simulator_animate_ship_log_test");
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        let folder = folder.to_str().unwrap();
+
+        let route_plan_path = format!("{}/route_plan.csv", folder);
+        std::fs::write(&route_plan_path, "leg;start_lat;start_lon;end_lat;end_lon;tacking_width;min_proximity\n1;0.0;0.0;0.0;0.5;0.0;0.0\n").unwrap();
+        let mut boat = Boat::new();
+        boat.route_plan = Some(load_route_plan(&route_plan_path).unwrap());
+        boat.velocity_mean = Some(1.0);
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+        sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        boat.ship_log[0].wind = Some(PhysVec::new(10.0, 0.0));
+        assert!(boat.ship_log.len() > 10);
+
+        // Thinned out to the most frames, with the first and last entries
+        let options = AnimationOptions { max_frames: 5, ..AnimationOptions::new() };
+        let html = get_ship_log_animation_html(&boat.ship_log, Some(&route_plan_path), &MapStyle::new(), &options).unwrap();
+        let frame_count = html.matches("\"label\":\"1970-").count();
+        assert!(frame_count >= 2 && frame_count <= 5);
+        assert_eq!(html.matches("\"traces\":[2,3]").count(), frame_count);
+        assert_eq!(html.contains(&format!("{{\"name\":\"{}\"", frame_count)), false);
+        let last = &boat.ship_log[boat.ship_log.len() - 1];
+        assert_eq!(html.contains(&format!("\"lat\":[{}],\"lon\":[{}]", last.coordinates_current.y(), last.coordinates_current.x())), true);
+        assert_eq!(html.contains("Plotly.newPlot"), true);
+
+        // The wind arrow points where the wind blows to, south for wind from the north
+        let (latitudes, longitudes) = get_wind_arrow_coordinates(&boat.ship_log[0]);
+        assert_eq!(latitudes.len(), 6);
+        assert!(latitudes[1].unwrap() < latitudes[0].unwrap());
+        assert!((longitudes[1].unwrap() - longitudes[0].unwrap()).abs() < 1e-9);
+        assert_eq!(get_wind_arrow_coordinates(&boat.ship_log[1]).0.len(), 0);
+
+        // Saved headless as HTML only
+        let ship_log_path = format!("{}/ship_log.csv", folder);
+        ship_logs_to_csv(&ship_log_path, &boat.ship_log).unwrap();
+        let figure_path = format!("{}/animation.html", folder);
+        animate_ship_log(&ship_log_path, None, &PlotOptions::headless(&figure_path), &options).unwrap();
+        assert_eq!(std::path::Path::new(&figure_path).is_file(), true);
+        assert!(animate_ship_log(&ship_log_path, None, &PlotOptions::headless(&format!("{}/animation.png", folder)), &options).is_err());
+
+        let _ = std::fs::remove_dir_all(folder);
+    }
 
-        // Run marine_vessel_simulator formatting function on each file
-        // Output formatted file into output folder
-        let _ = match aishub_shiplog_csv_to_marine_vessel_simulator_shiplog_csv(input_file_path.as_str(), output_file_path.as_str()) {
-            Ok(_) => {},
-            Err(e) => println!("Could not format file {:?}. Error: {}", input_file_path, e),
+    #[test]
+    fn plot_ship_log_time_series_test() {
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let mut ship_log = vec![
+            ShipLogEntry::new(start_time, geo::Point::new(0.0, 0.0), geo::Point::new(0.0, 0.0), geo::Point::new(1.0, 0.0), None, None, None, None, None, None, None, None),
+            ShipLogEntry::new(start_time + time::Duration::hours(1), geo::Point::new(0.0, 0.0), geo::Point::new(0.1, 0.0), geo::Point::new(1.0, 0.0), None, None, None, None, None, None, None, None),
+        ];
+        ship_log[1].heading = Some(-10.0);
+        ship_log[1].wind = Some(PhysVec::new(12.0, 180.0));
+
+        // Four traces on three y axes, with the speed from the positions and gaps where values are missing
+        let json = get_ship_log_time_series_figure(&ship_log).unwrap().to_json();
+        assert_eq!(json.contains("\"name\":\"Speed over ground\""), true);
+        assert_eq!(json.contains("\"name\":\"Heading\""), true);
+        assert_eq!(json.contains("\"name\":\"Course\""), true);
+        assert_eq!(json.contains("\"name\":\"Wind speed\""), true);
+        assert_eq!(json.contains("\"y3\""), true);
+        assert_eq!(json.contains("[null,350.0]"), true);
+        assert_eq!(json.contains("[null,12.0]"), true);
+        assert_eq!(json.contains("1970-01-01 01:00:00"), true);
+        let speed = Earth.distance(geo::Point::new(0.0, 0.0), geo::Point::new(0.1, 0.0)) / 3600.0;
+        assert_eq!(json.contains(&format!("[null,{:?}]", speed)), true);
+
+        assert!(get_ship_log_time_series_figure(&[]).is_err());
+
+        // Saved headless as HTML
+        let figure_path = std::env::temp_dir().join("marine_vessel_simulator_time_series_test.html");
+        let figure_path = figure_path.to_str().unwrap();
+        plot_ship_log_time_series(&ship_log, &PlotOptions::headless(figure_path)).unwrap();
+        assert_eq!(std::path::Path::new(figure_path).is_file(), true);
+        let _ = std::fs::remove_file(figure_path);
+    }
+
+    #[test]
+    fn departure_travel_times_test() {
+        // Three departures a week apart, the last one not completed
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let start_times = vec![start_time + time::Duration::days(14), start_time, start_time + time::Duration::days(7)];
+        let simulation = Simulation::new(SimMethod::ConstVelocity, start_times, time::Duration::hours(1), 1000, None, None);
+        let result = |days: i64, status: SimulationStatus| {
+            let mut kpis = VoyageKpis::from_ship_log(&[]);
+            kpis.duration = time::Duration::days(days);
+            SimulationResult { status, iterations: 1, kpis, progress: VoyageProgress::new(start_time, 1, 0.0) }
         };
+        let results = vec![result(30, SimulationStatus::MaxIterationsReached), result(10, SimulationStatus::Completed), result(20, SimulationStatus::Completed)];
+
+        // Sorted by departure, the bands only use the completed voyages in the window
+        let rows = get_departure_travel_times(&simulation, &results, time::Duration::days(14)).unwrap();
+        assert_eq!(rows.iter().map(|row| row.travel_time).collect::<Vec<f64>>(), vec![10.0, 20.0, 30.0]);
+        assert_eq!(rows[2].completed, false);
+        assert_eq!(rows[0].band.unwrap()[2], 15.0);
+        assert_eq!(rows[1].band.unwrap()[2], 15.0);
+        assert_eq!(rows[2].band.unwrap(), [20.0; 5]);
+        let rows = get_departure_travel_times(&simulation, &results, time::Duration::days(1)).unwrap();
+        assert_eq!(rows[0].band.unwrap(), [10.0; 5]);
+        assert_eq!(rows[2].band.is_none(), true);
+        assert!(get_departure_travel_times(&simulation, &results[..2], time::Duration::days(1)).is_err());
+
+        // CSV
+        let csv_path = std::env::temp_dir().join("marine_vessel_simulator_departure_travel_times_test.csv");
+        let csv_path = csv_path.to_str().unwrap();
+        departure_travel_times_to_csv(csv_path, &simulation, &results, time::Duration::days(1)).unwrap();
+        let csv = std::fs::read_to_string(csv_path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1].starts_with("1970-01-01 00:00:00;true;10;"), true);
+        assert_eq!(lines[3].ends_with("false;30;0;;;;;;"), true);
+        let _ = std::fs::remove_file(csv_path);
+
+        // Figure with the bands, median and a trace for the voyages that were not completed
+        let json = get_departure_travel_times_figure(&simulation, &results, time::Duration::days(14)).unwrap().to_json();
+        assert_eq!(json.contains("\"name\":\"Median\""), true);
+        assert_eq!(json.contains("\"name\":\"Not completed\""), true);
+        assert_eq!(json.contains("tonexty"), true);
     }
 
-    // return ok
-    return Ok(());
-}
+    #[test]
+    fn plot_polar_speed_plot_csv_test() {
+        let csv_path = std::env::temp_dir().join("marine_vessel_simulator_polar_speed_plot_test.csv");
+        let csv_path = csv_path.to_str().unwrap();
+        std::fs::write(csv_path, "TWA\\TWS;2;4;6\n0;;;\n45;1.5;;3\n90;2;3.5;4.5\n").unwrap();
+
+        // A trace per wind speed with data, empty cells are gaps
+        let json = get_polar_speed_plot_figure(csv_path).unwrap().to_json();
+        assert_eq!(json.matches("\"type\":\"scatterpolar\"").count(), 3);
+        assert_eq!(json.contains("\"name\":\"TWS 4\""), true);
+        assert_eq!(json.contains("[null,null,3.5]"), true);
+
+        // Wind speeds without data are left out
+        std::fs::write(csv_path, "TWA\\TWS;2;4\n45;1.5;\n90;2;\n").unwrap();
+        let json = get_polar_speed_plot_figure(csv_path).unwrap().to_json();
+        assert_eq!(json.matches("\"type\":\"scatterpolar\"").count(), 1);
+
+        // Saved headless
+        let figure_path = csv_path.replace(".csv", ".html");
+        plot_polar_speed_plot_csv(csv_path, &PlotOptions::headless(&figure_path)).unwrap();
+        assert_eq!(std::path::Path::new(&figure_path).is_file(), true);
+
+        std::fs::write(csv_path, "TWA\\TWS;2\n45;fast\n").unwrap();
+        assert!(get_polar_speed_plot_figure(csv_path).is_err());
+        let _ = std::fs::remove_file(csv_path);
+        let _ = std::fs::remove_file(figure_path);
+    }
 
-/// Function that takes all the csv files in the input folder, filters them by the given values (if None then does not filter) and outputs them to the output folder
-/// Only .csv files formatted by aishub_data_collector can be in the input_folder
-/// All files in the output_folder that are named the same name as the files in the input_folder will be overwritten.
-/// navstat filter. Only ship log entries that have the same navigational status as given are included in the output file.
-/// min_vel filter. Minimum velocity, only ship log entries with a velocity strictly higher than the minimum are included in the output file. This means if the min_vel is zero then velocities of zero are not included but all higher velocities are. 
-pub fn filter_shipping_log_data(input_folder: &String, output_folder: &String, min_vel: Option<f64>, navstat: Option<NavigationStatus>) -> Result<(), io::Error> {
-    // Get list of all files in input folder
-    let files: std::fs::ReadDir = std::fs::read_dir(std::path::Path::new(input_folder)).expect(format!("Error reading input folder {:?}", input_folder).as_str());
+    #[test]
+    fn map_style_test() {
+        // The default style is the orthographic globe
+        let json = get_map_figure(&MapStyle::new()).to_json();
+        assert_eq!(json.contains("\"type\":\"orthographic\""), true);
+        assert_eq!(json.contains("rgb(230, 145, 56)"), true);
+        assert_eq!(json.contains("\"template\""), false);
+
+        // Projection, colors, grid and dark mode
+        let style = MapStyle { projection: MapProjection::Mercator, land_color: (1, 2, 3), show_grid: false, ..MapStyle::dark() };
+        let json = get_map_figure(&style).to_json();
+        assert_eq!(json.contains("\"type\":\"mercator\""), true);
+        assert_eq!(json.contains("rgb(1, 2, 3)"), true);
+        assert_eq!(json.contains("\"showgrid\":false"), true);
+        assert_eq!(json.contains("\"template\""), true);
+        assert_eq!(get_map_figure(&MapStyle { projection: MapProjection::NaturalEarth, ..MapStyle::new() }).to_json().contains("\"type\":\"natural earth\""), true);
+
+        // Through the plot options
+        assert_eq!(PlotOptions::new().map_style(MapStyle::dark()).map_style.dark_mode, true);
+    }
 
-    // Loop through all the files
-    for file in files {
-        // Get DirEntry
-        let file: std::fs::DirEntry = file.expect("Error reading file in input folder");
+    #[test]
+    fn tacking_corridor_test() {
+        let route_plan_path = std::env::temp_dir().join("marine_vessel_simulator_tacking_corridor_test.csv");
+        let route_plan_path = route_plan_path.to_str().unwrap();
+        // Width changes between the legs and the last leg has no corridor
+        std::fs::write(route_plan_path, "leg;start_lat;start_lon;end_lat;end_lon;tacking_width;min_proximity\n1;0.0;0.0;0.0;1.0;20000.0;0.0\n2;0.0;1.0;1.0;1.0;5000.0;0.0\n3;1.0;1.0;1.0;2.0;0.0;0.0\n").unwrap();
+
+        let mut figure = plotly::Plot::new();
+        let style = MapStyle { corridor_opacity: 0.4, ..MapStyle::new() };
+        assert_eq!(add_route_plan_traces(&mut figure, route_plan_path, &style).unwrap(), 3);
+        let json = figure.to_json();
+        assert_eq!(json.matches("\"name\":\"Tacking corridor\"").count(), 2);
+        assert_eq!(json.matches("\"fill\":\"toself\"").count(), 2);
+        assert_eq!(json.contains("rgba(0, 114, 178, 0.4)"), true);
+        assert_eq!(json.contains("\"name\":\"Route Plan\""), true);
+
+        // Closed polygon 10 km to each side of the first leg along the equator
+        let bearing = Earth.bearing(geo::Point::new(0.0, 0.0), geo::Point::new(1.0, 0.0));
+        let port = Earth.destination(geo::Point::new(0.0, 0.0), bearing - 90.0, 10000.0);
+        assert!((port.y() - 0.0899).abs() < 1e-3);
+        assert_eq!(json.contains(&format!("{:?}", port.y())), true);
+
+        let _ = std::fs::remove_file(route_plan_path);
+    }
 
-        // Check if the file is a .csv file, if not, skip this file
-        let filename = file.file_name();
-        let filename: &str = filename.to_str().unwrap();
-        if !check_file_extension(filename, ".csv") {
-            // Notify user and skip file
-            println!("File {:?} is not a .csv file. Skipping this file", filename);
-            continue;
+    #[test]
+    fn cross_track_error_test() {
+        // North of a leg going east is to port
+        let leg = SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(1.0, 0.0), 0.0, 0.0);
+        let north = geo::Point::new(0.5, 0.1);
+        let xte = cross_track_distance(&leg, north);
+        assert!((xte + Earth.distance(geo::Point::new(0.5, 0.0), north)).abs() < 1.0, "Cross track error {} should be about -11.1 km", xte);
+        assert!((cross_track_distance(&leg, geo::Point::new(0.5, -0.1)) + xte).abs() < 1e-6);
+        assert!((get_min_point_to_great_circle_dist(leg.p1, leg.p2, north) - xte.abs()).abs() < 1e-6);
+
+        // Recorded at each step from the current leg, about zero when sailing along it
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![leg]));
+        boat.velocity_mean = Some(5.0);
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+        sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        assert!(boat.ship_log.len() > 2);
+        for entry in &boat.ship_log {
+            assert!(entry.cross_track_error.unwrap().abs() < 1.0);
         }
 
-        // Get shiplogs from csv file. If there is an error, notify user and skip this file
-        let input_filepath = input_folder.clone() + "/" + filename;
-        let input_shiplog = match csv_to_ship_log(input_filepath.as_str()){
-            Ok(s) => s,
-            Err(e) => {
-                println!("An error occured while getting shiplog data from {:?}\nSkipping this file\nError message: {}", input_filepath.as_str(), e);
-                continue;
-            },
-        };
+        // Entries pushed off the leg, and entries that already have a cross track error keep it
+        boat.current_leg = Some(1);
+        boat.push_ship_log_entry(ShipLogEntry::new(start_time, leg.p1, north, leg.p2, None, None, None, None, None, None, None, None));
+        assert_eq!(boat.ship_log.last().unwrap().cross_track_error, Some(xte));
+        let mut entry = ShipLogEntry::new(start_time, leg.p1, north, leg.p2, None, None, None, None, None, None, None, None);
+        entry.cross_track_error = Some(3.0);
+        boat.push_ship_log_entry(entry);
+        assert_eq!(boat.ship_log.last().unwrap().cross_track_error, Some(3.0));
+        boat.route_plan = None;
+        boat.push_ship_log_entry(ShipLogEntry::new(start_time, leg.p1, north, leg.p2, None, None, None, None, None, None, None, None));
+        assert_eq!(boat.ship_log.last().unwrap().cross_track_error, None);
+    }
 
-        // init empty output shiplog
-        let mut output_shiplog: Vec<ShipLogEntry> = vec![];
+    #[test]
+    fn along_track_distance_test() {
+        // Leg along the equator, the along track distance is the distance along the equator
+        let leg = SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(1.0, 0.0), 0.0, 0.0);
+        let beside = geo::Point::new(0.5, 0.1);
+        assert!((leg.along_track_distance(beside) - Earth.distance(leg.p1, geo::Point::new(0.5, 0.0))).abs() < 1.0);
+        assert!((leg.along_track_distance(geo::Point::new(-0.5, -0.1)) + Earth.distance(leg.p1, geo::Point::new(-0.5, 0.0))).abs() < 1.0);
+        assert!((leg.along_track_distance(leg.p2) - leg.length()).abs() < 1e-6);
+
+        // The closest point of the segment is beside the point, p1 or p2
+        assert!((get_min_point_to_segment_dist(leg.p1, leg.p2, beside) - leg.cross_track_distance(beside).abs()).abs() < 1e-6);
+        let behind = geo::Point::new(-0.5, 0.1);
+        assert_eq!(get_min_point_to_segment_dist(leg.p1, leg.p2, behind), Earth.distance(leg.p1, behind));
+        let past = geo::Point::new(1.5, -0.1);
+        assert_eq!(get_min_point_to_segment_dist(leg.p1, leg.p2, past), Earth.distance(leg.p2, past));
+        assert_eq!(get_min_point_to_segment_dist(leg.p1, leg.p1, past), Earth.distance(leg.p1, past));
+    }
 
-        // Loop through each entry in the shiplog
-        for entry in input_shiplog {
-            // For each entry, if it passes all the filters, add it to the output shiplog, if not, continue to next entry
-            // Check if velocity filter is in use
-            if min_vel.is_some() {
-                // Check if entry contains velocity, if not then continue to next entry
-                if entry.velocity.is_none() {
-                    continue;
-                }
-                // Check if entries velocity is higher than min_vel, if not then continue to next entry
-                if entry.velocity.unwrap().magnitude <= min_vel.unwrap() {
-                    continue;
-                }
-            }
+    // Test a route across the Pacific, over the ±180° meridian
+    #[test]
+    fn antimeridian_test() {
+        assert_eq!(normalize_longitude(190.0), -170.0);
+        assert_eq!(normalize_longitude(-180.0), 180.0);
+        assert_eq!(get_longitude_difference(170.0, -170.0), 20.0);
+        assert_eq!(unwrap_longitudes(&[170.0, -175.0, -160.0]), vec![170.0, 185.0, 200.0]);
+        assert_eq!(unwrap_longitudes(&[-170.0, 170.0]), vec![-170.0, -190.0]);
+
+        // The leg goes east the short way, about 20° of arc, and its middle is on the ±180° meridian
+        let leg = SailingLeg::new(geo::Point::new(170.0, 10.0), geo::Point::new(-170.0, 10.0), 50000.0, 0.0);
+        let expected_length = Earth.distance(geo::Point::new(0.0, 10.0), geo::Point::new(20.0, 10.0));
+        assert!((leg.length() - expected_length).abs() < 1.0, "Leg length {} should be {}", leg.length(), expected_length);
+        let bearing = Earth.bearing(leg.p1, leg.p2);
+        assert!(bearing > 0.0 && bearing < 90.0, "Bearing {} should be a little north of east", bearing);
+        assert!((leg.point_at(0.5).x().abs() - 180.0).abs() < 1e-6);
+        assert!(leg.cross_track_distance(geo::Point::new(180.0, 10.0)).abs() < 100000.0);
+
+        // Only the ±180° meridian is crossed, north of the end points
+        let crossings = get_route_plan_crossings(&vec![leg], 10.0);
+        assert_eq!(crossings.len(), 1, "Crossings: {:?}", crossings);
+        assert_eq!(crossings[0].line, GraticuleLine::Meridian(180.0));
+        assert!(crossings[0].location.y() > 10.0);
+
+        // The route plan and its corridor are drawn with unwrapped longitudes, not across the whole map
+        let route_plan_path = std::env::temp_dir().join("marine_vessel_simulator_antimeridian_test.csv");
+        let route_plan_path = route_plan_path.to_str().unwrap();
+        std::fs::write(route_plan_path, "leg;start_lat;start_lon;end_lat;end_lon;tacking_width;min_proximity\n1;10.0;170.0;10.0;-170.0;50000.0;0.0\n").unwrap();
+        let mut figure = plotly::Plot::new();
+        assert_eq!(add_route_plan_traces(&mut figure, route_plan_path, &MapStyle::new()).unwrap(), 2);
+        let json = figure.to_json();
+        assert_eq!(json.contains("\"lon\":[170.0,190.0]"), true, "Route plan longitudes are not unwrapped: {}", json);
+        let _ = std::fs::remove_file(route_plan_path);
+
+        // Grid routing across the ±180° meridian stays in the Pacific
+        let polar = PolarDiagram::new(vec![0.0, 45.0, 90.0, 180.0], vec![10.0], vec![vec![0.0], vec![3.0], vec![4.0], vec![4.0]]).unwrap();
+        let mut router = GridRouter::new(polar, 0.25);
+        router.margin = 0.5;
+        let start = geo::Point::new(179.5, 0.0);
+        let end = geo::Point::new(-179.5, 0.0);
+        let departure = time::UtcDateTime::from_unix_timestamp(0).unwrap();
+        let route_plan = router.route(&ConstantWeather::new(PhysVec::new(10.0, 0.0), None), start, end, departure).unwrap();
+        assert_eq!(route_plan.first().unwrap().p1, start);
+        assert_eq!(route_plan.last().unwrap().p2, end);
+        let route_length: f64 = route_plan.iter().map(|leg| leg.length()).sum();
+        assert!(route_length < 1.5*Earth.distance(start, end), "Route of {} m goes the long way around: {:?}", route_length, route_plan);
+    }
 
-            // Check if navigational status filter is in use
-            if navstat.is_some() {
-                // Check if entry contains navstat, if not then continue to next entry
-                if entry.navigation_status.is_none() {
-                    continue;
-                }
-                // Check if navigation status of input shiplog entry matches filter, if not, skip this entry and continue to next one
-                if entry.navigation_status.unwrap() != navstat.unwrap() {
-                    continue;
-                }
-            }
+    // Test that a simulation can use its own Earth model without changing the model of the rest of the program
+    #[test]
+    fn simulation_earth_model_test() {
+        let leg = SailingLeg::new(geo::Point::new(-20.0, 64.0), geo::Point::new(4.0, 52.0), 0.0, 0.0);
+        let sphere_length = leg.length();
+        let wgs84_length = with_earth_model(EarthModel::Wgs84, || leg.length());
+        assert_eq!(wgs84_length, EarthModel::Wgs84.distance(leg.p1, leg.p2));
+        assert!((wgs84_length / sphere_length - 1.0).abs() > 1e-4, "Sphere: {} m, WGS84: {} m", sphere_length, wgs84_length);
+        // Back to the model of the program
+        assert_eq!(get_earth_model(), EarthModel::MEAN_SPHERE);
+
+        // The same voyage at constant velocity on both models
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let mut distances: Vec<f64> = Vec::new();
+        for earth_model in [None, Some(EarthModel::Wgs84)] {
+            let mut boat = Boat::new();
+            boat.route_plan = Some(RoutePlan::new(vec![leg]));
+            boat.velocity_mean = Some(5.0);
+            let mut simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 10000, None, None);
+            simulation.earth_model = earth_model;
+            let sim_result = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+            assert_eq!(sim_result.is_completed(), true);
+            distances.push(sim_result.kpis.distance);
+        }
+        assert!((distances[0] - sphere_length).abs() < 10.0, "Sphere distance: {} m, leg: {} m", distances[0], sphere_length);
+        assert!((distances[1] - wgs84_length).abs() < 10.0, "WGS84 distance: {} m, leg: {} m", distances[1], wgs84_length);
+        assert_eq!(get_earth_model(), EarthModel::MEAN_SPHERE);
+
+        // The radius of a sphere must be positive
+        let mut simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 10000, None, None);
+        simulation.earth_model = Some(EarthModel::Sphere { radius: 0.0 });
+        assert_eq!(simulation.validate().is_err(), true);
+    }
 
-            // Add entry to output shiplog since all filters have been passed
-            output_shiplog.push(entry);
+    #[test]
+    fn timestamp_round_trip_test() {
+        let timestamps = [
+            UtcDateTime::from_unix_timestamp(0).unwrap(),
+            UtcDateTime::new(time::Date::from_calendar_date(2024, time::Month::February, 29).unwrap(), time::Time::from_hms(23, 59, 59).unwrap()),
+            UtcDateTime::new(time::Date::from_calendar_date(999, time::Month::December, 31).unwrap(), time::Time::from_hms(7, 5, 3).unwrap()),
+        ];
+        assert_eq!(format_timestamp(timestamps[0]), "1970-01-01 00:00:00");
+        assert_eq!(format_timestamp(timestamps[2]), "0999-12-31 07:05:03");
+        for timestamp in timestamps {
+            assert_eq!(parse_timestamp(&format_timestamp(timestamp)).unwrap(), timestamp);
         }
+        // Fractions of a second are not written
+        let with_milliseconds = timestamps[1] + time::Duration::milliseconds(500);
+        assert_eq!(parse_timestamp(&format_timestamp(with_milliseconds)).unwrap(), timestamps[1]);
+
+        // The time since year 0
+        let year_zero = UtcDateTime::new(time::Date::from_calendar_date(0, time::Month::January, 1).unwrap(), time::Time::MIDNIGHT);
+        let seconds = (timestamps[1] - year_zero).as_seconds_f64();
+        #[allow(deprecated)]
+        let timestamp_string = timestamp_to_string(uom::si::f64::Time::new::<uom::si::time::second>(seconds)).unwrap();
+        assert_eq!(timestamp_string, "2024-02-29 23:59:59");
+    }
 
-        // If output shiplog is empty, do not make a filtered file, notify user and skip to next file
-        if output_shiplog.len() < 1 {
-            println!("No data left after filtering {:?}", input_filepath);
-            continue;
+    #[test]
+    fn voyage_progress_test() {
+        // Two legs of 0.5° along the equator at 5 m/s with 1 hour time steps
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 0.0),
+            SailingLeg::new(geo::Point::new(0.5, 0.0), geo::Point::new(1.0, 0.0), 0.0, 0.0),
+        ]));
+        boat.velocity_mean = Some(5.0);
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let total_distance = Earth.distance(geo::Point::new(0.0, 0.0), geo::Point::new(1.0, 0.0));
+
+        // Record the progress reported at each time step
+        let reports: std::sync::Arc<std::sync::Mutex<Vec<VoyageProgress>>> = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let reports_clone = reports.clone();
+        let mut simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+        simulation.add_progress_callback(move |progress| reports_clone.lock().unwrap().push(*progress));
+        let sim_result = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+
+        // The distance covered grows with the simulated time, about 18 km per hour, and the legs are completed in order
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len() > 5, true, "Only {} progress reports", reports.len());
+        for pair in reports.windows(2) {
+            assert_eq!(pair[1].distance_covered >= pair[0].distance_covered - 1.0, true, "Progress went back: {:?}", pair);
+            assert_eq!(pair[1].legs_completed >= pair[0].legs_completed, true);
+            assert_eq!(pair[1].simulated_time > pair[0].simulated_time, true);
         }
+        let second = &reports[1];
+        assert_eq!(second.departure, start_time);
+        assert_eq!((second.total_distance - total_distance).abs() < 1.0, true);
+        assert_eq!((second.distance_covered - 5.0*second.simulated_time.as_seconds_f64()).abs() < 1.0, true, "Progress: {:?}", second);
+        assert_eq!(reports.iter().any(|progress| progress.legs_completed == 1), true);
+        assert_eq!((second.percent() - 100.0*second.distance_covered/total_distance).abs() < 1e-9, true);
+
+        // The voyage is done, the boat no longer reports progress
+        assert_eq!(sim_result.progress.legs_completed, 2);
+        assert_eq!(sim_result.progress.total_legs, 2);
+        assert_eq!(sim_result.progress.fraction(), 1.0);
+        assert_eq!(sim_result.progress.simulated_time, sim_result.kpis.duration);
+        let num_reports = reports.len();
+        boat.push_ship_log_entry(boat.ship_log.last().unwrap().clone());
+        assert_eq!(reports.len(), num_reports);
+
+        // A voyage cut short by the simulated duration covers part of the route
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(1.0, 0.0), 0.0, 0.0)]));
+        boat.velocity_mean = Some(5.0);
+        let mut simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+        simulation.max_simulated_duration = Some(time::Duration::hours(3));
+        let sim_result = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        assert_eq!(sim_result.is_completed(), false);
+        assert_eq!(sim_result.progress.legs_completed, 0);
+        assert_eq!(sim_result.progress.fraction() > 0.0 && sim_result.progress.fraction() < 1.0, true, "Progress: {:?}", sim_result.progress);
+    }
 
-        // Check if output folder exists, if not, create it
-        if !std::path::Path::new(output_folder).exists() {
-            let _ = match std::fs::create_dir_all(output_folder) {
-                Ok(_) => {},
-                Err(e) => return Err(io::Error::new(io::ErrorKind::Other, format!("Error creating directory {:?}: {}", output_folder, e)))
-            };
+    #[test]
+    #[cfg(feature = "server")]
+    fn simulation_server_test() {
+        let server = SimulationServer::new();
+        let mut boat = Boat::new();
+        boat.velocity_mean = Some(5.0);
+        let mut simulation = Simulation::new(SimMethod::ConstVelocity, vec![UtcDateTime::from_unix_timestamp(0).unwrap()], time::Duration::hours(1), 1000, None, None);
+        let route_plan = RoutePlan::new(vec![SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 0.0)]);
+        let body = format!("{{\"boat\": {}, \"route_plan\": {}, \"simulation\": {}}}", serde_json::to_string(&boat).unwrap(), serde_json::to_string(&route_plan).unwrap(), serde_json::to_string(&simulation).unwrap());
+
+        // Submit and wait for the simulation to finish
+        let (status_code, json) = server.handle_request("POST", "/simulations", &body);
+        assert_eq!(status_code, 202, "{}", json);
+        let id = serde_json::from_str::<serde_json::Value>(&json).unwrap()["id"].as_u64().unwrap();
+        let mut status = String::new();
+        for _ in 0..500 {
+            let (_, json) = server.handle_request("GET", &format!("/simulations/{}", id), "");
+            status = serde_json::from_str::<serde_json::Value>(&json).unwrap()["status"].as_str().unwrap().to_string();
+            if status != "running" {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(status, "finished");
+
+        // Fetch the results
+        let (status_code, json) = server.handle_request("GET", &format!("/simulations/{}/ship_log", id), "");
+        assert_eq!(status_code, 200);
+        assert_eq!(serde_json::from_str::<Vec<ShipLogEntry>>(&json).unwrap().is_empty(), false);
+        let (status_code, json) = server.handle_request("GET", &format!("/simulations/{}/statistics", id), "");
+        assert_eq!(status_code, 200);
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&json).unwrap()["voyages"][0]["completed"], serde_json::Value::Bool(true));
+
+        // Bad requests
+        assert_eq!(server.handle_request("GET", "/simulations/99", "").0, 404);
+        assert_eq!(server.handle_request("DELETE", "/simulations", "").0, 405);
+        assert_eq!(server.handle_request("POST", "/simulations", "not json").0, 400);
+        simulation.start_times.clear();
+        let body = format!("{{\"boat\": {}, \"simulation\": {}}}", serde_json::to_string(&boat).unwrap(), serde_json::to_string(&simulation).unwrap());
+        assert_eq!(server.handle_request("POST", "/simulations", &body).0, 400, "Invalid simulations should not start");
+        assert_eq!(SimulationServer::new().max_body_size(10).handle_request("POST", "/simulations", &body).0, 413);
+        assert_eq!(SimulationServer::new().max_running_jobs(0).handle_request("POST", "/simulations", &body).0, 503);
+
+        // A simulation that panics fails instead of running forever
+        simulation.start_times.push(UtcDateTime::from_unix_timestamp(0).unwrap());
+        simulation.add_progress_callback(|_| panic!("Progress callback panicked"));
+        let id = server.submit(SimulationRequest { boat, route_plan: Some(route_plan), simulation }).unwrap();
+        let mut job = server.get_job(id).unwrap();
+        for _ in 0..500 {
+            job = server.get_job(id).unwrap();
+            if job.status != SimulationJobStatus::Running {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
         }
+        assert_eq!(job.status, SimulationJobStatus::Failed);
+        assert_eq!(job.error.unwrap().contains("Progress callback panicked"), true);
+        assert_eq!(server.running_jobs(), 0);
+    }
 
-        // Make output filepath
-        let output_filepath: String = output_folder.clone() + "/" + filename;
+    #[test]
+    fn telemetry_test() {
+        use std::io::Read;
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.1, 0.0), 0.0, 0.0)]));
+        boat.velocity_mean = Some(5.0);
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![UtcDateTime::from_unix_timestamp(0).unwrap()], time::Duration::minutes(10), 1000, None, None);
+        sim_waypoint_mission(&mut boat, simulation.start_times[0], &simulation).unwrap();
+
+        // Signal K delta
+        let delta = get_signalk_delta(&boat.ship_log[1], Some(&boat.ship_log[0]), "vessels.self");
+        assert_eq!(delta.starts_with("{\"context\":\"vessels.self\",\"updates\":[{"), true, "{}", delta);
+        assert_eq!(delta.contains("\"timestamp\":\"1970-01-01T00:10:00.000Z\""), true, "{}", delta);
+        assert_eq!(delta.contains("{\"path\":\"navigation.position\",\"value\":{\"latitude\":0,\"longitude\":"), true, "{}", delta);
+        assert_eq!(delta.contains("navigation.speedOverGround"), true, "{}", delta);
+
+        // MQTT, against a broker that accepts the connection and keeps what it receives
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let broker = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut connect = [0u8; 2];
+            stream.read_exact(&mut connect).unwrap();
+            let mut rest = vec![0u8; connect[1] as usize];
+            stream.read_exact(&mut rest).unwrap();
+            std::io::Write::write_all(&mut stream, &[0x20, 2, 0, 0]).unwrap();
+            let mut received: Vec<u8> = Vec::new();
+            stream.read_to_end(&mut received).unwrap();
+            return (connect[0], rest, received);
+        });
+        let mut publisher = TelemetryPublisher::mqtt(&address, "test", "vessels/self", "vessels.self", None).unwrap();
+        publisher.write_ship_log(&boat.ship_log).unwrap();
+        publisher.finish().unwrap();
+        drop(publisher);
+        let (connect_type, connect_rest, received) = broker.join().unwrap();
+        assert_eq!(connect_type, 0x10, "The first packet should be CONNECT");
+        assert_eq!(connect_rest[8..10], [0, 0], "The keep alive should be turned off since entries can be far apart");
+        assert_eq!(received[0], 0x30, "Entries should be published with QoS 0");
+        assert_eq!(received[received.len()-2..], [0xE0, 0], "The publisher should disconnect when finished");
+        assert_eq!(String::from_utf8_lossy(&received).matches("vessels/self{\"context\":\"vessels.self\"").count(), boat.ship_log.len(), "Each entry should be one message");
+
+        assert_eq!(TelemetryPublisher::signalk(std::io::sink(), "vessels.self", Some(0.0)).is_err(), true, "The time scale must be positive");
+    }
 
-        // Check if file exists, if it does, delete it
-        if std::path::Path::new(&output_filepath).exists() {
-            let _ = match std::fs::remove_file(&output_filepath) {
-                Ok(_) => {},
-                Err(e) => return Err(io::Error::new(io::ErrorKind::Other, format!("Error removing file {:?}: {}", output_filepath, e))),
-            };
+    #[test]
+    fn co_simulation_test() {
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.2, 0.0), 0.0, 1000.0),
+            SailingLeg::new(geo::Point::new(0.2, 0.0), geo::Point::new(0.2, 0.2), 0.0, 1000.0),
+        ]));
+        boat.velocity_mean = Some(5.0);
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![UtcDateTime::from_unix_timestamp(0).unwrap()], time::Duration::minutes(10), 1000, None, None);
+        let mut sim = CoSimulation::new(&mut boat, simulation.start_times[0], &simulation).unwrap();
+        assert_eq!(sim.result(&boat).is_none(), true, "The voyage has not ended");
+
+        // Lowering the sail stops the boat without wind or current
+        sim.command(&mut boat, ControlCommand { sail_fraction: Some(0.0), ..Default::default() }).unwrap();
+        let state = sim.step(&mut boat).unwrap();
+        assert_eq!(Earth.distance(state.location, geo::Point::new(0.0, 0.0)) < 1e-6, true);
+        assert_eq!(sim.command(&mut boat, ControlCommand { sail_fraction: Some(1.5), ..Default::default() }).is_err(), true);
+
+        // Steer straight for the next waypoint until the voyage ends
+        sim.command(&mut boat, ControlCommand { sail_fraction: Some(1.0), ..Default::default() }).unwrap();
+        let mut state = sim.state(&boat).unwrap();
+        while !state.is_finished() {
+            let heading = Earth.bearing(state.location, state.next_waypoint);
+            sim.command(&mut boat, ControlCommand { heading: Some(heading), ..Default::default() }).unwrap();
+            state = sim.step(&mut boat).unwrap();
         }
+        assert_eq!(state.status, Some(SimulationStatus::Completed));
+        assert_eq!(state.current_leg, 2);
+        assert_eq!(sim.step(&mut boat).is_err(), true, "Should not step after the voyage has ended");
+
+        let result = sim.result(&boat).unwrap();
+        assert_eq!(result.kpis.waypoint_arrivals.len(), 2);
+        assert_eq!(result.iterations, state.iterations);
+        assert_eq!(boat.ship_log.len(), state.iterations + 1);
+    }
 
-        // Save output shiplog to output folder and continue to next file
-        let _ = ship_logs_to_csv(output_filepath.as_str(), &output_shiplog);
-    }   // End loop through files
+    #[test]
+    #[cfg(feature = "scripting")]
+    fn scripting_test() {
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.2, 0.0), 0.0, 1000.0),
+        ]));
+        boat.velocity_mean = Some(5.0);
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![UtcDateTime::from_unix_timestamp(0).unwrap()], time::Duration::minutes(10), 1000, None, None);
+
+        // Lower the sail and motor to the waypoint, counting the steps in memory
+        let mut script = BehaviorScript::new("sail_fraction = 0; engine_speed = 3.0; let steps = if memory.steps == () { 1 } else { memory.steps + 1 }; memory.steps = steps;").unwrap();
+        let result = sim_waypoint_mission_scripted(&mut boat, simulation.start_times[0], &simulation, &mut script).unwrap();
+        assert_eq!(result.status, SimulationStatus::Completed);
+        assert_eq!(boat.ship_log.last().unwrap().navigation_status, Some(NavigationStatus::UnderwayUsingEngine));
+        assert_eq!(boat.ship_log.len(), result.iterations + 1);
+
+        // Scripts that do not compile, fail or give something other than a number are errors
+        let state = CoSimulation::new(&mut boat, simulation.start_times[0], &simulation).unwrap().state(&boat).unwrap();
+        assert_eq!(BehaviorScript::new("heading = ").is_err(), true);
+        assert_eq!(BehaviorScript::new("loop {}").unwrap().evaluate(&state, &boat).is_err(), true);
+        assert_eq!(BehaviorScript::new("heading = \"north\";").unwrap().evaluate(&state, &boat).is_err(), true);
+    }
 
-    // Return ok
-    return Ok(());
-}
+    #[test]
+    fn draft_from_displacement_test() {
+        // 1025 tons displace 1000 m^3 of sea water, 1 m deep with a 100 m by 20 m hull and block coefficient 0.5
+        let mut boat = Boat::builder()
+            .mass(uom::si::f64::Mass::new::<uom::si::mass::ton>(1025.0))
+            .length(uom::si::f64::Length::new::<uom::si::length::meter>(100.0))
+            .width(uom::si::f64::Length::new::<uom::si::length::meter>(20.0))
+            .block_coefficient(0.5)
+            .design_displacement(uom::si::f64::Mass::new::<uom::si::mass::ton>(1025.0))
+            .cargo_max_capacity(uom::si::f64::Mass::new::<uom::si::mass::ton>(2000.0))
+            .velocity_mean(5.0)
+            .build();
+        assert_eq!((boat.draft.unwrap() - 1.0).abs() < 1e-9, true);
+        assert_eq!(boat.get_displacement_speed_factor(), 1.0);
+
+        // Loading the same mass of cargo doubles the draft and makes the vessel slower
+        boat.load_cargo(uom::si::f64::Mass::new::<uom::si::mass::ton>(1025.0)).unwrap();
+        assert_eq!((boat.draft.unwrap() - 2.0).abs() < 1e-9, true);
+        assert_eq!((boat.get_displacement_speed_factor() - 0.5_f64.powf(2.0/9.0)).abs() < 1e-9, true);
+
+        // The draft is logged at each time step
+        boat.route_plan = Some(RoutePlan::new(vec![SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.1, 0.0), 0.0, 0.0)]));
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+        sim_waypoint_mission_constant_velocity(&mut boat, start_time, &simulation).unwrap();
+        assert_eq!(boat.ship_log.len() > 1, true);
+        assert_eq!(boat.ship_log.iter().all(|entry| (entry.draft.unwrap() - 2.0).abs() < 1e-9), true);
+    }
 
-/// Function that loops through all the data points in a polar plot source data csv file to find the speed grade coefficient of the vessel.
-/// The speed grade controls how quickly the vessel reaches v_max with increasing wind speed.
-/// v_max is the maximum velocity the vessel can reach (hull speed)
-/// returns the mean k and the standard deviation
-pub fn get_k(source_data_path: &str, vmax: f64) -> Result<(f64, f64), io::Error> {
-    // Check if the file is a .csv file, if not, return -1000.0
-    if !check_file_extension(source_data_path, ".csv") {
-        // Return error
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("File {:?} is not a .csv file. Cannot calculate k value", source_data_path)));
+    #[test]
+    fn cargo_types_test() {
+        let mut boat = Boat::builder()
+            .teu_capacity(100)
+            .liquid_capacity(500.0)
+            .cargo_max_capacity(uom::si::f64::Mass::new::<uom::si::mass::ton>(2000.0))
+            .build();
+        boat.add_cargo(Cargo::Containers { teu: 80, mass: uom::si::f64::Mass::new::<uom::si::mass::ton>(800.0) }).unwrap();
+        boat.add_cargo(Cargo::Liquid { volume: 400.0, density: 1000.0 }).unwrap();
+        assert_eq!((boat.cargo_current.get::<uom::si::mass::ton>() - 1200.0).abs() < 1e-9, true);
+
+        // Each type has its own capacity and the total mass is limited by cargo_max_capacity
+        assert_eq!(boat.add_cargo(Cargo::Containers { teu: 30, mass: uom::si::f64::Mass::new::<uom::si::mass::ton>(300.0) }).is_err(), true);
+        assert_eq!(boat.add_cargo(Cargo::Liquid { volume: 200.0, density: 1000.0 }).is_err(), true);
+        assert_eq!(boat.add_cargo(Cargo::Bulk { mass: uom::si::f64::Mass::new::<uom::si::mass::ton>(900.0) }).is_err(), true);
+        assert_eq!(boat.cargo.len(), 2, "Cargo that does not fit is not added");
+
+        // Statistics per cargo type, 80 TEU and 400 m^3 carried 1852 m
+        let kpis = VoyageKpis { duration: time::Duration::days(1), distance: 1852.0, ..VoyageKpis::from_ship_log(&[]) };
+        let statistics = get_cargo_statistics(&boat, &kpis);
+        assert_eq!(statistics.len(), 2);
+        assert_eq!(statistics[0].cargo_type, CargoType::Containers);
+        assert_eq!((statistics[0].transport_work - 80.0).abs() < 1e-9, true);
+        assert_eq!((statistics[0].utilization_percent.unwrap() - 80.0).abs() < 1e-9, true);
+        assert_eq!(statistics[1].cargo_type, CargoType::Liquid);
+        assert_eq!((statistics[1].transport_work_per_day.unwrap() - 400.0).abs() < 1e-9, true);
+
+        // Unloading a type removes its mass
+        assert_eq!(boat.unload_cargo_type(CargoType::Containers).len(), 1);
+        assert_eq!((boat.cargo_current.get::<uom::si::mass::ton>() - 400.0).abs() < 1e-9, true);
+        assert_eq!(boat.get_cargo_amount(CargoType::Containers), 0.0);
     }
 
-    // Init k vector
-    let mut k_vec: Vec<f64> = Vec::new();
+    #[test]
+    fn cargo_operation_log_test() {
+        let mut boat = Boat::new();
+        boat.cargo_max_capacity = Some(uom::si::f64::Mass::new::<uom::si::mass::ton>(100.0));
+        boat.location = Some(geo::Point::new(-21.9, 64.15));
+        boat.time_now = UtcDateTime::from_unix_timestamp(0).unwrap();
+
+        // Loading and unloading are logged with the time
+        boat.load_cargo(uom::si::f64::Mass::new::<uom::si::mass::ton>(60.0)).unwrap();
+        boat.time_now = UtcDateTime::from_unix_timestamp(3600).unwrap();
+        boat.unload_cargo(uom::si::f64::Mass::new::<uom::si::mass::ton>(25.0)).unwrap();
+        assert_eq!(boat.events.len(), 2);
+        assert_eq!(boat.events[0].kind, SimulationEventKind::CargoOperation { cargo_loaded: 60.0, cargo_unloaded: 0.0, cargo_on_board: 60.0 });
+        assert_eq!(boat.events[1].kind, SimulationEventKind::CargoOperation { cargo_loaded: 0.0, cargo_unloaded: 25.0, cargo_on_board: 35.0 });
+        assert_eq!(boat.ship_log.len(), 2);
+        assert_eq!(boat.ship_log[1].timestamp, boat.time_now);
+        assert_eq!(boat.ship_log[1].cargo_on_board, Some(uom::si::f64::Mass::new::<uom::si::mass::ton>(35.0)));
+
+        // Bad cargo is an error, nothing changes and nothing is logged
+        assert_eq!(boat.unload_cargo(uom::si::f64::Mass::new::<uom::si::mass::ton>(50.0)).is_err(), true);
+        assert_eq!(boat.load_cargo(uom::si::f64::Mass::new::<uom::si::mass::ton>(-1.0)).is_err(), true);
+        assert_eq!(boat.load_cargo(uom::si::f64::Mass::new::<uom::si::mass::ton>(150.0)).is_err(), true);
+        assert_eq!(boat.cargo_current, uom::si::f64::Mass::new::<uom::si::mass::ton>(35.0));
+        assert_eq!(boat.events.len(), 2);
+        assert_eq!(boat.ship_log.len(), 2);
+    }
 
-    // Read the CSV file
-    let mut csv_reader = csv::ReaderBuilder::new()
-        .delimiter(b';')
-        .has_headers(true)
-        .from_path(source_data_path)
-        .expect(format!("Failed to open file: {}\n", source_data_path).as_str());
+    #[test]
+    fn tank_and_bunkering_test() {
+        // 167 km at 5 m/s takes 9.3 hours, the fuel lasts 7.2 hours
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let new_boat = || Boat::builder()
+            .velocity_mean(5.0)
+            .fuel_tank(Tank::new(1.2, 4.0, 0.0))
+            .route_plan(RoutePlan::new(vec![SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(1.5, 0.0), 0.0, 0.0)]))
+            .build();
+        let mut simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::minutes(30), 1000, None, None);
+
+        // Without bunkering ports the vessel runs out of fuel
+        let mut boat = new_boat();
+        let result = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        assert_eq!(result.status, SimulationStatus::TankEmpty);
+        assert_eq!((result.kpis.fuel.unwrap() - 1200.0).abs() < 1e-6, true);
+
+        // With a bunkering port along the way the vessel detours, fills the tank and completes the voyage
+        let port = geo::Point::new(1.0, 0.05);
+        let mut bunkering = Bunkering::new(vec![port]);
+        bunkering.reserve_fraction = 0.75;
+        bunkering.wait = time::Duration::hours(1);
+        simulation.bunkering = Some(bunkering);
+        let mut boat = new_boat();
+        let result = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        assert_eq!(result.status, SimulationStatus::Completed);
+        assert_eq!(result.kpis.events.iter().filter(|event| matches!(event.kind, SimulationEventKind::Bunkered { .. })).count(), 1);
+        assert_eq!(boat.ship_log.iter().any(|entry| Earth.distance(entry.coordinates_current, port) < 1.0), true);
+        assert_eq!(result.kpis.fuel.unwrap() > 1200.0, true);
+
+        // The bunkering stop and the consumption are undone after the voyage
+        assert_eq!(boat.route_plan.as_ref().unwrap().len(), 1);
+        assert_eq!(boat.fuel_tank.unwrap().level, 1.2);
+    }
 
-    // Loop through all lines of file, compute k for each one and add to k_vec
-    for result in csv_reader.records() {
-        match result {
-            Ok(entry) => {
-                // Get AWA [°]
-                let awa: f64 = entry.get(0).unwrap().parse::<f64>().unwrap();
-                // Get AWS [m/s]
-                let aws: f64 = entry.get(1).unwrap().parse::<f64>().unwrap();
-                // Get VWS (Vessel water sped, the speed through water) [m/s]
-                let vws: f64 = entry.get(2).unwrap().parse::<f64>().unwrap();
-                // Get Heading [°]
-                // let heading: f64 = entry.get(3).unwrap().parse::<f64>().unwrap();
-                // Get wind speed [m/s]
-                // let wind_speed: f64 = entry.get(4).unwrap().parse::<f64>().unwrap();
-                // get wind angle [°]
-                // let wind_angle: f64 = entry.get(5).unwrap().parse::<f64>().unwrap();
-                // get ocean current speed [m/s]
-                // let ocean_current_speed: f64 = entry.get(6).unwrap().parse::<f64>().unwrap();
-                // get ocean current angle [°]
-                // let ocean_current_angle: f64 = entry.get(7).unwrap().parse::<f64>().unwrap();
+    #[test]
+    fn crew_test() {
+        // Rest windows, also past midnight
+        let mut crew = Crew::new(1, 2);
+        crew.rest_windows = vec![(time::macros::time!(22:00), time::macros::time!(2:00))];
+        let midnight = UtcDateTime::from_unix_timestamp(0).unwrap();
+        assert_eq!(crew.is_resting(midnight), true);
+        assert_eq!(crew.is_resting(midnight + time::Duration::hours(3)), false);
+        assert_eq!(crew.can_maneuver(midnight), false);
+
+        // At most 2 maneuvers in any hour
+        let morning = midnight + time::Duration::hours(8);
+        crew.record_maneuver(morning);
+        crew.record_maneuver(morning + time::Duration::minutes(10));
+        assert_eq!(crew.can_maneuver(morning + time::Duration::minutes(20)), false);
+        assert_eq!(crew.can_maneuver(morning + time::Duration::minutes(61)), true);
+
+        // A single-handed sailor does not change the sail while resting at 03:00
+        let mut boat = Boat::builder()
+            .velocity_mean(5.0)
+            .crew(Crew::single_handed())
+            .route_plan(RoutePlan::new(vec![SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.5, 0.0), 0.0, 1000.0)]))
+            .build();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![midnight + time::Duration::hours(3)], time::Duration::minutes(30), 1000, None, None);
+        let mut sim = CoSimulation::new(&mut boat, simulation.start_times[0], &simulation).unwrap();
+        sim.command(&mut boat, ControlCommand { sail_fraction: Some(0.5), ..Default::default() }).unwrap();
+        assert_eq!(sim.state(&boat).unwrap().sail_fraction, 1.0);
+
+        // After the rest the sail is changed
+        for _ in 0..4 {
+            sim.step(&mut boat).unwrap();
+        }
+        sim.command(&mut boat, ControlCommand { sail_fraction: Some(0.5), ..Default::default() }).unwrap();
+        assert_eq!(sim.state(&boat).unwrap().sail_fraction, 0.5);
+    }
 
-                // compute k
-                let k: f64 = (1.0/aws)*((std::f64::consts::PI * vws)/(2.0*vmax*(1.0 - (awa*std::f64::consts::PI/180.0 + (std::f64::consts::PI / 4.0)).cos()))).tan();
+    #[test]
+    fn hull_type_test() {
+        // A monohull is the reference, a catamaran is slower in light wind and faster in strong wind
+        assert_eq!(HullType::Monohull.speed_factor(3.0), 1.0);
+        assert_eq!(HullType::Catamaran.speed_factor(1.0) < 1.0, true);
+        assert_eq!(HullType::Catamaran.speed_factor(10.0) > 1.0, true);
+        assert_eq!((HullType::Trimaran.speed_factor(20.0) - 2.0_f64.powf(1.0/3.0)).abs() < 1e-9, true);
+
+        // The displacement of a catamaran is carried by both hulls, 10.25 tons displace 10 m^3 in two 20 m by 2 m hulls
+        let mut boat = Boat::builder()
+            .hull_type(HullType::Catamaran)
+            .mass(uom::si::f64::Mass::new::<uom::si::mass::ton>(10.25))
+            .length(uom::si::f64::Length::new::<uom::si::length::meter>(20.0))
+            .width(uom::si::f64::Length::new::<uom::si::length::meter>(10.0))
+            .block_coefficient(0.5)
+            .build();
+        assert_eq!(boat.get_draft_from_displacement(), None, "A multihull needs the width of its hulls");
+        boat.hull_width = Some(uom::si::f64::Length::new::<uom::si::length::meter>(2.0));
+        assert_eq!((boat.get_draft_from_displacement().unwrap() - 0.25).abs() < 1e-9, true);
+    }
 
-                // add k to k_vec
-                k_vec.push(k);
-                },
-            // If there is some error with the result, notify user and continue to the next one
-            Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Error getting entry from file {:?}: {}", source_data_path, e))),
-        };
+    #[test]
+    fn foil_test() {
+        assert_eq!(Foil::new(-1.0, 0.5).is_err(), true);
+        assert_eq!(Foil::new(8.0, 0.95).is_err(), true);
+        let foil = Foil::new(8.0, 0.6).unwrap();
+        assert_eq!(foil.speed_factor(7.9), 1.0);
+        assert_eq!((foil.speed_factor(8.0) - 0.4_f64.powf(-2.0/9.0)).abs() < 1e-9, true);
+
+        // The foil lifts the boat above the takeoff speed only
+        let mut boat = Boat::builder()
+            .velocity_max(10.0)
+            .speed_grade_coefficient(1.0)
+            .build();
+        boat.heading = Some(0.0);
+        let wind = PhysVec::new(20.0, 90.0);
+        let speed = get_vessel_velocity(&boat, wind, None).unwrap().magnitude;
+        boat.foil = Some(Foil::new(speed + 1.0, 0.6).unwrap());
+        assert_eq!((get_vessel_velocity(&boat, wind, None).unwrap().magnitude - speed).abs() < 1e-9, true);
+        boat.foil = Some(foil);
+        boat.foil.as_mut().unwrap().takeoff_speed = speed - 1.0;
+        assert_eq!(get_vessel_velocity(&boat, wind, None).unwrap().magnitude > speed, true);
+
+        // Whether the boat is foiling comes with the velocity the foil was applied to
+        assert_eq!(get_vessel_velocity_and_foiling(&boat, wind, None).unwrap().1, Some(true));
+        boat.foil.as_mut().unwrap().takeoff_speed = speed + 1.0;
+        assert_eq!(get_vessel_velocity_and_foiling(&boat, wind, None).unwrap().1, Some(false));
+        boat.foil = None;
+        assert_eq!(get_vessel_velocity_and_foiling(&boat, wind, None).unwrap().1, None);
+
+        // Constant velocity simulations do not use the foil, so they do not record foiling
+        boat.foil = Some(foil);
+        boat.velocity_mean = Some(speed + 10.0);
+        boat.route_plan = Some(RoutePlan::new(vec![SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.1, 0.0), 0.0, 0.0)]));
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::minutes(10), 1000, None, None);
+        sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        assert_eq!(boat.ship_log.iter().all(|entry| entry.foiling.is_none()), true);
     }
 
-    let k_vec_len: f64 = k_vec.len() as f64;
+    #[test]
+    fn drift_test() {
+        // 10 m/s wind from the north and 0.5 m/s current to the east, the boat drifts 0.3 m/s south with the default windage factor
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(1.0, 0.0), 0.0, 0.0)]));
+        let drift_velocity = get_drift_velocity(&boat, PhysVec::new(10.0, 0.0), Some(PhysVec::new(0.5, 90.0)));
+        assert_eq!((drift_velocity.magnitude - (0.3_f64.powi(2) + 0.5_f64.powi(2)).sqrt()).abs() < 1e-9, true);
+
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let mut simulation = Simulation::new(SimMethod::Drift, vec![start_time], time::Duration::hours(1), 10, None, None);
+        assert_eq!(simulation.validate().is_err(), true, "Drift needs weather");
+        simulation.weather = Some(std::sync::Arc::new(ConstantWeather::new(PhysVec::new(10.0, 0.0), Some(PhysVec::new(0.5, 90.0)))));
+        let sim_result = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        assert_eq!(sim_result.status, SimulationStatus::MaxIterationsReached);
+        let last_entry = boat.ship_log.last().unwrap();
+        assert_eq!(last_entry.navigation_status, Some(NavigationStatus::NotUnderCommand));
+        assert_eq!((last_entry.coordinates_current.y() + 0.3*36000.0/111_195.0).abs() < 1e-3, true, "Latitude: {}", last_entry.coordinates_current.y());
+        assert_eq!((last_entry.coordinates_current.x() - 0.5*36000.0/111_195.0).abs() < 1e-3, true, "Longitude: {}", last_entry.coordinates_current.x());
+
+        // Drifting onto land stops the simulation
+        simulation.land_mask = Some(LandMask::new(vec![geo::Polygon::new(geo::LineString::from(vec![(0.05, -1.0), (1.0, -1.0), (1.0, 1.0), (0.05, 1.0), (0.05, -1.0)]), vec![])]));
+        let sim_result = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        assert_eq!(sim_result.status, SimulationStatus::RanAground);
+        assert_eq!(boat.ship_log.last().unwrap().coordinates_current.x() < 0.05, true);
+    }
 
-    // Compute mean k
-    let mut sum_k: f64 = 0.0;
-    for k in k_vec.iter() {
-        sum_k = sum_k + k;
+    #[test]
+    fn drift_ensemble_test() {
+        // Wind from the north, the search area drifts south and grows
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(1.0, 0.0), 0.0, 0.0)]));
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let mut simulation = Simulation::new(SimMethod::Drift, vec![start_time], time::Duration::hours(1), 10, None, None);
+        let ensemble = DriftEnsemble::new(50);
+        assert_eq!(run_drift_ensemble(&boat, &simulation, &ensemble).is_err(), true, "Needs weather");
+        simulation.weather = Some(std::sync::Arc::new(ConstantWeather::new(PhysVec::new(10.0, 0.0), None)));
+        let search_area = run_drift_ensemble(&boat, &simulation, &ensemble).unwrap();
+        assert_eq!(boat.ship_log.len(), 0, "The template is not changed");
+        assert_eq!(search_area.snapshots.len(), 11);
+        let (first, last) = (&search_area.snapshots[0], search_area.snapshots.last().unwrap());
+        assert_eq!(last.positions.len(), 50);
+        assert_eq!(last.centre.y() < first.centre.y() - 0.05, true, "Centre: {:?}", last.centre);
+        assert_eq!(geo::Area::unsigned_area(&last.polygon) > geo::Area::unsigned_area(&first.polygon), true);
+        assert_eq!(search_area.at(start_time + time::Duration::minutes(90)).unwrap().timestamp, start_time + time::Duration::hours(1));
+
+        let geojson = search_area.to_geojson().unwrap();
+        assert_eq!(geojson.starts_with("{\"type\":\"FeatureCollection\""), true);
+        assert_eq!(geojson.matches("\"type\":\"Polygon\"").count(), 11);
+
+        // The same seed gives the same search area
+        let mut ensemble = DriftEnsemble::new(20);
+        ensemble.seed = Some(42);
+        let search_area = run_drift_ensemble(&boat, &simulation, &ensemble).unwrap();
+        assert_eq!(run_drift_ensemble(&boat, &simulation, &ensemble).unwrap(), search_area);
+        ensemble.seed = Some(43);
+        assert_eq!(run_drift_ensemble(&boat, &simulation, &ensemble).unwrap() != search_area, true);
     }
-    let mean_k: f64 = sum_k / k_vec_len;
 
-    // Compute standard deviation of k
-    let mut sum_k: f64 = 0.0;
-    for k in k_vec.iter() {
-        sum_k = sum_k + (k - mean_k).powi(2);
+    #[test]
+    fn anchor_wait_test() {
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let mut boat = Boat::new();
+        boat.velocity_mean = Some(5.0);
+        let mut route_plan = RoutePlan::new(vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.1, 0.0), 0.0, 0.0),
+            SailingLeg::new(geo::Point::new(0.1, 0.0), geo::Point::new(0.2, 0.0), 0.0, 0.0),
+        ]);
+        route_plan.legs[0].anchor_wait = Some(AnchorWait::until(start_time + time::Duration::hours(10)));
+        boat.route_plan = Some(route_plan);
+        let mut simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+
+        // Wait at anchor until the time
+        let sim_result = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        assert_eq!(sim_result.is_completed(), true);
+        assert_eq!(sim_result.kpis.duration > time::Duration::hours(10), true);
+        assert_eq!(sim_result.kpis.events.iter().filter(|event| matches!(event.kind, SimulationEventKind::Anchored { moored: false, .. })).count(), 1);
+        let at_anchor: Vec<&ShipLogEntry> = boat.ship_log.iter().filter(|entry| entry.navigation_status == Some(NavigationStatus::AtAnchor)).collect();
+        assert_eq!(at_anchor.len() > 5, true);
+        assert_eq!(at_anchor.last().unwrap().timestamp, start_time + time::Duration::hours(10));
+
+        // Waiting for the weather needs weather
+        boat.route_plan.as_mut().unwrap().legs[0].anchor_wait = Some(AnchorWait::for_weather(10.0, time::Duration::hours(5)));
+        assert_eq!(sim_waypoint_mission(&mut boat, start_time, &simulation).is_err(), true);
+
+        // The wind does not drop, so the boat waits the max wait swinging downwind of its anchor
+        boat.ship_log.clear();
+        simulation.weather = Some(std::sync::Arc::new(ConstantWeather::new(PhysVec::new(15.0, 0.0), None)));
+        assert_eq!(simulation.validate().is_ok(), true, "Constant velocity may have weather for the anchor waits");
+        let sim_result = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        assert_eq!(sim_result.kpis.events.iter().any(|event| event.kind == SimulationEventKind::Anchored { wait: time::Duration::hours(5), moored: false }), true);
+        let swinging = boat.ship_log.iter().find(|entry| entry.navigation_status == Some(NavigationStatus::AtAnchor)).unwrap();
+        assert_eq!((Earth.distance(swinging.coordinates_current, geo::Point::new(0.1, 0.0)) - AnchorWait::DEFAULT_SWING_RADIUS).abs() < 1.0, true);
+        assert_eq!(swinging.coordinates_current.y() < 0.0, true, "Swings south of the anchor in a northerly wind");
+
+        // A wait for a time far away ends at the default max wait, or earlier at the max simulated duration
+        assert_eq!(AnchorWait::until(start_time + time::Duration::days(365)).max_wait, AnchorWait::DEFAULT_MAX_WAIT);
+        boat.route_plan.as_mut().unwrap().legs[0].anchor_wait = Some(AnchorWait::until(start_time + time::Duration::days(365)));
+        boat.ship_log.clear();
+        simulation.max_simulated_duration = Some(time::Duration::days(2));
+        let sim_result = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        assert_eq!(sim_result.status, SimulationStatus::MaxSimulatedDurationReached);
+        assert_eq!(boat.ship_log.last().unwrap().timestamp <= start_time + time::Duration::days(2) + simulation.time_step, true);
     }
-    let std_k: f64 = (sum_k / (k_vec_len-1.0)).sqrt();
 
-    // Return vmax
-    return Ok((mean_k, std_k));
-}
+    #[test]
+    fn port_database_test() {
+        let ports = PortDatabase::built_in();
+        assert_eq!(ports.get("ISREY").unwrap().name, "Reykjavik");
+        assert_eq!(ports.get("de ham").unwrap().locode, "DEHAM");
+        assert_eq!(ports.get("XXXXX"), None);
+        assert_eq!(ports.nearest(geo::Point::new(-22.0, 64.0)).unwrap().locode, "ISREY");
+
+        let route_plan = RoutePlan::between_ports("ISREY", "DEHAM", 200.0).unwrap();
+        assert_eq!(route_plan[0].p1, ports.get("ISREY").unwrap().location);
+        assert_eq!(route_plan.last().unwrap().p2, ports.get("DEHAM").unwrap().location);
+        assert_eq!(route_plan.len() > 1, true);
+        assert_eq!(RoutePlan::between_ports("ISREY", "XXXXX", 200.0).is_err(), true);
+
+        // Ports loaded from a file replace the built-in ports with the same UN/LOCODE
+        let csv_path = std::env::temp_dir().join("marine_vessel_simulator_port_database_test.csv");
+        let csv_path = csv_path.to_str().unwrap();
+        std::fs::write(csv_path, "locode;name;latitude;longitude\nIS HAF;Hafnarfjordur;64.070;-21.960\nISREY;Reykjavik old harbour;64.152;-21.940\n").unwrap();
+        let mut ports = PortDatabase::built_in();
+        ports.append(PortDatabase::from_csv(csv_path).unwrap());
+        assert_eq!(ports.get("ISHAF").unwrap().name, "Hafnarfjordur");
+        assert_eq!(ports.get("ISREY").unwrap().name, "Reykjavik old harbour");
+        assert_eq!(ports.route_plan("ISHAF", "ISREY", 0.0).unwrap().len(), 1);
+        std::fs::write(csv_path, "locode;name;latitude;longitude\nREY;Reykjavik;64.150;-21.950\n").unwrap();
+        match PortDatabase::from_csv(csv_path) {
+            Err(MarineSimError::CsvField { line, field, .. }) => {
+                assert_eq!(line, 2);
+                assert_eq!(field, "locode");
+            },
+            other => panic!("Expected a CsvField error, got {:?}", other.map(|ports| ports.ports.len())),
+        }
+    }
 
+    #[test]
+    fn traffic_lane_test() {
+        // Following the lane the heading is kept within the tolerance of the direction of traffic
+        let follow = TrafficLane::follow(45.0);
+        assert_eq!(follow.heading(50.0), 50.0);
+        assert_eq!(follow.heading(90.0), 60.0);
+        assert_eq!(follow.allows(55.0), true);
+        assert_eq!(follow.allows(225.0), false);
+
+        // Crossing the lane the heading is at right angles to the traffic, to the side of the bearing
+        let cross = TrafficLane::cross(0.0);
+        assert_eq!(cross.heading(80.0), 90.0);
+        assert_eq!(cross.heading(250.0), 270.0);
+        assert_eq!(cross.allows(100.0), true);
+        assert_eq!(cross.allows(45.0), false);
+
+        // A route plan eastwards along the equator follows a lane running east and crosses one running north
+        let mut route_plan = RoutePlan::new(vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.1, 0.0), 0.0, 0.0),
+            SailingLeg::new(geo::Point::new(0.1, 0.0), geo::Point::new(0.2, 0.0), 0.0, 0.0),
+        ]);
+        route_plan.legs[0].traffic_lane = Some(TrafficLane::follow(90.0));
+        route_plan.legs[1].traffic_lane = Some(TrafficLane::cross(0.0));
+        assert_eq!(route_plan.traffic_lane_violations().is_empty(), true);
+        // The return voyage sails against the traffic of the first lane
+        assert_eq!(route_plan.reversed().traffic_lane_violations(), vec![1]);
+
+        // Simulations refuse route plans that break the rules of their lanes
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let mut boat = Boat::new();
+        boat.velocity_mean = Some(5.0);
+        boat.route_plan = Some(route_plan.reversed());
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+        assert_eq!(sim_waypoint_mission(&mut boat, start_time, &simulation).is_err(), true);
+        boat.route_plan = Some(route_plan);
+        assert_eq!(sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap().is_completed(), true);
+    }
+
+    #[test]
+    fn ship_log_batch_test() {
+        // Writer that remembers the number of entries in each batch
+        struct BatchSizeWriter(std::sync::Arc<std::sync::Mutex<Vec<usize>>>);
+        impl ShipLogWriter for BatchSizeWriter {
+            fn write_ship_log(&mut self, ship_log: &[ShipLogEntry]) -> Result<(), io::Error> {
+                self.0.lock().unwrap().push(ship_log.len());
+                return Ok(());
+            }
+        }
 
+        // A voyage of about 740 time steps
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(2.0, 0.0), 0.0, 0.0)]));
+        boat.velocity_mean = Some(5.0);
+        let mut simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::minutes(1), 100000, None, None);
+        simulation.decimated_log_interval = Some(time::Duration::HOUR);
+        let mut boat_copy = boat.clone();
+        let result_copy = sim_waypoint_mission(&mut boat_copy, start_time, &simulation).unwrap();
+
+        // Streamed in batches of 50 entries, with the same results and decimated ship log
+        let batch_sizes = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        simulation.set_ship_log_writer(BatchSizeWriter(batch_sizes.clone()));
+        simulation.ship_log_batch_size = Some(50);
+        let result = sim_waypoint_mission(&mut boat, start_time, &simulation).unwrap();
+        assert_eq!(result, result_copy, "Streaming should not change the results");
+        assert_eq!(boat.ship_log.len(), 0);
+        assert_eq!(boat.decimated_ship_log.iter().map(|entry| entry.timestamp).collect::<Vec<UtcDateTime>>(), boat_copy.decimated_ship_log.iter().map(|entry| entry.timestamp).collect::<Vec<UtcDateTime>>());
+        let batch_sizes = batch_sizes.lock().unwrap().clone();
+        assert_eq!(batch_sizes.iter().sum::<usize>(), boat_copy.ship_log.len());
+        assert_eq!(batch_sizes.len() > 10, true);
+        assert_eq!(batch_sizes.iter().all(|size| *size <= 50), true);
+
+        // The ship log stays bounded while the voyage is logged
+        boat.voyage_log = Some(VoyageLog::new(&boat, &simulation));
+        for entry in &boat_copy.ship_log {
+            boat.push_ship_log_entry(entry.clone());
+            assert_eq!(boat.ship_log.len() <= 50, true, "The ship log has {} entries", boat.ship_log.len());
+        }
+        let kpis = boat.voyage_log.take().unwrap().finish(&mut boat);
+        assert_eq!((kpis.duration, kpis.distance), (result_copy.kpis.duration, result_copy.kpis.distance));
+    }
+    #[test]
+    fn empirical_speed_test() {
+        // Speeds are drawn from the same month and wind angle bucket, the conditions are dropped when nothing matches
+        let january = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let july = january + time::Duration::days(190);
+        let model = EmpiricalSpeedModel {
+            samples: vec![
+                EmpiricalSpeedSample { speed: 2.0, month: 1, wind_angle: Some(30.0) },
+                EmpiricalSpeedSample { speed: 6.0, month: 1, wind_angle: Some(150.0) },
+                EmpiricalSpeedSample { speed: 4.0, month: 7, wind_angle: None },
+            ],
+            by_month: true,
+            wind_angle_bucket_size: Some(90.0),
+        };
+        for _ in 0..20 {
+            assert_eq!(model.sample(january, Some(20.0)), 2.0);
+            assert_eq!(model.sample(january, Some(170.0)), 6.0);
+            assert_eq!(model.sample(july, Some(20.0)), 4.0, "No wind angle in July, only the month is used");
+            assert_eq!([2.0, 6.0].contains(&model.sample(january, None)), true);
+        }
 
-// Set up tests here
-//-----------------------------------------------------------------------------------
-#[cfg(test)]
-mod tests {
-    use super::*;
+        // The model from a ship log uses the logged speeds
+        let point = geo::Point::new(0.0, 0.0);
+        let ship_log = vec![ShipLogEntry::new(january, point, point, point, None, Some(PhysVec::new(3.0, 90.0)), None, None, None, None, None, None)];
+        assert_eq!(EmpiricalSpeedModel::from_ship_log(&ship_log, false, None).unwrap().samples[0].speed, 3.0);
+        assert_eq!(EmpiricalSpeedModel::from_ship_log(&Vec::new(), false, None).is_err(), true);
+
+        // Simulating with the model, sailing east in wind from the north draws the beam reach speed
+        let mut boat = Boat::new();
+        boat.route_plan = Some(RoutePlan::new(vec![SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.1, 0.0), 0.0, 0.0)]));
+        boat.empirical_speed_model = Some(EmpiricalSpeedModel {
+            samples: vec![
+                EmpiricalSpeedSample { speed: 2.0, month: 1, wind_angle: Some(45.0) },
+                EmpiricalSpeedSample { speed: 6.0, month: 1, wind_angle: Some(100.0) },
+            ],
+            by_month: false,
+            wind_angle_bucket_size: Some(90.0),
+        });
+        let mut simulation = Simulation::new(SimMethod::EmpiricalSpeed, vec![january], time::Duration::minutes(10), 1000, None, None);
+        simulation.weather = Some(std::sync::Arc::new(ConstantWeather::new(PhysVec::new(10.0, 0.0), None)));
+        let sim_result = sim_waypoint_mission(&mut boat, january, &simulation).unwrap();
+        assert_eq!(sim_result.is_completed(), true);
+        assert_eq!(boat.ship_log[1..].iter().all(|entry| entry.velocity.unwrap().magnitude == 6.0 && entry.wind.is_some()), true);
+
+        // A boat without a model can not be simulated
+        boat.empirical_speed_model = None;
+        assert_eq!(matches!(sim_waypoint_mission(&mut boat, january, &simulation), Err(MarineSimError::MissingBoatField(_))), true);
+    }
 
-    // Test get_min_point_to_great_circle_dist function
     #[test]
-    fn get_min_point_to_great_circle_dist_test() {
-        let tolerance = 1.0;
-        println!("Testing get_min_point_to_great_circle_dist function...");
-        println!("Tolerance: {} meter", tolerance);
-        println!("Earth radius: {} meters", geo::Haversine.radius());
-        // First test short distance on both sides of equator and close to both end points
-        let lon1 = 0.0;
-        let lat1 = 0.0;
-        let lon2 = 10.0;
-        let lat2 = 0.0;
-        let lon3 = 10.0;
-        let lat3 = 10.0;
-        let lon4 = 50.0;
-        let lat4 = -10.0;
-        let lon5 = 0.0;
-        let lat5 = 0.000001;
-        let lon6 = 10.0;
-        let lat6 = 0.000001;
-        let p1 = geo::Point::new(lon1, lat1);
-        let p2 = geo::Point::new(lon2, lat2);
-        let p3 = geo::Point::new(lon3, lat3);
-        let p4 = geo::Point::new(lon4, lat4);
-        let p5 = geo::Point::new(lon5, lat5);
-        let p6 = geo::Point::new(lon6, lat6);
-        let correct_dist = geo::Haversine.radius() * (lat3*2.0*std::f64::consts::PI/360.0); // 1111.950802335329128468111081452 kilometers
-        let dist = get_min_point_to_great_circle_dist(p1, p2, p3);
-        // Assert if dist is closer than the tolerance to the correct_dist
-        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
-        let dist = get_min_point_to_great_circle_dist(p1, p2, p4);
-        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
-        let correct_dist = geo::Haversine.radius() * (lat5*2.0*std::f64::consts::PI/360.0); // 1111.950802335329128468111081452 kilometers
-        let dist = get_min_point_to_great_circle_dist(p1, p2, p5);
-        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
-        let dist = get_min_point_to_great_circle_dist(p1, p2, p6);
-        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
-        
-        // Then test long distance from prime meridian
-        let lon1 = 0.0;
-        let lat1 = 89.0;
-        let lon2 = 0.0;
-        let lat2 = -89.0;
-        let lon3 = 0.0;
-        let lat3 = 0.0;
-        let lon4 = -50.0;
-        let lat4 = 0.0;
-        let lon5 = 0.0;
-        let lat5 = 89.000001;
-        let lon6 = 0.0;
-        let lat6 = -89.000001;
-        let p1 = geo::Point::new(lon1, lat1);
-        let p2 = geo::Point::new(lon2, lat2);
-        let p3 = geo::Point::new(lon3, lat3);
-        let p4 = geo::Point::new(lon4, lat4);
-        let p5 = geo::Point::new(lon5, lat5);
-        let p6 = geo::Point::new(lon6, lat6);
-        // Assert if dist is closer than the tolerance to the correct_dist
-        let correct_dist = 0.0;
-        let dist = get_min_point_to_great_circle_dist(p1, p2, p3);
-        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
-        // Assert if dist is closer than the tolerance to the correct_dist
-        let correct_dist = geo::Haversine.radius() * (lon4*2.0*std::f64::consts::PI/360.0).abs();
-        let dist = get_min_point_to_great_circle_dist(p1, p2, p4);
-        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
+    fn find_best_departure_times_test() {
+        // The vessel waits at anchor at the first waypoint until 20:00, so the later it departs the shorter the voyage
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let mut boat = Boat::new();
+        boat.velocity_mean = Some(5.0);
+        let mut route_plan = RoutePlan::new(vec![
+            SailingLeg::new(geo::Point::new(0.0, 0.0), geo::Point::new(0.1, 0.0), 0.0, 0.0),
+            SailingLeg::new(geo::Point::new(0.1, 0.0), geo::Point::new(0.2, 0.0), 0.0, 0.0),
+        ]);
+        route_plan.legs[0].anchor_wait = Some(AnchorWait::until(start_time + time::Duration::hours(20)));
+        boat.route_plan = Some(route_plan);
+        let simulation = Simulation::new(SimMethod::ConstVelocity, vec![start_time], time::Duration::hours(1), 1000, None, None);
+
+        let window_end = start_time + time::Duration::hours(6);
+        let departures = find_best_departure_times(&mut boat, &simulation, start_time, window_end, time::Duration::hours(3), DepartureObjective::MinDuration).unwrap();
+        assert_eq!(departures.iter().map(|(departure, _)| *departure).collect::<Vec<UtcDateTime>>(), vec![window_end, start_time + time::Duration::hours(3), start_time]);
+        assert_eq!(departures[0].1.duration < departures[2].1.duration, true);
+        assert_eq!(boat.ship_log.is_empty(), false, "The ship logs of the voyages are added to the boat");
+
+        // Without wind or waves in the ship log all voyages score the same and keep the order of departure
+        for objective in [DepartureObjective::MinMaxWindSpeed, DepartureObjective::MinMaxWaveHeight, DepartureObjective::MinTimeAboveWaveHeight(2.0)] {
+            let departures = find_best_departure_times(&mut boat, &simulation, start_time, window_end, time::Duration::hours(3), objective).unwrap();
+            assert_eq!(departures[0].0, start_time, "Objective: {:?}", objective);
+        }
 
-        let correct_dist = geo::Haversine.radius() * ((lat5-lat1)*2.0*std::f64::consts::PI/360.0).abs();
-        let dist = get_min_point_to_great_circle_dist(p1, p2, p5);
-        // Assert if dist is closer than the tolerance to the correct_dist
-        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
-        let correct_dist = geo::Haversine.radius() * ((lat6-lat2)*2.0*std::f64::consts::PI/360.0).abs();
-        let dist = get_min_point_to_great_circle_dist(p1, p2, p6);
-        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
-        
-        // Test for edge cases where p1 or p2 and p3 are the same
-        let correct_dist = 0.0;
-        let dist = get_min_point_to_great_circle_dist(p1, p2, p1);
-        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0);
-        let dist = get_min_point_to_great_circle_dist(p1, p2, p2);
-        assert_eq!((correct_dist-dist).abs() <= tolerance, true, "Correct distance: {:.2} km, calculated distance: {:.2} km", correct_dist/1000.0, dist/1000.0); 
+        // Invalid windows
+        assert_eq!(find_best_departure_times(&mut boat, &simulation, start_time, window_end, time::Duration::ZERO, DepartureObjective::MinDuration).is_err(), true);
+        assert_eq!(find_best_departure_times(&mut boat, &simulation, window_end, start_time, time::Duration::hours(1), DepartureObjective::MinDuration).is_err(), true);
+
+        // The wave objectives score the waves of the voyage, three hours with waves above 2 m and a maximum of 3.5 m
+        let p1 = geo::Point::new(0.0, 0.0);
+        let p2 = geo::Point::new(1.0, 0.0);
+        let mut ship_log: Vec<ShipLogEntry> = (0..=5).map(|i| ShipLogEntry::new(start_time + time::Duration::hours(i), p1, p1, p2, None, None, None, None, None, None, None, None)).collect();
+        for (entry, wave_height) in ship_log.iter_mut().zip([1.0, 1.5, 2.5, 3.5, 3.0, 1.0]) {
+            entry.wave_height = Some(wave_height);
+        }
+        let kpis = VoyageKpis::from_ship_log(&ship_log);
+        assert_eq!(get_departure_score(DepartureObjective::MinMaxWaveHeight, &kpis, &ship_log), 3.5);
+        assert_eq!(get_departure_score(DepartureObjective::MinTimeAboveWaveHeight(2.0), &kpis, &ship_log), 3.0*3600.0);
+        assert_eq!(get_departure_score(DepartureObjective::MinTimeAboveWaveHeight(4.0), &kpis, &ship_log), 0.0);
+        assert_eq!(get_departure_score(DepartureObjective::MinDuration, &kpis, &ship_log), 5.0*3600.0);
+    }
+
+    #[test]
+    fn cached_weather_test() {
+        // Counts how often the weather is fetched
+        #[derive(Debug)]
+        struct CountingWeather {
+            calls: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+        }
+        impl WeatherProvider for CountingWeather {
+            fn wind(&self, timestamp: UtcDateTime, location: geo::Point) -> Result<PhysVec, io::Error> {
+                self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                return Ok(PhysVec::new(location.y(), (timestamp.unix_timestamp()/3600) as f64));
+            }
+            fn ocean_current(&self, _timestamp: UtcDateTime, _location: geo::Point) -> Result<Option<PhysVec>, io::Error> {
+                self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                return Ok(None);
+            }
+        }
+
+        let num_calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let weather = CachedWeather::new(Box::new(CountingWeather { calls: num_calls.clone() }), time::Duration::hours(1), 0.5);
+        let calls = || num_calls.load(std::sync::atomic::Ordering::SeqCst);
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+
+        // The weather is fetched at the rounded time and location, once per cell of the cache
+        let wind = weather.wind(start_time + time::Duration::minutes(20), geo::Point::new(0.1, 1.1)).unwrap();
+        assert_eq!(wind, PhysVec::new(1.0, 0.0));
+        assert_eq!(calls(), 1);
+        assert_eq!(weather.wind(start_time + time::Duration::minutes(-20), geo::Point::new(-0.1, 0.9)).unwrap(), wind);
+        assert_eq!(calls(), 1, "The same cell should come from the cache");
+        assert_eq!(weather.wind(start_time + time::Duration::minutes(40), geo::Point::new(0.1, 1.1)).unwrap(), PhysVec::new(1.0, 1.0));
+        assert_eq!(calls(), 2, "The next hour is a new cell");
+
+        // Unknown ocean currents are cached too
+        assert_eq!(weather.ocean_current(start_time, geo::Point::new(0.0, 0.0)).unwrap(), None);
+        assert_eq!(weather.ocean_current(start_time, geo::Point::new(0.0, 0.0)).unwrap(), None);
+        assert_eq!(calls(), 3);
+
+        // Clearing the cache fetches the weather again
+        weather.clear();
+        weather.wind(start_time, geo::Point::new(0.0, 1.0)).unwrap();
+        assert_eq!(calls(), 4);
     }
-}
\ No newline at end of file
+
+    #[test]
+    #[cfg(feature = "copernicus")]
+    fn wind_against_current_test() {
+        // Wind from the north against a 2 m/s current flowing north
+        let weather = ConstantWeather::new(PhysVec::new(12.0, 0.0), Some(PhysVec::new(2.0, 0.0)));
+        let start_time = UtcDateTime::from_unix_timestamp(0).unwrap();
+        let location = geo::Point::new(-70.0, 38.0);
+        let wind = weather.wind(start_time, location).unwrap();
+        let ocean_current = weather.ocean_current(start_time, location).unwrap().unwrap();
+        let settings = WindAgainstCurrent::default();
+        assert_eq!((WindAgainstCurrent::current_against_wind(wind, ocean_current) - 2.0).abs() < 1e-9, true);
+        assert_eq!((settings.amplification(wind, ocean_current).unwrap() - 1.6).abs() < 1e-9, true);
+        // Current flowing with the wind, too little current and too little wind
+        assert_eq!(settings.amplification(wind, PhysVec::new(2.0, 180.0)), None);
+        assert_eq!(settings.amplification(wind, PhysVec::new(0.5, 0.0)), None);
+        assert_eq!(settings.amplification(PhysVec::new(5.0, 0.0), ocean_current), None);
+
+        let mut boat = Boat::new();
+        boat.location = Some(location);
+        boat.heading = Some(90.0);
+        boat.wind_velocity_multiplier = Some(0.5);
+        let mut simulation = Simulation::new(SimMethod::WeatherDataFromCopernicus, vec![start_time], time::Duration::hours(1), 10, None, None);
+        simulation.weather = Some(std::sync::Arc::new(weather));
+        let mut in_wind_against_current = false;
+        // Without the settings there is no amplification
+        assert_eq!(update_wind_against_current(&mut boat, &simulation, wind, ocean_current, start_time, &mut in_wind_against_current), 1.0);
+        assert_eq!(boat.events.len(), 0);
+
+        // The event fires once when the condition starts
+        simulation.wind_against_current = Some(settings);
+        for hour in 0..3 {
+            let amplification = update_wind_against_current(&mut boat, &simulation, wind, ocean_current, start_time + time::Duration::hours(hour), &mut in_wind_against_current);
+            assert_eq!((amplification - 1.6).abs() < 1e-9, true, "Amplification: {}", amplification);
+        }
+        assert_eq!(boat.events.len(), 1);
+        match &boat.events[0].kind {
+            SimulationEventKind::WindAgainstCurrent { wind_speed, current_against_wind, amplification } => {
+                assert_eq!(*wind_speed, 12.0);
+                assert_eq!((current_against_wind - 2.0).abs() < 1e-9, true);
+                assert_eq!((amplification - 1.6).abs() < 1e-9, true);
+            },
+            kind => panic!("Expected a wind against current event, got {:?}", kind),
+        }
+        assert_eq!(boat.events[0].timestamp, start_time);
+
+        // And again when it starts after it has ended
+        assert_eq!(update_wind_against_current(&mut boat, &simulation, PhysVec::new(5.0, 0.0), ocean_current, start_time + time::Duration::hours(3), &mut in_wind_against_current), 1.0);
+        assert_eq!(in_wind_against_current, false);
+        update_wind_against_current(&mut boat, &simulation, wind, ocean_current, start_time + time::Duration::hours(4), &mut in_wind_against_current);
+        assert_eq!(boat.events.len(), 2);
+        assert_eq!(boat.events[1].timestamp, start_time + time::Duration::hours(4));
+
+        // The speed through water is reduced by the amplification, the current is not
+        let normal = get_sailing_velocity(&boat, wind, ocean_current, false, 1.0);
+        let amplified = get_sailing_velocity(&boat, wind, ocean_current, false, 1.6);
+        let through_water = (normal - ocean_current).magnitude;
+        assert_eq!((through_water - 6.0).abs() < 1e-9, true, "Speed through water: {}", through_water);
+        assert_eq!(((amplified - ocean_current).magnitude - through_water/1.6).abs() < 1e-9, true);
+        assert_eq!(amplified.magnitude < normal.magnitude, true);
+    }
+
+    #[test]
+    fn route_plan_generators_test() {
+        // About 111 km along the equator, split into legs of at most 20 km
+        let p1 = geo::Point::new(0.0, 0.0);
+        let p2 = geo::Point::new(1.0, 0.0);
+        let total_dist = Earth.distance(p1, p2);
+        for (name, route_plan) in [("great circle", RoutePlan::great_circle(p1, p2, 20.0)), ("rhumb line", RoutePlan::rhumb_line(p1, p2, 20.0))] {
+            assert_eq!(route_plan.legs.len(), 6, "{}", name);
+            // The route starts and ends exactly at the end points and the legs are connected
+            assert_eq!(route_plan.legs[0].p1, p1, "{}", name);
+            assert_eq!(route_plan.legs.last().unwrap().p2, p2, "{}", name);
+            for pair in route_plan.legs.windows(2) {
+                assert_eq!(pair[0].p2, pair[1].p1, "{}", name);
+            }
+            // The waypoints are evenly spaced and no leg is longer than the spacing
+            for leg in &route_plan.legs {
+                assert_eq!(leg.length() <= 20000.0, true, "{}: {} m", name, leg.length());
+                assert_eq!((leg.length() - total_dist/6.0).abs() < 1.0, true, "{}: {} m", name, leg.length());
+            }
+        }
+        // A spacing that is not positive or longer than the route gives a single leg
+        assert_eq!(RoutePlan::great_circle(p1, p2, 0.0).legs.len(), 1);
+        assert_eq!(RoutePlan::rhumb_line(p1, p2, -5.0).legs.len(), 1);
+        assert_eq!(RoutePlan::great_circle(p1, p2, 500.0).legs.len(), 1);
+
+        // The rhumb line keeps a constant bearing, the great circle from Reykjavik to New York does not
+        let reykjavik: geo::Point = geo::Point::new(-21.94, 64.15);
+        let new_york: geo::Point = geo::Point::new(-74.0, 40.7);
+        let bearing: f64 = Rhumb.bearing(reykjavik, new_york);
+        for leg in &RoutePlan::rhumb_line(reykjavik, new_york, 200.0).legs[..] {
+            assert_eq!((Rhumb.bearing(reykjavik, leg.p2) - bearing).abs() < 1e-6, true);
+        }
+        let great_circle = RoutePlan::great_circle(reykjavik, new_york, 200.0);
+        assert_eq!(great_circle.legs.len() > 1, true);
+        assert_eq!((Rhumb.bearing(reykjavik, great_circle.legs[0].p2) - bearing).abs() > 1.0, true);
+
+        // Crossing the antimeridian near Fiji, about 213 km, the route goes the short way and not around the world
+        let p1: geo::Point = geo::Point::new(179.0, -17.0);
+        let p2: geo::Point = geo::Point::new(-179.0, -17.0);
+        for (name, route_plan) in [("great circle", RoutePlan::great_circle(p1, p2, 50.0)), ("rhumb line", RoutePlan::rhumb_line(p1, p2, 50.0))] {
+            assert_eq!(route_plan.legs.len(), 5, "{}", name);
+            assert_eq!(route_plan.legs[0].p1, p1, "{}", name);
+            assert_eq!(route_plan.legs.last().unwrap().p2, p2, "{}", name);
+            for leg in &route_plan.legs {
+                assert_eq!(leg.length() <= 50000.0, true, "{}: {} m", name, leg.length());
+                assert_eq!(leg.p2.x().abs() >= 179.0 - 1e-9 && leg.p2.x().abs() <= 180.0, true, "{}: {:?}", name, leg.p2);
+            }
+            // Exactly one leg crosses from east to west longitude
+            assert_eq!(route_plan.legs.iter().filter(|leg| leg.p1.x() > 0.0 && leg.p2.x() < 0.0).count(), 1, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+/// NMEA 0183 export for the Marine vessel simulator.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// Emits a simulated voyage as NMEA 0183 sentences so it can be fed into chart plotter software, e.g. OpenCPN, and autopilot test benches.
+/// Each ship log entry becomes the sentences:
+/// - RMC: Recommended minimum data, time, position, speed and course over ground
+/// - VHW: Heading and speed through water
+/// - MWV: True wind speed and angle relative to the bow
+/// - HDT: True heading
+/// Sentences are left out if the ship log entry does not have the data for them, e.g. MWV without wind
+/// See: <https://gpsd.gitlab.io/gpsd/NMEA.html>
+
+use crate::*;   // To use everything from the crate
+use std::io::Write;
+
+/// Knots per meter per second
+const KNOTS_PER_MPS: f64 = 3600.0/1852.0;
+
+/// Returns the NMEA 0183 sentences of a ship log entry, RMC, VHW, MWV and HDT in that order, each with its checksum and without line endings
+/// The speed over ground is the velocity of the entry, or the speed from the previous entry if the entry has no velocity
+/// The speed through water in VHW is taken as the velocity of the entry since ship logs do not have the ocean current
+/// previous: The previous entry of the ship log, None for the first entry
+/// # Example:
+/// `for sentence in get_nmea_sentences(&boat.ship_log[1], Some(&boat.ship_log[0])) { println!("{}", sentence); }`
+pub fn get_nmea_sentences(entry: &ShipLogEntry, previous: Option<&ShipLogEntry>) -> Vec<String> {
+    let mut sentences: Vec<String> = Vec::with_capacity(4);
+
+    // Speed and course over ground, unit [m/s] and [°]
+    let (speed_over_ground, course_over_ground): (Option<f64>, Option<f64>) = match (entry.velocity, previous) {
+        (Some(velocity), _) => (Some(velocity.magnitude), Some(velocity.angle)),
+        (None, Some(previous)) => {
+            let seconds = (entry.timestamp - previous.timestamp).as_seconds_f64();
+            let speed = if seconds > 0.0 { Some(Earth.distance(previous.coordinates_current, entry.coordinates_current) / seconds) } else { None };
+            (speed, entry.track_angle.or(Some(Earth.bearing(previous.coordinates_current, entry.coordinates_current))))
+        },
+        (None, None) => (None, entry.track_angle),
+    };
+    let heading: Option<f64> = entry.heading.map(|heading| heading.rem_euclid(360.0));
+
+    // RMC, always
+    let latitude = entry.coordinates_current.y();
+    let longitude = entry.coordinates_current.x();
+    sentences.push(get_nmea_sentence(&format!("GPRMC,{:02}{:02}{:02}.00,A,{},{},{},{},{},{},{:02}{:02}{:02},,,A",
+        entry.timestamp.hour(), entry.timestamp.minute(), entry.timestamp.second(),
+        format_nmea_coordinate(latitude.abs(), 2), if latitude < 0.0 { "S" } else { "N" },
+        format_nmea_coordinate(longitude.abs(), 3), if longitude < 0.0 { "W" } else { "E" },
+        speed_over_ground.map_or(String::new(), |speed| format!("{:.1}", speed*KNOTS_PER_MPS)),
+        course_over_ground.map_or(String::new(), |course| format!("{:.1}", course.rem_euclid(360.0))),
+        entry.timestamp.day(), entry.timestamp.month() as u8, entry.timestamp.year().rem_euclid(100))));
+
+    // VHW, if the heading or velocity is known
+    let speed_through_water: Option<f64> = entry.velocity.map(|velocity| velocity.magnitude);
+    if heading.is_some() || speed_through_water.is_some() {
+        sentences.push(get_nmea_sentence(&format!("IIVHW,{},T,,M,{},N,{},K",
+            heading.map_or(String::new(), |heading| format!("{:.1}", heading)),
+            speed_through_water.map_or(String::new(), |speed| format!("{:.1}", speed*KNOTS_PER_MPS)),
+            speed_through_water.map_or(String::new(), |speed| format!("{:.1}", speed*3.6)))));
+    }
+
+    // MWV, if the wind and heading are known. The wind angle is where the wind comes from, clockwise from the bow
+    if let (Some(wind), Some(heading)) = (entry.wind, heading) {
+        sentences.push(get_nmea_sentence(&format!("IIMWV,{:.1},T,{:.1},N,A", (wind.angle - heading).rem_euclid(360.0), wind.magnitude*KNOTS_PER_MPS)));
+    }
+
+    // HDT, if the heading is known
+    if let Some(heading) = heading {
+        sentences.push(get_nmea_sentence(&format!("IIHDT,{:.1},T", heading)));
+    }
+
+    return sentences;
+}
+
+/// Adds the start delimiter and checksum to the fields of an NMEA 0183 sentence, e.g. "IIHDT,90.0,T" becomes "$IIHDT,90.0,T*1B"
+pub fn get_nmea_sentence(fields: &str) -> String {
+    return format!("${}*{:02X}", fields, get_nmea_checksum(fields));
+}
+
+/// Returns the checksum of the fields of an NMEA 0183 sentence, the XOR of all the characters between the start delimiter ($ or !) and *
+pub fn get_nmea_checksum(fields: &str) -> u8 {
+    return fields.bytes().fold(0, |checksum, byte| checksum ^ byte);
+}
+
+/// Formats a latitude or longitude in degrees as degrees and decimal minutes, e.g. 64.5 becomes 6430.0000 for a latitude
+/// degree_digits: Number of digits for the degrees, 2 for latitude and 3 for longitude
+fn format_nmea_coordinate(degrees: f64, degree_digits: usize) -> String {
+    let mut whole_degrees = degrees.trunc();
+    let mut minutes = (degrees - whole_degrees)*60.0;
+    // Rounding the minutes to 4 decimals could give 60.0000
+    if (minutes*10000.0).round() >= 600000.0 {
+        whole_degrees += 1.0;
+        minutes = 0.0;
+    }
+    return format!("{:0width$}{:07.4}", whole_degrees as u32, minutes, width = degree_digits);
+}
+
+/// Writes a ship log as a stream of NMEA 0183 sentences, see get_nmea_sentences(). Each sentence ends with CR LF as in the standard
+/// writer: Where the sentences are written, e.g. a file or a TCP stream
+/// time_scale: If set, the sentences of each entry are written in real time divided by the time scale, e.g. 60.0 plays an hour of the voyage in a minute. If None, all the sentences are written at once
+/// # Example:
+/// `let (mut stream, _) = std::net::TcpListener::bind("0.0.0.0:10110")?.accept()?;`
+/// `write_ship_log_nmea(&mut stream, &boat.ship_log, Some(60.0))?;`
+pub fn write_ship_log_nmea<W: Write>(writer: &mut W, ship_log: &[ShipLogEntry], time_scale: Option<f64>) -> Result<(), MarineSimError> {
+    if let Some(time_scale) = time_scale {
+        if !(time_scale > 0.0) {
+            return Err(MarineSimError::InvalidInput("The time scale must be positive".to_string()));
+        }
+    }
+
+    for i in 0..ship_log.len() {
+        let previous = if i > 0 { Some(&ship_log[i-1]) } else { None };
+        // Wait until the time of the entry
+        if let (Some(time_scale), Some(previous)) = (time_scale, previous) {
+            let seconds = (ship_log[i].timestamp - previous.timestamp).as_seconds_f64() / time_scale;
+            if seconds > 0.0 {
+                std::thread::sleep(std::time::Duration::from_secs_f64(seconds));
+            }
+        }
+        for sentence in get_nmea_sentences(&ship_log[i], previous) {
+            writer.write_all(sentence.as_bytes())?;
+            writer.write_all(b"\r\n")?;
+        }
+        writer.flush()?;
+    }
+    return Ok(());
+}
+
+/// Writes a ship log to a file of NMEA 0183 sentences, see write_ship_log_nmea()
+/// Will overwrite any file with the same file name at file_path
+/// # Example:
+/// `ship_log_to_nmea_file("voyage.nmea", &boat.ship_log)?;`
+pub fn ship_log_to_nmea_file(file_path: &str, ship_log: &[ShipLogEntry]) -> Result<(), MarineSimError> {
+    let mut writer = io::BufWriter::new(std::fs::File::create(file_path)?);
+    return write_ship_log_nmea(&mut writer, ship_log, None);
+}
+
+/// Serves a ship log as a timed stream of NMEA 0183 sentences over TCP, see write_ship_log_nmea()
+/// Waits for one client to connect to the address, e.g. a chart plotter set up with a TCP connection to the address, then streams the ship log to it and returns
+/// address: The address to listen on, e.g. "0.0.0.0:10110". Port 10110 is the port registered for NMEA 0183 over TCP
+/// # Example:
+/// `serve_ship_log_nmea("0.0.0.0:10110", &boat.ship_log, Some(60.0))?;`
+pub fn serve_ship_log_nmea(address: &str, ship_log: &[ShipLogEntry], time_scale: Option<f64>) -> Result<(), MarineSimError> {
+    let listener = std::net::TcpListener::bind(address)?;
+    let (mut stream, _) = listener.accept()?;
+    return write_ship_log_nmea(&mut stream, ship_log, time_scale);
+}
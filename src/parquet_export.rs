@@ -0,0 +1,156 @@
+/// Parquet export for the Marine vessel simulator, only with the "parquet" feature.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// Writes ship logs, including the weather at each step, to Parquet files with typed columns, so multi-year and multi-boat simulations with millions of rows can be read quickly with pandas, polars or DuckDB.
+/// The columns are the same as in ship_logs_to_csv() with a name column in front so ship logs of many boats or departures can go in one file, and the coordinates split into latitude and longitude columns.
+/// Timestamps are stored as UTC timestamps in milliseconds, optional values that are None are stored as nulls. See ShipLogParquetWriter, which can also stream the ship logs of a simulation, see Simulation.set_ship_log_writer()
+
+use crate::*;   // To use everything from the crate
+use std::sync::Arc;
+use arrow::array::{ArrayRef, BooleanArray, Float64Array, StringArray, TimestampMillisecondArray, UInt8Array};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+
+/// Returns the schema of the ship log Parquet files, see ShipLogParquetWriter
+pub fn get_ship_log_parquet_schema() -> Schema {
+    let float_column = |name: &str| Field::new(name, DataType::Float64, true);
+    return Schema::new(vec![
+        Field::new("name", DataType::Utf8, false),
+        Field::new("timestamp", DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())), false),
+        Field::new("latitude_initial[°]", DataType::Float64, false),
+        Field::new("longitude_initial[°]", DataType::Float64, false),
+        Field::new("latitude[°]", DataType::Float64, false),
+        Field::new("longitude[°]", DataType::Float64, false),
+        Field::new("latitude_final[°]", DataType::Float64, false),
+        Field::new("longitude_final[°]", DataType::Float64, false),
+        float_column("cargo_on_board[ton]"),
+        float_column("velocity[m/s]"),
+        float_column("velocity_angle[°]"),
+        float_column("course[°]"),
+        float_column("heading[°]"),
+        float_column("track_angle[°]"),
+        float_column("true_bearing[°]"),
+        float_column("draught[m]"),
+        Field::new("navigation_status", DataType::UInt8, true),
+        float_column("depth[m]"),
+        float_column("wind_speed[m/s]"),
+        float_column("wind_angle[°]"),
+        float_column("wave_height[m]"),
+        float_column("cross_track_error[m]"),
+        Field::new("foiling", DataType::Boolean, true),
+    ]);
+}
+
+/// Writes ship logs to a Parquet file a batch at a time, so the whole output does not have to be in memory at once
+/// Each call to write() becomes at least one row group in the file. The file is not valid until close() is called
+/// # Example:
+/// `let mut writer = ShipLogParquetWriter::create("ship_logs.parquet")?;`
+/// `for (i, result) in results.iter().enumerate() { writer.write(&format!("departure {}", i), &result.ship_log)?; }`
+/// `writer.close()?;`
+pub struct ShipLogParquetWriter {
+    writer: ArrowWriter<File>,
+    schema: Arc<Schema>,
+    file_path: String,
+}
+
+impl ShipLogParquetWriter {
+    /// Creates a Parquet file for ship logs, compressed with Snappy
+    /// Will overwrite any file with the same file name at file_path. file_path must end with ".parquet"
+    pub fn create(file_path: &str) -> Result<ShipLogParquetWriter, MarineSimError> {
+        if !check_file_extension(file_path, ".parquet") {
+            return Err(MarineSimError::InvalidInput("The filepath must end with \".parquet\"".to_string()));
+        }
+        let schema = Arc::new(get_ship_log_parquet_schema());
+        let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+        let writer = ArrowWriter::try_new(File::create(file_path)?, schema.clone(), Some(properties)).map_err(|e| parquet_error(file_path, e))?;
+        return Ok(ShipLogParquetWriter {
+            writer,
+            schema,
+            file_path: file_path.to_string(),
+        });
+    }
+
+    /// Writes the entries of a ship log
+    /// name: Written in the name column of every entry, e.g. the name of the boat or the departure
+    pub fn write(&mut self, name: &str, ship_log: &[ShipLogEntry]) -> Result<(), MarineSimError> {
+        if ship_log.is_empty() {
+            return Ok(());
+        }
+        let float_column = |value: fn(&ShipLogEntry) -> Option<f64>| -> ArrayRef { Arc::new(ship_log.iter().map(value).collect::<Float64Array>()) };
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(StringArray::from(vec![name; ship_log.len()])),
+            Arc::new(TimestampMillisecondArray::from(ship_log.iter().map(|entry| (entry.timestamp.unix_timestamp_nanos() / 1_000_000) as i64).collect::<Vec<i64>>()).with_timezone("UTC")),
+            float_column(|entry| Some(entry.coordinates_initial.y())),
+            float_column(|entry| Some(entry.coordinates_initial.x())),
+            float_column(|entry| Some(entry.coordinates_current.y())),
+            float_column(|entry| Some(entry.coordinates_current.x())),
+            float_column(|entry| Some(entry.coordinates_final.y())),
+            float_column(|entry| Some(entry.coordinates_final.x())),
+            float_column(|entry| entry.cargo_on_board.map(|cargo| cargo.get::<uom::si::mass::ton>())),
+            float_column(|entry| entry.velocity.map(|velocity| velocity.magnitude)),
+            float_column(|entry| entry.velocity.map(|velocity| velocity.angle)),
+            float_column(|entry| entry.course),
+            float_column(|entry| entry.heading),
+            float_column(|entry| entry.track_angle),
+            float_column(|entry| entry.true_bearing),
+            float_column(|entry| entry.draft),
+            Arc::new(ship_log.iter().map(|entry| entry.navigation_status.map(|status| status as u8)).collect::<UInt8Array>()),
+            float_column(|entry| entry.depth),
+            float_column(|entry| entry.wind.map(|wind| wind.magnitude)),
+            float_column(|entry| entry.wind.map(|wind| wind.angle)),
+            float_column(|entry| entry.wave_height),
+            float_column(|entry| entry.cross_track_error),
+            Arc::new(ship_log.iter().map(|entry| entry.foiling).collect::<BooleanArray>()),
+        ];
+        let batch = RecordBatch::try_new(self.schema.clone(), columns).map_err(|e| MarineSimError::Io(io::Error::new(io::ErrorKind::InvalidData, format!("Could not make a record batch for {}: {}", self.file_path, e))))?;
+        self.writer.write(&batch).map_err(|e| parquet_error(&self.file_path, e))?;
+        // Write the row group so the entries do not stay in memory
+        return self.writer.flush().map_err(|e| parquet_error(&self.file_path, e));
+    }
+
+    /// Writes the footer of the Parquet file and closes it
+    pub fn close(self) -> Result<(), MarineSimError> {
+        let file_path = self.file_path;
+        self.writer.close().map_err(|e| parquet_error(&file_path, e))?;
+        return Ok(());
+    }
+}
+
+/// So a simulation can stream its ship logs to a Parquet file, see Simulation.set_ship_log_writer()
+/// The name of each voyage is its departure time, e.g. "2025-05-01T00:00:00Z". The file is closed by finish(), do not call close() afterwards
+impl ShipLogWriter for ShipLogParquetWriter {
+    fn write_ship_log(&mut self, ship_log: &[ShipLogEntry]) -> Result<(), io::Error> {
+        let name = match ship_log.first() {
+            Some(entry) => entry.timestamp.format(&time::format_description::well_known::Rfc3339).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Could not format timestamp {}: {}", entry.timestamp, e)))?,
+            None => return Ok(()),
+        };
+        self.write(&name, ship_log)?;
+        return Ok(());
+    }
+
+    fn finish(&mut self) -> Result<(), io::Error> {
+        self.writer.finish().map_err(|e| parquet_error(&self.file_path, e))?;
+        return Ok(());
+    }
+}
+
+/// Writes ship logs to a Parquet file, see ShipLogParquetWriter
+/// Will overwrite any file with the same file name at file_path. file_path must end with ".parquet"
+/// ship_logs: The name and ship log of each boat or departure
+/// # Example:
+/// `ship_logs_to_parquet("ship_logs.parquet", &[("Sailing cargo ship", &boat.ship_log)])?;`
+pub fn ship_logs_to_parquet(file_path: &str, ship_logs: &[(&str, &[ShipLogEntry])]) -> Result<(), MarineSimError> {
+    let mut writer = ShipLogParquetWriter::create(file_path)?;
+    for (name, ship_log) in ship_logs {
+        writer.write(name, ship_log)?;
+    }
+    return writer.close();
+}
+
+/// Makes a MarineSimError from a Parquet error
+fn parquet_error(file_path: &str, e: parquet::errors::ParquetError) -> MarineSimError {
+    return MarineSimError::Io(io::Error::other(format!("Could not write Parquet file {}: {}", file_path, e)));
+}
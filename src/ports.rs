@@ -0,0 +1,189 @@
+/// Port database for the Marine vessel simulator.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// Ports are looked up by their UN/LOCODE, e.g. "ISREY" for Reykjavik or "DEHAM" for Hamburg, so studies can name their ports instead of typing coordinates, see PortDatabase and RoutePlan::between_ports().
+/// A small database of major ports is built in, larger ones can be loaded from CSV files. See: <https://unece.org/trade/cefact/unlocode-code-list-country-and-territory>
+/// Note: The coordinates of the built-in ports are approximate positions in or off each port, they are NOT meant for navigation.
+
+use crate::*;   // To use everything from the crate
+
+/// The built-in ports: (UN/LOCODE, name, latitude, longitude)
+const BUILT_IN_PORTS: &[(&str, &str, f64, f64)] = &[
+    ("AUSYD", "Sydney", -33.860, 151.200),
+    ("BEANR", "Antwerp", 51.263, 4.400),
+    ("BRSSZ", "Santos", -23.960, -46.300),
+    ("CAHAL", "Halifax", 44.640, -63.560),
+    ("CNSHA", "Shanghai", 30.620, 122.070),
+    ("DEHAM", "Hamburg", 53.545, 9.967),
+    ("DKAAR", "Aarhus", 56.150, 10.230),
+    ("DKCPH", "Copenhagen", 55.700, 12.600),
+    ("EGPSD", "Port Said", 31.260, 32.300),
+    ("ESALG", "Algeciras", 36.130, -5.430),
+    ("ESBCN", "Barcelona", 41.350, 2.160),
+    ("ESLPA", "Las Palmas", 28.140, -15.410),
+    ("ESVLC", "Valencia", 39.440, -0.320),
+    ("FOTHO", "Torshavn", 62.005, -6.770),
+    ("FRLEH", "Le Havre", 49.480, 0.100),
+    ("FRMRS", "Marseille", 43.330, 5.340),
+    ("GBFXT", "Felixstowe", 51.955, 1.305),
+    ("GBLON", "London", 51.505, 0.050),
+    ("GBSOU", "Southampton", 50.900, -1.400),
+    ("GLGOH", "Nuuk", 64.170, -51.740),
+    ("GRPIR", "Piraeus", 37.940, 23.620),
+    ("HKHKG", "Hong Kong", 22.300, 114.170),
+    ("IEDUB", "Dublin", 53.345, -6.200),
+    ("ISREY", "Reykjavik", 64.150, -21.950),
+    ("ITGOA", "Genoa", 44.400, 8.920),
+    ("JPTYO", "Tokyo", 35.620, 139.780),
+    ("LTKLJ", "Klaipeda", 55.720, 21.100),
+    ("LVRIX", "Riga", 57.050, 24.030),
+    ("MTMAR", "Marsaxlokk", 35.820, 14.540),
+    ("NLRTM", "Rotterdam", 51.950, 4.050),
+    ("NOBGO", "Bergen", 60.400, 5.310),
+    ("NOOSL", "Oslo", 59.900, 10.740),
+    ("PLGDN", "Gdansk", 54.400, 18.670),
+    ("PTFNC", "Funchal", 32.640, -16.910),
+    ("PTLIS", "Lisbon", 38.700, -9.150),
+    ("SEGOT", "Gothenburg", 57.690, 11.880),
+    ("SENYN", "Nynashamn", 58.900, 17.950),
+    ("SGSIN", "Singapore", 1.260, 103.840),
+    ("USBOS", "Boston", 42.350, -71.040),
+    ("USLAX", "Los Angeles", 33.730, -118.260),
+    ("USNYC", "New York", 40.670, -74.040),
+    ("ZACPT", "Cape Town", -33.910, 18.430),
+];
+
+/// Struct for a port
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Port {
+    /// UN/LOCODE of the port, 2 letter country code and 3 character location code without a space, e.g. "ISREY"
+    pub locode: String,
+    pub name: String,
+    /// Position of the port, x is longitude and y is latitude
+    pub location: geo::Point,
+}
+
+impl Port {
+    /// Creates a new port, the UN/LOCODE is written in capitals without spaces, e.g. "is rey" becomes "ISREY"
+    pub fn new(locode: &str, name: &str, location: geo::Point) -> Port {
+        Port {
+            locode: normalize_locode(locode),
+            name: name.to_string(),
+            location,
+        }
+    }
+}
+
+/// Database of ports that can be looked up by UN/LOCODE, see Port
+/// # Example:
+/// `let mut ports = PortDatabase::built_in();`
+/// `ports.append(PortDatabase::from_csv("my_ports.csv")?);`
+/// `let route_plan = ports.route_plan("ISREY", "DEHAM", 200.0)?;`
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortDatabase {
+    pub ports: Vec<Port>,
+}
+
+impl PortDatabase {
+    /// Creates an empty port database
+    pub fn new() -> PortDatabase {
+        PortDatabase {
+            ports: Vec::new(),
+        }
+    }
+
+    /// Returns the database of the built-in major ports
+    pub fn built_in() -> PortDatabase {
+        return PortDatabase {
+            ports: BUILT_IN_PORTS.iter().map(|(locode, name, lat, lon)| Port::new(locode, name, geo::Point::new(*lon, *lat))).collect(),
+        };
+    }
+
+    /// Loads ports from a CSV file with the columns locode, name, latitude and longitude, separated with semicolons and with a header line
+    /// # Example file:
+    /// `locode;name;latitude;longitude`
+    /// `ISREY;Reykjavik;64.150;-21.950`
+    pub fn from_csv(file_path: &str) -> Result<PortDatabase, MarineSimError> {
+        // Check file extension
+        if !check_file_extension(file_path, ".csv") {
+            return Err(MarineSimError::InvalidInput("File path must end with .csv".to_string()));
+        }
+
+        let mut csv_reader = csv::ReaderBuilder::new()
+            .delimiter(b';')
+            .has_headers(true)
+            .from_path(file_path)?;
+
+        let mut ports: Vec<Port> = Vec::new();
+        for result in csv_reader.records() {
+            let record = result?;
+            let locode = normalize_locode(record.get(0).unwrap_or(""));
+            if locode.len() != 5 || !locode.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err(csv_field_error(&record, "locode", file_path, format!("Invalid UN/LOCODE \"{}\"", locode)));
+            }
+            let name = record.get(1).unwrap_or("").trim();
+            let lat: f64 = match record.get(2).map(|s| s.trim().parse::<f64>()) {
+                Some(Ok(lat)) if (-90.0..=90.0).contains(&lat) => lat,
+                _ => return Err(csv_field_error(&record, "latitude", file_path, "Invalid latitude, must be a number from -90 to 90".to_string())),
+            };
+            let lon: f64 = match record.get(3).map(|s| s.trim().parse::<f64>()) {
+                Some(Ok(lon)) if (-180.0..=180.0).contains(&lon) => lon,
+                _ => return Err(csv_field_error(&record, "longitude", file_path, "Invalid longitude, must be a number from -180 to 180".to_string())),
+            };
+            ports.push(Port::new(&locode, name, geo::Point::new(lon, lat)));
+        }
+        return Ok(PortDatabase {
+            ports,
+        });
+    }
+
+    /// Adds a port, replacing any port with the same UN/LOCODE
+    pub fn add(&mut self, port: Port) {
+        self.ports.retain(|existing| existing.locode != port.locode);
+        self.ports.push(port);
+    }
+
+    /// Adds the ports of another database, replacing the ports with the same UN/LOCODEs
+    pub fn append(&mut self, other: PortDatabase) {
+        for port in other.ports {
+            self.add(port);
+        }
+    }
+
+    /// Returns the port with the UN/LOCODE, None if it is not in the database. Case and spaces are ignored, e.g. "is rey" finds "ISREY"
+    pub fn get(&self, locode: &str) -> Option<&Port> {
+        let locode = normalize_locode(locode);
+        return self.ports.iter().find(|port| port.locode == locode);
+    }
+
+    /// Returns the port closest to a location, None if the database is empty
+    pub fn nearest(&self, location: geo::Point) -> Option<&Port> {
+        return self.ports.iter().min_by(|a, b| Earth.distance(location, a.location).total_cmp(&Earth.distance(location, b.location)));
+    }
+
+    /// Creates a route plan following the great circle between two ports, see RoutePlan::great_circle()
+    /// Returns an error if a port is not in the database
+    pub fn route_plan(&self, from: &str, to: &str, waypoint_spacing_km: f64) -> Result<RoutePlan, MarineSimError> {
+        let from_port = self.get(from).ok_or_else(|| MarineSimError::InvalidInput(format!("No port with UN/LOCODE {} in the port database", from)))?;
+        let to_port = self.get(to).ok_or_else(|| MarineSimError::InvalidInput(format!("No port with UN/LOCODE {} in the port database", to)))?;
+        return Ok(RoutePlan::great_circle(from_port.location, to_port.location, waypoint_spacing_km));
+    }
+}
+
+impl RoutePlan {
+    /// Creates a route plan following the great circle between two of the built-in ports, by their UN/LOCODEs, see PortDatabase
+    /// Use PortDatabase.route_plan() for ports loaded from a file
+    /// Note: The great circle between ports can cross land, check it with LandMask.legs_crossing_land() or route around land with GridRouter
+    /// # Example:
+    /// `boat.route_plan = Some(RoutePlan::between_ports("ISREY", "DEHAM", 200.0)?);`
+    pub fn between_ports(from: &str, to: &str, waypoint_spacing_km: f64) -> Result<RoutePlan, MarineSimError> {
+        return PortDatabase::built_in().route_plan(from, to, waypoint_spacing_km);
+    }
+}
+
+/// Returns the UN/LOCODE in capitals without spaces, e.g. "is rey" becomes "ISREY"
+fn normalize_locode(locode: &str) -> String {
+    return locode.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase();
+}
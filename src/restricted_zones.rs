@@ -0,0 +1,211 @@
+/// Restricted zones for the Marine vessel simulator, e.g. emission control areas (ECAs), traffic separation schemes (TSS), piracy high risk areas and military zones.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// Restricted zones are attached to a route plan, see RoutePlan.zones. A zone can either be sailed through with a warning or avoided by rerouting the legs around it,
+/// see ZoneAction. Use get_time_in_zones() to find how long a vessel spent inside each zone, e.g. for compliance reports.
+/// Note: Rerouting keeps the legs clear of the zone but a vessel tacking within the tacking width of a leg can still enter it, use a margin of at least half the tacking width to prevent that.
+
+use crate::*;   // To use everything from the crate
+use geo::{Centroid, Contains, ConvexHull};  // To check if points are in zones and find the way around them
+
+/// Enum of the kinds of restricted zones
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ZoneKind {
+    /// Emission control area (ECA/SECA)
+    EmissionControlArea,
+    /// Traffic separation scheme
+    TrafficSeparationScheme,
+    /// Piracy high risk area
+    PiracyArea,
+    /// Military exercise or firing area
+    MilitaryZone,
+    /// Any other restricted area
+    Other,
+}
+
+/// Enum of what the simulator does with a restricted zone
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ZoneAction {
+    /// Sail through the zone, but add a ZoneEntered event to boat.events and print a warning each time the vessel enters it
+    Warn,
+    /// Reroute the legs that cross the zone around it before simulating
+    Avoid,
+}
+
+/// Struct for a restricted zone
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RestrictedZone {
+    /// Name of the zone
+    pub name: String,
+    /// What kind of zone it is
+    pub kind: ZoneKind,
+    /// What the simulator does with the zone
+    pub action: ZoneAction,
+    /// Area of the zone, x is longitude and y is latitude
+    pub polygon: geo::Polygon,
+    /// \[m\]. How far outside the zone rerouted legs go
+    pub margin: f64,
+}
+
+impl RestrictedZone {
+    /// Creates a new restricted zone
+    /// Defaults:
+    /// - margin: 2000 m
+    pub fn new(name: &str, kind: ZoneKind, action: ZoneAction, polygon: geo::Polygon) -> RestrictedZone {
+        RestrictedZone {
+            name: name.to_string(),
+            kind,
+            action,
+            polygon,
+            margin: 2000.0,
+        }
+    }
+
+    /// Loads restricted zones of one kind from a CSV file in the same format as LandMask::from_csv(), the polygon id is used as the name of the zone
+    /// # Example:
+    /// `route_plan.zones.append(&mut RestrictedZone::from_csv("north_sea_eca.csv", ZoneKind::EmissionControlArea, ZoneAction::Warn)?);`
+    pub fn from_csv(file_path: &str, kind: ZoneKind, action: ZoneAction) -> Result<Vec<RestrictedZone>, io::Error> {
+        // Check file extension
+        if !check_file_extension(file_path, ".csv") {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "File path must end with .csv"));
+        }
+
+        let polygons = read_polygons_from_csv(file_path)?;
+        return Ok(polygons.into_iter().map(|(name, polygon)| RestrictedZone::new(&name, kind, action, polygon)).collect());
+    }
+
+    /// Returns true if the point is inside the zone
+    pub fn contains(&self, point: geo::Point) -> bool {
+        return self.polygon.contains(&point);
+    }
+
+    /// Returns true if the great circle line from p1 to p2 enters or touches the zone
+    pub fn crosses(&self, p1: geo::Point, p2: geo::Point) -> bool {
+        return line_crosses_polygons(std::slice::from_ref(&self.polygon), p1, p2);
+    }
+
+    /// Finds the shortest way around the zone from p1 to p2, along the convex hull of the zone pushed out by the margin
+    /// Returns the waypoints between p1 and p2, or None if there is no way around, e.g. if p1 or p2 is inside the zone
+    pub fn detour(&self, p1: geo::Point, p2: geo::Point) -> Option<Vec<geo::Point>> {
+        if self.contains(p1) || self.contains(p2) {
+            return None;
+        }
+
+        // Corners of the convex hull pushed out from the centre by the margin
+        let hull = self.polygon.convex_hull();
+        let centroid = hull.centroid()?;
+        let mut corners: Vec<geo::Point> = hull.exterior().points().map(|corner| Earth.destination(corner, Earth.bearing(centroid, corner), self.margin)).collect();
+        // The first corner is repeated at the end of the ring
+        corners.pop();
+        let n = corners.len();
+        if n < 3 {
+            return None;
+        }
+
+        // Which corners can be reached directly from p1 and p2, and which edges between corners are clear of the zone
+        let from_p1: Vec<bool> = corners.iter().map(|corner| !self.crosses(p1, *corner)).collect();
+        let to_p2: Vec<bool> = corners.iter().map(|corner| !self.crosses(*corner, p2)).collect();
+        let edge_clear: Vec<bool> = (0..n).map(|i| !self.crosses(corners[i], corners[(i + 1) % n])).collect();
+
+        // Try every start and end corner going both ways around the zone, keep the shortest, (length, waypoints)
+        let mut best: Option<(f64, Vec<geo::Point>)> = None;
+        for forward in [true, false] {
+            for start in 0..n {
+                if !from_p1[start] {
+                    continue;
+                }
+                let mut waypoints: Vec<geo::Point> = vec![corners[start]];
+                let mut length = Earth.distance(p1, corners[start]);
+                let mut i = start;
+                // Walk around the hull until the end can be reached or an edge is blocked
+                for _ in 0..n {
+                    if to_p2[i] {
+                        let total_length = length + Earth.distance(corners[i], p2);
+                        if best.as_ref().map_or(true, |(best_length, _)| total_length < *best_length) {
+                            best = Some((total_length, waypoints.clone()));
+                        }
+                    }
+                    let (next, edge) = match forward {
+                        true => ((i + 1) % n, i),
+                        false => ((i + n - 1) % n, (i + n - 1) % n),
+                    };
+                    if !edge_clear[edge] || next == start {
+                        break;
+                    }
+                    length += Earth.distance(corners[i], corners[next]);
+                    waypoints.push(corners[next]);
+                    i = next;
+                }
+            }
+        }
+        return best.map(|(_, waypoints)| waypoints);
+    }
+}
+
+impl RoutePlan {
+    /// Reroutes the legs that cross zones with ZoneAction::Avoid around them, the new legs keep the tacking width and minimum proximity of the leg they replace and the last new leg keeps its port call
+    /// Returns the number of legs that were rerouted, or an error if a leg can not be rerouted, e.g. if a waypoint is inside a zone
+    pub fn reroute_around_zones(&mut self) -> Result<usize, io::Error> {
+        let mut num_rerouted: usize = 0;
+        let zones: Vec<RestrictedZone> = self.zones.iter().filter(|zone| zone.action == ZoneAction::Avoid).cloned().collect();
+        for zone in &zones {
+            let mut i = 0;
+            while i < self.legs.len() {
+                let leg = self.legs[i];
+                if !zone.crosses(leg.p1, leg.p2) {
+                    i += 1;
+                    continue;
+                }
+                let waypoints = match zone.detour(leg.p1, leg.p2) {
+                    Some(w) => w,
+                    None => return Err(io::Error::new(io::ErrorKind::Other, format!("Could not reroute leg {} around restricted zone {}, is a waypoint inside the zone?", i + 1, zone.name))),
+                };
+
+                // Replace the leg with legs through the detour waypoints
+                let mut points: Vec<geo::Point> = vec![leg.p1];
+                points.extend(waypoints);
+                points.push(leg.p2);
+                let mut new_legs: Vec<SailingLeg> = points.windows(2).map(|pair| SailingLeg::new(pair[0], pair[1], leg.tacking_width, leg.min_proximity)).collect();
+                // The port call and anchor wait stay at the end of the original leg
+                if let Some(last_leg) = new_legs.last_mut() {
+                    last_leg.port_call = leg.port_call;
+                    last_leg.anchor_wait = leg.anchor_wait;
+                }
+                let num_new_legs = new_legs.len();
+                self.legs.splice(i..i+1, new_legs);
+                num_rerouted += 1;
+                i += num_new_legs;
+            }
+        }
+
+        // Rerouting around one zone can take a leg into another
+        for (i, leg) in self.legs.iter().enumerate() {
+            if let Some(zone) = zones.iter().find(|zone| zone.crosses(leg.p1, leg.p2)) {
+                return Err(io::Error::new(io::ErrorKind::Other, format!("Leg {} still crosses restricted zone {} after rerouting, are the zones too close together?", i + 1, zone.name)));
+            }
+        }
+        return Ok(num_rerouted);
+    }
+}
+
+/// Gets the total time the vessel spent inside each zone according to the ship log
+/// The time between two consecutive entries is counted if the later entry is inside the zone, as in get_time_in_navigation_status()
+/// Returns the name of each zone with the time spent inside it
+/// # Example:
+/// `let time_in_zones = get_time_in_zones(&boat.ship_log, &route_plan.zones);`
+pub fn get_time_in_zones(ship_log: &[ShipLogEntry], zones: &[RestrictedZone]) -> Vec<(String, time::Duration)> {
+    let mut time_in_zones: Vec<(String, time::Duration)> = Vec::new();
+    for zone in zones {
+        let mut total_time = time::Duration::ZERO;
+        for i in 1..ship_log.len() {
+            if zone.contains(ship_log[i].coordinates_current) {
+                total_time += ship_log[i].timestamp - ship_log[i-1].timestamp;
+            }
+        }
+        time_in_zones.push((zone.name.clone(), total_time));
+    }
+    return time_in_zones;
+}
@@ -0,0 +1,384 @@
+/// Weather routing for the Marine vessel simulator.
+/// Finds the fastest route between two points for a given departure time with the isochrone method, using a polar diagram and a weather provider.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// The isochrone method: From the start, sail every heading for one time step. The points reached make up the first isochrone (front).
+/// From every point on the front, sail every heading again for one time step, and keep only the points farthest from the start in each direction.
+/// Repeat until the end can be reached within one time step. The route is then found by going back from the last point to the start.
+/// Note: Tacking and manoeuvring time are not included, the route is the fastest route for the polar diagram.
+
+use crate::*;   // To use everything from the crate
+
+/// One point reached by the isochrone router
+#[derive(Debug, Copy, Clone)]
+struct IsochroneNode {
+    /// Location of the node
+    location: geo::Point,
+    /// Index of the node this node was reached from, None for the start
+    parent: Option<usize>,
+}
+
+/// Isochrone weather router
+#[derive(Debug, Clone)]
+pub struct IsochroneRouter {
+    /// The polar diagram of the boat
+    pub polar: PolarDiagram,
+    /// Time between isochrones
+    pub time_step: time::Duration,
+    /// Step between headings tried from each point, in degrees
+    pub heading_step: f64,
+    /// Size of the sectors, seen from the start, used to prune the isochrones. Only the point farthest from the start in each sector is kept. In degrees
+    pub sector_size: f64,
+    /// Maximum number of isochrones before giving up
+    pub max_steps: u64,
+    /// Tacking width of the legs in the returned route plan, in \[m\]
+    pub tacking_width: f64,
+    /// Minimum proximity of the legs in the returned route plan, in \[m\]
+    pub min_proximity: f64,
+    /// Land mask, steps that cross land are not used
+    pub land_mask: Option<LandMask>,
+}
+
+impl IsochroneRouter {
+    /// Creates a new isochrone router
+    /// Defaults:
+    /// - heading_step: 5°
+    /// - sector_size: 2°
+    /// - max_steps: 1000
+    /// - tacking_width: RoutePlan::DEFAULT_TACKING_WIDTH
+    /// - min_proximity: RoutePlan::DEFAULT_MIN_PROXIMITY
+    /// - land_mask: None
+    pub fn new(polar: PolarDiagram, time_step: time::Duration) -> IsochroneRouter {
+        IsochroneRouter {
+            polar,
+            time_step,
+            heading_step: 5.0,
+            sector_size: 2.0,
+            max_steps: 1000,
+            tacking_width: RoutePlan::DEFAULT_TACKING_WIDTH,
+            min_proximity: RoutePlan::DEFAULT_MIN_PROXIMITY,
+            land_mask: None,
+        }
+    }
+
+    /// Returns the velocity over ground in \[m/s\] when sailing on a heading with the weather at a location and time
+    fn velocity(&self, weather: &dyn WeatherProvider, timestamp: UtcDateTime, location: geo::Point, heading: f64) -> Result<PhysVec, io::Error> {
+        let wind = weather.wind(timestamp, location)?;
+        // True wind angle, 0° is head to wind
+        let mut wind_angle = (heading - wind.angle) % 360.0;
+        if wind_angle < 0.0 {
+            wind_angle += 360.0;
+        }
+        let speed = self.polar.boat_speed(wind.magnitude, wind_angle);
+        let velocity = PhysVec::new(speed, heading);
+        match weather.ocean_current(timestamp, location)? {
+            Some(current) => return Ok(velocity + current),
+            None => return Ok(velocity),
+        }
+    }
+
+    /// Finds the fastest route from start to end when leaving at the departure time
+    /// Returns the route as a route plan with one leg per isochrone, ready to be used as boat.route_plan
+    /// # Example:
+    /// `let router = IsochroneRouter::new(PolarDiagram::from_csv("polar.csv")?, time::Duration::hours(3));`
+    /// `boat.route_plan = Some(router.route(&ConstantWeather::new(PhysVec::new(8.0, 270.0), None), start, end, departure)?);`
+    pub fn route(&self, weather: &dyn WeatherProvider, start: geo::Point, end: geo::Point, departure: UtcDateTime) -> Result<RoutePlan, io::Error> {
+        if self.heading_step <= 0.0 || self.sector_size <= 0.0 || self.time_step <= time::Duration::ZERO {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "heading_step, sector_size and time_step must be positive"));
+        }
+        let step_seconds = self.time_step.as_seconds_f64();
+        let n_headings = (360.0 / self.heading_step).round().max(1.0) as usize;
+        let n_sectors = (360.0 / self.sector_size).ceil().max(1.0) as usize;
+
+        // All nodes reached, the fronts hold indices into it
+        let mut nodes: Vec<IsochroneNode> = vec![IsochroneNode { location: start, parent: None }];
+        let mut front: Vec<usize> = vec![0];
+        let mut timestamp = departure;
+
+        for _ in 0..self.max_steps {
+            // Check if the end can be reached within one time step from any node on the front
+            let mut arrival: Option<usize> = None;
+            for &i in &front {
+                let location = nodes[i].location;
+                let distance = Earth.distance(location, end);
+                let velocity = self.velocity(weather, timestamp, location, Earth.bearing(location, end))?;
+                if velocity.magnitude * step_seconds >= distance && !self.crosses_land(location, end) {
+                    arrival = Some(i);
+                    break;
+                }
+            }
+            if let Some(i) = arrival {
+                return Ok(self.backtrack(&nodes, i, end));
+            }
+
+            // Best node in each sector seen from the start, (distance from start, node)
+            let mut sectors: Vec<Option<(f64, IsochroneNode)>> = vec![None; n_sectors];
+            for &i in &front {
+                let location = nodes[i].location;
+                for h in 0..n_headings {
+                    let heading = h as f64 * self.heading_step;
+                    let velocity = self.velocity(weather, timestamp, location, heading)?;
+                    if velocity.magnitude <= 0.0 {
+                        continue;
+                    }
+                    let new_location = Earth.destination(location, velocity.angle, velocity.magnitude * step_seconds);
+                    if self.crosses_land(location, new_location) {
+                        continue;
+                    }
+                    // Sector from the start
+                    let distance_from_start = Earth.distance(start, new_location);
+                    let mut bearing_from_start = Earth.bearing(start, new_location) % 360.0;
+                    if bearing_from_start < 0.0 {
+                        bearing_from_start += 360.0;
+                    }
+                    let sector = ((bearing_from_start / self.sector_size) as usize).min(n_sectors - 1);
+                    let better = match sectors[sector] {
+                        Some((best_distance, _)) => distance_from_start > best_distance,
+                        None => true,
+                    };
+                    if better {
+                        sectors[sector] = Some((distance_from_start, IsochroneNode { location: new_location, parent: Some(i) }));
+                    }
+                }
+            }
+
+            // The new front is the best node in each sector
+            front = Vec::new();
+            for (_, node) in sectors.into_iter().flatten() {
+                nodes.push(node);
+                front.push(nodes.len() - 1);
+            }
+            if front.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::Other, "Isochrone routing found no way forward, is the start on land or the wind too weak?"));
+            }
+            timestamp = timestamp + self.time_step;
+        }
+
+        return Err(io::Error::new(io::ErrorKind::TimedOut, format!("Isochrone routing did not reach the end within {} time steps", self.max_steps)));
+    }
+
+    /// Returns true if a land mask is set and the line from p1 to p2 crosses land
+    fn crosses_land(&self, p1: geo::Point, p2: geo::Point) -> bool {
+        match &self.land_mask {
+            Some(land_mask) => return land_mask.crosses_land(p1, p2),
+            None => return false,
+        }
+    }
+
+    /// Goes back from the last node to the start and makes a route plan ending at the end point
+    fn backtrack(&self, nodes: &Vec<IsochroneNode>, last: usize, end: geo::Point) -> RoutePlan {
+        // Points from the end back to the start
+        let mut points: Vec<geo::Point> = vec![end];
+        let mut i = Some(last);
+        while let Some(index) = i {
+            points.push(nodes[index].location);
+            i = nodes[index].parent;
+        }
+        points.reverse();
+
+        // Make a leg between each pair of consecutive points
+        let mut legs: Vec<SailingLeg> = Vec::with_capacity(points.len() - 1);
+        for pair in points.windows(2) {
+            legs.push(SailingLeg::new(pair[0], pair[1], self.tacking_width, self.min_proximity));
+        }
+        return RoutePlan::from(legs);
+    }
+}
+
+/// Item in the A* priority queue, the node with the lowest estimated total cost is popped first
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct GridQueueItem {
+    /// \[s\]. Time to the node plus the estimated time from the node to the end
+    cost_estimate: f64,
+    /// Index of the node in the grid
+    index: usize,
+}
+
+impl Eq for GridQueueItem {}
+
+impl Ord for GridQueueItem {
+    /// Reversed so that std::collections::BinaryHeap, a max heap, pops the lowest cost first
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        return other.cost_estimate.total_cmp(&self.cost_estimate).then_with(|| other.index.cmp(&self.index));
+    }
+}
+
+impl PartialOrd for GridQueueItem {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+/// Grid weather router, a simpler alternative to IsochroneRouter
+/// Finds the fastest route with A* over a latitude/longitude grid around the start and end. Grid points on land are not used.
+/// The cost of moving between neighbouring grid points is the time it takes at the expected speed over ground, from the polar diagram, the wind and the ocean current
+/// Note: The grid does not wrap around the antimeridian (longitude ±180°)
+/// Note: The heuristic assumes the boat can not go faster than the fastest speed in the polar diagram, with strong favourable currents the route may not be the fastest one
+#[derive(Debug, Clone)]
+pub struct GridRouter {
+    /// The polar diagram of the boat
+    pub polar: PolarDiagram,
+    /// Distance between grid points in degrees of latitude and longitude
+    pub resolution: f64,
+    /// How far the grid extends past the start and end, in degrees
+    pub margin: f64,
+    /// Tacking width of the legs in the returned route plan, in \[m\]
+    pub tacking_width: f64,
+    /// Minimum proximity of the legs in the returned route plan, in \[m\]
+    pub min_proximity: f64,
+    /// Land mask, grid points on land and moves that cross land are not used
+    pub land_mask: Option<LandMask>,
+}
+
+impl GridRouter {
+    /// Creates a new grid router
+    /// Defaults:
+    /// - margin: 5°
+    /// - tacking_width: RoutePlan::DEFAULT_TACKING_WIDTH
+    /// - min_proximity: RoutePlan::DEFAULT_MIN_PROXIMITY
+    /// - land_mask: None
+    pub fn new(polar: PolarDiagram, resolution: f64) -> GridRouter {
+        GridRouter {
+            polar,
+            resolution,
+            margin: 5.0,
+            tacking_width: RoutePlan::DEFAULT_TACKING_WIDTH,
+            min_proximity: RoutePlan::DEFAULT_MIN_PROXIMITY,
+            land_mask: None,
+        }
+    }
+
+    /// Returns the expected speed over ground in \[m/s\] when sailing on a bearing with the weather at a location and time, the component of the velocity along the bearing
+    fn speed_along(&self, weather: &dyn WeatherProvider, timestamp: UtcDateTime, location: geo::Point, bearing: f64) -> Result<f64, io::Error> {
+        let wind = weather.wind(timestamp, location)?;
+        let mut speed = self.polar.boat_speed(wind.magnitude, bearing - wind.angle);
+        if let Some(current) = weather.ocean_current(timestamp, location)? {
+            speed += current.magnitude * ((current.angle - bearing)*consts::PI/180.0).cos();
+        }
+        return Ok(speed);
+    }
+
+    /// Finds the fastest route from start to end over the grid when leaving at the departure time
+    /// The start and end are connected to the closest grid points and moves in the same direction are merged into one leg
+    /// Returns the route as a route plan, ready to be used as boat.route_plan and simulated with sim_waypoint_missions()
+    /// # Example:
+    /// `let mut router = GridRouter::new(PolarDiagram::from_csv("polar.csv")?, 0.25);`
+    /// `router.land_mask = Some(LandMask::from_csv("north_atlantic_coastlines.csv")?);`
+    /// `boat.route_plan = Some(router.route(&ConstantWeather::new(PhysVec::new(8.0, 270.0), None), start, end, departure)?);`
+    pub fn route(&self, weather: &dyn WeatherProvider, start: geo::Point, end: geo::Point, departure: UtcDateTime) -> Result<RoutePlan, io::Error> {
+        if self.resolution <= 0.0 || self.margin < 0.0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Grid resolution must be positive and margin not negative"));
+        }
+        // Fastest speed the boat can go, for the heuristic
+        let max_speed: f64 = self.polar.boat_speeds.iter().flatten().fold(0.0, |a, b| a.max(*b));
+        if max_speed <= 0.0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Polar diagram has no boat speeds above zero"));
+        }
+
+        // Make the grid, south west corner and size
+        // The longitude of the end is unwrapped from the start so a grid across the ±180° meridian spans the short way, not around the rest of the globe
+        let end_longitude = start.x() + get_longitude_difference(start.x(), end.x());
+        let lon_min = start.x().min(end_longitude) - self.margin;
+        let lat_min = (start.y().min(end.y()) - self.margin).max(-89.0);
+        let lat_max = (start.y().max(end.y()) + self.margin).min(89.0);
+        let n_cols = ((start.x().max(end_longitude) + self.margin - lon_min) / self.resolution).ceil() as usize + 1;
+        let n_rows = ((lat_max - lat_min) / self.resolution).ceil() as usize + 1;
+        let grid_point = |index: usize| geo::Point::new(normalize_longitude(lon_min + (index % n_cols) as f64*self.resolution), lat_min + (index / n_cols) as f64*self.resolution);
+        let closest_grid_index = |point: geo::Point| {
+            let col = (((point.x() - lon_min).rem_euclid(360.0) / self.resolution).round() as usize).min(n_cols - 1);
+            let row = (((point.y() - lat_min) / self.resolution).round().max(0.0) as usize).min(n_rows - 1);
+            row*n_cols + col
+        };
+        let start_index = closest_grid_index(start);
+        let end_index = closest_grid_index(end);
+
+        // Time to each grid point [s], the grid point it was reached from and if it is done
+        let n_nodes = n_rows*n_cols;
+        let mut cost: Vec<f64> = vec![f64::INFINITY; n_nodes];
+        let mut parent: Vec<Option<usize>> = vec![None; n_nodes];
+        let mut done: Vec<bool> = vec![false; n_nodes];
+        // If each grid point is on land, checked when first needed
+        let mut on_land: Vec<Option<bool>> = vec![None; n_nodes];
+
+        // A* search
+        let mut queue: std::collections::BinaryHeap<GridQueueItem> = std::collections::BinaryHeap::new();
+        cost[start_index] = 0.0;
+        queue.push(GridQueueItem { cost_estimate: Earth.distance(grid_point(start_index), end) / max_speed, index: start_index });
+        while let Some(GridQueueItem { index, .. }) = queue.pop() {
+            if done[index] {
+                continue;
+            }
+            done[index] = true;
+            if index == end_index {
+                return Ok(self.make_route_plan(&parent, &grid_point, n_cols, start, end, end_index));
+            }
+
+            let location = grid_point(index);
+            let timestamp = departure + time::Duration::seconds_f64(cost[index]);
+            let (row, col) = ((index / n_cols) as i64, (index % n_cols) as i64);
+            // The 8 neighbouring grid points
+            for (d_row, d_col) in [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)] {
+                let (new_row, new_col) = (row + d_row, col + d_col);
+                if new_row < 0 || new_col < 0 || new_row >= n_rows as i64 || new_col >= n_cols as i64 {
+                    continue;
+                }
+                let new_index = (new_row as usize)*n_cols + new_col as usize;
+                if done[new_index] {
+                    continue;
+                }
+                let new_location = grid_point(new_index);
+
+                // Skip grid points on land and moves across land
+                if let Some(land_mask) = &self.land_mask {
+                    let is_on_land = *on_land[new_index].get_or_insert_with(|| land_mask.is_on_land(new_location));
+                    if is_on_land || land_mask.crosses_land(location, new_location) {
+                        continue;
+                    }
+                }
+
+                // Time to sail to the neighbouring grid point
+                let speed = self.speed_along(weather, timestamp, location, Earth.bearing(location, new_location))?;
+                if speed <= 0.0 {
+                    continue;
+                }
+                let new_cost = cost[index] + Earth.distance(location, new_location) / speed;
+                if new_cost < cost[new_index] {
+                    cost[new_index] = new_cost;
+                    parent[new_index] = Some(index);
+                    queue.push(GridQueueItem { cost_estimate: new_cost + Earth.distance(new_location, end) / max_speed, index: new_index });
+                }
+            }
+        }
+
+        return Err(io::Error::new(io::ErrorKind::NotFound, "Grid routing found no route from start to end, is the start or end on land or the grid margin too small?"));
+    }
+
+    /// Goes back from the end grid point to the start and makes a route plan from start to end
+    /// Consecutive moves in the same direction are merged into one leg
+    fn make_route_plan(&self, parent: &Vec<Option<usize>>, grid_point: &dyn Fn(usize) -> geo::Point, n_cols: usize, start: geo::Point, end: geo::Point, end_index: usize) -> RoutePlan {
+        // Grid indices from the start to the end
+        let mut path: Vec<usize> = vec![end_index];
+        while let Some(index) = parent[*path.last().unwrap()] {
+            path.push(index);
+        }
+        path.reverse();
+
+        // Keep the grid points where the direction changes, the closest grid points to the start and end are replaced by the start and end
+        // Direction of the move from one grid index to the next, (rows, columns)
+        let direction = |from: usize, to: usize| ((to / n_cols) as i64 - (from / n_cols) as i64, (to % n_cols) as i64 - (from % n_cols) as i64);
+        let mut points: Vec<geo::Point> = vec![start];
+        for i in 1..path.len().saturating_sub(1) {
+            if direction(path[i-1], path[i]) != direction(path[i], path[i+1]) {
+                points.push(grid_point(path[i]));
+            }
+        }
+        points.push(end);
+
+        // Make a leg between each pair of consecutive points
+        let mut legs: Vec<SailingLeg> = Vec::with_capacity(points.len() - 1);
+        for pair in points.windows(2) {
+            legs.push(SailingLeg::new(pair[0], pair[1], self.tacking_width, self.min_proximity));
+        }
+        return RoutePlan::from(legs);
+    }
+}
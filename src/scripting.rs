@@ -0,0 +1,152 @@
+/// Scripting hooks for the Marine vessel simulator, only with the "scripting" feature.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// A Rhai script (https://rhai.rs) is evaluated at each time step of a CoSimulation with the state of the boat and the weather, and can change the heading, reef the sail or run the engine.
+/// This makes it possible to prototype strategies, e.g. reefing in strong wind or motoring in calms, without recompiling the crate.
+/// Variables the script can read, angles in degrees from true north, speeds in \[m/s\] and distances in \[m\]:
+/// - time: Unix time of the boat in seconds
+/// - latitude, longitude
+/// - speed and course_over_ground: velocity over ground during the last step
+/// - wind_speed, wind_direction (where the wind comes from) and true_wind_angle, in \[0, 180\] for the heading the boat will steer
+/// - current_speed and current_direction (where the current flows to), zero if unknown
+/// - leg, legs: the leg the boat is sailing, starting at 1, and the number of legs
+/// - next_waypoint_latitude, next_waypoint_longitude, distance_to_next_waypoint, bearing_to_next_waypoint
+/// - cross_track_error, zero if unknown
+/// - cargo: \[tons\] on board
+/// - iterations: number of steps taken
+/// Variables the script can change, they start at the values the boat will use if the script leaves them alone:
+/// - heading: starts at bearing_to_next_waypoint, so the boat steers straight for the next waypoint unless the script changes it
+/// - sail_fraction: how much of the sail is set, in \[0, 1\], see ControlCommand.sail_fraction
+/// - engine_speed: speed through water the engine gives, 0.0 is off, see ControlCommand.engine_speed
+/// Variables the script creates with let are dropped after each time step, use the object map memory to keep values between the time steps, e.g. memory.reefed = true;
+/// print() writes to the tracing info events.
+/// # Example script:
+/// `if wind_speed > 15.0 { sail_fraction = 0.5; } else { sail_fraction = 1.0; }`
+/// `engine_speed = if wind_speed < 2.0 { 3.0 } else { 0.0 };`
+
+use crate::*;   // To use everything from the crate
+
+/// Maximum number of operations per evaluation of a script, stops scripts that never end
+const MAX_SCRIPT_OPERATIONS: u64 = 1_000_000;
+
+/// A compiled Rhai script that decides the heading, sail and engine of a boat at each time step, see the module documentation for the variables
+pub struct BehaviorScript {
+    engine: rhai::Engine,
+    ast: rhai::AST,
+    /// Variables of the script, the variable memory is kept between the time steps
+    scope: rhai::Scope<'static>,
+}
+
+impl BehaviorScript {
+    /// Compiles a script
+    /// # Example:
+    /// `let mut script = BehaviorScript::new("if wind_speed > 15.0 { sail_fraction = 0.5; }")?;`
+    pub fn new(script: &str) -> Result<BehaviorScript, MarineSimError> {
+        let mut engine = rhai::Engine::new();
+        engine.set_max_operations(MAX_SCRIPT_OPERATIONS);
+        engine.on_print(|text| tracing::info!(script = true, "{}", text));
+        engine.on_debug(|text, _, position| tracing::debug!(script = true, %position, "{}", text));
+        let ast = match engine.compile(script) {
+            Ok(ast) => ast,
+            Err(e) => return Err(MarineSimError::InvalidInput(format!("Could not compile script: {}", e))),
+        };
+        return Ok(BehaviorScript {
+            engine,
+            ast,
+            scope: rhai::Scope::new(),
+        });
+    }
+
+    /// Reads and compiles a script file, e.g. "strategies/reef_in_gusts.rhai"
+    pub fn from_file(file_path: &str) -> Result<BehaviorScript, MarineSimError> {
+        let script = std::fs::read_to_string(file_path)?;
+        return BehaviorScript::new(&script);
+    }
+
+    /// Evaluates the script with the state of the boat and returns the command it gives, see CoSimulation.command()
+    /// Returns an error if the script fails or sets a variable to something that is not a number
+    pub fn evaluate(&mut self, state: &StepState, boat: &Boat) -> Result<ControlCommand, MarineSimError> {
+        let bearing_to_next_waypoint = Earth.bearing(state.location, state.next_waypoint);
+        let ocean_current = state.ocean_current.unwrap_or(PhysVec::new(0.0, 0.0));
+
+        // Inputs
+        self.scope.set_value("time", state.timestamp.unix_timestamp());
+        self.scope.set_value("latitude", state.location.y());
+        self.scope.set_value("longitude", state.location.x());
+        self.scope.set_value("speed", state.velocity.magnitude);
+        self.scope.set_value("course_over_ground", state.velocity.angle);
+        self.scope.set_value("wind_speed", state.wind.magnitude);
+        self.scope.set_value("wind_direction", state.wind.angle);
+        self.scope.set_value("true_wind_angle", get_true_wind_angle(bearing_to_next_waypoint, state.wind.angle));
+        self.scope.set_value("current_speed", ocean_current.magnitude);
+        self.scope.set_value("current_direction", ocean_current.angle);
+        self.scope.set_value("leg", state.current_leg as i64);
+        self.scope.set_value("legs", boat.route_plan.as_ref().map_or(0, |route_plan| route_plan.len()) as i64);
+        self.scope.set_value("next_waypoint_latitude", state.next_waypoint.y());
+        self.scope.set_value("next_waypoint_longitude", state.next_waypoint.x());
+        self.scope.set_value("distance_to_next_waypoint", state.distance_to_next_waypoint);
+        self.scope.set_value("bearing_to_next_waypoint", bearing_to_next_waypoint);
+        self.scope.set_value("cross_track_error", state.cross_track_error.unwrap_or(0.0));
+        self.scope.set_value("cargo", boat.cargo_current.get::<uom::si::mass::ton>());
+        self.scope.set_value("iterations", state.iterations as i64);
+
+        // Outputs, start at what the boat does if the script leaves them alone
+        self.scope.set_value("heading", bearing_to_next_waypoint);
+        self.scope.set_value("sail_fraction", state.sail_fraction);
+        self.scope.set_value("engine_speed", state.engine_speed);
+        if !self.scope.contains("memory") {
+            self.scope.push("memory", rhai::Map::new());
+        }
+
+        // Run the script, then drop the variables it created
+        let num_variables = self.scope.len();
+        let result = self.engine.run_ast_with_scope(&mut self.scope, &self.ast);
+        let command = match result {
+            Ok(_) => get_script_command(&self.scope),
+            Err(e) => Err(MarineSimError::InvalidInput(format!("Script failed: {}", e))),
+        };
+        self.scope.rewind(num_variables);
+        return command;
+    }
+}
+
+/// Returns the command given by the output variables of a script
+fn get_script_command(scope: &rhai::Scope) -> Result<ControlCommand, MarineSimError> {
+    return Ok(ControlCommand {
+        heading: Some(get_script_number(scope, "heading")?),
+        sail_fraction: Some(get_script_number(scope, "sail_fraction")?),
+        engine_speed: Some(get_script_number(scope, "engine_speed")?),
+    });
+}
+
+/// Returns a number variable of a script, integers are converted to floats so the script can write e.g. heading = 90
+fn get_script_number(scope: &rhai::Scope, name: &str) -> Result<f64, MarineSimError> {
+    let value = match scope.get(name) {
+        Some(value) => value,
+        None => return Err(MarineSimError::InvalidInput(format!("Script removed the variable {}", name))),
+    };
+    if let Ok(number) = value.as_float() {
+        return Ok(number);
+    }
+    if let Ok(number) = value.as_int() {
+        return Ok(number as f64);
+    }
+    return Err(MarineSimError::InvalidInput(format!("Script set {} to {}, expected a number", name, value.type_name())));
+}
+
+/// Simulates a voyage of the boat along its route plan with a script deciding the heading, sail and engine at each time step, see CoSimulation and BehaviorScript
+/// Returns the result of the voyage, the same as sim_waypoint_mission()
+/// # Example:
+/// `let mut script = BehaviorScript::from_file("strategies/motor_in_calms.rhai")?;`
+/// `let result = sim_waypoint_mission_scripted(&mut boat, start_time, &simulation, &mut script)?;`
+pub fn sim_waypoint_mission_scripted(boat: &mut Boat, start_time: UtcDateTime, simulation: &Simulation, script: &mut BehaviorScript) -> Result<SimulationResult, MarineSimError> {
+    let _span = tracing::info_span!("voyage", departure = %format_timestamp(start_time), scripted = true).entered();
+    let mut co_simulation = CoSimulation::new(boat, start_time, simulation)?;
+    let mut state = co_simulation.state(boat)?;
+    while !state.is_finished() {
+        let command = script.evaluate(&state, boat)?;
+        co_simulation.command(boat, command)?;
+        state = co_simulation.step(boat)?;
+    }
+    return Ok(co_simulation.result(boat).expect("The voyage has ended"));
+}
@@ -0,0 +1,200 @@
+/// Search and rescue drift ensembles for the Marine vessel simulator.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// An object in the water, e.g. a life raft, a person or a disabled vessel, drifts with the ocean current and the wind, see SimMethod::Drift. How much it drifts with the wind (its leeway) and where it went into the water are not known exactly,
+/// so the search area is found by simulating an ensemble of drifts with perturbed windage factors, leeway directions and start positions, see DriftEnsemble and run_drift_ensemble().
+/// The search area at each time step is the convex hull of the drift positions closest to the centre of the ensemble, and can be exported to GeoJSON for charts and GIS tools, see SearchArea.to_geojson().
+
+use crate::*;   // To use everything from the crate
+use geo::{Centroid, ConvexHull};    // To find the centre and outline of the drift positions
+use rand::SeedableRng;  // To seed the random number generator of a drift ensemble
+
+/// Settings of a drift ensemble, see run_drift_ensemble()
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DriftEnsemble {
+    /// Number of simulated drifts
+    pub num_members: usize,
+    /// Standard deviation of the windage factor as a fraction of Boat.windage_factor, e.g. 0.3 for 30%
+    pub windage_factor_std: f64,
+    /// Standard deviation of the leeway direction from downwind in degrees, drifting objects often drift to one side of downwind
+    pub leeway_angle_std: f64,
+    /// \[m\]. Standard deviation of the start position from the first waypoint of the route plan, north-south and east-west
+    pub start_position_std: f64,
+    /// Fraction of the drift positions inside the search area, in (0, 1], e.g. 0.9 for a search area that holds 90% of the ensemble
+    pub containment: f64,
+    /// Seed of the random number generator, the same seed gives the same search area. None for a different ensemble each run
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seed: Option<u64>,
+}
+
+impl DriftEnsemble {
+    /// Creates the settings of a drift ensemble with num_members drifts
+    /// Defaults:
+    /// - windage_factor_std: 0.3
+    /// - leeway_angle_std: 20°
+    /// - start_position_std: 1000 m
+    /// - containment: 0.9
+    /// - seed: None
+    pub fn new(num_members: usize) -> DriftEnsemble {
+        DriftEnsemble {
+            num_members,
+            windage_factor_std: 0.3,
+            leeway_angle_std: 20.0,
+            start_position_std: 1000.0,
+            containment: 0.9,
+            seed: None,
+        }
+    }
+}
+
+/// The search area of a drift ensemble at one time, see SearchArea
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchAreaSnapshot {
+    pub timestamp: UtcDateTime,
+    /// Centre of the drift positions, x is longitude and y is latitude
+    pub centre: geo::Point,
+    /// Convex hull of the drift positions closest to the centre, holding SearchArea.containment of them
+    pub polygon: geo::Polygon,
+    /// Position of each member of the ensemble, members that ran aground stay where they stopped
+    pub positions: Vec<geo::Point>,
+}
+
+/// Probabilistic search area over time, made by run_drift_ensemble()
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchArea {
+    /// Fraction of the drift positions inside each polygon, see DriftEnsemble.containment
+    pub containment: f64,
+    /// The search area at each time step of the simulation, in chronological order
+    pub snapshots: Vec<SearchAreaSnapshot>,
+}
+
+impl SearchArea {
+    /// Makes the search area from the drift track of each member of an ensemble, the tracks must start at the same time and have the same time step
+    /// A track that ends early, e.g. because the object ran aground, stays at its last position
+    pub fn from_tracks(tracks: &[Vec<(UtcDateTime, geo::Point)>], containment: f64) -> SearchArea {
+        let mut snapshots: Vec<SearchAreaSnapshot> = Vec::new();
+        let longest = tracks.iter().max_by_key(|track| track.len());
+        if let Some(longest) = longest {
+            for (i, (timestamp, _)) in longest.iter().enumerate() {
+                let positions: Vec<geo::Point> = tracks.iter().filter_map(|track| track.get(i).or(track.last()).map(|(_, position)| *position)).collect();
+                snapshots.push(get_search_area_snapshot(*timestamp, positions, containment));
+            }
+        }
+        return SearchArea {
+            containment,
+            snapshots,
+        };
+    }
+
+    /// Returns the latest snapshot at or before the timestamp, None if the timestamp is before the first snapshot
+    pub fn at(&self, timestamp: UtcDateTime) -> Option<&SearchAreaSnapshot> {
+        return self.snapshots.iter().take_while(|snapshot| snapshot.timestamp <= timestamp).last();
+    }
+
+    /// Returns the search area as a GeoJSON FeatureCollection with one Polygon feature per snapshot
+    /// Each feature has the properties timestamp (RFC 3339), containment, members and the centre latitude and longitude
+    pub fn to_geojson(&self) -> Result<String, io::Error> {
+        let mut features: Vec<String> = Vec::with_capacity(self.snapshots.len());
+        for snapshot in &self.snapshots {
+            let timestamp = match snapshot.timestamp.format(&time::format_description::well_known::Rfc3339) {
+                Ok(timestamp) => timestamp,
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Could not format timestamp {}: {}", snapshot.timestamp, e))),
+            };
+            let ring: Vec<String> = snapshot.polygon.exterior().points().map(|point| format!("[{},{}]", point.x(), point.y())).collect();
+            features.push(format!(
+                "{{\"type\":\"Feature\",\"geometry\":{{\"type\":\"Polygon\",\"coordinates\":[[{}]]}},\"properties\":{{\"timestamp\":\"{}\",\"containment\":{},\"members\":{},\"centre_latitude\":{},\"centre_longitude\":{}}}}}",
+                ring.join(","), timestamp, self.containment, snapshot.positions.len(), snapshot.centre.y(), snapshot.centre.x()
+            ));
+        }
+        return Ok(format!("{{\"type\":\"FeatureCollection\",\"features\":[{}]}}\n", features.join(",")));
+    }
+
+    /// Writes the search area to a GeoJSON file, see SearchArea.to_geojson()
+    /// Will overwrite any file with the same file name at file_path. file_path must end with ".geojson"
+    /// # Example:
+    /// `search_area.write_geojson("search_area.geojson")?;`
+    pub fn write_geojson(&self, file_path: &str) -> Result<(), io::Error> {
+        if !check_file_extension(file_path, ".geojson") {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "The filepath must end with \".geojson\""));
+        }
+        return std::fs::write(file_path, self.to_geojson()?);
+    }
+}
+
+/// Returns the search area snapshot of the drift positions at a time
+fn get_search_area_snapshot(timestamp: UtcDateTime, positions: Vec<geo::Point>, containment: f64) -> SearchAreaSnapshot {
+    let centre = geo::MultiPoint::from(positions.clone()).centroid().unwrap_or(geo::Point::new(0.0, 0.0));
+
+    // The positions closest to the centre make the search area
+    let mut closest: Vec<geo::Point> = positions.clone();
+    closest.sort_by(|a, b| Earth.distance(centre, *a).total_cmp(&Earth.distance(centre, *b)));
+    let num_inside = ((containment*positions.len() as f64).ceil() as usize).clamp(1.min(positions.len()), positions.len());
+    closest.truncate(num_inside);
+
+    return SearchAreaSnapshot {
+        timestamp,
+        centre,
+        polygon: geo::MultiPoint::from(closest).convex_hull(),
+        positions,
+    };
+}
+
+/// Simulates an ensemble of drifts of the boat from the first of simulation.start_times and returns the probabilistic search area over time, see SearchArea
+/// Each member drifts as in SimMethod::Drift, from the first waypoint of the route plan moved at random by ensemble.start_position_std, with the windage factor of the boat perturbed by ensemble.windage_factor_std
+/// and the leeway turned from downwind by a random angle with standard deviation ensemble.leeway_angle_std
+/// Uses the weather, time step, max iterations, max simulated duration, land mask and bathymetry of the simulation, the simulation is not changed
+/// Each member is simulated on a copy of boat_template, so the template is not changed. Set ensemble.seed for a reproducible search area
+/// # Example:
+/// `let search_area = run_drift_ensemble(&life_raft, &simulation, &DriftEnsemble::new(500))?;`
+/// `search_area.write_geojson("search_area.geojson")?;`
+pub fn run_drift_ensemble(boat_template: &Boat, simulation: &Simulation, ensemble: &DriftEnsemble) -> Result<SearchArea, MarineSimError> {
+    let start_time: UtcDateTime = match simulation.start_times.first() {
+        Some(start_time) => *start_time,
+        None => return Err(MarineSimError::InvalidInput("Simulation has no start time".to_string())),
+    };
+    let weather = match &simulation.weather {
+        Some(weather) => weather.clone(),
+        None => return Err(MarineSimError::InvalidInput("A drift ensemble needs the wind and ocean current from simulation.weather".to_string())),
+    };
+    let start_position: geo::Point = match boat_template.route_plan.as_ref().and_then(|route_plan| route_plan.first()) {
+        Some(leg) => leg.p1,
+        None => return Err(MarineSimError::MissingBoatField("route plan".to_string())),
+    };
+    if ensemble.num_members == 0 {
+        return Err(MarineSimError::InvalidInput("A drift ensemble needs at least one member".to_string()));
+    }
+    if !(ensemble.containment > 0.0 && ensemble.containment <= 1.0) {
+        return Err(MarineSimError::InvalidInput(format!("The containment of a drift ensemble must be in (0, 1], it is {}", ensemble.containment)));
+    }
+
+    let mut rng: rand::rngs::StdRng = match ensemble.seed {
+        Some(seed) => rand::rngs::StdRng::seed_from_u64(seed),
+        None => rand::rngs::StdRng::from_rng(&mut rand::rng()),
+    };
+    let mut tracks: Vec<Vec<(UtcDateTime, geo::Point)>> = Vec::with_capacity(ensemble.num_members);
+    for i in 0..ensemble.num_members {
+        // Perturb the windage factor and the start position
+        let mut boat = boat_template.clone();
+        boat.ship_log = Vec::new();
+        boat.windage_factor = Some((boat_template.windage_factor.unwrap_or(0.03)*(1.0 + get_normal_random_with_rng(&mut rng, ensemble.windage_factor_std))).max(0.0));
+        let (north, east) = (get_normal_random_with_rng(&mut rng, ensemble.start_position_std), get_normal_random_with_rng(&mut rng, ensemble.start_position_std));
+        let start = Earth.destination(start_position, east.atan2(north).to_degrees(), (north*north + east*east).sqrt());
+        boat.route_plan.as_mut().unwrap().legs[0].p1 = start;
+
+        // Perturb the leeway direction by turning the wind, the wind only moves a drifting boat through its leeway
+        let member_weather = NoisyWeather {
+            provider: weather.clone(),
+            wind_speed_factor: 1.0,
+            wind_angle_offset: get_normal_random_with_rng(&mut rng, ensemble.leeway_angle_std),
+            ocean_current_factor: 1.0,
+        };
+
+        match sim_drift_with_weather(&mut boat, start_time, simulation, &member_weather) {
+            Ok(_) => tracks.push(boat.ship_log.iter().map(|entry| (entry.timestamp, entry.coordinates_current)).collect()),
+            Err(e) => return Err(MarineSimError::simulation(format!("Error during drift ensemble member {}", i), e)),
+        }
+    }
+
+    return Ok(SearchArea::from_tracks(&tracks, ensemble.containment));
+}
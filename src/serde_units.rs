@@ -0,0 +1,117 @@
+/// Unit-tagged serialization of uom quantities for the Marine vessel simulator, only with the "serde" feature.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// Quantities are written as a value with its unit, e.g. {"value": 12.5, "unit": "t"}, so config files are readable and do not depend on the base units of uom.
+/// When reading, any of the supported units can be used, e.g. {"value": 12500, "unit": "kg"}. Mass is written in metric tons, length in meters and area in square meters.
+/// Use with the serde field attribute "with", e.g. #[serde(with = "crate::serde_units::option_mass")]
+
+use crate::*;   // To use everything from the crate
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error};
+
+/// A value with its unit, the serialized form of a quantity
+#[derive(Serialize, Deserialize)]
+struct TaggedQuantity {
+    value: f64,
+    unit: String,
+}
+
+/// Converts a tagged mass to a uom mass, supported units are t, kg, g and lb
+fn mass_from_tagged(quantity: TaggedQuantity) -> Result<uom::si::f64::Mass, String> {
+    return match quantity.unit.as_str() {
+        "t" | "ton" | "tons" => Ok(uom::si::f64::Mass::new::<uom::si::mass::ton>(quantity.value)),
+        "kg" => Ok(uom::si::f64::Mass::new::<uom::si::mass::kilogram>(quantity.value)),
+        "g" => Ok(uom::si::f64::Mass::new::<uom::si::mass::gram>(quantity.value)),
+        "lb" => Ok(uom::si::f64::Mass::new::<uom::si::mass::pound>(quantity.value)),
+        unit => Err(format!("Unknown mass unit \"{}\", use t, kg, g or lb", unit)),
+    };
+}
+
+/// Converts a tagged length to a uom length, supported units are m, km, nmi and ft
+fn length_from_tagged(quantity: TaggedQuantity) -> Result<uom::si::f64::Length, String> {
+    return match quantity.unit.as_str() {
+        "m" => Ok(uom::si::f64::Length::new::<uom::si::length::meter>(quantity.value)),
+        "km" => Ok(uom::si::f64::Length::new::<uom::si::length::kilometer>(quantity.value)),
+        "nmi" => Ok(uom::si::f64::Length::new::<uom::si::length::nautical_mile>(quantity.value)),
+        "ft" => Ok(uom::si::f64::Length::new::<uom::si::length::foot>(quantity.value)),
+        unit => Err(format!("Unknown length unit \"{}\", use m, km, nmi or ft", unit)),
+    };
+}
+
+/// Converts a tagged area to a uom area, supported units are m2 (or m²) and ft2 (or ft²)
+fn area_from_tagged(quantity: TaggedQuantity) -> Result<uom::si::f64::Area, String> {
+    return match quantity.unit.as_str() {
+        "m2" | "m²" => Ok(uom::si::f64::Area::new::<uom::si::area::square_meter>(quantity.value)),
+        "ft2" | "ft²" => Ok(uom::si::f64::Area::new::<uom::si::area::square_foot>(quantity.value)),
+        unit => Err(format!("Unknown area unit \"{}\", use m2 or ft2", unit)),
+    };
+}
+
+fn tagged_mass(mass: &uom::si::f64::Mass) -> TaggedQuantity {
+    return TaggedQuantity { value: mass.get::<uom::si::mass::ton>(), unit: "t".to_string() };
+}
+
+fn tagged_length(length: &uom::si::f64::Length) -> TaggedQuantity {
+    return TaggedQuantity { value: length.get::<uom::si::length::meter>(), unit: "m".to_string() };
+}
+
+fn tagged_area(area: &uom::si::f64::Area) -> TaggedQuantity {
+    return TaggedQuantity { value: area.get::<uom::si::area::square_meter>(), unit: "m2".to_string() };
+}
+
+/// For uom::si::f64::Mass fields
+pub mod mass {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(mass: &uom::si::f64::Mass, serializer: S) -> Result<S::Ok, S::Error> {
+        return tagged_mass(mass).serialize(serializer);
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<uom::si::f64::Mass, D::Error> {
+        return mass_from_tagged(TaggedQuantity::deserialize(deserializer)?).map_err(D::Error::custom);
+    }
+}
+
+/// For Option<uom::si::f64::Mass> fields, use together with #[serde(default)] so the field can be left out
+pub mod option_mass {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(mass: &Option<uom::si::f64::Mass>, serializer: S) -> Result<S::Ok, S::Error> {
+        return mass.as_ref().map(tagged_mass).serialize(serializer);
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<uom::si::f64::Mass>, D::Error> {
+        return match Option::<TaggedQuantity>::deserialize(deserializer)? {
+            Some(quantity) => Ok(Some(mass_from_tagged(quantity).map_err(D::Error::custom)?)),
+            None => Ok(None),
+        };
+    }
+}
+
+/// For Option<uom::si::f64::Length> fields, use together with #[serde(default)] so the field can be left out
+pub mod option_length {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(length: &Option<uom::si::f64::Length>, serializer: S) -> Result<S::Ok, S::Error> {
+        return length.as_ref().map(tagged_length).serialize(serializer);
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<uom::si::f64::Length>, D::Error> {
+        return match Option::<TaggedQuantity>::deserialize(deserializer)? {
+            Some(quantity) => Ok(Some(length_from_tagged(quantity).map_err(D::Error::custom)?)),
+            None => Ok(None),
+        };
+    }
+}
+
+/// For uom::si::f64::Area fields
+pub mod area {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(area: &uom::si::f64::Area, serializer: S) -> Result<S::Ok, S::Error> {
+        return tagged_area(area).serialize(serializer);
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<uom::si::f64::Area, D::Error> {
+        return area_from_tagged(TaggedQuantity::deserialize(deserializer)?).map_err(D::Error::custom);
+    }
+}
@@ -0,0 +1,372 @@
+/// REST API server for the Marine vessel simulator, only with the "server" feature.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// Runs the simulator as a shared service. A boat, route plan and simulation settings are submitted as JSON, each submission is simulated on its own thread and the progress, ship log and statistics can be fetched as JSON while and after it runs.
+/// Endpoints:
+/// - POST /simulations with a SimulationRequest as the body, returns the id of the simulation
+/// - GET /simulations, returns the id and status of every simulation
+/// - GET /simulations/{id}, returns the status and progress of a simulation
+/// - GET /simulations/{id}/ship_log, returns the ship log of all voyages of a finished simulation, see ShipLogEntry
+/// - GET /simulations/{id}/statistics, returns the results of each voyage of a finished simulation, see SimulationResult
+/// The boat and simulation have the same fields as the config files, see Boat::from_config_file() and Simulation::from_config_file().
+/// At most max_running_jobs simulations run at the same time, further submissions are answered with 503 until one finishes, and request bodies larger than max_body_size are answered with 413.
+/// Note: The simulations are kept in memory until the server is dropped and there is no authentication, run it behind a reverse proxy if it is reachable from outside your network.
+
+use crate::*;   // To use everything from the crate
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+
+/// Body of POST /simulations
+#[derive(serde::Deserialize)]
+pub struct SimulationRequest {
+    /// The boat to simulate, fields that are left out get the defaults from Boat::new()
+    pub boat: Boat,
+    /// If set, replaces boat.route_plan
+    #[serde(default)]
+    pub route_plan: Option<RoutePlan>,
+    /// The simulation settings, validated before the simulation starts, see Simulation.validate()
+    pub simulation: Simulation,
+}
+
+/// Status of a simulation submitted to the server
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimulationJobStatus {
+    /// The simulation is running
+    Running,
+    /// All voyages have been simulated
+    Finished,
+    /// The simulation stopped with an error, the message is in SimulationJob.error
+    Failed,
+}
+
+impl fmt::Display for SimulationJobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SimulationJobStatus::Running => write!(f, "running"),
+            SimulationJobStatus::Finished => write!(f, "finished"),
+            SimulationJobStatus::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// A simulation submitted to the server
+#[derive(Debug, Clone)]
+pub struct SimulationJob {
+    pub status: SimulationJobStatus,
+    /// Error message if the simulation failed
+    pub error: Option<String>,
+    /// Departure times of the voyages, one voyage for each start time of the simulation
+    pub start_times: Vec<UtcDateTime>,
+    /// Number of voyages that have been simulated
+    pub voyages_completed: usize,
+    /// Progress of the voyage that is being simulated, None before the first time step
+    pub progress: Option<VoyageProgress>,
+    /// Ship log of all voyages, empty until the simulation has finished
+    pub ship_log: Vec<ShipLogEntry>,
+    /// Result of each voyage, empty until the simulation has finished
+    pub results: Vec<SimulationResult>,
+}
+
+impl SimulationJob {
+    /// Returns the fraction of all voyages of the simulation that has been simulated, in \[0, 1\]
+    pub fn fraction(&self) -> f64 {
+        if self.status == SimulationJobStatus::Finished {
+            return 1.0;
+        }
+        if self.start_times.is_empty() {
+            return 0.0;
+        }
+        let current_voyage = self.progress.map(|p| p.fraction()).unwrap_or(0.0);
+        return ((self.voyages_completed as f64 + current_voyage) / self.start_times.len() as f64).clamp(0.0, 1.0);
+    }
+}
+
+/// REST API server that runs simulations submitted as JSON, see the module documentation for the endpoints
+/// # Example:
+/// `SimulationServer::new().max_running_jobs(8).serve("0.0.0.0:8080")?;`
+#[derive(Debug, Clone)]
+pub struct SimulationServer {
+    /// Simulations submitted to the server, the id of a simulation is its index
+    jobs: Arc<Mutex<Vec<SimulationJob>>>,
+    /// Most simulations that run at the same time
+    max_running_jobs: usize,
+    /// \[bytes\]. Largest request body that is read
+    max_body_size: usize,
+}
+
+impl Default for SimulationServer {
+    fn default() -> SimulationServer {
+        return SimulationServer {
+            jobs: Arc::new(Mutex::new(Vec::new())),
+            max_running_jobs: SimulationServer::DEFAULT_MAX_RUNNING_JOBS,
+            max_body_size: SimulationServer::DEFAULT_MAX_BODY_SIZE,
+        };
+    }
+}
+
+impl SimulationServer {
+    /// Default number of simulations that run at the same time
+    pub const DEFAULT_MAX_RUNNING_JOBS: usize = 4;
+    /// Default largest request body in \[bytes\], 10 MB
+    pub const DEFAULT_MAX_BODY_SIZE: usize = 10_000_000;
+
+    /// Creates a new server without simulations, running at most SimulationServer::DEFAULT_MAX_RUNNING_JOBS simulations at a time and reading request bodies of at most SimulationServer::DEFAULT_MAX_BODY_SIZE
+    pub fn new() -> SimulationServer {
+        return SimulationServer::default();
+    }
+
+    /// Sets the most simulations that run at the same time, further submissions are rejected until one finishes
+    pub fn max_running_jobs(mut self, max_running_jobs: usize) -> SimulationServer {
+        self.max_running_jobs = max_running_jobs;
+        return self;
+    }
+
+    /// Sets the largest request body in \[bytes\] that is read, larger requests are rejected
+    pub fn max_body_size(mut self, max_body_size: usize) -> SimulationServer {
+        self.max_body_size = max_body_size;
+        return self;
+    }
+
+    /// Returns the number of simulations that are running
+    pub fn running_jobs(&self) -> usize {
+        return self.lock_jobs().iter().filter(|job| job.status == SimulationJobStatus::Running).count();
+    }
+
+    /// Listens for HTTP requests on the address, e.g. "127.0.0.1:8080", and answers them until the process is stopped
+    /// Requests are answered one at a time, the simulations run on their own threads
+    pub fn serve(&self, address: &str) -> Result<(), MarineSimError> {
+        let server = match tiny_http::Server::http(address) {
+            Ok(server) => server,
+            Err(e) => return Err(io::Error::new(io::ErrorKind::Other, format!("Could not listen on {}: {}", address, e)).into()),
+        };
+        tracing::info!(address, "Simulation server listening");
+
+        for mut request in server.incoming_requests() {
+            // Read at most one byte more than max_body_size, so larger bodies are rejected without reading them
+            let mut body = String::new();
+            let (status_code, json) = match request.body_length() {
+                Some(body_length) if body_length > self.max_body_size => get_body_too_large(self.max_body_size),
+                _ => match request.as_reader().take(self.max_body_size as u64 + 1).read_to_string(&mut body) {
+                    Ok(_) => self.handle_request(&request.method().to_string(), request.url(), &body),
+                    Err(e) => (400, get_error_json(&format!("Could not read request body: {}", e))),
+                },
+            };
+            tracing::debug!(method = %request.method(), url = request.url(), status_code, "Request");
+            let content_type = tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+            if let Err(e) = request.respond(tiny_http::Response::from_string(json).with_status_code(status_code).with_header(content_type)) {
+                tracing::warn!(error = %e, "Could not send response");
+            }
+        }
+        return Ok(());
+    }
+
+    /// Answers a request to the API, returns the HTTP status code and the JSON body of the response
+    /// Used by serve(), can also be called directly to put the API behind another HTTP server
+    /// # Example:
+    /// `let (status_code, json) = server.handle_request("GET", "/simulations/0", "");`
+    pub fn handle_request(&self, method: &str, url: &str, body: &str) -> (u16, String) {
+        // Ignore the query string and trailing slashes
+        let path = url.split('?').next().unwrap_or("").trim_end_matches('/');
+        let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+
+        match (method, parts.as_slice()) {
+            ("POST", ["simulations"]) => {
+                if body.len() > self.max_body_size {
+                    return get_body_too_large(self.max_body_size);
+                }
+                if self.running_jobs() >= self.max_running_jobs {
+                    return (503, get_error_json(&format!("{} simulations are already running, try again when one has finished", self.max_running_jobs)));
+                }
+                let request: SimulationRequest = match serde_json::from_str(body) {
+                    Ok(request) => request,
+                    Err(e) => return (400, get_error_json(&format!("Could not parse simulation request: {}", e))),
+                };
+                match self.submit(request) {
+                    Ok(id) => return (202, serde_json::json!({ "id": id }).to_string()),
+                    Err(e) => return (400, get_error_json(&e.to_string())),
+                }
+            }
+            ("GET", ["simulations"]) => {
+                let jobs = self.lock_jobs();
+                let list: Vec<serde_json::Value> = jobs.iter().enumerate().map(|(id, job)| serde_json::json!({ "id": id, "status": job.status.to_string() })).collect();
+                return (200, serde_json::Value::Array(list).to_string());
+            }
+            ("GET", ["simulations", id]) => {
+                return self.with_job(id, |id, job| (200, get_job_status_json(id, job).to_string()));
+            }
+            ("GET", ["simulations", id, "ship_log"]) => {
+                return self.with_finished_job(id, |_, job| match serde_json::to_string(&job.ship_log) {
+                    Ok(json) => (200, json),
+                    Err(e) => (500, get_error_json(&e.to_string())),
+                });
+            }
+            ("GET", ["simulations", id, "statistics"]) => {
+                return self.with_finished_job(id, |id, job| (200, get_job_statistics_json(id, job).to_string()));
+            }
+            (_, ["simulations"]) | (_, ["simulations", _]) | (_, ["simulations", _, "ship_log"]) | (_, ["simulations", _, "statistics"]) => {
+                return (405, get_error_json(&format!("Method {} is not allowed for {}", method, path)));
+            }
+            _ => return (404, get_error_json(&format!("Unknown endpoint {}", path))),
+        }
+    }
+
+    /// Starts simulating a request on a new thread, returns the id of the simulation
+    /// If the simulation panics the simulation is marked as failed with the panic message
+    /// Returns an error if the simulation settings are not valid, see Simulation.validate(), or if max_running_jobs simulations are already running
+    pub fn submit(&self, request: SimulationRequest) -> Result<usize, MarineSimError> {
+        let mut boat = request.boat;
+        let mut simulation = request.simulation;
+        if request.route_plan.is_some() {
+            boat.route_plan = request.route_plan;
+        }
+        simulation.validate()?;
+
+        // Add the job
+        let id = {
+            let mut jobs = self.lock_jobs();
+            if jobs.iter().filter(|job| job.status == SimulationJobStatus::Running).count() >= self.max_running_jobs {
+                return Err(MarineSimError::InvalidInput(format!("{} simulations are already running", self.max_running_jobs)));
+            }
+            jobs.push(SimulationJob {
+                status: SimulationJobStatus::Running,
+                error: None,
+                start_times: simulation.start_times.clone(),
+                voyages_completed: 0,
+                progress: None,
+                ship_log: Vec::new(),
+                results: Vec::new(),
+            });
+            jobs.len() - 1
+        };
+
+        // Keep the progress of the job up to date, the voyages before the one being simulated are done
+        let jobs = Arc::clone(&self.jobs);
+        simulation.add_progress_callback(move |progress: &VoyageProgress| {
+            let mut jobs = jobs.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+            let job = &mut jobs[id];
+            job.voyages_completed = job.start_times.iter().position(|start_time| *start_time == progress.departure).unwrap_or(job.voyages_completed);
+            job.progress = Some(*progress);
+        });
+
+        // Simulate
+        let jobs = Arc::clone(&self.jobs);
+        std::thread::spawn(move || {
+            let _span = tracing::info_span!("server_simulation", id).entered();
+            // Catch panics so the job does not stay running forever
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| sim_waypoint_missions(&mut boat, &simulation)));
+            let mut jobs = jobs.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+            let job = &mut jobs[id];
+            match result {
+                Ok(Ok(results)) => {
+                    job.status = SimulationJobStatus::Finished;
+                    job.voyages_completed = results.len();
+                    job.results = results;
+                    job.ship_log = std::mem::take(&mut boat.ship_log);
+                }
+                Ok(Err(e)) => {
+                    tracing::warn!(error = %e, "Simulation failed");
+                    job.status = SimulationJobStatus::Failed;
+                    job.error = Some(e.to_string());
+                }
+                Err(panic) => {
+                    let message: String = match (panic.downcast_ref::<&str>(), panic.downcast_ref::<String>()) {
+                        (Some(message), _) => message.to_string(),
+                        (_, Some(message)) => message.clone(),
+                        _ => "unknown panic".to_string(),
+                    };
+                    tracing::warn!(panic = %message, "Simulation panicked");
+                    job.status = SimulationJobStatus::Failed;
+                    job.error = Some(format!("The simulation panicked: {}", message));
+                }
+            }
+        });
+
+        return Ok(id);
+    }
+
+    /// Returns a copy of a simulation, None if there is no simulation with the id
+    pub fn get_job(&self, id: usize) -> Option<SimulationJob> {
+        return self.lock_jobs().get(id).cloned();
+    }
+
+    /// Locks the simulations, also if a simulation panicked while holding the lock since the simulations are only changed as a whole
+    fn lock_jobs(&self) -> std::sync::MutexGuard<'_, Vec<SimulationJob>> {
+        return self.jobs.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+    }
+
+    /// Calls f with the simulation with the id from the URL, or returns 404 if there is no such simulation
+    fn with_job<F: FnOnce(usize, &SimulationJob) -> (u16, String)>(&self, id: &str, f: F) -> (u16, String) {
+        let jobs = self.lock_jobs();
+        match id.parse::<usize>().ok().and_then(|id| jobs.get(id).map(|job| (id, job))) {
+            Some((id, job)) => return f(id, job),
+            None => return (404, get_error_json(&format!("No simulation with id {}", id))),
+        }
+    }
+
+    /// Calls f with the simulation with the id from the URL if it has finished, returns 409 if it is still running or has failed
+    fn with_finished_job<F: FnOnce(usize, &SimulationJob) -> (u16, String)>(&self, id: &str, f: F) -> (u16, String) {
+        return self.with_job(id, |id, job| match job.status {
+            SimulationJobStatus::Finished => f(id, job),
+            _ => (409, get_error_json(&format!("Simulation {} is {}", id, job.status))),
+        });
+    }
+}
+
+/// Returns the JSON body of an error response
+fn get_error_json(message: &str) -> String {
+    return serde_json::json!({ "error": message }).to_string();
+}
+
+/// Returns the response to a request body larger than max_body_size
+fn get_body_too_large(max_body_size: usize) -> (u16, String) {
+    return (413, get_error_json(&format!("Request body is larger than {} bytes", max_body_size)));
+}
+
+/// Returns the status and progress of a simulation as JSON
+fn get_job_status_json(id: usize, job: &SimulationJob) -> serde_json::Value {
+    let progress = job.progress.map(|p| serde_json::json!({
+        "departure": format_timestamp(p.departure),
+        "legs_completed": p.legs_completed,
+        "total_legs": p.total_legs,
+        "distance_covered": p.distance_covered,
+        "total_distance": p.total_distance,
+        "simulated_time": p.simulated_time.as_seconds_f64(),
+        "percent": p.percent(),
+    }));
+    return serde_json::json!({
+        "id": id,
+        "status": job.status.to_string(),
+        "error": job.error,
+        "voyages": job.start_times.len(),
+        "voyages_completed": job.voyages_completed,
+        "percent": 100.0*job.fraction(),
+        "voyage_progress": progress,
+    });
+}
+
+/// Returns the result of each voyage of a simulation as JSON, durations in \[s\], distances in \[m\] and speeds in \[m/s\]
+fn get_job_statistics_json(id: usize, job: &SimulationJob) -> serde_json::Value {
+    let voyages: Vec<serde_json::Value> = job.results.iter().zip(job.start_times.iter()).map(|(result, start_time)| serde_json::json!({
+        "departure": format_timestamp(*start_time),
+        "status": format!("{:?}", result.status),
+        "completed": result.is_completed(),
+        "iterations": result.iterations,
+        "duration": result.kpis.duration.as_seconds_f64(),
+        "distance": result.kpis.distance,
+        "speed_mean": result.kpis.speed_mean,
+        "num_tacks": result.kpis.num_tacks,
+        "max_wind_speed": result.kpis.max_wind_speed,
+        "time_under_sail_percent": result.kpis.time_under_sail_percent,
+        "percent_of_route": result.progress.percent(),
+    })).collect();
+    let durations: Vec<f64> = job.results.iter().filter(|result| result.is_completed()).map(|result| result.kpis.duration.as_seconds_f64()).collect();
+    let duration_mean: Option<f64> = if durations.is_empty() { None } else { Some(durations.iter().sum::<f64>() / durations.len() as f64) };
+    return serde_json::json!({
+        "id": id,
+        "voyages_completed": durations.len(),
+        "duration_mean": duration_mean,
+        "duration_min": durations.iter().copied().reduce(f64::min),
+        "duration_max": durations.iter().copied().reduce(f64::max),
+        "voyages": voyages,
+    });
+}
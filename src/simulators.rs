@@ -6,11 +6,17 @@ use crate::*;   // To use everything from the crate
 
 /// Enum of simulation methods
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SimMethod {
     /// Constant velocity, uses the mean velocity of the boat
     ConstVelocity,
     /// Use the mean and std of the boat speed
     MeanAndSTDVelocity,
+    /// Draw the boat speed at random from real ship logs, optionally conditioned on the month and true wind angle, see EmpiricalSpeedModel
+    EmpiricalSpeed,
+    /// No propulsion, the vessel drifts from the first waypoint of its route plan with the ocean current and the wind on it, e.g. drifting buoys, disabled vessels or lost cargo
+    /// The wind and ocean current are taken from simulation.weather, see sim_drift() and Boat.windage_factor
+    Drift,
     // Use downloaded weather data from file
     // WeatherDataFromFile,
     /// Use the copernicus weather data from the past for the exact location of the boat to simulate the boat movements
@@ -21,8 +27,663 @@ pub enum SimMethod {
     // Copernicus_Weather_Forecast,
 }
 
+/// Enum of what the simulation does when the boat is about to sail into sea ice above its max_ice_concentration
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IceAvoidance {
+    /// Stop the simulation with an error saying where the ice is
+    Stop,
+    /// Reroute around the ice edge by detouring to the closest ice free point to the side of the leg
+    Reroute,
+}
+
+impl Default for IceAvoidance {
+    fn default() -> IceAvoidance {
+        return IceAvoidance::Stop;
+    }
+}
+
+/// Settings for bunkering stops, see Simulation.bunkering
+/// When a tank of the vessel drops below the reserve the simulator inserts a detour to the closest bunkering port in the route plan, where the tanks are filled, see Tank and PortCall.refill_tanks
+/// Without bunkering the simulation stops with SimulationStatus::TankEmpty when a tank runs empty
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bunkering {
+    /// Ports where the vessel can fill its tanks
+    pub ports: Vec<geo::Point>,
+    /// Fraction of the capacity, in \[0, 1\], a tank can drop to before the vessel detours to a bunkering port
+    pub reserve_fraction: f64,
+    /// Time spent in the bunkering port
+    pub wait: time::Duration,
+}
+
+impl Bunkering {
+    /// Creates bunkering settings for the ports, with a reserve of a quarter of the tanks and 6 hours in port
+    /// # Example:
+    /// `simulation.bunkering = Some(Bunkering::new(vec![geo::Point::new(-21.94, 64.15), geo::Point::new(-6.77, 62.01)]));`
+    pub fn new(ports: Vec<geo::Point>) -> Bunkering {
+        Bunkering {
+            ports,
+            reserve_fraction: 0.25,
+            wait: time::Duration::hours(6),
+        }
+    }
+}
+
+/// Settings for wind against current. When the wind blows against a strong current (e.g. the Gulf Stream or the Agulhas current) the waves get shorter, steeper and higher
+/// which slows the boat down more than adding the wind and current together would suggest
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindAgainstCurrent {
+    /// \[m/s\]. Minimum component of the ocean current against the wind for the condition to apply
+    pub min_current: f64,
+    /// \[m/s\]. Minimum wind speed for the condition to apply
+    pub min_wind_speed: f64,
+    /// Increase in wave height and wave resistance per \[m/s\] of current against the wind. E.g. 0.3 means that 2 m/s of current against the wind makes the waves 1.6 times higher
+    pub amplification_per_current_speed: f64,
+}
+
+impl WindAgainstCurrent {
+    /// Creates new wind against current settings
+    pub fn new(min_current: f64, min_wind_speed: f64, amplification_per_current_speed: f64) -> WindAgainstCurrent {
+        WindAgainstCurrent {
+            min_current,
+            min_wind_speed,
+            amplification_per_current_speed,
+        }
+    }
+
+    /// Returns the component of the ocean current against the wind in \[m/s\], negative if the current flows with the wind
+    /// The wind angle is where the wind comes from and the current angle where the current flows to, as in the simulators
+    pub fn current_against_wind(wind: PhysVec, ocean_current: PhysVec) -> f64 {
+        return ocean_current.magnitude * ((ocean_current.angle - wind.angle)*consts::PI/180.0).cos();
+    }
+
+    /// Returns the amplification factor (1 or more) of the wave height and wave resistance for the wind and ocean current
+    /// Returns None if the wind and current don't meet the thresholds
+    pub fn amplification(&self, wind: PhysVec, ocean_current: PhysVec) -> Option<f64> {
+        let current_against_wind = WindAgainstCurrent::current_against_wind(wind, ocean_current);
+        if wind.magnitude < self.min_wind_speed || current_against_wind < self.min_current {
+            return None;
+        }
+        return Some(1.0 + self.amplification_per_current_speed*current_against_wind);
+    }
+}
+
+impl Default for WindAgainstCurrent {
+    /// About 2 knots of current against a force 5 wind or more, 30% more wave resistance per m/s of current against the wind
+    fn default() -> Self {
+        WindAgainstCurrent::new(1.0, 8.0, 0.3)
+    }
+}
+
+/// Enum of the kinds of events that can happen during a simulation
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SimulationEventKind {
+    /// The vessel ran aground. depth is the water depth in \[m\] and min_depth the depth in \[m\] the vessel needs (draft + minimum under keel clearance)
+    Grounding { depth: f64, min_depth: f64 },
+    /// The wind started blowing against a strong ocean current, see WindAgainstCurrent. wind_speed and current_against_wind are in \[m/s\]
+    WindAgainstCurrent { wind_speed: f64, current_against_wind: f64, amplification: f64 },
+    /// The vessel entered a restricted zone with ZoneAction::Warn, see RestrictedZone
+    ZoneEntered { zone: String, kind: ZoneKind },
+    /// The vessel made a port call, see PortCall. The cargo is in metric tons
+    PortCall { cargo_loaded: f64, cargo_unloaded: f64, wait: time::Duration },
+    /// Cargo was loaded or unloaded with Boat.load_cargo(), Boat.unload_cargo() or the other cargo methods of Boat. The cargo is in metric tons
+    CargoOperation { cargo_loaded: f64, cargo_unloaded: f64, cargo_on_board: f64 },
+    /// The vessel filled its tanks at a port call, see PortCall.refill_tanks. fuel and fresh_water are the metric tons taken on board
+    Bunkered { fuel: f64, fresh_water: f64 },
+    /// The vessel waited at anchor, or moored if moored is true, see AnchorWait. wait is how long it waited
+    Anchored { wait: time::Duration, moored: bool },
+    /// The vessel started sailing a leg of its route plan, the leg number is in SimulationEvent.leg
+    LegStarted,
+    /// The vessel reached the waypoint at the end of the leg in SimulationEvent.leg, see WaypointArrival
+    WaypointReached { waypoint: geo::Point },
+    /// The vessel tacked and now has the wind on the given side
+    Tacked { wind_side: VesselSide },
+    /// The weather exceeded the operational limits of the vessel and it started using its storm tactic, see Boat.update_storm_bound(). wind_speed is in \[m/s\] and wave_height in \[m\]
+    StormLimitExceeded { wind_speed: f64, wave_height: Option<f64> },
+    /// The simulation of the voyage finished with the given status
+    SimulationFinished { status: SimulationStatus },
+}
+
+/// A function called with every event of a simulated voyage, see Simulation.add_event_callback()
+#[derive(Clone)]
+pub struct EventCallback(pub std::sync::Arc<dyn Fn(&SimulationEvent) + Send + Sync>);
+
+impl fmt::Debug for EventCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EventCallback")
+    }
+}
+
+/// A function called with the progress of a simulated voyage at every time step, see Simulation.add_progress_callback()
+#[derive(Clone)]
+pub struct ProgressCallback(pub std::sync::Arc<dyn Fn(&VoyageProgress) + Send + Sync>);
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ProgressCallback")
+    }
+}
+
+/// A ship log writer shared by the voyages of a simulation, see Simulation.set_ship_log_writer()
+#[derive(Clone)]
+pub struct SharedShipLogWriter(pub std::sync::Arc<std::sync::Mutex<dyn ShipLogWriter>>);
+
+impl SharedShipLogWriter {
+    /// Writes the ship log of a voyage, see ShipLogWriter.write_ship_log()
+    pub fn write_ship_log(&self, ship_log: &[ShipLogEntry]) -> Result<(), io::Error> {
+        return match self.0.lock() {
+            Ok(mut writer) => writer.write_ship_log(ship_log),
+            Err(_) => Err(io::Error::new(io::ErrorKind::Other, "The ship log writer was poisoned by a panic in another thread")),
+        };
+    }
+
+    /// Finishes writing, see ShipLogWriter.finish()
+    pub fn finish(&self) -> Result<(), io::Error> {
+        return match self.0.lock() {
+            Ok(mut writer) => writer.finish(),
+            Err(_) => Err(io::Error::new(io::ErrorKind::Other, "The ship log writer was poisoned by a panic in another thread")),
+        };
+    }
+}
+
+impl fmt::Debug for SharedShipLogWriter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SharedShipLogWriter")
+    }
+}
+
+/// The ship log of a voyage while it is simulated, see sim_waypoint_mission()
+/// The entries are added to the key performance indicators, the decimated ship log and the restricted zone checks of the voyage as they are logged,
+/// so when the simulation streams its ship logs they can be written in batches and removed from boat.ship_log while the voyage runs, see Simulation.set_ship_log_writer()
+/// The last entry always stays in boat.ship_log since the simulators continue from it
+#[derive(Clone)]
+pub(crate) struct VoyageLog {
+    /// Index of the first entry of the voyage in boat.ship_log
+    log_start: usize,
+    /// Entries of the voyage in boat.ship_log before this index have been added up
+    added: usize,
+    /// Entries of the voyage in boat.ship_log before this index have been written to the ship log writer
+    written: usize,
+    totals: ShipLogTotals,
+    /// Makes the decimated ship log, if Simulation.decimated_log_interval is set
+    resampler: Option<ShipLogResampler>,
+    decimated_log: Vec<ShipLogEntry>,
+    /// Restricted zones with ZoneAction::Warn and if the vessel was inside each of them at the last entry
+    zones: Vec<(RestrictedZone, bool)>,
+    /// Ship log writer and batch size, if the simulation streams its ship logs
+    writer: Option<(SharedShipLogWriter, usize)>,
+    /// Kind and message of the first error from the ship log writer, returned when the voyage finishes
+    write_error: Option<(io::ErrorKind, String)>,
+}
+
+impl VoyageLog {
+    /// Starts the ship log of a voyage of the boat, after the entries already in boat.ship_log
+    pub(crate) fn new(boat: &Boat, simulation: &Simulation) -> VoyageLog {
+        let zones: Vec<(RestrictedZone, bool)> = boat.route_plan.as_ref().map_or(Vec::new(), |route_plan| route_plan.zones.iter().filter(|zone| zone.action == ZoneAction::Warn).map(|zone| (zone.clone(), false)).collect());
+        VoyageLog {
+            log_start: boat.ship_log.len(),
+            added: boat.ship_log.len(),
+            written: boat.ship_log.len(),
+            totals: ShipLogTotals::default(),
+            resampler: simulation.decimated_log_interval.map(|interval| ShipLogResampler::new(interval, false)),
+            decimated_log: Vec::new(),
+            zones,
+            writer: simulation.ship_log_writer.clone().map(|writer| (writer, simulation.ship_log_batch_size.unwrap_or(Simulation::DEFAULT_SHIP_LOG_BATCH_SIZE).max(1))),
+            write_error: None,
+        }
+    }
+
+    /// Called by Boat.push_ship_log_entry() with each new entry, writes the entries and removes them from boat.ship_log when there is a full batch
+    pub(crate) fn update(&mut self, boat: &mut Boat) {
+        let batch_size: usize = match &self.writer {
+            Some((_, batch_size)) => *batch_size,
+            None => return,
+        };
+        if boat.ship_log.len().saturating_sub(self.written) < batch_size {
+            return;
+        }
+        self.add_entries(boat);
+        self.write_entries(boat);
+        // Keep the last entry
+        let keep_from = (boat.ship_log.len() - 1).max(self.log_start);
+        boat.ship_log.drain(self.log_start..keep_from);
+        self.added = boat.ship_log.len();
+        self.written = boat.ship_log.len();
+    }
+
+    /// Adds up the entries of boat.ship_log that have not been added, a ZoneEntered event is added to boat.events when the vessel enters a restricted zone with ZoneAction::Warn
+    fn add_entries(&mut self, boat: &mut Boat) {
+        for i in self.added..boat.ship_log.len() {
+            let entry = &boat.ship_log[i];
+            self.totals.add(entry);
+            if let Some(resampler) = &mut self.resampler {
+                resampler.add(entry, &mut self.decimated_log);
+            }
+            for (zone, inside) in self.zones.iter_mut() {
+                let entry_inside = zone.contains(entry.coordinates_current);
+                if entry_inside && !*inside {
+                    tracing::warn!("Vessel entered restricted zone {} at {}", zone.name, entry.timestamp);
+                    boat.events.push(SimulationEvent {
+                        timestamp: entry.timestamp,
+                        location: entry.coordinates_current,
+                        leg: None,
+                        kind: SimulationEventKind::ZoneEntered { zone: zone.name.clone(), kind: zone.kind },
+                    });
+                }
+                *inside = entry_inside;
+            }
+        }
+        self.added = boat.ship_log.len();
+    }
+
+    /// Writes the entries of boat.ship_log that have not been written to the ship log writer, if there is one. After an error nothing more is written
+    fn write_entries(&mut self, boat: &Boat) {
+        if let Some((writer, _)) = &self.writer {
+            if self.write_error.is_none() {
+                if let Err(e) = writer.write_ship_log(&boat.ship_log[self.written..]) {
+                    self.write_error = Some((e.kind(), e.to_string()));
+                }
+            }
+        }
+        self.written = boat.ship_log.len();
+    }
+
+    /// Adds up the rest of the ship log of the voyage and returns its key performance indicators, see VoyageKpis::from_ship_log()
+    /// The decimated ship log of the voyage is added to boat.decimated_ship_log
+    pub(crate) fn finish(&mut self, boat: &mut Boat) -> VoyageKpis {
+        self.add_entries(boat);
+        if let Some(resampler) = &self.resampler {
+            resampler.finish(&mut self.decimated_log);
+        }
+        boat.decimated_ship_log.append(&mut self.decimated_log);
+        return self.totals.kpis();
+    }
+
+    /// Writes the rest of the ship log of the voyage and removes it from boat.ship_log, if the simulation streams its ship logs
+    /// Returns the first error from the ship log writer during the voyage
+    pub(crate) fn write(mut self, boat: &mut Boat) -> Result<(), MarineSimError> {
+        if self.writer.is_none() {
+            return Ok(());
+        }
+        self.write_entries(boat);
+        boat.ship_log.truncate(self.log_start);
+        return match self.write_error {
+            Some((kind, message)) => Err(MarineSimError::Io(io::Error::new(kind, message))),
+            None => Ok(()),
+        };
+    }
+}
+
+/// Struct for an event that happened during a simulation, stored in boat.events
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimulationEvent {
+    /// When the event happened
+    pub timestamp: UtcDateTime,
+    /// Where the event happened
+    pub location: geo::Point,
+    /// The leg the vessel was on when the event happened
+    pub leg: Option<u32>,
+    /// What happened
+    pub kind: SimulationEventKind,
+}
+
+/// Struct for the arrival of a vessel at a waypoint of its route plan, stored in boat.waypoint_arrivals
+/// Useful for schedules and port bookings
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WaypointArrival {
+    /// The leg that ends at the waypoint
+    pub leg: u32,
+    /// The waypoint, the end of the leg in the route plan
+    pub waypoint: geo::Point,
+    /// Where the vessel was when it reached the waypoint, within the minimum proximity of the leg
+    pub location: geo::Point,
+    /// When the vessel reached the waypoint, interpolated within the time step
+    pub timestamp: UtcDateTime,
+}
+
+/// Key performance indicators of a simulated voyage, returned by sim_waypoint_mission() alongside the ship log
+#[derive(Debug, Clone, PartialEq)]
+pub struct VoyageKpis {
+    /// Time from the first to the last ship log entry of the voyage
+    pub duration: time::Duration,
+    /// \[m\]. Distance sailed, the sum of the distances between consecutive ship log entries
+    pub distance: f64,
+    /// \[m/s\]. Average speed over ground, distance/duration. None if the duration is zero
+    pub speed_mean: Option<f64>,
+    /// Number of tacks during the voyage, None if unknown
+    pub num_tacks: Option<u64>,
+    /// \[kg\]. Fuel used from the fuel tank during the voyage, None if the boat has no fuel tank, see Boat.fuel_tank
+    pub fuel: Option<f64>,
+    /// \[m/s\]. Maximum wind speed encountered during the voyage, None if the ship log has no wind data
+    pub max_wind_speed: Option<f64>,
+    /// \[%\]. Percentage of the duration spent under sail (NavigationStatus::UnderwaySailing). None if the duration is zero
+    pub time_under_sail_percent: Option<f64>,
+    /// When the vessel reached each waypoint of the route plan during the voyage, see WaypointArrival. Empty if not known
+    pub waypoint_arrivals: Vec<WaypointArrival>,
+    /// Events of the voyage in chronological order, empty if unknown, see SimulationEvent
+    pub events: Vec<SimulationEvent>,
+    /// Summary of the weather encountered during the voyage, None if the ship log has no weather data, see WeatherSummary
+    pub weather: Option<WeatherSummary>,
+}
+
+impl VoyageKpis {
+    /// Computes the key performance indicators of a voyage from its ship log
+    /// num_tacks and fuel can not be found from the ship log and are set to None
+    pub fn from_ship_log(ship_log: &[ShipLogEntry]) -> VoyageKpis {
+        let mut totals = ShipLogTotals::default();
+        for entry in ship_log {
+            totals.add(entry);
+        }
+        return totals.kpis();
+    }
+}
+
+/// Running totals of a ship log, so VoyageKpis and WeatherSummary can be computed one entry at a time while a voyage is simulated, see VoyageLog
+/// The wind speeds are kept for their percentiles, see DistributionStats
+#[derive(Debug, Clone, Default)]
+struct ShipLogTotals {
+    first_timestamp: Option<UtcDateTime>,
+    /// Timestamp and position of the last entry added
+    last: Option<(UtcDateTime, geo::Point)>,
+    /// \[m\]. Distance between consecutive entries
+    distance: f64,
+    time_under_sail: time::Duration,
+    /// \[m/s\]
+    wind_speeds: Vec<f64>,
+    /// \[m\]
+    max_wave_height: Option<f64>,
+    /// \[s\]. Time with wind and heading logged, and of it the time upwind
+    time_with_wind: f64,
+    time_upwind: f64,
+    /// \[s\]. Time with heading and course logged, and of it the time tacking
+    time_with_course: f64,
+    time_tacking: f64,
+}
+
+impl ShipLogTotals {
+    /// Adds the next entry of the ship log. The time between two consecutive entries is counted with the later entry, as in get_time_in_navigation_status()
+    fn add(&mut self, entry: &ShipLogEntry) {
+        if let Some(wind) = entry.wind {
+            self.wind_speeds.push(wind.magnitude);
+        }
+        if let Some(wave_height) = entry.wave_height {
+            self.max_wave_height = Some(self.max_wave_height.map_or(wave_height, |max| max.max(wave_height)));
+        }
+        if let Some((last_timestamp, last_location)) = self.last {
+            self.distance += Earth.distance(last_location, entry.coordinates_current);
+            let duration = entry.timestamp - last_timestamp;
+            if entry.navigation_status == Some(NavigationStatus::UnderwaySailing) {
+                self.time_under_sail += duration;
+            }
+
+            // Time upwind, downwind and tacking
+            let seconds = duration.as_seconds_f64();
+            let heading = entry.heading.or(entry.track_angle);
+            if let (Some(wind), Some(heading)) = (entry.wind, heading) {
+                self.time_with_wind += seconds;
+                if get_true_wind_angle(heading, wind.angle) < 90.0 {
+                    self.time_upwind += seconds;
+                }
+            }
+            if let (Some(heading), Some(course)) = (heading, entry.course) {
+                self.time_with_course += seconds;
+                // The true wind angle is also the smallest angle between the heading and the course
+                if get_true_wind_angle(heading, course) > WeatherSummary::TACKING_MIN_DEVIATION {
+                    self.time_tacking += seconds;
+                }
+            }
+        }
+        else {
+            self.first_timestamp = Some(entry.timestamp);
+        }
+        self.last = Some((entry.timestamp, entry.coordinates_current));
+    }
+
+    /// Returns the key performance indicators of the entries added, num_tacks and fuel are set to None, see VoyageKpis::from_ship_log()
+    fn kpis(&self) -> VoyageKpis {
+        // Duration
+        let duration = match (self.first_timestamp, self.last) {
+            (Some(first_timestamp), Some((last_timestamp, _))) => last_timestamp - first_timestamp,
+            _ => time::Duration::ZERO,
+        };
+
+        // Average speed and time under sail
+        let seconds = duration.as_seconds_f64();
+        let (speed_mean, time_under_sail_percent) = if seconds > 0.0 {
+            (Some(self.distance / seconds), Some(100.0 * self.time_under_sail.as_seconds_f64() / seconds))
+        } else {
+            (None, None)
+        };
+
+        return VoyageKpis {
+            duration,
+            distance: self.distance,
+            speed_mean,
+            num_tacks: None,
+            fuel: None,
+            max_wind_speed: self.wind_speeds.iter().copied().reduce(f64::max),
+            time_under_sail_percent,
+            waypoint_arrivals: Vec::new(),
+            events: Vec::new(),
+            weather: self.weather_summary(),
+        };
+    }
+
+    /// Returns the weather summary of the entries added, see WeatherSummary::from_ship_log()
+    fn weather_summary(&self) -> Option<WeatherSummary> {
+        if self.wind_speeds.is_empty() && self.max_wave_height.is_none() {
+            return None;
+        }
+        let percent = |time: f64, total: f64| if total > 0.0 { Some(100.0*time/total) } else { None };
+
+        return Some(WeatherSummary {
+            wind_speed: DistributionStats::from_values(&self.wind_speeds),
+            max_wave_height: self.max_wave_height,
+            upwind_percent: percent(self.time_upwind, self.time_with_wind),
+            downwind_percent: percent(self.time_with_wind - self.time_upwind, self.time_with_wind),
+            tacking_percent: percent(self.time_tacking, self.time_with_course),
+        });
+    }
+}
+
+/// Statistics of one type of cargo carried on a voyage, see get_cargo_statistics()
+/// Amounts are in the unit of the cargo type, TEU for containers, tons for bulk and \[m^3\] for liquids, see CargoType.unit()
+/// Makes vessels carrying different cargo comparable, e.g. TEU*nmi per day of a container feeder against tons*nmi per day of a bulk sail carrier
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CargoStatistics {
+    pub cargo_type: CargoType,
+    /// Amount of the cargo on board
+    pub amount: f64,
+    /// Capacity of the vessel for the cargo type, None if there is no limit
+    pub capacity: Option<f64>,
+    /// \[%\]. Percentage of the capacity used, None if there is no limit or the capacity is zero
+    pub utilization_percent: Option<f64>,
+    /// \[amount*nmi\]. Transport work, the amount of cargo times the distance sailed in nautical miles
+    pub transport_work: f64,
+    /// \[amount*nmi/day\]. Transport work per day of the voyage, None if the duration is zero
+    pub transport_work_per_day: Option<f64>,
+}
+
+/// Returns the statistics of each type of cargo the boat has on board for a voyage with the key performance indicators kpis, see CargoStatistics
+/// Assumes the cargo in boat.cargo after the voyage, i.e. after the port calls (see PortCall.cargo_load), was on board for the whole voyage. Cargo types the boat does not carry are left out
+/// # Example:
+/// `let result = sim_waypoint_mission(&mut boat, start_time, &simulation)?;`
+/// `for statistics in get_cargo_statistics(&boat, &result.kpis) { println!("{}: {:.0} {}*nmi", statistics.cargo_type, statistics.transport_work, statistics.cargo_type.unit()); }`
+pub fn get_cargo_statistics(boat: &Boat, kpis: &VoyageKpis) -> Vec<CargoStatistics> {
+    let metres_per_nautical_mile: f64 = 1852.0;
+    let days = kpis.duration.as_seconds_f64()/86400.0;
+    let mut statistics: Vec<CargoStatistics> = Vec::new();
+    for cargo_type in CargoType::ALL {
+        if !boat.cargo.iter().any(|cargo| cargo.cargo_type() == cargo_type) {
+            continue;
+        }
+        let amount = boat.get_cargo_amount(cargo_type);
+        let capacity = boat.get_cargo_capacity(cargo_type);
+        let transport_work = amount*kpis.distance/metres_per_nautical_mile;
+        statistics.push(CargoStatistics {
+            cargo_type,
+            amount,
+            capacity,
+            utilization_percent: capacity.filter(|capacity| *capacity > 0.0).map(|capacity| 100.0*amount/capacity),
+            transport_work,
+            transport_work_per_day: if days > 0.0 { Some(transport_work/days) } else { None },
+        });
+    }
+    return statistics;
+}
+
+/// Summary of the weather a vessel encountered during a voyage or trip, computed from the wind and wave height in its ship log
+/// Keeps the weather that shaped the result of a weather-based simulation, e.g. to tell a slow voyage in headwinds from a slow vessel
+/// The time between two consecutive entries is counted with the later entry, as in get_time_in_navigation_status()
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeatherSummary {
+    /// \[m/s\]. Wind speeds of the ship log entries, see DistributionStats. None if no wind was logged
+    pub wind_speed: Option<DistributionStats>,
+    /// \[m\]. Maximum significant wave height logged, None if no wave height was logged
+    pub max_wave_height: Option<f64>,
+    /// \[%\]. Percentage of the time with wind and heading logged that the true wind angle was less than 90°. None if the wind and heading were never logged together
+    pub upwind_percent: Option<f64>,
+    /// \[%\]. Percentage of the time with wind and heading logged that the true wind angle was 90° or more
+    pub downwind_percent: Option<f64>,
+    /// \[%\]. Percentage of the time with heading and course logged that the vessel was tacking, i.e. the heading was more than WeatherSummary::TACKING_MIN_DEVIATION off the course. None if the heading and course were never logged together
+    pub tacking_percent: Option<f64>,
+}
+
+impl WeatherSummary {
+    /// \[°\]. Minimum difference between the heading and the course for the vessel to be counted as tacking
+    pub const TACKING_MIN_DEVIATION: f64 = 10.0;
+
+    /// Computes the weather summary of a ship log, None if the ship log has no wind or wave height data
+    /// The heading is taken from the track angle if it was not logged
+    /// # Example:
+    /// `let weather = WeatherSummary::from_ship_log(&boat.ship_log);`
+    pub fn from_ship_log(ship_log: &[ShipLogEntry]) -> Option<WeatherSummary> {
+        let mut totals = ShipLogTotals::default();
+        for entry in ship_log {
+            totals.add(entry);
+        }
+        return totals.weather_summary();
+    }
+}
+
+/// Enum of how a simulated voyage ended
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SimulationStatus {
+    /// The vessel reached the end of its route plan
+    Completed,
+    /// The vessel ran aground, see Simulation.bathymetry
+    RanAground,
+    /// The simulation used all of simulation.max_iterations before the vessel reached the end of its route plan
+    MaxIterationsReached,
+    /// The voyage took longer than simulation.max_simulated_duration before the vessel reached the end of its route plan
+    MaxSimulatedDurationReached,
+    /// A tank of the vessel ran empty before the vessel reached the end of its route plan, see Tank and Simulation.bunkering
+    TankEmpty,
+}
+
+impl SimulationStatus {
+    /// Returns true if the vessel reached the end of its route plan
+    pub fn is_completed(&self) -> bool {
+        return *self == SimulationStatus::Completed;
+    }
+}
+
+impl fmt::Display for SimulationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SimulationStatus::Completed => write!(f, "Simulation completed"),
+            SimulationStatus::RanAground => write!(f, "Vessel ran aground. Stopping simulation"),
+            SimulationStatus::MaxIterationsReached => write!(f, "Maximized number of iterations. Stopping simulation"),
+            SimulationStatus::MaxSimulatedDurationReached => write!(f, "Maximized simulated duration. Stopping simulation"),
+            SimulationStatus::TankEmpty => write!(f, "A tank ran empty. Stopping simulation"),
+        }
+    }
+}
+
+/// Result of a simulated voyage, returned by sim_waypoint_mission()
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationResult {
+    /// How the voyage ended
+    pub status: SimulationStatus,
+    /// Number of iterations (time steps) the simulation used
+    pub iterations: usize,
+    /// Key performance indicators of the voyage, e.g. the simulated time (duration), distance sailed, number of tacks and the maximum wind speed encountered
+    pub kpis: VoyageKpis,
+    /// How far along the route plan the vessel got, e.g. to see how much of the route a voyage that was not completed covered
+    pub progress: VoyageProgress,
+}
+
+impl SimulationResult {
+    /// Returns true if the vessel reached the end of its route plan
+    pub fn is_completed(&self) -> bool {
+        return self.status.is_completed();
+    }
+}
+
+/// Progress of a simulated voyage along its route plan, so a user interface can show the percentage of the distance covered instead of the number of legs
+/// Reported at every time step to the progress callbacks of the simulation, see Simulation.add_progress_callback(), and at the end of the voyage in SimulationResult.progress
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct VoyageProgress {
+    /// Departure time of the voyage
+    pub departure: UtcDateTime,
+    /// Number of legs of the route plan the vessel has finished
+    pub legs_completed: u32,
+    /// Number of legs in the route plan
+    pub total_legs: u32,
+    /// \[m\]. Distance covered along the route plan, the length of the finished legs and how far along the current leg the vessel is
+    pub distance_covered: f64,
+    /// \[m\]. Length of the route plan, the sum of the great circle lengths of its legs
+    pub total_distance: f64,
+    /// Simulated time since the departure
+    pub simulated_time: time::Duration,
+}
+
+impl VoyageProgress {
+    /// Creates the progress of a voyage that has not started, with no legs completed, no distance covered and no simulated time
+    pub fn new(departure: UtcDateTime, total_legs: u32, total_distance: f64) -> VoyageProgress {
+        return VoyageProgress {
+            departure,
+            legs_completed: 0,
+            total_legs,
+            distance_covered: 0.0,
+            total_distance,
+            simulated_time: time::Duration::ZERO,
+        };
+    }
+
+    /// Returns the fraction of the route plan distance covered, in \[0, 1\]. 1 if the route plan has no length
+    pub fn fraction(&self) -> f64 {
+        if !(self.total_distance > 0.0) {
+            return 1.0;
+        }
+        return (self.distance_covered / self.total_distance).clamp(0.0, 1.0);
+    }
+
+    /// Returns the percentage of the route plan distance covered, in \[0, 100\]
+    pub fn percent(&self) -> f64 {
+        return 100.0*self.fraction();
+    }
+}
+
+/// Copernicus marine information used to download weather data, see copernicusmarine_rs
+#[cfg(feature = "copernicus")]
+pub use copernicusmarine_rs::Copernicus;
+
+/// Stand-in for the Copernicus marine information when the crate is built without the "copernicus" feature, e.g. for wasm32-unknown-unknown
+/// Has no values, so Simulation.copernicus is always None and Simulation::new() takes the same arguments with and without the feature
+#[cfg(not(feature = "copernicus"))]
+#[derive(Debug, Clone)]
+pub enum Copernicus {}
+
 /// Struct for simulation
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Simulation {
     /// The simulation method to use
     pub simulation_method: SimMethod,
@@ -32,17 +693,81 @@ pub struct Simulation {
     pub time_step: time::Duration,
     /// The maximum number of iterations for the simulation
     pub max_iterations: usize,
+    /// If set, the simulation of a voyage also stops when the voyage has taken this long in simulated time, independent of the time step, see SimulationStatus::MaxSimulatedDurationReached
+    /// Useful to compare runs with different time steps, where max_iterations would stop them after different simulated times
+    pub max_simulated_duration: Option<time::Duration>,
     /// Weather data file for the simulation
     pub weather_data_file: Option<String>,
     /// Copernicus information
-    pub copernicus: Option<copernicusmarine_rs::Copernicus>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub copernicus: Option<Copernicus>,
     /// Progress bar, set to none if not needed, if you use, set the length to the total number of legs in all simulations
+    /// Needs the "progress_bar" feature
+    #[cfg(feature = "progress_bar")]
+    #[cfg_attr(feature = "serde", serde(skip))]
     pub progress_bar: Option<indicatif::ProgressBar>,
     /// How many segments the route should be split into if the simulation calls for it
     pub n_segments: Option<u64>,
+    /// \[m\]. If set, the simulator looks this far ahead along the route and if the weather there exceeds the operational limits of the boat
+    /// it inserts a detour waypoint this far downwind of the bad weather and continues. None means no storm avoidance
+    /// Only used by SimMethod::WeatherDataFromCopernicus
+    pub storm_avoidance_distance: Option<f64>,
+    /// What to do when the boat is about to sail into sea ice above boat.max_ice_concentration, defaults to IceAvoidance::Stop
+    /// Only used by SimMethod::WeatherDataFromCopernicus
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ice_avoidance: IceAvoidance,
+    /// Land mask, if set the simulation stops with an error if the boat would sail onto land
+    /// Not used by SimMethod::FastWeatherDataFromCopernicus
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub land_mask: Option<LandMask>,
+    /// Bathymetry, if set the water depth is logged in the ship log and the simulation stops if the water is shallower than the boat needs (boat.draft + boat.min_under_keel_clearance)
+    /// Not used by SimMethod::FastWeatherDataFromCopernicus
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub bathymetry: Option<Bathymetry>,
+    /// If set, each simulated voyage is also resampled to this interval, e.g. hourly or daily, and added to boat.decimated_ship_log alongside the full resolution boat.ship_log
+    pub decimated_log_interval: Option<time::Duration>,
+    /// If set, wind against a strong ocean current makes the waves higher and slows the boat down, and a WindAgainstCurrent event is added to boat.events each time it starts
+    /// None means wind and current are simply added together
+    /// Only used by SimMethod::WeatherDataFromCopernicus
+    pub wind_against_current: Option<WindAgainstCurrent>,
+    /// If set, the vessel detours to a bunkering port when a tank runs low, see Bunkering. None means the simulation stops when a tank runs empty
+    /// The inserted bunkering legs are removed from the route plan and the tanks set back to their levels at departure after each voyage, so every start time departs the same
+    /// Not used by SimMethod::FastWeatherDataFromCopernicus
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bunkering: Option<Bunkering>,
+    /// If set, the wind and ocean current are taken from this weather provider instead of directly from copernicus, e.g. NestedWeather to use a regional high resolution model inside its area
+    /// Wave height and sea ice are still taken from copernicus
+    /// Only used by SimMethod::WeatherDataFromCopernicus and SimMethod::EmpiricalSpeed (for the wind angle, see EmpiricalSpeedModel)
+    /// SimMethod::ConstVelocity and SimMethod::MeanAndSTDVelocity only use it for anchor waits that wait for the wind to drop, see AnchorWait::for_weather()
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub weather: Option<std::sync::Arc<dyn WeatherProvider + Send + Sync>>,
+    /// If set, run_monte_carlo() moves the departure of each run by a random time of up to this much before or after the first start time
+    pub departure_jitter: Option<time::Duration>,
+    /// If set, run_monte_carlo() perturbs the weather from Simulation.weather in each run, see NoisyWeather
+    pub weather_noise: Option<WeatherNoise>,
+    /// Functions called with every event of each simulated voyage, see Simulation.add_event_callback()
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub event_callbacks: Vec<EventCallback>,
+    /// Functions called with the progress of each simulated voyage at every time step, see Simulation.add_progress_callback()
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub progress_callbacks: Vec<ProgressCallback>,
+    /// If set, the ship log of each simulated voyage is written here in batches while the voyage is simulated and removed from boat.ship_log, so long simulations do not keep every entry in memory
+    /// See Simulation.set_ship_log_writer()
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub ship_log_writer: Option<SharedShipLogWriter>,
+    /// Number of ship log entries written to the ship log writer at a time. If None, defaults to Simulation::DEFAULT_SHIP_LOG_BATCH_SIZE
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ship_log_batch_size: Option<usize>,
+    /// If set, the distances, bearings and destinations of each simulated voyage are computed on this model of the Earth instead of the one set by set_earth_model()
+    /// E.g. EarthModel::Wgs84 for long legs, where route distances differ by up to 0.5% from the sphere, which matters for fuel and ETA studies. See with_earth_model()
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub earth_model: Option<EarthModel>,
 }
 
 impl Simulation {
+    /// Default number of ship log entries written at a time when the simulation streams its ship logs, see Simulation.ship_log_batch_size
+    pub const DEFAULT_SHIP_LOG_BATCH_SIZE: usize = 1000;
+
     /// Creates a new simulation with the given parameters
     /// # Example - Adding a progress bar
     /// // Init progress bar for simulation
@@ -51,30 +776,358 @@ impl Simulation {
     /// progress_bar.set_style(indicatif::ProgressStyle::with_template("[{elapsed_precise}] {bar} {pos:>3}/{len:3} ETA:{eta:>1}").unwrap()); //.progress_chars("##-"));
     /// // Add progress bar to simulation
     /// my_sim.progress_bar = Some(progress_bar); // Set the progress bar for the simulation
-    pub fn new(simulation_method: SimMethod, start_times: Vec<UtcDateTime>, time_step: time::Duration, max_iterations: usize, weather_data_file: Option<String>, copernicus: Option<copernicusmarine_rs::Copernicus>) -> Self {
+    pub fn new(simulation_method: SimMethod, start_times: Vec<UtcDateTime>, time_step: time::Duration, max_iterations: usize, weather_data_file: Option<String>, copernicus: Option<Copernicus>) -> Self {
         Simulation {
             simulation_method,
             start_times,
             time_step,
             max_iterations,
+            max_simulated_duration: None,
             weather_data_file,
             copernicus,
+            #[cfg(feature = "progress_bar")]
             progress_bar: None,
             n_segments: None,
+            storm_avoidance_distance: None,
+            ice_avoidance: IceAvoidance::Stop,
+            land_mask: None,
+            bathymetry: None,
+            decimated_log_interval: None,
+            wind_against_current: None,
+            bunkering: None,
+            weather: None,
+            departure_jitter: None,
+            weather_noise: None,
+            event_callbacks: Vec::new(),
+            progress_callbacks: Vec::new(),
+            ship_log_writer: None,
+            ship_log_batch_size: None,
+            earth_model: None,
+        }
+    }
+
+    /// Adds a function that is called with every event of each simulated voyage, e.g. to update a dashboard or for custom logging
+    /// The callbacks are called in chronological order of the events when the simulation of a voyage finishes, the last event of each voyage is SimulationFinished
+    /// With sim_waypoint_missions_parallel() the callbacks can be called from several threads at once
+    /// # Example:
+    /// `simulation.add_event_callback(|event| println!("{}: {:?}", event.timestamp, event.kind));`
+    pub fn add_event_callback<F: Fn(&SimulationEvent) + Send + Sync + 'static>(&mut self, callback: F) {
+        self.event_callbacks.push(EventCallback(std::sync::Arc::new(callback)));
+    }
+
+    /// Adds a function that is called with the progress of each simulated voyage at every time step, e.g. to show the percentage of the route distance covered in a user interface, see VoyageProgress
+    /// Unlike the event callbacks these are called while the voyage is simulated. With sim_waypoint_missions_parallel() the callbacks can be called from several threads at once, tell the voyages apart by VoyageProgress.departure
+    /// # Example:
+    /// `simulation.add_progress_callback(|progress| println!("{}: {:.1}%, {} of {} legs", progress.departure, progress.percent(), progress.legs_completed, progress.total_legs));`
+    pub fn add_progress_callback<F: Fn(&VoyageProgress) + Send + Sync + 'static>(&mut self, callback: F) {
+        self.progress_callbacks.push(ProgressCallback(std::sync::Arc::new(callback)));
+    }
+
+    /// Streams the ship log of each simulated voyage to a writer, e.g. a CSV or Parquet file, instead of keeping it in boat.ship_log
+    /// The ship log is written in batches of Simulation.ship_log_batch_size entries while the voyage is simulated, the KPIs and decimated ship log are added up as the entries are logged, so at most one batch is in memory at a time
+    /// The ship log hook still sees every entry. sim_waypoint_missions() and sim_waypoint_missions_parallel() finish the writer when all voyages are done, after sim_waypoint_mission() call SharedShipLogWriter.finish() yourself
+    /// With sim_waypoint_missions_parallel() the batches of different voyages can be mixed together in the writer, tell them apart by their timestamps
+    /// # Example:
+    /// `simulation.set_ship_log_writer(CsvShipLogWriter::create("ship_logs.csv")?);`
+    pub fn set_ship_log_writer<W: ShipLogWriter + 'static>(&mut self, writer: W) {
+        self.ship_log_writer = Some(SharedShipLogWriter(std::sync::Arc::new(std::sync::Mutex::new(writer))));
+    }
+
+    /// Returns a SimulationBuilder for the simulation method, see SimulationBuilder.build()
+    /// # Example:
+    /// `let simulation = Simulation::builder(SimMethod::ConstVelocity).start_time(start_time).time_step(time::Duration::minutes(10)).build()?;`
+    pub fn builder(simulation_method: SimMethod) -> SimulationBuilder {
+        return SimulationBuilder {
+            simulation: Simulation::new(simulation_method, Vec::new(), time::Duration::minutes(10), 10000, None, None),
+        };
+    }
+
+    /// Checks that the simulation can be run
+    /// Returns an error if there are no start times, the time step is not positive, max_iterations is zero or the weather settings do not match the simulation method
+    pub fn validate(&self) -> Result<(), MarineSimError> {
+        self.validate_config()?;
+
+        // Weather that can not be in a config file, so it is set after loading
+        match self.simulation_method {
+            SimMethod::Drift if self.weather.is_none() => {
+                return Err(MarineSimError::InvalidInput("Drift needs the wind and ocean current from simulation.weather".to_string()));
+            }
+            SimMethod::WeatherDataFromCopernicus | SimMethod::FastWeatherDataFromCopernicus if self.copernicus.is_none() => {
+                return Err(MarineSimError::InvalidInput("Missing copernicus info from simulation".to_string()));
+            }
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    /// Checks the settings that can be in a config file, see Simulation::from_config_file()
+    /// Same as Simulation.validate() except that it does not check that simulation.copernicus and simulation.weather are set when the simulation method needs them
+    pub fn validate_config(&self) -> Result<(), MarineSimError> {
+        if self.start_times.is_empty() {
+            return Err(MarineSimError::InvalidInput("Simulation has no start time".to_string()));
+        }
+        if !self.time_step.is_positive() {
+            return Err(MarineSimError::InvalidInput(format!("Time step must be positive, it is {}", self.time_step)));
+        }
+        if self.max_iterations == 0 {
+            return Err(MarineSimError::InvalidInput("max_iterations must be more than zero".to_string()));
+        }
+        if self.max_simulated_duration.is_some_and(|duration| !duration.is_positive()) {
+            return Err(MarineSimError::InvalidInput("max_simulated_duration must be positive".to_string()));
+        }
+        if self.ship_log_batch_size == Some(0) {
+            return Err(MarineSimError::InvalidInput("ship_log_batch_size must be more than zero".to_string()));
+        }
+        if let Some(EarthModel::Sphere { radius }) = self.earth_model {
+            if !(radius > 0.0) {
+                return Err(MarineSimError::InvalidInput(format!("The radius of the Earth model must be positive, it is {} m", radius)));
+            }
+        }
+
+        // Weather settings
+        match self.simulation_method {
+            SimMethod::ConstVelocity | SimMethod::MeanAndSTDVelocity => {
+                // simulation.weather is allowed since anchor waits can wait for the wind to drop, see AnchorWait::for_weather()
+                if self.copernicus.is_some() || self.weather_data_file.is_some() {
+                    return Err(MarineSimError::InvalidInput(format!("{:?} only uses weather from simulation.weather for anchor waits, remove copernicus and weather_data_file from the simulation", self.simulation_method)));
+                }
+            }
+            SimMethod::EmpiricalSpeed => {
+                if self.copernicus.is_some() || self.weather_data_file.is_some() {
+                    return Err(MarineSimError::InvalidInput("EmpiricalSpeed only uses weather from simulation.weather, remove copernicus and weather_data_file from the simulation".to_string()));
+                }
+            }
+            SimMethod::Drift => {
+                if self.copernicus.is_some() || self.weather_data_file.is_some() {
+                    return Err(MarineSimError::InvalidInput("Drift only uses weather from simulation.weather, remove copernicus and weather_data_file from the simulation".to_string()));
+                }
+            }
+            SimMethod::WeatherDataFromCopernicus | SimMethod::FastWeatherDataFromCopernicus => {
+                if self.weather_data_file.is_none() {
+                    return Err(MarineSimError::InvalidInput("Missing weather data file name from simulation".to_string()));
+                }
+                if matches!(self.simulation_method, SimMethod::FastWeatherDataFromCopernicus) && self.n_segments.is_none_or(|n_segments| n_segments == 0) {
+                    return Err(MarineSimError::InvalidInput("Missing n_segments from simulation".to_string()));
+                }
+            }
+        }
+        return Ok(());
+    }
+}
+
+/// Builder for Simulation, see Simulation::builder()
+/// Defaults to a 10 minute time step, 10000 max iterations, 100 segments for SimMethod::FastWeatherDataFromCopernicus and otherwise the same as Simulation::new()
+/// At least one start time must be added
+pub struct SimulationBuilder {
+    simulation: Simulation,
+}
+
+impl SimulationBuilder {
+    /// Adds a start time
+    pub fn start_time(mut self, start_time: UtcDateTime) -> SimulationBuilder {
+        self.simulation.start_times.push(start_time);
+        return self;
+    }
+
+    /// Adds start times
+    pub fn start_times(mut self, start_times: Vec<UtcDateTime>) -> SimulationBuilder {
+        self.simulation.start_times.extend(start_times);
+        return self;
+    }
+
+    /// Sets the time step, must be positive
+    pub fn time_step(mut self, time_step: time::Duration) -> SimulationBuilder {
+        self.simulation.time_step = time_step;
+        return self;
+    }
+
+    /// Sets the maximum number of iterations for each voyage
+    pub fn max_iterations(mut self, max_iterations: usize) -> SimulationBuilder {
+        self.simulation.max_iterations = max_iterations;
+        return self;
+    }
+
+    /// Stops each voyage when it has taken this long in simulated time, see Simulation.max_simulated_duration
+    pub fn max_simulated_duration(mut self, max_simulated_duration: time::Duration) -> SimulationBuilder {
+        self.simulation.max_simulated_duration = Some(max_simulated_duration);
+        return self;
+    }
+
+    /// Sets the weather data file, needed for the copernicus simulation methods
+    pub fn weather_data_file(mut self, weather_data_file: &str) -> SimulationBuilder {
+        self.simulation.weather_data_file = Some(weather_data_file.to_string());
+        return self;
+    }
+
+    /// Sets the copernicus information, needed for the copernicus simulation methods
+    pub fn copernicus(mut self, copernicus: Copernicus) -> SimulationBuilder {
+        self.simulation.copernicus = Some(copernicus);
+        return self;
+    }
+
+    /// Sets the progress bar, see Simulation.progress_bar
+    #[cfg(feature = "progress_bar")]
+    pub fn progress_bar(mut self, progress_bar: indicatif::ProgressBar) -> SimulationBuilder {
+        self.simulation.progress_bar = Some(progress_bar);
+        return self;
+    }
+
+    /// Sets how many segments the route is split into, only used by SimMethod::FastWeatherDataFromCopernicus
+    pub fn n_segments(mut self, n_segments: u64) -> SimulationBuilder {
+        self.simulation.n_segments = Some(n_segments);
+        return self;
+    }
+
+    /// \[m\]. Sets the storm avoidance look ahead distance, see Simulation.storm_avoidance_distance
+    pub fn storm_avoidance_distance(mut self, storm_avoidance_distance: f64) -> SimulationBuilder {
+        self.simulation.storm_avoidance_distance = Some(storm_avoidance_distance);
+        return self;
+    }
+
+    /// Sets what to do when the boat is about to sail into sea ice, see IceAvoidance
+    pub fn ice_avoidance(mut self, ice_avoidance: IceAvoidance) -> SimulationBuilder {
+        self.simulation.ice_avoidance = ice_avoidance;
+        return self;
+    }
+
+    /// Sets the land mask, see Simulation.land_mask
+    pub fn land_mask(mut self, land_mask: LandMask) -> SimulationBuilder {
+        self.simulation.land_mask = Some(land_mask);
+        return self;
+    }
+
+    /// Sets the bathymetry, see Simulation.bathymetry
+    pub fn bathymetry(mut self, bathymetry: Bathymetry) -> SimulationBuilder {
+        self.simulation.bathymetry = Some(bathymetry);
+        return self;
+    }
+
+    /// Sets the interval of the decimated ship log, see Simulation.decimated_log_interval
+    pub fn decimated_log_interval(mut self, decimated_log_interval: time::Duration) -> SimulationBuilder {
+        self.simulation.decimated_log_interval = Some(decimated_log_interval);
+        return self;
+    }
+
+    /// Sets the wind against current settings, see WindAgainstCurrent
+    pub fn wind_against_current(mut self, wind_against_current: WindAgainstCurrent) -> SimulationBuilder {
+        self.simulation.wind_against_current = Some(wind_against_current);
+        return self;
+    }
+
+    /// Sets the bunkering ports, see Bunkering
+    pub fn bunkering(mut self, bunkering: Bunkering) -> SimulationBuilder {
+        self.simulation.bunkering = Some(bunkering);
+        return self;
+    }
+
+    /// Sets the weather provider, see Simulation.weather
+    pub fn weather<W: WeatherProvider + Send + Sync + 'static>(mut self, weather: W) -> SimulationBuilder {
+        self.simulation.weather = Some(std::sync::Arc::new(weather));
+        return self;
+    }
+
+    /// Sets the departure jitter for run_monte_carlo(), see Simulation.departure_jitter
+    pub fn departure_jitter(mut self, departure_jitter: time::Duration) -> SimulationBuilder {
+        self.simulation.departure_jitter = Some(departure_jitter);
+        return self;
+    }
+
+    /// Sets the weather noise for run_monte_carlo(), see WeatherNoise
+    pub fn weather_noise(mut self, weather_noise: WeatherNoise) -> SimulationBuilder {
+        self.simulation.weather_noise = Some(weather_noise);
+        return self;
+    }
+
+    /// Adds a function that is called with every event of each simulated voyage, see Simulation.add_event_callback()
+    pub fn event_callback<F: Fn(&SimulationEvent) + Send + Sync + 'static>(mut self, callback: F) -> SimulationBuilder {
+        self.simulation.add_event_callback(callback);
+        return self;
+    }
+
+    /// Adds a function that is called with the progress of each simulated voyage at every time step, see Simulation.add_progress_callback()
+    pub fn progress_callback<F: Fn(&VoyageProgress) + Send + Sync + 'static>(mut self, callback: F) -> SimulationBuilder {
+        self.simulation.add_progress_callback(callback);
+        return self;
+    }
+
+    /// Streams the ship log of each simulated voyage to a writer, see Simulation.set_ship_log_writer()
+    pub fn ship_log_writer<W: ShipLogWriter + 'static>(mut self, writer: W) -> SimulationBuilder {
+        self.simulation.set_ship_log_writer(writer);
+        return self;
+    }
+
+    /// Sets the number of ship log entries written at a time when streaming, see Simulation.ship_log_batch_size
+    pub fn ship_log_batch_size(mut self, ship_log_batch_size: usize) -> SimulationBuilder {
+        self.simulation.ship_log_batch_size = Some(ship_log_batch_size);
+        return self;
+    }
+
+    /// Model of the Earth for the distances, bearings and destinations of the simulated voyages, see Simulation.earth_model
+    pub fn earth_model(mut self, earth_model: EarthModel) -> SimulationBuilder {
+        self.simulation.earth_model = Some(earth_model);
+        return self;
+    }
+
+    /// Returns the simulation if it is valid, see Simulation.validate()
+    pub fn build(mut self) -> Result<Simulation, MarineSimError> {
+        // Default number of segments for the fast simulation
+        if matches!(self.simulation.simulation_method, SimMethod::FastWeatherDataFromCopernicus) && self.simulation.n_segments.is_none() {
+            self.simulation.n_segments = Some(100);
         }
+        self.simulation.validate()?;
+        return Ok(self.simulation);
     }
 }
 
 
 /// Function that simulates more than one waypoint mission
 /// Saves the results of each simulation in the boat.ship_log
-pub fn sim_waypoint_missions(boat: &mut Boat, simulation: &Simulation) -> Result<Vec<String>, io::Error> {
+/// With the "parallel" feature, sim_waypoint_missions_parallel() does the same using all CPU cores
+/// Returns the simulation result of each voyage, see SimulationResult
+pub fn sim_waypoint_missions(boat: &mut Boat, simulation: &Simulation) -> Result<Vec<SimulationResult>, MarineSimError> {
+    // The tracing events of the simulation are in a span with the simulation method
+    let _span = tracing::info_span!("simulation", method = ?simulation.simulation_method, voyages = simulation.start_times.len()).entered();
+
     // Init sim_msg:
-    let mut sim_msg_vec: Vec<String> = Vec::new();
+    let mut sim_msg_vec: Vec<SimulationResult> = Vec::new();
+
+    // If simulation has progress bar, set it up and use it
+    #[cfg(feature = "progress_bar")]
+    setup_progress_bar(simulation);
+
+    // Runs sim_waypoint_mission for each start time in start_times
+    for (i, start_time) in simulation.start_times.iter().enumerate() {
+        match sim_waypoint_mission(boat, *start_time, simulation) {
+            Ok(sim_result) => {
+                // Add the simulation result to sim_msg_vec
+                sim_msg_vec.push(sim_result);
+            }
+            Err(e) => {
+                // Print the error message
+                return Err(MarineSimError::simulation(format!("Error during simulation {}", i.to_string()), e));
+            }
+        }
+    }
+    // Finish progress bar
+    #[cfg(feature = "progress_bar")]
+    if let Some(progress_bar) = &simulation.progress_bar {
+        progress_bar.finish();
+    }
+
+    // Finish the ship log writer
+    if let Some(writer) = &simulation.ship_log_writer {
+        writer.finish()?;
+    }
 
+    // Run successful, return Ok(sim_msg_vec)
+    return Ok(sim_msg_vec);
+}
+
+/// Sets up the progress bar of the simulation, if it has one, to redraw live in an interactive terminal and print static lines otherwise
+#[cfg(feature = "progress_bar")]
+fn setup_progress_bar(simulation: &Simulation) {
     // Check for interactive terminal for progress bar
     let is_interactive_terminal = atty::is(atty::Stream::Stdout);
-    // If simulation has progress bar, set it up and use it
     if !(simulation.progress_bar.is_none()) {
         // If terminal is interactive, use live redraw, otherwise use static redraw
         if is_interactive_terminal {
@@ -85,47 +1138,653 @@ pub fn sim_waypoint_missions(boat: &mut Boat, simulation: &Simulation) -> Result
             // Force static redraw every step to stdout (or to log)
             // bar.set_draw_target(indicatif::ProgressDrawTarget::stdout_with_hz(1)); // Or `.stdout_with_hz(1)` for slow redraw
             let eta = time::UtcDateTime::now().saturating_add(time::Duration::new(simulation.progress_bar.as_ref().unwrap().eta().as_secs() as i64, 0)); // What time the simulations will end
-            println!("Elapsed: {:?}, Steps {}/{}, ETA: {}-{}-{} {}:{}:{}", simulation.progress_bar.as_ref().unwrap().elapsed(), simulation.progress_bar.as_ref().unwrap().position(), simulation.progress_bar.as_ref().unwrap().length().unwrap(), eta.year(), eta.month() as u8, eta.day(), eta.hour()+1, eta.minute(), eta.second());
+            tracing::info!(elapsed_secs = simulation.progress_bar.as_ref().unwrap().elapsed().as_secs(), steps = simulation.progress_bar.as_ref().unwrap().position(), total_steps = simulation.progress_bar.as_ref().unwrap().length().unwrap(), eta = %format_timestamp(eta), "Simulation progress");
         }
         simulation.progress_bar.as_ref().unwrap().inc(0);
     }
-    
-    // Runs sim_waypoint_mission for each start time in start_times
-    for (i, start_time) in simulation.start_times.iter().enumerate() {
-        match sim_waypoint_mission(boat, *start_time, simulation) {
-            Ok(sim_msg) => {
-                // Add sim_msg to sim_msg_vec
-                sim_msg_vec.push(sim_msg);
+}
+
+/// Function that simulates more than one waypoint mission in parallel, the start times are spread over threads with rayon (needs the "parallel" feature)
+/// Each start time is simulated on a clone of the boat. Afterwards the ship logs, decimated ship logs, events and waypoint arrivals of all voyages are added to the boat in the order of simulation.start_times,
+/// the tacks and time hove-to are added up and the rest of the boat is left as after the last voyage, the same as with sim_waypoint_missions()
+/// The progress bar is shared by the threads
+/// Note: The ship log hook is called from the threads, so entries from different voyages can arrive mixed together
+/// Returns the simulation result of each voyage, see SimulationResult
+#[cfg(feature = "parallel")]
+pub fn sim_waypoint_missions_parallel(boat: &mut Boat, simulation: &Simulation) -> Result<Vec<SimulationResult>, MarineSimError> {
+    use rayon::prelude::*;
+    // The voyages on other threads are not in this span, each voyage has its own span, see sim_waypoint_mission()
+    let _span = tracing::info_span!("simulation", method = ?simulation.simulation_method, voyages = simulation.start_times.len()).entered();
+
+    // If simulation has progress bar, set it up and use it
+    #[cfg(feature = "progress_bar")]
+    setup_progress_bar(simulation);
+
+    // Take the logs out of the boat so they are not copied to every thread
+    let mut ship_log = std::mem::take(&mut boat.ship_log);
+    let mut decimated_ship_log = std::mem::take(&mut boat.decimated_ship_log);
+    let mut events = std::mem::take(&mut boat.events);
+    let mut waypoint_arrivals = std::mem::take(&mut boat.waypoint_arrivals);
+    let num_tacks_start = boat.num_tacks;
+    let time_hove_to_start = boat.time_hove_to;
+
+    // Simulate each start time on its own copy of the boat
+    let template: &Boat = boat;
+    let results: Vec<Result<(Boat, SimulationResult), MarineSimError>> = simulation.start_times.par_iter().map(|start_time| {
+        let mut voyage_boat = template.clone();
+        let sim_result = sim_waypoint_mission(&mut voyage_boat, *start_time, simulation)?;
+        return Ok((voyage_boat, sim_result));
+    }).collect();
+
+    // Merge the voyages in order of start time
+    let mut sim_msg_vec: Vec<SimulationResult> = Vec::with_capacity(results.len());
+    let mut num_tacks = num_tacks_start;
+    let mut time_hove_to = time_hove_to_start;
+    let mut last_boat: Option<Boat> = None;
+    for (i, result) in results.into_iter().enumerate() {
+        match result {
+            Ok((mut voyage_boat, sim_result)) => {
+                ship_log.append(&mut voyage_boat.ship_log);
+                decimated_ship_log.append(&mut voyage_boat.decimated_ship_log);
+                events.append(&mut voyage_boat.events);
+                waypoint_arrivals.append(&mut voyage_boat.waypoint_arrivals);
+                num_tacks += voyage_boat.num_tacks - num_tacks_start;
+                time_hove_to += voyage_boat.time_hove_to - time_hove_to_start;
+                sim_msg_vec.push(sim_result);
+                last_boat = Some(voyage_boat);
             }
             Err(e) => {
-                // Print the error message
-                return Err(io::Error::new(io::ErrorKind::Other, format!("Error during simulation {}: {}", i.to_string(), e)));
+                // Put the logs back and return the error
+                boat.ship_log = ship_log;
+                boat.decimated_ship_log = decimated_ship_log;
+                boat.events = events;
+                boat.waypoint_arrivals = waypoint_arrivals;
+                return Err(MarineSimError::simulation(format!("Error during simulation {}", i.to_string()), e));
             }
         }
     }
+    if let Some(last_boat) = last_boat {
+        *boat = last_boat;
+    }
+    boat.ship_log = ship_log;
+    boat.decimated_ship_log = decimated_ship_log;
+    boat.events = events;
+    boat.waypoint_arrivals = waypoint_arrivals;
+    boat.num_tacks = num_tacks;
+    boat.time_hove_to = time_hove_to;
+
     // Finish progress bar
-    simulation.progress_bar.as_ref().unwrap().finish();
+    #[cfg(feature = "progress_bar")]
+    if let Some(progress_bar) = &simulation.progress_bar {
+        progress_bar.finish();
+    }
+
+    // Finish the ship log writer
+    if let Some(writer) = &simulation.ship_log_writer {
+        writer.finish()?;
+    }
 
     // Run successful, return Ok(sim_msg_vec)
     return Ok(sim_msg_vec);
 }
 
+/// Enum of what find_best_departure_times() minimizes
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DepartureObjective {
+    /// Shortest voyage duration
+    MinDuration,
+    /// Lowest maximum wind speed encountered during the voyage, needs wind in the ship log (SimMethod::WeatherDataFromCopernicus)
+    MinMaxWindSpeed,
+    /// Lowest maximum significant wave height encountered during the voyage, needs wave height in the ship log (SimMethod::WeatherDataFromCopernicus)
+    MinMaxWaveHeight,
+    /// Least time with the significant wave height above this many meters, needs wave height in the ship log (SimMethod::WeatherDataFromCopernicus)
+    /// The time between two consecutive entries is counted if the later entry has waves above the height, as in get_time_in_navigation_status(). Can not be used with Simulation.ship_log_writer
+    MinTimeAboveWaveHeight(f64),
+}
+
+/// Returns the score of a voyage for find_best_departure_times(), lower is better. Voyages with unknown values, e.g. no wind in the ship log, score infinity
+/// ship_log: The ship log of the voyage
+pub(crate) fn get_departure_score(objective: DepartureObjective, kpis: &VoyageKpis, ship_log: &[ShipLogEntry]) -> f64 {
+    return match objective {
+        DepartureObjective::MinDuration => kpis.duration.as_seconds_f64(),
+        DepartureObjective::MinMaxWindSpeed => kpis.max_wind_speed.unwrap_or(f64::INFINITY),
+        DepartureObjective::MinMaxWaveHeight => kpis.weather.as_ref().and_then(|weather| weather.max_wave_height).unwrap_or(f64::INFINITY),
+        DepartureObjective::MinTimeAboveWaveHeight(wave_height) => {
+            if !ship_log.iter().any(|entry| entry.wave_height.is_some()) {
+                return f64::INFINITY;
+            }
+            ship_log.windows(2).filter(|pair| pair[1].wave_height.is_some_and(|height| height > wave_height)).map(|pair| (pair[1].timestamp - pair[0].timestamp).as_seconds_f64()).sum()
+        },
+    };
+}
+
+/// Weather window finder, simulates the waypoint mission for departure times from window_start to window_end every interval
+/// Returns the departure times of the voyages that reached the end of the route with their key performance indicators, best first according to the objective
+/// Voyages that did not finish, e.g. ran aground or ran out of iterations, are left out. The ship logs of all voyages are added to boat.ship_log as in sim_waypoint_missions()
+/// simulation.start_times is not used
+/// Tip: Set simulation.weather to a CachedWeather so the weather is only downloaded once for all the departure times
+/// # Example:
+/// `let departures = find_best_departure_times(&mut boat, &simulation, window_start, window_start + time::Duration::days(14), time::Duration::hours(6), DepartureObjective::MinDuration)?;`
+/// `println!("Best departure: {}", departures[0].0);`
+pub fn find_best_departure_times(boat: &mut Boat, simulation: &Simulation, window_start: UtcDateTime, window_end: UtcDateTime, interval: time::Duration, objective: DepartureObjective) -> Result<Vec<(UtcDateTime, VoyageKpis)>, MarineSimError> {
+    if interval <= time::Duration::ZERO {
+        return Err(MarineSimError::InvalidInput("Interval between departure times must be positive".to_string()));
+    }
+    if window_end < window_start {
+        return Err(MarineSimError::InvalidInput("Departure window must end after it starts".to_string()));
+    }
+    if matches!(objective, DepartureObjective::MinTimeAboveWaveHeight(_)) && simulation.ship_log_writer.is_some() {
+        return Err(MarineSimError::InvalidInput("DepartureObjective::MinTimeAboveWaveHeight needs the ship log, it can not be used with a ship log writer".to_string()));
+    }
+
+    // Simulate each departure time and score the voyage
+    let mut departures: Vec<(UtcDateTime, VoyageKpis, f64)> = Vec::new();
+    let mut departure = window_start;
+    while departure <= window_end {
+        let ship_log_start = boat.ship_log.len();
+        let sim_result = match sim_waypoint_mission(boat, departure, simulation) {
+            Ok(result) => result,
+            Err(e) => return Err(MarineSimError::simulation(format!("Error during simulation departing {}", departure), e)),
+        };
+        if sim_result.is_completed() {
+            let score = get_departure_score(objective, &sim_result.kpis, boat.ship_log.get(ship_log_start..).unwrap_or(&[]));
+            departures.push((departure, sim_result.kpis, score));
+        }
+        departure = departure + interval;
+    }
+
+    // Sort best first, voyages with unknown values last
+    departures.sort_by(|a, b| a.2.total_cmp(&b.2));
+    return Ok(departures.into_iter().map(|(departure, kpis, _)| (departure, kpis)).collect());
+}
+
+/// The latest departure that arrives before a deadline, see find_latest_departure()
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatestDeparture {
+    /// Latest departure time found that arrives on time
+    pub departure: UtcDateTime,
+    /// When the vessel arrives at the end of the route plan with that departure
+    pub arrival: UtcDateTime,
+    /// How long before the required arrival time the vessel arrives
+    pub slack: time::Duration,
+    /// Key performance indicators of the voyage
+    pub kpis: VoyageKpis,
+}
+
+/// Arrival deadline planning, finds the latest departure between earliest_departure and required_arrival that reaches the end of the route plan by required_arrival
+/// The departure is found by bisection to within tolerance, which presumes that departing later never makes the vessel arrive earlier. With changing weather this is not always true, then a departure that arrives on time is found but it might not be the latest one
+/// Returns None if departing at earliest_departure does not arrive in time. The ship logs of all simulated voyages are added to boat.ship_log as in sim_waypoint_missions()
+/// simulation.start_times is not used
+/// Tip: Set simulation.weather to a CachedWeather so the weather is only downloaded once for all the departure times
+/// # Example:
+/// `let latest = find_latest_departure(&mut boat, &simulation, now, deadline, time::Duration::minutes(30))?;`
+/// `if let Some(latest) = latest { println!("Depart by {} to arrive {} early", latest.departure, latest.slack); }`
+pub fn find_latest_departure(boat: &mut Boat, simulation: &Simulation, earliest_departure: UtcDateTime, required_arrival: UtcDateTime, tolerance: time::Duration) -> Result<Option<LatestDeparture>, MarineSimError> {
+    if tolerance <= time::Duration::ZERO {
+        return Err(MarineSimError::InvalidInput("Tolerance of the departure time must be positive".to_string()));
+    }
+    if required_arrival <= earliest_departure {
+        return Err(MarineSimError::InvalidInput("Required arrival must be after the earliest departure".to_string()));
+    }
+
+    // The earliest departure must arrive on time, otherwise no departure does
+    let mut latest: LatestDeparture = match simulate_departure(boat, simulation, earliest_departure, required_arrival)? {
+        Some(latest) => latest,
+        None => return Ok(None),
+    };
+
+    // Bisect between the latest departure known to arrive on time and the first known not to. Departing at the required arrival is always too late
+    let mut too_late: UtcDateTime = required_arrival;
+    while too_late - latest.departure > tolerance {
+        let departure = latest.departure + (too_late - latest.departure)/2;
+        match simulate_departure(boat, simulation, departure, required_arrival)? {
+            Some(on_time) => latest = on_time,
+            None => too_late = departure,
+        }
+    }
+    return Ok(Some(latest));
+}
+
+/// Simulates the voyage departing at departure, returns the departure if the vessel reaches the end of the route plan by required_arrival and None otherwise
+fn simulate_departure(boat: &mut Boat, simulation: &Simulation, departure: UtcDateTime, required_arrival: UtcDateTime) -> Result<Option<LatestDeparture>, MarineSimError> {
+    let sim_result = match sim_waypoint_mission(boat, departure, simulation) {
+        Ok(result) => result,
+        Err(e) => return Err(MarineSimError::simulation(format!("Error during simulation departing {}", departure), e)),
+    };
+    if !sim_result.is_completed() {
+        return Ok(None);
+    }
+
+    // Use the interpolated arrival at the last waypoint if there is one
+    let arrival = match sim_result.kpis.waypoint_arrivals.last() {
+        Some(waypoint_arrival) => waypoint_arrival.timestamp,
+        None => departure + sim_result.kpis.duration,
+    };
+    if arrival > required_arrival {
+        return Ok(None);
+    }
+    return Ok(Some(LatestDeparture {
+        departure,
+        arrival,
+        slack: required_arrival - arrival,
+        kpis: sim_result.kpis,
+    }));
+}
+
+/// Key performance indicators of one round trip of a recurring schedule, see sim_round_trips()
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundTripKpis {
+    /// When the vessel left the first port of the route plan
+    pub departure: UtcDateTime,
+    /// When the vessel got back to the first port of the route plan
+    pub arrival: UtcDateTime,
+    /// The voyage along the route plan
+    pub outbound: VoyageKpis,
+    /// The voyage back along the reversed route plan
+    pub inbound: VoyageKpis,
+}
+
+impl RoundTripKpis {
+    /// Time from departure until the vessel is back, including the turnaround at the far port
+    pub fn duration(&self) -> time::Duration {
+        return self.arrival - self.departure;
+    }
+
+    /// \[m\]. Distance sailed on the round trip
+    pub fn distance(&self) -> f64 {
+        return self.outbound.distance + self.inbound.distance;
+    }
+}
+
+/// Results of a recurring schedule of round trips over a calendar period, see sim_round_trips()
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduleKpis {
+    /// The round trips completed within the period, in order
+    pub round_trips: Vec<RoundTripKpis>,
+    /// Length of the period
+    pub period: time::Duration,
+    /// Completed round trips per year, scaled from the period to a year of 365.25 days
+    pub round_trips_per_year: f64,
+    /// Mean duration of the round trips, None if no round trip was completed
+    pub round_trip_duration_mean: Option<time::Duration>,
+    /// \[m\]. Distance sailed per year on the completed round trips, scaled as round_trips_per_year
+    pub distance_per_year: f64,
+}
+
+/// Simulates a vessel sailing back and forth along its route plan from period_start until period_end, e.g. to compare yearly capacity of sailing and motor vessels on a service
+/// Each round trip sails the route plan and then the reversed route plan (see RoutePlan.reversed()), the vessel waits turnaround at each end before sailing again
+/// Round trips not completed by period_end are not counted, and the schedule stops if a voyage does not reach the end of its route, e.g. runs aground or out of iterations
+/// The ship logs of all voyages are added to boat.ship_log as in sim_waypoint_missions(), and boat.route_plan is left as it was
+/// simulation.start_times is not used
+/// # Example:
+/// `let schedule = sim_round_trips(&mut boat, &simulation, start_of_year, start_of_year + time::Duration::days(365), time::Duration::hours(24))?;`
+/// `println!("Round trips per year: {:.1}", schedule.round_trips_per_year);`
+pub fn sim_round_trips(boat: &mut Boat, simulation: &Simulation, period_start: UtcDateTime, period_end: UtcDateTime, turnaround: time::Duration) -> Result<ScheduleKpis, MarineSimError> {
+    if period_end <= period_start {
+        return Err(MarineSimError::InvalidInput("Schedule period must end after it starts".to_string()));
+    }
+    if turnaround < time::Duration::ZERO {
+        return Err(MarineSimError::InvalidInput("Turnaround time can not be negative".to_string()));
+    }
+    let outbound_route: RoutePlan = match &boat.route_plan {
+        Some(route_plan) => route_plan.clone(),
+        None => return Err(MarineSimError::MissingBoatField("route plan".to_string())),
+    };
+    let inbound_route: RoutePlan = outbound_route.reversed();
+
+    // Sail round trips until the period is over
+    let mut round_trips: Vec<RoundTripKpis> = Vec::new();
+    let mut departure = period_start;
+    let mut result: Result<(), MarineSimError> = Ok(());
+    while departure < period_end {
+        // Sail the route plan and back
+        let mut voyages: Vec<VoyageKpis> = Vec::with_capacity(2);
+        let mut voyage_start = departure;
+        for route_plan in [&outbound_route, &inbound_route] {
+            boat.route_plan = Some(route_plan.clone());
+            let sim_result = match sim_waypoint_mission(boat, voyage_start, simulation) {
+                Ok(sim_result) => sim_result,
+                Err(e) => {
+                    result = Err(MarineSimError::simulation(format!("Error during round trip departing {}", departure), e));
+                    break;
+                }
+            };
+            if !sim_result.is_completed() {
+                tracing::warn!("Round trip departing {} did not finish: {}. Stopping schedule", departure, sim_result.status);
+                break;
+            }
+            // The voyage ends at its last ship log entry, the time of the SimulationFinished event since the ship log may have been streamed to a ship log writer
+            voyage_start = sim_result.kpis.events.last().map_or(voyage_start + sim_result.kpis.duration, |event| event.timestamp) + turnaround;
+            voyages.push(sim_result.kpis);
+        }
+        if voyages.len() < 2 {
+            break;
+        }
+
+        // Only count the round trip if the vessel is back within the period
+        let arrival = voyage_start - turnaround;
+        if arrival > period_end {
+            break;
+        }
+        let inbound = voyages.pop().unwrap();
+        let outbound = voyages.pop().unwrap();
+        round_trips.push(RoundTripKpis { departure, arrival, outbound, inbound });
+        departure = voyage_start;
+    }
+    // Put the route plan back
+    boat.route_plan = Some(outbound_route);
+    result?;
+
+    // Annualize
+    let period = period_end - period_start;
+    let years = period.as_seconds_f64() / (365.25*24.0*3600.0);
+    let round_trip_duration_mean = match round_trips.len() {
+        0 => None,
+        n => Some(round_trips.iter().map(|round_trip| round_trip.duration()).sum::<time::Duration>() / n as u32),
+    };
+    let distance: f64 = round_trips.iter().map(|round_trip| round_trip.distance()).sum();
+
+    return Ok(ScheduleKpis {
+        round_trips_per_year: round_trips.len() as f64 / years,
+        distance_per_year: distance / years,
+        round_trips,
+        period,
+        round_trip_duration_mean,
+    });
+}
+
+/// Statistics of a distribution of values, e.g. the travel times of a Monte Carlo ensemble, see run_monte_carlo()
+#[derive(Debug, Clone, PartialEq)]
+pub struct DistributionStats {
+    /// Number of values
+    pub n: usize,
+    pub mean: f64,
+    /// Sample standard deviation, zero if there is only one value
+    pub std: f64,
+    pub min: f64,
+    pub max: f64,
+    /// 5th percentile
+    pub p5: f64,
+    /// 50th percentile (median)
+    pub p50: f64,
+    /// 95th percentile
+    pub p95: f64,
+    /// 95% confidence interval of the mean (low, high), using the normal approximation mean ± 1.96*std/sqrt(n)
+    pub mean_ci95: (f64, f64),
+}
+
+impl DistributionStats {
+    /// Computes the statistics of the finite values, None if there are no finite values
+    pub fn from_values(values: &Vec<f64>) -> Option<DistributionStats> {
+        let mut sorted: Vec<f64> = values.iter().copied().filter(|value| value.is_finite()).collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let n = sorted.len();
+        let (mean, std) = match n {
+            1 => (sorted[0], 0.0),
+            _ => get_vec_f64_mean_and_std(&sorted, true).ok()?,
+        };
+        let half_width = 1.96*std/(n as f64).sqrt();
+        return Some(DistributionStats {
+            n,
+            mean,
+            std,
+            min: sorted[0],
+            max: sorted[n - 1],
+            p5: get_percentile(&sorted, 5.0),
+            p50: get_percentile(&sorted, 50.0),
+            p95: get_percentile(&sorted, 95.0),
+            mean_ci95: (mean - half_width, mean + half_width),
+        });
+    }
+}
+
+/// Returns the percentile, in \[0, 100\], of sorted values, linearly interpolated between the closest values
+pub(crate) fn get_percentile(sorted: &Vec<f64>, percentile: f64) -> f64 {
+    let position = (percentile/100.0).clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let below = position.floor() as usize;
+    let above = position.ceil() as usize;
+    let fraction = position - below as f64;
+    return sorted[below]*(1.0 - fraction) + sorted[above]*fraction;
+}
+
+/// Results of a Monte Carlo ensemble of simulations, see run_monte_carlo()
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonteCarloResult {
+    /// Number of runs simulated
+    pub n_runs: usize,
+    /// Number of runs that reached the end of the route
+    pub n_completed: usize,
+    /// \[s\]. Travel times of the completed runs, None if no run was completed
+    pub travel_time: Option<DistributionStats>,
+    /// \[m\]. Distances sailed on the completed runs, None if no run was completed
+    pub distance: Option<DistributionStats>,
+    /// Departure time and simulation result of each run
+    pub runs: Vec<(UtcDateTime, SimulationResult)>,
+}
+
+/// Runs a Monte Carlo ensemble of n_runs simulations of the waypoint mission from the first of simulation.start_times and returns the distributions of the travel time and distance
+/// The stochastic inputs are perturbed in each run:
+/// - The velocity is drawn at random by the simulation method, e.g. SimMethod::MeanAndSTDVelocity and SimMethod::EmpiricalSpeed
+/// - The departure is moved by a random time within simulation.departure_jitter, if set
+/// - The weather from simulation.weather is perturbed with simulation.weather_noise, if both are set, see NoisyWeather
+/// Only the runs that reach the end of the route are included in the distributions. The ship logs of all runs are added to boat.ship_log as in sim_waypoint_missions()
+/// simulation.weather is put back as it was afterwards
+/// # Example:
+/// `simulation.departure_jitter = Some(time::Duration::hours(12));`
+/// `let result = run_monte_carlo(&mut boat, &mut simulation, 500)?;`
+/// `println!("Travel time 95th percentile: {:.1} days", result.travel_time.unwrap().p95/86400.0);`
+pub fn run_monte_carlo(boat: &mut Boat, simulation: &mut Simulation, n_runs: usize) -> Result<MonteCarloResult, MarineSimError> {
+    let base_departure: UtcDateTime = match simulation.start_times.first() {
+        Some(start_time) => *start_time,
+        None => return Err(MarineSimError::InvalidInput("Simulation has no start time".to_string())),
+    };
+    if simulation.weather_noise.is_some() && simulation.weather.is_none() {
+        return Err(MarineSimError::InvalidInput("Weather noise needs a weather provider in simulation.weather".to_string()));
+    }
+
+    let weather = simulation.weather.clone();
+    let mut runs: Vec<(UtcDateTime, SimulationResult)> = Vec::with_capacity(n_runs);
+    let mut result: Result<(), MarineSimError> = Ok(());
+    for i in 0..n_runs {
+        // Perturb the departure and the weather
+        let departure = match simulation.departure_jitter {
+            Some(jitter) if jitter > time::Duration::ZERO => base_departure + time::Duration::seconds_f64(rand::random_range(-1.0..=1.0)*jitter.as_seconds_f64()),
+            _ => base_departure,
+        };
+        if let (Some(weather), Some(noise)) = (&weather, simulation.weather_noise) {
+            simulation.weather = Some(std::sync::Arc::new(NoisyWeather::new(weather.clone(), noise)));
+        }
+
+        match sim_waypoint_mission(boat, departure, simulation) {
+            Ok(sim_result) => runs.push((departure, sim_result)),
+            Err(e) => {
+                result = Err(MarineSimError::simulation(format!("Error during Monte Carlo run {}", i), e));
+                break;
+            }
+        }
+    }
+    // Put the weather back
+    simulation.weather = weather;
+    result?;
+
+    // Distributions of the completed runs
+    let completed: Vec<&VoyageKpis> = runs.iter().filter(|(_, sim_result)| sim_result.is_completed()).map(|(_, sim_result)| &sim_result.kpis).collect();
+    let travel_times: Vec<f64> = completed.iter().map(|kpis| kpis.duration.as_seconds_f64()).collect();
+    let distances: Vec<f64> = completed.iter().map(|kpis| kpis.distance).collect();
+
+    return Ok(MonteCarloResult {
+        n_runs,
+        n_completed: completed.len(),
+        travel_time: DistributionStats::from_values(&travel_times),
+        distance: DistributionStats::from_values(&distances),
+        runs,
+    });
+}
+
+/// The values of a boat parameter to simulate with sweep_parameter()
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterSweep {
+    /// The parameter to change
+    pub parameter: BoatParameter,
+    /// The values to simulate, in the units of the parameter, see BoatParameter
+    pub values: Vec<f64>,
+}
+
+impl ParameterSweep {
+    /// Creates a parameter sweep over the given values
+    pub fn new(parameter: BoatParameter, values: Vec<f64>) -> ParameterSweep {
+        ParameterSweep {
+            parameter,
+            values,
+        }
+    }
+
+    /// Creates a parameter sweep over num_values evenly spaced values from start to end, both included
+    /// # Example:
+    /// `let sweep = ParameterSweep::linspace(BoatParameter::SailArea, 100.0, 500.0, 9);`
+    pub fn linspace(parameter: BoatParameter, start: f64, end: f64, num_values: usize) -> ParameterSweep {
+        let values: Vec<f64> = match num_values {
+            0 => Vec::new(),
+            1 => vec![start],
+            n => (0..n).map(|i| start + (end - start)*(i as f64)/((n - 1) as f64)).collect(),
+        };
+        return ParameterSweep::new(parameter, values);
+    }
+}
+
+/// Results of one value of a parameter sweep, see sweep_parameter()
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepPoint {
+    /// The value of the parameter
+    pub value: f64,
+    /// Number of voyages that reached the end of the route
+    pub n_completed: usize,
+    /// \[s\]. Travel times of the completed voyages, None if no voyage was completed
+    pub travel_time: Option<DistributionStats>,
+    /// Simulation result of each start time in simulation.start_times
+    pub results: Vec<SimulationResult>,
+}
+
+/// Sensitivity analysis, simulates the voyages from every start time in simulation.start_times for each value of a boat parameter, e.g. sail areas from 100 to 500 m²
+/// Each value is simulated on a copy of boat_template, so the template is not changed
+/// Returns one SweepPoint per value in the order of the values, the travel time statistics against the values make the travel time curve
+/// Tip: Set simulation.weather to a CachedWeather so the weather is only downloaded once for all the values
+/// # Example:
+/// `let curve = sweep_parameter(&boat, &ParameterSweep::linspace(BoatParameter::SailArea, 100.0, 500.0, 9), &simulation)?;`
+/// `for point in &curve { println!("{} m²: {:?}", point.value, point.travel_time.as_ref().map(|t| t.mean/86400.0)); }`
+pub fn sweep_parameter(boat_template: &Boat, sweep: &ParameterSweep, simulation: &Simulation) -> Result<Vec<SweepPoint>, MarineSimError> {
+    if simulation.start_times.is_empty() {
+        return Err(MarineSimError::InvalidInput("Simulation has no start time".to_string()));
+    }
+
+    let mut curve: Vec<SweepPoint> = Vec::with_capacity(sweep.values.len());
+    for value in &sweep.values {
+        let mut boat = boat_template.clone();
+        sweep.parameter.set(&mut boat, *value)?;
+
+        let mut results: Vec<SimulationResult> = Vec::with_capacity(simulation.start_times.len());
+        for start_time in &simulation.start_times {
+            match sim_waypoint_mission(&mut boat, *start_time, simulation) {
+                Ok(sim_result) => results.push(sim_result),
+                Err(e) => return Err(MarineSimError::simulation(format!("Error during simulation with {:?} = {} departing {}", sweep.parameter, value, start_time), e)),
+            }
+        }
+
+        let travel_times: Vec<f64> = results.iter().filter(|sim_result| sim_result.is_completed()).map(|sim_result| sim_result.kpis.duration.as_seconds_f64()).collect();
+        curve.push(SweepPoint {
+            value: *value,
+            n_completed: travel_times.len(),
+            travel_time: DistributionStats::from_values(&travel_times),
+            results,
+        });
+    }
+    return Ok(curve);
+}
+
 /// Function to simulate the boat following a waypoint mission
 /// Is basically a simulation handler that pipes the boat to the correct simulation function
-pub fn sim_waypoint_mission(boat: &mut Boat, start_time: time::UtcDateTime, simulation: &Simulation) -> Result<String, io::Error> {
+/// Returns how the voyage ended, the number of iterations used and the key performance indicators of the voyage, see SimulationResult
+/// Legs crossing restricted zones with ZoneAction::Avoid are rerouted around them first, the boat gets its own route plan back after the voyage, and entering zones with ZoneAction::Warn adds a ZoneEntered event to boat.events, see RoutePlan.zones
+/// The events of the voyage, e.g. LegStarted, WaypointReached and SimulationFinished, are added to boat.events and VoyageKpis.events and passed to the event callbacks of the simulation, see Simulation.add_event_callback()
+/// If simulation.earth_model is set the voyage is simulated on that model of the Earth, see with_earth_model()
+pub fn sim_waypoint_mission(boat: &mut Boat, start_time: time::UtcDateTime, simulation: &Simulation) -> Result<SimulationResult, MarineSimError> {
+    // The tracing events of the voyage are in a span with its departure time
+    let _span = tracing::info_span!("voyage", departure = %format_timestamp(start_time)).entered();
+    // The boat reports the progress of the voyage to the progress callbacks with each new ship log entry, until the voyage ends
+    boat.voyage_departure = Some(start_time);
+    boat.progress_callbacks = simulation.progress_callbacks.clone();
+    let result = match simulation.earth_model {
+        Some(earth_model) => with_earth_model(earth_model, || sim_waypoint_mission_on_earth_model(boat, start_time, simulation)),
+        None => sim_waypoint_mission_on_earth_model(boat, start_time, simulation),
+    };
+    boat.voyage_departure = None;
+    boat.progress_callbacks.clear();
+    boat.voyage_log = None;
+    return result;
+}
+
+/// Simulates a waypoint mission on the Earth model of the thread, see sim_waypoint_mission()
+fn sim_waypoint_mission_on_earth_model(boat: &mut Boat, start_time: time::UtcDateTime, simulation: &Simulation) -> Result<SimulationResult, MarineSimError> {
+    // Remember the route plan and tanks at departure, rerouting around restricted zones and bunkering stops change them during the voyage
+    let route_plan_departure: Option<RoutePlan> = boat.route_plan.clone();
+    let tanks_departure: (Option<Tank>, Option<Tank>) = (boat.fuel_tank, boat.fresh_water_tank);
+
+    let result = sim_voyage_on_earth_model(boat, start_time, simulation, tanks_departure);
+
+    // Remove the rerouted legs and bunkering stops from the route plan and set the tanks back to their levels at departure, also if the voyage failed
+    boat.route_plan = route_plan_departure;
+    (boat.fuel_tank, boat.fresh_water_tank) = tanks_departure;
+    if let Some(tank) = boat.fuel_tank {
+        boat.fuel_current = uom::si::f64::Mass::new::<uom::si::mass::ton>(tank.level);
+    }
+    return result;
+}
+
+/// Simulates a voyage for sim_waypoint_mission_on_earth_model(), which sets the route plan and tanks back to tanks_departure afterwards
+fn sim_voyage_on_earth_model(boat: &mut Boat, start_time: time::UtcDateTime, simulation: &Simulation, tanks_departure: (Option<Tank>, Option<Tank>)) -> Result<SimulationResult, MarineSimError> {
     // Check if the boat has a route plan, if no route plan
     if boat.route_plan.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Boat has no route plan"));
+        return Err(MarineSimError::MissingBoatField("route plan".to_string()));
     }
 
+    // Remember where the voyage starts in the ship log and how many tacks the boat had made before it
+    let log_start: usize = boat.ship_log.len();
+    let num_tacks_start: u64 = boat.num_tacks;
+    let arrivals_start: usize = boat.waypoint_arrivals.len();
+    let events_start: usize = boat.events.len();
+
+    // Reroute the legs that cross restricted zones the boat should avoid
+    if boat.route_plan.as_ref().unwrap().zones.iter().any(|zone| zone.action == ZoneAction::Avoid) {
+        boat.route_plan.as_mut().unwrap().reroute_around_zones()?;
+    }
+
+    // Legs in traffic lanes must follow the direction of traffic or cross at right angles
+    check_traffic_lanes(boat.route_plan.as_ref().unwrap())?;
+
+    // The voyage starts on the first leg
+    let first_waypoint = match boat.route_plan.as_ref().unwrap().first() {
+        Some(leg) => leg.p1,
+        None => return Err(MarineSimError::InvalidInput("Route plan has no legs".to_string())),
+    };
+    boat.events.push(SimulationEvent {
+        timestamp: start_time,
+        location: first_waypoint,
+        leg: Some(1),
+        kind: SimulationEventKind::LegStarted,
+    });
+
+    // The ship log of the voyage is added up, and streamed if wanted, as it is logged, see VoyageLog
+    boat.voyage_log = Some(VoyageLog::new(boat, simulation));
+
     // match simulation method and run corresponding simulation function
-    match simulation.simulation_method {
+    let (status, iterations): (SimulationStatus, usize) = match simulation.simulation_method {
         SimMethod::ConstVelocity => {
             // Simulate the boat using constant velocity
             match sim_waypoint_mission_constant_velocity(boat, start_time, simulation) {
-                Ok(sim_msg) => {
-                    return Ok(sim_msg);
-                }
+                Ok(sim_status) => sim_status,
                 Err(e) => {
                     return Err(e);
                 }
@@ -134,9 +1793,25 @@ pub fn sim_waypoint_mission(boat: &mut Boat, start_time: time::UtcDateTime, simu
         SimMethod::MeanAndSTDVelocity => {
             // Simulate the boat using constant velocity
             match sim_waypoint_mission_mean_and_std_velocity(boat, start_time, simulation) {
-                Ok(sim_msg) => {
-                    return Ok(sim_msg);
+                Ok(sim_status) => sim_status,
+                Err(e) => {
+                    return Err(e);
                 }
+            }
+        }
+        SimMethod::EmpiricalSpeed => {
+            // Simulate the boat using speeds from real ship logs
+            match sim_waypoint_mission_empirical_speed(boat, start_time, simulation) {
+                Ok(sim_status) => sim_status,
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+        SimMethod::Drift => {
+            // Simulate the boat drifting with the current and wind
+            match sim_drift(boat, start_time, simulation) {
+                Ok(sim_status) => sim_status,
                 Err(e) => {
                     return Err(e);
                 }
@@ -153,48 +1828,99 @@ pub fn sim_waypoint_mission(boat: &mut Boat, start_time: time::UtcDateTime, simu
         //         }
         //     }
         // }
+        #[cfg(feature = "copernicus")]
         SimMethod::WeatherDataFromCopernicus => {
             // Simulate the boat using weather data from Copernicus
             match sim_waypoint_mission_weather_data_from_copernicus(boat, start_time, simulation) {
-                Ok(sim_msg) => {
-                    return Ok(sim_msg);
-                }
+                Ok(sim_status) => sim_status,
                 Err(e) => {
                     return Err(e);
                 }
             }
         }
+        #[cfg(feature = "copernicus")]
         SimMethod::FastWeatherDataFromCopernicus => {
             // Simualate the boat quickly using 1 download of weather data from copernicus
             match fast_sim_waypoint_mission_weather_data_from_copernicus(boat, start_time, simulation) {
-                Ok(sim_msg) => {
-                    return Ok(sim_msg);
-                }
+                Ok(sim_status) => sim_status,
                 Err(e) => {
                     return Err(e);
                 }
             }
         }
+        #[cfg(not(feature = "copernicus"))]
+        SimMethod::WeatherDataFromCopernicus | SimMethod::FastWeatherDataFromCopernicus => {
+            return Err(MarineSimError::InvalidInput(format!("{:?} needs the \"copernicus\" feature", simulation.simulation_method)));
+        }
         // Add other simulation methods here
-    } 
+    };
+
+    // Add up the rest of the ship log of the voyage, this adds a ZoneEntered event for each restricted zone with ZoneAction::Warn the boat entered and the decimated ship log if wanted
+    let mut voyage_log: VoyageLog = match boat.voyage_log.take() {
+        Some(voyage_log) => voyage_log,
+        None => return Err(MarineSimError::InvalidInput("The ship log of the voyage was removed from the boat during the voyage".to_string())),
+    };
+    let mut kpis = voyage_log.finish(boat);
+
+    // The voyage is finished, put its events in chronological order and call the event callbacks
+    if let Some(last_entry) = boat.ship_log[log_start..].last() {
+        let (timestamp, location) = (last_entry.timestamp, last_entry.coordinates_current);
+        boat.events.push(SimulationEvent {
+            timestamp,
+            location,
+            leg: boat.current_leg,
+            kind: SimulationEventKind::SimulationFinished { status },
+        });
+    }
+    boat.events[events_start..].sort_by_key(|event| event.timestamp);
+    for event in &boat.events[events_start..] {
+        for callback in &simulation.event_callbacks {
+            (callback.0)(event);
+        }
+    }
+
+    // Key performance indicators the ship log does not have
+    kpis.num_tacks = Some(boat.num_tacks - num_tacks_start);
+    kpis.waypoint_arrivals = boat.waypoint_arrivals[arrivals_start..].to_vec();
+    kpis.events = boat.events[events_start..].to_vec();
+    kpis.fuel = boat.fuel_tank.map(|tank| 1000.0*(tank.consumed - tanks_departure.0.map_or(0.0, |tank| tank.consumed)));
+
+    // How far along the route plan the voyage got, the route plan is done if the voyage was completed
+    let mut progress = boat.get_voyage_progress(start_time).unwrap();
+    progress.simulated_time = kpis.duration;
+    if status.is_completed() {
+        progress.legs_completed = progress.total_legs;
+        progress.distance_covered = progress.total_distance;
+    }
+
+    // Write the rest of the ship log of the voyage to the ship log writer and remove it from the boat, if wanted
+    voyage_log.write(boat)?;
+
+    return Ok(SimulationResult {
+        status,
+        iterations,
+        kpis,
+        progress,
+    });
 }
 
 
 // Simulators
 //----------------------------------------------------
 /// Simulates the boat using constant velocity (uses boat.mean_velocity)
-pub fn sim_waypoint_mission_constant_velocity(boat: &mut Boat, start_time: time::UtcDateTime, simulation: &Simulation) -> Result<String, io::Error> {
+pub fn sim_waypoint_mission_constant_velocity(boat: &mut Boat, start_time: time::UtcDateTime, simulation: &Simulation) -> Result<(SimulationStatus, usize), MarineSimError> {
     // Verify that boat has mean velocity set
-    if boat.velocity_mean.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing mean velocity"));
-    }
+    let velocity_mean: f64 = match boat.velocity_mean {
+        Some(velocity_mean) => velocity_mean,
+        None => return Err(MarineSimError::MissingBoatField("mean velocity".to_string())),
+    };
 
     // Set boats current location to the first waypoint
-    boat.location = Some(boat.route_plan.as_ref().expect("Route plan missing?")[0].p1);
+    boat.location = Some(get_boat_route_plan(boat)?[0].p1);
     // Set current leg to 1
     boat.current_leg = Some(1);
     // Get total number of legs
-    let total_legs: usize = boat.route_plan.as_ref().expect("Route plan missing?").len();
+    let total_legs: usize = get_boat_route_plan(boat)?.len();
 
     // Init travel_dist as zero
     let mut travel_dist: f64;
@@ -203,72 +1929,106 @@ pub fn sim_waypoint_mission_constant_velocity(boat: &mut Boat, start_time: time:
     // Get initial location
     let coordinates_initial = boat.location.unwrap();
     // Get final location to last waypoint
-    let coordinates_final = boat.route_plan.as_ref().expect("Route plan missing?")[total_legs - 1].p2;                
+    let coordinates_final = get_boat_route_plan(boat)?[total_legs - 1].p2;                
     let new_log_entry: ShipLogEntry = ShipLogEntry {
-        timestamp: time::UtcDateTime::new(time::Date::from_calendar_date(start_time.year(), start_time.month(), start_time.day()).expect("Couldn't make time::Date"), time::Time::from_hms(start_time.hour(), start_time.minute(), start_time.second()).expect("Couldn't make time::Time")),
+        timestamp: start_time - time::Duration::nanoseconds(start_time.nanosecond() as i64),
         coordinates_initial: coordinates_initial,
         coordinates_current: coordinates_initial,
         coordinates_final: coordinates_final,
         cargo_on_board: Some(boat.cargo_current),
-        velocity: Some(PhysVec::new(boat.velocity_mean.unwrap(), 0.0)),  // Initial velocity is defaulted to direction zero degrees
+        velocity: Some(PhysVec::new(velocity_mean, 0.0)),  // Initial velocity is defaulted to direction zero degrees
         course: None,
         heading: None,
         track_angle: Some(Rhumb.bearing(coordinates_initial, boat.route_plan.as_ref().unwrap()[0].p2)),
         true_bearing: None,
         draft: None,
         navigation_status: None,
+        depth: None,
+        wind: None,
+        wave_height: None,
+        cross_track_error: None,
+        foiling: None,
     };
     // Push first ship log entry
-    boat.ship_log.push(new_log_entry);
+    boat.push_ship_log_entry(new_log_entry);
+    // When the voyage started, for the maximum simulated duration
+    let voyage_start: UtcDateTime = start_time;
+    // Start time of the voyage, pushed back by the time spent in port at port calls
+    let mut start_time = start_time;
 
     // Loop through each time step
     for i in 0..simulation.max_iterations {
+        // Stop if the voyage has taken longer than the maximum simulated duration
+        if max_simulated_duration_reached(boat, simulation, voyage_start) {
+            return Ok((SimulationStatus::MaxSimulatedDurationReached, i));
+        }
+        // Stop if a tank ran empty, detour to a bunkering port if one runs low
+        if let Some(status) = check_tanks(boat, simulation.bunkering.as_ref()) {
+            return Ok((status, i));
+        }
         // Simulate the boat moving towards the next waypoint
         // Get distance traveled [m] in time step [s] with velocity [m/s]
-        // travel_dist = boat.velocity_mean.unwrap() * time_step;
-        travel_dist = boat.velocity_mean.unwrap() * simulation.time_step.as_seconds_f64();
+        // travel_dist = velocity_mean * time_step;
+        travel_dist = velocity_mean * simulation.time_step.as_seconds_f64();
 
         // While still have some distance left to travel during time step
         while travel_dist > 0.0 {
 
             // Get next waypoint
-            let next_waypoint: geo::Point = boat.route_plan.as_ref().expect("Route plan missing?")[(boat.current_leg.unwrap()-1) as usize].p2;
+            let next_waypoint: geo::Point = get_boat_route_plan(boat)?[(boat.current_leg.unwrap()-1) as usize].p2;
             // Get distance to next waypoint from current location
-            let dist_to_next_waypoint: f64 = Haversine.distance(boat.location.unwrap(), next_waypoint);
+            let dist_to_next_waypoint: f64 = Earth.distance(boat.location.unwrap(), next_waypoint);
             // Set vessel heading as heading to next waypoint
-            boat.heading = Some(geo::Haversine.bearing(boat.location.unwrap(), next_waypoint));
+            boat.heading = Some(Earth.bearing(boat.location.unwrap(), next_waypoint));
 
             // if distance traveled is greater than the distance to the next waypoint move to next waypoint, update current leg number and go to next while loop iteration
             if travel_dist > dist_to_next_waypoint {
+                // Check that the boat does not run aground on the way to the next waypoint
+                if let Some(land_mask) = &simulation.land_mask {
+                    land_mask.check_step(boat.location.unwrap(), next_waypoint, boat.current_leg.unwrap())?;
+                }
                 // Move to next waypoint
                 boat.location = Some(next_waypoint);
+                // Log the arrival at the waypoint, the vessel arrives part way through the time step
+                let arrival_time = get_time_after_steps(start_time, simulation.time_step, i + 1)? - time::Duration::seconds_f64((travel_dist - dist_to_next_waypoint)/velocity_mean);
+                log_waypoint_arrival(boat, arrival_time)?;
 
                 // If the boat has reached the last waypoint, stop the simulation
                 if boat.location.unwrap() == coordinates_final {
                     // Update ship logs with last point
                     let new_log_entry: ShipLogEntry = ShipLogEntry {
                         // Set timestamp to last shiplogentry + time step
-                        timestamp: boat.ship_log.last().unwrap().timestamp.checked_add(simulation.time_step).expect("Couldn't add seconds, probably an overflow occured"),
+                        timestamp: add_duration(boat.ship_log.last().unwrap().timestamp, simulation.time_step)?,
                         coordinates_initial: coordinates_initial,
                         coordinates_current: boat.location.unwrap(),
                         coordinates_final: coordinates_final,
                         cargo_on_board: Some(boat.cargo_current),
-                        velocity: Some(PhysVec::new(boat.velocity_mean.expect("Missing vessel mean velocity"), boat.heading.expect("Missing vessel heading"))),
+                        velocity: Some(PhysVec::new(velocity_mean, get_boat_heading(boat)?)),
                         course: None,
                         heading: boat.heading,
                         track_angle: Some(Rhumb.bearing(boat.ship_log.last().unwrap().coordinates_current, boat.location.unwrap())),
                         true_bearing: None,
                         draft: None,
                         navigation_status: None,
+                        depth: None,
+                        wind: None,
+                        wave_height: None,
+                        cross_track_error: None,
+                        foiling: None,
                     };
 
                     // Push the new log entry to the ship log
-                    boat.ship_log.push(new_log_entry);
+                    boat.push_ship_log_entry(new_log_entry);
+                    // Make the port call at the final waypoint, if any
+                    do_waypoint_stops(boat, simulation, boat.ship_log.last().unwrap().timestamp, coordinates_initial, coordinates_final)?;
 
                     // Stop the simulation
-                    return Ok("Simulation completed".to_string());
+                    return Ok((SimulationStatus::Completed, i + 1));
                 }
 
+                // Make the port call at the waypoint, if any
+                start_time += do_waypoint_stops(boat, simulation, arrival_time, coordinates_initial, coordinates_final)?;
+
                 // Update current leg number
                 boat.current_leg = Some(boat.current_leg.unwrap() + 1);
                 // Reduce travel distance by distance to next waypoint
@@ -277,32 +2037,48 @@ pub fn sim_waypoint_mission_constant_velocity(boat: &mut Boat, start_time: time:
             // Otherwise, move boat towards next waypoint and log to ship_log
             else {
                 // Get bearing to next waypoint
-                let bearing = Haversine.bearing(boat.location.unwrap(), next_waypoint);
+                let bearing = Earth.bearing(boat.location.unwrap(), next_waypoint);
 
                 // Get the new location of the boat with distance left to travel during timestep and bearing to next waypoint
-                let new_location: geo::Point = Haversine.destination(boat.location.unwrap(), bearing, travel_dist); // travel_dist in meters, https://docs.rs/geo/0.30.0/geo/algorithm/line_measures/metric_spaces/struct.HaversineMeasure.html#method.destination
+                let new_location: geo::Point = Earth.destination(boat.location.unwrap(), bearing, travel_dist); // travel_dist in meters, see Earth.destination()
+
+                // Check that the boat does not run aground
+                if let Some(land_mask) = &simulation.land_mask {
+                    land_mask.check_step(boat.location.unwrap(), new_location, boat.current_leg.unwrap())?;
+                }
 
                 // Update the location of the boat
                 boat.location = Some(new_location);
 
                 // Log the new location to the ship log
-                let new_log_entry: ShipLogEntry = ShipLogEntry {
-                    timestamp: start_time.checked_add(simulation.time_step.checked_mul((i + 1) as i32).expect("Could not multiply, an overflow error probably occurred")).expect("Could not add timestep, an overflow probably occurred"),
+                let mut new_log_entry: ShipLogEntry = ShipLogEntry {
+                    timestamp: get_time_after_steps(start_time, simulation.time_step, i + 1)?,
                     coordinates_initial: coordinates_initial,
                     coordinates_current: boat.location.unwrap(),
                     coordinates_final: coordinates_final,
                     cargo_on_board: Some(boat.cargo_current),
-                    velocity: Some(PhysVec::new(boat.velocity_mean.unwrap(), boat.heading.unwrap())),
+                    velocity: Some(PhysVec::new(velocity_mean, boat.heading.unwrap())),
                     course: None,
                     heading: boat.heading,
                     track_angle: Some(Rhumb.bearing(boat.ship_log.last().unwrap().coordinates_current, boat.location.unwrap())),
                     true_bearing: None,
                     draft: None,
                     navigation_status: None,
+                    depth: None,
+                    wind: None,
+                    wave_height: None,
+                    cross_track_error: None,
+                    foiling: None,
                     };
 
+                // Check the water depth, stop if the boat ran aground
+                let grounded = check_depth(boat, simulation, &mut new_log_entry);
+
                 // Push the new log entry to the ship log
-                boat.ship_log.push(new_log_entry);
+                boat.push_ship_log_entry(new_log_entry);
+                if grounded {
+                    return Ok((SimulationStatus::RanAground, i + 1));
+                }
 
                 // Set travel distance to zero for next loop
                 travel_dist = travel_dist - travel_dist;
@@ -312,22 +2088,54 @@ pub fn sim_waypoint_mission_constant_velocity(boat: &mut Boat, start_time: time:
 
     // Simulation ran through all the iterations, return ship log and error that the simulation did not finish
     // Return the ship log TODO: Move inside for loop
-    return Ok("Maximized number of iterations. Stopping simulation".to_string());
+    return Ok((SimulationStatus::MaxIterationsReached, simulation.max_iterations));
 }
 
 /// Simulates the boat using mean and standard deviation velocity (uses boat.mean_velocity and boat.std_velocity)
-pub fn sim_waypoint_mission_mean_and_std_velocity(boat: &mut Boat, start_time: time::UtcDateTime, simulation: &Simulation) -> Result<String, io::Error> {
+pub fn sim_waypoint_mission_mean_and_std_velocity(boat: &mut Boat, start_time: time::UtcDateTime, simulation: &Simulation) -> Result<(SimulationStatus, usize), MarineSimError> {
     // Verify that boat has mean and std velocity set
-    if boat.velocity_mean.is_none() || boat.velocity_std.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing mean or standard deviation velocity"));
-    }
+    let (velocity_mean, velocity_std): (f64, f64) = match (boat.velocity_mean, boat.velocity_std) {
+        (Some(velocity_mean), Some(velocity_std)) => (velocity_mean, velocity_std),
+        _ => return Err(MarineSimError::MissingBoatField("mean or standard deviation velocity".to_string())),
+    };
+
+    // Working velocity is mean velocity plus a random standard deviation from the mean
+    return sim_waypoint_mission_sampled_speed(boat, start_time, simulation, |_, _| Ok((velocity_mean + rand::random_range(-1.0..=1.0) * velocity_std, None)));
+}
+
+/// Simulates the boat using speeds drawn at random from real ship logs (uses boat.empirical_speed_model)
+/// If the model is conditioned on the true wind angle, the wind is taken from simulation.weather. Without simulation.weather the wind angle condition is not used
+pub fn sim_waypoint_mission_empirical_speed(boat: &mut Boat, start_time: time::UtcDateTime, simulation: &Simulation) -> Result<(SimulationStatus, usize), MarineSimError> {
+    // Verify that boat has an empirical speed model with speeds in it
+    let speed_model: EmpiricalSpeedModel = match &boat.empirical_speed_model {
+        Some(model) if !model.samples.is_empty() => model.clone(),
+        _ => return Err(MarineSimError::MissingBoatField("empirical speed model or it has no speeds".to_string())),
+    };
+
+    // Working velocity is a speed drawn from the speed model
+    return sim_waypoint_mission_sampled_speed(boat, start_time, simulation, |boat, time_now| {
+        // Get the wind if the speed model needs it and the simulation has weather
+        let wind: Option<PhysVec> = match (&simulation.weather, speed_model.wind_angle_bucket_size) {
+            (Some(weather), Some(_)) => Some(weather.wind(time_now, get_boat_location(boat)?)?),
+            _ => None,
+        };
+        let wind_angle: Option<f64> = match wind {
+            Some(wind) => Some(get_true_wind_angle(get_boat_heading(boat)?, wind.angle)),
+            None => None,
+        };
+        return Ok((speed_model.sample(time_now, wind_angle), wind));
+    });
+}
 
+/// Simulates the boat sailing from waypoint to waypoint with a new speed each time step, for SimMethod::MeanAndSTDVelocity and SimMethod::EmpiricalSpeed
+/// sample_speed: Returns the speed in \[m/s\] for the time step starting at the given time, when the boat is heading to the next waypoint, and the wind to log if it is known
+fn sim_waypoint_mission_sampled_speed<F: FnMut(&Boat, UtcDateTime) -> Result<(f64, Option<PhysVec>), MarineSimError>>(boat: &mut Boat, start_time: time::UtcDateTime, simulation: &Simulation, mut sample_speed: F) -> Result<(SimulationStatus, usize), MarineSimError> {
     // Set boats current location to the first waypoint
-    boat.location = Some(boat.route_plan.as_ref().expect("Route plan missing?")[0].p1);
+    boat.location = Some(get_boat_route_plan(boat)?[0].p1);
     // Set current leg to 1
     boat.current_leg = Some(1);
     // Get total number of legs
-    let total_legs: usize = boat.route_plan.as_ref().expect("Route plan missing?").len();
+    let total_legs: usize = get_boat_route_plan(boat)?.len();
 
     // Init travel_dist, unit [m]
     let mut travel_dist: f64;
@@ -338,9 +2146,9 @@ pub fn sim_waypoint_mission_mean_and_std_velocity(boat: &mut Boat, start_time: t
     // Get initial location
     let coordinates_initial = boat.location.unwrap();
     // Get final location to last waypoint
-    let coordinates_final = boat.route_plan.as_ref().expect("Route plan missing?")[total_legs - 1].p2;                
+    let coordinates_final = get_boat_route_plan(boat)?[total_legs - 1].p2;                
     let new_log_entry: ShipLogEntry = ShipLogEntry {
-        timestamp: time::UtcDateTime::new(time::Date::from_calendar_date(start_time.year(), start_time.month(), start_time.day()).expect("Could not make time::Date from values"), time::Time::from_hms(start_time.hour(), start_time.minute(), start_time.second()).expect("Could not make time::Time from values")),
+        timestamp: start_time - time::Duration::nanoseconds(start_time.nanosecond() as i64),
         coordinates_initial: coordinates_initial,
         coordinates_current: coordinates_initial,
         coordinates_final: coordinates_final,
@@ -352,19 +2160,38 @@ pub fn sim_waypoint_mission_mean_and_std_velocity(boat: &mut Boat, start_time: t
         true_bearing: None,
         draft: None,
         navigation_status: None,
+        depth: None,
+        wind: None,
+        wave_height: None,
+        cross_track_error: None,
+        foiling: None,
     };
     // Push first ship log entry
-    boat.ship_log.push(new_log_entry);
+    boat.push_ship_log_entry(new_log_entry);
+    // When the voyage started, for the maximum simulated duration
+    let voyage_start: UtcDateTime = start_time;
+    // Start time of the voyage, pushed back by the time spent in port at port calls
+    let mut start_time = start_time;
 
 
     // Loop through each time step
     for i in 0..simulation.max_iterations {
+        // Stop if the voyage has taken longer than the maximum simulated duration
+        if max_simulated_duration_reached(boat, simulation, voyage_start) {
+            return Ok((SimulationStatus::MaxSimulatedDurationReached, i));
+        }
+        // Stop if a tank ran empty, detour to a bunkering port if one runs low
+        if let Some(status) = check_tanks(boat, simulation.bunkering.as_ref()) {
+            return Ok((status, i));
+        }
         // Simulate the boat moving towards the next waypoint
         // Get next waypoint
-        let next_waypoint: geo::Point = boat.route_plan.as_ref().expect("Route plan missing?")[(boat.current_leg.unwrap()-1) as usize].p2;
-        boat.heading = Some(Haversine.bearing(boat.location.unwrap(), next_waypoint));
-        // Working velocity is mean velocity plus a random standard deviation from the mean
-        working_velocity = PhysVec::new(boat.velocity_mean.expect("Missing vessel mean velocity") + rand::random_range(-1.0..=1.0) * boat.velocity_std.expect("Missing standard deviation for vessel velocity"), boat.heading.expect("Missing vessel heading"));
+        let next_waypoint: geo::Point = get_boat_route_plan(boat)?[(boat.current_leg.unwrap()-1) as usize].p2;
+        boat.heading = Some(Earth.bearing(boat.location.unwrap(), next_waypoint));
+        // Working velocity is the sampled speed towards the next waypoint
+        let time_now: UtcDateTime = boat.ship_log.last().unwrap().timestamp;
+        let (speed, wind): (f64, Option<PhysVec>) = sample_speed(boat, time_now)?;
+        working_velocity = PhysVec::new(speed, get_boat_heading(boat)?);
 
         // Get distance traveled in time step, unit [m]
         travel_dist = working_velocity.magnitude * simulation.time_step.as_seconds_f64();
@@ -372,21 +2199,28 @@ pub fn sim_waypoint_mission_mean_and_std_velocity(boat: &mut Boat, start_time: t
         // While still have some distance left to travel during time step
         while travel_dist > 0.0 {
             // Get next waypoint
-            let next_waypoint: geo::Point = boat.route_plan.as_ref().expect("Route plan missing?")[(boat.current_leg.unwrap()-1) as usize].p2;
+            let next_waypoint: geo::Point = get_boat_route_plan(boat)?[(boat.current_leg.unwrap()-1) as usize].p2;
             // Get distance to next waypoint from current location
-            let dist_to_next_waypoint: f64 = Haversine.distance(boat.location.unwrap(), next_waypoint);
+            let dist_to_next_waypoint: f64 = Earth.distance(boat.location.unwrap(), next_waypoint);
 
             // if distance traveled is greater than the distance to the next waypoint move to next waypoint, update current leg number and go to next while loop iteration
             if travel_dist > dist_to_next_waypoint {
+                // Check that the boat does not run aground on the way to the next waypoint
+                if let Some(land_mask) = &simulation.land_mask {
+                    land_mask.check_step(boat.location.unwrap(), next_waypoint, boat.current_leg.unwrap())?;
+                }
                 // Move to next waypoint
                 boat.location = Some(next_waypoint);
+                // Log the arrival at the waypoint, the vessel arrives part way through the time step
+                let arrival_time = get_time_after_steps(start_time, simulation.time_step, i + 1)? - time::Duration::seconds_f64((travel_dist - dist_to_next_waypoint)/working_velocity.magnitude);
+                log_waypoint_arrival(boat, arrival_time)?;
 
                 // If the boat has reached the last waypoint, stop the simulation
                 if boat.location.unwrap() == coordinates_final {
                     // Update ship logs with last point
                     let new_log_entry: ShipLogEntry = ShipLogEntry {
                         // Set timestamp to last shiplogentry + time step
-                        timestamp: boat.ship_log.last().unwrap().timestamp.checked_add(simulation.time_step).expect("Could not add time::Duration to time::UtcDateTime. Maybe an overflow happened?"),
+                        timestamp: add_duration(boat.ship_log.last().unwrap().timestamp, simulation.time_step)?,
                         coordinates_initial: coordinates_initial,
                         coordinates_current: boat.location.unwrap(),
                         coordinates_final: coordinates_final,
@@ -398,15 +2232,25 @@ pub fn sim_waypoint_mission_mean_and_std_velocity(boat: &mut Boat, start_time: t
                         true_bearing: None,
                         draft: None,
                         navigation_status: None,
+                        depth: None,
+                        wind: wind,
+                        wave_height: None,
+                        cross_track_error: None,
+                        foiling: None,
                     };
 
                     // Push the new log entry to the ship log
-                    boat.ship_log.push(new_log_entry);
+                    boat.push_ship_log_entry(new_log_entry);
+                    // Make the port call at the final waypoint, if any
+                    do_waypoint_stops(boat, simulation, boat.ship_log.last().unwrap().timestamp, coordinates_initial, coordinates_final)?;
 
                     // Stop the simulation
-                    return Ok("Simulation completed".to_string());
+                    return Ok((SimulationStatus::Completed, i + 1));
                 }
 
+                // Make the port call at the waypoint, if any
+                start_time += do_waypoint_stops(boat, simulation, arrival_time, coordinates_initial, coordinates_final)?;
+
                 // Update current leg number
                 boat.current_leg = Some(boat.current_leg.unwrap() + 1);
                 // Reduce travel distance by distance to next waypoint
@@ -415,69 +2259,200 @@ pub fn sim_waypoint_mission_mean_and_std_velocity(boat: &mut Boat, start_time: t
             // Otherwise, move boat towards next waypoint and log to ship_log
             else {
                 // Get bearing to next waypoint
-                let bearing = Haversine.bearing(boat.location.unwrap(), next_waypoint);
+                let bearing = Earth.bearing(boat.location.unwrap(), next_waypoint);
 
                 // Get the new location of the boat with distance left to travel during timestep and bearing to next waypoint, important to use meters for travel_dist
-                let new_location: geo::Point = Haversine.destination(boat.location.unwrap(), bearing, travel_dist);
+                let new_location: geo::Point = Earth.destination(boat.location.unwrap(), bearing, travel_dist);
+
+                // Check that the boat does not run aground
+                if let Some(land_mask) = &simulation.land_mask {
+                    land_mask.check_step(boat.location.unwrap(), new_location, boat.current_leg.unwrap())?;
+                }
 
                 // Update the location of the boat
                 boat.location = Some(new_location);
 
-                // Log the new location to the ship log
-                let new_log_entry: ShipLogEntry = ShipLogEntry {
-                    timestamp: start_time.checked_add(simulation.time_step.checked_mul((i + 1) as i32).expect("Could not multiply time::Duration with value. Maybe an overflow occurred?")).expect("Could not add time::Duration to time::UtcDateTime. Maybe an overflow occurred?"),
-                    coordinates_initial: coordinates_initial,
-                    coordinates_current: boat.location.unwrap(),
-                    coordinates_final: coordinates_final,
-                    cargo_on_board: Some(boat.cargo_current),
-                    velocity: Some(working_velocity),
-                    course: None,
-                    heading: boat.heading,
-                    track_angle: Some(Rhumb.bearing(boat.ship_log.last().unwrap().coordinates_current, boat.location.unwrap())),
-                    true_bearing: None,
-                    draft: None,
-                    navigation_status: None,
-                    };
+                // Log the new location to the ship log
+                let mut new_log_entry: ShipLogEntry = ShipLogEntry {
+                    timestamp: get_time_after_steps(start_time, simulation.time_step, i + 1)?,
+                    coordinates_initial: coordinates_initial,
+                    coordinates_current: boat.location.unwrap(),
+                    coordinates_final: coordinates_final,
+                    cargo_on_board: Some(boat.cargo_current),
+                    velocity: Some(working_velocity),
+                    course: None,
+                    heading: boat.heading,
+                    track_angle: Some(Rhumb.bearing(boat.ship_log.last().unwrap().coordinates_current, boat.location.unwrap())),
+                    true_bearing: None,
+                    draft: None,
+                    navigation_status: None,
+                    depth: None,
+                    wind: wind,
+                    wave_height: None,
+                    cross_track_error: None,
+                    foiling: None,
+                    };
+
+                // Check the water depth, stop if the boat ran aground
+                let grounded = check_depth(boat, simulation, &mut new_log_entry);
+
+                // Push the new log entry to the ship log
+                boat.push_ship_log_entry(new_log_entry);
+                if grounded {
+                    return Ok((SimulationStatus::RanAground, i + 1));
+                }
+
+                // Set travel distance to zero for next loop
+                travel_dist = 0.0;
+            }
+        } // End while loop
+    } // End for loop
+
+    // Simulation ran through all the iterations, return ship log and error that the simulation did not finish
+    // Return the ship log TODO: Move inside for loop
+    return Ok((SimulationStatus::MaxIterationsReached, simulation.max_iterations));
+}
+
+/// Simulates the boat drifting without propulsion, e.g. a drifting buoy, a disabled vessel or lost cargo, moving only with the ocean current and the wind on it, see get_drift_velocity()
+/// The boat starts at the first waypoint of its route plan, the rest of the route plan is not sailed. The wind and ocean current are taken from simulation.weather
+/// The simulation runs until simulation.max_iterations or simulation.max_simulated_duration. If the boat drifts onto land (simulation.land_mask) it stops at its last position at sea,
+/// and if it drifts onto land or into water shallower than its draft (simulation.bathymetry) the simulation stops with SimulationStatus::RanAground
+/// The navigation status is logged as NotUnderCommand
+pub fn sim_drift(boat: &mut Boat, start_time: time::UtcDateTime, simulation: &Simulation) -> Result<(SimulationStatus, usize), MarineSimError> {
+    // Verify that the simulation has weather to drift with
+    let weather = match &simulation.weather {
+        Some(weather) => weather.clone(),
+        None => return Err(MarineSimError::InvalidInput("Drift needs the wind and ocean current from simulation.weather".to_string())),
+    };
+    return sim_drift_with_weather(boat, start_time, simulation, weather.as_ref());
+}
+
+/// Simulates the boat drifting as in sim_drift(), with the wind and ocean current taken from weather instead of simulation.weather, e.g. perturbed weather for a drift ensemble, see run_drift_ensemble()
+pub(crate) fn sim_drift_with_weather(boat: &mut Boat, start_time: time::UtcDateTime, simulation: &Simulation, weather: &(dyn WeatherProvider + Send + Sync)) -> Result<(SimulationStatus, usize), MarineSimError> {
+    // Set boats current location to the first waypoint, the boat does not steer
+    boat.location = Some(get_boat_route_plan(boat)?[0].p1);
+    boat.current_leg = Some(1);
+    boat.heading = None;
+    boat.navigation_status = Some(NavigationStatus::NotUnderCommand);
+    let coordinates_initial = boat.location.unwrap();
+    let coordinates_final = get_boat_route_plan(boat)?.last().unwrap().p2;
+
+    // Init ship_log_entry
+    let new_log_entry: ShipLogEntry = ShipLogEntry {
+        timestamp: start_time,
+        coordinates_initial: coordinates_initial,
+        coordinates_current: coordinates_initial,
+        coordinates_final: coordinates_final,
+        cargo_on_board: Some(boat.cargo_current),
+        velocity: None,
+        course: None,
+        heading: None,
+        track_angle: None,
+        true_bearing: None,
+        draft: None,
+        navigation_status: Some(NavigationStatus::NotUnderCommand),
+        depth: None,
+        wind: None,
+        wave_height: None,
+        cross_track_error: None,
+        foiling: None,
+    };
+    // Push first ship log entry
+    boat.push_ship_log_entry(new_log_entry);
+
+    // Loop through each time step
+    for i in 0..simulation.max_iterations {
+        // Stop if the voyage has taken longer than the maximum simulated duration
+        if max_simulated_duration_reached(boat, simulation, start_time) {
+            return Ok((SimulationStatus::MaxSimulatedDurationReached, i));
+        }
+        // Stop if a tank ran empty, a drifting boat can not detour to bunker
+        if let Some(status) = check_tanks(boat, None) {
+            return Ok((status, i));
+        }
+
+        // Drift with the weather at the start of the time step
+        let location: geo::Point = boat.location.unwrap();
+        let time_now: UtcDateTime = boat.ship_log.last().unwrap().timestamp;
+        let wind: PhysVec = weather.wind(time_now, location)?;
+        let drift_velocity: PhysVec = get_drift_velocity(boat, wind, weather.ocean_current(time_now, location)?);
+        let mut new_location: geo::Point = Earth.destination(location, drift_velocity.angle, drift_velocity.magnitude*simulation.time_step.as_seconds_f64());
+
+        // If the boat drifts onto land it stays at its last position at sea
+        let stranded: bool = simulation.land_mask.as_ref().is_some_and(|land_mask| land_mask.crosses_land(location, new_location));
+        if stranded {
+            new_location = location;
+            boat.navigation_status = Some(NavigationStatus::Aground);
+        }
+        boat.location = Some(new_location);
+
+        // Log the new location to the ship log
+        let mut new_log_entry: ShipLogEntry = ShipLogEntry {
+            timestamp: add_duration(time_now, simulation.time_step)?,
+            coordinates_initial: coordinates_initial,
+            coordinates_current: new_location,
+            coordinates_final: coordinates_final,
+            cargo_on_board: Some(boat.cargo_current),
+            velocity: Some(drift_velocity),
+            course: None,
+            heading: None,
+            track_angle: Some(Rhumb.bearing(location, new_location)),
+            true_bearing: None,
+            draft: None,
+            navigation_status: boat.navigation_status,
+            depth: None,
+            wind: Some(wind),
+            wave_height: None,
+            cross_track_error: None,
+            foiling: None,
+        };
 
-                // Push the new log entry to the ship log
-                boat.ship_log.push(new_log_entry);
+        // Check the water depth, stop if the boat ran aground
+        let grounded = check_depth(boat, simulation, &mut new_log_entry);
 
-                // Set travel distance to zero for next loop
-                travel_dist = travel_dist - travel_dist;
-            }
-        } // End while loop
-    } // End for loop
+        // Push the new log entry to the ship log
+        boat.push_ship_log_entry(new_log_entry);
+        if grounded || stranded {
+            return Ok((SimulationStatus::RanAground, i + 1));
+        }
+    }
 
-    // Simulation ran through all the iterations, return ship log and error that the simulation did not finish
-    // Return the ship log TODO: Move inside for loop
-    return Ok("Maximized number of iterations. Stopping simulation".to_string());
+    // Simulation ran through all the iterations
+    return Ok((SimulationStatus::MaxIterationsReached, simulation.max_iterations));
 }
 
 /// Simulates the boat using weather data from file
 /// NOTE: Currently uses 5 m/s blowing in from the north as a placeholder for the weather data
 /// Note: Tacking width is the total width around the center of leg line for each leg.
-pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_time: time::UtcDateTime, simulation: &Simulation) -> Result<String, io::Error> {
+/// Note: If the weather exceeds the operational limits of the boat (max_wind_speed, max_wave_height) the boat uses its storm tactic until the weather drops, see Boat.update_storm_bound().
+/// Note: If simulation.wind_against_current is set, wind against a strong ocean current amplifies the wave height and slows the boat down, see WindAgainstCurrent.
+/// While hove-to the navigation status is logged as NotUnderCommand and while running downwind it is logged as RestrictedManeuverability.
+/// The time spent hove-to during the simulation is stored in boat.time_hove_to
+/// Note: If simulation.storm_avoidance_distance is set, the boat detours around bad weather ahead, the detour waypoints are stored in boat.detour_waypoints
+/// Note: If boat.max_ice_concentration is set, the boat does not sail into sea ice above it, see simulation.ice_avoidance
+#[cfg(feature = "copernicus")]
+pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_time: time::UtcDateTime, simulation: &Simulation) -> Result<(SimulationStatus, usize), MarineSimError> {
     // Verify that necessary fields are set
     if simulation.weather_data_file.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing weather data file name from simulation"));
+        return Err(MarineSimError::InvalidInput("Missing weather data file name from simulation".to_string()));
     }
     if simulation.copernicus.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing copernicus info from simulation"))
+        return Err(MarineSimError::InvalidInput("Missing copernicus info from simulation".to_string()))
     }
     if boat.mass.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing mass from boat"));
+        return Err(MarineSimError::MissingBoatField("mass".to_string()));
     }
     if boat.sail.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing sail from boat"));
+        return Err(MarineSimError::MissingBoatField("sail".to_string()));
     }
     if boat.min_angle_of_attack.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing minimum angle of attack from boat"));
+        return Err(MarineSimError::MissingBoatField("minimum angle of attack".to_string()));
     }
     if boat.route_plan.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing route plan from boat"));
+        return Err(MarineSimError::MissingBoatField("route plan".to_string()));
     }
     if boat.wind_velocity_multiplier.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing wind_velocity_multiplier from vessel"));
+        return Err(MarineSimError::MissingBoatField("wind_velocity_multiplier".to_string()));
     }
     // TODO: Add drag
     // if boat.hull_drag_coefficient.is_none() {
@@ -489,11 +2464,11 @@ pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_
     let is_interactive_terminal = atty::is(atty::Stream::Stdout);
 
     // Set boats current location to the first waypoint
-    boat.location = Some(boat.route_plan.as_ref().expect("Route plan missing?")[0].p1);
+    boat.location = Some(get_boat_route_plan(boat)?[0].p1);
     // Set current leg to 1
     boat.current_leg = Some(1);
     // Get total number of legs
-    let total_legs: usize = boat.route_plan.as_ref().expect("Route plan missing?").len();
+    let total_legs: usize = get_boat_route_plan(boat)?.len();
 
     // Init travel_dist, unit [m]
     let mut travel_dist: f64;
@@ -506,7 +2481,7 @@ pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_
     let coordinates_final = boat.route_plan.as_ref().unwrap()[total_legs - 1].p2;
     // Init ship_log_entry
     let new_log_entry: ShipLogEntry = ShipLogEntry {
-        timestamp: time::UtcDateTime::new(time::Date::from_calendar_date(start_time.year(), start_time.month(), start_time.day()).expect("Could not make time::Date from values"), time::Time::from_hms(start_time.hour(), start_time.minute(), start_time.second()).expect("Could not make time::Time from values")),
+        timestamp: start_time - time::Duration::nanoseconds(start_time.nanosecond() as i64),
         coordinates_initial: coordinates_initial,
         coordinates_current: coordinates_initial,
         coordinates_final: coordinates_final,
@@ -518,9 +2493,23 @@ pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_
         true_bearing: None,
         draft: None,
         navigation_status: Some(NavigationStatus::UnderwaySailing),
+        depth: None,
+        wind: None,
+        wave_height: None,
+        cross_track_error: None,
+        foiling: None,
     };
     // Push first ship log entry
-    boat.ship_log.push(new_log_entry);
+    boat.push_ship_log_entry(new_log_entry);
+
+    // Reset storm state, time hove-to and detours for this voyage
+    boat.storm_bound = false;
+    boat.time_hove_to = time::Duration::ZERO;
+    boat.detour_waypoints = Vec::new();
+    // Storm detour, (start, end) of the line the boat is currently following instead of the leg line. None when not on a detour
+    let mut detour: Option<(geo::Point, geo::Point)> = None;
+    // True while the wind is blowing against a strong ocean current, so the event is only added when it starts
+    let mut in_wind_against_current: bool = false;
 
     // Init wind vector, unit [m/s]
     let mut wind: PhysVec;
@@ -545,6 +2534,14 @@ pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_
     // Loop through each time step
     let mut iteration: usize = 0;
     while iteration <= simulation.max_iterations {
+        // Stop if the voyage has taken longer than the maximum simulated duration
+        if max_simulated_duration_reached(boat, simulation, start_time) {
+            return Ok((SimulationStatus::MaxSimulatedDurationReached, iteration));
+        }
+        // Stop if a tank ran empty, detour to a bunkering port if one runs low
+        if let Some(status) = check_tanks(boat, simulation.bunkering.as_ref()) {
+            return Ok((status, iteration));
+        }
         // Increment number of iterations
         iteration += 1;
         // Simulate the boat moving towards the next waypoint
@@ -564,24 +2561,31 @@ pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_
         min_proximity = boat.route_plan.as_ref().unwrap()[(boat.current_leg.unwrap()-1) as usize].min_proximity;
 
         // Get boat current time and location
-        let boat_time_now: UtcDateTime = boat.ship_log.last().unwrap().timestamp;
-        let longitude: f64 = boat.location.expect("Boat has no location").x();
-        let latitude: f64 = boat.location.expect("Boat has no location").y();
+        let mut boat_time_now: UtcDateTime = boat.ship_log.last().unwrap().timestamp;
+        let longitude: f64 = get_boat_location(boat)?.x();
+        let latitude: f64 = get_boat_location(boat)?.y();
 
         // Get distance to next waypoint from current location
-        dist_to_next_waypoint = Haversine.distance(boat.location.unwrap(), next_waypoint);
+        dist_to_next_waypoint = Earth.distance(boat.location.unwrap(), next_waypoint);
 
         // if distance to the next waypoint is shorter than the simulation minimum proximity (or we are at the next waypoint)
         // Then we are at the next waypoint. Check if this is the final waypoint (if so, finish simulation) or go to next leg and continue simulation
         if (dist_to_next_waypoint <= min_proximity) || (boat.location.unwrap() == next_waypoint) {
+            // Log the arrival and make the port call at the waypoint, if any, and continue from the departure time
+            log_waypoint_arrival(boat, boat_time_now)?;
+            do_waypoint_stops(boat, simulation, boat_time_now, coordinates_initial, coordinates_final)?;
+            boat_time_now = boat.ship_log.last().unwrap().timestamp;
+
             // If the boat has reached the last waypoint, stop the simulation
             if next_waypoint == coordinates_final {
                 // Stop the simulation
-                return Ok("Simulation completed".to_string());
+                return Ok((SimulationStatus::Completed, iteration));
             }
 
             // Update current leg number
             boat.current_leg = Some(boat.current_leg.unwrap() + 1);
+            // New leg, any detour is over
+            detour = None;
         
             // Since leg number increased, update progress bar if a progress bar is in use
             if !(simulation.progress_bar.is_none()) {
@@ -590,7 +2594,7 @@ pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_
                 // If not interactive terminal, print progressbar manually
                 if is_interactive_terminal == false {
                     let eta = time::UtcDateTime::now().saturating_add(time::Duration::new(simulation.progress_bar.as_ref().unwrap().eta().as_secs() as i64, 0)); // What time the simulations will end
-                println!("Elapsed: {} secs, Steps {}/{}, ETA: {}-{}-{} {}:{}:{}", simulation.progress_bar.as_ref().unwrap().elapsed().as_secs(), simulation.progress_bar.as_ref().unwrap().position(), simulation.progress_bar.as_ref().unwrap().length().unwrap(), eta.year(), eta.month() as u8, eta.day(), eta.hour(), eta.minute(), eta.second());
+                tracing::info!(elapsed_secs = simulation.progress_bar.as_ref().unwrap().elapsed().as_secs(), steps = simulation.progress_bar.as_ref().unwrap().position(), total_steps = simulation.progress_bar.as_ref().unwrap().length().unwrap(), eta = %format_timestamp(eta), "Simulation progress");
                 }
             }   // End if
         }   // End if
@@ -598,59 +2602,126 @@ pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_
         // Get last and next waypoint from routeplan
         last_waypoint = boat.route_plan.as_ref().unwrap()[(boat.current_leg.unwrap()-1) as usize].p1;
         next_waypoint = boat.route_plan.as_ref().unwrap()[(boat.current_leg.unwrap()-1) as usize].p2;
+
+        // If on a storm detour, follow the detour line instead of the leg line
+        if let Some((_, detour_end)) = detour {
+            // If the detour waypoint has been reached, head from it back to the end of the leg
+            if (detour_end != next_waypoint) && (Earth.distance(boat.location.unwrap(), detour_end) <= min_proximity) {
+                detour = Some((detour_end, next_waypoint));
+            }
+        }
+        if let Some((detour_start, detour_end)) = detour {
+            last_waypoint = detour_start;
+            next_waypoint = detour_end;
+        }
         course = Rhumb.bearing(last_waypoint, next_waypoint);
         // Recalculate distance to next waypoint from current location in case we just reached a waypoint and are going to the next one
-        dist_to_next_waypoint = Haversine.distance(boat.location.unwrap(), next_waypoint);
+        dist_to_next_waypoint = Earth.distance(boat.location.unwrap(), next_waypoint);
 
-        // Get tacking width from route plan
+        // Get tacking width and traffic lane from route plan
         let tacking_width: f64 = boat.route_plan.as_ref().unwrap()[(boat.current_leg.unwrap()-1) as usize].tacking_width;
+        let traffic_lane: Option<TrafficLane> = boat.route_plan.as_ref().unwrap()[(boat.current_leg.unwrap()-1) as usize].traffic_lane;
 
-        // Get wind data from Copernicus
-        let dataset_id: String = match copernicusmarine_rs::get_dataset_id(copernicusmarine_rs::CopernicusVariable::EastwardWind, boat_time_now, boat_time_now) {
-            Ok(id) => id,
-            Err(e) => panic!("Error getting dataset id from copernicusmarine: {}", e),
-        };
-        // let wind_data = match simulation.copernicus.as_ref().unwrap().get_f64_values("cmems_obs-wind_glo_phy_nrt_l4_0.125deg_PT1H".to_string(), vec!["eastward_wind".to_string(), "northward_wind".to_string()], boat_time_now, boat_time_now, longitude, longitude, latitude, latitude, None, None) {
-        let wind_data = match simulation.copernicus.as_ref().unwrap().get_f64_values(dataset_id, vec!["eastward_wind".to_string(), "northward_wind".to_string()], boat_time_now, boat_time_now, longitude, longitude, latitude, latitude, None, None) {
-            Ok(w) => w,
-            Err(e) => return Err(io::Error::new(io::ErrorKind::Other, format!("Error getting wind data from copernicusmarine: {}", e))),
-        };
-        let wind_east_data = &wind_data[0];
-        let wind_north_data = &wind_data[1];
-
-        // Wind speed and direction
-        let wind_east: f64 = wind_east_data[0].unwrap();
-        let wind_north: f64 = wind_north_data[0].unwrap();
-        let wind_angle: f64 = get_north_angle_from_northward_and_eastward_property(wind_east, wind_north);   // Angle in degrees
-        let wind_speed = uom::si::f64::Velocity::new::<uom::si::velocity::meter_per_second>((wind_east*wind_east + wind_north*wind_north).sqrt().into());
-        wind = PhysVec::new(wind_speed.get::<uom::si::velocity::meter_per_second>(), wind_angle);    // unit [m/s]
-
-        // Get ocean current data from Copernicus
-        // "uo" is the eastward sea water velocity and "vo" is the northward sea water velocity
-        let dataset_id: String = match copernicusmarine_rs::get_dataset_id(copernicusmarine_rs::CopernicusVariable::EastwardSeaWaterVelocity, boat_time_now, boat_time_now) {
-            Ok(id) => id,
-            Err(e) => panic!("Error getting dataset id from copernicusmarine: {}", e),
-        };
-        // let ocean_current_data = match simulation.copernicus.as_ref().unwrap().get_f64_values("cmems_mod_glo_phy-cur_anfc_0.083deg_PT6H-i".to_string(), vec!["uo".to_string(), "vo".to_string()], boat_time_now, boat_time_now, longitude, longitude, latitude, latitude, Some(1.0), Some(1.0)){
-        // let ocean_current_data = match simulation.copernicus.as_ref().unwrap().get_f64_values(dataset_id, vec!["uo".to_string(), "vo".to_string()], boat_time_now, boat_time_now, longitude, longitude, latitude, latitude, Some(1.0), Some(1.0)){
-        // let ocean_current_data = match simulation.copernicus.as_ref().unwrap().get_f64_values(dataset_id, vec!["uo".to_string(), "vo".to_string()], boat_time_now, boat_time_now, longitude, longitude, latitude, latitude, Some(0.49402499198913574), Some(0.49402499198913574)){
-        let ocean_current_data = match simulation.copernicus.as_ref().unwrap().get_f64_values(dataset_id, vec!["uo".to_string(), "vo".to_string()], boat_time_now, boat_time_now, longitude, longitude, latitude, latitude, Some(0.0), Some(1.0)){
-            Ok(o) => o,
-            Err(e) => panic!("Error getting ocean current data from copernicusmarine: {}", e),
+        // Get wind and ocean current from simulation.weather if set, e.g. nested regional weather models, otherwise from Copernicus
+        if let Some(weather) = &simulation.weather {
+            wind = weather.wind(boat_time_now, boat.location.unwrap())?;
+            ocean_current = weather.ocean_current(boat_time_now, boat.location.unwrap())?.unwrap_or(PhysVec::new(0.0, 0.0));
+        }
+        else {
+            // Get wind data from Copernicus
+            let dataset_id: String = match copernicusmarine_rs::get_dataset_id(copernicusmarine_rs::CopernicusVariable::EastwardWind, boat_time_now, boat_time_now) {
+                Ok(id) => id,
+                Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting dataset id from copernicusmarine: {}", e))),
+            };
+            // let wind_data = match simulation.copernicus.as_ref().unwrap().get_f64_values("cmems_obs-wind_glo_phy_nrt_l4_0.125deg_PT1H".to_string(), vec!["eastward_wind".to_string(), "northward_wind".to_string()], boat_time_now, boat_time_now, longitude, longitude, latitude, latitude, None, None) {
+            let wind_data = match simulation.copernicus.as_ref().unwrap().get_f64_values(dataset_id, vec!["eastward_wind".to_string(), "northward_wind".to_string()], boat_time_now, boat_time_now, longitude, longitude, latitude, latitude, None, None) {
+                Ok(w) => w,
+                Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting wind data from copernicusmarine: {}", e))),
+            };
+            // Wind speed and direction
+            let (wind_east, wind_north): (f64, f64) = match get_copernicus_east_north(&wind_data, "wind")? {
+                (Some(east), Some(north)) => (east, north),
+                _ => return Err(MarineSimError::WeatherFetch("No wind data from copernicusmarine".to_string())),
+            };
+            let wind_angle: f64 = get_north_angle_from_northward_and_eastward_property(wind_east, wind_north);   // Angle in degrees
+            let wind_speed = uom::si::f64::Velocity::new::<uom::si::velocity::meter_per_second>((wind_east*wind_east + wind_north*wind_north).sqrt().into());
+            wind = PhysVec::new(wind_speed.get::<uom::si::velocity::meter_per_second>(), wind_angle);    // unit [m/s]
+
+            // Get ocean current data from Copernicus
+            // "uo" is the eastward sea water velocity and "vo" is the northward sea water velocity
+            let dataset_id: String = match copernicusmarine_rs::get_dataset_id(copernicusmarine_rs::CopernicusVariable::EastwardSeaWaterVelocity, boat_time_now, boat_time_now) {
+                Ok(id) => id,
+                Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting dataset id from copernicusmarine: {}", e))),
+            };
+            // let ocean_current_data = match simulation.copernicus.as_ref().unwrap().get_f64_values("cmems_mod_glo_phy-cur_anfc_0.083deg_PT6H-i".to_string(), vec!["uo".to_string(), "vo".to_string()], boat_time_now, boat_time_now, longitude, longitude, latitude, latitude, Some(1.0), Some(1.0)){
+            // let ocean_current_data = match simulation.copernicus.as_ref().unwrap().get_f64_values(dataset_id, vec!["uo".to_string(), "vo".to_string()], boat_time_now, boat_time_now, longitude, longitude, latitude, latitude, Some(1.0), Some(1.0)){
+            // let ocean_current_data = match simulation.copernicus.as_ref().unwrap().get_f64_values(dataset_id, vec!["uo".to_string(), "vo".to_string()], boat_time_now, boat_time_now, longitude, longitude, latitude, latitude, Some(0.49402499198913574), Some(0.49402499198913574)){
+            let ocean_current_data = match simulation.copernicus.as_ref().unwrap().get_f64_values(dataset_id, vec!["uo".to_string(), "vo".to_string()], boat_time_now, boat_time_now, longitude, longitude, latitude, latitude, Some(0.0), Some(1.0)){
+                Ok(o) => o,
+                Err(e) => return Err(MarineSimError::WeatherFetch(format!("Error getting ocean current data from copernicusmarine: {}", e))),
+            };
+            // Ocean current speed and direction
+            let (ocean_current_east, ocean_current_north) = get_copernicus_east_north(&ocean_current_data, "ocean current")?;
+            let ocean_current_east: f64 = ocean_current_east.unwrap_or(0.0);
+            let ocean_current_north: f64 = ocean_current_north.unwrap_or(0.0);
+            let ocean_current_angle: f64 = get_north_angle_from_northward_and_eastward_property(ocean_current_east, ocean_current_north);   // Angle in degrees
+            let ocean_current_speed = uom::si::f64::Velocity::new::<uom::si::velocity::meter_per_second>((ocean_current_east*ocean_current_east + ocean_current_north*ocean_current_north).sqrt().into());
+            ocean_current = PhysVec::new(ocean_current_speed.get::<uom::si::velocity::meter_per_second>(), ocean_current_angle);    // unit [m/s]
+        }
+
+        // Wind against current amplifies the waves, 1.0 means no amplification
+        let wind_against_current_amplification: f64 = update_wind_against_current(boat, simulation, wind, ocean_current, boat_time_now, &mut in_wind_against_current);
+
+        // Get significant wave height from Copernicus if the boat has a wave height limit, unit [m]
+        // Amplified if the wind is against the current
+        let wave_height: Option<f64> = match boat.max_wave_height {
+            Some(_) => get_wave_height_from_copernicus(simulation.copernicus.as_ref().unwrap(), boat_time_now, boat.location.unwrap())?.map(|h| h*wind_against_current_amplification),
+            None => None,
         };
-        let ocean_current_east_data = &ocean_current_data[0];
-        let ocean_current_north_data = &ocean_current_data[1];
 
-        // Ocean current speed and direction
-        let ocean_current_east: f64 = ocean_current_east_data[0].expect("ocean current fill value?");
-        let ocean_current_north: f64 = ocean_current_north_data[0].expect("ocean current fill value?");
-        let ocean_current_angle: f64 = get_north_angle_from_northward_and_eastward_property(ocean_current_east, ocean_current_north);   // Angle in degrees
-        let ocean_current_speed = uom::si::f64::Velocity::new::<uom::si::velocity::meter_per_second>((ocean_current_east*ocean_current_east + ocean_current_north*ocean_current_north).sqrt().into());
-        ocean_current = PhysVec::new(ocean_current_speed.get::<uom::si::velocity::meter_per_second>(), ocean_current_angle);    // unit [m/s]
+        // Check if the weather exceeds the operational limits of the boat, or if the boat is still waiting for the weather to drop
+        let was_storm_bound: bool = boat.storm_bound;
+        let storm_bound: bool = boat.update_storm_bound(wind.magnitude, wave_height);
+        if storm_bound && !was_storm_bound {
+            boat.events.push(SimulationEvent {
+                timestamp: boat_time_now,
+                location: boat.location.unwrap(),
+                leg: boat.current_leg,
+                kind: SimulationEventKind::StormLimitExceeded { wind_speed: wind.magnitude, wave_height },
+            });
+        }
+
+        // Storm avoidance, if the weather ahead exceeds the operational limits of the boat, detour downwind of it
+        // Only one detour at a time and not while storm bound, then the storm tactic is used instead
+        if let (Some(avoidance_dist), None, false) = (simulation.storm_avoidance_distance, detour, storm_bound) {
+            // Look ahead along the line to the next waypoint, but not past it
+            let look_ahead_point = Earth.destination(boat.location.unwrap(), Earth.bearing(boat.location.unwrap(), next_waypoint), avoidance_dist.min(dist_to_next_waypoint));
+            let wind_ahead = match &simulation.weather {
+                Some(weather) => weather.wind(boat_time_now, look_ahead_point)?,
+                None => get_wind_from_copernicus(simulation.copernicus.as_ref().unwrap(), boat_time_now, look_ahead_point)?,
+            };
+            let wave_height_ahead: Option<f64> = match boat.max_wave_height {
+                Some(_) => get_wave_height_from_copernicus(simulation.copernicus.as_ref().unwrap(), boat_time_now, look_ahead_point)?,
+                None => None,
+            };
+
+            // If the weather ahead is too much, insert a detour waypoint downwind of it
+            if boat.exceeds_operational_limits(wind_ahead.magnitude, wave_height_ahead) {
+                let detour_waypoint = Earth.destination(look_ahead_point, (wind_ahead.angle + 180.0) % 360.0, avoidance_dist);
+                boat.detour_waypoints.push(detour_waypoint);
+                detour = Some((boat.location.unwrap(), detour_waypoint));
+
+                // Follow the detour line from now on
+                last_waypoint = boat.location.unwrap();
+                next_waypoint = detour_waypoint;
+                course = Rhumb.bearing(last_waypoint, next_waypoint);
+                dist_to_next_waypoint = Earth.distance(boat.location.unwrap(), next_waypoint);
+            }
+        }
 
         // Compute heading
         // Compute angle of wind relative to line between current location and next waypoint. North: 0°, East: 90°, South: 180°, West: 270°
-        bearing_to_next_waypoint = Haversine.bearing(boat.location.unwrap(), next_waypoint);
+        bearing_to_next_waypoint = Earth.bearing(boat.location.unwrap(), next_waypoint);
         // Compute angle of wind relative to boat heading
         let relative_wind_angle = wind.angle - bearing_to_next_waypoint;
         // Relative wind angle must be in the range of -180° to 180°
@@ -674,14 +2745,31 @@ pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_
 
         // println!("Heading adjustment: {:.4}", heading_adjustment);
 
+        // If the weather exceeds the operational limits of the boat, use the storm tactic
+        if storm_bound {
+            match boat.storm_tactic {
+                StormTactic::HeaveTo => {
+                    // Lie close-hauled on the current tack making very little way forward
+                    boat.hold_tack(wind.angle);
+                    boat.navigation_status = Some(NavigationStatus::NotUnderCommand);
+                }
+                StormTactic::RunDownwind => {
+                    // Point the stern to the wind
+                    boat.heading = Some((wind.angle + 180.0) % 360.0);
+                    boat.navigation_status = Some(NavigationStatus::RestrictedManeuverability);
+                }
+            }
+        }
         // If absolute relative wind angle is smaller than minimum angle of attack, then use tacking method
-        if relative_wind_angle.abs() < boat.min_angle_of_attack.unwrap() {
+        else if relative_wind_angle.abs() < boat.min_angle_of_attack.unwrap() {
             boat.hold_tack(wind.angle);
+            boat.navigation_status = Some(NavigationStatus::UnderwaySailing);
         } // Otherwise relative wind angle is bigger than minimum angle of attack, then go straight towards next waypoint
         else {
-            // Set heading to the bearing to next waypoint
-            boat.heading = Some(bearing_to_next_waypoint);
+            // Set heading to the bearing to next waypoint, or the heading the traffic lane mandates
+            boat.heading = Some(traffic_lane.map_or(bearing_to_next_waypoint, |lane| lane.heading(bearing_to_next_waypoint)));
             // boat.heading = Some(bearing_to_next_waypoint + heading_adjustment);
+            boat.navigation_status = Some(NavigationStatus::UnderwaySailing);
         }
         // adjust heading
         boat.heading = Some(boat.heading.unwrap() + heading_adjustment);
@@ -707,16 +2795,17 @@ pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_
 
         // Working velocity is initial velocity plus final velocity divided by 2
         // TODO: implement properly
-        working_velocity = PhysVec::new(wind.magnitude*boat.wind_velocity_multiplier.unwrap(), boat.heading.unwrap()) + ocean_current;
+        // Speed through water is divided by the wind against current amplification to account for the higher wave resistance
+        working_velocity = get_sailing_velocity(boat, wind, ocean_current, storm_bound, wind_against_current_amplification);
         // working_velocity = PhysVec::new(wind.magnitude*1.5, boat.heading.unwrap());
         // working_velocity = boat.velocity_mean.unwrap(); // (boat.velocity_current.unwrap() + final_velocity) / 2.0; // working_velocity in meters per second
 
         // Update the current velocity of the boat
-        let test_velocity = match get_vessel_velocity(boat, wind, Some(ocean_current)){
+        let (test_velocity, foiling) = match get_vessel_velocity_and_foiling(boat, wind, Some(ocean_current)){
             Ok(v) => v,
-            Err(e) => panic!("Error calculating vessel velocity: {}", e),
+            Err(e) => return Err(MarineSimError::simulation("Error calculating vessel velocity".to_string(), e)),
         };
-        println!("Test velocity: {:?}", test_velocity);
+        tracing::trace!("Test velocity: {:?}", test_velocity);
         // boat.velocity_current = Some(working_velocity);
         boat.velocity_current = Some(test_velocity);
 
@@ -730,7 +2819,8 @@ pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_
 
         // Move boat forwards along actual direction and log to ship_log
         // If distance traveled is greater than the distance to the next waypoint, set travel_dist to dist_to_next_waypoint and change temp_time_step
-        if travel_dist > dist_to_next_waypoint {
+        // Not done when storm bound since then the boat is not heading towards the next waypoint
+        if !storm_bound && travel_dist > dist_to_next_waypoint {
             // Set travel distance [m] as distance to next waypoint
             travel_dist = dist_to_next_waypoint;
 
@@ -740,13 +2830,15 @@ pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_
         }
 
         // Get the new location of the boat with distance left to travel during timestep and bearing to next waypoint, important to use unit [meter] for travel_dist
-        new_location = Haversine.destination(boat.location.unwrap(), working_velocity.angle, travel_dist);
+        new_location = Earth.destination(boat.location.unwrap(), working_velocity.angle, travel_dist);
         // If new location is further away from leg line than half of tacking width, tack before moving
         let current_loc_min_dist_to_leg_line = get_min_point_to_great_circle_dist(last_waypoint, next_waypoint, boat.location.unwrap());
         let new_loc_min_dist_to_leg_line = get_min_point_to_great_circle_dist(last_waypoint, next_waypoint, new_location);
 
         // If currently inside or on boundary but heading out of boundary, tack
-        if ((tacking_width/2.0) <  new_loc_min_dist_to_leg_line) && (current_loc_min_dist_to_leg_line <= tacking_width/2.0) {
+        // Storm bound boats do not tack and neither do crews that can not maneuver now (see Crew) or boats in a traffic lane, they may leave the tacking boundary
+        let can_maneuver: bool = !storm_bound && boat.can_maneuver(boat_time_now) && traffic_lane.is_none();
+        if can_maneuver && ((tacking_width/2.0) <  new_loc_min_dist_to_leg_line) && (current_loc_min_dist_to_leg_line <= tacking_width/2.0) {
             // Move to edge of tacking width, tack and go to next iteration of while loop
             // Minimum distance to tacking edge from current location
             let dist_to_tacking_edge = (tacking_width/2.0) - current_loc_min_dist_to_leg_line;
@@ -758,7 +2850,7 @@ pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_
             travel_dist = travel_dist * (dist_to_tacking_edge / dist_to_new_location)*0.9;
 
             // Update location
-            new_location = Haversine.destination(boat.location.unwrap(), boat.heading.unwrap(), travel_dist);
+            new_location = Earth.destination(boat.location.unwrap(), boat.heading.unwrap(), travel_dist);
             let new_loc_min_dist_to_leg_line = get_min_point_to_great_circle_dist(last_waypoint, next_waypoint, new_location);
 
             // Double check that new location is inside/on tacking edge.
@@ -766,7 +2858,7 @@ pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_
             // travel_dist = travel_dist - 2.0*(tacking_width/2.0 - new_loc_min_dist_to_leg_line).abs();
 
             // Update location
-            // new_location = Haversine.destination(boat.location.unwrap(), boat.heading.unwrap(), travel_dist);
+            // new_location = Earth.destination(boat.location.unwrap(), boat.heading.unwrap(), travel_dist);
             // let new_loc_min_dist_to_leg_line = get_min_point_to_great_circle_dist(last_waypoint, next_waypoint, new_location);
 
             // If distance to tacking edge is less than 10% of tacking width/2 then tack, otherwise keep going
@@ -774,19 +2866,83 @@ pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_
             if dist_to_tacking_edge <= 0.1*tacking_width/2.0 {
                 // Tack
                 boat.tack(wind.angle);
+                boat.record_maneuver(boat_time_now);
+                boat.events.push(SimulationEvent {
+                    timestamp: boat_time_now,
+                    location: boat.location.unwrap(),
+                    leg: boat.current_leg,
+                    kind: SimulationEventKind::Tacked { wind_side: boat.wind_preferred_side },
+                });
             }
 
             // Set temp_time_step [s] to time left in simulation time_step after moving to tacking edge
             let time_passed = travel_dist / working_velocity.magnitude;
             temp_time_step = Some(working_time_step - time_passed);
         }
+        // Outside the boundary and heading further out after a rest of the crew, tack as soon as the crew can
+        // Only for boats with a crew (see Crew), boats without one sail out of the boundary as before, e.g. after a storm
+        else if boat.crew.is_some() && can_maneuver && (current_loc_min_dist_to_leg_line > tacking_width/2.0) && (new_loc_min_dist_to_leg_line > current_loc_min_dist_to_leg_line) {
+            boat.tack(wind.angle);
+            boat.record_maneuver(boat_time_now);
+            boat.events.push(SimulationEvent {
+                timestamp: boat_time_now,
+                location: boat.location.unwrap(),
+                leg: boat.current_leg,
+                kind: SimulationEventKind::Tacked { wind_side: boat.wind_preferred_side },
+            });
+            // Go to the next iteration without moving, with the new heading
+            temp_time_step = Some(working_time_step);
+            continue;
+        }
+
+        // Check that the boat does not run aground
+        if let Some(land_mask) = &simulation.land_mask {
+            land_mask.check_step(boat.location.unwrap(), new_location, boat.current_leg.unwrap())?;
+        }
+
+        // Check for sea ice at the new location
+        if let Some(max_ice_concentration) = boat.max_ice_concentration {
+            let ice_concentration = get_sea_ice_concentration_from_copernicus(simulation.copernicus.as_ref().unwrap(), boat_time_now, new_location)?.unwrap_or(0.0);
+            if ice_concentration > max_ice_concentration {
+                match simulation.ice_avoidance {
+                    IceAvoidance::Stop => {
+                        return Err(MarineSimError::Geometry(format!("Sea ice concentration {:.2} exceeds the maximum of {:.2} for the boat at latitude {:.4}, longitude {:.4} on leg {} at {}", ice_concentration, max_ice_concentration, new_location.y(), new_location.x(), boat.current_leg.unwrap(), boat_time_now)));
+                    }
+                    IceAvoidance::Reroute => {
+                        // Search for the closest ice free point to either side of the line to the next waypoint, in steps of the tacking width (at least 10 km)
+                        let step_dist = tacking_width.max(10000.0);
+                        let mut ice_free_point: Option<geo::Point> = None;
+                        'search: for step in 1..=10 {
+                            for side in [90.0, -90.0] {
+                                let candidate = Earth.destination(new_location, (course + side + 360.0) % 360.0, step_dist*(step as f64));
+                                let candidate_ice = get_sea_ice_concentration_from_copernicus(simulation.copernicus.as_ref().unwrap(), boat_time_now, candidate)?.unwrap_or(0.0);
+                                if candidate_ice <= max_ice_concentration {
+                                    ice_free_point = Some(candidate);
+                                    break 'search;
+                                }
+                            }
+                        }
+                        match ice_free_point {
+                            Some(p) => {
+                                // Detour to the ice free point and try again from the current location without moving
+                                boat.detour_waypoints.push(p);
+                                detour = Some((boat.location.unwrap(), p));
+                                temp_time_step = None;
+                                continue;
+                            }
+                            None => return Err(MarineSimError::Geometry(format!("Could not find a way around sea ice at latitude {:.4}, longitude {:.4} on leg {} at {}", new_location.y(), new_location.x(), boat.current_leg.unwrap(), boat_time_now))),
+                        }
+                    }
+                }
+            }
+        }
 
         // Update the location of the boat
         boat.location = Some(new_location);
 
         // Log the new location to the ship log
-        let new_log_entry: ShipLogEntry = ShipLogEntry {
-            timestamp: boat.ship_log.last().unwrap().timestamp.checked_add(time::Duration::seconds_f64(working_time_step)).expect("Could not add time::Duration to time::UtcDateTime. Maybe an overflow occurred?"),
+        let mut new_log_entry: ShipLogEntry = ShipLogEntry {
+            timestamp: add_duration(boat.ship_log.last().unwrap().timestamp, time::Duration::seconds_f64(working_time_step))?,
             coordinates_initial: coordinates_initial,
             coordinates_current: boat.location.unwrap(),
             coordinates_final: coordinates_final,
@@ -797,48 +2953,65 @@ pub fn sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_
             heading: boat.heading,
             true_bearing: None,
             draft: None,
-            navigation_status: Some(NavigationStatus::UnderwaySailing),
+            navigation_status: boat.navigation_status,
+            depth: None,
+            wind: Some(wind),
+            wave_height: wave_height,
+            cross_track_error: None,
+            foiling: foiling,
             };
 
+        // Add time step to time hove-to
+        if storm_bound && boat.storm_tactic == StormTactic::HeaveTo {
+            boat.time_hove_to += new_log_entry.timestamp - boat.ship_log.last().unwrap().timestamp;
+        }
+
+        // Check the water depth, stop if the boat ran aground
+        let grounded = check_depth(boat, simulation, &mut new_log_entry);
+
         // Push the new log entry to the ship log
-        boat.ship_log.push(new_log_entry);
+        boat.push_ship_log_entry(new_log_entry);
+        if grounded {
+            return Ok((SimulationStatus::RanAground, iteration));
+        }
     } // End while loop
 
     // Simulation ran through all the iterations, return ship log and error that the simulation did not finish
     // Return the ship log TODO: Move inside for loop
-    return Ok("Maximized number of iterations. Stopping simulation".to_string());
+    return Ok((SimulationStatus::MaxIterationsReached, simulation.max_iterations));
 }
 
 /// Simulates the boat quickly using 1 download of weather data from copernicus marine
 /// Downloads the 
-pub fn fast_sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_time: time::UtcDateTime, simulation: &Simulation) -> Result<String, io::Error> {
+#[cfg(feature = "copernicus")]
+pub fn fast_sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, start_time: time::UtcDateTime, simulation: &Simulation) -> Result<(SimulationStatus, usize), MarineSimError> {
     // Verify that necessary fields are set
     if simulation.weather_data_file.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing weather data file name from simulation"));
+        return Err(MarineSimError::InvalidInput("Missing weather data file name from simulation".to_string()));
     }
     if simulation.copernicus.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing copernicus info from simulation"));
+        return Err(MarineSimError::InvalidInput("Missing copernicus info from simulation".to_string()));
     }
     if simulation.n_segments.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing n_segments from simulation"));
+        return Err(MarineSimError::InvalidInput("Missing n_segments from simulation".to_string()));
     }
     if simulation.weather_data_file.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing weather_data_file from simulation"));
+        return Err(MarineSimError::InvalidInput("Missing weather_data_file from simulation".to_string()));
     }
     if boat.mass.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing mass from boat"));
+        return Err(MarineSimError::MissingBoatField("mass".to_string()));
     }
     if boat.sail.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing sail from boat"));
+        return Err(MarineSimError::MissingBoatField("sail".to_string()));
     }
     if boat.min_angle_of_attack.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing minimum angle of attack from boat"));
+        return Err(MarineSimError::MissingBoatField("minimum angle of attack".to_string()));
     }
     if boat.route_plan.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing route plan from boat"));
+        return Err(MarineSimError::MissingBoatField("route plan".to_string()));
     }
     if boat.wind_velocity_multiplier.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Missing wind_velocity_multiplier from vessel"));
+        return Err(MarineSimError::MissingBoatField("wind_velocity_multiplier".to_string()));
     }
     // TODO: Add drag
     // if boat.hull_drag_coefficient.is_none() {
@@ -846,18 +3019,18 @@ pub fn fast_sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, s
     // }
 
     // Segment route into waypoints
-    let (segment_points, segment_dist) = segment_waypoint_mission(boat.route_plan.clone().unwrap(), simulation.n_segments.unwrap());
+    let (segment_points, segment_dist) = segment_waypoint_mission(boat.route_plan.clone().unwrap().legs, simulation.n_segments.unwrap());
 
     // Get the weather data for all the waypoints from weather file information, load data from file
-    let (_timestamps, weather_points, wind_vec, ocean_current_vec) = get_weather_data_from_csv_file(simulation.weather_data_file.clone().unwrap());
+    let (_timestamps, weather_points, wind_vec, ocean_current_vec) = get_weather_data_from_csv_file(simulation.weather_data_file.clone().unwrap())?;
 
     // Sanity check that the points are the same, if not, return error
     if segment_points.len() != weather_points.len() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, std::format!("Waypoints don't match. From route plan are {} points but from weather data are {} points", segment_points.len(), weather_points.len())));
+        return Err(MarineSimError::Geometry(format!("Waypoints don't match. From route plan are {} points but from weather data are {} points", segment_points.len(), weather_points.len())));
     }
     for i in 0..segment_points.len() {
         if segment_points[i] != weather_points[i] {
-            return Err(io::Error::new(io::ErrorKind::InvalidInput, std::format!("Waypoints don't match. From route plan, point {} is: {:?} but from weather data point {} is {:?}", i, segment_points[i], i, weather_points[i])));
+            return Err(MarineSimError::Geometry(format!("Waypoints don't match. From route plan, point {} is: {:?} but from weather data point {} is {:?}", i, segment_points[i], i, weather_points[i])));
         }
     }
 
@@ -867,15 +3040,23 @@ pub fn fast_sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, s
     boat.navigation_status = Some(NavigationStatus::UnderwaySailing);
     boat.current_leg = Some(1);
     boat.time_now = start_time;
-    boat.log_entry_into_ship_log();
+    boat.log_entry_into_ship_log()?;
 
 
     // Calculate how much time it would take to travel between each point using the weather data, minimum angle of attack etc.
     // Loop through each point
     for i in 1..segment_points.len() {
+        // Stop if the voyage has taken longer than the maximum simulated duration
+        if max_simulated_duration_reached(boat, simulation, start_time) {
+            return Ok((SimulationStatus::MaxSimulatedDurationReached, i - 1));
+        }
+        // Stop if a tank ran empty, the route is fixed so there are no bunkering stops
+        if let Some(status) = check_tanks(boat, None) {
+            return Ok((status, i - 1));
+        }
         // Check the bearing, distance and conditions from current point to next point
-        boat.heading = Some(geo::Haversine.bearing(boat.location.unwrap(), segment_points[i]));
-        boat.true_bearing = Some(geo::Haversine.bearing(boat.location.unwrap(), segment_points[i]));
+        boat.heading = Some(Earth.bearing(boat.location.unwrap(), segment_points[i]));
+        boat.true_bearing = Some(Earth.bearing(boat.location.unwrap(), segment_points[i]));
         
         // Calculate time it would take to sail to next point, add to boats time
         // Get working velocity
@@ -889,7 +3070,7 @@ pub fn fast_sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, s
         // Time to next point, should be in seconds.
         let time_to_next_point = segment_dist / velocity_component_along_bearing;
         // Update boat time
-        boat.time_now = boat.time_now.checked_add(time::Duration::seconds_f64(time_to_next_point)).expect(format!("Could not add {} seconds to boat time: {}", time_to_next_point, boat.time_now).as_str());
+        boat.time_now = add_duration(boat.time_now, time::Duration::seconds_f64(time_to_next_point))?;
 
         // Move boat to point
         boat.location = Some(segment_points[i]);
@@ -897,12 +3078,13 @@ pub fn fast_sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, s
         // Do we pass a waypoint?
         // If closer than minimum proximity to next waypoint, update current leg
         let mut waypoint_passed: Option<usize> = None;
-        if geo::Haversine.distance(boat.location.unwrap(), boat.route_plan.as_ref().unwrap()[(boat.current_leg.unwrap()-1) as usize].p2) <= boat.route_plan.as_ref().unwrap()[(boat.current_leg.unwrap() -1) as usize].min_proximity {
+        if Earth.distance(boat.location.unwrap(), boat.route_plan.as_ref().unwrap()[(boat.current_leg.unwrap()-1) as usize].p2) <= boat.route_plan.as_ref().unwrap()[(boat.current_leg.unwrap() -1) as usize].min_proximity {
             waypoint_passed = Some(boat.current_leg.unwrap() as usize);
         }
 
         // If we pass a waypoint (finish a leg), update leg number and progress bar
         if waypoint_passed.is_some() {
+            log_waypoint_arrival(boat, boat.time_now)?;
             // If it was the last point, break the loop
             if boat.route_plan.as_ref().unwrap()[waypoint_passed.unwrap()].p2 == boat.route_plan.as_ref().unwrap().last().unwrap().p2 {
                 // Route finished so break
@@ -917,31 +3099,433 @@ pub fn fast_sim_waypoint_mission_weather_data_from_copernicus(boat: &mut Boat, s
         }
 
         // Add new ship log entry
-        boat.log_entry_into_ship_log();
+        boat.log_entry_into_ship_log()?;
     }
 
     // Simulation finished
-    return Ok("Simulation completed".to_string());
+    return Ok((SimulationStatus::Completed, segment_points.len()));
+}
+
+// Co-simulation
+//----------------------------------------------------
+/// Command from an external controller, e.g. an autopilot or a reinforcement learning agent, applied at the next step of a CoSimulation
+/// Fields that are None keep the last value
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct ControlCommand {
+    /// Heading to steer in degrees. North: 0°, East: 90°, South: 180°, West: 270°
+    pub heading: Option<f64>,
+    /// How much of the sail is set, in \[0, 1\]. The speed through water is multiplied with it, 1.0 is full sail, reefing lowers it and 0.0 drifts with the ocean current
+    pub sail_fraction: Option<f64>,
+    /// \[m/s\]. Speed through water the engine gives, the boat motors when it is faster than sailing. 0.0 turns the engine off
+    pub engine_speed: Option<f64>,
+}
+
+/// State of the boat after a step of a CoSimulation, what an external controller sees before deciding its next command
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepState {
+    /// Time of the boat after the step
+    pub timestamp: UtcDateTime,
+    pub location: geo::Point,
+    /// Heading in degrees the boat steered during the step
+    pub heading: f64,
+    /// \[m/s\]. Velocity over ground during the step
+    pub velocity: PhysVec,
+    /// \[m/s\]. Wind at the start of the step, the angle is where the wind comes from. Calm if the simulation has no weather
+    pub wind: PhysVec,
+    /// \[m/s\]. Ocean current at the start of the step, None if unknown
+    pub ocean_current: Option<PhysVec>,
+    /// True wind angle in degrees, in \[0, 180\], see get_true_wind_angle()
+    pub true_wind_angle: f64,
+    /// The leg the boat is sailing, starting at 1
+    pub current_leg: u32,
+    /// The end of the current leg
+    pub next_waypoint: geo::Point,
+    /// \[m\]
+    pub distance_to_next_waypoint: f64,
+    /// \[m\]. Signed cross track error from the current leg, positive to starboard, see cross_track_distance()
+    pub cross_track_error: Option<f64>,
+    /// How much of the sail is set, see ControlCommand.sail_fraction
+    pub sail_fraction: f64,
+    /// \[m/s\]. See ControlCommand.engine_speed
+    pub engine_speed: f64,
+    /// Number of steps taken
+    pub iterations: usize,
+    /// How the voyage ended, None while it is still going
+    pub status: Option<SimulationStatus>,
+}
+
+impl StepState {
+    /// Returns true if the voyage has ended, after that CoSimulation.step() returns an error
+    pub fn is_finished(&self) -> bool {
+        return self.status.is_some();
+    }
+}
+
+/// Simulates a voyage one time step at a time with an external controller in the loop instead of the built-in tacking logic
+/// The controller sets the heading, sail and engine with CoSimulation.command() between the steps, the boat holds its heading until told otherwise
+/// The wind and ocean current are taken from simulation.weather, calm if it is not set. The speed through water is from get_vessel_velocity(), i.e. the polar diagram
+/// or velocity_max and speed_grade_coefficient of the boat, or boat.velocity_mean in the heading if the boat has neither
+/// The boat reaches a waypoint when a step passes within SailingLeg.min_proximity of it, so set the minimum proximity to how close the controller has to steer
+/// Uses time_step, max_iterations, max_simulated_duration, land_mask, bathymetry, earth_model and progress_callbacks of the simulation
+/// # Example:
+/// `let mut sim = CoSimulation::new(&mut boat, start_time, &simulation)?;`
+/// `let mut state = sim.state(&boat)?;`
+/// `while !state.is_finished() {`
+/// `    sim.command(&mut boat, ControlCommand { heading: Some(my_autopilot(&state)), ..Default::default() })?;`
+/// `    state = sim.step(&mut boat)?;`
+/// `}`
+/// `let result = sim.result(&boat).unwrap();`
+pub struct CoSimulation<'a> {
+    simulation: &'a Simulation,
+    start_time: UtcDateTime,
+    /// Where the voyage starts in the ship log, waypoint arrivals and events of the boat
+    log_start: usize,
+    arrivals_start: usize,
+    events_start: usize,
+    num_tacks_start: u64,
+    coordinates_initial: geo::Point,
+    coordinates_final: geo::Point,
+    iterations: usize,
+    sail_fraction: f64,
+    engine_speed: f64,
+    wind: PhysVec,
+    ocean_current: Option<PhysVec>,
+    status: Option<SimulationStatus>,
+}
+
+impl<'a> CoSimulation<'a> {
+    /// Starts a voyage of the boat along its route plan at the start time, heading for the first waypoint with full sail and the engine off
+    pub fn new(boat: &mut Boat, start_time: UtcDateTime, simulation: &'a Simulation) -> Result<CoSimulation<'a>, MarineSimError> {
+        let (coordinates_initial, first_waypoint, coordinates_final) = match boat.route_plan.as_ref() {
+            Some(route_plan) if !route_plan.is_empty() => (route_plan[0].p1, route_plan[0].p2, route_plan[route_plan.len() - 1].p2),
+            _ => return Err(MarineSimError::MissingBoatField("route plan".to_string())),
+        };
+        if simulation.max_iterations == 0 {
+            return Err(MarineSimError::InvalidInput("max_iterations must be positive".to_string()));
+        }
+        check_traffic_lanes(boat.route_plan.as_ref().unwrap())?;
+
+        let mut co_simulation = CoSimulation {
+            simulation,
+            start_time,
+            log_start: boat.ship_log.len(),
+            arrivals_start: boat.waypoint_arrivals.len(),
+            events_start: boat.events.len(),
+            num_tacks_start: boat.num_tacks,
+            coordinates_initial,
+            coordinates_final,
+            iterations: 0,
+            sail_fraction: 1.0,
+            engine_speed: 0.0,
+            wind: PhysVec::new(0.0, 0.0),
+            ocean_current: None,
+            status: None,
+        };
+
+        // The boat reports the progress of the voyage to the progress callbacks until it ends
+        boat.voyage_departure = Some(start_time);
+        boat.progress_callbacks = simulation.progress_callbacks.clone();
+
+        // Start at the first waypoint
+        boat.location = Some(coordinates_initial);
+        boat.current_leg = Some(1);
+        boat.time_now = start_time;
+        boat.heading = Some(co_simulation.on_earth_model(|| Earth.bearing(coordinates_initial, first_waypoint)));
+        (co_simulation.wind, co_simulation.ocean_current) = co_simulation.get_weather(start_time, coordinates_initial)?;
+        boat.push_ship_log_entry(ShipLogEntry {
+            timestamp: start_time,
+            coordinates_initial,
+            coordinates_current: coordinates_initial,
+            coordinates_final,
+            cargo_on_board: Some(boat.cargo_current),
+            velocity: None,
+            course: None,
+            heading: boat.heading,
+            track_angle: None,
+            true_bearing: boat.heading,
+            draft: boat.draft,
+            navigation_status: Some(NavigationStatus::UnderwaySailing),
+            depth: None,
+            wind: Some(co_simulation.wind),
+            wave_height: None,
+            cross_track_error: None,
+            foiling: None,
+        });
+        return Ok(co_simulation);
+    }
+
+    /// Sets the heading, sail and engine for the next steps, fields that are None keep the last value
+    /// If the boat has a crew (see Crew) sail changes and tacks are maneuvers, when the crew can not maneuver the sail and heading are kept and the command for them ignored
+    /// Returns an error if the heading is not finite, the sail fraction is not in \[0, 1\] or the engine speed is negative
+    pub fn command(&mut self, boat: &mut Boat, command: ControlCommand) -> Result<(), MarineSimError> {
+        if let Some(heading) = command.heading {
+            if !heading.is_finite() {
+                return Err(MarineSimError::InvalidInput(format!("The heading must be finite, it is {}", heading)));
+            }
+        }
+        if let Some(sail_fraction) = command.sail_fraction {
+            if !(0.0..=1.0).contains(&sail_fraction) {
+                return Err(MarineSimError::InvalidInput(format!("The sail fraction must be in [0, 1], it is {}", sail_fraction)));
+            }
+            if sail_fraction != self.sail_fraction {
+                if boat.can_maneuver(boat.time_now) {
+                    self.sail_fraction = sail_fraction;
+                    boat.record_maneuver(boat.time_now);
+                }
+                else {
+                    tracing::debug!("The crew can not change the sail at {}, keeping the sail fraction at {}", format_timestamp(boat.time_now), self.sail_fraction);
+                }
+            }
+        }
+        if let Some(engine_speed) = command.engine_speed {
+            if !(engine_speed >= 0.0 && engine_speed.is_finite()) {
+                return Err(MarineSimError::InvalidInput(format!("The engine speed must be zero or positive, it is {}", engine_speed)));
+            }
+            self.engine_speed = engine_speed;
+        }
+        if let Some(heading) = command.heading {
+            let heading = heading.rem_euclid(360.0);
+            // Count a tack when the bow turns through the wind
+            if let Some(old_heading) = boat.heading {
+                let old_angle = get_signed_wind_angle(old_heading, self.wind.angle);
+                let new_angle = get_signed_wind_angle(heading, self.wind.angle);
+                if self.wind.magnitude > 0.0 && old_angle.abs() < 90.0 && new_angle.abs() < 90.0 && old_angle.signum() != new_angle.signum() {
+                    if !boat.can_maneuver(boat.time_now) {
+                        tracing::debug!("The crew can not tack at {}, keeping the heading at {:.1}", format_timestamp(boat.time_now), old_heading);
+                        return Ok(());
+                    }
+                    if boat.get_current_leg().is_some_and(|leg| leg.traffic_lane.is_some()) {
+                        tracing::debug!("No tacking in the traffic lane of leg {}, keeping the heading at {:.1}", boat.current_leg.unwrap_or(0), old_heading);
+                        return Ok(());
+                    }
+                    boat.num_tacks += 1;
+                    boat.record_maneuver(boat.time_now);
+                }
+            }
+            boat.heading = Some(heading);
+        }
+        return Ok(());
+    }
+
+    /// Moves the boat one time step with the commanded heading, sail and engine and returns its new state
+    /// Returns an error if the voyage has already ended, see StepState.status
+    pub fn step(&mut self, boat: &mut Boat) -> Result<StepState, MarineSimError> {
+        if let Some(status) = self.status {
+            return Err(MarineSimError::InvalidInput(format!("The voyage has ended: {}", status)));
+        }
+        let result = match self.simulation.earth_model {
+            Some(earth_model) => with_earth_model(earth_model, || self.step_on_earth_model(boat)),
+            None => self.step_on_earth_model(boat),
+        };
+        // The voyage is over, stop reporting progress
+        if self.status.is_some() || result.is_err() {
+            boat.voyage_departure = None;
+            boat.progress_callbacks.clear();
+        }
+        return result;
+    }
+
+    /// Moves the boat one time step on the Earth model of the thread, see step()
+    fn step_on_earth_model(&mut self, boat: &mut Boat) -> Result<StepState, MarineSimError> {
+        // Stop if out of iterations or simulated time
+        if self.iterations >= self.simulation.max_iterations {
+            self.status = Some(SimulationStatus::MaxIterationsReached);
+            return self.state(boat);
+        }
+        if max_simulated_duration_reached(boat, self.simulation, self.start_time) {
+            self.status = Some(SimulationStatus::MaxSimulatedDurationReached);
+            return self.state(boat);
+        }
+        if let Some(status) = check_tanks(boat, self.simulation.bunkering.as_ref()) {
+            self.status = Some(status);
+            return self.state(boat);
+        }
+        self.iterations += 1;
+
+        // Weather at the start of the step
+        let time_now: UtcDateTime = boat.ship_log.last().map_or(boat.time_now, |entry| entry.timestamp);
+        let location: geo::Point = get_boat_location(boat)?;
+        (self.wind, self.ocean_current) = self.get_weather(time_now, location)?;
+
+        // Velocity over ground, unit [m/s]
+        let heading: f64 = get_boat_heading(boat)?;
+        let (speed_through_water, foiling): (f64, Option<bool>) = if boat.polar_diagram.is_some() || (boat.velocity_max.is_some() && boat.speed_grade_coefficient.is_some()) {
+            let (velocity, foiling) = get_vessel_velocity_and_foiling(boat, self.wind, None)?;
+            (velocity.magnitude, foiling)
+        } else {
+            match boat.velocity_mean {
+                Some(velocity_mean) => (velocity_mean, None),
+                None => return Err(MarineSimError::MissingBoatField("polar diagram, velocity max and speed grade coefficient, or mean velocity".to_string())),
+            }
+        };
+        let sailing_speed: f64 = speed_through_water*self.sail_fraction;
+        let navigation_status = if self.engine_speed > sailing_speed { NavigationStatus::UnderwayUsingEngine } else { NavigationStatus::UnderwaySailing };
+        let velocity: PhysVec = PhysVec::new(sailing_speed.max(self.engine_speed), heading) + self.ocean_current.unwrap_or(PhysVec::new(0.0, 0.0));
+
+        // Move the boat
+        let new_location: geo::Point = Earth.destination(location, velocity.angle, velocity.magnitude*self.simulation.time_step.as_seconds_f64());
+        let leg_number: u32 = get_boat_current_leg(boat)?;
+        if let Some(land_mask) = &self.simulation.land_mask {
+            land_mask.check_step(location, new_location, leg_number)?;
+        }
+        let timestamp: UtcDateTime = add_duration(time_now, self.simulation.time_step)?;
+        boat.location = Some(new_location);
+        boat.velocity_current = Some(velocity);
+        boat.time_now = timestamp;
+
+        // Log the step
+        let leg: SailingLeg = *boat.get_current_leg().ok_or_else(|| MarineSimError::MissingBoatField("current leg".to_string()))?;
+        let mut new_log_entry = ShipLogEntry {
+            timestamp,
+            coordinates_initial: self.coordinates_initial,
+            coordinates_current: new_location,
+            coordinates_final: self.coordinates_final,
+            cargo_on_board: Some(boat.cargo_current),
+            velocity: Some(velocity),
+            course: None,
+            heading: Some(heading),
+            track_angle: Some(Rhumb.bearing(location, new_location)),
+            true_bearing: Some(Earth.bearing(new_location, leg.p2)),
+            draft: boat.draft,
+            navigation_status: Some(navigation_status),
+            depth: None,
+            wind: Some(self.wind),
+            wave_height: None,
+            cross_track_error: None,
+            foiling,
+        };
+        boat.navigation_status = Some(navigation_status);
+        let grounded = check_depth(boat, self.simulation, &mut new_log_entry);
+        boat.push_ship_log_entry(new_log_entry);
+        if grounded {
+            self.status = Some(SimulationStatus::RanAground);
+            return self.state(boat);
+        }
+
+        // The boat reaches the waypoint if it passed close enough to it during the step
+        if get_min_point_to_segment_dist(location, new_location, leg.p2) <= leg.min_proximity {
+            log_waypoint_arrival(boat, timestamp)?;
+            do_waypoint_stops(boat, self.simulation, timestamp, self.coordinates_initial, self.coordinates_final)?;
+            if leg_number as usize >= get_boat_route_plan(boat)?.len() {
+                self.status = Some(SimulationStatus::Completed);
+            }
+            else {
+                boat.current_leg = Some(leg_number + 1);
+            }
+        }
+
+        return self.state(boat);
+    }
+
+    /// Returns the current state of the boat without moving it, e.g. for the first command of the controller
+    /// Returns an error if the boat has no location, heading or current leg, e.g. if it was changed outside of the co-simulation
+    pub fn state(&self, boat: &Boat) -> Result<StepState, MarineSimError> {
+        let location = get_boat_location(boat)?;
+        let heading = get_boat_heading(boat)?;
+        let leg = boat.get_current_leg().ok_or_else(|| MarineSimError::MissingBoatField("current leg".to_string()))?;
+        let distance_to_next_waypoint = self.on_earth_model(|| Earth.distance(location, leg.p2));
+        return Ok(StepState {
+            timestamp: boat.ship_log.last().map_or(boat.time_now, |entry| entry.timestamp),
+            location,
+            heading,
+            velocity: boat.velocity_current.unwrap_or(PhysVec::new(0.0, 0.0)),
+            wind: self.wind,
+            ocean_current: self.ocean_current,
+            true_wind_angle: get_true_wind_angle(heading, self.wind.angle),
+            current_leg: get_boat_current_leg(boat)?,
+            next_waypoint: leg.p2,
+            distance_to_next_waypoint,
+            cross_track_error: boat.ship_log.last().and_then(|entry| entry.cross_track_error),
+            sail_fraction: self.sail_fraction,
+            engine_speed: self.engine_speed,
+            iterations: self.iterations,
+            status: self.status,
+        });
+    }
+
+    /// Returns the result of the voyage, the same as sim_waypoint_mission() returns, None while the voyage is still going
+    pub fn result(&self, boat: &Boat) -> Option<SimulationResult> {
+        let status = self.status?;
+        let ship_log = &boat.ship_log[self.log_start.min(boat.ship_log.len())..];
+        let mut kpis = VoyageKpis::from_ship_log(ship_log);
+        kpis.num_tacks = Some(boat.num_tacks - self.num_tacks_start);
+        kpis.waypoint_arrivals = boat.waypoint_arrivals[self.arrivals_start.min(boat.waypoint_arrivals.len())..].to_vec();
+        kpis.events = boat.events[self.events_start.min(boat.events.len())..].to_vec();
+        let mut progress = self.on_earth_model(|| boat.get_voyage_progress(self.start_time))?;
+        progress.simulated_time = kpis.duration;
+        if status.is_completed() {
+            progress.legs_completed = progress.total_legs;
+            progress.distance_covered = progress.total_distance;
+        }
+        return Some(SimulationResult {
+            status,
+            iterations: self.iterations,
+            kpis,
+            progress,
+        });
+    }
+
+    /// Returns the wind and ocean current at a time and location from simulation.weather, calm if it is not set
+    fn get_weather(&self, timestamp: UtcDateTime, location: geo::Point) -> Result<(PhysVec, Option<PhysVec>), MarineSimError> {
+        return match &self.simulation.weather {
+            Some(weather) => Ok((weather.wind(timestamp, location)?, weather.ocean_current(timestamp, location)?)),
+            None => Ok((PhysVec::new(0.0, 0.0), None)),
+        };
+    }
+
+    /// Runs f on the Earth model of the simulation, if it has one, see with_earth_model()
+    fn on_earth_model<T, F: FnOnce() -> T>(&self, f: F) -> T {
+        return match self.simulation.earth_model {
+            Some(earth_model) => with_earth_model(earth_model, f),
+            None => f(),
+        };
+    }
+}
+
+/// Returns the angle in degrees, in \[-180, 180), from the heading to where the wind comes from, positive when the wind is on the starboard side
+fn get_signed_wind_angle(heading: f64, wind_angle: f64) -> f64 {
+    return (wind_angle - heading + 540.0).rem_euclid(360.0) - 180.0;
 }
 
 // Helper functions
 //---------------------------------------------------------------------------------
 /// Function that returns the estimated velocity of the vessel in reference to the Earth. That is ground speed along with direction.
 /// If ocean current is given, assumes that vessel follows current completely before taking wind into account
+/// If the boat has a polar diagram the speed through water is taken from it, see Boat.polar_diagram
+/// The speed through water is multiplied by the displacement speed factor, so a loaded vessel is slower, see Boat.get_displacement_speed_factor()
+/// Without a polar diagram the speed through water is also multiplied by the speed factor of the hull type, see HullType.speed_factor()
+/// If the boat has a foil the speed through water is multiplied by the speed factor of the foil above the takeoff speed, see Foil.speed_factor()
 // TODO: make the function and use in simulation functions
-pub fn get_vessel_velocity(boat: &Boat, wind: PhysVec, ocean_current: Option<PhysVec>) -> Result<PhysVec, io::Error> {
+pub fn get_vessel_velocity(boat: &Boat, wind: PhysVec, ocean_current: Option<PhysVec>) -> Result<PhysVec, MarineSimError> {
+    return Ok(get_vessel_velocity_and_foiling(boat, wind, ocean_current)?.0);
+}
+
+/// Returns the velocity of the vessel in reference to the Earth like get_vessel_velocity(), and whether the vessel is foiling at that velocity
+/// The foiling flag is None if the boat has no foil, otherwise whether the speed through water is above the takeoff speed of the foil, see Foil.is_foiling()
+/// Used by the simulators for ShipLogEntry.foiling, so the flag matches the speed factor of the foil that was applied
+pub fn get_vessel_velocity_and_foiling(boat: &Boat, wind: PhysVec, ocean_current: Option<PhysVec>) -> Result<(PhysVec, Option<bool>), MarineSimError> {
     // TODO: Should be able to accomodate multiple types of vessels, currently only works for sail powered ones
     // Check that boat has heading, if not, return error
     if boat.heading.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Boat heading can not be None"));
+        return Err(MarineSimError::MissingBoatField("heading".to_string()));
+    }
+
+    // If the boat has a polar diagram, use it for the speed through water
+    if let Some(polar_diagram) = &boat.polar_diagram {
+        let heading = boat.heading.unwrap();
+        let vws: f64 = polar_diagram.boat_speed(wind.magnitude, get_true_wind_angle(heading, wind.angle))*boat.get_displacement_speed_factor();
+        // Foiling vessels are faster above the takeoff speed, see Foil
+        let (vws, foiling): (f64, Option<bool>) = get_speed_with_foil(boat, vws);
+        return Ok((PhysVec::new(vws, heading) + ocean_current.unwrap_or(PhysVec::new(0.0, 0.0)), foiling));
     }
+
     // Check that boat has velocity max
     if boat.velocity_max.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Boat velocity max can not be None"));
+        return Err(MarineSimError::MissingBoatField("velocity max".to_string()));
     }
     // Check that boat has speed grade coefficient
     if boat.speed_grade_coefficient.is_none() {
-        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Boat speed grade coefficient can not be None"));
+        return Err(MarineSimError::MissingBoatField("speed grade coefficient".to_string()));
     }
 
     // init velocity
@@ -969,11 +3553,457 @@ pub fn get_vessel_velocity(boat: &Boat, wind: PhysVec, ocean_current: Option<Phy
 
     // Compute vessel velocity through water (vws = vessel water speed, there might be a better more recognised term used by the industry)
     // Using approximation from https://github.com/G0rocks/marine_vessel_simulator/issues/77
-    let vws: f64 = (2.0*boat.velocity_max.unwrap()/std::f64::consts::PI)*(boat.speed_grade_coefficient.unwrap()*aw.magnitude).atan()*(1.0-(aw.angle*std::f64::consts::PI/180.0 - std::f64::consts::PI/4.0).cos());
+    let vws: f64 = (2.0*boat.velocity_max.unwrap()/std::f64::consts::PI)*(boat.speed_grade_coefficient.unwrap()*aw.magnitude).atan()*(1.0-(aw.angle*std::f64::consts::PI/180.0 - std::f64::consts::PI/4.0).cos())*boat.get_displacement_speed_factor()*boat.hull_type.speed_factor(wind.magnitude);
+    // Foiling vessels are faster above the takeoff speed, see Foil
+    let (vws, foiling): (f64, Option<bool>) = get_speed_with_foil(boat, vws);
 
     // Make output speed including ocean current
     vel = PhysVec::new(vws, heading) + vel;
 
     // Return vessel velocity
-    Ok(vel)
-}
\ No newline at end of file
+    Ok((vel, foiling))
+}
+
+/// Returns the speed through water in \[m/s\] multiplied by the speed factor of the foil of the boat, and whether the boat is foiling, None if it has no foil, see Foil
+fn get_speed_with_foil(boat: &Boat, speed: f64) -> (f64, Option<bool>) {
+    return match &boat.foil {
+        Some(foil) => (speed*foil.speed_factor(speed), Some(foil.is_foiling(speed))),
+        None => (speed, None),
+    };
+}
+
+/// Returns the velocity over ground of a vessel without propulsion, the ocean current plus the leeway downwind, see SimMethod::Drift
+/// The leeway is the wind speed multiplied by boat.windage_factor, 0.03 if it is not set, in the direction the wind blows to
+/// # Example:
+/// With 10 m/s wind from the north, no current and the default windage factor the vessel drifts south at 0.3 m/s
+pub fn get_drift_velocity(boat: &Boat, wind: PhysVec, ocean_current: Option<PhysVec>) -> PhysVec {
+    let leeway = PhysVec::new(wind.magnitude*boat.windage_factor.unwrap_or(0.03), (wind.angle + 180.0) % 360.0);
+    return leeway + ocean_current.unwrap_or(PhysVec::new(0.0, 0.0));
+}
+
+
+/// Returns the wave height and wave resistance amplification (1 or more) for the wind and ocean current, 1.0 if simulation.wind_against_current is None or the thresholds are not met, see WindAgainstCurrent
+/// Adds a WindAgainstCurrent event to boat.events when the condition starts. in_wind_against_current tracks whether the condition applied in the last time step
+#[cfg(feature = "copernicus")]
+pub(crate) fn update_wind_against_current(boat: &mut Boat, simulation: &Simulation, wind: PhysVec, ocean_current: PhysVec, timestamp: UtcDateTime, in_wind_against_current: &mut bool) -> f64 {
+    return match simulation.wind_against_current.and_then(|w| w.amplification(wind, ocean_current)) {
+        Some(amplification) => {
+            // Add an event when the condition starts
+            if !*in_wind_against_current {
+                boat.events.push(SimulationEvent {
+                    timestamp,
+                    location: boat.location.unwrap(),
+                    leg: boat.current_leg,
+                    kind: SimulationEventKind::WindAgainstCurrent { wind_speed: wind.magnitude, current_against_wind: WindAgainstCurrent::current_against_wind(wind, ocean_current), amplification },
+                });
+            }
+            *in_wind_against_current = true;
+            amplification
+        },
+        None => {
+            *in_wind_against_current = false;
+            1.0
+        },
+    };
+}
+
+/// Returns the working velocity over ground \[m/s\] of a sailing vessel for the wind, ocean current and storm tactic, see sim_waypoint_mission_weather_data_from_copernicus()
+/// The speed through water is divided by the wind against current amplification to account for the higher wave resistance
+#[cfg(feature = "copernicus")]
+pub(crate) fn get_sailing_velocity(boat: &Boat, wind: PhysVec, ocean_current: PhysVec, storm_bound: bool, wind_against_current_amplification: f64) -> PhysVec {
+    return match (storm_bound, boat.storm_tactic) {
+        // Hove-to, the boat makes very little way forward, drifts downwind (leeway) and with the ocean current
+        (true, StormTactic::HeaveTo) => PhysVec::new(wind.magnitude*boat.wind_velocity_multiplier.unwrap()*boat.heave_to_speed_multiplier.unwrap_or(0.05)/wind_against_current_amplification, boat.heading.unwrap())
+            + PhysVec::new(wind.magnitude*boat.heave_to_leeway_multiplier.unwrap_or(0.03), (wind.angle + 180.0) % 360.0)
+            + ocean_current,
+        // Running downwind at reduced speed
+        (true, StormTactic::RunDownwind) => PhysVec::new(wind.magnitude*boat.wind_velocity_multiplier.unwrap()*boat.storm_velocity_multiplier.unwrap_or(0.5)/wind_against_current_amplification, boat.heading.unwrap()) + ocean_current,
+        // Normal sailing
+        (false, _) => PhysVec::new(wind.magnitude*boat.wind_velocity_multiplier.unwrap()/wind_against_current_amplification, boat.heading.unwrap()) + ocean_current,
+    };
+}
+
+/// Checks the water depth at the location of a new ship log entry against the draft of the boat, if the simulation has bathymetry
+/// Sets the depth of the entry and returns true if the boat ran aground, in which case the navigation status of the entry is set to aground and a grounding event is added to boat.events
+/// If the boat has no draft only the depth is logged
+fn check_depth(boat: &mut Boat, simulation: &Simulation, entry: &mut ShipLogEntry) -> bool {
+    // Get depth at location
+    let depth = match &simulation.bathymetry {
+        Some(bathymetry) => bathymetry.depth_at(entry.coordinates_current),
+        None => return false,
+    };
+    entry.depth = depth;
+
+    // Compare against the depth the boat needs
+    if let (Some(depth), Some(draft)) = (depth, boat.draft) {
+        let min_depth = draft + boat.min_under_keel_clearance.unwrap_or(0.0);
+        if depth < min_depth {
+            entry.navigation_status = Some(NavigationStatus::Aground);
+            boat.navigation_status = Some(NavigationStatus::Aground);
+            boat.events.push(SimulationEvent {
+                timestamp: entry.timestamp,
+                location: entry.coordinates_current,
+                leg: boat.current_leg,
+                kind: SimulationEventKind::Grounding { depth, min_depth },
+            });
+            return true;
+        }
+    }
+    return false;
+}
+
+/// Checks the tanks of the boat at the start of a time step, see Tank
+/// Returns SimulationStatus::TankEmpty if a tank is empty. Otherwise, if a tank is below the bunkering reserve and the boat is not heading to or coming from a bunkering port on this leg,
+/// inserts a detour to the closest bunkering port before the next waypoint in the route plan and returns None
+fn check_tanks(boat: &mut Boat, bunkering: Option<&Bunkering>) -> Option<SimulationStatus> {
+    if boat.tanks().any(|tank| tank.is_empty()) {
+        return Some(SimulationStatus::TankEmpty);
+    }
+    let bunkering = bunkering?;
+    if !boat.tanks().any(|tank| tank.fraction() < bunkering.reserve_fraction) {
+        return None;
+    }
+    let (location, leg) = match (boat.location, boat.get_current_leg()) {
+        (Some(location), Some(leg)) => (location, *leg),
+        _ => return None,
+    };
+    if leg.port_call.is_some_and(|port_call| port_call.refill_tanks) || bunkering.ports.contains(&leg.p1) {
+        return None;    // Already heading to a bunkering port or just left one
+    }
+    let port = bunkering.ports.iter().copied().min_by(|a, b| Earth.distance(location, *a).total_cmp(&Earth.distance(location, *b)))?;
+
+    // Sail to the port and fill the tanks, then on to the next waypoint
+    let mut to_port = SailingLeg::new(location, port, leg.tacking_width, leg.min_proximity);
+    to_port.port_call = Some(PortCall::bunkering(bunkering.wait));
+    let mut from_port = leg;
+    from_port.p1 = port;
+    let index = (boat.current_leg.unwrap() - 1) as usize;
+    let route_plan = boat.route_plan.as_mut().unwrap();
+    route_plan.legs[index] = to_port;
+    route_plan.legs.insert(index + 1, from_port);
+    tracing::info!("Tank below the reserve, detouring to the bunkering port at latitude {:.4}, longitude {:.4}", port.y(), port.x());
+    return None;
+}
+
+/// Returns true if simulation.max_simulated_duration is set and the voyage that started at start_time has lasted that long according to the last ship log entry
+fn max_simulated_duration_reached(boat: &Boat, simulation: &Simulation, start_time: UtcDateTime) -> bool {
+    return match (simulation.max_simulated_duration, boat.ship_log.last()) {
+        (Some(max_duration), Some(last_entry)) => last_entry.timestamp - start_time >= max_duration,
+        _ => false,
+    };
+}
+
+/// Adds the arrival of the boat at the end of its current leg to boat.waypoint_arrivals
+/// Also adds a WaypointReached event to boat.events, and a LegStarted event if there is a next leg
+/// Returns an error if the boat has no route plan, current leg or location
+fn log_waypoint_arrival(boat: &mut Boat, timestamp: UtcDateTime) -> Result<(), MarineSimError> {
+    let leg: u32 = get_boat_current_leg(boat)?;
+    let num_legs = get_boat_route_plan(boat)?.len() as u32;
+    let waypoint = get_boat_route_plan(boat)?[(leg - 1) as usize].p2;
+    let location = get_boat_location(boat)?;
+    boat.waypoint_arrivals.push(WaypointArrival {
+        leg,
+        waypoint,
+        location,
+        timestamp,
+    });
+    boat.events.push(SimulationEvent {
+        timestamp,
+        location,
+        leg: Some(leg),
+        kind: SimulationEventKind::WaypointReached { waypoint },
+    });
+    if leg < num_legs {
+        boat.events.push(SimulationEvent {
+            timestamp,
+            location,
+            leg: Some(leg + 1),
+            kind: SimulationEventKind::LegStarted,
+        });
+    }
+    return Ok(());
+}
+
+/// Makes the port call at the end of the current leg, if the leg has one, see PortCall
+/// Unloads and loads the cargo, updates the draft from the displacement (see Boat.update_draft()) or else with boat.tons_per_cm_immersion if it is set, adds a PortCall event to boat.events
+/// and logs the vessel moored in port on arrival and departure
+/// Returns the time spent in port, zero if the leg has no port call, or an error if the cargo on board would be negative or above boat.cargo_max_capacity
+fn do_port_call(boat: &mut Boat, arrival_time: UtcDateTime, coordinates_initial: geo::Point, coordinates_final: geo::Point) -> Result<time::Duration, MarineSimError> {
+    // Get port call at the end of the current leg
+    let leg_num: u32 = get_boat_current_leg(boat)?;
+    let port_call: PortCall = match boat.route_plan.as_ref().and_then(|route_plan| route_plan.get((leg_num - 1) as usize)).and_then(|leg| leg.port_call) {
+        Some(port_call) => port_call,
+        None => return Ok(time::Duration::ZERO),
+    };
+
+    // Untyped cargo only changes cargo_current, so it is not allowed with cargo by type on board or loaded, as the cargo statistics would go stale
+    let has_untyped_cargo = port_call.cargo_loaded.value != 0.0 || port_call.cargo_unloaded.value != 0.0;
+    if has_untyped_cargo && (!boat.cargo.is_empty() || port_call.cargo_load.is_some()) {
+        return Err(MarineSimError::InvalidInput(format!("Port call at the end of leg {} loads or unloads untyped cargo while the vessel carries cargo by type, use PortCall.cargo_load and PortCall.cargo_unload", leg_num)));
+    }
+
+    // Cargo by type unloaded and loaded at the port
+    let cargo_type_unloaded: uom::si::f64::Mass = boat.cargo.iter().filter(|cargo| Some(cargo.cargo_type()) == port_call.cargo_unload).map(|cargo| cargo.mass()).sum();
+    let cargo_type_loaded = port_call.cargo_load.map_or(uom::si::f64::Mass::new::<uom::si::mass::ton>(0.0), |cargo| cargo.mass());
+    if let Some(cargo) = port_call.cargo_load {
+        let cargo_type = cargo.cargo_type();
+        if !(cargo.amount() >= 0.0) || !(cargo.mass().value >= 0.0) {
+            return Err(MarineSimError::InvalidInput(format!("Port call at the end of leg {} loads negative {} cargo", leg_num, cargo_type)));
+        }
+        if let Some(capacity) = boat.get_cargo_capacity(cargo_type) {
+            let amount_kept = if port_call.cargo_unload == Some(cargo_type) { 0.0 } else { boat.get_cargo_amount(cargo_type) };
+            if amount_kept + cargo.amount() > capacity {
+                return Err(MarineSimError::InvalidInput(format!("Port call at the end of leg {} loads more {} cargo than the vessel can carry", leg_num, cargo_type)));
+            }
+        }
+    }
+
+    // Unload and load cargo
+    let cargo_new = boat.cargo_current - port_call.cargo_unloaded - cargo_type_unloaded + port_call.cargo_loaded + cargo_type_loaded;
+    if cargo_new.get::<uom::si::mass::ton>() < 0.0 {
+        return Err(MarineSimError::InvalidInput(format!("Port call at the end of leg {} unloads more cargo than is on board", leg_num)));
+    }
+    if let Some(cargo_max_capacity) = boat.cargo_max_capacity {
+        if cargo_new > cargo_max_capacity {
+            return Err(MarineSimError::InvalidInput(format!("Port call at the end of leg {} loads more cargo than the vessel can carry", leg_num)));
+        }
+    }
+    // Update the draft, from the displacement if the hull is known, otherwise the vessel sinks 1 cm for every tons_per_cm_immersion tons of cargo
+    let cargo_old = boat.cargo_current;
+    boat.cargo_current = cargo_new;
+    if let Some(cargo_type) = port_call.cargo_unload {
+        boat.cargo.retain(|cargo| cargo.cargo_type() != cargo_type);
+    }
+    if let Some(cargo) = port_call.cargo_load {
+        boat.cargo.push(cargo);
+    }
+    if boat.get_draft_from_displacement().is_some() {
+        boat.update_draft();
+    }
+    else if let (Some(draft), Some(tpc)) = (boat.draft, boat.tons_per_cm_immersion) {
+        let delta_tons = (cargo_new - cargo_old).get::<uom::si::mass::ton>();
+        boat.draft = Some(draft + delta_tons/tpc/100.0);
+    }
+
+    // Fill the tanks at bunkering ports
+    let location = get_boat_location(boat)?;
+    if port_call.refill_tanks {
+        let fuel = boat.fuel_tank.as_mut().map_or(0.0, |tank| tank.refill());
+        let fresh_water = boat.fresh_water_tank.as_mut().map_or(0.0, |tank| tank.refill());
+        if let Some(tank) = boat.fuel_tank {
+            boat.fuel_current = uom::si::f64::Mass::new::<uom::si::mass::ton>(tank.level);
+        }
+        boat.events.push(SimulationEvent {
+            timestamp: arrival_time,
+            location,
+            leg: Some(leg_num),
+            kind: SimulationEventKind::Bunkered { fuel, fresh_water },
+        });
+    }
+
+    // Log the vessel moored in port on arrival and departure
+    let departure_time = add_duration(arrival_time, port_call.wait)?;
+    for timestamp in [arrival_time, departure_time] {
+        boat.push_ship_log_entry(ShipLogEntry {
+            timestamp,
+            coordinates_initial,
+            coordinates_current: location,
+            coordinates_final,
+            cargo_on_board: Some(boat.cargo_current),
+            velocity: Some(PhysVec::new(0.0, 0.0)),
+            course: None,
+            heading: boat.heading,
+            track_angle: None,
+            true_bearing: None,
+            draft: boat.draft,
+            navigation_status: Some(NavigationStatus::Moored),
+            depth: None,
+            wind: None,
+            wave_height: None,
+            cross_track_error: None,
+            foiling: None,
+        });
+    }
+
+    boat.events.push(SimulationEvent {
+        timestamp: arrival_time,
+        location,
+        leg: Some(leg_num),
+        kind: SimulationEventKind::PortCall {
+            cargo_loaded: (port_call.cargo_loaded + cargo_type_loaded).get::<uom::si::mass::ton>(),
+            cargo_unloaded: (port_call.cargo_unloaded + cargo_type_unloaded).get::<uom::si::mass::ton>(),
+            wait: port_call.wait,
+        },
+    });
+    return Ok(port_call.wait);
+}
+
+/// Makes the stops at the end of the current leg, first the port call and then the anchor wait if the leg has them, see do_port_call() and do_anchor_wait()
+/// Returns the total time stopped, zero if the leg has neither
+fn do_waypoint_stops(boat: &mut Boat, simulation: &Simulation, arrival_time: UtcDateTime, coordinates_initial: geo::Point, coordinates_final: geo::Point) -> Result<time::Duration, MarineSimError> {
+    let port_time = do_port_call(boat, arrival_time, coordinates_initial, coordinates_final)?;
+    let anchor_time = do_anchor_wait(boat, simulation, arrival_time + port_time, coordinates_initial, coordinates_final)?;
+    return Ok(port_time + anchor_time);
+}
+
+/// Waits at anchor or moored at the end of the current leg, if the leg has an anchor wait, see AnchorWait
+/// The vessel drops anchor where it is and is logged every simulation.time_step with the navigation status AtAnchor or Moored. At anchor it lies at the swing radius from the anchor in the direction the ocean current and wind push it (see get_drift_velocity()),
+/// and it weighs anchor back over the anchor. An Anchored event is added to boat.events and the navigation status is set back to what it was before
+/// The weather is taken from simulation.weather, or from simulation.copernicus with the "copernicus" feature. Without weather the vessel lies still over its anchor
+/// Returns the time waited, zero if the leg has no anchor wait or there is nothing to wait for, or an error if the wait is for the weather and the simulation has no weather
+fn do_anchor_wait(boat: &mut Boat, simulation: &Simulation, arrival_time: UtcDateTime, coordinates_initial: geo::Point, coordinates_final: geo::Point) -> Result<time::Duration, MarineSimError> {
+    let leg_num: u32 = get_boat_current_leg(boat)?;
+    let anchor_wait: AnchorWait = match boat.get_current_leg().and_then(|leg| leg.anchor_wait) {
+        Some(anchor_wait) => anchor_wait,
+        None => return Ok(time::Duration::ZERO),
+    };
+    let anchor: geo::Point = get_boat_location(boat)?;
+    let navigation_status: Option<NavigationStatus> = boat.navigation_status;
+
+    // Stop waiting at the max simulated duration of the voyage, see Simulation.max_simulated_duration
+    let max_time: Option<UtcDateTime> = match (boat.voyage_departure, simulation.max_simulated_duration) {
+        (Some(departure), Some(max_duration)) => departure.checked_add(max_duration),
+        _ => None,
+    };
+
+    let mut timestamp: UtcDateTime = arrival_time;
+    let mut iterations: usize = 0;
+    loop {
+        // Get the weather at the anchorage
+        let weather: Option<(PhysVec, Option<PhysVec>)> = get_simulation_weather(simulation, timestamp, anchor)?;
+        if anchor_wait.max_wind_speed.is_some() && weather.is_none() {
+            return Err(MarineSimError::InvalidInput(format!("The anchor wait at the end of leg {} waits for the wind to drop, but the simulation has no weather", leg_num)));
+        }
+        let wind: Option<PhysVec> = weather.map(|(wind, _)| wind);
+
+        // Wait until the time and the weather, but no longer than the max wait, the max simulated duration or max_iterations time steps
+        let waited: time::Duration = timestamp - arrival_time;
+        let done: bool = waited >= anchor_wait.max_wait
+            || max_time.is_some_and(|max_time| timestamp >= max_time)
+            || iterations >= simulation.max_iterations
+            || (anchor_wait.until.is_none_or(|until| timestamp >= until) && anchor_wait.max_wind_speed.is_none_or(|max_wind_speed| wind.is_some_and(|wind| wind.magnitude <= max_wind_speed)));
+        if done && waited == time::Duration::ZERO {
+            return Ok(time::Duration::ZERO);
+        }
+
+        // Lie downstream of the anchor while waiting, weigh anchor over it
+        let location: geo::Point = match (done || anchor_wait.moored, weather) {
+            (false, Some((wind, ocean_current))) => {
+                let drift = get_drift_velocity(boat, wind, ocean_current);
+                match drift.magnitude > 0.0 {
+                    true => Earth.destination(anchor, drift.angle, anchor_wait.swing_radius),
+                    false => anchor,
+                }
+            }
+            _ => anchor,
+        };
+        boat.location = Some(location);
+        boat.navigation_status = Some(anchor_wait.navigation_status());
+        boat.push_ship_log_entry(ShipLogEntry {
+            timestamp,
+            coordinates_initial,
+            coordinates_current: location,
+            coordinates_final,
+            cargo_on_board: Some(boat.cargo_current),
+            velocity: Some(PhysVec::new(0.0, 0.0)),
+            course: None,
+            heading: boat.heading,
+            track_angle: None,
+            true_bearing: None,
+            draft: boat.draft,
+            navigation_status: Some(anchor_wait.navigation_status()),
+            depth: None,
+            wind,
+            wave_height: None,
+            cross_track_error: None,
+            foiling: None,
+        });
+        if done {
+            break;
+        }
+
+        // Next time step, stopping at the time waited for, at the max wait and at the max simulated duration
+        let mut step: time::Duration = simulation.time_step.min(anchor_wait.max_wait - waited);
+        if let Some(until) = anchor_wait.until {
+            if timestamp < until {
+                step = step.min(until - timestamp);
+            }
+        }
+        if let Some(max_time) = max_time {
+            step = step.min(max_time - timestamp);
+        }
+        timestamp = add_duration(timestamp, step)?;
+        iterations += 1;
+    }
+
+    // Sail on as before
+    boat.navigation_status = navigation_status;
+    let wait: time::Duration = timestamp - arrival_time;
+    boat.events.push(SimulationEvent {
+        timestamp: arrival_time,
+        location: anchor,
+        leg: Some(leg_num),
+        kind: SimulationEventKind::Anchored { wait, moored: anchor_wait.moored },
+    });
+    return Ok(wait);
+}
+
+/// Returns the wind and ocean current at a time and location from simulation.weather, or from simulation.copernicus with the "copernicus" feature, None if the simulation has no weather
+fn get_simulation_weather(simulation: &Simulation, timestamp: UtcDateTime, location: geo::Point) -> Result<Option<(PhysVec, Option<PhysVec>)>, MarineSimError> {
+    if let Some(weather) = &simulation.weather {
+        return Ok(Some((weather.wind(timestamp, location)?, weather.ocean_current(timestamp, location)?)));
+    }
+    #[cfg(feature = "copernicus")]
+    if let Some(copernicus) = &simulation.copernicus {
+        return Ok(Some((get_wind_from_copernicus(copernicus, timestamp, location)?, get_ocean_current_from_copernicus(copernicus, timestamp, location)?)));
+    }
+    return Ok(None);
+}
+
+/// Returns an error naming the legs of the route plan that break the rules of their traffic lanes, see RoutePlan.traffic_lane_violations()
+fn check_traffic_lanes(route_plan: &RoutePlan) -> Result<(), MarineSimError> {
+    let violations: Vec<usize> = route_plan.traffic_lane_violations();
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let legs: Vec<String> = violations.iter().map(|i| (i + 1).to_string()).collect();
+    return Err(MarineSimError::InvalidInput(format!("Legs {} break the rules of their traffic lanes, see TrafficLane", legs.join(", "))));
+}
+
+/// Returns the route plan of the boat, an error if the boat has no route plan or the route plan has no legs
+fn get_boat_route_plan(boat: &Boat) -> Result<&RoutePlan, MarineSimError> {
+    return match boat.route_plan.as_ref() {
+        Some(route_plan) if !route_plan.is_empty() => Ok(route_plan),
+        Some(_) => Err(MarineSimError::InvalidInput("Route plan has no legs".to_string())),
+        None => Err(MarineSimError::MissingBoatField("route plan".to_string())),
+    };
+}
+
+/// Returns the location of the boat, an error if it has none
+fn get_boat_location(boat: &Boat) -> Result<geo::Point, MarineSimError> {
+    return boat.location.ok_or_else(|| MarineSimError::MissingBoatField("location".to_string()));
+}
+
+/// Returns the heading of the boat, an error if it has none
+fn get_boat_heading(boat: &Boat) -> Result<f64, MarineSimError> {
+    return boat.heading.ok_or_else(|| MarineSimError::MissingBoatField("heading".to_string()));
+}
+
+/// Returns the number of the leg the boat is sailing, starting at 1, an error if it has no current leg
+fn get_boat_current_leg(boat: &Boat) -> Result<u32, MarineSimError> {
+    return boat.current_leg.ok_or_else(|| MarineSimError::MissingBoatField("current leg".to_string()));
+}
+
+/// Returns the time plus the duration, an error if the result is out of the range of UtcDateTime
+fn add_duration(time: UtcDateTime, duration: time::Duration) -> Result<UtcDateTime, MarineSimError> {
+    return time.checked_add(duration).ok_or_else(|| MarineSimError::InvalidInput(format!("{} plus {} is out of the range of dates", format_timestamp(time), duration)));
+}
+
+/// Returns the time after a number of time steps from the start time, an error if the result is out of the range of UtcDateTime
+fn get_time_after_steps(start_time: UtcDateTime, time_step: time::Duration, steps: usize) -> Result<UtcDateTime, MarineSimError> {
+    let duration: time::Duration = match i32::try_from(steps).ok().and_then(|steps| time_step.checked_mul(steps)) {
+        Some(duration) => duration,
+        None => return Err(MarineSimError::InvalidInput(format!("{} time steps of {} is out of the range of durations", steps, time_step))),
+    };
+    return add_duration(start_time, duration);
+}
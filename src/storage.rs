@@ -0,0 +1,410 @@
+/// SQLite storage of simulation results for the Marine vessel simulator, only with the "storage" feature.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// Stores boats, simulations, the result and ship log of every simulated departure (run) and shipping statistics in one SQLite database, so the history of parameter sweeps can be queried instead of kept in a folder of CSV files.
+/// The schema is stable, new versions only add tables and columns. The schema version is stored in PRAGMA user_version, see SCHEMA_VERSION.
+/// Timestamps are stored as Unix time in milliseconds, durations in seconds and all other quantities in the units of the struct fields they come from, e.g. \[m\] and \[m/s\].
+/// The database can be read with any SQLite client, e.g. `SELECT boats.name, AVG(runs.duration) FROM runs JOIN simulations ON runs.simulation_id = simulations.id JOIN boats ON simulations.boat_id = boats.id GROUP BY boats.name;`
+
+use crate::*;   // To use everything from the crate
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// Version of the database schema, stored in PRAGMA user_version
+/// 1: First version. 2: Added ship_log_entries.cross_track_error. 3: Added ship_log_entries.foiling
+pub const SCHEMA_VERSION: i64 = 3;
+
+/// SQL that creates the tables of the database
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS boats (
+    id INTEGER PRIMARY KEY,
+    name TEXT,
+    imo INTEGER,
+    length REAL,
+    width REAL,
+    mass REAL,
+    velocity_mean REAL,
+    velocity_std REAL
+);
+CREATE TABLE IF NOT EXISTS simulations (
+    id INTEGER PRIMARY KEY,
+    boat_id INTEGER NOT NULL REFERENCES boats(id),
+    name TEXT NOT NULL,
+    simulation_method TEXT NOT NULL,
+    time_step REAL NOT NULL,
+    max_iterations INTEGER NOT NULL,
+    created INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    simulation_id INTEGER NOT NULL REFERENCES simulations(id),
+    departure INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    iterations INTEGER NOT NULL,
+    duration REAL NOT NULL,
+    distance REAL NOT NULL,
+    speed_mean REAL,
+    num_tacks INTEGER,
+    fuel REAL,
+    max_wind_speed REAL,
+    time_under_sail_percent REAL
+);
+CREATE TABLE IF NOT EXISTS ship_log_entries (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    timestamp INTEGER NOT NULL,
+    latitude_initial REAL NOT NULL,
+    longitude_initial REAL NOT NULL,
+    latitude REAL NOT NULL,
+    longitude REAL NOT NULL,
+    latitude_final REAL NOT NULL,
+    longitude_final REAL NOT NULL,
+    cargo_on_board REAL,
+    velocity REAL,
+    velocity_angle REAL,
+    course REAL,
+    heading REAL,
+    track_angle REAL,
+    true_bearing REAL,
+    draft REAL,
+    navigation_status INTEGER,
+    depth REAL,
+    wind_speed REAL,
+    wind_angle REAL,
+    wave_height REAL,
+    cross_track_error REAL,
+    foiling INTEGER
+);
+CREATE INDEX IF NOT EXISTS ship_log_entries_run_id ON ship_log_entries(run_id, timestamp);
+CREATE TABLE IF NOT EXISTS statistics (
+    id INTEGER PRIMARY KEY,
+    simulation_id INTEGER REFERENCES simulations(id),
+    name TEXT NOT NULL,
+    num_trips INTEGER NOT NULL,
+    speed_mean REAL,
+    speed_std REAL,
+    cargo_mean REAL,
+    cargo_std REAL,
+    travel_time_mean REAL,
+    travel_time_std REAL,
+    dist_mean REAL,
+    dist_std REAL,
+    route_efficiency_mean REAL,
+    route_efficiency_std REAL,
+    wind_speed_mean REAL,
+    wind_speed_max REAL,
+    wave_height_max REAL,
+    upwind_percent_mean REAL,
+    downwind_percent_mean REAL,
+    tacking_percent_mean REAL
+);
+CREATE TABLE IF NOT EXISTS statistics_metadata (
+    statistics_id INTEGER NOT NULL REFERENCES statistics(id),
+    key TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+";
+
+/// A simulated departure read from the database, see ResultDatabase.get_runs()
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredRun {
+    /// Id of the run, used to get its ship log with ResultDatabase.get_ship_log()
+    pub id: i64,
+    pub simulation_id: i64,
+    /// Name of the simulation the run belongs to
+    pub simulation_name: String,
+    /// Name of the boat that was simulated, if it has one
+    pub boat_name: Option<String>,
+    pub departure: UtcDateTime,
+    /// How the voyage ended, see SimulationStatus
+    pub status: String,
+    pub iterations: u64,
+    pub duration: time::Duration,
+    /// \[m\]
+    pub distance: f64,
+    /// \[m/s\]
+    pub speed_mean: Option<f64>,
+    pub num_tacks: Option<u64>,
+    /// \[kg\]
+    pub fuel: Option<f64>,
+    /// \[m/s\]
+    pub max_wind_speed: Option<f64>,
+    /// \[%\]
+    pub time_under_sail_percent: Option<f64>,
+}
+
+/// SQLite database of simulation results
+/// # Example:
+/// `let database = ResultDatabase::open("results.sqlite")?;`
+/// `let boat_id = database.insert_boat(&boat)?;`
+/// `let simulation_id = database.insert_simulation(boat_id, "Wind-assist 2025", &simulation)?;`
+/// `for departure in simulation.start_times.iter() {`
+/// `    let result = sim_waypoint_mission(&mut boat, *departure, &simulation)?;`
+/// `    database.insert_run(simulation_id, *departure, &result, &boat.ship_log)?;`
+/// `}`
+pub struct ResultDatabase {
+    connection: Connection,
+}
+
+impl ResultDatabase {
+    /// Opens a database file, or creates it if it does not exist, and creates the tables that are missing
+    /// Returns an error if the database was made by a newer version of the simulator, see SCHEMA_VERSION
+    pub fn open(file_path: &str) -> Result<ResultDatabase, MarineSimError> {
+        return ResultDatabase::from_connection(Connection::open(file_path)?);
+    }
+
+    /// Opens a database in memory, e.g. for tests. It is gone when the ResultDatabase is dropped
+    pub fn open_in_memory() -> Result<ResultDatabase, MarineSimError> {
+        return ResultDatabase::from_connection(Connection::open_in_memory()?);
+    }
+
+    /// Creates the tables and checks the schema version
+    fn from_connection(connection: Connection) -> Result<ResultDatabase, MarineSimError> {
+        let version: i64 = connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            return Err(MarineSimError::InvalidInput(format!("The database has schema version {} but this version of the simulator only knows schema version {}", version, SCHEMA_VERSION)));
+        }
+        connection.execute_batch(SCHEMA)?;
+        // Columns added since the tables of older databases were created
+        if version == 1 {
+            connection.execute_batch("ALTER TABLE ship_log_entries ADD COLUMN cross_track_error REAL;")?;
+        }
+        if (1..=2).contains(&version) {
+            connection.execute_batch("ALTER TABLE ship_log_entries ADD COLUMN foiling INTEGER;")?;
+        }
+        connection.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        connection.pragma_update(None, "foreign_keys", true)?;
+        return Ok(ResultDatabase {
+            connection,
+        });
+    }
+
+    /// The connection to the database, for queries that ResultDatabase does not have a function for
+    pub fn connection(&self) -> &Connection {
+        return &self.connection;
+    }
+
+    /// Stores a boat and returns its id
+    /// The name, IMO number, length \[m\], width \[m\], mass \[ton\] and velocity mean and std \[m/s\] are stored
+    pub fn insert_boat(&self, boat: &Boat) -> Result<i64, MarineSimError> {
+        self.connection.execute("INSERT INTO boats (name, imo, length, width, mass, velocity_mean, velocity_std) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)", params![
+            boat.name,
+            boat.imo,
+            boat.length.map(|length| length.get::<uom::si::length::meter>()),
+            boat.width.map(|width| width.get::<uom::si::length::meter>()),
+            boat.mass.map(|mass| mass.get::<uom::si::mass::ton>()),
+            boat.velocity_mean,
+            boat.velocity_std,
+        ])?;
+        return Ok(self.connection.last_insert_rowid());
+    }
+
+    /// Stores the settings of a simulation of a boat and returns its id. The simulation method, time step and max iterations are stored
+    /// name: Name of the simulation, e.g. the parameter values of a sweep
+    pub fn insert_simulation(&self, boat_id: i64, name: &str, simulation: &Simulation) -> Result<i64, MarineSimError> {
+        self.connection.execute("INSERT INTO simulations (boat_id, name, simulation_method, time_step, max_iterations, created) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", params![
+            boat_id,
+            name,
+            format!("{:?}", simulation.simulation_method),
+            simulation.time_step.as_seconds_f64(),
+            simulation.max_iterations as i64,
+            get_unix_milliseconds(UtcDateTime::now()),
+        ])?;
+        return Ok(self.connection.last_insert_rowid());
+    }
+
+    /// Stores the result and ship log of a simulated departure and returns the id of the run
+    /// Everything is stored in one transaction so a run is never stored without its ship log
+    pub fn insert_run(&self, simulation_id: i64, departure: UtcDateTime, result: &SimulationResult, ship_log: &[ShipLogEntry]) -> Result<i64, MarineSimError> {
+        let transaction = self.connection.unchecked_transaction()?;
+        transaction.execute("INSERT INTO runs (simulation_id, departure, status, iterations, duration, distance, speed_mean, num_tacks, fuel, max_wind_speed, time_under_sail_percent) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)", params![
+            simulation_id,
+            get_unix_milliseconds(departure),
+            format!("{:?}", result.status),
+            result.iterations as i64,
+            result.kpis.duration.as_seconds_f64(),
+            result.kpis.distance,
+            result.kpis.speed_mean,
+            result.kpis.num_tacks.map(|num_tacks| num_tacks as i64),
+            result.kpis.fuel,
+            result.kpis.max_wind_speed,
+            result.kpis.time_under_sail_percent,
+        ])?;
+        let run_id = transaction.last_insert_rowid();
+        {
+            let mut statement = transaction.prepare("INSERT INTO ship_log_entries VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)")?;
+            for entry in ship_log {
+                statement.execute(params![
+                    run_id,
+                    get_unix_milliseconds(entry.timestamp),
+                    entry.coordinates_initial.y(),
+                    entry.coordinates_initial.x(),
+                    entry.coordinates_current.y(),
+                    entry.coordinates_current.x(),
+                    entry.coordinates_final.y(),
+                    entry.coordinates_final.x(),
+                    entry.cargo_on_board.map(|cargo| cargo.get::<uom::si::mass::ton>()),
+                    entry.velocity.map(|velocity| velocity.magnitude),
+                    entry.velocity.map(|velocity| velocity.angle),
+                    entry.course,
+                    entry.heading,
+                    entry.track_angle,
+                    entry.true_bearing,
+                    entry.draft,
+                    entry.navigation_status.map(|status| status as i64),
+                    entry.depth,
+                    entry.wind.map(|wind| wind.magnitude),
+                    entry.wind.map(|wind| wind.angle),
+                    entry.wave_height,
+                    entry.cross_track_error,
+                    entry.foiling,
+                ])?;
+            }
+        }
+        transaction.commit()?;
+        return Ok(run_id);
+    }
+
+    /// Stores shipping statistics, including their metadata, and returns their id
+    /// simulation_id: The simulation the statistics were computed from, None e.g. for statistics of real ship logs
+    /// name: Name of the statistics, e.g. the name used with append_shipping_statistics_to_csv()
+    pub fn insert_statistics(&self, simulation_id: Option<i64>, name: &str, statistics: &ShippingStatistics) -> Result<i64, MarineSimError> {
+        let transaction = self.connection.unchecked_transaction()?;
+        transaction.execute("INSERT INTO statistics (simulation_id, name, num_trips, speed_mean, speed_std, cargo_mean, cargo_std, travel_time_mean, travel_time_std, dist_mean, dist_std, route_efficiency_mean, route_efficiency_std, wind_speed_mean, wind_speed_max, wave_height_max, upwind_percent_mean, downwind_percent_mean, tacking_percent_mean) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)", params![
+            simulation_id,
+            name,
+            statistics.num_trips as i64,
+            statistics.speed_mean,
+            statistics.speed_std,
+            statistics.cargo_mean,
+            statistics.cargo_std,
+            statistics.travel_time_mean.map(|duration| duration.as_seconds_f64()),
+            statistics.travel_time_std.map(|duration| duration.as_seconds_f64()),
+            statistics.dist_mean,
+            statistics.dist_std,
+            statistics.route_efficiency_mean,
+            statistics.route_efficiency_std,
+            statistics.wind_speed_mean,
+            statistics.wind_speed_max,
+            statistics.wave_height_max,
+            statistics.upwind_percent_mean,
+            statistics.downwind_percent_mean,
+            statistics.tacking_percent_mean,
+        ])?;
+        let statistics_id = transaction.last_insert_rowid();
+        for (key, value) in statistics.metadata.iter() {
+            transaction.execute("INSERT INTO statistics_metadata (statistics_id, key, value) VALUES (?1, ?2, ?3)", params![statistics_id, key, value])?;
+        }
+        transaction.commit()?;
+        return Ok(statistics_id);
+    }
+
+    /// Returns the stored runs in the order they were stored
+    /// simulation_id: Only the runs of this simulation, or all runs if None
+    pub fn get_runs(&self, simulation_id: Option<i64>) -> Result<Vec<StoredRun>, MarineSimError> {
+        let mut statement = self.connection.prepare("SELECT runs.id, runs.simulation_id, simulations.name, boats.name, runs.departure, runs.status, runs.iterations, runs.duration, runs.distance, runs.speed_mean, runs.num_tacks, runs.fuel, runs.max_wind_speed, runs.time_under_sail_percent
+            FROM runs JOIN simulations ON runs.simulation_id = simulations.id JOIN boats ON simulations.boat_id = boats.id
+            WHERE ?1 IS NULL OR runs.simulation_id = ?1 ORDER BY runs.id")?;
+        let runs = statement.query_map(params![simulation_id], |row| {
+            Ok(StoredRun {
+                id: row.get(0)?,
+                simulation_id: row.get(1)?,
+                simulation_name: row.get(2)?,
+                boat_name: row.get(3)?,
+                departure: get_utc_date_time(row.get(4)?),
+                status: row.get(5)?,
+                iterations: row.get::<_, i64>(6)? as u64,
+                duration: time::Duration::seconds_f64(row.get(7)?),
+                distance: row.get(8)?,
+                speed_mean: row.get(9)?,
+                num_tacks: row.get::<_, Option<i64>>(10)?.map(|num_tacks| num_tacks as u64),
+                fuel: row.get(11)?,
+                max_wind_speed: row.get(12)?,
+                time_under_sail_percent: row.get(13)?,
+            })
+        })?.collect::<Result<Vec<StoredRun>, rusqlite::Error>>()?;
+        return Ok(runs);
+    }
+
+    /// Returns the ship log of a stored run in chronological order
+    /// Returns an error if there is no run with the id
+    pub fn get_ship_log(&self, run_id: i64) -> Result<Vec<ShipLogEntry>, MarineSimError> {
+        let run_exists = self.connection.query_row("SELECT id FROM runs WHERE id = ?1", params![run_id], |row| row.get::<_, i64>(0)).optional()?.is_some();
+        if !run_exists {
+            return Err(MarineSimError::InvalidInput(format!("No run with id {} in the database", run_id)));
+        }
+        let mut statement = self.connection.prepare("SELECT timestamp, latitude_initial, longitude_initial, latitude, longitude, latitude_final, longitude_final, cargo_on_board, velocity, velocity_angle, course, heading, track_angle, true_bearing, draft, navigation_status, depth, wind_speed, wind_angle, wave_height, cross_track_error, foiling
+            FROM ship_log_entries WHERE run_id = ?1 ORDER BY timestamp")?;
+        let ship_log = statement.query_map(params![run_id], |row| {
+            let point = |latitude: f64, longitude: f64| geo::Point::new(longitude, latitude);
+            let vector = |magnitude: Option<f64>, angle: Option<f64>| magnitude.zip(angle).map(|(magnitude, angle)| PhysVec::new(magnitude, angle));
+            Ok(ShipLogEntry {
+                timestamp: get_utc_date_time(row.get(0)?),
+                coordinates_initial: point(row.get(1)?, row.get(2)?),
+                coordinates_current: point(row.get(3)?, row.get(4)?),
+                coordinates_final: point(row.get(5)?, row.get(6)?),
+                cargo_on_board: row.get::<_, Option<f64>>(7)?.map(|cargo| uom::si::f64::Mass::new::<uom::si::mass::ton>(cargo)),
+                velocity: vector(row.get(8)?, row.get(9)?),
+                course: row.get(10)?,
+                heading: row.get(11)?,
+                track_angle: row.get(12)?,
+                true_bearing: row.get(13)?,
+                draft: row.get(14)?,
+                navigation_status: row.get::<_, Option<u8>>(15)?.and_then(|status| NavigationStatus::try_from(status).ok()),
+                depth: row.get(16)?,
+                wind: vector(row.get(17)?, row.get(18)?),
+                wave_height: row.get(19)?,
+                cross_track_error: row.get(20)?,
+                foiling: row.get(21)?,
+            })
+        })?.collect::<Result<Vec<ShipLogEntry>, rusqlite::Error>>()?;
+        return Ok(ship_log);
+    }
+
+    /// Returns the stored shipping statistics with their names, in the order they were stored
+    /// simulation_id: Only the statistics of this simulation, or all statistics if None
+    pub fn get_statistics(&self, simulation_id: Option<i64>) -> Result<Vec<(String, ShippingStatistics)>, MarineSimError> {
+        let mut statement = self.connection.prepare("SELECT id, name, num_trips, speed_mean, speed_std, cargo_mean, cargo_std, travel_time_mean, travel_time_std, dist_mean, dist_std, route_efficiency_mean, route_efficiency_std, wind_speed_mean, wind_speed_max, wave_height_max, upwind_percent_mean, downwind_percent_mean, tacking_percent_mean
+            FROM statistics WHERE ?1 IS NULL OR simulation_id = ?1 ORDER BY id")?;
+        let rows = statement.query_map(params![simulation_id], |row| {
+            let duration = |seconds: Option<f64>| seconds.map(time::Duration::seconds_f64);
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, ShippingStatistics {
+                num_trips: row.get::<_, i64>(2)? as u64,
+                speed_mean: row.get(3)?,
+                speed_std: row.get(4)?,
+                cargo_mean: row.get(5)?,
+                cargo_std: row.get(6)?,
+                travel_time_mean: duration(row.get(7)?),
+                travel_time_std: duration(row.get(8)?),
+                dist_mean: row.get(9)?,
+                dist_std: row.get(10)?,
+                route_efficiency_mean: row.get(11)?,
+                route_efficiency_std: row.get(12)?,
+                wind_speed_mean: row.get(13)?,
+                wind_speed_max: row.get(14)?,
+                wave_height_max: row.get(15)?,
+                upwind_percent_mean: row.get(16)?,
+                downwind_percent_mean: row.get(17)?,
+                tacking_percent_mean: row.get(18)?,
+                ..Default::default()
+            }))
+        })?.collect::<Result<Vec<(i64, String, ShippingStatistics)>, rusqlite::Error>>()?;
+
+        // Add the metadata
+        let mut metadata_statement = self.connection.prepare("SELECT key, value FROM statistics_metadata WHERE statistics_id = ?1 ORDER BY rowid")?;
+        let mut statistics_vec: Vec<(String, ShippingStatistics)> = Vec::with_capacity(rows.len());
+        for (id, name, mut statistics) in rows {
+            statistics.metadata = metadata_statement.query_map(params![id], |row| Ok((row.get(0)?, row.get(1)?)))?.collect::<Result<Vec<(String, String)>, rusqlite::Error>>()?;
+            statistics_vec.push((name, statistics));
+        }
+        return Ok(statistics_vec);
+    }
+}
+
+/// Returns the Unix time of a timestamp in milliseconds
+fn get_unix_milliseconds(timestamp: UtcDateTime) -> i64 {
+    return (timestamp.unix_timestamp_nanos() / 1_000_000) as i64;
+}
+
+/// Returns the timestamp of a Unix time in milliseconds, or the Unix epoch if the time is out of range
+fn get_utc_date_time(milliseconds: i64) -> UtcDateTime {
+    return UtcDateTime::from_unix_timestamp_nanos(milliseconds as i128 * 1_000_000).unwrap_or(UtcDateTime::UNIX_EPOCH);
+}
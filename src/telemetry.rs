@@ -0,0 +1,269 @@
+/// Live telemetry publishing for the Marine vessel simulator.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// Publishes each ship log entry as a Signal K delta, so the simulator can drive dashboards and shore monitoring software as a synthetic vessel.
+/// The deltas are sent as newline separated JSON over a stream, e.g. to the TCP interface of a Signal K server, or as MQTT messages to a broker.
+/// The entries can be published in simulated time (all at once), real time or accelerated real time, see TelemetryPublisher.time_scale
+/// Signal K values are in SI units, angles in radians from true north. Paths:
+/// - navigation.position: latitude and longitude in degrees
+/// - navigation.speedOverGround and navigation.courseOverGroundTrue
+/// - navigation.headingTrue
+/// - navigation.state, from the navigation status
+/// - navigation.courseGreatCircle.crossTrackError
+/// - environment.wind.speedTrue and environment.wind.directionTrue, where the wind comes from
+/// - environment.depth.belowSurface
+/// Values are left out if the ship log entry does not have the data for them
+/// See: <https://signalk.org/specification/1.7.0/doc/data_model.html> and <https://docs.oasis-open.org/mqtt/mqtt/v3.1.1/mqtt-v3.1.1.html>
+
+use crate::*;   // To use everything from the crate
+use std::io::{Read, Write};
+
+/// Returns a ship log entry as a Signal K delta in JSON, without a line ending
+/// context: The vessel the delta is about, e.g. "vessels.self" or "vessels.urn:mrn:imo:mmsi:123456789"
+/// previous: The previous entry of the ship log, used for the speed and course over ground if the entry has no velocity. None for the first entry
+/// # Example:
+/// `println!("{}", get_signalk_delta(&boat.ship_log[1], Some(&boat.ship_log[0]), "vessels.self"));`
+pub fn get_signalk_delta(entry: &ShipLogEntry, previous: Option<&ShipLogEntry>, context: &str) -> String {
+    let mut values: Vec<String> = Vec::new();
+    let mut push_value = |path: &str, value: String| values.push(format!("{{\"path\":\"{}\",\"value\":{}}}", path, value));
+
+    push_value("navigation.position", format!("{{\"latitude\":{},\"longitude\":{}}}", entry.coordinates_current.y(), entry.coordinates_current.x()));
+
+    // Speed and course over ground, unit [m/s] and [rad]
+    let (speed_over_ground, course_over_ground): (Option<f64>, Option<f64>) = match (entry.velocity, previous) {
+        (Some(velocity), _) => (Some(velocity.magnitude), Some(velocity.angle)),
+        (None, Some(previous)) => {
+            let seconds = (entry.timestamp - previous.timestamp).as_seconds_f64();
+            let speed = if seconds > 0.0 { Some(Earth.distance(previous.coordinates_current, entry.coordinates_current) / seconds) } else { None };
+            (speed, entry.track_angle.or(Some(Earth.bearing(previous.coordinates_current, entry.coordinates_current))))
+        },
+        (None, None) => (None, entry.track_angle),
+    };
+    if let Some(speed) = speed_over_ground {
+        push_value("navigation.speedOverGround", speed.to_string());
+    }
+    if let Some(course) = course_over_ground {
+        push_value("navigation.courseOverGroundTrue", course.rem_euclid(360.0).to_radians().to_string());
+    }
+    if let Some(heading) = entry.heading {
+        push_value("navigation.headingTrue", heading.rem_euclid(360.0).to_radians().to_string());
+    }
+    if let Some(state) = entry.navigation_status.and_then(get_signalk_navigation_state) {
+        push_value("navigation.state", format!("\"{}\"", state));
+    }
+    if let Some(cross_track_error) = entry.cross_track_error {
+        push_value("navigation.courseGreatCircle.crossTrackError", cross_track_error.to_string());
+    }
+    if let Some(wind) = entry.wind {
+        push_value("environment.wind.speedTrue", wind.magnitude.to_string());
+        push_value("environment.wind.directionTrue", wind.angle.rem_euclid(360.0).to_radians().to_string());
+    }
+    if let Some(depth) = entry.depth {
+        push_value("environment.depth.belowSurface", depth.to_string());
+    }
+
+    let timestamp = entry.timestamp;
+    return format!("{{\"context\":\"{}\",\"updates\":[{{\"source\":{{\"label\":\"marine_vessel_simulator\"}},\"timestamp\":\"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z\",\"values\":[{}]}}]}}",
+        context, timestamp.year(), timestamp.month() as u8, timestamp.day(), timestamp.hour(), timestamp.minute(), timestamp.second(), timestamp.millisecond(), values.join(","));
+}
+
+/// Returns the Signal K navigation.state of a navigation status, None if Signal K has no state for it
+fn get_signalk_navigation_state(navigation_status: NavigationStatus) -> Option<&'static str> {
+    return match navigation_status {
+        NavigationStatus::UnderwayUsingEngine => Some("motoring"),
+        NavigationStatus::AtAnchor => Some("anchored"),
+        NavigationStatus::NotUnderCommand => Some("not under command"),
+        NavigationStatus::RestrictedManeuverability => Some("restricted manouverability"),
+        NavigationStatus::ConstrainedByDraft => Some("constrained by draft"),
+        NavigationStatus::Moored => Some("moored"),
+        NavigationStatus::Aground => Some("aground"),
+        NavigationStatus::EngagedInFishing => Some("fishing"),
+        NavigationStatus::UnderwaySailing => Some("sailing"),
+        NavigationStatus::ActiveAisSart => Some("ais-sart"),
+        NavigationStatus::NotDefinedOrDefault => Some("default"),
+        _ => None,
+    };
+}
+
+/// How a TelemetryPublisher sends the Signal K deltas
+#[derive(Debug, Clone, PartialEq)]
+pub enum TelemetryTransport {
+    /// Newline separated JSON, e.g. over TCP to a Signal K server
+    SignalK,
+    /// MQTT 3.1.1 PUBLISH messages with quality of service 0 on the topic, one delta per message
+    Mqtt { topic: String },
+}
+
+/// Publishes ship log entries as Signal K deltas, see get_signalk_delta()
+/// Can publish a finished ship log, stream the ship logs of a simulation as a ship log writer or publish each entry live while the simulation runs as a ship log hook
+/// # Example - Stream a simulation to a Signal K server, an hour of the voyage per minute:
+/// `let publisher = TelemetryPublisher::signalk_tcp("localhost:8375", "vessels.self", Some(60.0))?;`
+/// `boat.set_ship_log_hook(publisher.into_ship_log_hook());`
+/// `sim_waypoint_missions(&mut boat, &simulation)?;`
+/// # Example - Publish a ship log to an MQTT broker:
+/// `let mut publisher = TelemetryPublisher::mqtt("localhost:1883", "simulator", "vessels/self/delta", "vessels.self", None)?;`
+/// `publisher.write_ship_log(&boat.ship_log)?;`
+/// `publisher.finish()?;`
+pub struct TelemetryPublisher {
+    writer: Box<dyn Write + Send>,
+    /// How the deltas are sent
+    pub transport: TelemetryTransport,
+    /// The vessel the deltas are about, e.g. "vessels.self"
+    pub context: String,
+    /// If set, the entries are published in real time divided by the time scale, e.g. 1.0 for real time and 60.0 to publish an hour of the voyage in a minute
+    /// If None, the entries are published as fast as they come
+    pub time_scale: Option<f64>,
+    /// The last entry that was published
+    previous: Option<ShipLogEntry>,
+}
+
+impl TelemetryPublisher {
+    /// Creates a publisher that writes newline separated Signal K deltas to a writer, e.g. a file or a TCP stream
+    pub fn signalk<W: Write + Send + 'static>(writer: W, context: &str, time_scale: Option<f64>) -> Result<TelemetryPublisher, MarineSimError> {
+        return TelemetryPublisher::new(Box::new(writer), TelemetryTransport::SignalK, context, time_scale);
+    }
+
+    /// Connects to the TCP interface of a Signal K server, e.g. "localhost:8375", and creates a publisher that sends the deltas to it
+    pub fn signalk_tcp(address: &str, context: &str, time_scale: Option<f64>) -> Result<TelemetryPublisher, MarineSimError> {
+        let stream = std::net::TcpStream::connect(address)?;
+        return TelemetryPublisher::signalk(stream, context, time_scale);
+    }
+
+    /// Connects to an MQTT broker, e.g. "localhost:1883", and creates a publisher that publishes each delta as a message on the topic
+    /// The connection has no keep alive, so the broker does not disconnect the publisher however long it is between entries
+    /// client_id: The MQTT client identifier, must be unique on the broker
+    pub fn mqtt(address: &str, client_id: &str, topic: &str, context: &str, time_scale: Option<f64>) -> Result<TelemetryPublisher, MarineSimError> {
+        let mut stream = std::net::TcpStream::connect(address)?;
+
+        // CONNECT with a clean session and the keep alive turned off
+        // The publisher only sends when an entry is published, which can be minutes apart in real time or while the simulation fetches weather data, and a broker disconnects clients that are silent for 1.5 times the keep alive
+        let mut variable_header_and_payload: Vec<u8> = Vec::new();
+        push_mqtt_string(&mut variable_header_and_payload, "MQTT")?;
+        variable_header_and_payload.extend_from_slice(&[4, 0x02, 0, 0]);
+        push_mqtt_string(&mut variable_header_and_payload, client_id)?;
+        stream.write_all(&get_mqtt_packet(0x10, &variable_header_and_payload)?)?;
+
+        // Wait for CONNACK
+        let mut connack = [0u8; 4];
+        stream.read_exact(&mut connack)?;
+        if connack[0] != 0x20 || connack[1] != 2 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "MQTT broker did not answer with CONNACK").into());
+        }
+        if connack[3] != 0 {
+            return Err(io::Error::new(io::ErrorKind::ConnectionRefused, format!("MQTT broker refused the connection with return code {}", connack[3])).into());
+        }
+
+        return TelemetryPublisher::new(Box::new(stream), TelemetryTransport::Mqtt { topic: topic.to_string() }, context, time_scale);
+    }
+
+    /// Creates a publisher, checks the time scale
+    fn new(writer: Box<dyn Write + Send>, transport: TelemetryTransport, context: &str, time_scale: Option<f64>) -> Result<TelemetryPublisher, MarineSimError> {
+        if let Some(time_scale) = time_scale {
+            if !(time_scale > 0.0) {
+                return Err(MarineSimError::InvalidInput(format!("The time scale must be positive, it is {}", time_scale)));
+            }
+        }
+        return Ok(TelemetryPublisher {
+            writer,
+            transport,
+            context: context.to_string(),
+            time_scale,
+            previous: None,
+        });
+    }
+
+    /// Publishes a ship log entry, first waiting for the time since the previous entry divided by the time scale if a time scale is set
+    pub fn publish(&mut self, entry: &ShipLogEntry) -> Result<(), MarineSimError> {
+        // Wait until the time of the entry
+        if let (Some(time_scale), Some(previous)) = (self.time_scale, &self.previous) {
+            let seconds = (entry.timestamp - previous.timestamp).as_seconds_f64() / time_scale;
+            if seconds > 0.0 {
+                std::thread::sleep(std::time::Duration::from_secs_f64(seconds));
+            }
+        }
+
+        let delta = get_signalk_delta(entry, self.previous.as_ref(), &self.context);
+        match &self.transport {
+            TelemetryTransport::SignalK => {
+                self.writer.write_all(delta.as_bytes())?;
+                self.writer.write_all(b"\n")?;
+            }
+            TelemetryTransport::Mqtt { topic } => {
+                let mut variable_header_and_payload: Vec<u8> = Vec::with_capacity(topic.len() + delta.len() + 2);
+                push_mqtt_string(&mut variable_header_and_payload, topic)?;
+                variable_header_and_payload.extend_from_slice(delta.as_bytes());
+                self.writer.write_all(&get_mqtt_packet(0x30, &variable_header_and_payload)?)?;
+            }
+        }
+        self.writer.flush()?;
+        self.previous = Some(entry.clone());
+        return Ok(());
+    }
+
+    /// Turns the publisher into a ship log hook that publishes each entry while the simulation runs, see Boat.set_ship_log_hook()
+    /// With a time scale the simulation runs no faster than the time scale, so the vessel moves on the dashboard as it would at sea
+    /// Errors are logged as warnings since the hook can not return them
+    pub fn into_ship_log_hook(self) -> impl Fn(&ShipLogEntry) + Send + Sync + 'static {
+        let publisher = std::sync::Mutex::new(self);
+        return move |entry: &ShipLogEntry| {
+            if let Err(e) = publisher.lock().unwrap().publish(entry) {
+                tracing::warn!(error = %e, "Could not publish ship log entry");
+            }
+        };
+    }
+}
+
+impl ShipLogWriter for TelemetryPublisher {
+    /// Publishes the ship log of a voyage, see TelemetryPublisher.publish()
+    fn write_ship_log(&mut self, ship_log: &[ShipLogEntry]) -> Result<(), io::Error> {
+        for entry in ship_log {
+            self.publish(entry)?;
+        }
+        // The next voyage does not continue from this one
+        self.previous = None;
+        return Ok(());
+    }
+
+    /// Disconnects from the MQTT broker, if publishing with MQTT
+    fn finish(&mut self) -> Result<(), io::Error> {
+        if let TelemetryTransport::Mqtt { .. } = self.transport {
+            self.writer.write_all(&[0xE0, 0])?;
+        }
+        return self.writer.flush();
+    }
+}
+
+/// Adds a UTF-8 string with its two byte length to an MQTT packet
+fn push_mqtt_string(packet: &mut Vec<u8>, string: &str) -> Result<(), MarineSimError> {
+    let length: u16 = match string.len().try_into() {
+        Ok(length) => length,
+        Err(_) => return Err(MarineSimError::InvalidInput(format!("MQTT strings can not be longer than 65535 bytes, the string is {} bytes", string.len()))),
+    };
+    packet.extend_from_slice(&length.to_be_bytes());
+    packet.extend_from_slice(string.as_bytes());
+    return Ok(());
+}
+
+/// Returns an MQTT packet with the first byte of the fixed header, the remaining length and the rest of the packet
+fn get_mqtt_packet(first_byte: u8, rest: &[u8]) -> Result<Vec<u8>, MarineSimError> {
+    // The remaining length is encoded with 7 bits per byte, at most 4 bytes
+    if rest.len() > 268_435_455 {
+        return Err(MarineSimError::InvalidInput(format!("MQTT packets can not be longer than 268435455 bytes, the packet is {} bytes", rest.len())));
+    }
+    let mut packet: Vec<u8> = Vec::with_capacity(rest.len() + 5);
+    packet.push(first_byte);
+    let mut remaining_length = rest.len();
+    loop {
+        let mut byte = (remaining_length % 128) as u8;
+        remaining_length /= 128;
+        if remaining_length > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if remaining_length == 0 {
+            break;
+        }
+    }
+    packet.extend_from_slice(rest);
+    return Ok(packet);
+}
@@ -4,6 +4,9 @@
 
 use crate::*;   // To use everything from the crate
 
+/// \[kg/m^3\]. Density of sea water, used to get the draft of a vessel from its displacement
+const SEAWATER_DENSITY: f64 = 1025.0;
+
 // Structs and Enums
 //----------------------------------------------------
 /// Struct to hold sailing leg data
@@ -11,19 +14,648 @@ use crate::*;   // To use everything from the crate
 /// p2: End point of the leg
 /// tacking_width: Width of the tacking zone around the leg line. The boat will try to stay within this zone when sailing the leg. The width will have the line between p1 and p2 in the middle of the tacking zone.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SailingLeg {
     pub p1: geo::Point,
     pub p2: geo::Point,
     /// Tacking width in \[m\]
     pub tacking_width: f64,
     /// The minimum proximity in \[m\] to p2 to consider the vessel "at p2"
-    pub min_proximity: f64
+    pub min_proximity: f64,
+    /// Port call at p2, if the vessel stops there to load or unload cargo. None if the vessel sails on
+    pub port_call: Option<PortCall>,
+    /// Wait at anchor or moored at p2, after the port call if there is one, e.g. until a tide or for the weather to improve. None if the vessel sails on
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub anchor_wait: Option<AnchorWait>,
+    /// Lane of a traffic separation scheme the leg follows or crosses, None if the leg is outside of traffic lanes
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub traffic_lane: Option<TrafficLane>,
+}
+
+impl SailingLeg {
+    /// Creates a new SailingLeg from p1 to p2
+    pub fn new(p1: geo::Point, p2: geo::Point, tacking_width: f64, min_proximity: f64) -> SailingLeg {
+        SailingLeg {
+            p1,
+            p2,
+            tacking_width,
+            min_proximity,
+            port_call: None,
+            anchor_wait: None,
+            traffic_lane: None,
+        }
+    }
+
+    /// Returns the great circle length of the leg in \[m\]
+    pub fn length(&self) -> f64 {
+        return Earth.distance(self.p1, self.p2);
+    }
+
+    /// Returns the point at a fraction of the way along the great circle from p1 to p2
+    /// fraction: 0.0 is p1 and 1.0 is p2, values outside of \[0, 1\] are clamped
+    pub fn point_at(&self, fraction: f64) -> geo::Point {
+        let fraction = fraction.clamp(0.0, 1.0);
+        // Check end points explicitly to avoid floating point errors
+        if fraction == 0.0 {
+            return self.p1;
+        }
+        if fraction == 1.0 {
+            return self.p2;
+        }
+        // Travel along the initial bearing, stays on the great circle between p1 and p2
+        return Earth.destination(self.p1, Earth.bearing(self.p1, self.p2), fraction*self.length());
+    }
+
+    /// Returns the great circle bearing in degrees at a fraction of the way from p1 to p2. North: 0°, East: 90°, South: 180°, West: 270°
+    /// Note that the bearing changes along a great circle, bearing_at(0.0) is the initial bearing and bearing_at(1.0) is the final bearing
+    /// fraction: 0.0 is p1 and 1.0 is p2, values outside of \[0, 1\] are clamped
+    pub fn bearing_at(&self, fraction: f64) -> f64 {
+        let fraction = fraction.clamp(0.0, 1.0);
+        // At p2 there is no bearing to p2, use the reverse of the bearing from p2 to p1 instead
+        if fraction == 1.0 {
+            return (Earth.bearing(self.p2, self.p1) + 180.0) % 360.0;
+        }
+        return Earth.bearing(self.point_at(fraction), self.p2);
+    }
+
+    /// Returns the signed cross track distance in \[m\] from the great circle through p1 and p2 to a point
+    /// Positive if the point is to the right (starboard) of the leg when going from p1 to p2, negative if to the left (port)
+    /// Use the absolute value to compare against half of the tacking width, see also get_min_point_to_great_circle_dist()
+    pub fn cross_track_distance(&self, point: geo::Point) -> f64 {
+        // Radius of sphere (Earth)
+        let r = Earth.radius();
+        // Angular distance from p1 to point
+        let angular_dist = Earth.distance(self.p1, point) / r;
+        // Angle between the leg and the line from p1 to point, in radians
+        let angle = (Earth.bearing(self.p1, point) - Earth.bearing(self.p1, self.p2)) * consts::PI/180.0;
+        return r*(angular_dist.sin() * angle.sin()).asin();
+    }
+
+    /// Returns the signed along track distance in \[m\] from p1 to the point on the great circle through p1 and p2 that is closest to a point
+    /// Negative if the closest point is behind p1 and more than the length of the leg if it is past p2, see get_min_point_to_segment_dist()
+    pub fn along_track_distance(&self, point: geo::Point) -> f64 {
+        // Radius of sphere (Earth)
+        let r = Earth.radius();
+        // Angular distance from p1 to point
+        let angular_dist = Earth.distance(self.p1, point) / r;
+        // Angle between the leg and the line from p1 to point, in radians
+        let angle = (Earth.bearing(self.p1, point) - Earth.bearing(self.p1, self.p2)) * consts::PI/180.0;
+        // Angular cross track distance, then the along track distance from the spherical law of cosines
+        let cross_track = (angular_dist.sin() * angle.sin()).asin();
+        let along_track = r*(angular_dist.cos() / cross_track.cos()).clamp(-1.0, 1.0).acos();
+        // More than 90° off the leg means the point is behind p1
+        if angle.cos() < 0.0 {
+            return -along_track;
+        }
+        return along_track;
+    }
+}
+
+/// Struct for a port call at the end of a leg, see SailingLeg.port_call
+/// At the port call the cargo is unloaded and loaded, the draft updated (see Boat.tons_per_cm_immersion) and the vessel waits in port before sailing on
+/// cargo_loaded and cargo_unloaded only change Boat.cargo_current, cargo by type in Boat.cargo is loaded and unloaded with cargo_load and cargo_unload
+/// A port call with untyped cargo is an error for a vessel with cargo by type on board, as get_cargo_statistics() would no longer match the cargo on board
+/// # Example:
+/// Unload all the containers and load bulk cargo
+/// `leg.port_call = Some(PortCall::new(0.0, 0.0, time::Duration::hours(12)).with_cargo_unload(CargoType::Containers).with_cargo_load(Cargo::Bulk { mass: uom::si::f64::Mass::new::<uom::si::mass::ton>(800.0) }));`
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortCall {
+    /// Cargo loaded at the port
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::mass"))]
+    pub cargo_loaded: uom::si::f64::Mass,
+    /// Cargo unloaded at the port
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::mass"))]
+    pub cargo_unloaded: uom::si::f64::Mass,
+    /// Time spent in port
+    pub wait: time::Duration,
+    /// If true the fuel and fresh water tanks of the vessel are filled at the port, see Tank
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub refill_tanks: bool,
+    /// Cargo by type loaded at the port, added to Boat.cargo, see Boat.add_cargo()
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cargo_load: Option<Cargo>,
+    /// Type of cargo unloaded at the port, all the cargo of the type in Boat.cargo is unloaded before any cargo is loaded, see Boat.unload_cargo_type()
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cargo_unload: Option<CargoType>,
+}
+
+impl PortCall {
+    /// Creates a new port call, the cargo is in metric tons
+    pub fn new(cargo_loaded_tons: f64, cargo_unloaded_tons: f64, wait: time::Duration) -> PortCall {
+        PortCall {
+            cargo_loaded: uom::si::f64::Mass::new::<uom::si::mass::ton>(cargo_loaded_tons),
+            cargo_unloaded: uom::si::f64::Mass::new::<uom::si::mass::ton>(cargo_unloaded_tons),
+            wait,
+            refill_tanks: false,
+            cargo_load: None,
+            cargo_unload: None,
+        }
+    }
+
+    /// Creates a port call that only fills the tanks of the vessel, see Bunkering
+    pub fn bunkering(wait: time::Duration) -> PortCall {
+        let mut port_call = PortCall::new(0.0, 0.0, wait);
+        port_call.refill_tanks = true;
+        return port_call;
+    }
+
+    /// Sets the cargo by type loaded at the port
+    pub fn with_cargo_load(mut self, cargo: Cargo) -> PortCall {
+        self.cargo_load = Some(cargo);
+        return self;
+    }
+
+    /// Sets the type of cargo unloaded at the port
+    pub fn with_cargo_unload(mut self, cargo_type: CargoType) -> PortCall {
+        self.cargo_unload = Some(cargo_type);
+        return self;
+    }
+}
+
+/// Struct for a timed wait at the end of a leg, at anchor or moored, see SailingLeg.anchor_wait
+/// The vessel waits at least until the given time and, if max_wind_speed is set, until the wind has dropped to it, but never longer than max_wait
+/// While waiting the navigation status is AtAnchor, or Moored, and a vessel at anchor lies downstream of its anchor at the swing radius, pushed by the ocean current and the wind, see get_drift_velocity()
+/// # Example:
+/// Anchor until the wind drops to 12 m/s, for at most 2 days
+/// `leg.anchor_wait = Some(AnchorWait::for_weather(12.0, time::Duration::days(2)));`
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AnchorWait {
+    /// Wait at least until this time, None to only wait for the weather
+    pub until: Option<UtcDateTime>,
+    /// \[m/s\]. Wait until the wind is at or below this speed, None to only wait until the time. Needs the weather of the simulation
+    pub max_wind_speed: Option<f64>,
+    /// Longest time to wait, from the arrival at the waypoint
+    /// The wait also ends when the voyage reaches simulation.max_simulated_duration or the wait has taken simulation.max_iterations time steps
+    pub max_wait: time::Duration,
+    /// If true the vessel is moored, e.g. to a buoy or a quay, and holds its position, otherwise it lies at anchor
+    pub moored: bool,
+    /// \[m\]. How far from its anchor the vessel swings with the current and wind
+    pub swing_radius: f64,
+}
+
+impl AnchorWait {
+    /// Default swing radius in \[m\], about the length of anchor chain a small vessel lets out
+    pub const DEFAULT_SWING_RADIUS: f64 = 50.0;
+    /// Default longest time to wait, 30 days
+    pub const DEFAULT_MAX_WAIT: time::Duration = time::Duration::days(30);
+
+    /// Creates a wait at anchor until a time, for at most AnchorWait::DEFAULT_MAX_WAIT
+    pub fn until(until: UtcDateTime) -> AnchorWait {
+        AnchorWait {
+            until: Some(until),
+            max_wind_speed: None,
+            max_wait: AnchorWait::DEFAULT_MAX_WAIT,
+            moored: false,
+            swing_radius: AnchorWait::DEFAULT_SWING_RADIUS,
+        }
+    }
+
+    /// Creates a wait at anchor until the wind is at or below max_wind_speed in \[m/s\], for at most max_wait
+    pub fn for_weather(max_wind_speed: f64, max_wait: time::Duration) -> AnchorWait {
+        AnchorWait {
+            until: None,
+            max_wind_speed: Some(max_wind_speed),
+            max_wait,
+            moored: false,
+            swing_radius: AnchorWait::DEFAULT_SWING_RADIUS,
+        }
+    }
+
+    /// Returns the navigation status of the vessel while it waits
+    pub fn navigation_status(&self) -> NavigationStatus {
+        return match self.moored {
+            true => NavigationStatus::Moored,
+            false => NavigationStatus::AtAnchor,
+        };
+    }
+}
+
+/// How a leg uses a traffic lane, see TrafficLane
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TrafficLaneKind {
+    /// The leg follows the lane in its direction of traffic
+    Follow,
+    /// The leg crosses the lane at right angles to its direction of traffic
+    Cross,
+}
+
+/// Struct for a lane of a traffic separation scheme (TSS) on a leg, see SailingLeg.traffic_lane
+/// Vessels in a lane follow its direction of traffic and vessels crossing it do so on a heading as near to right angles to the direction of traffic as possible, see COLREG rule 10
+/// Tacking is not allowed inside a lane, so the boat holds its tack on legs with a traffic lane and steers the heading the lane mandates, see TrafficLane.heading()
+/// Note: A sailing vessel can not beat up a lane against the wind without tacking, use the engine or plan the legs around the lane instead
+/// # Example:
+/// Cross the Dover Strait TSS, where the south-west lane runs 212°
+/// `leg.traffic_lane = Some(TrafficLane::cross(212.0));`
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrafficLane {
+    pub kind: TrafficLaneKind,
+    /// Direction of traffic in the lane in degrees. North: 0°, East: 90°, South: 180°, West: 270°
+    pub direction: f64,
+    /// Largest allowed difference in degrees between the bearing of the leg and the direction of traffic, or the right angle to it when crossing
+    pub tolerance: f64,
+}
+
+impl TrafficLane {
+    /// Default tolerance in degrees
+    pub const DEFAULT_TOLERANCE: f64 = 15.0;
+
+    /// Creates a lane the leg follows in the direction of traffic, in degrees
+    pub fn follow(direction: f64) -> TrafficLane {
+        TrafficLane {
+            kind: TrafficLaneKind::Follow,
+            direction: direction.rem_euclid(360.0),
+            tolerance: TrafficLane::DEFAULT_TOLERANCE,
+        }
+    }
+
+    /// Creates a lane the leg crosses, the direction of traffic is in degrees
+    pub fn cross(direction: f64) -> TrafficLane {
+        TrafficLane {
+            kind: TrafficLaneKind::Cross,
+            direction: direction.rem_euclid(360.0),
+            tolerance: TrafficLane::DEFAULT_TOLERANCE,
+        }
+    }
+
+    /// Returns the heading in degrees the lane mandates for a vessel that wants to go in the direction of bearing
+    /// Following the lane the bearing is kept if it is within the tolerance of the direction of traffic, otherwise it is turned to the edge of the tolerance
+    /// Crossing the lane the heading is at right angles to the direction of traffic, to the side of the bearing
+    pub fn heading(&self, bearing: f64) -> f64 {
+        let off: f64 = get_angle_difference(bearing, self.direction);
+        return match self.kind {
+            TrafficLaneKind::Follow => (self.direction + off.clamp(-self.tolerance, self.tolerance)).rem_euclid(360.0),
+            TrafficLaneKind::Cross => (self.direction + 90.0*off.signum()).rem_euclid(360.0),
+        };
+    }
+
+    /// Returns true if a vessel going in the direction of bearing, in degrees, keeps to the rules of the lane
+    pub fn allows(&self, bearing: f64) -> bool {
+        return get_angle_difference(bearing, self.heading(bearing)).abs() <= self.tolerance;
+    }
+}
+
+/// Returns the signed difference in degrees from angle b to angle a, in \[-180, 180\)
+fn get_angle_difference(a: f64, b: f64) -> f64 {
+    return (a - b + 180.0).rem_euclid(360.0) - 180.0;
+}
+
+/// Struct for a tank of the vessel, e.g. fuel or fresh water, see Boat.fuel_tank and Boat.fresh_water_tank
+/// The tank is consumed from at every new ship log entry while a voyage is simulated, over the time since the last entry
+/// The simulation stops with SimulationStatus::TankEmpty when a tank runs empty, unless the vessel gets to a bunkering port first, see Simulation.bunkering
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tank {
+    /// \[tons\]. How much the tank holds
+    pub capacity: f64,
+    /// \[tons\]. How much is in the tank
+    pub level: f64,
+    /// \[tons/day\]. Consumed all the time, at sea and in port, e.g. by the crew or the generators
+    pub consumption_per_day: f64,
+    /// \[tons/hour\]. Consumed while the engine runs, i.e. while the navigation status is NavigationStatus::UnderwayUsingEngine
+    pub engine_consumption_per_hour: f64,
+    /// \[tons\]. Total consumed from the tank, used for VoyageKpis.fuel
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub consumed: f64,
+}
+
+impl Tank {
+    /// Creates a full tank
+    /// # Example:
+    /// `boat.fuel_tank = Some(Tank::new(40.0, 0.1, 0.5));`
+    pub fn new(capacity: f64, consumption_per_day: f64, engine_consumption_per_hour: f64) -> Tank {
+        Tank {
+            capacity,
+            level: capacity,
+            consumption_per_day,
+            engine_consumption_per_hour,
+            consumed: 0.0,
+        }
+    }
+
+    /// Returns true if the tank is empty
+    pub fn is_empty(&self) -> bool {
+        return self.level <= 0.0;
+    }
+
+    /// Returns how full the tank is, in \[0, 1\]. 0.0 if the tank has no capacity
+    pub fn fraction(&self) -> f64 {
+        if self.capacity <= 0.0 {
+            return 0.0;
+        }
+        return (self.level/self.capacity).clamp(0.0, 1.0);
+    }
+
+    /// Consumes from the tank for the duration, with the engine running or not. The level does not go below zero
+    pub fn consume(&mut self, duration: time::Duration, engine_running: bool) {
+        let hours = duration.as_seconds_f64()/3600.0;
+        let mut amount = self.consumption_per_day*hours/24.0;
+        if engine_running {
+            amount += self.engine_consumption_per_hour*hours;
+        }
+        let amount = amount.min(self.level).max(0.0);
+        self.level -= amount;
+        self.consumed += amount;
+    }
+
+    /// Fills the tank and returns how much was added in \[tons\]
+    pub fn refill(&mut self) -> f64 {
+        let added = (self.capacity - self.level).max(0.0);
+        self.level = self.capacity;
+        return added;
+    }
+}
+
+/// Crew of the vessel, limits how often and when it can maneuver, i.e. tack or change the sail, see Boat.crew
+/// A vessel without a crew model maneuvers whenever it needs to, e.g. an autonomous vessel or one with a full crew
+/// # Example:
+/// `boat.crew = Some(Crew::single_handed());`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Crew {
+    /// Number of people on board
+    pub size: u32,
+    /// Maximum number of maneuvers, tacks and sail changes, in any hour
+    pub max_maneuvers_per_hour: u32,
+    /// Times of day (UTC) the crew rests, as (start, end). No maneuvers are made during a rest window. A window can pass midnight, e.g. (22:00, 02:00)
+    pub rest_windows: Vec<(time::Time, time::Time)>,
+    /// When the crew maneuvered during the last hour
+    #[cfg_attr(feature = "serde", serde(skip))]
+    maneuvers: Vec<UtcDateTime>,
+}
+
+impl Crew {
+    /// Creates a crew of size people that makes at most max_maneuvers_per_hour maneuvers per hour, without rest windows
+    pub fn new(size: u32, max_maneuvers_per_hour: u32) -> Crew {
+        Crew {
+            size,
+            max_maneuvers_per_hour,
+            rest_windows: Vec::new(),
+            maneuvers: Vec::new(),
+        }
+    }
+
+    /// A single-handed sailor, 2 maneuvers per hour and rests from 02:00 to 05:00 and 13:00 to 14:00 UTC
+    pub fn single_handed() -> Crew {
+        let mut crew = Crew::new(1, 2);
+        crew.rest_windows = vec![(time::macros::time!(2:00), time::macros::time!(5:00)), (time::macros::time!(13:00), time::macros::time!(14:00))];
+        return crew;
+    }
+
+    /// A short-handed crew of two on alternating watches, 4 maneuvers per hour and no sail changes from 03:00 to 05:00 UTC when the watches change
+    pub fn short_handed() -> Crew {
+        let mut crew = Crew::new(2, 4);
+        crew.rest_windows = vec![(time::macros::time!(3:00), time::macros::time!(5:00))];
+        return crew;
+    }
+
+    /// Returns true if the time is in one of the rest windows
+    pub fn is_resting(&self, time: UtcDateTime) -> bool {
+        let time_of_day = time.time();
+        return self.rest_windows.iter().any(|(start, end)| {
+            if start <= end {
+                *start <= time_of_day && time_of_day < *end
+            } else {
+                time_of_day >= *start || time_of_day < *end
+            }
+        });
+    }
+
+    /// Returns true if the crew can maneuver at the time, i.e. it is not resting and has made fewer than max_maneuvers_per_hour maneuvers in the hour before
+    pub fn can_maneuver(&self, time: UtcDateTime) -> bool {
+        if self.is_resting(time) {
+            return false;
+        }
+        let num_maneuvers = self.maneuvers.iter().filter(|maneuver| time - **maneuver < time::Duration::HOUR && **maneuver <= time).count();
+        return num_maneuvers < self.max_maneuvers_per_hour as usize;
+    }
+
+    /// Records a maneuver at the time, maneuvers older than an hour are forgotten
+    pub fn record_maneuver(&mut self, time: UtcDateTime) {
+        self.maneuvers.retain(|maneuver| time - *maneuver < time::Duration::HOUR);
+        self.maneuvers.push(time);
+    }
+}
+
+/// Enum of the kinds of cargo a vessel carries, each measured in the unit its capacity is given in, see Boat.add_cargo()
+/// # Example:
+/// `boat.add_cargo(Cargo::Containers { teu: 120, mass: uom::si::f64::Mass::new::<uom::si::mass::ton>(1500.0) })?;`
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Cargo {
+    /// Containers, counted in twenty-foot equivalent units (TEU), with their total mass
+    Containers {
+        teu: u32,
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::mass"))]
+        mass: uom::si::f64::Mass,
+    },
+    /// Dry bulk, e.g. grain or ore, by mass
+    Bulk {
+        #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::mass"))]
+        mass: uom::si::f64::Mass,
+    },
+    /// Liquid in tanks, e.g. oil or chemicals, volume in \[m^3\] and density in \[kg/m^3\]
+    Liquid { volume: f64, density: f64 },
+}
+
+impl Cargo {
+    /// Returns the type of the cargo
+    pub fn cargo_type(&self) -> CargoType {
+        return match self {
+            Cargo::Containers { .. } => CargoType::Containers,
+            Cargo::Bulk { .. } => CargoType::Bulk,
+            Cargo::Liquid { .. } => CargoType::Liquid,
+        };
+    }
+
+    /// Returns the mass of the cargo, for liquids the volume times the density
+    pub fn mass(&self) -> uom::si::f64::Mass {
+        return match self {
+            Cargo::Containers { mass, .. } => *mass,
+            Cargo::Bulk { mass } => *mass,
+            Cargo::Liquid { volume, density } => uom::si::f64::Mass::new::<uom::si::mass::kilogram>(volume*density),
+        };
+    }
+
+    /// Returns the amount of cargo in the unit of its type, TEU for containers, tons for bulk and \[m^3\] for liquids, see CargoType.unit()
+    pub fn amount(&self) -> f64 {
+        return match self {
+            Cargo::Containers { teu, .. } => *teu as f64,
+            Cargo::Bulk { mass } => mass.get::<uom::si::mass::ton>(),
+            Cargo::Liquid { volume, .. } => *volume,
+        };
+    }
+}
+
+/// Enum of the types of cargo, see Cargo
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CargoType {
+    Containers,
+    Bulk,
+    Liquid,
+}
+
+impl CargoType {
+    /// All cargo types, in the order statistics are given in, see get_cargo_statistics()
+    pub const ALL: [CargoType; 3] = [CargoType::Containers, CargoType::Bulk, CargoType::Liquid];
+
+    /// Returns the unit the cargo type is measured in
+    pub fn unit(&self) -> &'static str {
+        return match self {
+            CargoType::Containers => "TEU",
+            CargoType::Bulk => "tons",
+            CargoType::Liquid => "m^3",
+        };
+    }
+}
+
+impl std::fmt::Display for CargoType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Struct to hold a route plan, the legs of a voyage in order
+/// Derefs to the vector of legs so it can be indexed and iterated like one
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoutePlan {
+    pub legs: Vec<SailingLeg>,
+    /// Restricted zones along the route, e.g. emission control areas, see RestrictedZone
+    pub zones: Vec<RestrictedZone>,
+}
+
+impl RoutePlan {
+    /// Default tacking width in \[m\] for legs made by the route generators
+    pub const DEFAULT_TACKING_WIDTH: f64 = 20000.0;
+    /// Default minimum proximity in \[m\] for legs made by the route generators
+    pub const DEFAULT_MIN_PROXIMITY: f64 = 1000.0;
+
+    /// Creates a new route plan from legs, without restricted zones
+    pub fn new(legs: Vec<SailingLeg>) -> RoutePlan {
+        RoutePlan {
+            legs,
+            zones: Vec::new(),
+        }
+    }
+
+    /// Creates a route plan following the great circle (shortest path) from p1 to p2
+    /// The great circle is split into legs of at most waypoint_spacing_km, with the default tacking width and minimum proximity
+    /// If waypoint_spacing_km is not positive, the route plan has a single leg
+    /// # Example:
+    /// `boat.route_plan = Some(RoutePlan::great_circle(halifax, le_havre, 200.0));`
+    pub fn great_circle(p1: geo::Point, p2: geo::Point, waypoint_spacing_km: f64) -> RoutePlan {
+        let line = SailingLeg::new(p1, p2, RoutePlan::DEFAULT_TACKING_WIDTH, RoutePlan::DEFAULT_MIN_PROXIMITY);
+        let n_legs = get_num_route_legs(line.length(), waypoint_spacing_km);
+
+        // Make a leg between each pair of consecutive points along the great circle
+        let mut legs: Vec<SailingLeg> = Vec::with_capacity(n_legs);
+        for i in 0..n_legs {
+            legs.push(SailingLeg::new(line.point_at(i as f64 / n_legs as f64), line.point_at((i + 1) as f64 / n_legs as f64), RoutePlan::DEFAULT_TACKING_WIDTH, RoutePlan::DEFAULT_MIN_PROXIMITY));
+        }
+        return RoutePlan::new(legs);
+    }
+
+    /// Creates a route plan following the rhumb line (constant bearing) from p1 to p2
+    /// The rhumb line is split into legs of at most waypoint_spacing_km, with the default tacking width and minimum proximity
+    /// Note that each leg is sailed as a great circle, so keep the spacing short for the route to stay close to the rhumb line
+    /// If waypoint_spacing_km is not positive, the route plan has a single leg
+    pub fn rhumb_line(p1: geo::Point, p2: geo::Point, waypoint_spacing_km: f64) -> RoutePlan {
+        let bearing = Rhumb.bearing(p1, p2);
+        let total_dist = Rhumb.distance(p1, p2);
+        let n_legs = get_num_route_legs(total_dist, waypoint_spacing_km);
+
+        // Make a leg between each pair of consecutive points along the rhumb line, use p2 explicitly as the last point to avoid floating point errors
+        let mut legs: Vec<SailingLeg> = Vec::with_capacity(n_legs);
+        let mut last_point = p1;
+        for i in 1..=n_legs {
+            let point = match i == n_legs {
+                true => p2,
+                false => Rhumb.destination(p1, bearing, total_dist*(i as f64)/(n_legs as f64)),
+            };
+            legs.push(SailingLeg::new(last_point, point, RoutePlan::DEFAULT_TACKING_WIDTH, RoutePlan::DEFAULT_MIN_PROXIMITY));
+            last_point = point;
+        }
+        return RoutePlan::new(legs);
+    }
+
+    /// Returns the route plan sailed the other way, e.g. for the return voyage of a round trip
+    /// Each leg keeps its tacking width, minimum proximity and traffic lane, and each port call and anchor wait stays at the same waypoint. The restricted zones are kept
+    /// Note: A leg that followed a traffic lane now sails against its traffic, see RoutePlan.traffic_lane_violations()
+    pub fn reversed(&self) -> RoutePlan {
+        let mut legs: Vec<SailingLeg> = Vec::with_capacity(self.legs.len());
+        for i in (0..self.legs.len()).rev() {
+            let mut leg = SailingLeg::new(self.legs[i].p2, self.legs[i].p1, self.legs[i].tacking_width, self.legs[i].min_proximity);
+            // The new end of the leg is the end of the previous leg, the start of the route has no port call or anchor wait
+            (leg.port_call, leg.anchor_wait) = match i {
+                0 => (None, None),
+                _ => (self.legs[i-1].port_call, self.legs[i-1].anchor_wait),
+            };
+            leg.traffic_lane = self.legs[i].traffic_lane;
+            legs.push(leg);
+        }
+        return RoutePlan {
+            legs,
+            zones: self.zones.clone(),
+        };
+    }
+
+    /// Returns the indices of the legs that break the rules of their traffic lanes, see TrafficLane.allows()
+    /// Both the initial and the final bearing of each leg are checked, since the bearing changes along a great circle
+    pub fn traffic_lane_violations(&self) -> Vec<usize> {
+        return self.legs.iter().enumerate()
+            .filter(|(_, leg)| leg.traffic_lane.is_some_and(|lane| !lane.allows(leg.bearing_at(0.0)) || !lane.allows(leg.bearing_at(1.0))))
+            .map(|(i, _)| i)
+            .collect();
+    }
+}
+
+impl From<Vec<SailingLeg>> for RoutePlan {
+    fn from(legs: Vec<SailingLeg>) -> Self {
+        RoutePlan::new(legs)
+    }
+}
+
+impl std::ops::Deref for RoutePlan {
+    type Target = Vec<SailingLeg>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.legs
+    }
+}
+
+impl std::ops::DerefMut for RoutePlan {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.legs
+    }
+}
+
+impl<'a> IntoIterator for &'a RoutePlan {
+    type Item = &'a SailingLeg;
+    type IntoIter = std::slice::Iter<'a, SailingLeg>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.legs.iter()
+    }
+}
+
+/// Returns the number of legs needed to split a distance in \[m\] into legs of at most waypoint_spacing_km, at least 1
+fn get_num_route_legs(dist: f64, waypoint_spacing_km: f64) -> usize {
+    if waypoint_spacing_km <= 0.0 {
+        return 1;
+    }
+    return ((dist / (waypoint_spacing_km*1000.0)).ceil() as usize).max(1);
 }
 
 /// Struct to hold ship long entry
 /// For every ship log you must know the time, where you started, where you are now and where you are going
 /// Other fields are optional, but potentially useful for analysis later
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ShipLogEntry {
     pub timestamp: time::UtcDateTime,
     /// The initial coordinates of the voyage, not the leg
@@ -33,6 +665,7 @@ pub struct ShipLogEntry {
     /// The final coordinates of the voyage, not the leg
     pub coordinates_final: geo::Point,
     /// How much cargo is on board at the time of the log entry
+    #[cfg_attr(feature = "serde", serde(default, with = "crate::serde_units::option_mass"))]
     pub cargo_on_board: Option<uom::si::f64::Mass>,
     /// Current velocity of the boat
     pub velocity: Option<PhysVec>,
@@ -48,12 +681,26 @@ pub struct ShipLogEntry {
     pub draft: Option<f64>,
     /// Navigation status of the boat at the time of the log entry
     pub navigation_status: Option<NavigationStatus>,
+    /// Water depth in meters at the location of the boat at the time of the log entry, see Bathymetry
+    pub depth: Option<f64>,
+    /// Wind at the location of the boat at the time of the log entry in \[m/s\], if known
+    pub wind: Option<PhysVec>,
+    /// Significant wave height in meters at the location of the boat at the time of the log entry, if known
+    pub wave_height: Option<f64>,
+    /// Signed cross track error in \[m\] from the great circle of the leg the vessel was sailing, positive to starboard and negative to port, see cross_track_distance()
+    /// Set by Boat.push_ship_log_entry() if the vessel has a route plan and a current leg
+    pub cross_track_error: Option<f64>,
+    /// True if the vessel was foiling at the time of the log entry, see Foil
+    /// Set by the simulators that apply the speed factor of the foil, see get_vessel_velocity_and_foiling(), None if the vessel has no foil or the simulation method does not use it
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub foiling: Option<bool>,
 }
 
 /// Navigational status of the vessel based on the AIS navigation status codes
 /// See: <https://support.marinetraffic.com/en/articles/9552867-what-is-the-significance-of-the-ais-navigational-status-values>
 #[derive(Debug, Copy, Clone, PartialEq)]
 #[repr(u64)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub enum NavigationStatus {
     UnderwayUsingEngine         = 0,
     AtAnchor                    = 1,
@@ -101,7 +748,10 @@ impl TryFrom<u8> for NavigationStatus {
 }
 
 /// Struct to represent a sail
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sail {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::area"))]
     pub area: uom::si::f64::Area,       // Area of the sail
     pub current_angle_of_attack: f64,   // Current angle of attack in degrees. Angle between sails chordlength and the wind direction
     pub lift_coefficient: f64,          // Lift coefficient of the sail
@@ -120,8 +770,11 @@ impl Sail {
 }
 
 /// Struct to represent rudder
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rudder {
     /// Area of the rudder
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::area"))]
     pub area: uom::si::f64::Area,
     /// Current angle of attack in degrees. Angle between rudders chordlength and the boats heading. 0° means rudder is aligned with the boat's heading. Negative values mean rudder is turned to port, positive values mean rudder is turned to starboard.
     pub current_angle_of_attack: f64,
@@ -143,7 +796,8 @@ impl Rudder {
 }
 
 /// Enum to represent the side of the marine vessel
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum VesselSide {
     Port,   // Left side of the boat when onboard and facing the bow
     Starboard, // Right side of the boat when onboard and facing the bow
@@ -159,45 +813,684 @@ impl VesselSide {
     }
 }
 
+/// Enum of the hull configurations of a vessel, see Boat.hull_type
+/// The speed without a polar diagram and the draft from the displacement depend on it, a polar diagram of the vessel already includes its hull
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HullType {
+    /// One hull
+    #[default]
+    Monohull,
+    /// Two hulls side by side
+    Catamaran,
+    /// A main hull with a float to each side
+    Trimaran,
+}
+
+impl HullType {
+    /// Returns the number of hulls
+    pub fn num_hulls(&self) -> u32 {
+        return match self {
+            HullType::Monohull => 1,
+            HullType::Catamaran => 2,
+            HullType::Trimaran => 3,
+        };
+    }
+
+    /// Returns the number of hulls carrying the displacement at rest, the floats of a trimaran are mostly out of the water
+    pub fn num_hulls_in_water(&self) -> u32 {
+        return match self {
+            HullType::Monohull | HullType::Trimaran => 1,
+            HullType::Catamaran => 2,
+        };
+    }
+
+    /// Returns the typical wetted surface relative to a monohull of the same length and displacement
+    pub fn wetted_surface_factor(&self) -> f64 {
+        return match self {
+            HullType::Monohull => 1.0,
+            HullType::Catamaran => 1.4,
+            HullType::Trimaran => 1.25,
+        };
+    }
+
+    /// Returns the typical righting moment relative to a monohull of the same length and displacement, multihulls get theirs from the beam instead of a ballast keel
+    pub fn righting_moment_factor(&self) -> f64 {
+        return match self {
+            HullType::Monohull => 1.0,
+            HullType::Catamaran => 2.5,
+            HullType::Trimaran => 2.0,
+        };
+    }
+
+    /// Returns the speed through water relative to a monohull of the same length and displacement in the wind speed \[m/s\]
+    /// In light wind friction dominates and the speed goes with 1/sqrt(wetted surface), in strong wind the sail the hull can carry dominates and the speed goes with the cube root of the righting moment
+    /// The factor goes linearly from the light wind value at 0 m/s to the strong wind value at 8 m/s
+    pub fn speed_factor(&self, wind_speed: f64) -> f64 {
+        let light_wind = self.wetted_surface_factor().powf(-0.5);
+        let strong_wind = self.righting_moment_factor().powf(1.0/3.0);
+        let blend = (wind_speed/8.0).clamp(0.0, 1.0);
+        return light_wind + (strong_wind - light_wind)*blend;
+    }
+}
+
+/// Hydrofoils that lift the hull of a vessel out of the water above a takeoff speed, for foiling craft and foil-assisted ferries, see Boat.foil
+/// While foiling the foils carry part of the weight of the vessel, so the hull has a lower effective displacement and less drag
+/// Whether the vessel was foiling is recorded in ShipLogEntry.foiling by the simulators that use the foil, see get_vessel_velocity_and_foiling()
+/// # Example:
+/// A foil-assisted ferry where the foils carry 60% of the weight above 8 m/s
+/// `let foil = Foil::new(8.0, 0.6)?;`
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Foil {
+    /// \[m/s\]. Speed through water above which the vessel foils
+    pub takeoff_speed: f64,
+    /// Fraction of the weight of the vessel carried by the foils while foiling, in \[0, 0.9\]. Close to 0.9 for fully foiling craft, lower for foil-assisted hulls
+    pub lift_fraction: f64,
+}
+
+impl Foil {
+    /// Largest lift fraction, the hull and foil struts always keep some drag
+    pub const MAX_LIFT_FRACTION: f64 = 0.9;
+
+    /// Creates a foil model
+    /// Returns an error if the takeoff speed is negative or the lift fraction is not in \[0, Foil::MAX_LIFT_FRACTION\]
+    pub fn new(takeoff_speed: f64, lift_fraction: f64) -> Result<Foil, MarineSimError> {
+        if !(takeoff_speed >= 0.0) {
+            return Err(MarineSimError::InvalidInput(format!("The takeoff speed of a foil must be 0 or more, got {}", takeoff_speed)));
+        }
+        if !(0.0..=Foil::MAX_LIFT_FRACTION).contains(&lift_fraction) {
+            return Err(MarineSimError::InvalidInput(format!("The lift fraction of a foil must be in [0, {}], got {}", Foil::MAX_LIFT_FRACTION, lift_fraction)));
+        }
+        return Ok(Foil {
+            takeoff_speed,
+            lift_fraction,
+        });
+    }
+
+    /// Returns true if the vessel foils at a speed through water in \[m/s\]
+    pub fn is_foiling(&self, speed: f64) -> bool {
+        return speed >= self.takeoff_speed;
+    }
+
+    /// Returns the fraction of the displacement left on the hull while foiling
+    pub fn effective_displacement_factor(&self) -> f64 {
+        return 1.0 - self.lift_fraction.clamp(0.0, Foil::MAX_LIFT_FRACTION);
+    }
+
+    /// Returns the factor the speed through water is multiplied with at a speed through water in \[m/s\], 1.0 below the takeoff speed
+    /// Same as Boat.get_displacement_speed_factor(), with the same power the speed is proportional to displacement^(-2/9), so a lift fraction of 0.6 gives 1.23 times the speed
+    pub fn speed_factor(&self, speed: f64) -> f64 {
+        if !self.is_foiling(speed) {
+            return 1.0;
+        }
+        return self.effective_displacement_factor().powf(-2.0/9.0);
+    }
+}
+
+/// Enum of the numeric parameters of a boat that can be calibrated or swept, see calibrate_boat()
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BoatParameter {
+    /// Boat.hull_drag_coefficient
+    HullDragCoefficient,
+    /// Area of the sail in \[m^2\], Boat.sail.area
+    SailArea,
+    /// Boat.sail.lift_coefficient
+    SailLiftCoefficient,
+    /// Boat.sail.drag_coefficient
+    SailDragCoefficient,
+    /// \[m/s\]. Boat.velocity_mean
+    VelocityMean,
+    /// \[m/s\]. Boat.velocity_std
+    VelocityStd,
+    /// \[m/s\]. Boat.velocity_max
+    VelocityMax,
+    /// Boat.speed_grade_coefficient
+    SpeedGradeCoefficient,
+    /// Boat.wind_velocity_multiplier
+    WindVelocityMultiplier,
+}
+
+impl BoatParameter {
+    /// Returns the value of the parameter of the boat, None if it is not set
+    pub fn get(&self, boat: &Boat) -> Option<f64> {
+        match self {
+            BoatParameter::HullDragCoefficient => boat.hull_drag_coefficient,
+            BoatParameter::SailArea => boat.sail.as_ref().map(|sail| sail.area.get::<uom::si::area::square_meter>()),
+            BoatParameter::SailLiftCoefficient => boat.sail.as_ref().map(|sail| sail.lift_coefficient),
+            BoatParameter::SailDragCoefficient => boat.sail.as_ref().map(|sail| sail.drag_coefficient),
+            BoatParameter::VelocityMean => boat.velocity_mean,
+            BoatParameter::VelocityStd => boat.velocity_std,
+            BoatParameter::VelocityMax => boat.velocity_max,
+            BoatParameter::SpeedGradeCoefficient => boat.speed_grade_coefficient,
+            BoatParameter::WindVelocityMultiplier => boat.wind_velocity_multiplier,
+        }
+    }
+
+    /// Sets the parameter of the boat to value
+    /// Returns an error for the sail parameters if the boat has no sail
+    pub fn set(&self, boat: &mut Boat, value: f64) -> Result<(), MarineSimError> {
+        match self {
+            BoatParameter::HullDragCoefficient => boat.hull_drag_coefficient = Some(value),
+            BoatParameter::VelocityMean => boat.velocity_mean = Some(value),
+            BoatParameter::VelocityStd => boat.velocity_std = Some(value),
+            BoatParameter::VelocityMax => boat.velocity_max = Some(value),
+            BoatParameter::SpeedGradeCoefficient => boat.speed_grade_coefficient = Some(value),
+            BoatParameter::WindVelocityMultiplier => boat.wind_velocity_multiplier = Some(value),
+            BoatParameter::SailArea | BoatParameter::SailLiftCoefficient | BoatParameter::SailDragCoefficient => {
+                let sail = match boat.sail.as_mut() {
+                    Some(sail) => sail,
+                    None => return Err(MarineSimError::MissingBoatField(format!("sail to set {:?}", self))),
+                };
+                match self {
+                    BoatParameter::SailArea => sail.area = uom::si::f64::Area::new::<uom::si::area::square_meter>(value),
+                    BoatParameter::SailLiftCoefficient => sail.lift_coefficient = value,
+                    _ => sail.drag_coefficient = value,
+                }
+            }
+        }
+        return Ok(());
+    }
+}
+
+/// Enum to represent what the vessel does when the weather exceeds its operational limits
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StormTactic {
+    /// Heave-to, the vessel lies close-hauled making very little way forward and drifts downwind (leeway) and with the current
+    /// See Boat.heave_to_speed_multiplier and Boat.heave_to_leeway_multiplier
+    HeaveTo,
+    /// Run downwind at reduced speed, see Boat.storm_velocity_multiplier
+    RunDownwind,
+}
+
+
+/// Struct to hold a polar diagram, the boat speed through water for each true wind angle and true wind speed
+/// Between the values in the diagram the boat speed is linearly interpolated, see PolarDiagram.boat_speed()
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PolarDiagram {
+    /// True wind angles in degrees, ascending in \[0, 180\]. 0° is head to wind
+    pub wind_angles: Vec<f64>,
+    /// True wind speeds in \[m/s\], ascending
+    pub wind_speeds: Vec<f64>,
+    /// Boat speeds through water in \[m/s\], boat_speeds\[wind angle index\]\[wind speed index\]
+    pub boat_speeds: Vec<Vec<f64>>,
+}
+
+impl PolarDiagram {
+    /// Creates a new polar diagram, checks that the size of boat_speeds matches the wind angles and wind speeds
+    pub fn new(wind_angles: Vec<f64>, wind_speeds: Vec<f64>, boat_speeds: Vec<Vec<f64>>) -> Result<PolarDiagram, MarineSimError> {
+        if wind_angles.is_empty() || wind_speeds.is_empty() {
+            return Err(MarineSimError::InvalidInput("Polar diagram must have at least one wind angle and one wind speed".to_string()));
+        }
+        if boat_speeds.len() != wind_angles.len() || boat_speeds.iter().any(|row| row.len() != wind_speeds.len()) {
+            return Err(MarineSimError::InvalidInput(format!("Polar diagram boat speeds must have {} rows (wind angles) and {} columns (wind speeds)", wind_angles.len(), wind_speeds.len())));
+        }
+        return Ok(PolarDiagram {
+            wind_angles,
+            wind_speeds,
+            boat_speeds,
+        });
+    }
+
+    /// Loads a polar diagram from a CSV file in the format written by make_polar_speed_plot_csv() (and used by openCPN) using meters per second
+    /// The first row is the header "TWA\\TWS" followed by the wind speeds, each following row is a wind angle followed by the boat speeds. The delimiter is a semicolon.
+    /// Empty cells are read as a boat speed of zero
+    pub fn from_csv(file_path: &str) -> Result<PolarDiagram, MarineSimError> {
+        // Check file extension
+        if !check_file_extension(file_path, ".csv") {
+            return Err(MarineSimError::InvalidInput("File path must end with .csv".to_string()));
+        }
+
+        // Read the CSV file
+        let mut csv_reader = csv::ReaderBuilder::new()
+            .delimiter(b';')
+            .has_headers(true)
+            .from_path(file_path)?;
+
+        // Wind speeds from the header, skip the first column
+        let mut wind_speeds: Vec<f64> = Vec::new();
+        for cell in csv_reader.headers()?.iter().skip(1) {
+            match cell.trim().parse::<f64>() {
+                Ok(ws) => wind_speeds.push(ws),
+                Err(_) => return Err(MarineSimError::CsvParse { file: file_path.to_string(), message: format!("Invalid wind speed {:?} in polar diagram header", cell) }),
+            }
+        }
+
+        // Wind angles and boat speeds from the rows
+        let mut wind_angles: Vec<f64> = Vec::new();
+        let mut boat_speeds: Vec<Vec<f64>> = Vec::new();
+        for result in csv_reader.records() {
+            let record = result?;
+            let mut row: Vec<f64> = Vec::new();
+            for (i, cell) in record.iter().enumerate() {
+                let value = match cell.trim() {
+                    "" => 0.0,
+                    c => match c.parse::<f64>() {
+                        Ok(v) => v,
+                        Err(_) => return Err(MarineSimError::CsvParse { file: file_path.to_string(), message: format!("Invalid value {:?} in polar diagram", cell) }),
+                    },
+                };
+                if i == 0 {
+                    wind_angles.push(value);
+                }
+                else {
+                    row.push(value);
+                }
+            }
+            // Missing cells at the end of the row are zero
+            row.resize(wind_speeds.len(), 0.0);
+            boat_speeds.push(row);
+        }
+
+        return PolarDiagram::new(wind_angles, wind_speeds, boat_speeds);
+    }
+
+    /// Returns the boat speed through water in \[m/s\] for a true wind speed in \[m/s\] and a true wind angle in degrees
+    /// The wind angle can be in \[-180, 180\] or \[0, 360\], the boat is assumed to be equally fast on both tacks
+    /// Outside of the diagram the closest values are used, except below the lowest wind speed where the boat speed goes linearly to zero at zero wind
+    pub fn boat_speed(&self, wind_speed: f64, wind_angle: f64) -> f64 {
+        // Fold the wind angle into [0, 180]
+        let angle = get_true_wind_angle(wind_angle, 0.0);
+
+        // Interpolate between wind angles
+        let (a0, a1, a_frac) = get_interpolation_indices(&self.wind_angles, angle);
+        // Interpolate between wind speeds, below the lowest wind speed scale down to zero at zero wind
+        if wind_speed < self.wind_speeds[0] && self.wind_speeds[0] > 0.0 {
+            let speed_at_lowest = self.boat_speeds[a0][0]*(1.0 - a_frac) + self.boat_speeds[a1][0]*a_frac;
+            return speed_at_lowest * wind_speed.max(0.0) / self.wind_speeds[0];
+        }
+        let (s0, s1, s_frac) = get_interpolation_indices(&self.wind_speeds, wind_speed);
+
+        // Bilinear interpolation
+        let speed_a0 = self.boat_speeds[a0][s0]*(1.0 - s_frac) + self.boat_speeds[a0][s1]*s_frac;
+        let speed_a1 = self.boat_speeds[a1][s0]*(1.0 - s_frac) + self.boat_speeds[a1][s1]*s_frac;
+        return speed_a0*(1.0 - a_frac) + speed_a1*a_frac;
+    }
+
+    /// Fits a polar diagram to a ship log, e.g. an AIS log from aishub_shiplog_csv_to_marine_vessel_simulator_shiplog_csv(), by averaging the boat speed in bins of true wind angle and true wind speed
+    /// The wind is taken from the ship log entries. Entries without wind get it from the weather provider (e.g. copernicus reanalysis data) if one is given, otherwise they are skipped
+    /// If the weather provider knows the ocean current it is subtracted from the velocity over ground to get the speed through water
+    /// Entries where the vessel is moored, at anchor or aground are skipped. Missing velocities are found from the distance and time to the previous entry
+    /// Bins without samples are linearly interpolated from the bins around them, first between wind angles and then between wind speeds
+    /// wind_angle_step: Size of the wind angle bins in degrees, e.g. 10
+    /// wind_speed_step: Size of the wind speed bins in \[m/s\], e.g. 2
+    /// # Example:
+    /// `boat.polar_diagram = Some(PolarDiagram::fit_to_ship_log(&ais_log, Some(&copernicus), 10.0, 2.0)?);`
+    pub fn fit_to_ship_log(ship_log: &[ShipLogEntry], weather: Option<&dyn WeatherProvider>, wind_angle_step: f64, wind_speed_step: f64) -> Result<PolarDiagram, MarineSimError> {
+        if wind_angle_step <= 0.0 || wind_speed_step <= 0.0 {
+            return Err(MarineSimError::InvalidInput("Wind angle step and wind speed step must be positive".to_string()));
+        }
+
+        // Samples of (true wind angle, true wind speed, boat speed through water)
+        let mut samples: Vec<(f64, f64, f64)> = Vec::new();
+        for i in 0..ship_log.len() {
+            let entry = &ship_log[i];
+            // Skip entries where the vessel is not sailing
+            if matches!(entry.navigation_status, Some(NavigationStatus::Moored) | Some(NavigationStatus::AtAnchor) | Some(NavigationStatus::Aground)) {
+                continue;
+            }
+
+            // Get velocity over ground, from the previous entry if it is missing
+            let velocity: PhysVec = match entry.velocity {
+                Some(velocity) => velocity,
+                None => {
+                    if i == 0 {
+                        continue;
+                    }
+                    let previous = &ship_log[i-1];
+                    let time_diff = (entry.timestamp - previous.timestamp).as_seconds_f64();
+                    if time_diff <= 0.0 {
+                        continue;
+                    }
+                    PhysVec::new(Earth.distance(previous.coordinates_current, entry.coordinates_current)/time_diff, Earth.bearing(previous.coordinates_current, entry.coordinates_current))
+                }
+            };
+            let heading: f64 = entry.heading.or(entry.course).unwrap_or(velocity.angle);
+
+            // Get wind and ocean current
+            let wind: PhysVec = match (entry.wind, weather) {
+                (Some(wind), _) => wind,
+                (None, Some(weather)) => weather.wind(entry.timestamp, entry.coordinates_current)?,
+                (None, None) => continue,
+            };
+            let mut velocity_through_water = velocity;
+            if let Some(weather) = weather {
+                if let Some(ocean_current) = weather.ocean_current(entry.timestamp, entry.coordinates_current)? {
+                    velocity_through_water = velocity - ocean_current;
+                }
+            }
+
+            samples.push((get_true_wind_angle(heading, wind.angle), wind.magnitude, velocity_through_water.magnitude));
+        }
+        if samples.is_empty() {
+            return Err(MarineSimError::InvalidInput("No ship log entries with both velocity and wind, can not fit a polar diagram".to_string()));
+        }
+
+        // Make the bins, wind angles from 0° to 180° and wind speeds from one step up to the highest wind speed sampled
+        let num_angles = (180.0/wind_angle_step).ceil() as usize + 1;
+        let wind_angles: Vec<f64> = (0..num_angles).map(|i| (i as f64*wind_angle_step).min(180.0)).collect();
+        let max_wind_speed = samples.iter().map(|(_, wind_speed, _)| *wind_speed).fold(0.0, f64::max);
+        let num_speeds = ((max_wind_speed/wind_speed_step).round() as usize).max(1);
+        let wind_speeds: Vec<f64> = (1..=num_speeds).map(|i| i as f64*wind_speed_step).collect();
+
+        // Average the boat speed in each bin
+        let mut sums: Vec<Vec<f64>> = vec![vec![0.0; num_speeds]; num_angles];
+        let mut counts: Vec<Vec<usize>> = vec![vec![0; num_speeds]; num_angles];
+        for (wind_angle, wind_speed, boat_speed) in &samples {
+            let a = ((wind_angle/wind_angle_step).round() as usize).min(num_angles - 1);
+            let s = ((wind_speed/wind_speed_step).round() as usize).clamp(1, num_speeds) - 1;
+            sums[a][s] += boat_speed;
+            counts[a][s] += 1;
+        }
+        let mut means: Vec<Vec<Option<f64>>> = (0..num_angles).map(|a| (0..num_speeds).map(|s| match counts[a][s] {
+            0 => None,
+            n => Some(sums[a][s]/n as f64),
+        }).collect()).collect();
+
+        // Fill empty bins, first between wind angles for each wind speed, then between wind speeds for wind speeds with no samples at all
+        for s in 0..num_speeds {
+            let mut column: Vec<Option<f64>> = means.iter().map(|row| row[s]).collect();
+            fill_gaps(&wind_angles, &mut column);
+            for a in 0..num_angles {
+                means[a][s] = column[a];
+            }
+        }
+        for row in means.iter_mut() {
+            fill_gaps(&wind_speeds, row);
+        }
+        let boat_speeds: Vec<Vec<f64>> = means.into_iter().map(|row| row.into_iter().map(|speed| speed.unwrap_or(0.0)).collect()).collect();
+
+        return PolarDiagram::new(wind_angles, wind_speeds, boat_speeds);
+    }
+}
+
+/// Fills the None values in a vector by linear interpolation between the closest known values at the positions x, and with the closest known value at the ends
+/// Leaves the vector as it is if no values are known
+fn fill_gaps(x: &Vec<f64>, values: &mut Vec<Option<f64>>) {
+    let known: Vec<usize> = (0..values.len()).filter(|i| values[*i].is_some()).collect();
+    if known.is_empty() {
+        return;
+    }
+    for i in 0..values.len() {
+        if values[i].is_some() {
+            continue;
+        }
+        // Closest known values below and above
+        let below = known.iter().rev().find(|k| **k < i);
+        let above = known.iter().find(|k| **k > i);
+        values[i] = match (below, above) {
+            (Some(b), Some(a)) => {
+                let frac = (x[i] - x[*b])/(x[*a] - x[*b]);
+                Some(values[*b].unwrap()*(1.0 - frac) + values[*a].unwrap()*frac)
+            }
+            (Some(b), None) => values[*b],
+            (None, Some(a)) => values[*a],
+            (None, None) => None,
+        };
+    }
+}
+
+/// Returns the indices of the values in an ascending vector on each side of x and the fraction of the way from the first to the second
+/// Clamps to the first and last values
+fn get_interpolation_indices(values: &Vec<f64>, x: f64) -> (usize, usize, f64) {
+    let n = values.len();
+    if n == 1 || x <= values[0] {
+        return (0, 0, 0.0);
+    }
+    if x >= values[n-1] {
+        return (n-1, n-1, 0.0);
+    }
+    // First index with a value bigger than x
+    let i = values.partition_point(|v| *v <= x);
+    return (i-1, i, (x - values[i-1]) / (values[i] - values[i-1]));
+}
+
+
+/// One speed from a real ship log, see EmpiricalSpeedModel
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EmpiricalSpeedSample {
+    /// \[m/s\]. Speed over ground
+    pub speed: f64,
+    /// Month the speed was logged in, 1 to 12
+    pub month: u8,
+    /// True wind angle in degrees, in \[0, 180\], None if the ship log had no wind or heading
+    pub wind_angle: Option<f64>,
+}
+
+/// Speed model made from real ship logs, the boat speed is drawn at random from the logged speeds (bootstrapping)
+/// The speeds can be conditioned on the month and on the true wind angle, so e.g. winter voyages only use speeds logged in the same month
+/// A data driven alternative between SimMethod::MeanAndSTDVelocity and the full physics of SimMethod::WeatherDataFromCopernicus
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EmpiricalSpeedModel {
+    /// The logged speeds
+    pub samples: Vec<EmpiricalSpeedSample>,
+    /// If true, only speeds logged in the same month are drawn
+    pub by_month: bool,
+    /// If set, only speeds logged at true wind angles in the same bucket of this size, in degrees, are drawn. Needs wind in the simulation, see Simulation.weather
+    pub wind_angle_bucket_size: Option<f64>,
+}
+
+impl EmpiricalSpeedModel {
+    /// Makes an empirical speed model from a real ship log, e.g. read with csv_to_ship_log()
+    /// The speed of each entry is the logged velocity if there is one, otherwise the distance from the entry before divided by the time between them
+    /// Entries that are not moving, e.g. in port, should be filtered out first, see filter_shipping_log_data()
+    /// # Example:
+    /// `boat.empirical_speed_model = Some(EmpiricalSpeedModel::from_ship_log(&csv_to_ship_log("real_voyages.csv")?, true, Some(45.0))?);`
+    pub fn from_ship_log(ship_log: &[ShipLogEntry], by_month: bool, wind_angle_bucket_size: Option<f64>) -> Result<EmpiricalSpeedModel, MarineSimError> {
+        let mut samples: Vec<EmpiricalSpeedSample> = Vec::new();
+        for (i, entry) in ship_log.iter().enumerate() {
+            // Speed from the log, or from the distance and time since the entry before
+            let speed: Option<f64> = match (entry.velocity, i) {
+                (Some(velocity), _) => Some(velocity.magnitude),
+                (None, 0) => None,
+                (None, _) => {
+                    let dt = (entry.timestamp - ship_log[i-1].timestamp).as_seconds_f64();
+                    match dt > 0.0 {
+                        true => Some(Earth.distance(ship_log[i-1].coordinates_current, entry.coordinates_current) / dt),
+                        false => None,
+                    }
+                },
+            };
+            let speed = match speed {
+                Some(s) if s.is_finite() && s >= 0.0 => s,
+                _ => continue,
+            };
+
+            // True wind angle from the logged wind and heading, or track angle if there is no heading
+            let wind_angle: Option<f64> = match (entry.wind, entry.heading.or(entry.track_angle)) {
+                (Some(wind), Some(heading)) => Some(get_true_wind_angle(heading, wind.angle)),
+                _ => None,
+            };
+
+            samples.push(EmpiricalSpeedSample {
+                speed,
+                month: entry.timestamp.month() as u8,
+                wind_angle,
+            });
+        }
+
+        if samples.is_empty() {
+            return Err(MarineSimError::InvalidInput("No speeds found in ship log for empirical speed model".to_string()));
+        }
+        return Ok(EmpiricalSpeedModel {
+            samples,
+            by_month,
+            wind_angle_bucket_size,
+        });
+    }
+
+    /// Draws a random speed in \[m/s\] for a time and, if known, true wind angle in degrees
+    /// If no speeds match both the month and wind angle, the wind angle condition is dropped, and then the month condition
+    pub fn sample(&self, timestamp: UtcDateTime, wind_angle: Option<f64>) -> f64 {
+        let month = timestamp.month() as u8;
+        let bucket = |angle: f64, size: f64| (get_true_wind_angle(angle, 0.0) / size).floor() as i64;
+        let same_month = |sample: &&EmpiricalSpeedSample| !self.by_month || sample.month == month;
+        let same_wind_angle = |sample: &&EmpiricalSpeedSample| match (self.wind_angle_bucket_size, wind_angle, sample.wind_angle) {
+            (Some(size), Some(angle), Some(sample_angle)) if size > 0.0 => bucket(angle, size) == bucket(sample_angle, size),
+            (Some(_), Some(_), None) => false,
+            _ => true,
+        };
+
+        // Matching speeds, relax the conditions if there are none
+        let mut candidates: Vec<&EmpiricalSpeedSample> = self.samples.iter().filter(same_month).filter(same_wind_angle).collect();
+        if candidates.is_empty() {
+            candidates = self.samples.iter().filter(same_month).collect();
+        }
+        if candidates.is_empty() {
+            candidates = self.samples.iter().collect();
+        }
+        return candidates[rand::random_range(0..candidates.len())].speed;
+    }
+}
+
+/// Returns the true wind angle in degrees, in \[0, 180\], for a heading and the angle the wind comes from
+/// 0° is head to wind and 180° is dead downwind, the boat is assumed to be equally fast on both tacks
+pub fn get_true_wind_angle(heading: f64, wind_angle: f64) -> f64 {
+    let mut angle = (heading - wind_angle) % 360.0;
+    if angle < 0.0 {
+        angle += 360.0;
+    }
+    if angle > 180.0 {
+        angle = 360.0 - angle;
+    }
+    return angle;
+}
+
+
+/// Closure that is called with every new entry in a boat's ship log, see Boat.set_ship_log_hook()
+pub type ShipLogHook = std::sync::Arc<dyn Fn(&ShipLogEntry) + Send + Sync>;
+
 
 /// Struct to hold boat metadata
 /// All fields are optional, so that the struct can be created without knowing all the values
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Boat {
-    /// The vessels maximum cargo storage capacity (by weight)
+    /// Block coefficient (Cb) of the hull, the underwater volume divided by length * width * draft, e.g. 0.8 for a bulk carrier and 0.4 for a sailing yacht
+    /// With mass, length and width the draft is computed from the displacement each time step, see Boat.get_draft_from_displacement()
+    pub block_coefficient: Option<f64>,
+    /// Cargo on board by type, see Boat.add_cargo(). cargo_current is the total mass of the cargo on board
+    pub cargo: Vec<Cargo>,
+    /// The vessels maximum cargo storage capacity (by weight), also the capacity for bulk cargo
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::option_mass"))]
     pub cargo_max_capacity: Option<uom::si::f64::Mass>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::mass"))]
     pub cargo_current: uom::si::f64::Mass,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::option_mass"))]
     pub cargo_mean: Option<uom::si::f64::Mass>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::option_mass"))]
     pub cargo_std: Option<uom::si::f64::Mass>,
+    /// Crew of the vessel, if set it limits how often and when the vessel tacks and changes the sail, see Crew. None means no limits
+    pub crew: Option<Crew>,
     pub current_leg: Option<u32>,
+    /// Decimated ship log, resampled from the ship log of each simulated voyage if Simulation.decimated_log_interval is set. See resample_ship_log()
+    pub decimated_ship_log: Vec<ShipLogEntry>,
+    /// Displacement the speed of the vessel is given for, e.g. the displacement the polar diagram was measured at. If set the speed through water is lower when the vessel is heavier, see Boat.get_displacement_speed_factor()
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::option_mass"))]
+    pub design_displacement: Option<uom::si::f64::Mass>,
     pub destination: Option<geo::Point>,
+    /// Detour waypoints the simulator inserted to avoid storms during the last simulation, see Simulation.storm_avoidance_distance
+    pub detour_waypoints: Vec<geo::Point>,
     /// The draft (a.k.a draught) of the vessel in meters
+    /// Updated from the displacement when cargo is loaded and at each ship log entry if the vessel has a block coefficient, mass, length and width, see Boat.update_draft()
     pub draft: Option<f64>,
+    /// Speeds sampled from real ship logs, used by SimMethod::EmpiricalSpeed. See EmpiricalSpeedModel
+    pub empirical_speed_model: Option<EmpiricalSpeedModel>,
+    /// Events that happened during simulations, e.g. groundings. See SimulationEvent
+    pub events: Vec<SimulationEvent>,
+    /// Hydrofoils of the vessel, if it has any. Above the takeoff speed the speed through water is higher, see Foil
+    pub foil: Option<Foil>,
+    /// Fresh water tank of the vessel, consumed while voyages are simulated, see Tank
+    pub fresh_water_tank: Option<Tank>,
+    /// Fuel on board, part of the displacement of the vessel, see Boat.get_displacement(). Kept equal to the level of the fuel tank if the vessel has one
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::mass"))]
+    pub fuel_current: uom::si::f64::Mass,
+    /// Fuel tank of the vessel, consumed while voyages are simulated, see Tank
+    pub fuel_tank: Option<Tank>,
     /// Heading in degrees. North: 0°, East: 90°, South: 180°, West: 270°
     pub heading: Option<f64>,
+    /// Leeway when hove-to as a fraction of the wind speed, i.e. how fast the vessel drifts downwind. If None, defaults to 0.03
+    pub heave_to_leeway_multiplier: Option<f64>,
+    /// Multiplier for the vessel forward velocity when hove-to. If None, defaults to 0.05
+    pub heave_to_speed_multiplier: Option<f64>,
     /// Coefficient of drag for the hull
     pub hull_drag_coefficient: Option<f64>,
+    /// Hull configuration of the vessel, defaults to HullType::Monohull
+    pub hull_type: HullType,
+    /// Width of each hull at the waterline of a multihull, needed for the draft from the displacement of a multihull, see Boat.get_draft_from_displacement()
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::option_length"))]
+    pub hull_width: Option<uom::si::f64::Length>,
     /// The IMO number of the vessel
     pub imo: Option<u32>,
     /// The length of the vessel
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::option_length"))]
     pub length: Option<uom::si::f64::Length>,
+    /// \[m^3\]. Tank capacity for liquid cargo, see Cargo::Liquid. None means no limit
+    pub liquid_capacity: Option<f64>,
     pub location: Option<geo::Point>,
     /// Mass of the boat without cargo or fuel (a.k.a dry weight)
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::option_mass"))]
     pub mass: Option<uom::si::f64::Mass>,
+    /// The maximum sea ice concentration, in \[0, 1\], the vessel sails into. See Simulation.ice_avoidance for what happens when exceeded. None means no limit
+    pub max_ice_concentration: Option<f64>,
+    /// [m]. The maximum significant wave height the vessel operates in. If exceeded the vessel uses its storm tactic. None means no limit
+    pub max_wave_height: Option<f64>,
+    /// [m/s]. The maximum wind speed the vessel operates in. If exceeded the vessel uses its storm tactic. None means no limit
+    pub max_wind_speed: Option<f64>,
     pub min_angle_of_attack: Option<f64>,
+    /// [m]. Minimum water depth under the keel. The vessel runs aground if the water depth is less than draft + min_under_keel_clearance. If None, defaults to 0
+    pub min_under_keel_clearance: Option<f64>,
     /// The name of the vessel
     pub name: Option<String>,
+    /// Number of times the boat has tacked, see Boat.tack()
+    pub num_tacks: u64,
     pub navigation_status: Option<NavigationStatus>,
+    /// Polar diagram of the vessel. If set, get_vessel_velocity() uses it for the speed through water instead of velocity_max and speed_grade_coefficient. See PolarDiagram.fit_to_ship_log()
+    pub polar_diagram: Option<PolarDiagram>,
+    /// Called with the progress of the voyage at every new entry in the ship log while sim_waypoint_mission() runs, see Simulation.add_progress_callback()
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) progress_callbacks: Vec<ProgressCallback>,
     /// Note that for evaluating the route plan then the minimum proximity of the final point of the roue plan must be zero
-    pub route_plan: Option<Vec<SailingLeg>>,
+    pub route_plan: Option<RoutePlan>,
     pub rudder: Option<Rudder>,
     pub sail: Option<Sail>,
     pub ship_log: Vec<ShipLogEntry>,
+    /// Called with every new entry in the ship log, e.g. to mirror the entries into a database or UI in real time. See Boat.set_ship_log_hook()
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub ship_log_hook: Option<ShipLogHook>,
     /// [s/m] https://github.com/G0rocks/marine_vessel_simulator/issues/77
     pub speed_grade_coefficient: Option<f64>,
+    /// True while the vessel is using its storm tactic, see Boat.update_storm_bound()
+    pub storm_bound: bool,
+    /// The vessel resumes sailing when the weather drops below this fraction of its operational limits. If None, defaults to 0.8
+    /// Keeps the vessel from switching back and forth between sailing and the storm tactic when the weather is close to the limits
+    pub storm_resume_fraction: Option<f64>,
+    /// What the vessel does when the weather exceeds its operational limits (max_wind_speed and max_wave_height)
+    pub storm_tactic: StormTactic,
+    /// Multiplier for the vessel velocity when running downwind in a storm. If None, defaults to 0.5
+    pub storm_velocity_multiplier: Option<f64>,
+    /// Container capacity in twenty-foot equivalent units, see Cargo::Containers. None means no limit
+    pub teu_capacity: Option<u32>,
+    /// Total time the vessel has spent hove-to, see StormTactic::HeaveTo
+    pub time_hove_to: time::Duration,
     /// The current time for the boat
     pub time_now: time::UtcDateTime,
+    /// Tons per centimetre immersion (TPC), the cargo in metric tons that makes the vessel sink 1 cm deeper. Used to update the draft at port calls, see PortCall
+    /// None means the draft does not change with the cargo
+    pub tons_per_cm_immersion: Option<f64>,
     /// The true bearing (true as in from north) to the next waypoint
     pub true_bearing: Option<f64>,
+    /// Departure time of the voyage sim_waypoint_mission() is simulating, None between voyages
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) voyage_departure: Option<UtcDateTime>,
+    /// Ship log of the voyage sim_waypoint_mission() is simulating, None between voyages. See VoyageLog
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) voyage_log: Option<VoyageLog>,
     /// [m/s]. Current velocity of the boat with magnitude and direction
     pub velocity_current: Option<PhysVec>,
     /// [m/s]. The average velocity of the boat, only magnitude, take care of your units. Good practice to use the same velocity units everywhere, \[m/s\] recommended.
@@ -206,12 +1499,28 @@ pub struct Boat {
     pub velocity_max: Option<f64>,
     /// [m/s]. The standard deviation of the velocity of the boat, only magnitude
     pub velocity_std: Option<f64>,
+    /// When the boat reached each waypoint during simulations, see WaypointArrival
+    pub waypoint_arrivals: Vec<WaypointArrival>,
     /// [m]. The width of the vessel
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_units::option_length"))]
     pub width: Option<uom::si::f64::Length>,
     /// Preferred side of the boat for the wind to hit
     pub wind_preferred_side: VesselSide,
     /// Multiplier for the wind velocity to simulate different sail efficiencies. When simulating, the velocity of the vessel in the vessels heading will be the wind velocity multiplied by this multiplier. Note that ocean currents can also impact vessel velocity.
     pub wind_velocity_multiplier: Option<f64>,
+    /// Drift speed downwind as a fraction of the wind speed (a.k.a. leeway coefficient) when the vessel has no propulsion, see SimMethod::Drift and get_drift_velocity(). If None, defaults to 0.03
+    /// E.g. about 0.01 for a low floating container, 0.03 for a life raft and 0.05 for a vessel with high topsides
+    pub windage_factor: Option<f64>,
+}
+
+
+/// Builder for Boat, see Boat::builder()
+/// Fields that are not set keep the defaults from Boat::new()
+/// # Example:
+/// `let boat = Boat::builder().name("Ship").velocity_mean(5.0).route_plan(route_plan).build_for(SimMethod::ConstVelocity)?;`
+#[derive(Clone)]
+pub struct BoatBuilder {
+    boat: Boat,
 }
 
 // Implementation of the Boat struct
@@ -219,39 +1528,150 @@ pub struct Boat {
 impl Boat {
     /// Creates a new Boat instance with mostly None in the fields, though some fields have default values
     /// Make sure to set the values you need to use to the correct values 
-    /// Defaults all to None except cargo_current to zero, ship_log to an empty vector, time_now to UtcDateTime::now(), wind_preferred_side to starboard since then we have the right of way in most cases.
+    /// Defaults all to None except cargo_current and fuel_current to zero, cargo, decimated_ship_log, detour_waypoints, events, ship_log and waypoint_arrivals to an empty vector, num_tacks to zero, storm_bound to false, storm_tactic to heave-to, hull_type to monohull, time_hove_to to zero, time_now to UtcDateTime::now(), wind_preferred_side to starboard since then we have the right of way in most cases.
     pub fn new() -> Boat {
         Boat {
+            block_coefficient: None,
+            cargo: Vec::new(),
             cargo_current: uom::si::f64::Mass::new::<uom::si::mass::ton>(0.0),
             cargo_max_capacity: None,
             cargo_mean: None,
             cargo_std: None,
+            crew: None,
             current_leg: None,
+            decimated_ship_log: Vec::new(),
+            design_displacement: None,
             destination: None,
+            detour_waypoints: Vec::new(),
             draft: None,
+            empirical_speed_model: None,
+            events: Vec::new(),
+            foil: None,
+            fresh_water_tank: None,
+            fuel_current: uom::si::f64::Mass::new::<uom::si::mass::ton>(0.0),
+            fuel_tank: None,
             heading: None,
+            heave_to_leeway_multiplier: None,
+            heave_to_speed_multiplier: None,
             hull_drag_coefficient: None,
+            hull_type: HullType::Monohull,
+            hull_width: None,
             imo: None,
             length: None,
+            liquid_capacity: None,
             location: None,
             mass: None,
+            max_ice_concentration: None,
+            max_wave_height: None,
+            max_wind_speed: None,
             min_angle_of_attack: None,
+            min_under_keel_clearance: None,
             name: None,
+            num_tacks: 0,
             navigation_status: None,
+            polar_diagram: None,
+            progress_callbacks: Vec::new(),
             route_plan: None,
             rudder: None,
             sail: None,
             ship_log: Vec::new(),
+            ship_log_hook: None,
             speed_grade_coefficient: None,
+            storm_bound: false,
+            storm_resume_fraction: None,
+            storm_tactic: StormTactic::HeaveTo,
+            storm_velocity_multiplier: None,
+            teu_capacity: None,
+            time_hove_to: time::Duration::ZERO,
             time_now: UtcDateTime::now(),
+            tons_per_cm_immersion: None,
             true_bearing: None,
+            voyage_departure: None,
+            voyage_log: None,
             velocity_current: None,
             velocity_mean: None,
             velocity_max: None,
             velocity_std: None,
+            waypoint_arrivals: Vec::new(),
             width: None,
             wind_preferred_side: VesselSide::Starboard,
             wind_velocity_multiplier: None,
+            windage_factor: None,
+        }
+    }
+
+    /// Returns a BoatBuilder to set up a boat and validate it for a simulation method, see BoatBuilder.build_for()
+    pub fn builder() -> BoatBuilder {
+        return BoatBuilder { boat: Boat::new() };
+    }
+
+    /// Returns the fields the boat is missing to be simulated with the simulation method, empty if none are missing
+    /// # Example:
+    /// `let missing = boat.missing_fields_for(&SimMethod::WeatherDataFromCopernicus);`
+    pub fn missing_fields_for(&self, method: &SimMethod) -> Vec<String> {
+        let mut missing: Vec<String> = Vec::new();
+        // All simulation methods sail along the route plan
+        if self.route_plan.as_ref().is_none_or(|route_plan| route_plan.is_empty()) {
+            missing.push("route plan".to_string());
+        }
+        match method {
+            SimMethod::ConstVelocity => {
+                if self.velocity_mean.is_none() {
+                    missing.push("mean velocity".to_string());
+                }
+            }
+            SimMethod::MeanAndSTDVelocity => {
+                if self.velocity_mean.is_none() {
+                    missing.push("mean velocity".to_string());
+                }
+                if self.velocity_std.is_none() {
+                    missing.push("standard deviation velocity".to_string());
+                }
+            }
+            SimMethod::EmpiricalSpeed => {
+                if self.empirical_speed_model.as_ref().is_none_or(|model| model.samples.is_empty()) {
+                    missing.push("empirical speed model or it has no speeds".to_string());
+                }
+            }
+            SimMethod::Drift => {
+                // The vessel only drifts from the first waypoint, the windage factor has a default
+            }
+            SimMethod::WeatherDataFromCopernicus | SimMethod::FastWeatherDataFromCopernicus => {
+                if self.mass.is_none() {
+                    missing.push("mass".to_string());
+                }
+                if self.sail.is_none() {
+                    missing.push("sail".to_string());
+                }
+                if self.min_angle_of_attack.is_none() {
+                    missing.push("minimum angle of attack".to_string());
+                }
+                if self.wind_velocity_multiplier.is_none() {
+                    missing.push("wind_velocity_multiplier".to_string());
+                }
+                // The vessel velocity comes from the polar diagram or from velocity max and the speed grade coefficient, see get_vessel_velocity()
+                if matches!(method, SimMethod::WeatherDataFromCopernicus) && self.polar_diagram.is_none() {
+                    if self.velocity_max.is_none() {
+                        missing.push("velocity max".to_string());
+                    }
+                    if self.speed_grade_coefficient.is_none() {
+                        missing.push("speed grade coefficient".to_string());
+                    }
+                }
+            }
+        }
+        return missing;
+    }
+
+    /// Returns true if the crew can maneuver, tack or change the sail, at the time, always true if the boat has no crew model, see Crew.can_maneuver()
+    pub fn can_maneuver(&self, time: UtcDateTime) -> bool {
+        return self.crew.as_ref().map_or(true, |crew| crew.can_maneuver(time));
+    }
+
+    /// Records a maneuver of the crew at the time, if the boat has a crew model, see Crew.record_maneuver()
+    pub fn record_maneuver(&mut self, time: UtcDateTime) {
+        if let Some(crew) = self.crew.as_mut() {
+            crew.record_maneuver(time);
         }
     }
 
@@ -260,6 +1680,7 @@ impl Boat {
     pub fn tack(&mut self, wind_angle: f64) {
         // Switch preferred wind side
         self.wind_preferred_side.switch();
+        self.num_tacks += 1;
         self.hold_tack(wind_angle);
     }
 
@@ -286,11 +1707,53 @@ impl Boat {
         }
     }
 
+    /// Checks if the weather exceeds the operational limits of the vessel
+    /// wind_speed: The wind speed in \[m/s\]
+    /// wave_height: The significant wave height in \[m\], if known
+    /// Returns false if the vessel has no limits set or the wave height is unknown
+    pub fn exceeds_operational_limits(&self, wind_speed: f64, wave_height: Option<f64>) -> bool {
+        // Check wind speed limit
+        if let Some(max_wind_speed) = self.max_wind_speed {
+            if wind_speed > max_wind_speed {
+                return true;
+            }
+        }
+        // Check wave height limit
+        if let (Some(max_wave_height), Some(h)) = (self.max_wave_height, wave_height) {
+            if h > max_wave_height {
+                return true;
+            }
+        }
+        // Within limits
+        return false;
+    }
+
+    /// Updates and returns Boat.storm_bound with hysteresis
+    /// The vessel becomes storm bound when the weather exceeds its operational limits and stays storm bound until the weather drops below storm_resume_fraction of the limits
+    /// wind_speed: The wind speed in \[m/s\]
+    /// wave_height: The significant wave height in \[m\], if known
+    pub fn update_storm_bound(&mut self, wind_speed: f64, wave_height: Option<f64>) -> bool {
+        if self.storm_bound {
+            // Scale the weather up instead of the limits down, same result
+            let resume_fraction = self.storm_resume_fraction.unwrap_or(0.8);
+            self.storm_bound = self.exceeds_operational_limits(wind_speed / resume_fraction, wave_height.map(|h| h / resume_fraction));
+        }
+        else {
+            self.storm_bound = self.exceeds_operational_limits(wind_speed, wave_height);
+        }
+        return self.storm_bound;
+    }
+
     /// Logs a new entry in the ship log
-    pub fn log_entry_into_ship_log(&mut self) {
+    /// Returns an error if the boat has no location
+    pub fn log_entry_into_ship_log(&mut self) -> Result<(), MarineSimError> {
+        let location = match self.location {
+            Some(location) => location,
+            None => return Err(MarineSimError::MissingBoatField("location".to_string())),
+        };
         // If there is a ship log entry already, use the last initial coordinates, otherwise, use boats current location
         let coord_initial = match self.ship_log.len() {
-            0 => self.location.expect("Tried to get boats location but no location was found"),
+            0 => location,
             _ => self.ship_log.last().unwrap().coordinates_initial,
         };
         // Same with final coordinates unless the boat has a destination
@@ -300,7 +1763,7 @@ impl Boat {
         }
         else {
             coord_final = match self.ship_log.len() {
-                0 => self.location.expect("Tried to get boats location but no location was found"),
+                0 => location,
                 _ => self.ship_log.last().unwrap().coordinates_final,
             };
         }
@@ -308,43 +1771,600 @@ impl Boat {
         let new_log_entry: ShipLogEntry = ShipLogEntry {
             timestamp: self.time_now,
             coordinates_initial: coord_initial,
-            coordinates_current: self.location.expect("Tried to get boats location but no location was found"),
+            coordinates_current: location,
             coordinates_final: coord_final,
             cargo_on_board: Some(self.cargo_current),
             velocity: self.velocity_current,
-            course: Some(geo::Haversine.bearing(coord_initial, coord_final)),
-            track_angle: Some(Rhumb.bearing(coord_initial, self.location.unwrap())),
+            course: Some(Earth.bearing(coord_initial, coord_final)),
+            track_angle: Some(Rhumb.bearing(coord_initial, location)),
             heading: self.heading,
             true_bearing: self.true_bearing,
             draft: self.draft,
             navigation_status: self.navigation_status,
+            depth: None,
+            wind: None,
+            wave_height: None,
+            cross_track_error: None,
+            foiling: None,
             };
 
         // Push the new log entry to the ship log
-        self.ship_log.push(new_log_entry);
+        self.push_ship_log_entry(new_log_entry);
+        return Ok(());
+    }
+
+    /// Pushes an entry to the ship log and calls the ship log hook with it, if there is one
+    /// Use this instead of ship_log.push() so the hook sees every entry
+    /// If the entry has no cross track error it is set from the current leg of the route plan, see cross_track_distance()
+    /// If the entry has no draft it is set to the draft of the vessel, after updating it from the displacement, see Boat.update_draft()
+    /// While a voyage is simulated the tanks are consumed from for the time since the last entry, see Boat.consume_tanks(), and the ship log is written in batches if the simulation streams it, see Simulation.set_ship_log_writer()
+    pub fn push_ship_log_entry(&mut self, mut entry: ShipLogEntry) {
+        // Consume from the tanks for the time since the last entry, while a voyage is simulated
+        if self.voyage_departure.is_some() {
+            if let Some(last_entry) = self.ship_log.last() {
+                let duration = entry.timestamp - last_entry.timestamp;
+                if duration > time::Duration::ZERO {
+                    self.consume_tanks(duration, entry.navigation_status == Some(NavigationStatus::UnderwayUsingEngine));
+                }
+            }
+        }
+        self.update_draft();
+        if entry.draft.is_none() {
+            entry.draft = self.draft;
+        }
+        if entry.cross_track_error.is_none() {
+            entry.cross_track_error = self.get_current_leg().map(|leg| leg.cross_track_distance(entry.coordinates_current));
+        }
+        if let Some(hook) = &self.ship_log_hook {
+            hook(&entry);
+        }
+        self.ship_log.push(entry);
+        // Write the ship log in batches while a voyage is simulated, if the simulation streams its ship logs
+        if let Some(mut voyage_log) = self.voyage_log.take() {
+            voyage_log.update(self);
+            self.voyage_log = Some(voyage_log);
+        }
+        // Report the progress of the voyage, if it is being simulated
+        if let Some(departure) = self.voyage_departure {
+            if !self.progress_callbacks.is_empty() {
+                if let Some(progress) = self.get_voyage_progress(departure) {
+                    for callback in &self.progress_callbacks {
+                        (callback.0)(&progress);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the leg of the route plan the vessel is sailing, None if it has no route plan or current leg
+    pub fn get_current_leg(&self) -> Option<&SailingLeg> {
+        let current_leg = self.current_leg? as usize;
+        return self.route_plan.as_ref()?.get(current_leg.checked_sub(1)?);
+    }
+
+    /// Returns the progress of the voyage along the route plan from the current leg and location, None if the boat has no route plan. See VoyageProgress
+    /// The legs before the current leg are completed and the distance along the current leg is to the point on it closest to the location
+    /// departure: Departure time of the voyage, the simulated time is from it to the last entry in the ship log
+    /// # Example:
+    /// `let percent = boat.get_voyage_progress(departure).map(|progress| progress.percent());`
+    pub fn get_voyage_progress(&self, departure: UtcDateTime) -> Option<VoyageProgress> {
+        let route_plan = self.route_plan.as_ref()?;
+        let mut progress = VoyageProgress::new(departure, route_plan.len() as u32, route_plan.iter().map(|leg| leg.length()).sum());
+        if let Some(last_entry) = self.ship_log.last() {
+            progress.simulated_time = (last_entry.timestamp - departure).max(time::Duration::ZERO);
+        }
+        if let (Some(leg), Some(location)) = (self.get_current_leg(), self.location) {
+            progress.legs_completed = self.current_leg.unwrap() - 1;
+            let distance_completed_legs: f64 = route_plan[..progress.legs_completed as usize].iter().map(|leg| leg.length()).sum();
+            progress.distance_covered = distance_completed_legs + leg.along_track_distance(location).clamp(0.0, leg.length());
+        }
+        return Some(progress);
+    }
+
+    /// Replays one recorded ship log entry, the state of the vessel is set to the state in the entry and the entry is pushed to the ship log
+    /// Sets the time, location, destination, velocity, heading, true bearing and navigation status, and the cargo and draft if they were logged
+    /// Used by replay_ship_log() to rebuild the vessel step by step from an exported ship log
+    pub fn replay_ship_log_entry(&mut self, entry: ShipLogEntry) {
+        self.time_now = entry.timestamp;
+        self.location = Some(entry.coordinates_current);
+        self.destination = Some(entry.coordinates_final);
+        self.velocity_current = entry.velocity;
+        self.heading = entry.heading;
+        self.true_bearing = entry.true_bearing;
+        self.navigation_status = entry.navigation_status;
+        if let Some(cargo) = entry.cargo_on_board {
+            self.cargo_current = cargo;
+        }
+        if entry.draft.is_some() {
+            self.draft = entry.draft;
+        }
+        self.push_ship_log_entry(entry);
+    }
+
+    /// Registers a closure that is called with every new entry in the ship log, replaces any previous hook
+    /// # Example:
+    /// `boat.set_ship_log_hook(|entry| println!("{}: {:?}", entry.timestamp, entry.coordinates_current));`
+    pub fn set_ship_log_hook<F: Fn(&ShipLogEntry) + Send + Sync + 'static>(&mut self, hook: F) {
+        self.ship_log_hook = Some(std::sync::Arc::new(hook));
     }
 
-    /// Loads cargo, makes sure to compare against the maximum cargo capacity of the vessel
-    pub fn load_cargo(&mut self, cargo: uom::si::f64::Mass) {
+    /// Loads cargo, cargo is the total cargo on board afterwards, makes sure to compare against the maximum cargo capacity of the vessel
+    /// The cargo by type in Boat.cargo is not changed, use Boat.add_cargo() for that
+    /// The draft is updated from the new displacement, see Boat.update_draft(), and the operation is logged, see Boat.log_cargo_operation()
+    /// Returns an error and leaves the cargo unchanged if the cargo is negative or too heavy
+    pub fn load_cargo(&mut self, cargo: uom::si::f64::Mass) -> Result<(), MarineSimError> {
+        // Check that the cargo is a mass the vessel can have on board
+        if !(cargo.value >= 0.0) {
+            return Err(MarineSimError::InvalidInput(format!("Cargo must not be negative, got {} t", cargo.get::<uom::si::mass::ton>())));
+        }
         // Check if the cargo is too heavy
         match self.cargo_max_capacity {
             Some(max_capacity) => {
                 if cargo > max_capacity {
-                    // TODO: return error instead of panic
-                    panic!("Cargo is too heavy");
+                    return Err(MarineSimError::InvalidInput(format!("Cargo is too heavy, {} t is more than the maximum capacity of {} t", cargo.get::<uom::si::mass::ton>(), max_capacity.get::<uom::si::mass::ton>())));
                 }
             }
             None => {}  // No max capacity set, so do nothing
         }
 
         // Set the cargo
+        let cargo_old = self.cargo_current;
         self.cargo_current = cargo;
+        self.update_draft();
+        self.log_cargo_operation(cargo_old);
+        return Ok(());
+    }
+
+    /// Unloads cargo from the cargo on board, the cargo by type in Boat.cargo is not changed, use Boat.unload_cargo_type() for that
+    /// The draft is updated and the operation logged as in Boat.load_cargo()
+    /// Returns an error and leaves the cargo unchanged if the cargo is negative or more than is on board
+    /// # Example:
+    /// `boat.unload_cargo(uom::si::f64::Mass::new::<uom::si::mass::ton>(40.0))?;`
+    pub fn unload_cargo(&mut self, cargo: uom::si::f64::Mass) -> Result<(), MarineSimError> {
+        if !(cargo.value >= 0.0) {
+            return Err(MarineSimError::InvalidInput(format!("Cargo to unload must not be negative, got {} t", cargo.get::<uom::si::mass::ton>())));
+        }
+        if cargo > self.cargo_current {
+            return Err(MarineSimError::InvalidInput(format!("Can not unload {} t, only {} t is on board", cargo.get::<uom::si::mass::ton>(), self.cargo_current.get::<uom::si::mass::ton>())));
+        }
+        return self.load_cargo(self.cargo_current - cargo);
+    }
+
+    /// Records a change of the cargo on board from cargo_old to cargo_current at the current time, if the boat has a location
+    /// Adds a SimulationEventKind::CargoOperation event to boat.events and an entry with the new cargo to the ship log
+    /// Nothing is logged if the cargo did not change or the boat has no location, e.g. while it is being set up
+    fn log_cargo_operation(&mut self, cargo_old: uom::si::f64::Mass) {
+        let location = match self.location {
+            Some(location) => location,
+            None => return,
+        };
+        let delta_tons = (self.cargo_current - cargo_old).get::<uom::si::mass::ton>();
+        if delta_tons == 0.0 {
+            return;
+        }
+        self.events.push(SimulationEvent {
+            timestamp: self.time_now,
+            location,
+            leg: self.current_leg,
+            kind: SimulationEventKind::CargoOperation {
+                cargo_loaded: delta_tons.max(0.0),
+                cargo_unloaded: (-delta_tons).max(0.0),
+                cargo_on_board: self.cargo_current.get::<uom::si::mass::ton>(),
+            },
+        });
+        self.log_entry_into_ship_log().expect("The boat has a location");
+    }
+
+    /// Adds cargo of a type to the cargo on board, checks the capacity for the type and that the total mass is not above cargo_max_capacity
+    /// The capacity of containers is teu_capacity, of bulk cargo_max_capacity and of liquids liquid_capacity
+    /// cargo_current is increased by the mass of the cargo and the draft updated, see Boat.load_cargo()
+    /// Returns an error and leaves the cargo unchanged if the cargo is negative or does not fit
+    /// # Example:
+    /// `boat.add_cargo(Cargo::Liquid { volume: 800.0, density: 850.0 })?;`
+    pub fn add_cargo(&mut self, cargo: Cargo) -> Result<(), MarineSimError> {
+        let cargo_type = cargo.cargo_type();
+        if !(cargo.amount() >= 0.0) || !(cargo.mass().value >= 0.0) {
+            return Err(MarineSimError::InvalidInput(format!("{} cargo must not be negative", cargo_type)));
+        }
+        if let Some(capacity) = self.get_cargo_capacity(cargo_type) {
+            let amount = self.get_cargo_amount(cargo_type) + cargo.amount();
+            if amount > capacity {
+                return Err(MarineSimError::InvalidInput(format!("{} cargo does not fit, {} {} is more than the capacity of {} {}", cargo_type, amount, cargo_type.unit(), capacity, cargo_type.unit())));
+            }
+        }
+        self.load_cargo(self.cargo_current + cargo.mass())?;
+        self.cargo.push(cargo);
+        return Ok(());
+    }
+
+    /// Unloads all the cargo of a type and returns it, cargo_current is decreased by its mass, the draft updated and the operation logged as in Boat.load_cargo()
+    pub fn unload_cargo_type(&mut self, cargo_type: CargoType) -> Vec<Cargo> {
+        let (unloaded, kept): (Vec<Cargo>, Vec<Cargo>) = std::mem::take(&mut self.cargo).into_iter().partition(|cargo| cargo.cargo_type() == cargo_type);
+        self.cargo = kept;
+        let mass: uom::si::f64::Mass = unloaded.iter().map(|cargo| cargo.mass()).sum();
+        let cargo_old = self.cargo_current;
+        self.cargo_current = (self.cargo_current - mass).max(uom::si::f64::Mass::new::<uom::si::mass::ton>(0.0));
+        self.update_draft();
+        self.log_cargo_operation(cargo_old);
+        return unloaded;
+    }
+
+    /// Returns the amount of cargo of a type on board, in the unit of the type, see CargoType.unit()
+    pub fn get_cargo_amount(&self, cargo_type: CargoType) -> f64 {
+        return self.cargo.iter().filter(|cargo| cargo.cargo_type() == cargo_type).map(|cargo| cargo.amount()).sum();
+    }
+
+    /// Returns the capacity of the vessel for a cargo type, in the unit of the type, None if there is no limit
+    pub fn get_cargo_capacity(&self, cargo_type: CargoType) -> Option<f64> {
+        return match cargo_type {
+            CargoType::Containers => self.teu_capacity.map(|teu| teu as f64),
+            CargoType::Bulk => self.cargo_max_capacity.map(|mass| mass.get::<uom::si::mass::ton>()),
+            CargoType::Liquid => self.liquid_capacity,
+        };
+    }
+
+    /// Returns the displacement of the vessel, the mass of the boat plus the cargo, fuel and fresh water on board. None if the boat has no mass
+    pub fn get_displacement(&self) -> Option<uom::si::f64::Mass> {
+        let fresh_water = uom::si::f64::Mass::new::<uom::si::mass::ton>(self.fresh_water_tank.map_or(0.0, |tank| tank.level));
+        return Some(self.mass? + self.cargo_current + self.fuel_current + fresh_water);
+    }
+
+    /// Returns the tanks of the vessel, the fuel tank and the fresh water tank if it has them
+    pub fn tanks(&self) -> impl Iterator<Item = &Tank> {
+        return [self.fuel_tank.as_ref(), self.fresh_water_tank.as_ref()].into_iter().flatten();
+    }
+
+    /// Consumes from the tanks for the duration, with the engine running or not, see Tank.consume()
+    /// fuel_current is set to the level of the fuel tank
+    pub fn consume_tanks(&mut self, duration: time::Duration, engine_running: bool) {
+        if let Some(tank) = self.fresh_water_tank.as_mut() {
+            tank.consume(duration, engine_running);
+        }
+        if let Some(tank) = self.fuel_tank.as_mut() {
+            tank.consume(duration, engine_running);
+            self.fuel_current = uom::si::f64::Mass::new::<uom::si::mass::ton>(tank.level);
+        }
+    }
+
+    /// Returns the draft in \[m\] the vessel floats at with its displacement, None if the boat has no block coefficient, mass, length or width
+    /// The hull is assumed wall sided, so the draft is the displaced volume of sea water divided by block_coefficient * length * width
+    /// For multihulls the width is hull_width times the number of hulls in the water (see HullType.num_hulls_in_water()), None if the boat has no hull_width
+    pub fn get_draft_from_displacement(&self) -> Option<f64> {
+        let block_coefficient = self.block_coefficient?;
+        let length = self.length?.get::<uom::si::length::meter>();
+        let width = match self.hull_type {
+            HullType::Monohull => self.width?.get::<uom::si::length::meter>(),
+            _ => self.hull_width?.get::<uom::si::length::meter>()*(self.hull_type.num_hulls_in_water() as f64),
+        };
+        let displaced_volume = self.get_displacement()?.get::<uom::si::mass::kilogram>()/SEAWATER_DENSITY;
+        return Some(displaced_volume/(block_coefficient*length*width));
+    }
+
+    /// Sets the draft from the displacement of the vessel, see Boat.get_draft_from_displacement()
+    /// Leaves the draft unchanged if the boat has no block coefficient, mass, length or width
+    pub fn update_draft(&mut self) {
+        if let Some(draft) = self.get_draft_from_displacement() {
+            self.draft = Some(draft);
+        }
+    }
+
+    /// Returns the factor the speed through water is multiplied with for the displacement of the vessel, 1.0 if the boat has no design displacement or mass
+    /// With the Admiralty coefficient the power needed is proportional to displacement^(2/3) * speed^3, so with the same power (wind or engine) the speed is proportional to displacement^(-2/9)
+    /// # Example:
+    /// A vessel loaded to twice its design displacement sails at 0.86 times the speed of its polar diagram
+    pub fn get_displacement_speed_factor(&self) -> f64 {
+        return match (self.design_displacement, self.get_displacement()) {
+            (Some(design_displacement), Some(displacement)) if displacement.value > 0.0 => (design_displacement/displacement).value.powf(2.0/9.0),
+            _ => 1.0,
+        };
+    }
+}
+
+/// Same as Boat::new()
+impl Default for Boat {
+    fn default() -> Boat {
+        return Boat::new();
+    }
+}
+
+// Implementation of the BoatBuilder struct
+//----------------------------------------------------
+impl BoatBuilder {
+    /// Sets the name of the vessel
+    pub fn name(mut self, name: &str) -> BoatBuilder {
+        self.boat.name = Some(name.to_string());
+        return self;
+    }
+
+    /// Block coefficient of the hull, with mass, length and width the draft is computed from the displacement
+    pub fn block_coefficient(mut self, block_coefficient: f64) -> BoatBuilder {
+        self.boat.block_coefficient = Some(block_coefficient);
+        return self;
+    }
+
+    /// Adds cargo on board, see Boat.add_cargo(). The capacity is not checked, use Boat.add_cargo() for that
+    pub fn cargo(mut self, cargo: Cargo) -> BoatBuilder {
+        self.boat.cargo_current = self.boat.cargo_current + cargo.mass();
+        self.boat.cargo.push(cargo);
+        return self;
+    }
+
+    /// The vessels maximum cargo storage capacity (by weight), also the capacity for bulk cargo
+    pub fn cargo_max_capacity(mut self, cargo_max_capacity: uom::si::f64::Mass) -> BoatBuilder {
+        self.boat.cargo_max_capacity = Some(cargo_max_capacity);
+        return self;
+    }
+
+    /// The mean cargo of the vessel
+    pub fn cargo_mean(mut self, cargo_mean: uom::si::f64::Mass) -> BoatBuilder {
+        self.boat.cargo_mean = Some(cargo_mean);
+        return self;
+    }
+
+    /// The standard deviation of the cargo of the vessel
+    pub fn cargo_std(mut self, cargo_std: uom::si::f64::Mass) -> BoatBuilder {
+        self.boat.cargo_std = Some(cargo_std);
+        return self;
+    }
+
+    /// Displacement the speed of the vessel is given for
+    pub fn design_displacement(mut self, design_displacement: uom::si::f64::Mass) -> BoatBuilder {
+        self.boat.design_displacement = Some(design_displacement);
+        return self;
+    }
+
+    /// The crew of the vessel, see Crew
+    pub fn crew(mut self, crew: Crew) -> BoatBuilder {
+        self.boat.crew = Some(crew);
+        return self;
+    }
+
+    /// The draft of the vessel in \[m\]
+    pub fn draft(mut self, draft: f64) -> BoatBuilder {
+        self.boat.draft = Some(draft);
+        return self;
+    }
+
+    /// Speeds sampled from real ship logs, needed for SimMethod::EmpiricalSpeed
+    pub fn empirical_speed_model(mut self, empirical_speed_model: EmpiricalSpeedModel) -> BoatBuilder {
+        self.boat.empirical_speed_model = Some(empirical_speed_model);
+        return self;
+    }
+
+    /// Hydrofoils of the vessel, see Foil
+    pub fn foil(mut self, foil: Foil) -> BoatBuilder {
+        self.boat.foil = Some(foil);
+        return self;
+    }
+
+    /// Fresh water tank of the vessel
+    pub fn fresh_water_tank(mut self, fresh_water_tank: Tank) -> BoatBuilder {
+        self.boat.fresh_water_tank = Some(fresh_water_tank);
+        return self;
+    }
+
+    /// Fuel on board
+    pub fn fuel(mut self, fuel: uom::si::f64::Mass) -> BoatBuilder {
+        self.boat.fuel_current = fuel;
+        return self;
+    }
+
+    /// Fuel tank of the vessel, fuel_current is set to its level
+    pub fn fuel_tank(mut self, fuel_tank: Tank) -> BoatBuilder {
+        self.boat.fuel_current = uom::si::f64::Mass::new::<uom::si::mass::ton>(fuel_tank.level);
+        self.boat.fuel_tank = Some(fuel_tank);
+        return self;
+    }
+
+    /// Leeway when hove-to as a fraction of the wind speed
+    pub fn heave_to_leeway_multiplier(mut self, heave_to_leeway_multiplier: f64) -> BoatBuilder {
+        self.boat.heave_to_leeway_multiplier = Some(heave_to_leeway_multiplier);
+        return self;
+    }
+
+    /// Multiplier for the vessel forward velocity when hove-to
+    pub fn heave_to_speed_multiplier(mut self, heave_to_speed_multiplier: f64) -> BoatBuilder {
+        self.boat.heave_to_speed_multiplier = Some(heave_to_speed_multiplier);
+        return self;
+    }
+
+    /// Coefficient of drag for the hull
+    pub fn hull_drag_coefficient(mut self, hull_drag_coefficient: f64) -> BoatBuilder {
+        self.boat.hull_drag_coefficient = Some(hull_drag_coefficient);
+        return self;
+    }
+
+    /// Hull configuration of the vessel, see HullType
+    pub fn hull_type(mut self, hull_type: HullType) -> BoatBuilder {
+        self.boat.hull_type = hull_type;
+        return self;
+    }
+
+    /// Width of each hull at the waterline of a multihull
+    pub fn hull_width(mut self, hull_width: uom::si::f64::Length) -> BoatBuilder {
+        self.boat.hull_width = Some(hull_width);
+        return self;
+    }
+
+    /// The IMO number of the vessel
+    pub fn imo(mut self, imo: u32) -> BoatBuilder {
+        self.boat.imo = Some(imo);
+        return self;
+    }
+
+    /// The length of the vessel
+    pub fn length(mut self, length: uom::si::f64::Length) -> BoatBuilder {
+        self.boat.length = Some(length);
+        return self;
+    }
+
+    /// \[m^3\]. Tank capacity for liquid cargo
+    pub fn liquid_capacity(mut self, liquid_capacity: f64) -> BoatBuilder {
+        self.boat.liquid_capacity = Some(liquid_capacity);
+        return self;
+    }
+
+    /// Mass of the boat without cargo or fuel, needed for the copernicus simulation methods
+    pub fn mass(mut self, mass: uom::si::f64::Mass) -> BoatBuilder {
+        self.boat.mass = Some(mass);
+        return self;
+    }
+
+    /// The maximum sea ice concentration, in \[0, 1\], the vessel sails into
+    pub fn max_ice_concentration(mut self, max_ice_concentration: f64) -> BoatBuilder {
+        self.boat.max_ice_concentration = Some(max_ice_concentration);
+        return self;
+    }
+
+    /// \[m\]. The maximum significant wave height the vessel operates in
+    pub fn max_wave_height(mut self, max_wave_height: f64) -> BoatBuilder {
+        self.boat.max_wave_height = Some(max_wave_height);
+        return self;
+    }
+
+    /// \[m/s\]. The maximum wind speed the vessel operates in
+    pub fn max_wind_speed(mut self, max_wind_speed: f64) -> BoatBuilder {
+        self.boat.max_wind_speed = Some(max_wind_speed);
+        return self;
+    }
+
+    /// Minimum angle of attack in degrees, needed for the copernicus simulation methods
+    pub fn min_angle_of_attack(mut self, min_angle_of_attack: f64) -> BoatBuilder {
+        self.boat.min_angle_of_attack = Some(min_angle_of_attack);
+        return self;
+    }
+
+    /// \[m\]. Minimum water depth under the keel
+    pub fn min_under_keel_clearance(mut self, min_under_keel_clearance: f64) -> BoatBuilder {
+        self.boat.min_under_keel_clearance = Some(min_under_keel_clearance);
+        return self;
+    }
+
+    /// Polar diagram of the vessel
+    pub fn polar_diagram(mut self, polar_diagram: PolarDiagram) -> BoatBuilder {
+        self.boat.polar_diagram = Some(polar_diagram);
+        return self;
+    }
+
+    /// The route plan, needed for all simulation methods
+    pub fn route_plan(mut self, route_plan: RoutePlan) -> BoatBuilder {
+        self.boat.route_plan = Some(route_plan);
+        return self;
+    }
+
+    /// The rudder of the vessel
+    pub fn rudder(mut self, rudder: Rudder) -> BoatBuilder {
+        self.boat.rudder = Some(rudder);
+        return self;
+    }
+
+    /// The sail of the vessel, needed for the copernicus simulation methods
+    pub fn sail(mut self, sail: Sail) -> BoatBuilder {
+        self.boat.sail = Some(sail);
+        return self;
+    }
+
+    /// \[s/m\]. Speed grade coefficient, see get_vessel_velocity()
+    pub fn speed_grade_coefficient(mut self, speed_grade_coefficient: f64) -> BoatBuilder {
+        self.boat.speed_grade_coefficient = Some(speed_grade_coefficient);
+        return self;
+    }
+
+    /// Fraction of the operational limits the weather has to drop below for the vessel to resume sailing
+    pub fn storm_resume_fraction(mut self, storm_resume_fraction: f64) -> BoatBuilder {
+        self.boat.storm_resume_fraction = Some(storm_resume_fraction);
+        return self;
+    }
+
+    /// Multiplier for the vessel velocity when running downwind in a storm
+    pub fn storm_velocity_multiplier(mut self, storm_velocity_multiplier: f64) -> BoatBuilder {
+        self.boat.storm_velocity_multiplier = Some(storm_velocity_multiplier);
+        return self;
+    }
+
+    /// Container capacity in twenty-foot equivalent units
+    pub fn teu_capacity(mut self, teu_capacity: u32) -> BoatBuilder {
+        self.boat.teu_capacity = Some(teu_capacity);
+        return self;
+    }
+
+    /// Tons per centimetre immersion (TPC)
+    pub fn tons_per_cm_immersion(mut self, tons_per_cm_immersion: f64) -> BoatBuilder {
+        self.boat.tons_per_cm_immersion = Some(tons_per_cm_immersion);
+        return self;
+    }
+
+    /// \[m/s\]. The average velocity of the boat, needed for SimMethod::ConstVelocity and SimMethod::MeanAndSTDVelocity
+    pub fn velocity_mean(mut self, velocity_mean: f64) -> BoatBuilder {
+        self.boat.velocity_mean = Some(velocity_mean);
+        return self;
+    }
+
+    /// \[m/s\]. The maximum velocity of the boat
+    pub fn velocity_max(mut self, velocity_max: f64) -> BoatBuilder {
+        self.boat.velocity_max = Some(velocity_max);
+        return self;
+    }
+
+    /// \[m/s\]. The standard deviation of the velocity of the boat, needed for SimMethod::MeanAndSTDVelocity
+    pub fn velocity_std(mut self, velocity_std: f64) -> BoatBuilder {
+        self.boat.velocity_std = Some(velocity_std);
+        return self;
+    }
+
+    /// The width of the vessel
+    pub fn width(mut self, width: uom::si::f64::Length) -> BoatBuilder {
+        self.boat.width = Some(width);
+        return self;
+    }
+
+    /// Multiplier for the wind velocity, needed for the copernicus simulation methods
+    pub fn wind_velocity_multiplier(mut self, wind_velocity_multiplier: f64) -> BoatBuilder {
+        self.boat.wind_velocity_multiplier = Some(wind_velocity_multiplier);
+        return self;
+    }
+
+    /// Drift speed downwind as a fraction of the wind speed, used by SimMethod::Drift
+    pub fn windage_factor(mut self, windage_factor: f64) -> BoatBuilder {
+        self.boat.windage_factor = Some(windage_factor);
+        return self;
+    }
+
+    /// What the vessel does when the weather exceeds its operational limits
+    pub fn storm_tactic(mut self, storm_tactic: StormTactic) -> BoatBuilder {
+        self.boat.storm_tactic = storm_tactic;
+        return self;
+    }
+
+    /// Preferred side of the boat for the wind to hit
+    pub fn wind_preferred_side(mut self, wind_preferred_side: VesselSide) -> BoatBuilder {
+        self.boat.wind_preferred_side = wind_preferred_side;
+        return self;
+    }
+
+    /// Returns the boat without validating it
+    /// The draft is computed from the displacement if the boat has a block coefficient, mass, length and width
+    pub fn build(mut self) -> Boat {
+        self.boat.update_draft();
+        return self.boat;
+    }
+
+    /// Returns the boat if it has all the fields needed to be simulated with the simulation method
+    /// Otherwise returns MarineSimError::MissingBoatFields with every missing field, see Boat.missing_fields_for()
+    pub fn build_for(mut self, method: SimMethod) -> Result<Boat, MarineSimError> {
+        self.boat.update_draft();
+        let missing = self.boat.missing_fields_for(&method);
+        if !missing.is_empty() {
+            return Err(MarineSimError::MissingBoatFields(missing));
+        }
+        return Ok(self.boat);
     }
 }
 
 // Implementation of the ShipLogEntry struct
 //----------------------------------------------------
 impl ShipLogEntry {
+    /// Creates a new ShipLogEntry, depth, wind, wave height and cross track error are set to None
     pub fn new(timestamp: UtcDateTime, coord_initial: geo::Point, coord_current: geo::Point, coord_final: geo::Point, cargo: Option<uom::si::f64::Mass>, velocity: Option<PhysVec>, course: Option<f64>, heading: Option<f64>, track_angle: Option<f64>, true_bearing: Option<f64>, draft: Option<f64>, navigation_status: Option<NavigationStatus>) -> ShipLogEntry {
         ShipLogEntry {
             timestamp: timestamp,
@@ -358,6 +2378,12 @@ impl ShipLogEntry {
             track_angle: track_angle,
             true_bearing: true_bearing,
             draft: draft,
-            navigation_status: navigation_status}
+            navigation_status: navigation_status,
+            depth: None,
+            wind: None,
+            wave_height: None,
+            cross_track_error: None,
+            foiling: None,
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,330 @@
+/// Weather providers for the Marine vessel simulator.
+/// A weather provider gives the wind and ocean current at a location and time, so routing and analysis code does not depend on where the weather comes from.
+/// Author: G0rocks
+/// Date: 2026-10-16
+
+use crate::*;   // To use everything from the crate
+
+/// Trait for sources of weather data
+pub trait WeatherProvider: fmt::Debug {
+    /// Returns the wind in \[m/s\] at a location and time. The angle follows the same convention as the wind in the simulators
+    fn wind(&self, timestamp: UtcDateTime, location: geo::Point) -> Result<PhysVec, io::Error>;
+    /// Returns the ocean current in \[m/s\] at a location and time, None if unknown
+    fn ocean_current(&self, timestamp: UtcDateTime, location: geo::Point) -> Result<Option<PhysVec>, io::Error>;
+}
+
+/// Copernicus marine as a weather provider, see get_wind_from_copernicus() and get_ocean_current_from_copernicus()
+/// Note: Every call downloads data, so routing with copernicus directly can be slow
+#[cfg(feature = "copernicus")]
+impl WeatherProvider for copernicusmarine_rs::Copernicus {
+    fn wind(&self, timestamp: UtcDateTime, location: geo::Point) -> Result<PhysVec, io::Error> {
+        return Ok(get_wind_from_copernicus(self, timestamp, location)?);
+    }
+
+    fn ocean_current(&self, timestamp: UtcDateTime, location: geo::Point) -> Result<Option<PhysVec>, io::Error> {
+        return Ok(get_ocean_current_from_copernicus(self, timestamp, location)?);
+    }
+}
+
+/// The same weather everywhere at all times, useful for testing and quick studies
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ConstantWeather {
+    /// Wind in \[m/s\]
+    pub wind: PhysVec,
+    /// Ocean current in \[m/s\], None if unknown
+    pub ocean_current: Option<PhysVec>,
+}
+
+impl ConstantWeather {
+    /// Creates new constant weather
+    pub fn new(wind: PhysVec, ocean_current: Option<PhysVec>) -> ConstantWeather {
+        ConstantWeather {
+            wind,
+            ocean_current,
+        }
+    }
+}
+
+impl WeatherProvider for ConstantWeather {
+    fn wind(&self, _timestamp: UtcDateTime, _location: geo::Point) -> Result<PhysVec, io::Error> {
+        return Ok(self.wind);
+    }
+
+    fn ocean_current(&self, _timestamp: UtcDateTime, _location: geo::Point) -> Result<Option<PhysVec>, io::Error> {
+        return Ok(self.ocean_current);
+    }
+}
+
+/// A weather provider used inside a geographic area, see NestedWeather
+#[derive(Debug)]
+pub struct WeatherRegion {
+    /// Name of the region, e.g. the name of the regional model
+    pub name: String,
+    /// Area where the provider is used, x is longitude and y is latitude
+    pub bounds: geo::Rect,
+    /// Regions with higher priority are used over regions with lower priority where they overlap
+    pub priority: i32,
+    /// The weather provider for the region
+    pub provider: Box<dyn WeatherProvider + Send + Sync>,
+}
+
+impl WeatherRegion {
+    /// Creates a new weather region
+    pub fn new(name: &str, bounds: geo::Rect, priority: i32, provider: Box<dyn WeatherProvider + Send + Sync>) -> WeatherRegion {
+        WeatherRegion {
+            name: name.to_string(),
+            bounds,
+            priority,
+            provider,
+        }
+    }
+}
+
+/// Nested weather models, regional (e.g. high resolution) weather providers inside their areas and a global weather provider everywhere else
+/// Near the edge of a region the weather is blended with the weather outside it, so there is no jump in the weather when the boat crosses the edge
+/// # Example:
+/// `let mut weather = NestedWeather::new(Box::new(copernicus));`
+/// `weather.add_region(WeatherRegion::new("Gulf Stream", geo::Rect::new((-80.0, 25.0), (-60.0, 45.0)), 1, Box::new(gulf_stream_model)));`
+#[derive(Debug)]
+pub struct NestedWeather {
+    /// Regional weather providers
+    pub regions: Vec<WeatherRegion>,
+    /// Weather provider used outside all regions
+    pub global: Box<dyn WeatherProvider + Send + Sync>,
+    /// Width of the blending zone inside the edge of each region, in degrees. 0 means a hard switch at the edge
+    pub blend_width: f64,
+}
+
+impl NestedWeather {
+    /// Creates new nested weather with only a global weather provider
+    /// Defaults:
+    /// - regions: none
+    /// - blend_width: 0.5°
+    pub fn new(global: Box<dyn WeatherProvider + Send + Sync>) -> NestedWeather {
+        NestedWeather {
+            regions: Vec::new(),
+            global,
+            blend_width: 0.5,
+        }
+    }
+
+    /// Adds a regional weather provider, the regions are kept sorted by priority, highest first
+    pub fn add_region(&mut self, region: WeatherRegion) {
+        self.regions.push(region);
+        self.regions.sort_by(|a, b| b.priority.cmp(&a.priority));
+    }
+
+    /// Returns the weather providers used at a location and their weights, which add up to 1
+    /// The highest priority region gets full weight inside its blending zone, and the rest of the weight goes to the next region or the global provider
+    pub fn providers_at(&self, location: geo::Point) -> Vec<(&(dyn WeatherProvider + Send + Sync), f64)> {
+        let mut providers: Vec<(&(dyn WeatherProvider + Send + Sync), f64)> = Vec::new();
+        // Weight not yet given to a provider
+        let mut weight_left: f64 = 1.0;
+        for region in &self.regions {
+            // Distance inside the region to its closest edge, in degrees
+            let (min, max) = (region.bounds.min(), region.bounds.max());
+            let inside = (location.x() - min.x).min(max.x - location.x()).min(location.y() - min.y).min(max.y - location.y());
+            if inside < 0.0 {
+                continue;
+            }
+            let region_weight = match self.blend_width > 0.0 {
+                true => (inside / self.blend_width).min(1.0),
+                false => 1.0,
+            };
+            if region_weight <= 0.0 {
+                continue;
+            }
+            providers.push((region.provider.as_ref(), weight_left*region_weight));
+            weight_left *= 1.0 - region_weight;
+            if weight_left <= 0.0 {
+                return providers;
+            }
+        }
+        providers.push((self.global.as_ref(), weight_left));
+        return providers;
+    }
+}
+
+/// Adds vectors together with weights, the angle of the sum is in \[0, 360)
+fn get_weighted_vector_sum(vectors: &Vec<(PhysVec, f64)>) -> PhysVec {
+    let mut sum = PhysVec::new(0.0, 0.0);
+    for (vector, weight) in vectors {
+        sum = sum + PhysVec::new(vector.magnitude*weight, vector.angle);
+    }
+    if sum.angle < 0.0 {
+        sum.angle += 360.0;
+    }
+    return sum;
+}
+
+impl WeatherProvider for NestedWeather {
+    fn wind(&self, timestamp: UtcDateTime, location: geo::Point) -> Result<PhysVec, io::Error> {
+        let mut winds: Vec<(PhysVec, f64)> = Vec::new();
+        for (provider, weight) in self.providers_at(location) {
+            winds.push((provider.wind(timestamp, location)?, weight));
+        }
+        return Ok(get_weighted_vector_sum(&winds));
+    }
+
+    /// Providers that don't know the ocean current are left out and the weights of the others scaled up
+    fn ocean_current(&self, timestamp: UtcDateTime, location: geo::Point) -> Result<Option<PhysVec>, io::Error> {
+        let mut currents: Vec<(PhysVec, f64)> = Vec::new();
+        for (provider, weight) in self.providers_at(location) {
+            if let Some(current) = provider.ocean_current(timestamp, location)? {
+                currents.push((current, weight));
+            }
+        }
+        let total_weight: f64 = currents.iter().map(|(_, weight)| weight).sum();
+        if total_weight <= 0.0 {
+            return Ok(None);
+        }
+        let currents: Vec<(PhysVec, f64)> = currents.into_iter().map(|(current, weight)| (current, weight/total_weight)).collect();
+        return Ok(Some(get_weighted_vector_sum(&currents)));
+    }
+}
+
+/// Caches the weather from another weather provider, so the same weather is only fetched once, e.g. when simulating many departure times
+/// The time and location are rounded to the resolution of the cache and the weather is fetched at the rounded time and location
+#[derive(Debug)]
+pub struct CachedWeather {
+    /// The weather provider to cache
+    pub provider: Box<dyn WeatherProvider + Send + Sync>,
+    /// Time resolution of the cache
+    pub time_resolution: time::Duration,
+    /// Resolution of the cache in degrees of latitude and longitude
+    pub spatial_resolution: f64,
+    /// Cached wind, by rounded (time, latitude, longitude)
+    wind_cache: std::sync::Mutex<std::collections::HashMap<(i64, i64, i64), PhysVec>>,
+    /// Cached ocean current, by rounded (time, latitude, longitude)
+    ocean_current_cache: std::sync::Mutex<std::collections::HashMap<(i64, i64, i64), Option<PhysVec>>>,
+}
+
+impl CachedWeather {
+    /// Creates a new, empty, weather cache
+    /// # Example:
+    /// `simulation.weather = Some(std::sync::Arc::new(CachedWeather::new(Box::new(copernicus), time::Duration::hours(1), 0.083)));`
+    pub fn new(provider: Box<dyn WeatherProvider + Send + Sync>, time_resolution: time::Duration, spatial_resolution: f64) -> CachedWeather {
+        CachedWeather {
+            provider,
+            time_resolution,
+            spatial_resolution,
+            wind_cache: std::sync::Mutex::new(std::collections::HashMap::new()),
+            ocean_current_cache: std::sync::Mutex::new(std::collections::HashMap::new()),
+        }
+    }
+
+    /// Returns the cache key and the rounded time and location
+    fn key(&self, timestamp: UtcDateTime, location: geo::Point) -> ((i64, i64, i64), UtcDateTime, geo::Point) {
+        let time_step = self.time_resolution.whole_seconds().max(1);
+        let time_index = (timestamp.unix_timestamp() as f64 / time_step as f64).round() as i64;
+        let lat_index = (location.y() / self.spatial_resolution).round() as i64;
+        let lon_index = (location.x() / self.spatial_resolution).round() as i64;
+        let rounded_time = UtcDateTime::from_unix_timestamp(time_index*time_step).unwrap_or(timestamp);
+        let rounded_location = geo::Point::new(lon_index as f64*self.spatial_resolution, lat_index as f64*self.spatial_resolution);
+        return ((time_index, lat_index, lon_index), rounded_time, rounded_location);
+    }
+
+    /// Empties the cache
+    pub fn clear(&self) {
+        self.wind_cache.lock().unwrap().clear();
+        self.ocean_current_cache.lock().unwrap().clear();
+    }
+}
+
+impl WeatherProvider for CachedWeather {
+    fn wind(&self, timestamp: UtcDateTime, location: geo::Point) -> Result<PhysVec, io::Error> {
+        let (key, rounded_time, rounded_location) = self.key(timestamp, location);
+        if let Some(wind) = self.wind_cache.lock().unwrap().get(&key) {
+            return Ok(*wind);
+        }
+        let wind = self.provider.wind(rounded_time, rounded_location)?;
+        self.wind_cache.lock().unwrap().insert(key, wind);
+        return Ok(wind);
+    }
+
+    fn ocean_current(&self, timestamp: UtcDateTime, location: geo::Point) -> Result<Option<PhysVec>, io::Error> {
+        let (key, rounded_time, rounded_location) = self.key(timestamp, location);
+        if let Some(ocean_current) = self.ocean_current_cache.lock().unwrap().get(&key) {
+            return Ok(*ocean_current);
+        }
+        let ocean_current = self.provider.ocean_current(rounded_time, rounded_location)?;
+        self.ocean_current_cache.lock().unwrap().insert(key, ocean_current);
+        return Ok(ocean_current);
+    }
+}
+
+/// How much the weather is perturbed in each run of an ensemble, see NoisyWeather and run_monte_carlo()
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeatherNoise {
+    /// Standard deviation of the wind speed error as a fraction of the wind speed, e.g. 0.1 for 10%
+    pub wind_speed_std: f64,
+    /// Standard deviation of the wind direction error in degrees
+    pub wind_angle_std: f64,
+    /// Standard deviation of the ocean current speed error as a fraction of the current speed
+    pub ocean_current_std: f64,
+}
+
+impl WeatherNoise {
+    /// Creates new weather noise
+    pub fn new(wind_speed_std: f64, wind_angle_std: f64, ocean_current_std: f64) -> WeatherNoise {
+        WeatherNoise {
+            wind_speed_std,
+            wind_angle_std,
+            ocean_current_std,
+        }
+    }
+}
+
+/// The weather from another provider with a random error, e.g. to simulate forecast errors in an ensemble of simulations
+/// The error is drawn when the NoisyWeather is created and is the same everywhere and at all times, so each NoisyWeather is one member of the ensemble
+#[derive(Debug)]
+pub struct NoisyWeather {
+    /// The weather provider to perturb
+    pub provider: std::sync::Arc<dyn WeatherProvider + Send + Sync>,
+    /// The wind speed is multiplied by this factor
+    pub wind_speed_factor: f64,
+    /// Degrees added to the wind angle
+    pub wind_angle_offset: f64,
+    /// The ocean current speed is multiplied by this factor
+    pub ocean_current_factor: f64,
+}
+
+impl NoisyWeather {
+    /// Creates a new ensemble member, draws the errors from normal distributions with the standard deviations of the noise
+    pub fn new(provider: std::sync::Arc<dyn WeatherProvider + Send + Sync>, noise: WeatherNoise) -> NoisyWeather {
+        NoisyWeather {
+            provider,
+            wind_speed_factor: (1.0 + get_normal_random(noise.wind_speed_std)).max(0.0),
+            wind_angle_offset: get_normal_random(noise.wind_angle_std),
+            ocean_current_factor: (1.0 + get_normal_random(noise.ocean_current_std)).max(0.0),
+        }
+    }
+}
+
+impl WeatherProvider for NoisyWeather {
+    fn wind(&self, timestamp: UtcDateTime, location: geo::Point) -> Result<PhysVec, io::Error> {
+        let wind = self.provider.wind(timestamp, location)?;
+        return Ok(PhysVec::new(wind.magnitude*self.wind_speed_factor, (wind.angle + self.wind_angle_offset).rem_euclid(360.0)));
+    }
+
+    fn ocean_current(&self, timestamp: UtcDateTime, location: geo::Point) -> Result<Option<PhysVec>, io::Error> {
+        let ocean_current = self.provider.ocean_current(timestamp, location)?;
+        return Ok(ocean_current.map(|current| PhysVec::new(current.magnitude*self.ocean_current_factor, current.angle)));
+    }
+}
+
+/// Returns a random number from a normal distribution with mean zero, using the Box-Muller transform
+pub(crate) fn get_normal_random(std: f64) -> f64 {
+    return get_normal_random_with_rng(&mut rand::rng(), std);
+}
+
+/// Returns a random number from a normal distribution with mean zero drawn from rng, e.g. a seeded rand::rngs::StdRng for reproducible results, see get_normal_random()
+pub(crate) fn get_normal_random_with_rng<R: rand::Rng>(rng: &mut R, std: f64) -> f64 {
+    if std <= 0.0 {
+        return 0.0;
+    }
+    let u1: f64 = rng.random_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.random_range(0.0..1.0);
+    return std * (-2.0*u1.ln()).sqrt() * (2.0*consts::PI*u2).cos();
+}
@@ -0,0 +1,79 @@
+/// Excel import for the Marine vessel simulator, only with the "xlsx" feature.
+/// Author: G0rocks
+/// Date: 2026-10-16
+/// Reads shipping logs from Excel workbooks (.xlsx, .xlsm and .xls) with the same column layout as the CSV logs, since shipping companies mostly deliver their logs as spreadsheets.
+/// The first worksheet is read and its first row is the header. Cells formatted as dates are read as UTC timestamps directly, so there is no conversion to text in the locale of the computer that saved the file.
+/// The cargo shipping log functions, e.g. evaluate_cargo_shipping_logs() and extract_trips(), read workbooks as well as CSV files. See xlsx_to_ship_log() for ship logs.
+
+use crate::*;   // To use everything from the crate
+use calamine::{Data, DataType, Reader};
+
+/// Returns true if the file is an Excel workbook, from the file extension
+pub fn is_excel_file(file_path: &str) -> bool {
+    return [".xlsx", ".xlsm", ".xls"].iter().any(|extension| check_file_extension(file_path, extension));
+}
+
+/// Reads a ship log from the first worksheet of an Excel workbook with the columns of ship_logs_to_csv()
+/// The header row must be the header ship_logs_to_csv() writes, as in ship_log_from_csv(). Rows that can not be read return an error with the row number, the header is row 1
+/// # Example:
+/// `let ship_log = xlsx_to_ship_log("delivered_logs.xlsx")?;`
+pub fn xlsx_to_ship_log(file_path: &str) -> Result<Vec<ShipLogEntry>, MarineSimError> {
+    let (header, records) = read_xlsx_records(file_path)?;
+    check_ship_log_header(&header, file_path)?;
+    let mut ship_log: Vec<ShipLogEntry> = Vec::new();
+    for record in records {
+        let entry = get_ship_log_entry_from_csv_record(&record, ship_log.last(), file_path)?;
+        ship_log.push(entry);
+    }
+    return Ok(ship_log);
+}
+
+/// Reads the first worksheet of an Excel workbook as text records, returns the header row and the rows below it
+/// Each record has the row number in the worksheet as its line, so errors point to the right row. Empty rows are skipped
+pub(crate) fn read_xlsx_records(file_path: &str) -> Result<(csv::StringRecord, Vec<csv::StringRecord>), MarineSimError> {
+    let xlsx_error = |message: String| MarineSimError::CsvParse { file: file_path.to_string(), message };
+    let mut workbook = calamine::open_workbook_auto(file_path).map_err(|e| xlsx_error(format!("Could not open workbook: {}", e)))?;
+    let range = match workbook.worksheet_range_at(0) {
+        Some(Ok(range)) => range,
+        Some(Err(e)) => return Err(xlsx_error(format!("Could not read the first worksheet: {}", e))),
+        None => return Err(xlsx_error("The workbook has no worksheets".to_string())),
+    };
+
+    let mut records: Vec<csv::StringRecord> = Vec::new();
+    // Rows before the start of the range are empty, the row numbers count from the top of the worksheet
+    let first_row = range.start().map_or(0, |(row, _)| row as u64);
+    let header = csv::StringRecord::from(range.rows().next().map_or(Vec::new(), |row| row.iter().map(get_xlsx_cell_text).collect::<Vec<String>>()));
+    for (i, row) in range.rows().enumerate().skip(1) {
+        if row.iter().all(|cell| cell.is_empty()) {
+            continue;
+        }
+        let mut record = csv::StringRecord::from(row.iter().map(get_xlsx_cell_text).collect::<Vec<String>>());
+        let mut position = csv::Position::new();
+        position.set_line(first_row + i as u64 + 1);
+        record.set_position(Some(position));
+        records.push(record);
+    }
+    return Ok((header, records));
+}
+
+/// Returns the text of a cell as it would be in a CSV file
+/// Dates become "YYYY-MM-DD hh:mm:ss" in UTC, see format_timestamp(), and numbers are written without a thousands separator
+pub(crate) fn get_xlsx_cell_text(cell: &Data) -> String {
+    return match cell {
+        Data::Empty | Data::Error(_) => String::new(),
+        Data::String(text) => text.trim().to_string(),
+        Data::Float(value) => value.to_string(),
+        Data::Int(value) => value.to_string(),
+        Data::Bool(value) => value.to_string(),
+        // ISO 8601 dates, e.g. "2025-05-01T12:00:00"
+        Data::DateTimeIso(text) => text.trim().replace('T', " ").trim_end_matches('Z').to_string(),
+        Data::DurationIso(text) => text.to_string(),
+        // Days since 1899-12-30 in the 1900 date system or since 1904-01-01 in the 1904 date system of workbooks saved on older Macs, as_datetime() converts from either
+        Data::DateTime(date_time) => {
+            match date_time.as_datetime().map(|date_time| UtcDateTime::from_unix_timestamp(date_time.and_utc().timestamp())) {
+                Some(Ok(timestamp)) => format_timestamp(timestamp),
+                _ => String::new(),
+            }
+        },
+    };
+}